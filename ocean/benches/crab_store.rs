@@ -0,0 +1,90 @@
+/*
+ * Measures adding, finding and removing crabs on a beach, by id and by
+ * index. It only uses methods beaches had before they kept their crabs in
+ * an arena, so running it on a checkout from before the arena times the
+ * vector of crabs and sorted handles they used to keep, for comparison.
+ * Run with `cargo bench --bench crab_store`.
+ */
+#![allow(deprecated)]
+
+use ocean::beach::{Beach, CrabId};
+use ocean::color::Color;
+use ocean::crab::Crab;
+use ocean::diet::Diet;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const POPULATION: usize = 50_000;
+const ROUNDS: u32 = 5;
+
+fn crab(k: usize) -> Crab {
+    Crab::new(
+        format!("crab {}", k),
+        k as u32 % 20 + 1,
+        Color::new(0, 0, 255),
+        Diet::Fish,
+    )
+}
+
+/** The removal order: every other crab, spread across the beach. */
+fn leaving(ids: &[CrabId]) -> Vec<CrabId> {
+    let mut leaving: Vec<CrabId> = ids.iter().copied().step_by(2).collect();
+    let half = leaving.len() / 2;
+    leaving.rotate_left(half);
+    leaving
+}
+
+fn populated() -> (Beach, Vec<CrabId>) {
+    let mut beach = Beach::new();
+    let ids = (0..POPULATION)
+        .map(|k| beach.add_crab_with_id(crab(k)))
+        .collect();
+    (beach, ids)
+}
+
+/**
+ * Runs `f` on a fresh beach of crabs `ROUNDS` times, timing only `f`, and
+ * prints the fastest run.
+ */
+fn time(label: &str, f: impl Fn(&mut Beach, &[CrabId])) {
+    let mut best = Duration::MAX;
+    for _ in 0..ROUNDS {
+        let (mut beach, ids) = populated();
+        let start = Instant::now();
+        f(&mut beach, &ids);
+        best = best.min(start.elapsed());
+    }
+    println!("{:>24}: {:?}", label, best);
+}
+
+fn main() {
+    let mut best = Duration::MAX;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        black_box(populated());
+        best = best.min(start.elapsed());
+    }
+    println!("{:>24}: {:?}", "add", best);
+
+    time("find by id", |beach, ids| {
+        for &id in ids {
+            black_box(beach.get_crab_by_id(id));
+        }
+    });
+    time("remove by id", |beach, ids| {
+        for id in leaving(ids) {
+            black_box(beach.remove_crab_by_id(id));
+        }
+        assert_eq!(beach.size(), POPULATION / 2);
+    });
+    time("remove from the front", |beach, _| {
+        while beach.size() > POPULATION / 2 {
+            black_box(beach.remove_crab(0));
+        }
+    });
+    time("remove from the back", |beach, _| {
+        while beach.size() > POPULATION / 2 {
+            black_box(beach.pop_crab());
+        }
+    });
+}
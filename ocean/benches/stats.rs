@@ -0,0 +1,50 @@
+/*
+ * Compares the serial and parallel statistics of a large beach.
+ * Run with `cargo bench --bench stats --features parallel`.
+ */
+use ocean::beach::Beach;
+use std::hint::black_box;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const POPULATION: usize = 200_000;
+const CLAN_SIZE: usize = 2_000;
+const ROUNDS: u32 = 20;
+
+fn time<T>(f: impl Fn() -> T) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(f());
+    }
+    start.elapsed() / ROUNDS
+}
+
+fn main() {
+    let threads = thread::available_parallelism().map_or(4, |n| n.get());
+    let mut beach = Beach::new();
+    beach.populate_random(POPULATION, 1);
+    let names: Vec<String> = beach.crabs().map(|c| c.name().to_string()).collect();
+    for (k, name) in names.iter().take(2 * CLAN_SIZE).enumerate() {
+        beach.add_member_to_clan(&format!("clan {}", k / CLAN_SIZE), name);
+    }
+    let last = &names[POPULATION - 1];
+    println!("{} crabs, {} threads", POPULATION, threads);
+
+    let serial = time(|| beach.get_fastest_crab().map(|c| c.speed()));
+    let parallel = time(|| beach.par_get_fastest_crab(threads).map(|c| c.speed()));
+    println!("fastest crab: serial {:?}, parallel {:?}", serial, parallel);
+
+    let serial = time(|| beach.find_crabs_by_name(last).len());
+    let parallel = time(|| beach.par_find_crabs_by_name(last, threads).len());
+    println!("find by name: serial {:?}, parallel {:?}", serial, parallel);
+
+    let start = Instant::now();
+    let winner = beach.get_winner_clan("clan 0", "clan 1");
+    let serial = start.elapsed();
+    let parallel = time(|| beach.par_get_winner_clan("clan 0", "clan 1", threads));
+    println!("winner clan: serial {:?}, parallel {:?}", serial, parallel);
+    assert_eq!(
+        winner,
+        beach.par_get_winner_clan("clan 0", "clan 1", threads)
+    );
+}
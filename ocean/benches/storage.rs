@@ -0,0 +1,42 @@
+/*
+ * Measures how crab and clan storage behaves under breed/reap churn.
+ * Run with `cargo bench --bench storage`.
+ */
+#![allow(deprecated)]
+
+use ocean::beach::Beach;
+use std::time::Instant;
+
+const POPULATION: usize = 20_000;
+const ROUNDS: usize = 50;
+const CLAN_SIZE: usize = 5;
+
+fn main() {
+    let start = Instant::now();
+    let mut beach = Beach::new();
+    beach.populate_random(POPULATION, 1);
+    let names: Vec<String> = beach.crabs().map(|c| c.name().to_string()).collect();
+    for (k, name) in names.iter().enumerate() {
+        beach.add_member_to_clan(&format!("clan {}", k / CLAN_SIZE), name);
+    }
+    let settled = beach.memory_stats();
+    println!("setup: {:?} in {:?}", settled, start.elapsed());
+
+    let start = Instant::now();
+    for round in 0..ROUNDS {
+        for k in 0..POPULATION / 10 {
+            beach.breed_crabs(k, k + 1, format!("churn {} {}", round, k));
+        }
+        for _ in 0..POPULATION / 10 {
+            beach.pop_crab();
+        }
+    }
+    let churned = beach.memory_stats();
+    println!("churn: {:?} in {:?}", churned, start.elapsed());
+    println!(
+        "clan bytes per member: {:.1}",
+        churned.clans as f64 / POPULATION as f64
+    );
+    assert_eq!(beach.size(), POPULATION);
+    assert!(churned.crabs <= settled.crabs * 2);
+}
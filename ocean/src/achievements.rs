@@ -0,0 +1,212 @@
+use crate::event::{BeachEvent, EventListener};
+use std::collections::HashMap;
+
+/** A goal that can be reached on a beach. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Milestone {
+    /** The first clan to reach this many members. */
+    ClanSize(usize),
+    /** The first crab to live on the beach for this many ticks. */
+    CrabSurvives(u64),
+    /** The beach is home to this many crabs at once. */
+    Population(usize),
+    /** This many crabs have been bred on the beach. */
+    Births(usize),
+}
+
+impl Milestone {
+    pub fn target(&self) -> u64 {
+        match *self {
+            Milestone::ClanSize(n) | Milestone::Population(n) | Milestone::Births(n) => n as u64,
+            Milestone::CrabSurvives(ticks) => ticks,
+        }
+    }
+}
+
+/** Progress towards a milestone. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Achievement {
+    pub milestone: Milestone,
+    /** Best progress so far, never more than the milestone's target. */
+    pub progress: u64,
+    /** The clan or crab that unlocked the achievement, if it is tied to one. */
+    pub unlocked_by: Option<String>,
+    /** The tick at which the achievement was unlocked. */
+    pub unlocked_at: Option<u64>,
+}
+
+impl Achievement {
+    pub fn is_unlocked(&self) -> bool {
+        self.unlocked_at.is_some()
+    }
+
+    /** Progress as a fraction between 0 and 1. */
+    pub fn fraction(&self) -> f64 {
+        let target = self.milestone.target();
+        if target == 0 {
+            1.0
+        } else {
+            self.progress as f64 / target as f64
+        }
+    }
+}
+
+/**
+ * Tracks achievements for one beach by listening to its events.
+ *
+ * Subscribe it with `Beach::subscribe`, keeping a clone of the `Rc` around
+ * to query progress.
+ */
+#[derive(Debug)]
+pub struct AchievementTracker {
+    achievements: Vec<Achievement>,
+    tick: u64,
+    population: usize,
+    births: usize,
+    // Crab name -> tick at which it arrived on the beach.
+    arrived_at: HashMap<String, u64>,
+}
+
+impl AchievementTracker {
+    /**
+     * Creates a tracker for the default milestones: a clan of 10, a crab
+     * surviving 100 ticks, a population of 100 and 10 births.
+     */
+    pub fn new() -> AchievementTracker {
+        AchievementTracker::with_milestones(&[
+            Milestone::ClanSize(10),
+            Milestone::CrabSurvives(100),
+            Milestone::Population(100),
+            Milestone::Births(10),
+        ])
+    }
+
+    pub fn with_milestones(milestones: &[Milestone]) -> AchievementTracker {
+        let mut achievements: Vec<Achievement> = Vec::new();
+        for &milestone in milestones {
+            if !achievements.iter().any(|a| a.milestone == milestone) {
+                achievements.push(Achievement {
+                    milestone,
+                    progress: 0,
+                    unlocked_by: None,
+                    unlocked_at: None,
+                });
+            }
+        }
+        AchievementTracker {
+            achievements,
+            tick: 0,
+            population: 0,
+            births: 0,
+            arrived_at: HashMap::new(),
+        }
+    }
+
+    /** All tracked achievements, in the order their milestones were given. */
+    pub fn achievements(&self) -> &[Achievement] {
+        &self.achievements
+    }
+
+    pub fn get(&self, milestone: Milestone) -> Option<&Achievement> {
+        self.achievements.iter().find(|a| a.milestone == milestone)
+    }
+
+    /** Unlocked achievements, in the order they were unlocked. */
+    pub fn unlocked(&self) -> Vec<&Achievement> {
+        let mut unlocked: Vec<&Achievement> = self
+            .achievements
+            .iter()
+            .filter(|a| a.is_unlocked())
+            .collect();
+        unlocked.sort_by_key(|a| a.unlocked_at);
+        unlocked
+    }
+
+    /**
+     * Updates every achievement of the kind selected by `matches` with the
+     * given progress, unlocking it on behalf of `by` once its target is met.
+     */
+    fn advance<F>(&mut self, matches: F, progress: u64, by: Option<&str>)
+    where
+        F: Fn(&Milestone) -> bool,
+    {
+        let tick = self.tick;
+        for achievement in self.achievements.iter_mut() {
+            if achievement.is_unlocked() || !matches(&achievement.milestone) {
+                continue;
+            }
+            let target = achievement.milestone.target();
+            achievement.progress = achievement.progress.max(progress.min(target));
+            if progress >= target {
+                achievement.unlocked_by = by.map(String::from);
+                achievement.unlocked_at = Some(tick);
+            }
+        }
+    }
+}
+
+impl Default for AchievementTracker {
+    fn default() -> Self {
+        AchievementTracker::new()
+    }
+}
+
+impl EventListener for AchievementTracker {
+    fn on_event(&mut self, event: &BeachEvent) {
+        match event {
+            BeachEvent::CrabAdded { name } => {
+                self.population += 1;
+                self.arrived_at.entry(name.clone()).or_insert(self.tick);
+                let population = self.population as u64;
+                self.advance(|m| matches!(m, Milestone::Population(_)), population, None);
+            }
+            BeachEvent::CrabBred { .. } => {
+                self.births += 1;
+                let births = self.births as u64;
+                self.advance(|m| matches!(m, Milestone::Births(_)), births, None);
+            }
+            BeachEvent::ClanMemberAdded {
+                clan_id, clan_size, ..
+            } => {
+                self.advance(
+                    |m| matches!(m, Milestone::ClanSize(_)),
+                    *clan_size as u64,
+                    Some(clan_id),
+                );
+            }
+            BeachEvent::Tick { tick } => {
+                self.tick = *tick;
+                // The longest-lived crab, oldest name first on ties.
+                let oldest = self
+                    .arrived_at
+                    .iter()
+                    .map(|(name, &arrived)| (tick - arrived, name))
+                    .max_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(a.1)))
+                    .map(|(age, name)| (age, name.clone()));
+                if let Some((age, name)) = oldest {
+                    self.advance(
+                        |m| matches!(m, Milestone::CrabSurvives(_)),
+                        age,
+                        Some(&name),
+                    );
+                }
+            }
+            // Crabs that die are announced as removed as well, so only the
+            // removal counts them.
+            BeachEvent::InbreedingWarning { .. } | BeachEvent::CrabDied { .. } => {}
+            BeachEvent::SeagullRaid {
+                casualties: names, ..
+            }
+            | BeachEvent::CrabsFished { names } => {
+                self.population = self.population.saturating_sub(names.len());
+                for name in names {
+                    self.arrived_at.remove(name);
+                }
+            }
+            BeachEvent::CrabRemoved { name } => {
+                self.population = self.population.saturating_sub(1);
+                self.arrived_at.remove(name);
+            }
+        }
+    }
+}
@@ -0,0 +1,358 @@
+use crate::beach::Beach;
+use crate::color::Color;
+use crate::crab::Crab;
+use crate::diet::Diet;
+use crate::timeline::{CrabState, WorldState};
+use std::collections::{BTreeMap, HashMap};
+
+/** A numeric trait of a crab that can be analyzed. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trait {
+    Speed,
+    ClawSize,
+    Health,
+    /** Hue of the crab's color, in degrees. */
+    Hue,
+}
+
+impl Trait {
+    pub fn of_crab(&self, crab: &Crab) -> f64 {
+        self.value(crab.speed(), crab.claw_size(), crab.health(), crab.color())
+    }
+
+    pub fn of_state(&self, state: &CrabState) -> f64 {
+        self.value(state.speed, state.claw_size, state.health, &state.color)
+    }
+
+    fn value(&self, speed: u32, claw_size: u32, health: u32, color: &Color) -> f64 {
+        match self {
+            Trait::Speed => speed as f64,
+            Trait::ClawSize => claw_size as f64,
+            Trait::Health => health as f64,
+            Trait::Hue => color.hue(),
+        }
+    }
+}
+
+/** Descriptive statistics of a sample. */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub count: usize,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub p25: f64,
+    pub median: f64,
+    pub p75: f64,
+    pub max: f64,
+}
+
+/** Returns the mean of the values, or None if there are none. */
+pub fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+}
+
+/** Returns the population standard deviation, or None if there are no values. */
+pub fn std_dev(values: &[f64]) -> Option<f64> {
+    let mean = mean(values)?;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+    Some(variance.sqrt())
+}
+
+/**
+ * Returns the `p`th percentile (0 to 100) of the values, interpolating
+ * linearly between the closest ranks, or None if there are no values.
+ */
+pub fn percentile(values: &[f64], p: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    Some(percentile_of_sorted(&sorted, p))
+}
+
+fn percentile_of_sorted(sorted: &[f64], p: f64) -> f64 {
+    let rank = p.clamp(0.0, 100.0) / 100.0 * (sorted.len() - 1) as f64;
+    let (low, high) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[low] + (sorted[high] - sorted[low]) * (rank - low as f64)
+}
+
+/** Returns the descriptive statistics of the values, or None if there are none. */
+pub fn summarize(values: &[f64]) -> Option<Summary> {
+    let mean = mean(values)?;
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    Some(Summary {
+        count: values.len(),
+        mean,
+        std_dev: std_dev(values)?,
+        min: sorted[0],
+        p25: percentile_of_sorted(&sorted, 25.0),
+        median: percentile_of_sorted(&sorted, 50.0),
+        p75: percentile_of_sorted(&sorted, 75.0),
+        max: sorted[sorted.len() - 1],
+    })
+}
+
+/**
+ * Returns the Pearson correlation coefficient of two paired samples, or None
+ * if they differ in length, have fewer than two pairs, or either is constant.
+ */
+pub fn correlation(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    if xs.len() != ys.len() || xs.len() < 2 {
+        return None;
+    }
+    let (mx, my) = (mean(xs)?, mean(ys)?);
+    let mut covariance = 0.0;
+    let (mut vx, mut vy) = (0.0, 0.0);
+    for (x, y) in xs.iter().zip(ys) {
+        covariance += (x - mx) * (y - my);
+        vx += (x - mx).powi(2);
+        vy += (y - my).powi(2);
+    }
+    if vx == 0.0 || vy == 0.0 {
+        None
+    } else {
+        Some(covariance / (vx * vy).sqrt())
+    }
+}
+
+/**
+ * Returns the correlation ratio (eta squared) between a grouping and a
+ * numeric sample: the share of the sample's variance explained by the
+ * groups, from 0 (none) to 1 (all). None if the sample is empty or constant.
+ */
+pub fn correlation_ratio<K: Eq + std::hash::Hash>(pairs: &[(K, f64)]) -> Option<f64> {
+    let values: Vec<f64> = pairs.iter().map(|(_, v)| *v).collect();
+    let overall = mean(&values)?;
+    let total: f64 = values.iter().map(|v| (v - overall).powi(2)).sum();
+    if total == 0.0 {
+        return None;
+    }
+    let mut groups: HashMap<&K, (f64, usize)> = HashMap::new();
+    for (key, value) in pairs {
+        let group = groups.entry(key).or_insert((0.0, 0));
+        group.0 += value;
+        group.1 += 1;
+    }
+    let between: f64 = groups
+        .values()
+        .map(|&(sum, n)| n as f64 * (sum / n as f64 - overall).powi(2))
+        .sum();
+    Some(between / total)
+}
+
+/** Returns the given trait of every crab on the beach, in beach order. */
+pub fn values(beach: &Beach, t: Trait) -> Vec<f64> {
+    beach.crabs().map(|crab| t.of_crab(crab)).collect()
+}
+
+/** Returns the given trait of every crab in a recorded world state. */
+pub fn state_values(state: &WorldState, t: Trait) -> Vec<f64> {
+    state.crabs().iter().map(|crab| t.of_state(crab)).collect()
+}
+
+pub fn describe(beach: &Beach, t: Trait) -> Option<Summary> {
+    summarize(&values(beach, t))
+}
+
+pub fn describe_state(state: &WorldState, t: Trait) -> Option<Summary> {
+    summarize(&state_values(state, t))
+}
+
+/** Returns the correlation between two traits across the crabs of the beach. */
+pub fn trait_correlation(beach: &Beach, a: Trait, b: Trait) -> Option<f64> {
+    correlation(&values(beach, a), &values(beach, b))
+}
+
+/**
+ * Returns the trait of each clan's resident members, by clan id. Crabs
+ * without a clan are not included.
+ */
+pub fn values_by_clan(beach: &Beach, t: Trait) -> BTreeMap<String, Vec<f64>> {
+    let clans = beach.get_clan_system();
+    clans
+        .clan_ids()
+        .map(|id| {
+            let values = clans
+                .clan_members(id)
+                .iter()
+                .filter_map(|&member| beach.resolve(member))
+                .map(|crab| t.of_crab(crab))
+                .collect();
+            (id.to_string(), values)
+        })
+        .collect()
+}
+
+/**
+ * Returns how strongly clan membership relates to the given trait (e.g. do
+ * clans differ in hue?), as a correlation ratio over clan members.
+ */
+pub fn clan_correlation(beach: &Beach, t: Trait) -> Option<f64> {
+    let pairs: Vec<(String, f64)> = values_by_clan(beach, t)
+        .into_iter()
+        .flat_map(|(id, values)| values.into_iter().map(move |v| (id.clone(), v)))
+        .collect();
+    correlation_ratio(&pairs)
+}
+
+/** A histogram bucket: how many values are from `start` up to `end`, exclusive. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bucket {
+    pub start: u64,
+    pub end: u64,
+    pub count: usize,
+}
+
+/**
+ * How many values fall into each of a run of equally wide buckets, from the
+ * one holding 0 to the one holding the largest value, empty ones included.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    pub bucket_size: u64,
+    pub buckets: Vec<Bucket>,
+}
+
+impl Histogram {
+    /** Returns the histogram of the values; a bucket size of 0 counts as 1. */
+    pub fn of(values: impl IntoIterator<Item = u64>, bucket_size: u64) -> Histogram {
+        let bucket_size = bucket_size.max(1);
+        let mut counts: Vec<usize> = Vec::new();
+        for value in values {
+            let k = (value / bucket_size) as usize;
+            if counts.len() <= k {
+                counts.resize(k + 1, 0);
+            }
+            counts[k] += 1;
+        }
+        let buckets = counts
+            .into_iter()
+            .enumerate()
+            .map(|(k, count)| Bucket {
+                start: k as u64 * bucket_size,
+                end: (k as u64 + 1) * bucket_size,
+                count,
+            })
+            .collect();
+        Histogram {
+            bucket_size,
+            buckets,
+        }
+    }
+
+    /** Returns how many values the histogram counts. */
+    pub fn total(&self) -> usize {
+        self.buckets.iter().map(|bucket| bucket.count).sum()
+    }
+}
+
+/** Which figure of a clan's speeds decides a competition between clans. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpeedMetric {
+    #[default]
+    Mean,
+    Median,
+    Max,
+}
+
+/** Statistics of the crabs of a clan that live on a beach. */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClanStats {
+    /** The number of the clan's crabs living on the beach. */
+    pub member_count: usize,
+    pub min_speed: u32,
+    pub max_speed: u32,
+    pub mean_speed: f64,
+    pub median_speed: f64,
+    /** The population standard deviation of the speeds. */
+    pub speed_std_dev: f64,
+    /** The most common diet; ties go to fish, then shellfish. */
+    pub dominant_diet: Diet,
+    /** The mean of each color channel, rounded down. */
+    pub average_color: Color,
+}
+
+impl ClanStats {
+    pub fn speed(&self, metric: SpeedMetric) -> f64 {
+        match metric {
+            SpeedMetric::Mean => self.mean_speed,
+            SpeedMetric::Median => self.median_speed,
+            SpeedMetric::Max => self.max_speed as f64,
+        }
+    }
+}
+
+impl Beach {
+    /** Returns the histogram of the crabs' speeds, in buckets of the given size. */
+    pub fn speed_histogram(&self, bucket_size: u32) -> Histogram {
+        Histogram::of(
+            self.crabs().map(|crab| crab.speed() as u64),
+            bucket_size as u64,
+        )
+    }
+
+    /** Returns how many crabs have each diet, including diets none have. */
+    pub fn diet_distribution(&self) -> HashMap<Diet, usize> {
+        let mut counts: HashMap<Diet, usize> = [Diet::Fish, Diet::Shellfish, Diet::Plants]
+            .into_iter()
+            .map(|diet| (diet, 0))
+            .collect();
+        for crab in self.crabs() {
+            *counts.entry(crab.diet()).or_default() += 1;
+        }
+        counts
+    }
+
+    /** Returns how many crabs have lived on the beach for each number of ticks. */
+    pub fn age_distribution(&self) -> BTreeMap<u64, usize> {
+        let mut counts = BTreeMap::new();
+        for index in 0..self.size() {
+            *counts.entry(self.age(index)).or_default() += 1;
+        }
+        counts
+    }
+
+    /**
+     * Returns the `p`th percentile (0 to 100) of the crabs' speeds, like
+     * `percentile`, or None if the beach is empty.
+     */
+    pub fn speed_percentile(&self, p: f64) -> Option<f64> {
+        percentile(&values(self, Trait::Speed), p)
+    }
+
+    /**
+     * Returns statistics of the given clan's crabs that live on this beach,
+     * or None if it has none.
+     */
+    pub fn clan_stats(&self, clan_id: &str) -> Option<ClanStats> {
+        let crabs = self.crab_query().in_clan(clan_id).collect();
+        let speeds: Vec<f64> = crabs.iter().map(|crab| crab.speed() as f64).collect();
+        let summary = summarize(&speeds)?;
+
+        let dominant_diet = [Diet::Fish, Diet::Shellfish, Diet::Plants]
+            .into_iter()
+            .rev()
+            .max_by_key(|&diet| crabs.iter().filter(|crab| crab.diet() == diet).count())?;
+        let channel = |c: fn(&Color) -> u8| {
+            let total: usize = crabs.iter().map(|crab| c(crab.color()) as usize).sum();
+            (total / crabs.len()) as u8
+        };
+        Some(ClanStats {
+            member_count: crabs.len(),
+            min_speed: crabs.iter().map(|crab| crab.typed_speed()).min()?.get(),
+            max_speed: crabs.iter().map(|crab| crab.typed_speed()).max()?.get(),
+            mean_speed: summary.mean,
+            median_speed: summary.median,
+            speed_std_dev: summary.std_dev,
+            dominant_diet,
+            average_color: Color::new(channel(|c| c.r), channel(|c| c.g), channel(|c| c.b)),
+        })
+    }
+}
@@ -0,0 +1,193 @@
+use crate::beach::{Beach, CrabId};
+use crate::crab::MAX_HEALTH;
+use crate::i18n::{tr, Message};
+use crate::simulation::Operation;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/** A broken invariant between the parts of a beach. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /** A clan lists a member that does not live on the beach. */
+    MemberMissing {
+        clan_id: String,
+        crab_name: String,
+    },
+    /** A clan lists the same member more than once. */
+    DuplicateMember {
+        clan_id: String,
+        crab_name: String,
+    },
+    /** A crab belongs to more than one clan. */
+    MultipleClans {
+        crab_name: String,
+        clan_ids: Vec<String>,
+    },
+    /** More than one crab has the same name, which makes names ambiguous. */
+    DuplicateName {
+        name: String,
+        count: usize,
+    },
+    HealthOutOfRange {
+        crab_name: String,
+        health: u32,
+    },
+    EmptyClan {
+        clan_id: String,
+    },
+    /** A relation involves a clan that does not exist. */
+    RelationWithoutClan {
+        clan_id: String,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = match self {
+            Violation::MemberMissing { clan_id, crab_name } => {
+                tr(Message::AuditMemberMissing, &[crab_name, clan_id])
+            }
+            Violation::DuplicateMember { clan_id, crab_name } => {
+                tr(Message::AuditDuplicateMember, &[crab_name, clan_id])
+            }
+            Violation::MultipleClans {
+                crab_name,
+                clan_ids,
+            } => tr(
+                Message::AuditMultipleClans,
+                &[crab_name, &clan_ids.join(", ")],
+            ),
+            Violation::DuplicateName { name, count } => {
+                tr(Message::AuditDuplicateName, &[name, count])
+            }
+            Violation::HealthOutOfRange { crab_name, health } => {
+                tr(Message::AuditHealthOutOfRange, &[crab_name, health])
+            }
+            Violation::EmptyClan { clan_id } => tr(Message::AuditEmptyClan, &[clan_id]),
+            Violation::RelationWithoutClan { clan_id } => {
+                tr(Message::AuditRelationWithoutClan, &[clan_id])
+            }
+        };
+        write!(f, "{}", text)
+    }
+}
+
+/**
+ * Checks the invariants that hold between a beach's crabs and its clan
+ * system, returning every violation found. An empty result means the beach
+ * is consistent.
+ */
+pub fn audit(beach: &Beach) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    let mut names: BTreeMap<&str, usize> = BTreeMap::new();
+    for crab in beach.crabs() {
+        *names.entry(crab.name()).or_default() += 1;
+        if crab.health() > MAX_HEALTH {
+            violations.push(Violation::HealthOutOfRange {
+                crab_name: crab.name().to_string(),
+                health: crab.health(),
+            });
+        }
+    }
+    for (&name, &count) in &names {
+        if count > 1 {
+            violations.push(Violation::DuplicateName {
+                name: name.to_string(),
+                count,
+            });
+        }
+    }
+
+    let clans = beach.get_clan_system();
+    let mut memberships: BTreeMap<CrabId, Vec<String>> = BTreeMap::new();
+    for clan_id in clans.clan_ids() {
+        let members = clans.clan_members(clan_id);
+        if members.is_empty() {
+            violations.push(Violation::EmptyClan {
+                clan_id: clan_id.to_string(),
+            });
+        }
+        for (k, &member) in members.iter().enumerate() {
+            if members[..k].contains(&member) {
+                violations.push(Violation::DuplicateMember {
+                    clan_id: clan_id.to_string(),
+                    crab_name: clans.describe(member),
+                });
+                continue;
+            }
+            if beach.inhabitant_name(member).is_none() {
+                violations.push(Violation::MemberMissing {
+                    clan_id: clan_id.to_string(),
+                    crab_name: clans.describe(member),
+                });
+            }
+            memberships
+                .entry(member)
+                .or_default()
+                .push(clan_id.to_string());
+        }
+    }
+    for (member, clan_ids) in memberships {
+        if clan_ids.len() > 1 {
+            violations.push(Violation::MultipleClans {
+                crab_name: clans.describe(member),
+                clan_ids,
+            });
+        }
+    }
+
+    for (id1, id2, _) in clans.relations() {
+        for id in [id1, id2] {
+            if !clans.has_clan(id) {
+                violations.push(Violation::RelationWithoutClan {
+                    clan_id: id.to_string(),
+                });
+            }
+        }
+    }
+    violations
+}
+
+/**
+ * Checks that a beach's names mean one crab each: no two crabs share a name,
+ * and every clan member lives on the beach.
+ */
+pub fn audit_names(beach: &Beach) -> Vec<Violation> {
+    audit(beach)
+        .into_iter()
+        .filter(|violation| {
+            matches!(
+                violation,
+                Violation::DuplicateName { .. } | Violation::MemberMissing { .. }
+            )
+        })
+        .collect()
+}
+
+/** When a simulation audits its beach. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditMode {
+    EveryOperation,
+    EveryTick,
+}
+
+/** The violations found right after an operation, with its context. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditReport {
+    pub tick: u64,
+    /** The position of the operation in the simulation's recording. */
+    pub index: usize,
+    pub operation: Operation,
+    pub violations: Vec<Violation>,
+}
+
+impl fmt::Display for AuditReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "#{} @{}: {:?}", self.index, self.tick, self.operation)?;
+        for violation in &self.violations {
+            writeln!(f, "  {}", violation)?;
+        }
+        Ok(())
+    }
+}
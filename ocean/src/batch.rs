@@ -0,0 +1,272 @@
+use crate::analysis::{summarize, Summary};
+use crate::beach::Beach;
+use crate::crab::Crab;
+use crate::ocean::Ocean;
+use crate::raid::RaidSettings;
+use crate::speed::Speed;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/** One combination of parameter values, by parameter name. */
+pub type Point = BTreeMap<String, f64>;
+/** The outcome of one run, by metric name. */
+pub type Metrics = BTreeMap<String, f64>;
+
+/**
+ * A grid of experiments: every combination of the values of each parameter
+ * axis, each run once per seed.
+ *
+ * An experiment is any function from a point and a seed to the metrics it
+ * measured; `ecology_experiment` is a ready-made one. Experiments build
+ * their own beaches, so runs can happen on several threads at once even
+ * though beaches themselves stay on one thread.
+ */
+#[derive(Debug, Clone)]
+pub struct Sweep {
+    axes: Vec<(String, Vec<f64>)>,
+    seeds: Vec<u64>,
+}
+
+impl Sweep {
+    /** Starts a sweep with no axes, run with the single seed 0. */
+    pub fn new() -> Sweep {
+        Sweep {
+            axes: Vec::new(),
+            seeds: vec![0],
+        }
+    }
+
+    /** Adds a parameter axis, replacing any axis with the same name. */
+    pub fn axis(mut self, name: &str, values: impl IntoIterator<Item = f64>) -> Sweep {
+        self.axes.retain(|(axis, _)| axis != name);
+        self.axes
+            .push((name.to_string(), values.into_iter().collect()));
+        self
+    }
+
+    /** Sets the seeds every point is run with. */
+    pub fn seeds(mut self, seeds: impl IntoIterator<Item = u64>) -> Sweep {
+        self.seeds = seeds.into_iter().collect();
+        self
+    }
+
+    /**
+     * Returns every point of the grid. The first axis changes slowest; with
+     * no axes, there is one empty point.
+     */
+    pub fn points(&self) -> Vec<Point> {
+        let mut points = vec![Point::new()];
+        for (name, values) in &self.axes {
+            points = points
+                .into_iter()
+                .flat_map(|point| {
+                    values.iter().map(move |&value| {
+                        let mut point = point.clone();
+                        point.insert(name.clone(), value);
+                        point
+                    })
+                })
+                .collect();
+        }
+        points
+    }
+
+    /** Runs every experiment in turn and aggregates the results. */
+    pub fn run(&self, experiment: impl Fn(&Point, u64) -> Metrics) -> ResultsTable {
+        let points = self.points();
+        let runs = self
+            .jobs(&points)
+            .map(|(p, seed)| experiment(&points[p], seed))
+            .collect();
+        self.aggregate(points, runs)
+    }
+
+    /**
+     * Runs the experiments on up to `threads` threads and aggregates the
+     * results, which are the same as those of `run`.
+     */
+    pub fn run_parallel(
+        &self,
+        threads: usize,
+        experiment: impl Fn(&Point, u64) -> Metrics + Sync,
+    ) -> ResultsTable {
+        let points = self.points();
+        let jobs: Vec<(usize, u64)> = self.jobs(&points).collect();
+        let next = AtomicUsize::new(0);
+        let results: Mutex<Vec<Option<Metrics>>> = Mutex::new(vec![None; jobs.len()]);
+        thread::scope(|scope| {
+            for _ in 0..threads.clamp(1, jobs.len().max(1)) {
+                scope.spawn(|| loop {
+                    let k = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(&(p, seed)) = jobs.get(k) else {
+                        break;
+                    };
+                    let metrics = experiment(&points[p], seed);
+                    results.lock().unwrap()[k] = Some(metrics);
+                });
+            }
+        });
+        let runs = results
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect();
+        self.aggregate(points, runs)
+    }
+
+    /** Every run as (point index, seed), point by point. */
+    fn jobs<'a>(&'a self, points: &'a [Point]) -> impl Iterator<Item = (usize, u64)> + 'a {
+        (0..points.len()).flat_map(move |p| self.seeds.iter().map(move |&seed| (p, seed)))
+    }
+
+    fn aggregate(&self, points: Vec<Point>, runs: Vec<Metrics>) -> ResultsTable {
+        let per_point = self.seeds.len();
+        let rows = points
+            .into_iter()
+            .enumerate()
+            .map(|(p, point)| {
+                let mut samples: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+                for metrics in &runs[p * per_point..(p + 1) * per_point] {
+                    for (name, &value) in metrics {
+                        samples.entry(name.clone()).or_default().push(value);
+                    }
+                }
+                let metrics = samples
+                    .into_iter()
+                    .filter_map(|(name, values)| Some((name, summarize(&values)?)))
+                    .collect();
+                ResultRow { point, metrics }
+            })
+            .collect();
+        ResultsTable {
+            axes: self.axes.iter().map(|(name, _)| name.clone()).collect(),
+            rows,
+        }
+    }
+}
+
+impl Default for Sweep {
+    fn default() -> Self {
+        Sweep::new()
+    }
+}
+
+/** The aggregated outcome of one point of a sweep, over all its seeds. */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResultRow {
+    pub point: Point,
+    pub metrics: BTreeMap<String, Summary>,
+}
+
+/** The results of a sweep, one row per point in grid order. */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResultsTable {
+    pub axes: Vec<String>,
+    pub rows: Vec<ResultRow>,
+}
+
+impl ResultsTable {
+    /**
+     * Returns the table as CSV: a column for each axis, then the mean,
+     * standard deviation, minimum and maximum of each metric.
+     */
+    pub fn to_csv(&self) -> String {
+        let metrics: Vec<&String> = self
+            .rows
+            .iter()
+            .flat_map(|row| row.metrics.keys())
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let mut header: Vec<String> = self.axes.clone();
+        for metric in &metrics {
+            for stat in ["mean", "std_dev", "min", "max"] {
+                header.push(format!("{}_{}", metric, stat));
+            }
+        }
+        let mut csv = header.join(",");
+        csv.push('\n');
+        for row in &self.rows {
+            let mut cells: Vec<String> = self
+                .axes
+                .iter()
+                .map(|axis| row.point.get(axis).map_or(String::new(), |v| v.to_string()))
+                .collect();
+            for metric in &metrics {
+                match row.metrics.get(*metric) {
+                    Some(s) => {
+                        cells.extend([s.mean, s.std_dev, s.min, s.max].map(|v| v.to_string()))
+                    }
+                    None => cells.extend(std::iter::repeat_n(String::new(), 4)),
+                }
+            }
+            let _ = writeln!(csv, "{}", cells.join(","));
+        }
+        csv
+    }
+}
+
+/**
+ * A ready-made experiment on one beach of random crabs sharing one reef.
+ * It reads these parameters from the point, using the given default for
+ * any that are missing:
+ *   - `crabs` (20): how many crabs start on the beach.
+ *   - `food` (2): how many of each kind of prey the reef starts with, per crab.
+ *   - `gulls` (0): how many seagulls raid the beach every 5 ticks.
+ *   - `ticks` (20): how long the experiment runs. Every crab forages once
+ *     per tick.
+ *
+ * It measures the final `population`, the `mean_speed` of the survivors
+ * (0 if there are none) and `meals`, the share of foraging trips that
+ * caught something.
+ */
+pub fn ecology_experiment(point: &Point, seed: u64) -> Metrics {
+    let param = |name: &str, default: f64| point.get(name).copied().unwrap_or(default).max(0.0);
+    let crabs = param("crabs", 20.0) as usize;
+    let food = (param("food", 2.0) * crabs as f64) as u32;
+    let gulls = param("gulls", 0.0) as usize;
+    let ticks = param("ticks", 20.0) as u64;
+
+    let mut ocean = Ocean::new();
+    let reef = ocean.generate_reef(food, food, food, food);
+    let mut beach = Beach::new();
+    beach.populate_random(crabs, seed);
+    for index in 0..beach.size() {
+        beach.get_crab_mut(index).discover_reef(reef.clone());
+    }
+    beach.set_raid_settings(RaidSettings {
+        gulls,
+        every: 5,
+        chance: 0.0,
+        seed,
+    });
+    let (mut trips, mut meals) = (0, 0);
+    for _ in 0..ticks {
+        for index in 0..beach.size() {
+            trips += 1;
+            if beach.forage(index) {
+                meals += 1;
+            }
+        }
+        beach.tick();
+    }
+
+    let population = beach.size();
+    let mean_speed = Speed::mean(beach.crabs().map(Crab::typed_speed)).unwrap_or(0.0);
+    Metrics::from([
+        (String::from("population"), population as f64),
+        (String::from("mean_speed"), mean_speed),
+        (
+            String::from("meals"),
+            if trips == 0 {
+                0.0
+            } else {
+                meals as f64 / trips as f64
+            },
+        ),
+    ])
+}
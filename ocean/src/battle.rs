@@ -0,0 +1,164 @@
+use crate::crab::Crab;
+
+/** One of the two participants in a battle. */
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Side {
+    Attacker,
+    Defender,
+}
+
+impl Side {
+    pub fn opponent(self) -> Side {
+        match self {
+            Side::Attacker => Side::Defender,
+            Side::Defender => Side::Attacker,
+        }
+    }
+}
+
+/** How a battle came to an end. */
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Resolution {
+    /** The given side won by reducing its opponent to zero health. */
+    Victory(Side),
+    /** The given side fled the battle, conceding it to its opponent. */
+    Retreat(Side),
+    /** Neither side had won or fled when the round limit was reached. */
+    Stalemate,
+}
+
+/** Tunable parameters for resolving a battle. */
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct BattleRules {
+    /** The battle is a stalemate after this many rounds. */
+    pub max_rounds: u32,
+    /** A crab whose health drops to or below this value will try to retreat. */
+    pub retreat_threshold: u32,
+}
+
+impl Default for BattleRules {
+    fn default() -> Self {
+        BattleRules {
+            max_rounds: 10,
+            retreat_threshold: 25,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct BattleReport {
+    pub resolution: Resolution,
+    /** Number of rounds fought, including the last one. */
+    pub rounds: u32,
+    /** Health lost by the attacker over the course of the battle. */
+    pub attacker_injury: u32,
+    /** Health lost by the defender over the course of the battle. */
+    pub defender_injury: u32,
+}
+
+impl BattleReport {
+    /**
+     * Returns the side that won the battle, either by victory or because its
+     * opponent retreated, or None on a stalemate.
+     */
+    pub fn winner(&self) -> Option<Side> {
+        match self.resolution {
+            Resolution::Victory(side) => Some(side),
+            Resolution::Retreat(side) => Some(side.opponent()),
+            Resolution::Stalemate => None,
+        }
+    }
+}
+
+/**
+ * Returns the damage dealt by one strike of the given crab's claws.
+ *
+ * Claw size matters most, but faster crabs hit a little harder.
+ */
+pub fn strike_damage(crab: &Crab) -> u32 {
+    crab.claw_size()
+        .saturating_mul(5)
+        .saturating_add(crab.typed_speed().get() / 10)
+}
+
+/**
+ * Lets `striker` hit `target` once, returning the damage actually dealt.
+ */
+fn strike(striker: &Crab, target: &mut Crab) -> u32 {
+    let before = target.health();
+    target.injure(strike_damage(striker));
+    before - target.health()
+}
+
+/**
+ * Resolves a fight between two crabs, injuring both in the process.
+ *
+ * Every round, the faster crab strikes first (the attacker, on ties), then
+ * the other strikes back if it is still standing. A crab reduced to zero
+ * health loses. At the end of a round, a wounded crab (at or below the
+ * retreat threshold) flees if it is at least as fast as its opponent;
+ * otherwise it is cornered and has to keep fighting. If both are wounded,
+ * the one with less health tries first.
+ *
+ * A crab that enters the battle with no health loses without a fight.
+ */
+pub fn fight(attacker: &mut Crab, defender: &mut Crab, rules: &BattleRules) -> BattleReport {
+    let mut report = BattleReport {
+        resolution: Resolution::Stalemate,
+        rounds: 0,
+        attacker_injury: 0,
+        defender_injury: 0,
+    };
+
+    if attacker.health() == 0 {
+        report.resolution = Resolution::Victory(Side::Defender);
+        return report;
+    }
+    if defender.health() == 0 {
+        report.resolution = Resolution::Victory(Side::Attacker);
+        return report;
+    }
+
+    let first = if defender.typed_speed() > attacker.typed_speed() {
+        Side::Defender
+    } else {
+        Side::Attacker
+    };
+
+    while report.rounds < rules.max_rounds {
+        report.rounds += 1;
+
+        for side in [first, first.opponent()] {
+            match side {
+                Side::Attacker => report.defender_injury += strike(attacker, defender),
+                Side::Defender => report.attacker_injury += strike(defender, attacker),
+            }
+            if attacker.health() == 0 || defender.health() == 0 {
+                report.resolution = Resolution::Victory(side);
+                return report;
+            }
+        }
+
+        let mut wounded = Vec::new();
+        if attacker.health() <= rules.retreat_threshold {
+            wounded.push((Side::Attacker, attacker.health()));
+        }
+        if defender.health() <= rules.retreat_threshold {
+            wounded.push((Side::Defender, defender.health()));
+        }
+        wounded.sort_by_key(|&(_, health)| health);
+
+        for (side, _) in wounded {
+            let can_flee = match side {
+                Side::Attacker => attacker.typed_speed() >= defender.typed_speed(),
+                Side::Defender => defender.typed_speed() >= attacker.typed_speed(),
+            };
+            if can_flee {
+                report.resolution = Resolution::Retreat(side);
+                return report;
+            }
+        }
+    }
+
+    report
+}
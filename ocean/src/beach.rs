@@ -1,24 +1,630 @@
+use crate::analysis::SpeedMetric;
+use crate::battle::{self, BattleReport, BattleRules};
+use crate::breeding::{BreedingError, BreedingRules, BreedingState, Parent, ParentChoice};
+use crate::capacity::EvictionPolicy;
+use crate::clans::{
+    ClanCrabs, ClanSystem, Constitution, Rejection, RejectionReason, Relation, StaleMembership,
+};
 use crate::color::Color;
+use crate::competition::{CompetitionRecord, TieBreaker};
+use crate::constraints::{Bounded, CheckedCrabsMut, ConstraintError, Constraints, CrabMut};
 use crate::crab::Crab;
 use crate::diet::Diet;
-use crate::clans::ClanSystem;
-use std::slice::Iter;
+use crate::disaster::{Disaster, DisasterReport, Displacement};
+use crate::distribution::TraitDistributions;
+use crate::economy::{self, Account, Earning, Economy, Spending, TreasuryRules};
+use crate::encounter::Encounter;
+use crate::environment::{Climate, Environment};
+use crate::error::OceanError;
+use crate::event::{BeachEvent, BeachObserver, EventBus, EventListener};
+use crate::food::{FeedingReport, Food, FoodSupply, HUNGER_DAMAGE};
+use crate::genealogy::{FamilyTree, Lineage};
+use crate::genetics::{BreedingPolicy, DefaultBreeding, Genetics, Heredity};
+use crate::history::{BeachSnapshot, History};
+use crate::i18n::{tr, Message};
+use crate::incubation::{Clutch, Incubator};
+use crate::inhabitant::Inhabitant;
+use crate::interference::{Humans, Interference, InterferenceRecord};
+use crate::leaderboard::Leaderboard;
+use crate::life::{
+    Cause, Death, LifeRules, LifeStage, LifeState, LifecycleConfig, Meal, TickReport,
+};
+use crate::metrics::{MetricsRecorder, TickMetrics};
+use crate::movement::{step_toward, Landmarks, MovementStrategy, Surroundings};
+use crate::names::{edit_distance, NameGenerator, NamePolicy, NameRegistry};
+use crate::observe::{Observe, Observers};
+use crate::pollution::{Pollution, CLEANUP_PER_TICK, POLLUTION_PER_CATCH};
+use crate::position::Position;
+use crate::predator::{Hunt, Predator};
+use crate::protection::{Protected, Protection};
+use crate::query::{Column, CrabQuery, Direction, Query, QueryResult};
+use crate::raid::{RaidReport, RaidSettings, Raids};
+use crate::rng::GlobalRng;
+use crate::speed::{Speed, SpeedTotal};
+use crate::store::{CrabStore, Crabs};
+use crate::survey::{RecaptureReport, Survey};
+use crate::territory::{SpatialIndex, Territory};
+use crate::tidepool::{Exchange, TidePools};
+use crate::watch::{Alert, Watch, WatchList};
+use crate::weather::{self, Weather, WeatherRules, WeatherSystem};
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore, SeedableRng};
+use rand_pcg::Pcg64;
+use std::cell::RefCell;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Display;
+use std::rc::Rc;
+use std::sync::mpsc::Receiver;
+
+/**
+ * An estimate of the memory a beach uses, in bytes. Only heap allocations
+ * and the structures holding them are counted, and map overhead is
+ * approximated, so the figures are best used to compare beaches.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MemoryStats {
+    /** The crab records and their handles, including each one's list of reefs. */
+    pub crabs: usize,
+    /** The crabs' names and the index of crabs by name. */
+    pub names: usize,
+    /** Clan ids, member lists and relations. */
+    pub clans: usize,
+}
+
+impl MemoryStats {
+    pub fn total(&self) -> usize {
+        self.crabs + self.names + self.clans
+    }
+}
+
+/**
+ * The stable identifier a beach gives each crab as it arrives. Unlike an
+ * index or a borrow, an id can be kept across any changes to the beach,
+ * and resolving it tells whether the crab is still there: prefer ids to
+ * indices wherever crabs may leave or be reordered. Ids are only
+ * meaningful to the beach that issued them.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CrabId(u64);
+
+/** What crab ids were called before they had a name of their own. */
+pub type CrabHandle = CrabId;
+
+#[cfg(feature = "binary")]
+impl CrabId {
+    /** The number the binary format saves the id as. */
+    pub(crate) fn raw(self) -> u64 {
+        self.0
+    }
+
+    /** Returns the id a binary save recorded as `raw`. */
+    pub(crate) fn from_raw(raw: u64) -> CrabId {
+        CrabId(raw)
+    }
+}
+
+impl std::fmt::Display for CrabId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/** The relations and sub-clan links of a clan system, copied out of it. */
+struct ClanLinks {
+    relations: Vec<(String, String, Relation)>,
+    // Sub-clan id -> the id of the clan it belongs to.
+    parents: Vec<(String, String)>,
+}
+
+impl ClanLinks {
+    fn of(clans: &ClanSystem) -> ClanLinks {
+        ClanLinks {
+            relations: clans
+                .relations()
+                .into_iter()
+                .map(|(id1, id2, relation)| (id1.to_string(), id2.to_string(), relation))
+                .collect(),
+            parents: clans
+                .clan_ids()
+                .filter_map(|child| {
+                    Some((child.to_string(), clans.parent_clan(child)?.to_string()))
+                })
+                .collect(),
+        }
+    }
+}
+
+/** How many crabs `Beach::import_stream` inserts at a time. */
+pub const IMPORT_BATCH: usize = 1024;
 
 #[derive(Debug)]
 pub struct Beach {
-    // TODO: Declare the fields of the Beach struct here.
+    // The crabs, with their handles and the ticks they arrived and last ate.
+    // Handles are issued in increasing order, so they stay sorted.
+    crabs: CrabStore,
+    next_handle: u64,
+    // The handles of the crabs with each name, in beach order. Every
+    // addition and removal goes through `push_crab` and `take_crab`,
+    // which keep it in step with the crabs.
+    by_name: HashMap<String, Vec<CrabId>>,
+    life: LifeRules,
+    lifecycle: LifecycleConfig,
+    breeding: BreedingState,
+    lineage: Lineage,
+    breeding_policy: Box<dyn BreedingPolicy>,
+    incubator: Incubator,
+    tide_pools: TidePools,
+    raids: Raids,
+    predators: Vec<Box<dyn Predator>>,
+    humans: Humans,
+    environment: Option<Environment>,
+    weather: Option<WeatherSystem>,
+    pollution: Pollution,
+    food: FoodSupply,
+    protection: Protection,
+    survey: Survey,
+    observers: Observers,
+    clan_system: ClanSystem,
+    events: EventBus,
+    economy: Economy,
+    treasury: TreasuryRules,
+    ticks: u64,
+    watches: WatchList,
+    constraints: Constraints,
+    territory: Option<SpatialIndex>,
+    movement: Option<Box<dyn MovementStrategy>>,
+    landmarks: Landmarks,
+    name_registry: Option<NameRegistry>,
+    history: Option<History>,
+    metrics: Option<MetricsRecorder>,
+    leaderboard: Leaderboard,
+    tie_breakers: Vec<TieBreaker>,
+    competitions: Vec<CompetitionRecord>,
+    // Scores crabs for clan leadership; the highest scoring member leads.
+    leadership: fn(&Crab) -> u32,
+    capacity: Option<usize>,
+    eviction: EvictionPolicy,
+    // Inhabitants of other species than crabs, in the order they arrived,
+    // with the ids they were issued alongside the crabs' to join clans by.
+    others: Vec<(CrabId, Box<dyn Inhabitant>)>,
+    // The beach's own random numbers, if it was seeded; otherwise its
+    // random operations draw from the crate-wide `GlobalRng`.
+    rng: Option<Pcg64>,
 }
 
 impl Beach {
     pub fn new() -> Beach {
-        unimplemented!();
+        Beach {
+            crabs: CrabStore::new(),
+            next_handle: 0,
+            by_name: HashMap::new(),
+            life: LifeRules::default(),
+            lifecycle: LifecycleConfig::default(),
+            breeding: BreedingState::default(),
+            lineage: Lineage::new(),
+            breeding_policy: Box::new(DefaultBreeding),
+            incubator: Incubator::default(),
+            tide_pools: TidePools::default(),
+            raids: Raids::default(),
+            predators: Vec::new(),
+            humans: Humans::new(),
+            environment: None,
+            weather: None,
+            pollution: Pollution::new(),
+            food: FoodSupply::new(),
+            protection: Protection::new(),
+            survey: Survey::new(),
+            observers: Observers::new(),
+            clan_system: ClanSystem::new(),
+            events: EventBus::new(),
+            economy: Economy::new(),
+            treasury: TreasuryRules::default(),
+            ticks: 0,
+            watches: WatchList::new(),
+            constraints: Constraints::new(),
+            territory: None,
+            movement: None,
+            landmarks: Landmarks::default(),
+            name_registry: None,
+            history: None,
+            metrics: None,
+            leaderboard: Leaderboard::new(),
+            tie_breakers: Vec::new(),
+            competitions: Vec::new(),
+            leadership: Crab::speed,
+            capacity: None,
+            eviction: EvictionPolicy::default(),
+            others: Vec::new(),
+            rng: None,
+        }
+    }
+
+    /**
+     * Returns an empty beach that holds at most `max_crabs` crabs, turning
+     * away any more; see `set_capacity`.
+     */
+    pub fn with_capacity(max_crabs: usize) -> Beach {
+        let mut beach = Beach::new();
+        beach.set_capacity(Some(max_crabs));
+        beach
+    }
+
+    /**
+     * Returns an empty beach with room reserved for `additional` crabs, so
+     * adding that many allocates no more room for them. Unlike
+     * `with_capacity`, it does not limit how many crabs the beach holds.
+     */
+    pub fn with_reserved(additional: usize) -> Beach {
+        let mut beach = Beach::new();
+        beach.reserve(additional);
+        beach
+    }
+
+    /** Reserves room for at least `additional` more crabs; see `with_reserved`. */
+    pub fn reserve(&mut self, additional: usize) {
+        self.crabs.reserve(additional);
+        self.by_name.reserve(additional);
+    }
+
+    /**
+     * Returns an empty beach whose random operations all draw from its own
+     * generator seeded with `seed`; see `set_seed`.
+     */
+    pub fn with_seed(seed: u64) -> Beach {
+        let mut beach = Beach::new();
+        beach.set_seed(seed);
+        beach
+    }
+
+    /**
+     * Seeds the beach's own random number generator. From now on the
+     * random diets of offspring, the mutations of its breeding policy and
+     * the times of `run_race` are drawn from it, so two beaches seeded
+     * alike and given the same calls behave alike.
+     */
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = Some(Pcg64::seed_from_u64(seed));
+    }
+
+    /**
+     * Calls `f` with the beach and the generator its random operations draw
+     * from: its own if it was seeded, or else `GlobalRng`.
+     */
+    pub(crate) fn with_rng<T>(&mut self, f: impl FnOnce(&Beach, &mut dyn RngCore) -> T) -> T {
+        match self.rng.take() {
+            Some(mut rng) => {
+                let result = f(self, &mut rng);
+                self.rng = Some(rng);
+                result
+            }
+            None => f(self, &mut GlobalRng),
+        }
+    }
+
+    /**
+     * Subscribes a listener to every event that happens on this beach from now on.
+     */
+    pub fn subscribe(&mut self, listener: Rc<RefCell<dyn EventListener>>) {
+        self.events.subscribe(listener);
+    }
+
+    /**
+     * Registers an observer whose callbacks are called as crabs arrive, are
+     * bred, join clans and leave the beach from now on.
+     */
+    pub fn register_observer(&mut self, observer: Rc<RefCell<dyn BeachObserver>>) {
+        self.events.register(observer);
+    }
+
+    /**
+     * Adds an observer that looks at the beach at the end of every tick from
+     * now on, after everything else that happens during the tick.
+     */
+    pub fn add_observer(&mut self, observer: Rc<RefCell<dyn Observe>>) {
+        self.observers.add(observer);
+    }
+
+    /**
+     * Registers a watch that calls `callback` each time its condition starts
+     * to hold. The condition is checked now, whenever a crab arrives or
+     * leaves, whenever clan membership changes, and on every tick.
+     */
+    pub fn watch(&mut self, name: &str, watch: Watch, callback: impl FnMut(&Alert) + 'static) {
+        self.watches.add(name, watch, callback);
+        self.check_watches();
+    }
+
+    /**
+     * Like `watch`, but sends the alerts down a channel instead. The watch is
+     * dropped once the receiver is.
+     */
+    pub fn watch_channel(&mut self, name: &str, watch: Watch) -> Receiver<Alert> {
+        let receiver = self.watches.add_channel(name, watch);
+        self.check_watches();
+        receiver
+    }
+
+    /**
+     * Removes the watches with the given name; returns whether there were any.
+     */
+    pub fn unwatch(&mut self, name: &str) -> bool {
+        self.watches.remove(name)
+    }
+
+    fn check_watches(&mut self) {
+        if self.watches.is_empty() {
+            return;
+        }
+        let mut watches = std::mem::take(&mut self.watches);
+        watches.check(self);
+        self.watches = watches;
+    }
+
+    /**
+     * Returns the number of ticks this beach has been simulated for.
+     */
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /**
+     * Advances the beach clock by one tick, and returns what happened: eggs
+     * hatch, the tide turns, seagulls, predators and humans strike, crabs
+     * move by the beach's movement strategy, and then crabs live by the
+     * beach's life rules. Crabs age, hungry crabs hunt for
+     * their diet, speeds change with age, and crabs die of old age or
+     * starvation. Crabs that hunt on their own also `Crab::advance` once, so
+     * their life state follows the ticks.
+     */
+    pub fn tick(&mut self) -> TickReport {
+        self.ticks += 1;
+        self.breeding.new_tick();
+        if let Some(environment) = self.environment.as_mut() {
+            environment.update(self.ticks);
+        }
+        if let Some(weather) = self.weather.as_mut() {
+            weather.advance(self.ticks);
+        }
+        self.suffer_pollution();
+        self.events.publish(BeachEvent::Tick { tick: self.ticks });
+        let mut report = TickReport {
+            tick: self.ticks,
+            births: self.hatch_clutches(),
+            ..TickReport::default()
+        };
+        let period = self.tide_pools.period();
+        if period > 0 && self.ticks.is_multiple_of(period) {
+            self.turn_tide();
+        }
+        report.deaths.extend(self.brave_weather());
+        let died = |cause| move |name| Death { name, cause };
+        if self.raids.due(self.ticks) {
+            let raid = self.seagull_raid();
+            let deaths = raid.casualties.into_iter().map(died(Cause::Seagulls));
+            report.deaths.extend(deaths);
+        }
+        report.hunts = self.hunt_crabs();
+        let eaten = report.hunts.iter().filter(|hunt| hunt.caught);
+        let deaths = eaten
+            .map(|hunt| hunt.prey.clone())
+            .map(died(Cause::Predation));
+        report.deaths.extend(deaths);
+        self.humans.reopen(self.ticks);
+        for interference in self.humans.take_due(self.ticks) {
+            let caught = self.interfere(interference);
+            report
+                .deaths
+                .extend(caught.into_iter().map(died(Cause::Fishing)));
+        }
+        self.move_crabs();
+        self.live(&mut report);
+        self.check_watches();
+        report.population = self.crabs.len();
+        if let Some(mut metrics) = self.metrics.take() {
+            metrics.record(TickMetrics::measure(self, &report));
+            self.metrics = Some(metrics);
+        }
+        let observers = std::mem::take(&mut self.observers);
+        observers.observe(self);
+        self.observers = observers;
+        report
+    }
+
+    /**
+     * Sets how crabs age, eat and die on each tick. Crabs count as just fed
+     * when the rules are set.
+     */
+    pub fn set_life_rules(&mut self, rules: LifeRules) {
+        self.life = rules;
+        self.crabs.fill_fed_at(self.ticks);
+    }
+
+    pub fn life_rules(&self) -> LifeRules {
+        self.life
+    }
+
+    /** Sets how crabs' speeds follow their age as `age_all` ages them. */
+    pub fn set_lifecycle(&mut self, lifecycle: LifecycleConfig) {
+        self.lifecycle = lifecycle;
+    }
+
+    pub fn lifecycle(&self) -> LifecycleConfig {
+        self.lifecycle
+    }
+
+    /**
+     * Ages every living crab by the given number of years, their speeds
+     * following the beach's lifecycle, as `Crab::grow_older` does.
+     */
+    pub fn age_all(&mut self, years: u64) {
+        if years == 0 || self.crabs.is_empty() {
+            return;
+        }
+        self.checkpoint();
+        let lifecycle = self.lifecycle;
+        for crab in &mut self.crabs {
+            crab.grow_older(years, &lifecycle);
+        }
+        self.check_watches();
+    }
+
+    /**
+     * Returns the stage of life of the crab at the given index by the
+     * beach's lifecycle. If the index is out of bounds, the method should
+     * panic.
+     */
+    pub fn life_stage(&self, index: usize) -> LifeStage {
+        self.crabs[index].life_stage(&self.lifecycle)
+    }
+
+    /** Returns how many ticks ago the crab at the given index last ate. */
+    pub fn ticks_since_meal(&self, index: usize) -> u64 {
+        self.ticks - self.crabs.fed_at(index)
+    }
+
+    /** Has every crab live through the tick by the life rules. */
+    fn live(&mut self, report: &mut TickReport) {
+        let rules = self.life;
+        for index in 0..self.crabs.len() {
+            let age = self.age(index);
+            let crab = &mut self.crabs[index];
+            if age < rules.maturity {
+                crab.set_speed(crab.typed_speed().saturating_add(Speed::new(1)).get());
+            } else if rules.senescence > 0 && age > rules.senescence {
+                crab.set_speed(crab.speed().saturating_sub(1).max(1));
+            }
+            if rules.appetite > 0 {
+                // Crabs that hunt on their own grow hungry on their own,
+                // and starve if they go on too long without a meal.
+                crab.advance();
+            }
+
+            if rules.appetite > 0 && self.ticks_since_meal(index) >= rules.appetite {
+                let caught = self.forage(index);
+                let crab = &mut self.crabs[index];
+                report.meals.push(Meal {
+                    name: crab.name().to_string(),
+                    diet: crab.diet(),
+                    caught,
+                });
+                if caught {
+                    crab.feed();
+                    crab.heal(rules.nourishment);
+                    self.crabs.set_fed_at(index, self.ticks);
+                } else {
+                    crab.injure(rules.starvation);
+                }
+            }
+        }
+
+        for index in (0..self.crabs.len()).rev() {
+            let cause = if rules.lifespan > 0 && self.age(index) > rules.lifespan {
+                Cause::OldAge
+            } else if rules.appetite > 0 && self.crabs[index].health() == 0 {
+                Cause::Starvation
+            } else {
+                continue;
+            };
+            let name = self
+                .unrecorded(|beach| beach.remove_crab_at(index))
+                .name()
+                .to_string();
+            self.events.publish(BeachEvent::CrabDied {
+                name: name.clone(),
+                cause,
+            });
+            report.deaths.push(Death { name, cause });
+        }
+    }
+
+    /**
+     * Removes every crab identical to one that came before it on the beach,
+     * like `remove_crab`, and returns them in the order they lived on the
+     * beach; see `Crab::is_identical`.
+     */
+    pub fn dedup_identical_crabs(&mut self) -> Vec<Crab> {
+        let mut copies = Vec::new();
+        for handles in self.by_name.values() {
+            for (k, &handle) in handles.iter().enumerate() {
+                let crab = self.crabs.get(handle).expect("named crabs live here");
+                let mut earlier = handles[..k].iter().filter_map(|&h| self.crabs.get(h));
+                if earlier.any(|e| e.is_identical(crab)) {
+                    copies.push(self.crabs.index_of(handle).expect("named crabs live here"));
+                }
+            }
+        }
+        if copies.is_empty() {
+            return Vec::new();
+        }
+        copies.sort_unstable();
+        self.record(|beach| {
+            let mut removed: Vec<Crab> = copies
+                .iter()
+                .rev()
+                .map(|&i| beach.remove_crab_at(i))
+                .collect();
+            removed.reverse();
+            removed
+        })
+    }
+
+    /**
+     * Removes every crab whose health has run out, like `remove_crab`, and
+     * returns them in the order they lived on the beach.
+     */
+    pub fn remove_dead_crabs(&mut self) -> Vec<Crab> {
+        if !self.crabs.iter().any(Crab::is_dead) {
+            return Vec::new();
+        }
+        self.record(|beach| {
+            let mut dead = Vec::new();
+            for index in (0..beach.crabs.len()).rev() {
+                if beach.crabs[index].is_dead() {
+                    dead.push(beach.remove_crab_at(index));
+                }
+            }
+            dead.reverse();
+            dead
+        })
+    }
+
+    /** Returns how many crabs are in each life state, in state order. */
+    pub fn count_by_state(&self) -> BTreeMap<LifeState, usize> {
+        let mut counts = BTreeMap::new();
+        for crab in &self.crabs {
+            *counts.entry(crab.state()).or_default() += 1;
+        }
+        counts
+    }
+
+    /**
+     * Estimates the memory used by the crabs, their names and the clans.
+     */
+    pub fn memory_stats(&self) -> MemoryStats {
+        let reef_lists: usize = self.crabs.iter().map(|c| c.reef_list_bytes()).sum();
+        MemoryStats {
+            crabs: self.crabs.memory_bytes() + reef_lists,
+            names: self.crabs.iter().map(|c| c.name_bytes()).sum::<usize>()
+                + self
+                    .by_name
+                    .iter()
+                    .map(|(name, handles)| {
+                        name.capacity()
+                            + handles.capacity() * std::mem::size_of::<CrabId>()
+                            + std::mem::size_of::<(String, Vec<CrabId>)>()
+                    })
+                    .sum::<usize>(),
+            clans: self.clan_system.memory_bytes(),
+        }
     }
 
     /**
      * Returns the number of crabs on the beach.
      */
     pub fn size(&self) -> usize {
-        unimplemented!();
+        self.crabs.len()
     }
 
     /**
@@ -27,64 +633,3339 @@ impl Beach {
      *   - After `add_crab` returns:
      *     - The Beach should hold the crab in its collection of crabs.
      *     - The newly added crab should be at the END of the collection.
+     *
+     * Returns the crabs evicted to make room for it, in the order they left.
+     * If the crab breaks the beach's constraints, the beach rejects
+     * duplicate names and the name is taken, or the beach is full and turns
+     * newcomers away, the method should panic.
      */
-    pub fn add_crab(&mut self, crab: Crab) {
-        unimplemented!();
+    pub fn add_crab(&mut self, crab: Crab) -> Vec<Crab> {
+        self.arrive(crab).1
     }
 
-    pub fn get_crab(&self, index: usize) -> &Crab {
-        unimplemented!();
+    /**
+     * Adds crabs at the end, in order, each like `add_crab`, reserving room
+     * for them up front and checking watches once they are all here. Returns
+     * the crabs evicted to make room for them, in the order they left; these
+     * may include crabs of the batch. The whole batch is checked before any
+     * crab is added, so if the method panics, the beach is left as it was.
+     * Counts as one change for `undo`.
+     */
+    pub fn add_crabs(&mut self, crabs: impl IntoIterator<Item = Crab>) -> Vec<Crab> {
+        let crabs: Vec<Crab> = crabs.into_iter().collect();
+        if crabs.is_empty() {
+            return Vec::new();
+        }
+        if let Err(reason) = self.check_batch(&crabs) {
+            panic!("{}", reason);
+        }
+        self.reserve(crabs.len());
+        self.checkpoint();
+        let mut evicted = Vec::new();
+        for crab in crabs {
+            evicted.extend(self.make_room());
+            self.push_crab(crab);
+        }
+        self.check_watches();
+        evicted
     }
 
-    pub fn crabs(&self) -> Iter<Crab> {
-        unimplemented!();
+    /**
+     * Returns an Err if adding the crabs one after the other would fail on
+     * one of them: a crab breaks the beach's constraints, a name is taken,
+     * on the beach or earlier in the batch, or the beach runs out of room it
+     * cannot make.
+     */
+    fn check_batch(&self, crabs: &[Crab]) -> Result<(), OceanError> {
+        let mut names = HashSet::new();
+        for crab in crabs {
+            self.check_arrival(crab)?;
+            if self.rejects_duplicate_names() && !names.insert(crab.name()) {
+                return Err(OceanError::DuplicateName(crab.name().to_string()));
+            }
+        }
+        self.check_room_for(crabs.len())
     }
 
     /**
-     * Returns:
-     *   - None if the beach is empty.
-     *   - Some of a reference to the Crab with the highest speed.
+     * Adds a crab like `add_crab` and returns the id it was given. Crabs
+     * evicted to make room for it are dropped; `add_crab` returns them.
      */
-    pub fn get_fastest_crab(&self) -> Option<&Crab> {
-        unimplemented!();
+    pub fn add_crab_with_id(&mut self, crab: Crab) -> CrabId {
+        self.arrive(crab).0
+    }
+
+    /** Adds a crab like `add_crab`, returning its id and the crabs evicted. */
+    fn arrive(&mut self, crab: Crab) -> (CrabId, Vec<Crab>) {
+        let checked = self.check_arrival(&crab).and_then(|_| self.check_room());
+        if let Err(reason) = checked {
+            panic!("{}", reason);
+        }
+        self.checkpoint();
+        let evicted = self.make_room();
+        let id = self.push_crab(crab);
+        self.check_watches();
+        (id, evicted)
     }
 
     /**
-     * Returns a vector of references to the crabs with a given name.
+     * Adds an inhabitant of any species, returning the id it is issued. A
+     * crab arrives like `add_crab`. Other species live alongside the crabs,
+     * where they can join clans and race, but they are not among `crabs`, do
+     * not count towards `size` or the capacity, and are not part of
+     * snapshots or the undo history.
      */
-    pub fn find_crabs_by_name(&self, name: &str) -> Vec<&Crab> {
-        unimplemented!();
+    pub fn add_inhabitant(&mut self, inhabitant: Box<dyn Inhabitant>) -> CrabId {
+        if inhabitant.is::<Crab>() {
+            let crab = inhabitant.into_any().downcast::<Crab>().unwrap();
+            self.add_crab_with_id(*crab)
+        } else {
+            let id = CrabId(self.next_handle);
+            self.next_handle += 1;
+            self.others.push((id, inhabitant));
+            id
+        }
+    }
+
+    /** Returns every inhabitant: the crabs, then the other species. */
+    pub fn inhabitants(&self) -> Vec<&dyn Inhabitant> {
+        let crabs = self.crabs.iter().map(|crab| crab as &dyn Inhabitant);
+        let others = self.others.iter().map(|(_, other)| other.as_ref());
+        crabs.chain(others).collect()
+    }
+
+    /** Returns the first inhabitant with the given name, if there is one. */
+    pub fn inhabitant(&self, name: &str) -> Option<&dyn Inhabitant> {
+        self.inhabitants()
+            .into_iter()
+            .find(|inhabitant| inhabitant.name() == name)
     }
 
     /**
-     * Breeds the `Crab`s at indices `i` and `j`, adding the new `Crab` to
-     * the end of the beach's crab vector. If the indices are out of bounds,
-     * the method should panic.
+     * Removes the first inhabitant with the given name, a crab like
+     * `remove_crab`, and returns it.
      */
-    pub fn breed_crabs(&mut self, i: usize, j: usize, name: String) {
-        unimplemented!();
+    pub fn remove_inhabitant(&mut self, name: &str) -> Option<Box<dyn Inhabitant>> {
+        if let Some(index) = self.crabs.iter().position(|c| c.name() == name) {
+            return Some(Box::new(self.remove_crab_at(index)));
+        }
+        let index = self.others.iter().position(|(_, o)| o.name() == name)?;
+        let (id, other) = self.others.remove(index);
+        self.remove_member_by_id(id);
+        Some(other)
     }
 
     /**
-     * Returns a reference to the clan system associated with the beach.
+     * Adds a crab like `add_crab`, returning the crabs evicted to make room
+     * for it, or why it cannot: the crab breaks the beach's constraints, the
+     * beach is full and turns newcomers away, or it keeps names unique and
+     * the name is taken.
      */
-    pub fn get_clan_system(&self) -> &ClanSystem {
-        unimplemented!();
+    pub fn admit_crab(&mut self, crab: Crab) -> Result<Vec<Crab>, OceanError> {
+        self.check_arrival(&crab)?;
+        self.check_room()?;
+        self.checkpoint();
+        let evicted = self.make_room();
+        self.push_crab(crab);
+        self.check_watches();
+        Ok(evicted)
     }
 
     /**
-     * Adds a crab that lives on the beach as a member to the clan system for the given clan id and the crab's name.
-     * A crab can only belong to one clan.
+     * Limits how many crabs the beach holds, or lifts the limit. Crabs
+     * arriving at a full beach, however they arrive, make room by the
+     * eviction policy, or are turned away: `add_crab`, `breed_crabs`,
+     * `transfer_crab` and `merge` panic, the `try_` and `admit_` methods
+     * return an Err, imports stop, and eggs that cannot hatch are lost. Crabs already on the beach stay, even if there
+     * are more than the limit allows.
      */
-    pub fn add_member_to_clan(&mut self, clan_id: &str, crab_name: &str) {
-        unimplemented!();
+    pub fn set_capacity(&mut self, max_crabs: Option<usize>) {
+        self.capacity = max_crabs;
+    }
+
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    /** Sets how a full beach makes room; it turns newcomers away unless set. */
+    pub fn set_eviction_policy(&mut self, policy: EvictionPolicy) {
+        self.eviction = policy;
+    }
+
+    pub fn eviction_policy(&self) -> EvictionPolicy {
+        self.eviction
     }
 
     /**
-     * Returns the id of the clan that wins the competition given two clan ids. The winner is decided based on the average speed of the clan members.
-     * Return `None` if there are no clear winners between two different existing clans. If the inputs are invalid, return an Err string.
+     * Returns an Err if the crab breaks the beach's constraints, or the
+     * beach keeps names unique and the crab's name is taken.
+     */
+    fn check_arrival(&self, crab: &Crab) -> Result<(), OceanError> {
+        self.constraints
+            .check(crab)
+            .map_err(OceanError::OutOfBounds)?;
+        self.check_name(crab.name())
+    }
+
+    /** Returns an Err if the beach is full and cannot make room for a crab. */
+    fn check_room(&self) -> Result<(), OceanError> {
+        self.check_room_for(1)
+    }
+
+    /**
+     * Returns an Err if the beach cannot make room for the given number of
+     * crabs arriving one after the other.
+     */
+    fn check_room_for(&self, arriving: usize) -> Result<(), OceanError> {
+        match self.capacity {
+            Some(capacity)
+                if self.crabs.len() + arriving > capacity
+                    && (capacity == 0 || self.eviction == EvictionPolicy::RejectNew) =>
+            {
+                Err(OceanError::BeachFull { capacity })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /**
+     * Evicts crabs by the eviction policy until there is room for one more,
+     * returning them in the order they left.
+     */
+    fn make_room(&mut self) -> Vec<Crab> {
+        let mut evicted = Vec::new();
+        while self
+            .capacity
+            .is_some_and(|capacity| self.crabs.len() >= capacity)
+        {
+            let Some(victim) = self.eviction.victim(&self.crabs) else {
+                break;
+            };
+            evicted.push(self.unrecorded(|beach| beach.remove_crab_at(victim)));
+        }
+        evicted
+    }
+
+    /**
+     * Adds a crab like `add_crab_with_id`, or returns why it cannot: the
+     * crab breaks the beach's constraints, or the beach rejects duplicate
+     * names and the name is taken.
+     */
+    pub fn register_crab(&mut self, crab: Crab) -> Result<CrabId, OceanError> {
+        self.check_arrival(&crab)?;
+        Ok(self.add_crab_with_id(crab))
+    }
+
+    /**
+     * Makes the beach keep its crabs' names unique from now on. Under
+     * `NamePolicy::Reject`, `register_crab` and `try_breed_crabs` refuse a
+     * taken name; crabs that arrive any other way, such as by hatching or
+     * being transferred, get a suffix under either policy. Crabs already on
+     * the beach keep their names; `audit::audit_names` finds the duplicates.
+     */
+    pub fn set_name_registry(&mut self, registry: Option<NameRegistry>) {
+        self.name_registry = registry;
+    }
+
+    pub fn name_registry(&self) -> Option<&NameRegistry> {
+        self.name_registry.as_ref()
+    }
+
+    fn rejects_duplicate_names(&self) -> bool {
+        self.name_registry
+            .is_some_and(|registry| registry.policy == NamePolicy::Reject)
+    }
+
+    /** Fails if the beach rejects duplicate names and `name` is taken. */
+    fn check_name(&self, name: &str) -> Result<(), OceanError> {
+        if self.rejects_duplicate_names() && self.by_name.contains_key(name) {
+            return Err(OceanError::DuplicateName(name.to_string()));
+        }
+        Ok(())
+    }
+
+    /**
+     * Returns a copy of the beach's crabs, clans and family records, which
+     * `restore` can put back later.
+     */
+    pub fn snapshot(&self) -> BeachSnapshot {
+        BeachSnapshot {
+            crabs: self.crabs.duplicate(),
+            by_name: self.by_name.clone(),
+            clan_system: self.clan_system.clone(),
+            lineage: self.lineage.clone(),
+            breeding: self.breeding.clone(),
+        }
+    }
+
+    /**
+     * Puts back the crabs, clans and family records of a snapshot, replacing
+     * the current ones; this counts as a change for `undo`. Ids issued since
+     * the snapshot are never reused, and the clock and everything else about
+     * the beach stay as they are.
+     */
+    pub fn restore(&mut self, snapshot: BeachSnapshot) {
+        self.checkpoint();
+        self.put_back(snapshot);
+    }
+
+    /**
+     * Puts back a snapshot like `restore`, and sets the clock to the tick it
+     * was taken at, without publishing an event; this is how a tick is
+     * undone or redone. Crabs in the snapshot must have arrived by `ticks`.
+     */
+    pub fn restore_at(&mut self, snapshot: BeachSnapshot, ticks: u64) {
+        self.restore(snapshot);
+        self.ticks = ticks;
+    }
+
+    fn put_back(&mut self, snapshot: BeachSnapshot) {
+        let BeachSnapshot {
+            crabs,
+            by_name,
+            clan_system,
+            lineage,
+            breeding,
+        } = snapshot;
+        self.crabs = crabs;
+        self.by_name = by_name;
+        self.clan_system = clan_system;
+        self.lineage = lineage;
+        self.breeding = breeding;
+        self.territory_may_move();
+        self.refresh_territory();
+        self.check_watches();
+    }
+
+    /**
+     * Starts measuring the beach at the end of every tick, keeping the
+     * measurements of the `retention` most recent ticks; see `MetricsRecorder`.
+     */
+    pub fn enable_metrics(&mut self, retention: usize) {
+        match &mut self.metrics {
+            Some(metrics) => metrics.set_retention(retention),
+            None => self.metrics = Some(MetricsRecorder::new(retention)),
+        }
+    }
+
+    /** Stops measuring the beach, forgetting the measurements so far. */
+    pub fn disable_metrics(&mut self) {
+        self.metrics = None;
+    }
+
+    /** Returns the beach's recent measurements, if it is being measured. */
+    pub fn metrics(&self) -> Option<&MetricsRecorder> {
+        self.metrics.as_ref()
+    }
+
+    /**
+     * Starts keeping snapshots from before the `depth` most recent changes,
+     * so that `undo` can take them back. A crab arriving, leaving, being
+     * bred or being renamed, and a clan gaining or losing members or
+     * relations, each count as one change; changing a crab in place does
+     * not. A depth of 0 keeps no history.
+     */
+    pub fn enable_history(&mut self, depth: usize) {
+        match &mut self.history {
+            Some(history) => history.set_depth(depth),
+            None => self.history = Some(History::new(depth)),
+        }
+    }
+
+    /** Stops keeping snapshots, forgetting the ones kept so far. */
+    pub fn disable_history(&mut self) {
+        self.history = None;
+    }
+
+    /** Returns how many changes `undo` can take back. */
+    pub fn undo_depth(&self) -> usize {
+        self.history.as_ref().map_or(0, History::len)
+    }
+
+    /**
+     * Takes back the most recent change, putting back the crabs, clans and
+     * family records from before it. Returns false if there is nothing to
+     * undo.
+     */
+    pub fn undo(&mut self) -> bool {
+        match self.history.as_mut().and_then(History::pop) {
+            Some(snapshot) => {
+                self.put_back(snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /** Takes a snapshot before a change, if the beach keeps history. */
+    fn checkpoint(&mut self) {
+        if self.history.is_some() {
+            let snapshot = self.snapshot();
+            if let Some(history) = &mut self.history {
+                history.push(snapshot);
+            }
+        }
+    }
+
+    /** Runs `change` as part of a change that has been checkpointed already. */
+    fn unrecorded<T>(&mut self, change: impl FnOnce(&mut Beach) -> T) -> T {
+        let history = self.history.take();
+        let result = change(self);
+        self.history = history;
+        result
+    }
+
+    /** Makes `change` one change for `undo`, however many steps it takes. */
+    pub(crate) fn record<T>(&mut self, change: impl FnOnce(&mut Beach) -> T) -> T {
+        self.checkpoint();
+        self.unrecorded(change)
+    }
+
+    /** Like `record`, but only counts `change` as a change if it succeeds. */
+    fn record_ok<T, E>(&mut self, change: impl FnOnce(&mut Beach) -> Result<T, E>) -> Result<T, E> {
+        let snapshot = self.history.is_some().then(|| self.snapshot());
+        let result = self.unrecorded(change);
+        if let (Ok(_), Some(snapshot), Some(history)) = (&result, snapshot, &mut self.history) {
+            history.push(snapshot);
+        }
+        result
+    }
+
+    /** Returns the name a crab called `name` gets on arriving here. */
+    fn free_name(&self, name: &str) -> String {
+        match self.name_registry {
+            Some(_) => NameRegistry::free_name(name, |name| self.by_name.contains_key(name)),
+            None => name.to_string(),
+        }
+    }
+
+    /**
+     * Sets the constraints crabs must keep to on arriving at the beach, by
+     * `add_crab` and the other methods that bring crabs from elsewhere, and
+     * on being changed through `update_crab`, `get_crab_mut`, `crabs_mut` or
+     * `for_each_crab_mut`. Crabs already here are not checked until they
+     * change. A new beach has `Constraints::new()`; `Constraints::none()`
+     * accepts every crab.
+     */
+    pub fn set_constraints(&mut self, constraints: Constraints) {
+        self.constraints = constraints;
+    }
+
+    pub fn constraints(&self) -> &Constraints {
+        &self.constraints
+    }
+
+    /**
+     * Adds a crab like `add_crab`, unless it breaks the beach's constraints.
+     */
+    pub fn try_add_crab(&mut self, crab: Crab) -> Result<(), ConstraintError> {
+        self.constraints.check(&crab)?;
+        self.add_crab(crab);
+        Ok(())
+    }
+
+    /**
+     * Changes the crab at the given index through `change`, then checks it
+     * against the beach's constraints. A change that breaks them is undone
+     * entirely and reported. If the index is out of bounds, the method
+     * should panic.
+     */
+    pub fn update_crab(
+        &mut self,
+        index: usize,
+        change: impl FnOnce(&mut Crab),
+    ) -> Result<(), ConstraintError> {
+        let crab = &mut self.crabs[index];
+        let before = crab.duplicate();
+        let position = crab.position();
+        change(crab);
+        let checked = self.constraints.check(crab);
+        if checked.is_err() {
+            *crab = before;
+        }
+        let moved_to = crab.position();
+        if let Some(spatial) = &mut self.territory {
+            spatial.relocate(self.crabs.handle(index), position, moved_to);
+        }
+        checked
+    }
+
+    /** Adds a crab at the end, issuing its handle and announcing it. */
+    fn push_crab(&mut self, crab: Crab) -> CrabId {
+        self.push_crab_as(CrabId(self.next_handle), crab)
+    }
+
+    /**
+     * Adds a crab at the end under the given handle, which must be newer
+     * than any issued so far, and announces it.
+     */
+    fn push_crab_as(&mut self, handle: CrabId, mut crab: Crab) -> CrabId {
+        self.next_handle = handle.0 + 1;
+        let name = self.free_name(crab.name());
+        if name != crab.name() {
+            crab.set_name(name.clone());
+        }
+        self.by_name.entry(name.clone()).or_default().push(handle);
+        if let Some(spatial) = &mut self.territory {
+            spatial.insert(handle, crab.position());
+        }
+        crab.set_id(Some(handle));
+        self.crabs.push(handle, crab, self.ticks);
+        self.events.publish(BeachEvent::CrabAdded { name });
+        handle
+    }
+
+    /**
+     * Returns a stable handle to the crab at the given index.
+     * If the index is out of bounds, the method should panic.
+     */
+    pub fn handle(&self, index: usize) -> CrabId {
+        self.crabs.handle(index)
+    }
+
+    /**
+     * Returns the current index of the crab a handle refers to, or None if
+     * that crab no longer lives on the beach.
+     */
+    pub fn index_of(&self, handle: CrabId) -> Option<usize> {
+        self.crabs.index_of(handle)
+    }
+
+    /**
+     * Returns the crab a handle refers to, or None if it no longer lives on
+     * the beach.
      */
-    pub fn get_winner_clan(&self, id1: &str, id2: &str) -> Result<Option<String>, String> {
-        unimplemented!();
+    pub fn resolve(&self, handle: CrabId) -> Option<&Crab> {
+        self.crabs.get(handle)
+    }
+
+    pub fn resolve_mut(&mut self, handle: CrabId) -> Option<&mut Crab> {
+        self.crabs.get(handle)?;
+        self.territory_may_move();
+        self.crabs.get_mut(handle)
+    }
+
+    /** Returns the crab with the given id, or None if it left the beach. */
+    pub fn get_crab_by_id(&self, id: CrabId) -> Option<&Crab> {
+        self.resolve(id)
+    }
+
+    /** Removes the crab with the given id like `remove_crab`, if it is still here. */
+    pub fn remove_crab_by_id(&mut self, id: CrabId) -> Option<Crab> {
+        self.index_of(id).map(|index| self.remove_crab_at(index))
+    }
+
+    /**
+     * Breeds the crabs with the given ids like `try_breed_crabs`, returning
+     * the id of the child. Fails if either crab has left the beach.
+     */
+    pub fn breed_crabs_by_id(
+        &mut self,
+        parent1: CrabId,
+        parent2: CrabId,
+        name: String,
+    ) -> Result<CrabId, OceanError> {
+        let index = |id| self.index_of(id).ok_or(OceanError::UnknownCrabId(id));
+        let (i, j) = (index(parent1)?, index(parent2)?);
+        self.try_breed_crabs(i, j, name)?;
+        Ok(self.crabs.handle(self.crabs.len() - 1))
+    }
+
+    /**
+     * Returns the crab at the given index. If the index is out of bounds,
+     * the method should panic.
+     */
+    #[deprecated(note = "indices shift as crabs leave; use `get_crab_by_id`")]
+    pub fn get_crab(&self, index: usize) -> &Crab {
+        self.crab_at(index)
+    }
+
+    pub(crate) fn crab_at(&self, index: usize) -> &Crab {
+        &self.crabs[index]
+    }
+
+    /** Returns the crab at the given index like `get_crab`, without panicking. */
+    pub fn try_get_crab(&self, index: usize) -> Result<&Crab, OceanError> {
+        self.check_index(index)?;
+        Ok(&self.crabs[index])
+    }
+
+    fn check_index(&self, index: usize) -> Result<(), OceanError> {
+        if self.crabs.is_empty() {
+            Err(OceanError::EmptyBeach)
+        } else if index >= self.crabs.len() {
+            Err(OceanError::InvalidIndex {
+                index,
+                size: self.crabs.len(),
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /**
+     * Returns the crab at the given index for changing in place. Watches
+     * see changes made through it when the beach next checks them, on its
+     * next change or tick; `for_each_crab_mut` checks them straight away.
+     * The beach's constraints are checked once the crab is let go; see
+     * `CrabMut`. If the index is out of bounds, the method should panic.
+     */
+    pub fn get_crab_mut(&mut self, index: usize) -> CrabMut<'_> {
+        self.territory_may_move();
+        CrabMut::new(&mut self.crabs[index], &self.constraints)
+    }
+
+    /**
+     * Removes the crab at the end of the collection like `remove_crab` and
+     * returns it, or None if the beach is empty.
+     */
+    pub fn pop_crab(&mut self) -> Option<Crab> {
+        let last = self.crabs.len().checked_sub(1)?;
+        Some(self.remove_crab_at(last))
+    }
+
+    /**
+     * Removes the crab at the given index, shifting later crabs down.
+     * If the index is out of bounds, the method should panic.
+     */
+    fn take_crab(&mut self, index: usize) -> Crab {
+        let crab = self.detach_crab(index);
+        self.events.publish(BeachEvent::CrabRemoved {
+            name: crab.name().to_string(),
+        });
+        crab
+    }
+
+    /**
+     * Removes the crab at the given index like `take_crab`, without
+     * announcing it, for removals whose own event names every crab taken.
+     */
+    fn detach_crab(&mut self, index: usize) -> Crab {
+        let (handle, mut crab) = self.crabs.remove(index);
+        crab.set_id(None);
+        self.tide_pools.leave(handle);
+        if let Some(spatial) = &mut self.territory {
+            spatial.remove(handle, crab.position());
+        }
+        if let Some(named) = self.by_name.get_mut(crab.name()) {
+            named.retain(|&h| h != handle);
+            if named.is_empty() {
+                self.by_name.remove(crab.name());
+            }
+        }
+        crab
+    }
+
+    /**
+     * Removes a crab that left the beach from its clan, if it had one,
+     * electing a new leader if it led the clan.
+     */
+    fn leave_clans(&mut self, handle: CrabId) {
+        self.clan_system.remove_member(handle);
+        self.fill_vacancies();
+    }
+
+    /**
+     * Removes the crab at the given index and returns it, shifting later
+     * crabs down. The crab also leaves its clan. If the index is out of
+     * bounds, the method should panic.
+     */
+    #[deprecated(note = "indices shift as crabs leave; use `remove_crab_by_id`")]
+    pub fn remove_crab(&mut self, index: usize) -> Crab {
+        self.remove_crab_at(index)
+    }
+
+    pub(crate) fn remove_crab_at(&mut self, index: usize) -> Crab {
+        self.checkpoint();
+        let handle = self.crabs.handle(index);
+        let crab = self.take_crab(index);
+        self.leave_clans(handle);
+        self.check_watches();
+        crab
+    }
+
+    /**
+     * Removes every crab with the given name, returning them in the order
+     * they lived on the beach, and takes each out of its clan.
+     */
+    pub fn remove_crabs_by_name(&mut self, name: &str) -> Vec<Crab> {
+        let handles = self.by_name.get(name).cloned().unwrap_or_default();
+        if !handles.is_empty() {
+            self.checkpoint();
+        }
+        let mut removed = Vec::new();
+        for &handle in handles.iter().rev() {
+            let index = self.crabs.index_of(handle).unwrap();
+            removed.push(self.take_crab(index));
+            self.leave_clans(handle);
+        }
+        removed.reverse();
+        if !removed.is_empty() {
+            self.check_watches();
+        }
+        removed
+    }
+
+    /**
+     * Moves the crab at the given index to another beach, where it joins
+     * the clan it belonged to here, and returns the crabs evicted from the
+     * other beach to make room for it. If the index is out of bounds, or the
+     * other beach is full and turns newcomers away, or the crab breaks its
+     * constraints, the method should panic.
+     */
+    pub fn transfer_crab(&mut self, index: usize, dest: &mut Beach) -> Vec<Crab> {
+        let checked = dest
+            .constraints
+            .check(&self.crabs[index])
+            .map_err(OceanError::OutOfBounds)
+            .and_then(|_| dest.check_room());
+        if let Err(reason) = checked {
+            panic!("{}", reason);
+        }
+        let handle = self.crabs.handle(index);
+        let clan = self.clan_system.get_clan_of(handle).map(String::from);
+        let crab = self.remove_crab_at(index);
+        dest.record(|dest| {
+            let evicted = dest.make_room();
+            let arrived = dest.push_crab(crab);
+            dest.check_watches();
+            if let Some(id) = clan {
+                dest.add_member_to_clan_by_id(&id, arrived);
+            }
+            evicted
+        })
+    }
+
+    /**
+     * Combines two beaches into one. This beach keeps its settings and
+     * crabs, and the other beach's crabs arrive after them, with new ids,
+     * and join their clans here. A clan found on both beaches ends up with
+     * the members of both. The other beach's clan relations and sub-clans
+     * are kept wherever this beach has none of its own.
+     *
+     * The other beach's crabs arrive like `add_crabs`, making room by this
+     * beach's eviction policy; the crabs evicted are dropped. If this beach
+     * cannot make room for them all, or one of them breaks its constraints,
+     * the method should panic.
+     */
+    pub fn merge(mut self, other: Beach) -> Beach {
+        let Beach {
+            crabs, clan_system, ..
+        } = other;
+        let checked = crabs
+            .iter()
+            .try_for_each(|crab| self.constraints.check(crab))
+            .map_err(OceanError::OutOfBounds)
+            .and_then(|_| self.check_room_for(crabs.len()));
+        if let Err(reason) = checked {
+            panic!("{}", reason);
+        }
+        self.checkpoint();
+        // The other beach's ids mean nothing here, so note what each became.
+        let mut arrived: HashMap<CrabId, CrabId> = HashMap::new();
+        for (handle, crab) in crabs.handles().into_iter().zip(crabs.into_crabs()) {
+            self.make_room();
+            arrived.insert(handle, self.push_crab(crab));
+        }
+        for id in clan_system.clan_ids() {
+            for member in clan_system.clan_members(id) {
+                if let Some(&crab) = arrived.get(member) {
+                    self.unrecorded(|beach| beach.add_member_to_clan_by_id(id, crab));
+                }
+            }
+        }
+        self.adopt_clan_links(ClanLinks::of(&clan_system));
+        self.check_watches();
+        self
+    }
+
+    /**
+     * Splits the beach in two: the crabs for which `f` returns true stay on
+     * the first beach, which keeps this beach's settings, and the others
+     * move to a new second beach, in order. Each crab keeps its clan
+     * memberships on the beach it ends up on, along with the relations and
+     * sub-clans between those clans.
+     */
+    pub fn split_by<F: Fn(&Crab) -> bool>(mut self, f: F) -> (Beach, Beach) {
+        let leaving: Vec<usize> = (0..self.crabs.len())
+            .filter(|&index| !f(&self.crabs[index]))
+            .collect();
+        // Clans left empty here lose their links, so note them first.
+        let links = ClanLinks::of(&self.clan_system);
+        let mut rest = Beach::new();
+        self.checkpoint();
+        for (moved, index) in leaving.into_iter().enumerate() {
+            self.unrecorded(|beach| beach.transfer_crab(index - moved, &mut rest));
+        }
+        rest.adopt_clan_links(links);
+        (self, rest)
+    }
+
+    /**
+     * Adds relations and sub-clan links between clans that exist here,
+     * unless this beach already has its own.
+     */
+    fn adopt_clan_links(&mut self, links: ClanLinks) {
+        for (id1, id2, relation) in links.relations {
+            if self.clan_system.get_relation(&id1, &id2).is_none() {
+                // Fails only if one of the clans does not exist here.
+                let _ = self.clan_system.set_relation(&id1, &id2, relation);
+            }
+        }
+        for (child, parent) in links.parents {
+            if self.clan_system.parent_clan(&child).is_none() {
+                // Fails if a clan is missing, or the link would make a cycle.
+                let _ = self.clan_system.create_subclan(&parent, &child);
+            }
+        }
+    }
+
+    pub fn crabs(&self) -> Crabs<'_> {
+        self.crabs.iter()
+    }
+
+    /** Returns the crabs ordered by speed, then name; see `Crab`'s ordering. */
+    pub fn crab_set(&self) -> BTreeSet<&Crab> {
+        self.crabs.iter().collect()
+    }
+
+    /**
+     * Returns the crabs for changing in place, in the order they live on the
+     * beach. A crab's name cannot be changed this way; see `rename_crab`.
+     * Like `get_crab_mut`, it leaves the watches to the beach's next change
+     * or tick, and checks each crab against the beach's constraints once it
+     * is let go.
+     */
+    pub fn crabs_mut(&mut self) -> CheckedCrabsMut<'_> {
+        self.territory_may_move();
+        CheckedCrabsMut::new(self.crabs.iter_mut(), &self.constraints)
+    }
+
+    /**
+     * Changes every crab in place through `change`, in the order they live on
+     * the beach, then checks the beach's watches once. Each crab is checked
+     * against the beach's constraints as soon as it is changed; if one
+     * breaks them, its change is undone as for `get_crab_mut`, the crabs
+     * after it are left alone, and the method should panic.
+     */
+    pub fn for_each_crab_mut(&mut self, mut change: impl FnMut(&mut Crab)) {
+        self.territory_may_move();
+        for crab in self.crabs.iter_mut() {
+            change(&mut CrabMut::new(crab, &self.constraints));
+        }
+        self.refresh_territory();
+        self.check_watches();
+    }
+
+    /**
+     * Gives the crab at the given index a new name, keeping the beach's name
+     * lookups up to date. The crab keeps its clan, role and leadership. On a
+     * beach that keeps names unique, a taken name gets a suffix. If the
+     * index is out of bounds, the method should panic.
+     */
+    pub fn rename_crab(&mut self, index: usize, name: String) {
+        if let Err(reason) = self.try_rename_crab(index, name) {
+            panic!("{}", reason);
+        }
+    }
+
+    /**
+     * Renames the crab at the given index like `rename_crab`, or returns an
+     * error if the index is out of bounds.
+     */
+    pub fn try_rename_crab(&mut self, index: usize, name: String) -> Result<(), OceanError> {
+        self.check_index(index)?;
+        let handle = self.crabs.handle(index);
+        let old = self.crabs[index].name().to_string();
+        if old == name {
+            return Ok(());
+        }
+        let name = self.free_name(&name);
+        self.checkpoint();
+        if let Some(named) = self.by_name.get_mut(&old) {
+            named.retain(|&h| h != handle);
+            if named.is_empty() {
+                self.by_name.remove(&old);
+            }
+        }
+        let named = self.by_name.entry(name.clone()).or_default();
+        let at = named.partition_point(|&h| h < handle);
+        named.insert(at, handle);
+        self.crabs[index].set_name(name.clone());
+        self.clan_system.rename_member(handle, &name);
+        self.check_watches();
+        Ok(())
+    }
+
+    /**
+     * Moves the crab at the given index to a new position.
+     * If the index is out of bounds, the method should panic.
+     */
+    pub fn place_crab(&mut self, index: usize, position: Position) {
+        self.refresh_territory();
+        let from = self.crabs[index].position();
+        self.crabs[index].set_position(position);
+        if let Some(spatial) = &mut self.territory {
+            spatial.relocate(self.crabs.handle(index), from, position);
+        }
+    }
+
+    /**
+     * Moves the crab at the given index to a new position, or returns why it
+     * cannot: the index is out of bounds, or the position lies outside the
+     * beach's territory.
+     */
+    pub fn move_crab(&mut self, index: usize, position: Position) -> Result<(), OceanError> {
+        self.check_index(index)?;
+        if let Some(territory) = self.territory() {
+            if !territory.contains(position) {
+                return Err(OceanError::OutsideTerritory {
+                    position,
+                    width: territory.width,
+                    height: territory.height,
+                });
+            }
+        }
+        self.place_crab(index, position);
+        Ok(())
+    }
+
+    /**
+     * Has crabs move on every tick: each picks a target by the strategy,
+     * then steps toward it as far as its effective speed allows.
+     */
+    pub fn set_movement_strategy(&mut self, strategy: Box<dyn MovementStrategy>) {
+        self.movement = Some(strategy);
+    }
+
+    /** Has crabs stay where they are on every tick, as they do unless told otherwise. */
+    pub fn clear_movement_strategy(&mut self) {
+        self.movement = None;
+    }
+
+    /** Returns where food lies and predators lurk, for moving crabs. */
+    pub fn landmarks(&self) -> &Landmarks {
+        &self.landmarks
+    }
+
+    pub fn landmarks_mut(&mut self) -> &mut Landmarks {
+        &mut self.landmarks
+    }
+
+    /**
+     * Moves every crab one step by the movement strategy, as a tick does.
+     * All crabs pick their targets before any of them moves. A crab never
+     * steps off the territory, and stays put rather than step into a region
+     * that humans have blocked off. Does nothing without a strategy.
+     */
+    pub fn move_crabs(&mut self) {
+        let Some(strategy) = &self.movement else {
+            return;
+        };
+        let bounds = self.territory().copied();
+        let steps: Vec<(usize, Position)> = (0..self.crabs.len())
+            .filter_map(|index| {
+                let view = Surroundings { beach: self, index };
+                let from = view.position();
+                let target = strategy.target(&view)?;
+                let mut to = step_toward(from, target, self.effective_speed(index));
+                if let Some(territory) = bounds {
+                    to.x = to.x.min(territory.width.saturating_sub(1));
+                    to.y = to.y.min(territory.height.saturating_sub(1));
+                }
+                (to != from && self.check_open(to).is_ok()).then_some((index, to))
+            })
+            .collect();
+        for (index, to) in steps {
+            self.place_crab(index, to);
+        }
+    }
+
+    /**
+     * Lays the beach out as a bounded grid, indexing where its crabs are so
+     * that nearby crabs can be found quickly.
+     */
+    pub fn set_territory(&mut self, territory: Territory) {
+        let crabs = (0..self.crabs.len())
+            .map(|index| (self.crabs.handle(index), self.crabs[index].position()));
+        self.territory = Some(SpatialIndex::new(territory, crabs));
+    }
+
+    pub fn territory(&self) -> Option<&Territory> {
+        self.territory.as_ref().map(SpatialIndex::territory)
+    }
+
+    /** Notes that crabs may move where the territory index cannot see. */
+    fn territory_may_move(&mut self) {
+        if let Some(spatial) = &mut self.territory {
+            spatial.mark_stale();
+        }
+    }
+
+    /** Rebuilds the territory index if crabs may have moved behind its back. */
+    fn refresh_territory(&mut self) {
+        if let Some(&territory) = self
+            .territory
+            .as_ref()
+            .filter(|spatial| spatial.is_stale())
+            .map(SpatialIndex::territory)
+        {
+            self.set_territory(territory);
+        }
+    }
+
+    /**
+     * Returns the crabs at most `radius` away from `point`, in the order they
+     * live on the beach.
+     */
+    pub fn crabs_within_radius(&self, point: Position, radius: u32) -> Vec<&Crab> {
+        match self
+            .territory
+            .as_ref()
+            .filter(|spatial| !spatial.is_stale())
+        {
+            Some(spatial) => spatial
+                .within(point, radius)
+                .into_iter()
+                .filter_map(|handle| self.resolve(handle))
+                .collect(),
+            None => self
+                .crabs
+                .iter()
+                .filter(|crab| {
+                    point.distance_squared(crab.position()) <= radius as u64 * radius as u64
+                })
+                .collect(),
+        }
+    }
+
+    /**
+     * Returns the crab closest to `point`, the first on the beach if several
+     * are as close, or None if the beach is empty.
+     */
+    pub fn nearest_crab(&self, point: Position) -> Option<&Crab> {
+        match self
+            .territory
+            .as_ref()
+            .filter(|spatial| !spatial.is_stale())
+        {
+            Some(spatial) => spatial
+                .nearest(point)
+                .and_then(|handle| self.resolve(handle)),
+            None => self
+                .crabs
+                .iter()
+                .min_by_key(|crab| point.distance_squared(crab.position())),
+        }
+    }
+
+    /**
+     * Moves the crab at the given index to a new position, unless the
+     * position is blocked off. If the index is out of bounds, the method
+     * should panic.
+     */
+    pub fn try_place_crab(&mut self, index: usize, position: Position) -> Result<(), String> {
+        self.check_open(position).map_err(|e| e.to_string())?;
+        self.place_crab(index, position);
+        Ok(())
+    }
+
+    fn check_open(&self, position: Position) -> Result<(), OceanError> {
+        match self.humans.blocked_until(position) {
+            Some(until) => Err(OceanError::RegionBlocked { position, until }),
+            None => Ok(()),
+        }
+    }
+
+    /**
+     * Gives the beach a climate, starting its temperature record now.
+     */
+    pub fn set_climate(&mut self, climate: Climate) {
+        self.environment = Some(Environment::new(climate, self.ticks));
+    }
+
+    /** The beach's climate and temperature record, if it has a climate. */
+    pub fn environment(&self) -> Option<&Environment> {
+        self.environment.as_ref()
+    }
+
+    pub fn environment_mut(&mut self) -> Option<&mut Environment> {
+        self.environment.as_mut()
+    }
+
+    /**
+     * Gives the beach weather and a tide, starting calm at low tide. They
+     * change on every tick from then on.
+     */
+    pub fn set_weather(&mut self, rules: WeatherRules) {
+        self.weather = Some(WeatherSystem::new(rules));
+    }
+
+    /**
+     * Makes the weather on the next tick `weather`; see
+     * `WeatherSystem::forecast`. A beach without weather is given weather
+     * that stays calm with a tide that never turns, apart from this.
+     */
+    pub fn forecast_weather(&mut self, weather: Weather) {
+        let system = self.weather.get_or_insert_with(|| {
+            WeatherSystem::new(WeatherRules {
+                tide_period: 0,
+                rain_chance: 0.0,
+                storm_chance: 0.0,
+                ..WeatherRules::default()
+            })
+        });
+        system.forecast(weather);
+    }
+
+    /** The beach's weather and tide, if it has weather. */
+    pub fn weather(&self) -> Option<&WeatherSystem> {
+        self.weather.as_ref()
+    }
+
+    /**
+     * Has the beach's crabs weather the tick: a storm throws every crab up
+     * to `storm_scatter` positions along each axis, and the high tide then
+     * washes away the unprotected crabs too slow to outrun it.
+     */
+    fn brave_weather(&mut self) -> Vec<Death> {
+        let Some(weather) = &self.weather else {
+            return Vec::new();
+        };
+        if weather.weather() == Weather::Storm {
+            let territory = self.territory().copied();
+            for index in 0..self.crabs.len() {
+                let from = self.crabs[index].position();
+                let weather = self.weather.as_mut().expect("the beach has weather");
+                let to = weather.scatter(from, territory.as_ref());
+                self.place_crab(index, to);
+            }
+        }
+        let mut deaths = Vec::new();
+        for index in (0..self.crabs.len()).rev() {
+            let speed = self.effective_speed(index);
+            let washed = self
+                .weather
+                .as_ref()
+                .is_some_and(|weather| weather.washes_away(speed));
+            if !washed || self.is_protected(index) {
+                continue;
+            }
+            let name = self
+                .unrecorded(|beach| beach.remove_crab_at(index))
+                .name()
+                .to_string();
+            self.events.publish(BeachEvent::CrabDied {
+                name: name.clone(),
+                cause: Cause::Tide,
+            });
+            deaths.push(Death {
+                name,
+                cause: Cause::Tide,
+            });
+        }
+        deaths.reverse();
+        deaths
+    }
+
+    /**
+     * Returns the speed of the crab at the given index at the current
+     * temperature and tide, which is never less than 1. Without a climate or
+     * weather, this is just its speed. If the index is out of bounds, the
+     * method should panic.
+     */
+    pub fn effective_speed(&self, index: usize) -> u32 {
+        let crab = &self.crabs[index];
+        let speed = match &self.environment {
+            Some(environment) => {
+                let speed = crab.speed() as f64 * environment.performance(crab.diet());
+                (speed.round() as u32).max(1)
+            }
+            None => crab.speed(),
+        };
+        match &self.weather {
+            Some(weather) => weather.drag(speed),
+            None => speed,
+        }
+    }
+
+    /**
+     * Returns:
+     *   - None if the beach is empty.
+     *   - Some of a reference to the Crab with the highest speed.
+     */
+    pub fn get_fastest_crab(&self) -> Option<&Crab> {
+        self.crabs.iter().max_by_key(|crab| crab.speed())
+    }
+
+    /**
+     * Returns the crabs from fastest to slowest; crabs as fast as each other
+     * keep the order they live on the beach.
+     */
+    pub fn crabs_sorted_by_speed(&self) -> Vec<&Crab> {
+        self.crab_query()
+            .order_by(Column::Speed, Direction::Descending)
+            .collect()
+    }
+
+    /** Returns the `n` fastest crabs, fastest first, like `crabs_sorted_by_speed`. */
+    pub fn top_n_fastest(&self, n: usize) -> Vec<&Crab> {
+        self.crab_query()
+            .order_by(Column::Speed, Direction::Descending)
+            .limit(n)
+            .collect()
+    }
+
+    /**
+     * Returns the crabs with each diet that any crab has, in the order they
+     * live on the beach.
+     */
+    pub fn group_by_diet(&self) -> HashMap<Diet, Vec<&Crab>> {
+        let mut groups: HashMap<Diet, Vec<&Crab>> = HashMap::new();
+        for crab in &self.crabs {
+            groups.entry(crab.diet()).or_default().push(crab);
+        }
+        groups
+    }
+
+    /**
+     * Returns the resident crabs of each clan that has any, in the order they
+     * live on the beach. Crabs in no clan are left out.
+     */
+    pub fn group_by_clan(&self) -> HashMap<&str, Vec<&Crab>> {
+        let mut groups: HashMap<&str, Vec<&Crab>> = HashMap::new();
+        for (k, crab) in self.crabs.iter().enumerate() {
+            if let Some(clan_id) = self.clan_system.get_clan_of(self.crabs.handle(k)) {
+                groups.entry(clan_id).or_default().push(crab);
+            }
+        }
+        groups
+    }
+
+    /**
+     * Returns a vector of references to the crabs with a given name.
+     */
+    pub fn find_crabs_by_name(&self, name: &str) -> Vec<&Crab> {
+        self.crabs_named(name).collect()
+    }
+
+    /**
+     * Returns the crabs whose names start with `prefix`, in the order they
+     * live on the beach.
+     */
+    pub fn find_crabs_by_name_prefix(&self, prefix: &str) -> Vec<&Crab> {
+        self.find_crabs_named_by(|name| name.starts_with(prefix))
+    }
+
+    /**
+     * Returns the crabs with a given name like `find_crabs_by_name`, but
+     * ignoring case, in the order they live on the beach.
+     */
+    pub fn find_crabs_by_name_ignore_case(&self, name: &str) -> Vec<&Crab> {
+        let name = name.to_lowercase();
+        self.find_crabs_named_by(|other| other.to_lowercase() == name)
+    }
+
+    /**
+     * Returns the crabs whose names are at most `max_distance` edits from
+     * `name`, by `names::edit_distance`, in the order they live on the beach.
+     */
+    pub fn find_crabs_by_name_fuzzy(&self, name: &str, max_distance: usize) -> Vec<&Crab> {
+        let length = name.chars().count();
+        self.find_crabs_named_by(|other| {
+            // Names of too different a length cannot be close enough.
+            other.chars().count().abs_diff(length) <= max_distance
+                && edit_distance(name, other) <= max_distance
+        })
+    }
+
+    /**
+     * Returns the crabs whose names match, in the order they live on the
+     * beach, testing each distinct name in the name index once.
+     */
+    fn find_crabs_named_by(&self, matches: impl Fn(&str) -> bool) -> Vec<&Crab> {
+        let mut handles: Vec<CrabId> = self
+            .by_name
+            .iter()
+            .filter(|(name, _)| matches(name))
+            .flat_map(|(_, handles)| handles.iter().copied())
+            .collect();
+        // Handles are issued in increasing order, so this is beach order.
+        handles.sort_unstable();
+        handles
+            .into_iter()
+            .filter_map(|handle| self.resolve(handle))
+            .collect()
+    }
+
+    /**
+     * Returns the crabs whose color is within `tolerance` of the given one,
+     * as measured by `Color::distance`.
+     */
+    pub fn find_crabs_by_color_near(&self, color: &Color, tolerance: f64) -> Vec<&Crab> {
+        self.crab_query()
+            .color_near(color.clone(), tolerance)
+            .collect()
+    }
+
+    /** Returns how many crabs have the given name. */
+    pub fn count_crabs_named(&self, name: &str) -> usize {
+        self.by_name.get(name).map_or(0, |handles| handles.len())
+    }
+
+    /**
+     * Parses and runs a query such as
+     * `SELECT name, speed FROM crabs WHERE speed > 10 ORDER BY speed DESC`.
+     */
+    pub fn query(&self, text: &str) -> Result<QueryResult, String> {
+        Ok(Query::parse(text)?.run(self))
+    }
+
+    /**
+     * Starts a query over the crabs of this beach built up in code, such as
+     * `beach.crab_query().speed_gt(10).in_clan("sharks").collect()`.
+     */
+    pub fn crab_query(&self) -> CrabQuery<'_> {
+        CrabQuery::new(self)
+    }
+
+    /**
+     * Returns how many ticks the crab at the given index has lived on this
+     * beach. If the index is out of bounds, the method should panic.
+     */
+    pub fn age(&self, index: usize) -> u64 {
+        self.ticks - self.crabs.arrived(index)
+    }
+
+    /**
+     * Rebuilds a beach at tick `ticks` from its crabs, each with its id, if
+     * it was saved with one, and the tick it arrived at, and then its clans,
+     * which `clans` builds once the crabs are here. Saved ids must grow from
+     * each crab to the next; crabs saved without one are issued new ids.
+     * Members that are not on the beach keep their ids, which are not
+     * issued again.
+     */
+    #[cfg(any(feature = "serde", feature = "binary"))]
+    pub(crate) fn from_saved<E>(
+        ticks: u64,
+        crabs: Vec<(Option<CrabId>, Crab, u64)>,
+        clans: impl FnOnce(&mut Beach) -> Result<ClanSystem, E>,
+    ) -> Result<Beach, E> {
+        let mut beach = Beach::new();
+        for (id, crab, arrived) in crabs {
+            match id {
+                Some(id) => beach.push_crab_as(id, crab),
+                None => beach.push_crab(crab),
+            };
+            let last = beach.crabs.len() - 1;
+            beach.crabs.set_arrived(last, arrived);
+        }
+        beach.ticks = ticks;
+        beach.crabs.fill_fed_at(ticks);
+        beach.clan_system = clans(&mut beach)?;
+        let clans = &beach.clan_system;
+        let members = clans.clan_ids().flat_map(|id| clans.clan_members(id));
+        if let Some(newest) = members.max() {
+            beach.next_handle = beach.next_handle.max(newest.0 + 1);
+        }
+        Ok(beach)
+    }
+
+    /**
+     * Returns the ids a clan member saved by name alone stands for, from
+     * before clans knew crabs by id: every crab with the name, or else a
+     * new id of its own, for a member no crab answered to.
+     */
+    #[cfg(any(feature = "serde", feature = "binary"))]
+    pub(crate) fn ids_saved_by_name(&mut self, name: &str) -> Vec<CrabId> {
+        let ids = self.by_name.get(name).cloned().unwrap_or_default();
+        if !ids.is_empty() {
+            return ids;
+        }
+        let id = CrabId(self.next_handle);
+        self.next_handle += 1;
+        vec![id]
+    }
+
+    /**
+     * Sets the limits on breeding that every breeding method enforces.
+     */
+    pub fn set_breeding_rules(&mut self, rules: BreedingRules) {
+        self.breeding.rules = rules;
+    }
+
+    /** The beach's breeding rules and the births counted against them. */
+    pub fn breeding(&self) -> &BreedingState {
+        &self.breeding
+    }
+
+    fn parent(&self, index: usize) -> Parent<'_> {
+        Parent {
+            handle: self.crabs.handle(index),
+            name: self.crabs[index].name(),
+            age: self.age(index),
+        }
+    }
+
+    /**
+     * Checks whether the crabs at indices `i` and `j` may breed now: the
+     * breeding rules allow it, neither is still cooling down from breeding,
+     * and they are close enough on the territory. If the indices are out of
+     * bounds, the method should panic.
+     */
+    pub fn can_breed(&self, i: usize, j: usize) -> Result<(), BreedingError> {
+        self.breeding
+            .check(self.parent(i), self.parent(j), &self.lineage)?;
+        self.check_breeding_distance(i, j)
+    }
+
+    /** Checks that two crabs are close enough to breed on the territory. */
+    fn check_breeding_distance(&self, i: usize, j: usize) -> Result<(), BreedingError> {
+        let Some(limit) = self.territory().and_then(|t| t.breeding_distance) else {
+            return Ok(());
+        };
+        let (a, b) = (&self.crabs[i], &self.crabs[j]);
+        let distance_squared = a.position().distance_squared(b.position());
+        if distance_squared > limit as u64 * limit as u64 {
+            return Err(BreedingError::TooFar {
+                crab1: a.name().to_string(),
+                crab2: b.name().to_string(),
+                distance: (distance_squared as f64).sqrt(),
+                max_distance: limit,
+            });
+        }
+        Ok(())
+    }
+
+    /**
+     * Breeds the crabs at indices `i` and `j` like `breed_crabs`, or returns
+     * why it cannot: an index is out of bounds, the breeding rules, weather
+     * or temperature forbid it, or the beach is full and turns newcomers
+     * away.
+     */
+    pub fn try_breed_crabs(&mut self, i: usize, j: usize, name: String) -> Result<(), OceanError> {
+        self.admit_offspring(i, j, name).map(drop)
+    }
+
+    /**
+     * Breeds the crabs at indices `i` and `j` like `try_breed_crabs`,
+     * returning the crabs evicted to make room for the child; these may
+     * include its parents.
+     */
+    pub fn admit_offspring(
+        &mut self,
+        i: usize,
+        j: usize,
+        name: String,
+    ) -> Result<Vec<Crab>, OceanError> {
+        self.breed_offspring(i, j, name, true)
+    }
+
+    /**
+     * Breeds the crabs at indices `i` and `j`, rolling whether the weather
+     * and temperature let them only if `roll` is set, so that a breeding
+     * which must happen does not fail at random.
+     */
+    fn breed_offspring(
+        &mut self,
+        i: usize,
+        j: usize,
+        name: String,
+        roll: bool,
+    ) -> Result<Vec<Crab>, OceanError> {
+        self.check_index(i)?;
+        self.check_index(j)?;
+        self.check_name(&name)?;
+        self.check_room()?;
+        self.can_breed(i, j)
+            .map_err(|e| OceanError::BreedingFailed(e.to_string()))?;
+        if roll {
+            self.breeding_succeeds(i, j)
+                .map_err(OceanError::BreedingFailed)?;
+        }
+        self.record_ok(|beach| {
+            beach
+                .begin_breeding(i, j, 1)
+                .map_err(OceanError::BreedingFailed)?;
+            let baby = beach.conceive(i, j, name);
+            let parents = (beach.crabs.handle(i), beach.crabs.handle(j));
+            let names = (
+                beach.crabs[i].name().to_string(),
+                beach.crabs[j].name().to_string(),
+            );
+            let evicted = beach.make_room();
+            beach.add_offspring(baby, parents, names);
+            Ok(evicted)
+        })
+    }
+
+    /**
+     * Breeds the fastest two crabs of a clan that may breed, like
+     * `breed_within_clan_by` with `ParentChoice::Fastest`.
+     */
+    pub fn breed_within_clan(
+        &mut self,
+        clan_id: &str,
+        name: String,
+    ) -> Result<(String, String), OceanError> {
+        self.breed_within_clan_by(clan_id, name, ParentChoice::Fastest)
+    }
+
+    /**
+     * Breeds two crabs of a clan living on the beach, picked by `choice`
+     * from the pairs the breeding rules allow, and enrolls the child in the
+     * clan. Returns the names of the parents, or why there is no child: the
+     * clan does not exist, fewer than two of its crabs live here, no pair
+     * may breed, in which case the error is the first pair's, or the clan
+     * cannot take the child, in which case it is not born. Counts as one
+     * change for `undo`.
+     */
+    pub fn breed_within_clan_by(
+        &mut self,
+        clan_id: &str,
+        name: String,
+        choice: ParentChoice,
+    ) -> Result<(String, String), OceanError> {
+        if !self.clan_system.has_clan(clan_id) {
+            return Err(OceanError::UnknownClan(clan_id.to_string()));
+        }
+        let mut residents: Vec<usize> = (0..self.crabs.len())
+            .filter(|&k| self.clan_system.is_member(clan_id, self.crabs.handle(k)))
+            .collect();
+        if residents.len() < 2 {
+            return Err(OceanError::ClanTooSmall {
+                clan: clan_id.to_string(),
+                residents: residents.len(),
+            });
+        }
+        match choice {
+            ParentChoice::Fastest => residents.sort_by_key(|&k| Reverse(self.crabs[k].speed())),
+            ParentChoice::Random => self.with_rng(|_, rng| residents.shuffle(rng)),
+        }
+        let (i, j) = residents
+            .iter()
+            .enumerate()
+            .flat_map(|(n, &i)| residents[n + 1..].iter().map(move |&j| (i, j)))
+            .find(|&(i, j)| self.can_breed(i, j).is_ok())
+            .unwrap_or((residents[0], residents[1]));
+        let parents = (
+            self.crabs[i].name().to_string(),
+            self.crabs[j].name().to_string(),
+        );
+        let before = self.snapshot();
+        self.record_ok(|beach| {
+            beach.admit_offspring(i, j, name)?;
+            let child = beach.crabs.handle(beach.crabs.len() - 1);
+            if let Err(error) = beach.try_add_member_to_clan_by_id(clan_id, child) {
+                beach.put_back(before);
+                return Err(error);
+            }
+            Ok(parents)
+        })
+    }
+
+    /**
+     * Checks the breeding rules for the crabs at indices `i` and `j` and
+     * counts `births` offspring against them, warning about inbreeding if the
+     * rules say to.
+     */
+    fn begin_breeding(&mut self, i: usize, j: usize, births: usize) -> Result<(), String> {
+        self.can_breed(i, j).map_err(|e| e.to_string())?;
+        let (a, b) = (self.crabs.handle(i), self.crabs.handle(j));
+        for _ in 0..births {
+            self.breeding.record(a, b);
+        }
+        if self.breeding.too_close(a, b, &self.lineage).is_some() {
+            self.events.publish(BeachEvent::InbreedingWarning {
+                parent1: self.crabs[i].name().to_string(),
+                parent2: self.crabs[j].name().to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /**
+     * Rolls whether the weather and temperature let the crabs at `i` and `j`
+     * breed, returning why not if they do not.
+     */
+    fn breeding_succeeds(&mut self, i: usize, j: usize) -> Result<(), String> {
+        let (a, b) = (self.crabs[i].diet(), self.crabs[j].diet());
+        let names = [self.crabs[i].name(), self.crabs[j].name()];
+        if !self
+            .weather
+            .as_mut()
+            .is_none_or(|weather| weather.breeding_succeeds())
+        {
+            return Err(tr(Message::BreedFailedWeather, &[&names[0], &names[1]]));
+        }
+        let Some(environment) = self.environment.as_mut() else {
+            return Ok(());
+        };
+        if environment.breeding_succeeds(a, b) {
+            return Ok(());
+        }
+        let temperature = format!("{:.1}", environment.temperature());
+        Err(tr(
+            Message::BreedFailedTemperature,
+            &[&names[0], &names[1], &temperature],
+        ))
+    }
+
+    /**
+     * Returns a child of the crabs at indices `i` and `j`, bred by the
+     * beach's breeding policy, one generation after the younger parent's.
+     * A seeded beach's policy breeds from the beach's generator.
+     */
+    fn conceive(&mut self, i: usize, j: usize, name: String) -> Crab {
+        let (parent1, parent2) = (&self.crabs[i], &self.crabs[j]);
+        let mut child = match &mut self.rng {
+            Some(rng) => self.breeding_policy.breed_from(parent1, parent2, name, rng),
+            None => self.breeding_policy.breed(parent1, parent2, name),
+        };
+        child.set_generation(parent1.generation().max(parent2.generation()) + 1);
+        child.set_parents((self.crabs.handle(i), self.crabs.handle(j)));
+        child
+    }
+
+    /**
+     * Breeds offspring by the given policy from now on, in place of the
+     * beach's genetics if it had any.
+     */
+    pub fn set_breeding_policy(&mut self, policy: Box<dyn BreedingPolicy>) {
+        self.breeding_policy = policy;
+    }
+
+    /**
+     * Makes offspring inherit their parents' speed and diet from now on,
+     * instead of being born with speed 1 and a random diet. This replaces
+     * the beach's breeding policy.
+     */
+    pub fn set_genetics(&mut self, genetics: Genetics) {
+        self.set_breeding_policy(Box::new(Heredity::new(genetics)));
+    }
+
+    /** The genetics offspring are bred by, if the breeding policy has any. */
+    pub fn genetics(&self) -> Option<Genetics> {
+        self.breeding_policy.genetics()
+    }
+
+    /**
+     * Returns the family tree of the crab with the given handle, tracing
+     * its ancestry back to the founders, even through crabs that have left
+     * the beach. Returns None if the handle was never issued by this beach.
+     */
+    pub fn get_lineage(&self, crab: CrabId) -> Option<FamilyTree> {
+        if crab.0 >= self.next_handle {
+            return None;
+        }
+        Some(
+            self.lineage
+                .family_tree(crab, |handle| self.resolve(handle).map(|crab| crab.name())),
+        )
+    }
+
+    /** Adds a newborn crab, recording and announcing its parents. */
+    fn add_offspring(
+        &mut self,
+        baby: Crab,
+        parents: (CrabId, CrabId),
+        (parent1, parent2): (String, String),
+    ) {
+        let child = baby.name().to_string();
+        self.push_crab(baby);
+        self.check_watches();
+        let handle = self.crabs.handle(self.crabs.len() - 1);
+        self.lineage.record(handle, parents.0, parents.1);
+        self.lineage.record_name(handle, &child);
+        self.lineage.record_name(parents.0, &parent1);
+        self.lineage.record_name(parents.1, &parent2);
+        self.events.publish(BeachEvent::CrabBred {
+            parent1,
+            parent2,
+            child,
+        });
+    }
+
+    /**
+     * Has the crabs at indices `i` and `j` lay a clutch of eggs at the given
+     * position, one egg for each name. The eggs count against the breeding
+     * rules now and hatch into juvenile crabs after incubating. Fails if the
+     * breeding rules forbid it, there are no names, or another clutch is
+     * already at the position. If the indices are out of bounds, the method
+     * should panic.
+     */
+    pub fn lay_clutch(
+        &mut self,
+        i: usize,
+        j: usize,
+        position: Position,
+        names: Vec<String>,
+    ) -> Result<(), OceanError> {
+        if names.is_empty() {
+            return Err(OceanError::ClutchEmpty);
+        }
+        if self.incubator.clutch_at(position).is_some() {
+            return Err(OceanError::ClutchPositionTaken(position));
+        }
+        self.check_open(position)?;
+        self.can_breed(i, j)
+            .map_err(|e| OceanError::BreedingFailed(e.to_string()))?;
+        let mut failure = None;
+        let names: Vec<String> = names
+            .into_iter()
+            .filter(|_| match self.breeding_succeeds(i, j) {
+                Ok(()) => true,
+                Err(why) => {
+                    failure = Some(why);
+                    false
+                }
+            })
+            .collect();
+        if let Some(why) = failure.filter(|_| names.is_empty()) {
+            return Err(OceanError::BreedingFailed(why));
+        }
+        self.begin_breeding(i, j, names.len())
+            .map_err(OceanError::BreedingFailed)?;
+        let eggs = names
+            .into_iter()
+            .map(|name| self.conceive(i, j, name))
+            .collect();
+        self.incubator.lay(Clutch {
+            parents: (self.crabs.handle(i), self.crabs.handle(j)),
+            parent_names: (
+                self.crabs[i].name().to_string(),
+                self.crabs[j].name().to_string(),
+            ),
+            position,
+            laid_at: self.ticks,
+            eggs,
+        })
+    }
+
+    /** The clutches incubating on this beach. */
+    pub fn incubator(&self) -> &Incubator {
+        &self.incubator
+    }
+
+    /**
+     * Gives access to the incubating clutches, e.g. to change how long they
+     * incubate or to expose them to predators and tides.
+     */
+    pub fn incubator_mut(&mut self) -> &mut Incubator {
+        &mut self.incubator
+    }
+
+    /** The beach's tide pools and the state of the tide. */
+    pub fn tide_pools(&self) -> &TidePools {
+        &self.tide_pools
+    }
+
+    /**
+     * Gives access to the tide pools, e.g. to add pools, move crabs into
+     * them or change how often the tide turns.
+     */
+    pub fn tide_pools_mut(&mut self) -> &mut TidePools {
+        &mut self.tide_pools
+    }
+
+    /**
+     * Turns the tide now, exchanging crabs between the tide pools and the
+     * main beach. Returns who moved.
+     */
+    pub fn turn_tide(&mut self) -> Exchange {
+        self.tide_pools.turn(&self.crabs.handles())
+    }
+
+    /**
+     * Protects the crab at the given index, or stops protecting it, returning
+     * whether that changed anything. Its clans' protection is unaffected.
+     * If the index is out of bounds, the method should panic.
+     */
+    pub fn set_crab_protected(&mut self, index: usize, protected: bool) -> bool {
+        let target = Protected::Crab(self.crabs.handle(index));
+        self.protection.set(target, protected, self.ticks)
+    }
+
+    /**
+     * Protects every member of a clan, or stops doing so, returning whether
+     * that changed anything.
+     */
+    pub fn set_clan_protected(&mut self, clan_id: &str, protected: bool) -> bool {
+        let target = Protected::Clan(clan_id.to_string());
+        self.protection.set(target, protected, self.ticks)
+    }
+
+    /**
+     * Returns whether the crab at the given index is protected, by itself or
+     * through one of its clans. If the index is out of bounds, the method
+     * should panic.
+     */
+    pub fn is_protected(&self, index: usize) -> bool {
+        let handle = self.crabs.handle(index);
+        if self.protection.is_crab_protected(handle) {
+            return true;
+        }
+        self.protection
+            .clans()
+            .any(|id| self.clan_system.is_member(id, handle))
+    }
+
+    /** The beach's protected crabs and clans, and the audit of changes. */
+    pub fn protection(&self) -> &Protection {
+        &self.protection
+    }
+
+    /** Returns the indices of the crabs that are not protected. */
+    fn unprotected(&self) -> Vec<usize> {
+        (0..self.crabs.len())
+            .filter(|&k| !self.is_protected(k))
+            .collect()
+    }
+
+    /**
+     * Tags up to `count` crabs picked at random, as the first step of a
+     * mark–recapture survey. Returns how many were newly tagged.
+     */
+    pub fn tag_sample(&mut self, count: usize, seed: u64) -> usize {
+        self.survey.tag_sample(&self.crabs.handles(), count, seed)
+    }
+
+    /**
+     * Catches up to `count` crabs picked at random, estimates the population
+     * from how many of them are tagged and releases them.
+     */
+    pub fn recapture(&mut self, count: usize, seed: u64) -> RecaptureReport {
+        self.survey.recapture(&self.crabs.handles(), count, seed)
+    }
+
+    /** The beach's mark–recapture survey. */
+    pub fn survey(&self) -> &Survey {
+        &self.survey
+    }
+
+    pub fn survey_mut(&mut self) -> &mut Survey {
+        &mut self.survey
+    }
+
+    /** Sets how seagulls raid the beach, reseeding the raids. */
+    pub fn set_raid_settings(&mut self, settings: RaidSettings) {
+        self.raids.set_settings(settings);
+    }
+
+    pub fn raid_settings(&self) -> RaidSettings {
+        self.raids.settings()
+    }
+
+    /** Returns the reports of every seagull raid so far, oldest first. */
+    pub fn raid_reports(&self) -> &[RaidReport] {
+        self.raids.reports()
+    }
+
+    /**
+     * Has the seagulls raid the beach now, whatever the schedule. The crabs
+     * they carry off leave the beach and their clans; protected crabs are
+     * spared. Returns what happened.
+     */
+    pub fn seagull_raid(&mut self) -> RaidReport {
+        let exposed = self.unprotected();
+        let mut victims: Vec<usize> = self
+            .raids
+            .pick_victims(exposed.iter().map(|&k| &self.crabs[k]))
+            .into_iter()
+            .map(|k| exposed[k])
+            .collect();
+        let casualties: Vec<String> = victims
+            .iter()
+            .map(|&k| self.crabs[k].name().to_string())
+            .collect();
+        victims.sort_unstable();
+        for &k in victims.iter().rev() {
+            let handle = self.crabs.handle(k);
+            self.detach_crab(k);
+            self.leave_clans(handle);
+        }
+        let report = RaidReport {
+            tick: self.ticks,
+            casualties,
+            survivors: self.crabs.len(),
+        };
+        self.raids.record(report.clone());
+        self.events.publish(BeachEvent::SeagullRaid {
+            casualties: report.casualties.clone(),
+            survivors: report.survivors,
+        });
+        self.check_watches();
+        report
+    }
+
+    /**
+     * Has a disaster strike the beach now, and returns what it did. Crabs
+     * it kills leave the beach and their clans. Counts as one change for
+     * `undo`.
+     */
+    pub fn apply_disaster(&mut self, disaster: Disaster) -> DisasterReport {
+        let mut report = DisasterReport::new(self.ticks, disaster);
+        self.record(|beach| match disaster {
+            Disaster::RedTide { percent } => report.killed = beach.red_tide(percent),
+            Disaster::HeatWave { percent } => {
+                let ids: Vec<String> = beach.clan_system.clan_ids().map(String::from).collect();
+                for id in ids {
+                    let loss = beach.get_treasury(&id) * percent.min(100) as u64 / 100;
+                    if loss > 0 {
+                        beach
+                            .economy
+                            .lose(&Account::clan(&id), loss)
+                            .expect("a clan holds its own treasury");
+                        report.treasury_losses.insert(id, loss);
+                    }
+                }
+            }
+            Disaster::StormSurge { reach } => {
+                for index in beach.unprotected() {
+                    let from = beach.crabs[index].position();
+                    let to = beach.with_rng(|beach, rng| {
+                        weather::scatter(from, reach, beach.territory(), rng)
+                    });
+                    if to != from {
+                        beach.place_crab(index, to);
+                        report.displaced.push(Displacement {
+                            name: beach.crabs[index].name().to_string(),
+                            from,
+                            to,
+                        });
+                    }
+                }
+            }
+        });
+        report
+    }
+
+    /** Kills the slowest `percent` of the crabs, sparing protected ones. */
+    fn red_tide(&mut self, percent: u32) -> Vec<String> {
+        let count = self.crabs.len() * percent.min(100) as usize / 100;
+        let mut victims = self.unprotected();
+        victims.sort_by_key(|&k| self.crabs[k].speed());
+        victims.truncate(count);
+        victims.sort_unstable();
+        let mut killed = Vec::new();
+        for &k in victims.iter().rev() {
+            let crab = self.remove_crab_at(k);
+            self.events.publish(BeachEvent::CrabDied {
+                name: crab.name().to_string(),
+                cause: Cause::Disaster,
+            });
+            killed.push(crab.name().to_string());
+        }
+        killed.reverse();
+        killed
+    }
+
+    /** Adds a predator that hunts on the beach on every tick from now on. */
+    pub fn add_predator(&mut self, predator: Box<dyn Predator>) {
+        self.predators.push(predator);
+    }
+
+    /** The predators hunting on the beach, in the order they were added. */
+    pub fn predators(&self) -> &[Box<dyn Predator>] {
+        &self.predators
+    }
+
+    /**
+     * Has every predator hunt now, in the order they were added. Each goes
+     * after the slowest crab at the current temperature, which gets away or
+     * not depending on its speed and the predator's. Eaten crabs leave the
+     * beach and their clans; protected crabs are never hunted. Returns every
+     * hunt.
+     */
+    pub fn hunt_crabs(&mut self) -> Vec<Hunt> {
+        let mut predators = std::mem::take(&mut self.predators);
+        let mut hunts = Vec::new();
+        for predator in &mut predators {
+            let exposed = self.unprotected();
+            let Some(index) = exposed.into_iter().min_by_key(|&k| self.effective_speed(k)) else {
+                break;
+            };
+            let crab = &self.crabs[index];
+            let caught = predator.try_catch(crab, self.effective_speed(index));
+            let name = crab.name().to_string();
+            if caught {
+                let handle = self.crabs.handle(index);
+                self.take_crab(index);
+                self.leave_clans(handle);
+                self.events.publish(BeachEvent::CrabDied {
+                    name: name.clone(),
+                    cause: Cause::Predation,
+                });
+            }
+            hunts.push(Hunt {
+                predator: predator.kind().to_string(),
+                prey: name,
+                caught,
+            });
+        }
+        self.predators = predators;
+        if hunts.iter().any(|hunt| hunt.caught) {
+            self.check_watches();
+        }
+        hunts
+    }
+
+    /**
+     * Plans human interference for the given tick. It happens at the end of
+     * that tick, or on the next tick if that one has already passed.
+     */
+    pub fn schedule_interference(&mut self, tick: u64, interference: Interference) {
+        self.humans.schedule(tick, interference);
+    }
+
+    /** The interference the beach has planned, is undergoing and went through. */
+    pub fn humans(&self) -> &Humans {
+        &self.humans
+    }
+
+    /**
+     * Has humans interfere with the beach now. Fished crabs leave the beach
+     * and their clans; protected crabs are never fished. Returns the names
+     * of the crabs taken.
+     */
+    pub fn interfere(&mut self, interference: Interference) -> Vec<String> {
+        let removed = match interference {
+            Interference::Fishing { count, seed } => {
+                let mut rng = Pcg64::seed_from_u64(seed);
+                let exposed = self.unprotected();
+                let amount = count.min(exposed.len());
+                let mut picked: Vec<usize> =
+                    rand::seq::index::sample(&mut rng, exposed.len(), amount)
+                        .into_iter()
+                        .map(|k| exposed[k])
+                        .collect();
+                let names: Vec<String> = picked
+                    .iter()
+                    .map(|&k| self.crabs[k].name().to_string())
+                    .collect();
+                picked.sort_unstable();
+                for &k in picked.iter().rev() {
+                    let handle = self.crabs.handle(k);
+                    self.detach_crab(k);
+                    self.leave_clans(handle);
+                }
+                self.pollution.add(POLLUTION_PER_CATCH * names.len() as f64);
+                self.events.publish(BeachEvent::CrabsFished {
+                    names: names.clone(),
+                });
+                self.check_watches();
+                names
+            }
+            Interference::Cleanup { region, ticks } => {
+                self.humans.block(region, self.ticks + ticks);
+                self.pollution.clean(CLEANUP_PER_TICK * ticks as f64);
+                Vec::new()
+            }
+        };
+        self.humans.record(InterferenceRecord {
+            tick: self.ticks,
+            interference,
+            removed: removed.clone(),
+        });
+        removed
+    }
+
+    /** The food lying on the beach for `feed_all`. */
+    pub fn food_supply(&self) -> &FoodSupply {
+        &self.food
+    }
+
+    pub fn food_supply_mut(&mut self) -> &mut FoodSupply {
+        &mut self.food
+    }
+
+    /** Puts portions of a food on the beach. */
+    pub fn add_food(&mut self, food: Food, portions: u32) {
+        self.food.add(food, portions);
+    }
+
+    /**
+     * Has every crab, in the order they live on the beach, eat a portion of
+     * the food for its diet. Crabs that eat regain their diet's energy and,
+     * for the life rules, have just had a meal; crabs that find nothing left lose `HUNGER_DAMAGE` health and 1 speed,
+     * down to 1. Returns who ate and who went hungry.
+     */
+    pub fn feed_all(&mut self) -> FeedingReport {
+        let mut report = FeedingReport::default();
+        for index in 0..self.crabs.len() {
+            let crab = &mut self.crabs[index];
+            let name = crab.name().to_string();
+            if self.food.take(Food::for_diet(crab.diet()), 1) == 1 {
+                crab.feed();
+                crab.heal(crab.diet().energy());
+                self.crabs.set_fed_at(index, self.ticks);
+                report.fed.push(name);
+            } else {
+                crab.injure(HUNGER_DAMAGE);
+                crab.set_speed(crab.speed().saturating_sub(1).max(1));
+                report.hungry.push(name);
+            }
+        }
+        report
+    }
+
+    /** How polluted the beach is. */
+    pub fn pollution(&self) -> &Pollution {
+        &self.pollution
+    }
+
+    /** Sets how much pollution fades away on its own every tick. */
+    pub fn set_pollution_decay(&mut self, per_tick: f64) {
+        self.pollution.natural_decay = per_tick;
+    }
+
+    /** Pollutes the beach, e.g. after a spill. */
+    pub fn pollute(&mut self, amount: f64) {
+        self.pollution.add(amount);
+    }
+
+    /** Cleans up to `amount` pollution, returning how much was removed. */
+    pub fn clean_up(&mut self, amount: f64) -> f64 {
+        self.pollution.clean(amount)
+    }
+
+    /**
+     * Has a tick of pollution wear down every crab's health and hold back
+     * the tide pools' regrowth, before some of it fades away.
+     */
+    fn suffer_pollution(&mut self) {
+        let damage = self.pollution.damage_per_tick();
+        if damage > 0 {
+            for crab in &mut self.crabs {
+                crab.injure(damage);
+            }
+        }
+        self.tide_pools.regrow(self.pollution.regrowth_factor());
+        self.pollution.decay();
+    }
+
+    /** Hatches every clutch that has incubated long enough, returning the juveniles' names. */
+    fn hatch_clutches(&mut self) -> Vec<String> {
+        let mut hatched = Vec::new();
+        for clutch in self.incubator.take_hatched(self.ticks) {
+            for mut juvenile in clutch.eggs {
+                if self.check_room().is_err() {
+                    continue;
+                }
+                self.make_room();
+                juvenile.set_position(clutch.position);
+                hatched.push(juvenile.name().to_string());
+                self.add_offspring(juvenile, clutch.parents, clutch.parent_names.clone());
+            }
+        }
+        hatched
+    }
+
+    /** Who was bred from whom on this beach. */
+    pub fn lineage(&self) -> &Lineage {
+        &self.lineage
+    }
+
+    /**
+     * Breeds the `Crab`s at indices `i` and `j`, adding the new `Crab` to
+     * the end of the beach's crab vector. If the indices are out of bounds,
+     * or the breeding rules forbid it, the method should panic. The weather
+     * and temperature never stop it; `try_breed_crabs` rolls for those.
+     */
+    #[deprecated(note = "indices shift as crabs leave; use `breed_crabs_by_id`")]
+    pub fn breed_crabs(&mut self, i: usize, j: usize, name: String) {
+        self.breed_crabs_at(i, j, name);
+    }
+
+    pub(crate) fn breed_crabs_at(&mut self, i: usize, j: usize, name: String) {
+        if let Err(reason) = self.breed_offspring(i, j, name, false) {
+            panic!("{}", reason);
+        }
+    }
+
+    /**
+     * Breeds the `Crab`s at indices `i` and `j` like `breed_crabs`, naming the
+     * hatchling with the given generator. Returns the hatchling's name.
+     */
+    pub fn hatch(&mut self, i: usize, j: usize, names: &mut NameGenerator) -> String {
+        for crab in &self.crabs {
+            names.reserve(crab.name());
+        }
+        let name = names.next_name();
+        self.breed_crabs_at(i, j, name.clone());
+        name
+    }
+
+    /**
+     * Adds `n` crabs with random speeds (1 to 50), colors and diets to the
+     * beach, named by a `NameGenerator`. The same seed always produces the
+     * same crabs, and the new names never clash with existing residents.
+     * Speeds and claw sizes are kept within the beach's constraints.
+     */
+    pub fn populate_random(&mut self, n: usize, seed: u64) {
+        let distributions = TraitDistributions {
+            limits: self.constraints.clone(),
+            ..TraitDistributions::new()
+        };
+        self.add_random_crabs(n, seed, &distributions);
+    }
+
+    /**
+     * Returns a new beach of `n_crabs` crabs made like `populate_random`,
+     * each of which joins one of `n_clans` clans, named "Clan 1" onwards, at
+     * random. Clans that no crab happens to join do not exist. The same seed
+     * always produces the same beach.
+     */
+    pub fn generate_random(n_crabs: usize, n_clans: usize, seed: u64) -> Beach {
+        let mut beach = Beach::new();
+        beach.populate_random(n_crabs, seed);
+        if n_clans == 0 {
+            return beach;
+        }
+        // A stream of its own, so the clans don't follow the crabs' traits.
+        let mut rng = Pcg64::seed_from_u64(seed.wrapping_add(1));
+        let names: Vec<String> = beach.crabs().map(|crab| crab.name().to_string()).collect();
+        for name in names {
+            let clan_id = format!("Clan {}", rng.gen_range(1..=n_clans));
+            beach.add_member_to_clan(&clan_id, &name);
+        }
+        beach
+    }
+
+    /**
+     * Adds `n` crabs like `populate_random`, but with speeds and claw sizes
+     * drawn from the given distributions. Fails, adding no crabs, if any
+     * distribution is invalid, or its limits reach past the beach's
+     * constraints.
+     */
+    pub fn populate_with(
+        &mut self,
+        n: usize,
+        seed: u64,
+        distributions: &TraitDistributions,
+    ) -> Result<(), String> {
+        distributions.validate()?;
+        for field in [Bounded::Speed, Bounded::ClawSize] {
+            let limits = distributions.limits.range(field);
+            let allowed = self.constraints.range(field);
+            if limits.start() < allowed.start() || limits.end() > allowed.end() {
+                return Err(tr(
+                    Message::InvalidDistribution,
+                    &[&format!(
+                        "{} limits {}..={} reach past the beach's {}..={}",
+                        field.name(),
+                        limits.start(),
+                        limits.end(),
+                        allowed.start(),
+                        allowed.end()
+                    )],
+                ));
+            }
+        }
+        self.add_random_crabs(n, seed, distributions);
+        Ok(())
+    }
+
+    fn add_random_crabs(&mut self, n: usize, seed: u64, distributions: &TraitDistributions) {
+        let mut rng = Pcg64::seed_from_u64(seed);
+        let mut names = NameGenerator::new(seed);
+        for crab in &self.crabs {
+            names.reserve(crab.name());
+        }
+
+        let limits = &distributions.limits;
+        if n > 0 {
+            self.checkpoint();
+        }
+        for _ in 0..n {
+            let color = Color::new(rng.gen(), rng.gen(), rng.gen());
+            let diet = [Diet::Fish, Diet::Shellfish, Diet::Plants][rng.gen_range(0..3)];
+            let speed = distributions.speed.sample(&mut rng, &limits.speed);
+            let mut crab = Crab::new(names.next_name(), speed, color, diet);
+            crab.set_claw_size(distributions.claw_size.sample(&mut rng, &limits.claw_size));
+            self.unrecorded(|beach| beach.add_crab(crab));
+        }
+    }
+
+    /**
+     * Adds crabs from a stream of records, such as crabs parsed from a large
+     * file, without collecting them first. Each crab must keep to the
+     * beach's constraints and have a non-empty name that no other crab on
+     * the beach has, and the beach must have or make room for it; crabs
+     * evicted to make room are dropped.
+     *
+     * Crabs are inserted in batches of `IMPORT_BATCH`; watches are checked
+     * and `progress` is called with the running total after each batch.
+     * Stops at the first bad record, keeping the crabs imported before it,
+     * and returns an error naming the record (counting from 1). Otherwise
+     * returns the number of crabs imported.
+     */
+    pub fn import_stream<I, E>(
+        &mut self,
+        records: I,
+        mut progress: impl FnMut(usize),
+    ) -> Result<usize, String>
+    where
+        I: IntoIterator<Item = Result<Crab, E>>,
+        E: Display,
+    {
+        let mut names: HashSet<String> = HashSet::new();
+        let mut imported = 0;
+        let mut batch = Vec::with_capacity(IMPORT_BATCH);
+        let mut result = Ok(());
+        for (k, record) in records.into_iter().enumerate() {
+            let crab = match record {
+                Ok(crab) if crab.name().is_empty() => Err(tr(Message::ImportEmptyName, &[])),
+                Ok(crab)
+                    if names.contains(crab.name()) || self.by_name.contains_key(crab.name()) =>
+                {
+                    Err(tr(Message::ImportDuplicateName, &[&crab.name()]))
+                }
+                Ok(crab) => self
+                    .constraints
+                    .check(&crab)
+                    .map_err(OceanError::OutOfBounds)
+                    .and_then(|_| self.check_room_for(batch.len() + 1))
+                    .map(|()| crab)
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+            match crab {
+                Ok(crab) => {
+                    names.insert(crab.name().to_string());
+                    batch.push(crab);
+                }
+                Err(reason) => {
+                    result = Err(tr(Message::ImportFailed, &[&(k + 1), &reason]));
+                    break;
+                }
+            }
+            if batch.len() == IMPORT_BATCH {
+                if imported == 0 {
+                    self.checkpoint();
+                }
+                imported += self.insert_batch(&mut batch);
+                progress(imported);
+            }
+        }
+        if !batch.is_empty() {
+            if imported == 0 {
+                self.checkpoint();
+            }
+            imported += self.insert_batch(&mut batch);
+            progress(imported);
+        }
+        result.map(|()| imported)
+    }
+
+    /** Moves a batch of crabs onto the beach, checking watches once. */
+    fn insert_batch(&mut self, batch: &mut Vec<Crab>) -> usize {
+        let n = batch.len();
+        self.reserve(n);
+        for crab in batch.drain(..) {
+            self.make_room();
+            self.push_crab(crab);
+        }
+        self.check_watches();
+        n
+    }
+
+    /**
+     * Returns mutable references to the two distinct crabs at indices `i` and `j`.
+     * Panics if the indices are equal or out of bounds.
+     */
+    fn get_two_crabs_mut(&mut self, i: usize, j: usize) -> (&mut Crab, &mut Crab) {
+        self.crabs.pair_mut(i, j)
+    }
+
+    /**
+     * Has the crab at index `i` attack the crab at index `j` under the default
+     * battle rules. Both crabs keep whatever injuries they suffer.
+     * If the indices are equal or out of bounds, the method should panic.
+     */
+    pub fn battle_crabs(&mut self, i: usize, j: usize) -> BattleReport {
+        let (attacker, defender) = self.get_two_crabs_mut(i, j);
+        battle::fight(attacker, defender, &BattleRules::default())
+    }
+
+    /**
+     * Has the crabs at indices `i` and `j` meet. The faster one eats the
+     * slower one if its diet allows and the slower one is not protected: the
+     * eater is fed and regains its diet's energy, and the eaten crab leaves the
+     * beach and its clan. A slower crab that could eat the faster one goes
+     * after it, but it gets away, as does a crab just as fast as its hunter.
+     * If the indices are equal or out of bounds, the method should panic.
+     */
+    pub fn simulate_encounter(&mut self, i: usize, j: usize) -> Encounter {
+        assert_ne!(i, j, "a crab cannot meet itself");
+        let (fast, slow) = if self.crabs[i].speed() >= self.crabs[j].speed() {
+            (i, j)
+        } else {
+            (j, i)
+        };
+        let hunts = |hunter: usize, prey: usize| {
+            self.crabs[hunter].diet().can_eat(&self.crabs[prey].diet()) && !self.is_protected(prey)
+        };
+        if !hunts(fast, slow) || self.crabs[fast].speed() == self.crabs[slow].speed() {
+            let (predator, prey) = if hunts(fast, slow) {
+                (fast, slow)
+            } else if hunts(slow, fast) {
+                (slow, fast)
+            } else {
+                return Encounter::Ignored;
+            };
+            return Encounter::Escaped {
+                predator: self.crabs[predator].name().to_string(),
+                prey: self.crabs[prey].name().to_string(),
+            };
+        }
+
+        self.checkpoint();
+        self.crabs.set_fed_at(fast, self.ticks);
+        let eater = &mut self.crabs[fast];
+        eater.feed();
+        eater.heal(eater.diet().energy());
+        let predator = eater.name().to_string();
+        let prey = self
+            .unrecorded(|beach| beach.remove_crab_at(slow))
+            .name()
+            .to_string();
+        self.events.publish(BeachEvent::CrabDied {
+            name: prey.clone(),
+            cause: Cause::Predation,
+        });
+        Encounter::Eaten { predator, prey }
+    }
+
+    /**
+     * Returns the shell economy of this beach's crabs and clans.
+     */
+    pub fn economy(&self) -> &Economy {
+        &self.economy
+    }
+
+    pub fn economy_mut(&mut self) -> &mut Economy {
+        &mut self.economy
+    }
+
+    /**
+     * Sends the crab at the given index hunting in its reefs, or in its tide
+     * pool's food stock if it lives in a pool. If it catches something, it
+     * earns `FORAGING_REWARD` shells, and its clan, if it has one, earns its
+     * foraging share. Crabs in a blocked-off region cannot forage.
+     * If the index is out of bounds, the method should panic.
+     */
+    pub fn forage(&mut self, index: usize) -> bool {
+        if self.check_open(self.crabs[index].position()).is_err() {
+            return false;
+        }
+        let pool = self.tide_pools.pool_of(self.crabs.handle(index));
+        let crab = &mut self.crabs[index];
+        let caught = match pool {
+            Some(k) => crab.hunt_in(self.tide_pools.pools()[k].food.clone()),
+            None => crab.hunt(),
+        };
+        if !caught {
+            return false;
+        }
+        // An account too full to take its reward simply goes without.
+        let account = Account::crab(crab.name());
+        let _ = self
+            .economy
+            .earn(account, economy::FORAGING_REWARD, Earning::Foraging);
+        let share = self.treasury.foraging_share;
+        if let Some(clan_id) = self.clan_system.get_clan_of(self.crabs.handle(index)) {
+            if share > 0 {
+                let _ = self
+                    .economy
+                    .earn(Account::clan(clan_id), share, Earning::Foraging);
+            }
+        }
+        true
+    }
+
+    /**
+     * Has a member of the given clan pay dues into the clan's treasury.
+     * Fails if the crab is not a member of the clan or cannot afford it.
+     */
+    pub fn pay_clan_dues(
+        &mut self,
+        clan_id: &str,
+        crab_name: &str,
+        amount: u64,
+    ) -> Result<(), OceanError> {
+        if !self.is_member_named(clan_id, crab_name) {
+            return Err(OceanError::NotClanMember {
+                crab: crab_name.to_string(),
+                clan: clan_id.to_string(),
+            });
+        }
+        self.economy
+            .check_afford(&Account::crab(crab_name), amount)?;
+        self.economy.check_room(&Account::clan(clan_id), amount)?;
+        self.economy
+            .pay_dues(crab_name, clan_id, amount)
+            .expect("dues within the balance and the room");
+        Ok(())
+    }
+
+    /** Sets how clans fill and spend their treasuries. */
+    pub fn set_treasury_rules(&mut self, rules: TreasuryRules) {
+        self.treasury = rules;
+    }
+
+    pub fn treasury_rules(&self) -> TreasuryRules {
+        self.treasury
+    }
+
+    /** Returns how many shells the clan's treasury holds. */
+    pub fn get_treasury(&self, clan_id: &str) -> u64 {
+        self.economy.balance(&Account::clan(clan_id))
+    }
+
+    /**
+     * Spends shells from the clan's treasury. Fails if there is no such
+     * clan or the treasury cannot afford it.
+     */
+    pub fn spend_treasury(
+        &mut self,
+        clan_id: &str,
+        amount: u64,
+        on: Spending,
+    ) -> Result<(), OceanError> {
+        if !self.clan_system.has_clan(clan_id) {
+            return Err(OceanError::UnknownClan(clan_id.to_string()));
+        }
+        let account = Account::clan(clan_id);
+        self.economy.check_afford(&account, amount)?;
+        self.economy
+            .spend(&account, amount, on)
+            .expect("spending within the balance");
+        Ok(())
+    }
+
+    /**
+     * Makes a member of the clan faster by `points`, paid for from the clan's
+     * treasury at the boost price per point. Fails, spending nothing, if the
+     * crab is not a member, if the treasury cannot afford it, or if the
+     * crab would be faster than the beach's constraints allow.
+     */
+    pub fn boost_member(
+        &mut self,
+        clan_id: &str,
+        crab_name: &str,
+        points: u32,
+    ) -> Result<(), String> {
+        let index = self
+            .inhabitant_id(crab_name)
+            .filter(|&id| self.clan_system.is_member(clan_id, id))
+            .and_then(|id| self.index_of(id));
+        let Some(index) = index else {
+            return Err(tr(Message::NotClanMember, &[&crab_name, &clan_id]));
+        };
+        let speed = self.crabs[index].speed();
+        self.crabs[index].set_speed(speed.saturating_add(points));
+        let checked = self.constraints.check(&self.crabs[index]);
+        self.crabs[index].set_speed(speed);
+        checked.map_err(|error| error.to_string())?;
+        let price = self.treasury.boost_price.saturating_mul(points as u64);
+        self.spend_treasury(clan_id, price, Spending::SpeedBoost)
+            .map_err(|error| error.to_string())?;
+        self.checkpoint();
+        self.crabs[index].set_speed(speed.saturating_add(points));
+        self.check_watches();
+        Ok(())
+    }
+
+    /**
+     * Buys meals from the clan's treasury, at the meal price each, for the
+     * clan's hungry and starving members, in the order they joined, for as
+     * long as the treasury can afford them. A fed crab is healed as if it
+     * had caught its meal. Returns the names of the crabs fed, or an Err if
+     * there is no such clan.
+     */
+    pub fn feed_hungry_members(&mut self, clan_id: &str) -> Result<Vec<String>, String> {
+        if !self.clan_system.has_clan(clan_id) {
+            return Err(tr(Message::NoSuchClan, &[&clan_id]));
+        }
+        let price = self.treasury.meal_price;
+        let mut fed = Vec::new();
+        for member in self.clan_system.clan_members(clan_id).to_vec() {
+            let Some(index) = self.index_of(member) else {
+                continue;
+            };
+            if !matches!(
+                self.crabs[index].state(),
+                LifeState::Hungry | LifeState::Starving
+            ) {
+                continue;
+            }
+            if self
+                .spend_treasury(clan_id, price, Spending::Meals)
+                .is_err()
+            {
+                break;
+            }
+            if fed.is_empty() {
+                self.checkpoint();
+            }
+            let crab = &mut self.crabs[index];
+            crab.feed();
+            crab.heal(self.life.nourishment);
+            fed.push(crab.name().to_string());
+            self.crabs.set_fed_at(index, self.ticks);
+        }
+        Ok(fed)
+    }
+
+    /**
+     * Returns a reference to the clan system associated with the beach.
+     */
+    pub fn get_clan_system(&self) -> &ClanSystem {
+        &self.clan_system
+    }
+
+    /**
+     * Returns the id of the first inhabitant with the given name: the crab
+     * that arrived first, or else one of another species. The clan methods
+     * that take a crab's name act on this inhabitant; the ones that take an
+     * id reach any crab, whichever others share its name.
+     */
+    pub fn inhabitant_id(&self, name: &str) -> Option<CrabId> {
+        if let Some(&handle) = self.by_name.get(name).and_then(|handles| handles.first()) {
+            return Some(handle);
+        }
+        self.others
+            .iter()
+            .find(|(_, other)| other.name() == name)
+            .map(|&(id, _)| id)
+    }
+
+    /** Returns the name of the inhabitant with the given id, if it lives here. */
+    pub(crate) fn inhabitant_name(&self, id: CrabId) -> Option<&str> {
+        if let Some(crab) = self.crabs.get(id) {
+            return Some(crab.name());
+        }
+        self.others
+            .iter()
+            .find(|&&(other, _)| other == id)
+            .map(|(_, other)| other.name())
+    }
+
+    /** Returns whether the first inhabitant with the given name is in the clan. */
+    fn is_member_named(&self, clan_id: &str, crab_name: &str) -> bool {
+        self.inhabitant_id(crab_name)
+            .is_some_and(|id| self.clan_system.is_member(clan_id, id))
+    }
+
+    /**
+     * Adds the first inhabitant with the given name to a clan, like
+     * `add_member_to_clan_by_id`. Does nothing if no inhabitant has the name.
+     */
+    pub fn add_member_to_clan(&mut self, clan_id: &str, crab_name: &str) {
+        if let Some(id) = self.inhabitant_id(crab_name) {
+            self.add_member_to_clan_by_id(clan_id, id);
+        }
+    }
+
+    /**
+     * Adds the inhabitant with the given id as a member to the clan system for the given clan id.
+     * A crab can only belong to one clan: a crab in another clan moves to this one, and adding a crab to its own
+     * clan again does nothing. A crab the clan's constitution turns away is not added, and nothing says so: a
+     * caller that needs to know whether the crab joined, or why not, must use `try_add_member_to_clan_by_id`.
+     * Nothing happens either if no inhabitant of the beach has the id.
+     */
+    pub fn add_member_to_clan_by_id(&mut self, clan_id: &str, id: CrabId) {
+        if self.clan_system.is_member(clan_id, id)
+            || self.check_admission_by_id(clan_id, id).is_err()
+        {
+            return;
+        }
+        self.enroll(clan_id, id);
+    }
+
+    /**
+     * Adds a crab to a clan like `add_member_to_clan`, whatever the clan's
+     * constitution says, as when undoing its leaving.
+     */
+    pub(crate) fn rejoin_clan(&mut self, clan_id: &str, crab_name: &str) {
+        if let Some(id) = self.inhabitant_id(crab_name) {
+            self.enroll(clan_id, id);
+        }
+    }
+
+    /** Adds an inhabitant to a clan, whatever the clan's constitution says. */
+    fn enroll(&mut self, clan_id: &str, id: CrabId) {
+        if self.clan_system.is_member(clan_id, id) {
+            return;
+        }
+        let Some(name) = self.inhabitant_name(id).map(str::to_string) else {
+            return;
+        };
+        self.checkpoint();
+        self.clan_system.join(clan_id, id, &name);
+        self.fill_vacancies();
+        self.events.publish(BeachEvent::ClanMemberAdded {
+            clan_id: clan_id.to_string(),
+            crab_name: name,
+            clan_size: self.clan_system.get_clan_member_count(clan_id),
+        });
+        self.check_watches();
+    }
+
+    /**
+     * Adds the first inhabitant with the given name to a clan, like
+     * `try_add_member_to_clan_by_id`, or returns why not; a name no
+     * inhabitant has is rejected as not on the beach.
+     */
+    pub fn try_add_member_to_clan(
+        &mut self,
+        clan_id: &str,
+        crab_name: &str,
+    ) -> Result<(), OceanError> {
+        let Some(id) = self.inhabitant_id(crab_name) else {
+            return Err(OceanError::Rejected(Rejection {
+                clan: clan_id.to_string(),
+                crab: crab_name.to_string(),
+                reason: RejectionReason::NotOnBeach,
+            }));
+        };
+        self.try_add_member_to_clan_by_id(clan_id, id)
+    }
+
+    /**
+     * Adds an inhabitant to a clan like `add_member_to_clan_by_id`, unless
+     * it already belongs to a clan, this one or another, or the clan's
+     * constitution turns it away, or no inhabitant has the id. Use
+     * `move_member` to change a crab's clan.
+     */
+    pub fn try_add_member_to_clan_by_id(
+        &mut self,
+        clan_id: &str,
+        id: CrabId,
+    ) -> Result<(), OceanError> {
+        let Some(name) = self.inhabitant_name(id) else {
+            return Err(OceanError::UnknownCrabId(id));
+        };
+        if let Some(clan) = self.clan_system.get_clan_of(id) {
+            return Err(OceanError::DuplicateMembership {
+                crab: name.to_string(),
+                clan: clan.to_string(),
+            });
+        }
+        self.check_admission_by_id(clan_id, id)
+            .map_err(OceanError::Rejected)?;
+        self.add_member_to_clan_by_id(clan_id, id);
+        Ok(())
+    }
+
+    /**
+     * Returns whether the first crab with the given name may join the clan
+     * by its constitution, or why not. A clan without a constitution admits
+     * any name.
+     */
+    pub fn check_admission(&self, clan_id: &str, crab_name: &str) -> Result<(), Rejection> {
+        match self.inhabitant_id(crab_name) {
+            Some(id) => self.check_admission_by_id(clan_id, id),
+            None if self.clan_system.constitution(clan_id).is_some() => Err(Rejection {
+                clan: clan_id.to_string(),
+                crab: crab_name.to_string(),
+                reason: RejectionReason::NotOnBeach,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /**
+     * Returns whether the crab with the given id may join the clan by its
+     * constitution, or why not. Only a crab living on the beach can be
+     * judged, so a clan with a constitution turns away anyone else.
+     */
+    pub fn check_admission_by_id(&self, clan_id: &str, id: CrabId) -> Result<(), Rejection> {
+        let Some(constitution) = self.clan_system.constitution(clan_id) else {
+            return Ok(());
+        };
+        let members = self.clan_system.get_clan_member_count(clan_id);
+        match self.crabs.get(id) {
+            Some(crab) => constitution.admits(clan_id, crab, members),
+            None => Err(Rejection {
+                clan: clan_id.to_string(),
+                crab: self
+                    .inhabitant_name(id)
+                    .map_or_else(|| id.to_string(), str::to_string),
+                reason: RejectionReason::NotOnBeach,
+            }),
+        }
+    }
+
+    /**
+     * Sets the rules crabs must meet to join a clan from now on; see
+     * `ClanSystem::set_constitution`.
+     */
+    pub fn set_constitution(&mut self, clan_id: &str, constitution: Constitution) {
+        self.checkpoint();
+        self.clan_system.set_constitution(clan_id, constitution);
+    }
+
+    /** Lifts a clan's rules, returning them, so that any crab may join. */
+    pub fn remove_constitution(&mut self, clan_id: &str) -> Option<Constitution> {
+        self.record_ok(|beach| beach.clan_system.remove_constitution(clan_id).ok_or(()))
+            .ok()
+    }
+
+    /** Returns the id of the clan the first crab with the given name belongs to. */
+    pub fn get_clan_of(&self, crab_name: &str) -> Option<&str> {
+        self.clan_system.get_clan_of(self.inhabitant_id(crab_name)?)
+    }
+
+    /**
+     * Returns the crabs of a clan, in the order they joined it. Members
+     * that are not crabs living on the beach are left out, and reported by
+     * `stale_memberships`.
+     */
+    pub fn clan_members(&self, clan_id: &str) -> impl Iterator<Item = &Crab> + '_ {
+        let members = self.clan_system.clan_members(clan_id).iter();
+        members.filter_map(|&member| self.resolve(member))
+    }
+
+    /**
+     * Returns the crabs of every clan, along with the memberships of those
+     * that are not crabs living on the beach.
+     */
+    pub fn clans_of_crabs(&self) -> ClanCrabs<'_> {
+        let mut crabs = ClanCrabs::default();
+        for id in self.clan_system.clan_ids() {
+            let mut members = Vec::new();
+            for &member in self.clan_system.clan_members(id) {
+                match self.resolve(member) {
+                    Some(crab) => members.push(crab),
+                    None => crabs.stale.push(StaleMembership {
+                        clan_id: id.to_string(),
+                        crab: member,
+                        crab_name: self
+                            .clan_system
+                            .member_name(member)
+                            .unwrap_or_default()
+                            .to_string(),
+                    }),
+                }
+            }
+            crabs.clans.insert(id, members);
+        }
+        crabs
+    }
+
+    /** Returns the memberships of those that are not crabs living on the beach. */
+    pub fn stale_memberships(&self) -> Vec<StaleMembership> {
+        self.clans_of_crabs().stale
+    }
+
+    fn crabs_named<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a Crab> + 'a {
+        let handles = self.by_name.get(name).map_or(&[][..], Vec::as_slice);
+        handles.iter().filter_map(|&handle| self.resolve(handle))
+    }
+
+    /**
+     * Moves the first crab with the given name from one clan to another,
+     * creating the destination clan if needed. Returns an error if the crab
+     * is not a member of `from`, or the destination's constitution turns it
+     * away.
+     */
+    pub fn move_member(&mut self, crab_name: &str, from: &str, to: &str) -> Result<(), OceanError> {
+        let member = self
+            .inhabitant_id(crab_name)
+            .filter(|&id| self.clan_system.is_member(from, id));
+        let Some(id) = member else {
+            return Err(OceanError::NotClanMember {
+                crab: crab_name.to_string(),
+                clan: from.to_string(),
+            });
+        };
+        self.check_admission_by_id(to, id)
+            .map_err(OceanError::Rejected)?;
+        self.add_member_to_clan_by_id(to, id);
+        Ok(())
+    }
+
+    /**
+     * Takes the first crab with the given name out of its clan, like
+     * `remove_member_by_id`.
+     */
+    pub fn remove_member(&mut self, crab_name: &str) -> Option<String> {
+        self.remove_member_by_id(self.inhabitant_id(crab_name)?)
+    }
+
+    /**
+     * Takes a crab out of its clan, dissolving the clan if it is left empty.
+     * Returns the id of the clan it left, if it had one.
+     */
+    pub fn remove_member_by_id(&mut self, id: CrabId) -> Option<String> {
+        self.clan_system.get_clan_of(id)?;
+        self.checkpoint();
+        let clan = self.clan_system.remove_member(id);
+        self.fill_vacancies();
+        self.check_watches();
+        clan
+    }
+
+    /**
+     * Takes the first crab with the given name out of the given clan,
+     * dissolving the clan if it is left empty. Returns whether the crab was
+     * a member.
+     */
+    pub fn remove_member_from_clan(&mut self, clan_id: &str, crab_name: &str) -> bool {
+        if !self.is_member_named(clan_id, crab_name) {
+            return false;
+        }
+        self.remove_member(crab_name);
+        true
+    }
+
+    /**
+     * Sets the relation between two clans of this beach.
+     */
+    pub fn set_clan_relation(
+        &mut self,
+        id1: &str,
+        id2: &str,
+        relation: Relation,
+    ) -> Result<(), String> {
+        self.record_ok(|beach| beach.clan_system.set_relation(id1, id2, relation))
+    }
+
+    /**
+     * Makes one clan of this beach a sub-clan of another.
+     */
+    pub fn create_subclan(&mut self, parent_id: &str, child_id: &str) -> Result<(), String> {
+        self.record_ok(|beach| beach.clan_system.create_subclan(parent_id, child_id))
+    }
+
+    /**
+     * Makes two clans of this beach allies.
+     */
+    pub fn form_alliance(&mut self, id1: &str, id2: &str) -> Result<(), String> {
+        self.record_ok(|beach| beach.clan_system.form_alliance(id1, id2))
+    }
+
+    /**
+     * Disbands a clan of this beach, returning the names of its former
+     * members, who are left without a clan. Its treasury stays in its
+     * account.
+     */
+    pub fn disband_clan(&mut self, clan_id: &str) -> Result<Vec<String>, OceanError> {
+        let members = self.record_ok(|beach| beach.clan_system.disband_clan(clan_id))?;
+        self.check_watches();
+        Ok(members)
+    }
+
+    /**
+     * Gives a clan of this beach a new id. Its treasury moves to the
+     * account of the new id.
+     */
+    pub fn rename_clan(&mut self, old_id: &str, new_id: &str) -> Result<(), OceanError> {
+        self.record_ok(|beach| -> Result<(), OceanError> {
+            beach.clan_system.rename_clan(old_id, new_id)?;
+            beach
+                .economy
+                .transfer_all(&Account::clan(old_id), &Account::clan(new_id));
+            Ok(())
+        })?;
+        self.check_watches();
+        Ok(())
+    }
+
+    /**
+     * Merges two clans of this beach into one called `new_id`, as
+     * `ClanSystem::merge_clans` does. The treasuries of both move to the
+     * merged clan's account, and if neither clan had a leader, the merged
+     * clan elects one. Returns an error, as `ClanSystem::merge_clans` does,
+     * or if a crab of the clan whose constitution is dropped does not meet
+     * the one the merged clan keeps.
+     */
+    pub fn merge_clans(&mut self, id1: &str, id2: &str, new_id: &str) -> Result<(), OceanError> {
+        if let Some(constitution) = self.clan_system.merged_constitution(id1, id2) {
+            let joining = if self.clan_system.constitution(id1).is_some() {
+                id2
+            } else {
+                id1
+            };
+            for crab in self.clan_members(joining) {
+                constitution
+                    .admits(new_id, crab, 0)
+                    .map_err(OceanError::Rejected)?;
+            }
+        }
+        self.record_ok(|beach| -> Result<(), OceanError> {
+            beach.clan_system.merge_clans(id1, id2, new_id)?;
+            for id in [id1, id2] {
+                beach
+                    .economy
+                    .transfer_all(&Account::clan(id), &Account::clan(new_id));
+            }
+            Ok(())
+        })?;
+        self.fill_vacancies();
+        self.check_watches();
+        Ok(())
+    }
+
+    /**
+     * Sets how crabs are scored for leading their clans, by speed unless set
+     * otherwise; e.g. `Crab::health` makes the healthiest member lead.
+     */
+    pub fn set_leadership_criterion(&mut self, criterion: fn(&Crab) -> u32) {
+        self.leadership = criterion;
+    }
+
+    /**
+     * Makes the member of the clan that scores highest by the leadership
+     * criterion its leader, the one that joined first if several score as
+     * high, and returns its name. A clan whose leader leaves it elects a new
+     * one straight away. Returns None if the clan has no members.
+     */
+    pub fn elect_leader(&mut self, clan_id: &str) -> Option<String> {
+        if !self.clan_system.has_clan(clan_id) {
+            return None;
+        }
+        self.checkpoint();
+        self.elect(clan_id)
+    }
+
+    fn elect(&mut self, clan_id: &str) -> Option<String> {
+        let (crabs, leadership) = (&self.crabs, self.leadership);
+        let leader = self
+            .clan_system
+            .elect_leader_by(clan_id, |member| crabs.get(member).map(leadership))?;
+        self.clan_system.member_name(leader).map(str::to_string)
+    }
+
+    /** Elects a new leader for every clan whose leader left it. */
+    fn fill_vacancies(&mut self) {
+        for clan_id in self.clan_system.take_vacancies() {
+            self.elect(&clan_id);
+        }
+    }
+
+    /** Returns the name of the clan's leader, if it has one. */
+    pub fn get_leader(&self, clan_id: &str) -> Option<&str> {
+        self.clan_system
+            .member_name(self.clan_system.get_leader(clan_id)?)
+    }
+
+    /**
+     * Gives the first crab with the given name, a member of a clan of this
+     * beach, a role, such as "scout", in place of any it had.
+     */
+    pub fn set_role(
+        &mut self,
+        clan_id: &str,
+        crab_name: &str,
+        role: &str,
+    ) -> Result<(), OceanError> {
+        let Some(id) = self.inhabitant_id(crab_name) else {
+            return Err(OceanError::NotClanMember {
+                crab: crab_name.to_string(),
+                clan: clan_id.to_string(),
+            });
+        };
+        self.record_ok(|beach| beach.clan_system.set_role(clan_id, id, role))
+    }
+
+    /** Returns the role of the first crab with the given name, if it has one. */
+    pub fn get_role(&self, crab_name: &str) -> Option<&str> {
+        self.clan_system.get_role(self.inhabitant_id(crab_name)?)
+    }
+
+    /**
+     * Returns the names of the clan's members with the given role, in the
+     * order they joined.
+     */
+    pub fn members_with_role(&self, clan_id: &str, role: &str) -> Vec<&str> {
+        self.clan_system.members_with_role(clan_id, role)
+    }
+
+    /**
+     * Returns the average speed of the resident crabs fighting for the given
+     * clan's alliance, counting its allies and every sub-clan, or None if the
+     * alliance has no resident members.
+     */
+    pub fn get_alliance_avg_speed(&self, clan_id: &str) -> Option<f64> {
+        let mut total = SpeedTotal::new();
+        for member in self.clan_system.alliance_members(clan_id) {
+            total.extend(self.resolve(member).map(Crab::typed_speed));
+        }
+        total.mean()
+    }
+
+    /**
+     * Pits the alliances of two clans against each other like
+     * `get_winner_clan`, and returns the id of whichever of the two clans
+     * leads the winning alliance. Fails if the clans are in the same alliance.
+     */
+    pub fn get_winner_alliance(&self, id1: &str, id2: &str) -> Result<Option<String>, OceanError> {
+        if id1 == id2 {
+            return Err(OceanError::ClanAgainstItself(id1.to_string()));
+        }
+        for id in [id1, id2] {
+            if !self.clan_system.has_clan(id) {
+                return Err(OceanError::UnknownClan(id.to_string()));
+            }
+        }
+        if self.clan_system.alliance(id1).iter().any(|id| id == id2) {
+            return Err(OceanError::SameAlliance(id1.to_string(), id2.to_string()));
+        }
+        let avg1 = self
+            .get_alliance_avg_speed(id1)
+            .ok_or_else(|| OceanError::UnknownClan(id1.to_string()))?;
+        let avg2 = self
+            .get_alliance_avg_speed(id2)
+            .ok_or_else(|| OceanError::UnknownClan(id2.to_string()))?;
+
+        if avg1 > avg2 {
+            Ok(Some(id1.to_string()))
+        } else if avg2 > avg1 {
+            Ok(Some(id2.to_string()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /**
+     * Returns the average speed of the resident crabs belonging to the given clan,
+     * or None if the clan has no resident members.
+     */
+    pub fn get_crabs_avg_speed(&self, clan_id: &str) -> Option<f64> {
+        let mut total = SpeedTotal::new();
+        total.extend(self.clan_members(clan_id).map(Crab::typed_speed));
+        total.mean()
+    }
+
+    /**
+     * Returns the id of the clan that wins the competition given two clan ids. The winner is decided based on the average speed of the clan members.
+     * Return `None` if there are no clear winners between two different existing clans. If the inputs are invalid, return an Err.
+     */
+    pub fn get_winner_clan(&self, id1: &str, id2: &str) -> Result<Option<String>, OceanError> {
+        self.get_winner_clan_by(id1, id2, SpeedMetric::Mean)
+    }
+
+    /**
+     * Decides a competition between two clans like `get_winner_clan`, but
+     * comparing the given figure of their members' speeds.
+     */
+    pub fn get_winner_clan_by(
+        &self,
+        id1: &str,
+        id2: &str,
+        metric: SpeedMetric,
+    ) -> Result<Option<String>, OceanError> {
+        if id1 == id2 {
+            return Err(OceanError::ClanAgainstItself(id1.to_string()));
+        }
+        let speed = |id: &str| {
+            self.clan_stats(id)
+                .map(|stats| stats.speed(metric))
+                .ok_or_else(|| OceanError::UnknownClan(id.to_string()))
+        };
+        let speed1 = speed(id1)?;
+        let speed2 = speed(id2)?;
+
+        if speed1 > speed2 {
+            Ok(Some(id1.to_string()))
+        } else if speed2 > speed1 {
+            Ok(Some(id2.to_string()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /**
+     * Sets the tie-breakers `record_competition` tries, in order, when two
+     * clans are as fast as each other. With none, which is how beaches
+     * start, such competitions are drawn.
+     */
+    pub fn set_tie_breakers(&mut self, tie_breakers: &[TieBreaker]) {
+        self.tie_breakers = tie_breakers.to_vec();
+    }
+
+    pub fn tie_breakers(&self) -> &[TieBreaker] {
+        &self.tie_breakers
+    }
+
+    /**
+     * Returns the winner of a competition between two existing clans that
+     * are as fast as each other by the first of the beach's tie-breakers
+     * that decides it, along with that tie-breaker.
+     */
+    fn break_tie(&mut self, id1: &str, id2: &str) -> Option<(String, TieBreaker)> {
+        let (stats1, stats2) = (self.clan_stats(id1)?, self.clan_stats(id2)?);
+        for tie_breaker in self.tie_breakers.clone() {
+            let comparison = match tie_breaker {
+                TieBreaker::MaxSpeed => stats1.max_speed.cmp(&stats2.max_speed),
+                TieBreaker::MemberCount => stats1.member_count.cmp(&stats2.member_count),
+                TieBreaker::CoinFlip => {
+                    if self.with_rng(|_, rng| rng.gen_bool(0.5)) {
+                        Ordering::Greater
+                    } else {
+                        Ordering::Less
+                    }
+                }
+            };
+            match comparison {
+                Ordering::Greater => return Some((id1.to_string(), tie_breaker)),
+                Ordering::Less => return Some((id2.to_string(), tie_breaker)),
+                Ordering::Equal => {}
+            }
+        }
+        None
+    }
+
+    /**
+     * Decides a competition between two clans like `get_winner_clan`,
+     * falling back on the beach's tie-breakers if they are as fast as each
+     * other, and records the result on the beach's leaderboard and in its
+     * competition history. The winner earns the victory prize into its
+     * treasury.
+     */
+    pub fn record_competition(
+        &mut self,
+        id1: &str,
+        id2: &str,
+    ) -> Result<Option<String>, OceanError> {
+        let mut winner = self.get_winner_clan(id1, id2)?;
+        let mut tie_breaker = None;
+        if winner.is_none() {
+            if let Some((tie_winner, decided_by)) = self.break_tie(id1, id2) {
+                winner = Some(tie_winner);
+                tie_breaker = Some(decided_by);
+            }
+        }
+        self.leaderboard
+            .record_competition(id1, id2, winner.as_deref());
+        let prize = self.treasury.victory_prize;
+        if let Some(winner) = winner.as_deref().filter(|_| prize > 0) {
+            // A treasury too full to take the prize goes without.
+            let _ = self
+                .economy
+                .earn(Account::clan(winner), prize, Earning::Competition);
+        }
+        self.competitions.push(CompetitionRecord {
+            tick: self.ticks,
+            clans: (id1.to_string(), id2.to_string()),
+            winner: winner.clone(),
+            tie_breaker,
+        });
+        Ok(winner)
+    }
+
+    /** The competitions recorded on the beach so far, oldest first. */
+    pub fn competition_history(&self) -> &[CompetitionRecord] {
+        &self.competitions
+    }
+
+    /** The recorded competitions the given clan took part in, oldest first. */
+    pub fn competition_history_of(&self, clan_id: &str) -> Vec<&CompetitionRecord> {
+        self.competitions
+            .iter()
+            .filter(|record| record.involves(clan_id))
+            .collect()
+    }
+
+    /**
+     * Decides and records a competition between two clans like
+     * `record_competition`, with each clan staking `stake` shells from its
+     * treasury on the result: the winner takes the loser's stake, and a draw
+     * leaves both where they were. Fails, recording nothing, if the
+     * competition is invalid, or either treasury cannot cover the stake or
+     * hold the other's. Should the victory prize leave the winner's
+     * treasury too full for the stake, the competition stays recorded, but
+     * the stake stays with the loser and an Err is returned.
+     */
+    pub fn wager_competition(
+        &mut self,
+        id1: &str,
+        id2: &str,
+        stake: u64,
+    ) -> Result<Option<String>, OceanError> {
+        self.get_winner_clan(id1, id2)?;
+        for id in [id1, id2] {
+            self.economy.check_afford(&Account::clan(id), stake)?;
+            self.economy.check_room(&Account::clan(id), stake)?;
+        }
+        let winner = self.record_competition(id1, id2)?;
+        if let Some(winner) = winner.as_deref() {
+            let loser = if winner == id1 { id2 } else { id1 };
+            let winnings = Account::clan(winner);
+            self.economy.check_room(&winnings, stake)?;
+            self.economy
+                .transfer(&Account::clan(loser), &winnings, stake)
+                .expect("a stake within the balance and the room");
+        }
+        Ok(winner)
+    }
+
+    /**
+     * Races the crabs with the given ids against each other and records the
+     * result on the beach's leaderboard. Returns the crabs in the order they
+     * finished, fastest first by their speed at the moment, with crabs as
+     * fast as each other in the same place. A crab listed twice runs once.
+     * If any of the crabs has left the beach, nothing is recorded and an
+     * Err is returned.
+     */
+    pub fn record_race(&mut self, ids: &[CrabId]) -> Result<Vec<Vec<CrabId>>, OceanError> {
+        let mut runners: Vec<(u32, CrabId)> = Vec::new();
+        for &id in ids {
+            let index = self.index_of(id).ok_or(OceanError::UnknownCrabId(id))?;
+            if !runners.iter().any(|&(_, runner)| runner == id) {
+                runners.push((self.effective_speed(index), id));
+            }
+        }
+        runners.sort_by_key(|&(speed, _)| std::cmp::Reverse(speed));
+        let places: Vec<Vec<CrabId>> = runners
+            .chunk_by(|(speed1, _), (speed2, _)| speed1 == speed2)
+            .map(|place| place.iter().map(|&(_, id)| id).collect())
+            .collect();
+        self.leaderboard.record_race(&places);
+        Ok(places)
+    }
+
+    /** The ratings earned in the races and competitions recorded so far. */
+    pub fn leaderboard(&self) -> &Leaderboard {
+        &self.leaderboard
+    }
+
+    pub fn leaderboard_mut(&mut self) -> &mut Leaderboard {
+        &mut self.leaderboard
+    }
+}
+
+/** A one-line summary of the beach; see `report` for more. */
+impl std::fmt::Display for Beach {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&tr(
+            Message::BeachSummary,
+            &[&self.size(), &self.clan_system.get_clan_count()],
+        ))
+    }
+}
+
+impl Default for Beach {
+    fn default() -> Self {
+        Beach::new()
+    }
+}
+
+/** Adds the crabs like `Beach::add_crabs`, dropping any crabs evicted for them. */
+impl Extend<Crab> for Beach {
+    fn extend<I: IntoIterator<Item = Crab>>(&mut self, crabs: I) {
+        self.add_crabs(crabs);
+    }
+}
+
+/** Gathers the crabs onto a new beach, in order. */
+impl FromIterator<Crab> for Beach {
+    fn from_iter<I: IntoIterator<Item = Crab>>(crabs: I) -> Beach {
+        let mut beach = Beach::new();
+        beach.add_crabs(crabs);
+        beach
     }
 }
@@ -0,0 +1,35 @@
+/*
+ * Drives a beach from the command line, one command per line; type `help`
+ * for the list. Run with `cargo run --features cli --bin ocean-cli`, giving
+ * the path of a file of commands to run it instead of reading stdin.
+ */
+use ocean::repl::Repl;
+use std::fs::File;
+use std::io::{self, BufReader, IsTerminal};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let mut repl = Repl::new();
+    let stdout = io::stdout().lock();
+    let result = match std::env::args().nth(1) {
+        Some(path) => match File::open(&path) {
+            Ok(file) => repl.run(BufReader::new(file), stdout, None),
+            Err(error) => {
+                eprintln!("{}: {}", path, error);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => {
+            let stdin = io::stdin();
+            let prompt = stdin.is_terminal().then_some("ocean> ");
+            repl.run(stdin.lock(), stdout, prompt)
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
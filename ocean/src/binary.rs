@@ -0,0 +1,374 @@
+use crate::beach::{Beach, CrabId};
+use crate::clans::{ClanSystem, Constitution, Relation};
+use crate::color::{Allele, Color, Genotype};
+use crate::crab::Crab;
+use crate::diet::Diet;
+use crate::i18n::{tr, Message};
+use crate::position::Position;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/** The bytes every binary save starts with. */
+pub const BINARY_MAGIC: [u8; 4] = *b"OCNB";
+
+/**
+ * The version of the binary format `save_binary` writes. `load_binary` reads
+ * this version and every one before it:
+ *   - 1: the clock, and each crab's name, speed, color, diet, claw size,
+ *     health and arrival, then each clan's members.
+ *   - 2: adds each crab's color alleles, hunger, age, position and
+ *     generation, and the clans' relations and sub-clans.
+ *   - 3: adds the clans' constitutions.
+ *   - 4: adds each crab's years.
+ *   - 5: adds each crab's id, and saves clan members by id before name.
+ */
+pub const BINARY_VERSION: u16 = 5;
+
+/** Appends little-endian fields to a buffer. */
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, n: u8) {
+        self.0.push(n);
+    }
+
+    fn u32(&mut self, n: u32) {
+        self.0.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn u64(&mut self, n: u64) {
+        self.0.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn len(&mut self, n: usize) {
+        self.u32(n as u32);
+    }
+
+    fn str(&mut self, s: &str) {
+        self.len(s.len());
+        self.0.extend_from_slice(s.as_bytes());
+    }
+
+    fn color(&mut self, color: &Color) {
+        self.0.extend_from_slice(&[color.r, color.g, color.b]);
+    }
+
+    fn diet(&mut self, diet: Diet) {
+        self.u8(match diet {
+            Diet::Fish => 0,
+            Diet::Shellfish => 1,
+            Diet::Plants => 2,
+        });
+    }
+}
+
+/** Reads little-endian fields from a buffer, failing if it runs out. */
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        if self.bytes.len() < n {
+            return Err(invalid(tr(Message::BinaryTruncated, &[])));
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn len(&mut self) -> io::Result<usize> {
+        Ok(self.u32()? as usize)
+    }
+
+    /** Reads a byte that says whether an optional field follows. */
+    fn flag(&mut self) -> io::Result<bool> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            n => Err(invalid(tr(Message::BinaryBadValue, &[&"flag", &n]))),
+        }
+    }
+
+    fn str(&mut self) -> io::Result<String> {
+        let len = self.len()?;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(invalid)
+    }
+
+    fn color(&mut self) -> io::Result<Color> {
+        Ok(Color::new(self.u8()?, self.u8()?, self.u8()?))
+    }
+
+    fn diet(&mut self) -> io::Result<Diet> {
+        match self.u8()? {
+            0 => Ok(Diet::Fish),
+            1 => Ok(Diet::Shellfish),
+            2 => Ok(Diet::Plants),
+            n => Err(invalid(tr(Message::BinaryBadValue, &[&"diet", &n]))),
+        }
+    }
+}
+
+fn invalid<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+/** Reads a version 1 crab, which has no alleles, hunger, age or position. */
+fn read_crab_v1(reader: &mut Reader) -> io::Result<Crab> {
+    let name = reader.str()?;
+    let speed = reader.u32()?;
+    let color = reader.color()?;
+    let diet = reader.diet()?;
+    let mut crab = Crab::new(name, speed, color, diet);
+    crab.set_claw_size(reader.u32()?);
+    crab.set_health(reader.u32()?);
+    Ok(crab)
+}
+
+/** Reads a crab of version 2 or later; crabs have years from version 4. */
+fn read_crab_v2(reader: &mut Reader, version: u16) -> io::Result<Crab> {
+    let name = reader.str()?;
+    let speed = reader.u32()?;
+    let mut alleles = Vec::with_capacity(2);
+    for _ in 0..2 {
+        alleles.push(Allele::new(reader.color()?, reader.u8()?));
+    }
+    let [a, b]: [Allele; 2] = alleles.try_into().unwrap();
+    let diet = reader.diet()?;
+    let mut crab = Crab::with_genotype(name, speed, Genotype::new(a, b), diet);
+    crab.set_claw_size(reader.u32()?);
+    crab.set_health(reader.u32()?);
+    crab.set_hunger(reader.u32()?);
+    crab.set_age(reader.u64()?);
+    if version >= 4 {
+        crab.set_years(reader.u64()?);
+    }
+    crab.set_position(Position::new(reader.u32()?, reader.u32()?));
+    crab.set_generation(reader.u32()?);
+    Ok(crab)
+}
+
+/**
+ * Reads the clans of a beach saved in the given version once its crabs are
+ * on `beach`. Before version 5, members were saved by name alone.
+ */
+fn read_clans(reader: &mut Reader, version: u16, beach: &mut Beach) -> io::Result<ClanSystem> {
+    let mut clan_system = ClanSystem::new();
+    for _ in 0..reader.len()? {
+        let id = reader.str()?;
+        for _ in 0..reader.len()? {
+            if version >= 5 {
+                let crab = CrabId::from_raw(reader.u64()?);
+                clan_system.add_member(&id, crab, &reader.str()?);
+            } else {
+                let name = reader.str()?;
+                for crab in beach.ids_saved_by_name(&name) {
+                    clan_system.add_member(&id, crab, &name);
+                }
+            }
+        }
+    }
+    if version >= 2 {
+        for _ in 0..reader.len()? {
+            let (id1, id2) = (reader.str()?, reader.str()?);
+            let relation = match reader.u8()? {
+                0 => Relation::Alliance,
+                1 => Relation::Rivalry,
+                n => return Err(invalid(tr(Message::BinaryBadValue, &[&"relation", &n]))),
+            };
+            clan_system
+                .set_relation(&id1, &id2, relation)
+                .map_err(invalid)?;
+        }
+        for _ in 0..reader.len()? {
+            let (child, parent) = (reader.str()?, reader.str()?);
+            clan_system
+                .create_subclan(&parent, &child)
+                .map_err(invalid)?;
+        }
+    }
+    if version >= 3 {
+        for _ in 0..reader.len()? {
+            let id = reader.str()?;
+            let min_speed = reader.u32()?;
+            let diet = if reader.flag()? {
+                Some(reader.diet()?)
+            } else {
+                None
+            };
+            let max_size = if reader.flag()? {
+                Some(reader.len()?)
+            } else {
+                None
+            };
+            let constitution = Constitution {
+                min_speed,
+                diet,
+                max_size,
+            };
+            clan_system.set_constitution(&id, constitution);
+        }
+    }
+    Ok(clan_system)
+}
+
+impl Beach {
+    /**
+     * Returns the beach in the compact binary format: `BINARY_MAGIC`, the
+     * format version, then the same things `Serialize for Beach` saves.
+     */
+    pub fn to_binary(&self) -> Vec<u8> {
+        let mut writer = Writer(BINARY_MAGIC.to_vec());
+        writer.0.extend_from_slice(&BINARY_VERSION.to_le_bytes());
+        writer.u64(self.ticks());
+        writer.len(self.size());
+        for (i, crab) in self.crabs().enumerate() {
+            writer.u64(self.handle(i).raw());
+            writer.str(crab.name());
+            writer.u32(crab.speed());
+            for allele in &crab.genotype().alleles {
+                writer.color(&allele.color);
+                writer.u8(allele.dominance);
+            }
+            writer.diet(crab.diet());
+            writer.u32(crab.claw_size());
+            writer.u32(crab.health());
+            writer.u32(crab.hunger());
+            writer.u64(crab.age());
+            writer.u64(crab.years());
+            writer.u32(crab.position().x);
+            writer.u32(crab.position().y);
+            writer.u32(crab.generation());
+            writer.u64(self.ticks() - self.age(i));
+        }
+
+        let clans = self.get_clan_system();
+        writer.len(clans.clan_ids().count());
+        for id in clans.clan_ids() {
+            writer.str(id);
+            let members = clans.clan_members(id);
+            writer.len(members.len());
+            for &member in members {
+                writer.u64(member.raw());
+                writer.str(clans.member_name(member).unwrap_or_default());
+            }
+        }
+        let relations = clans.relations();
+        writer.len(relations.len());
+        for (id1, id2, relation) in relations {
+            writer.str(id1);
+            writer.str(id2);
+            writer.u8(match relation {
+                Relation::Alliance => 0,
+                Relation::Rivalry => 1,
+            });
+        }
+        let subclans: Vec<(&str, &str)> = clans
+            .clan_ids()
+            .filter_map(|id| Some((id, clans.parent_clan(id)?)))
+            .collect();
+        writer.len(subclans.len());
+        for (child, parent) in subclans {
+            writer.str(child);
+            writer.str(parent);
+        }
+        writer.len(clans.constitutions().count());
+        for (id, constitution) in clans.constitutions() {
+            writer.str(id);
+            writer.u32(constitution.min_speed);
+            match constitution.diet {
+                Some(diet) => {
+                    writer.u8(1);
+                    writer.diet(diet);
+                }
+                None => writer.u8(0),
+            }
+            match constitution.max_size {
+                Some(max_size) => {
+                    writer.u8(1);
+                    writer.len(max_size);
+                }
+                None => writer.u8(0),
+            }
+        }
+        writer.0
+    }
+
+    /**
+     * Reads a beach written by `to_binary` in this or any earlier version
+     * of the format. Things an earlier version did not save start as they
+     * do for a new crab, or are left out. Fails if the bytes are not a
+     * binary save, come from a later version, or are cut short.
+     */
+    pub fn from_binary(bytes: &[u8]) -> io::Result<Beach> {
+        let mut reader = Reader { bytes };
+        if reader.take(4).ok() != Some(&BINARY_MAGIC[..]) {
+            return Err(invalid(tr(Message::BinaryBadMagic, &[])));
+        }
+        let version = reader.u16()?;
+        if version == 0 || version > BINARY_VERSION {
+            return Err(invalid(tr(
+                Message::BinaryUnsupportedVersion,
+                &[&version, &BINARY_VERSION],
+            )));
+        }
+
+        let ticks = reader.u64()?;
+        let mut crabs = Vec::new();
+        let mut newest: Option<CrabId> = None;
+        for _ in 0..reader.len()? {
+            let id = if version >= 5 {
+                let id = CrabId::from_raw(reader.u64()?);
+                if newest.is_some_and(|newest| newest >= id) {
+                    return Err(invalid(tr(Message::BinaryBadValue, &[&"id", &id])));
+                }
+                newest = Some(id);
+                Some(id)
+            } else {
+                None
+            };
+            let crab = match version {
+                1 => read_crab_v1(&mut reader)?,
+                _ => read_crab_v2(&mut reader, version)?,
+            };
+            let arrived = reader.u64()?;
+            if arrived > ticks {
+                return Err(invalid(tr(
+                    Message::BinaryBadValue,
+                    &[&"arrival", &arrived],
+                )));
+            }
+            crabs.push((id, crab, arrived));
+        }
+        Beach::from_saved(ticks, crabs, |beach| {
+            read_clans(&mut reader, version, beach)
+        })
+    }
+
+    /** Saves the beach in the binary format at the given path. See `to_binary`. */
+    pub fn save_binary<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_binary())
+    }
+
+    /** Loads a beach saved by `save_binary`, in this or an earlier version. */
+    pub fn load_binary<P: AsRef<Path>>(path: P) -> io::Result<Beach> {
+        Beach::from_binary(&fs::read(path)?)
+    }
+}
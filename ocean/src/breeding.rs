@@ -0,0 +1,263 @@
+use crate::beach::CrabId;
+use crate::genealogy::Lineage;
+use crate::i18n::{tr, Message};
+use std::collections::HashMap;
+use std::fmt;
+
+/**
+ * Limits on breeding that a beach enforces for every caller. The defaults
+ * impose no limits.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BreedingRules {
+    /** The most crabs that may be bred on the beach during one tick. */
+    pub per_tick_quota: Option<usize>,
+    /** How many ticks a parent must have lived on the beach. */
+    pub min_parent_age: u64,
+    /** How many ticks a crab must wait after breeding before it breeds again. */
+    pub cooldown: u64,
+    /** The most offspring any one pair of crabs may have together. */
+    pub max_offspring_per_pair: Option<u32>,
+    /** How closely related parents may be. */
+    pub inbreeding: Option<InbreedingLimit>,
+}
+
+/** What to do when two crabs that are too closely related breed. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InbreedingPolicy {
+    /** Refuse the breeding. */
+    Reject,
+    /** Allow the breeding but publish a `BeachEvent::InbreedingWarning`. */
+    Warn,
+}
+
+/**
+ * The closest relationship allowed between parents: pairs whose coefficient
+ * of relationship is above `max_relatedness` are handled by `policy`.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InbreedingLimit {
+    pub max_relatedness: f64,
+    pub policy: InbreedingPolicy,
+}
+
+/** How `Beach::breed_within_clan_by` picks the parents from a clan. */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParentChoice {
+    /** The fastest two crabs that may breed; of equals, the earliest to arrive. */
+    #[default]
+    Fastest,
+    /** Any two crabs that may breed, drawn from the beach's random numbers. */
+    Random,
+}
+
+/** Why two crabs may not breed now; see `Beach::can_breed`. */
+#[derive(Debug, Clone, PartialEq)]
+pub enum BreedingError {
+    /** The beach has already bred `quota` crabs this tick. */
+    QuotaReached { quota: usize },
+    /** A parent has lived on the beach for fewer than `min_age` ticks. */
+    TooYoung {
+        crab: String,
+        age: u64,
+        min_age: u64,
+    },
+    /** A parent bred too recently, and must wait `ticks_left` more ticks. */
+    CoolingDown { crab: String, ticks_left: u64 },
+    /** The parents already have `limit` offspring together. */
+    PairLimit {
+        crab1: String,
+        crab2: String,
+        limit: u32,
+    },
+    /** The parents are more closely related than the rules allow. */
+    TooClose {
+        crab1: String,
+        crab2: String,
+        relatedness: f64,
+        max_relatedness: f64,
+    },
+    /** The parents stand further apart than the territory allows. */
+    TooFar {
+        crab1: String,
+        crab2: String,
+        distance: f64,
+        max_distance: u32,
+    },
+}
+
+impl fmt::Display for BreedingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = match self {
+            BreedingError::QuotaReached { quota } => tr(Message::BreedQuotaReached, &[quota]),
+            BreedingError::TooYoung { crab, age, min_age } => {
+                tr(Message::BreedTooYoung, &[crab, age, min_age])
+            }
+            BreedingError::CoolingDown { crab, ticks_left } => {
+                tr(Message::BreedCoolingDown, &[crab, ticks_left])
+            }
+            BreedingError::PairLimit {
+                crab1,
+                crab2,
+                limit,
+            } => tr(Message::BreedPairLimit, &[crab1, crab2, limit]),
+            BreedingError::TooClose {
+                crab1,
+                crab2,
+                relatedness,
+                max_relatedness,
+            } => tr(
+                Message::BreedTooClose,
+                &[
+                    crab1,
+                    crab2,
+                    &format!("{:.3}", relatedness),
+                    max_relatedness,
+                ],
+            ),
+            BreedingError::TooFar {
+                crab1,
+                crab2,
+                distance,
+                max_distance,
+            } => tr(
+                Message::BreedTooFar,
+                &[crab1, crab2, &format!("{:.1}", distance), max_distance],
+            ),
+        };
+        f.write_str(&text)
+    }
+}
+
+impl std::error::Error for BreedingError {}
+
+/** A prospective parent, as the breeding rules see it. */
+#[derive(Debug, Clone, Copy)]
+pub struct Parent<'a> {
+    pub handle: CrabId,
+    pub name: &'a str,
+    pub age: u64,
+}
+
+/** A beach's breeding rules together with what they have counted so far. */
+#[derive(Debug, Clone, Default)]
+pub struct BreedingState {
+    pub rules: BreedingRules,
+    bred_this_tick: usize,
+    // Pairs of parents, smallest handle first -> their number of offspring.
+    offspring: HashMap<(CrabId, CrabId), u32>,
+    // Crabs that bred recently -> the ticks before they may breed again.
+    cooldowns: HashMap<CrabId, u64>,
+}
+
+fn pair(a: CrabId, b: CrabId) -> (CrabId, CrabId) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+impl BreedingState {
+    pub fn new(rules: BreedingRules) -> BreedingState {
+        BreedingState {
+            rules,
+            bred_this_tick: 0,
+            offspring: HashMap::new(),
+            cooldowns: HashMap::new(),
+        }
+    }
+
+    /** The number of crabs bred since the last tick. */
+    pub fn bred_this_tick(&self) -> usize {
+        self.bred_this_tick
+    }
+
+    /** The number of offspring two crabs have had together. */
+    pub fn offspring(&self, a: CrabId, b: CrabId) -> u32 {
+        self.offspring.get(&pair(a, b)).copied().unwrap_or(0)
+    }
+
+    /** The ticks before a crab may breed again; 0 if it may breed now. */
+    pub fn cooldown(&self, crab: CrabId) -> u64 {
+        self.cooldowns.get(&crab).copied().unwrap_or(0)
+    }
+
+    /**
+     * Returns the relatedness of two crabs if it is above the inbreeding
+     * limit, whatever the policy.
+     */
+    pub fn too_close(&self, a: CrabId, b: CrabId, lineage: &Lineage) -> Option<f64> {
+        let limit = self.rules.inbreeding?;
+        let relatedness = lineage.relatedness(a, b);
+        (relatedness > limit.max_relatedness).then_some(relatedness)
+    }
+
+    /** Checks whether the rules allow two crabs to breed now. */
+    pub fn check(&self, a: Parent, b: Parent, lineage: &Lineage) -> Result<(), BreedingError> {
+        if let Some(quota) = self.rules.per_tick_quota {
+            if self.bred_this_tick >= quota {
+                return Err(BreedingError::QuotaReached { quota });
+            }
+        }
+        for parent in [a, b] {
+            if parent.age < self.rules.min_parent_age {
+                return Err(BreedingError::TooYoung {
+                    crab: parent.name.to_string(),
+                    age: parent.age,
+                    min_age: self.rules.min_parent_age,
+                });
+            }
+        }
+        for parent in [a, b] {
+            let ticks_left = self.cooldown(parent.handle);
+            if ticks_left > 0 {
+                return Err(BreedingError::CoolingDown {
+                    crab: parent.name.to_string(),
+                    ticks_left,
+                });
+            }
+        }
+        if let Some(limit) = self.rules.max_offspring_per_pair {
+            if self.offspring(a.handle, b.handle) >= limit {
+                return Err(BreedingError::PairLimit {
+                    crab1: a.name.to_string(),
+                    crab2: b.name.to_string(),
+                    limit,
+                });
+            }
+        }
+        if let Some(limit) = self.rules.inbreeding {
+            if limit.policy == InbreedingPolicy::Reject {
+                if let Some(relatedness) = self.too_close(a.handle, b.handle, lineage) {
+                    return Err(BreedingError::TooClose {
+                        crab1: a.name.to_string(),
+                        crab2: b.name.to_string(),
+                        relatedness,
+                        max_relatedness: limit.max_relatedness,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /** Counts a birth to two parents, who then cool down. */
+    pub fn record(&mut self, a: CrabId, b: CrabId) {
+        self.bred_this_tick += 1;
+        *self.offspring.entry(pair(a, b)).or_default() += 1;
+        if self.rules.cooldown > 0 {
+            self.cooldowns.insert(a, self.rules.cooldown);
+            self.cooldowns.insert(b, self.rules.cooldown);
+        }
+    }
+
+    /** Starts counting a new tick's births, and counts down the cooldowns. */
+    pub fn new_tick(&mut self) {
+        self.bred_this_tick = 0;
+        self.cooldowns.retain(|_, ticks_left| {
+            *ticks_left -= 1;
+            *ticks_left > 0
+        });
+    }
+}
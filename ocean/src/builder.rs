@@ -0,0 +1,158 @@
+use crate::beach::{Beach, CrabId};
+use crate::color::Color;
+use crate::crab::Crab;
+use crate::diet::Diet;
+
+/** The name of a crab built without one. */
+pub const DEFAULT_NAME: &str = "Crab";
+
+/**
+ * Builds a crab one trait at a time, so that traits cannot be mixed up the
+ * way `Crab::new`'s arguments can. Unless set, a crab is named
+ * `DEFAULT_NAME`, has a speed of 1, is blue, eats plants, is newborn and
+ * belongs to no clan.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrabBuilder {
+    name: String,
+    speed: u32,
+    color: Color,
+    diet: Diet,
+    age: u64,
+    clan: Option<String>,
+}
+
+impl Default for CrabBuilder {
+    fn default() -> Self {
+        CrabBuilder {
+            name: DEFAULT_NAME.to_string(),
+            speed: 1,
+            color: Color::new_blue(),
+            diet: Diet::Plants,
+            age: 0,
+            clan: None,
+        }
+    }
+}
+
+impl CrabBuilder {
+    pub fn new() -> CrabBuilder {
+        CrabBuilder::default()
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> CrabBuilder {
+        self.name = name.into();
+        self
+    }
+
+    pub fn speed(mut self, speed: u32) -> CrabBuilder {
+        self.speed = speed;
+        self
+    }
+
+    pub fn color(mut self, color: Color) -> CrabBuilder {
+        self.color = color;
+        self
+    }
+
+    pub fn diet(mut self, diet: Diet) -> CrabBuilder {
+        self.diet = diet;
+        self
+    }
+
+    pub fn age(mut self, age: u64) -> CrabBuilder {
+        self.age = age;
+        self
+    }
+
+    /** The clan the crab joins when it is added to a beach with `Beach::add_built`. */
+    pub fn clan(mut self, clan_id: impl Into<String>) -> CrabBuilder {
+        self.clan = Some(clan_id.into());
+        self
+    }
+
+    /** Returns the crab. A crab is not in any clan until it lives on a beach. */
+    pub fn build(self) -> Crab {
+        self.into_parts().0
+    }
+
+    fn into_parts(self) -> (Crab, Option<String>) {
+        let mut crab = Crab::new(self.name, self.speed, self.color, self.diet);
+        crab.set_age(self.age);
+        (crab, self.clan)
+    }
+}
+
+/**
+ * A `CrabBuilder` for a crab that is added to a beach when built; see
+ * `Beach::spawn`.
+ */
+#[derive(Debug)]
+pub struct Spawn<'a> {
+    beach: &'a mut Beach,
+    crab: CrabBuilder,
+}
+
+impl<'a> Spawn<'a> {
+    pub fn name(self, name: impl Into<String>) -> Spawn<'a> {
+        self.with(|crab| crab.name(name))
+    }
+
+    pub fn speed(self, speed: u32) -> Spawn<'a> {
+        self.with(|crab| crab.speed(speed))
+    }
+
+    pub fn color(self, color: Color) -> Spawn<'a> {
+        self.with(|crab| crab.color(color))
+    }
+
+    pub fn diet(self, diet: Diet) -> Spawn<'a> {
+        self.with(|crab| crab.diet(diet))
+    }
+
+    pub fn age(self, age: u64) -> Spawn<'a> {
+        self.with(|crab| crab.age(age))
+    }
+
+    pub fn clan(self, clan_id: impl Into<String>) -> Spawn<'a> {
+        self.with(|crab| crab.clan(clan_id))
+    }
+
+    /** Adds the crab to the beach like `Beach::add_built`, returning its id. */
+    pub fn build(self) -> CrabId {
+        self.beach.add_built(self.crab)
+    }
+
+    fn with(mut self, change: impl FnOnce(CrabBuilder) -> CrabBuilder) -> Spawn<'a> {
+        self.crab = change(self.crab);
+        self
+    }
+}
+
+impl Beach {
+    /**
+     * Returns a builder for a new crab, which is added at the end of this
+     * beach when built.
+     */
+    pub fn spawn(&mut self) -> Spawn<'_> {
+        Spawn {
+            beach: self,
+            crab: CrabBuilder::new(),
+        }
+    }
+
+    /**
+     * Adds a built crab like `add_crab_with_id` and returns its id. If the
+     * builder names a clan, the crab joins it, creating it if need be.
+     */
+    pub fn add_built(&mut self, builder: CrabBuilder) -> CrabId {
+        let (crab, clan) = builder.into_parts();
+        self.record(|beach| {
+            let id = beach.add_crab_with_id(crab);
+            if let Some(clan_id) = clan {
+                beach.add_member_to_clan_by_id(&clan_id, id);
+            }
+            id
+        })
+    }
+}
@@ -0,0 +1,30 @@
+use crate::store::CrabStore;
+
+/** What a beach that is full does when another crab would arrive. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EvictionPolicy {
+    /** The newcomer is turned away. */
+    #[default]
+    RejectNew,
+    /** The slowest crab leaves to make room; of equals, the earliest to arrive. */
+    EvictSlowest,
+    /** The crab that has lived on the beach longest leaves to make room. */
+    EvictOldest,
+}
+
+impl EvictionPolicy {
+    /**
+     * Returns the index of the crab that leaves to make room, given the
+     * crabs and the ticks they arrived at, or None if none does.
+     */
+    pub(crate) fn victim(self, crabs: &CrabStore) -> Option<usize> {
+        let indices = 0..crabs.len();
+        match self {
+            EvictionPolicy::RejectNew => None,
+            EvictionPolicy::EvictSlowest => {
+                indices.min_by_key(|&k| (crabs[k].speed(), crabs.arrived(k), k))
+            }
+            EvictionPolicy::EvictOldest => indices.min_by_key(|&k| (crabs.arrived(k), k)),
+        }
+    }
+}
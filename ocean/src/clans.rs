@@ -1,39 +1,995 @@
+use crate::beach::CrabId;
+use crate::crab::Crab;
+use crate::diet::Diet;
+use crate::error::OceanError;
+use crate::i18n::{tr, Message};
+use crate::intern::{Interner, Symbol};
+use smallvec::SmallVec;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::{self, Write};
 
-#[derive(Debug)]
+/** How two clans feel about each other. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Relation {
+    Alliance,
+    Rivalry,
+}
+
+/**
+ * A member of a clan that is not a crab living on the beach, such as an
+ * inhabitant of another species, or a crab a save listed without it.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StaleMembership {
+    pub clan_id: String,
+    pub crab: CrabId,
+    pub crab_name: String,
+}
+
+/** The crabs of every clan on a beach; see `Beach::clans_of_crabs`. */
+#[derive(Debug, Clone, Default)]
+pub struct ClanCrabs<'a> {
+    /** The crabs of each clan by clan id, in the order they joined it. */
+    pub clans: BTreeMap<&'a str, Vec<&'a Crab>>,
+    /** The members that are not crabs on the beach, by clan id and then joining order. */
+    pub stale: Vec<StaleMembership>,
+}
+
+/**
+ * The rules a crab must meet to join a clan; see
+ * `ClanSystem::set_constitution`. The default admits every crab.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Constitution {
+    /** The slowest a crab may be and still join. */
+    pub min_speed: u32,
+    /** The diet a crab must follow to join, if the clan insists on one. */
+    pub diet: Option<Diet>,
+    /** The most members the clan may have, if it is limited. */
+    pub max_size: Option<usize>,
+}
+
+impl Constitution {
+    /**
+     * Returns whether the constitution lets `crab` join the clan `clan_id`,
+     * which has `members` members besides it, or why not.
+     */
+    pub fn admits(&self, clan_id: &str, crab: &Crab, members: usize) -> Result<(), Rejection> {
+        let rejection = |reason| Rejection {
+            clan: clan_id.to_string(),
+            crab: crab.name().to_string(),
+            reason,
+        };
+        if crab.speed() < self.min_speed {
+            return Err(rejection(RejectionReason::TooSlow {
+                speed: crab.speed(),
+                min_speed: self.min_speed,
+            }));
+        }
+        if let Some(diet) = self.diet.filter(|&diet| diet != crab.diet()) {
+            return Err(rejection(RejectionReason::WrongDiet {
+                diet: crab.diet(),
+                required: diet,
+            }));
+        }
+        if let Some(max_size) = self.max_size.filter(|&max_size| members >= max_size) {
+            return Err(rejection(RejectionReason::ClanFull { max_size }));
+        }
+        Ok(())
+    }
+}
+
+/** A crab turned away from a clan by its constitution. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rejection {
+    pub clan: String,
+    pub crab: String,
+    pub reason: RejectionReason,
+}
+
+/** Which of a clan's rules a crab failed to meet. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectionReason {
+    /** The crab runs at `speed`, slower than the clan's `min_speed`. */
+    TooSlow { speed: u32, min_speed: u32 },
+    /** The crab follows `diet`, but the clan requires `required`. */
+    WrongDiet { diet: Diet, required: Diet },
+    /** The clan already has its `max_size` members. */
+    ClanFull { max_size: usize },
+    /** No crab on the beach answers to the name, so it cannot be judged. */
+    NotOnBeach,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (crab, clan) = (&self.crab, &self.clan);
+        let text = match self.reason {
+            RejectionReason::TooSlow { speed, min_speed } => {
+                tr(Message::ClanRejectsSlow, &[crab, clan, &speed, &min_speed])
+            }
+            RejectionReason::WrongDiet { diet, required } => tr(
+                Message::ClanRejectsDiet,
+                &[
+                    crab,
+                    clan,
+                    &format!("{:?}", diet),
+                    &format!("{:?}", required),
+                ],
+            ),
+            RejectionReason::ClanFull { max_size } => {
+                tr(Message::ClanRejectsFull, &[crab, clan, &max_size])
+            }
+            RejectionReason::NotOnBeach => tr(Message::ClanRejectsAbsent, &[crab, clan]),
+        };
+        f.write_str(&text)
+    }
+}
+
+impl std::error::Error for Rejection {}
+
+/**
+ * A clan's member list. Most clans are small, so the first few members are
+ * stored inline and only larger clans allocate.
+ */
+type Members = SmallVec<[CrabId; 6]>;
+
+#[derive(Debug, Clone)]
 pub struct ClanSystem {
-    // TODO: add necessary fields
+    // Clan id -> its members, in the order they joined.
+    clans: BTreeMap<String, Members>,
+    // Member -> the name it goes by, for reporting members by name. Names
+    // are interned, so each is stored once however often it is referred to.
+    member_names: HashMap<CrabId, Symbol>,
+    names: Interner,
+    // Member -> the id of its clan. A crab belongs to at most one clan.
+    memberships: HashMap<CrabId, String>,
+    // Pairs of clan ids, smallest first -> their relation.
+    relations: BTreeMap<(String, String), Relation>,
+    // Sub-clan id -> the id of the clan it belongs to.
+    parents: BTreeMap<String, String>,
+    // Clan id -> its leader, for the clans that have one.
+    leaders: BTreeMap<String, CrabId>,
+    // Member -> its role in its clan, for the members that have one.
+    roles: HashMap<CrabId, String>,
+    // The clans whose leader left, until they have a leader again.
+    vacancies: BTreeSet<String>,
+    // Clan id -> the rules for joining it, for the clans that have them.
+    constitutions: BTreeMap<String, Constitution>,
 }
 
 impl ClanSystem {
     pub fn new() -> ClanSystem {
-        unimplemented!();
+        ClanSystem {
+            clans: BTreeMap::new(),
+            member_names: HashMap::new(),
+            names: Interner::new(),
+            memberships: HashMap::new(),
+            relations: BTreeMap::new(),
+            parents: BTreeMap::new(),
+            leaders: BTreeMap::new(),
+            roles: HashMap::new(),
+            vacancies: BTreeSet::new(),
+            constitutions: BTreeMap::new(),
+        }
+    }
+
+    /**
+     * Adds the crab with the given id, which goes by `crab_name`, to the
+     * clan with the given id, creating the clan if it does not exist yet. A
+     * crab belongs to one clan at a time, so a crab in another clan leaves
+     * it first, and the id of that clan is returned. Adding a crab to its
+     * own clan does nothing. Neither does adding a crab to a clan its
+     * constitution says is full; `try_add_member` says why.
+     */
+    pub fn add_member(&mut self, clan_id: &str, crab: CrabId, crab_name: &str) -> Option<String> {
+        self.try_add_member(clan_id, crab, crab_name)
+            .unwrap_or(None)
+    }
+
+    /**
+     * Adds a crab to a clan like `add_member`, or returns why not if the
+     * clan's constitution says it is full. The clan system knows crabs only
+     * by id and name, so the rest of the constitution is left to `Beach`.
+     */
+    pub fn try_add_member(
+        &mut self,
+        clan_id: &str,
+        crab: CrabId,
+        crab_name: &str,
+    ) -> Result<Option<String>, Rejection> {
+        if self.is_member(clan_id, crab) {
+            return Ok(None);
+        }
+        self.check_room(clan_id, crab_name, 1)?;
+        Ok(self.join(clan_id, crab, crab_name))
+    }
+
+    /**
+     * Returns whether `joining` more members fit in the clan by its
+     * constitution, or the rejection of `crab_name` if they do not.
+     */
+    pub(crate) fn check_room(
+        &self,
+        clan_id: &str,
+        crab_name: &str,
+        joining: usize,
+    ) -> Result<(), Rejection> {
+        let Some(max_size) = self.constitution(clan_id).and_then(|c| c.max_size) else {
+            return Ok(());
+        };
+        if self.get_clan_member_count(clan_id) + joining <= max_size {
+            return Ok(());
+        }
+        Err(Rejection {
+            clan: clan_id.to_string(),
+            crab: crab_name.to_string(),
+            reason: RejectionReason::ClanFull { max_size },
+        })
+    }
+
+    /** Adds a crab to a clan like `add_member`, whatever the constitution says. */
+    pub(crate) fn join(&mut self, clan_id: &str, crab: CrabId, crab_name: &str) -> Option<String> {
+        if self.is_member(clan_id, crab) {
+            return None;
+        }
+        let previous = self.remove_member(crab);
+        let name = self.names.intern(crab_name);
+        self.clans
+            .entry(clan_id.to_string())
+            .or_default()
+            .push(crab);
+        self.member_names.insert(crab, name);
+        self.memberships.insert(crab, clan_id.to_string());
+        previous
+    }
+
+    /**
+     * Records the new name of a member, which keeps its place in its clan,
+     * its role and its leadership. Does nothing if the crab is in no clan.
+     */
+    pub(crate) fn rename_member(&mut self, crab: CrabId, new_name: &str) {
+        if self.memberships.contains_key(&crab) {
+            let name = self.names.intern(new_name);
+            self.member_names.insert(crab, name);
+        }
+    }
+
+    /**
+     * Takes the crab with the given id out of its clan, returning the id of
+     * the clan it left, if it had one. The crab loses its role, and if it
+     * led the clan, the clan is left without a leader. A clan left without
+     * members is dissolved, along with its relations; its sub-clans pass to
+     * its own parent clan, if it has one.
+     */
+    pub fn remove_member(&mut self, crab: CrabId) -> Option<String> {
+        let clan_id = self.memberships.remove(&crab)?;
+        self.member_names.remove(&crab);
+        self.roles.remove(&crab);
+        if self.leaders.get(&clan_id) == Some(&crab) {
+            self.leaders.remove(&clan_id);
+            self.vacancies.insert(clan_id.clone());
+        }
+        let members = self
+            .clans
+            .get_mut(&clan_id)
+            .expect("every membership is in a clan");
+        members.retain(|member| *member != crab);
+        if members.is_empty() {
+            self.dissolve(&clan_id);
+        }
+        Some(clan_id)
+    }
+
+    /**
+     * Moves a crab from one clan to another, creating the destination clan
+     * if needed. Returns an error if the crab is not a member of `from`, or
+     * `to` is full by its constitution.
+     */
+    pub fn move_member(&mut self, crab: CrabId, from: &str, to: &str) -> Result<(), String> {
+        if !self.is_member(from, crab) {
+            return Err(tr(Message::NotClanMember, &[&self.describe(crab), &from]));
+        }
+        let name = self.member_name(crab).unwrap_or_default().to_string();
+        self.try_add_member(to, crab, &name)
+            .map_err(|rejection| rejection.to_string())?;
+        Ok(())
+    }
+
+    /** Returns the member's name, or else its id, for messages. */
+    pub(crate) fn describe(&self, crab: CrabId) -> String {
+        self.member_name(crab)
+            .map_or_else(|| crab.to_string(), str::to_string)
+    }
+
+    /** Returns the id of the clan the crab with the given id belongs to. */
+    pub fn get_clan_of(&self, crab: CrabId) -> Option<&str> {
+        self.memberships.get(&crab).map(|id| id.as_str())
+    }
+
+    /** Returns the name a member goes by, or None if it is in no clan. */
+    pub fn member_name(&self, crab: CrabId) -> Option<&str> {
+        let &name = self.member_names.get(&crab)?;
+        Some(self.names.resolve(name))
+    }
+
+    /** Returns the id of the clan's leader, if it has one. */
+    pub fn get_leader(&self, clan_id: &str) -> Option<CrabId> {
+        self.leaders.get(clan_id).copied()
+    }
+
+    /**
+     * Makes a member of the clan its leader. Returns an error if the crab
+     * is not a member of the clan.
+     */
+    pub fn set_leader(&mut self, clan_id: &str, crab: CrabId) -> Result<(), String> {
+        if !self.is_member(clan_id, crab) {
+            return Err(tr(
+                Message::NotClanMember,
+                &[&self.describe(crab), &clan_id],
+            ));
+        }
+        self.leaders.insert(clan_id.to_string(), crab);
+        self.vacancies.remove(clan_id);
+        Ok(())
+    }
+
+    /**
+     * Makes the member that scores highest its clan's leader, the one that
+     * joined first if several score as high, and returns its id. Members
+     * scored None cannot lead. Returns None, leaving the clan without a
+     * leader, if no member can.
+     */
+    pub fn elect_leader_by(
+        &mut self,
+        clan_id: &str,
+        mut score: impl FnMut(CrabId) -> Option<u32>,
+    ) -> Option<CrabId> {
+        let mut best: Option<(u32, CrabId)> = None;
+        for &member in self.clan_members(clan_id) {
+            let Some(points) = score(member) else {
+                continue;
+            };
+            if best.is_none_or(|(most, _)| points > most) {
+                best = Some((points, member));
+            }
+        }
+        match best {
+            Some((_, leader)) => {
+                self.leaders.insert(clan_id.to_string(), leader);
+                self.vacancies.remove(clan_id);
+            }
+            None => {
+                self.leaders.remove(clan_id);
+            }
+        }
+        self.get_leader(clan_id)
+    }
+
+    /**
+     * Takes the ids of the clans whose leader left since they were last
+     * taken, and that are still without one.
+     */
+    pub(crate) fn take_vacancies(&mut self) -> BTreeSet<String> {
+        let vacancies = std::mem::take(&mut self.vacancies);
+        vacancies
+            .into_iter()
+            .filter(|clan_id| self.has_clan(clan_id) && !self.leaders.contains_key(clan_id))
+            .collect()
+    }
+
+    /**
+     * Gives a member of the clan a role, such as "scout", in place of any it
+     * had. Returns an error if the crab is not a member of the clan.
+     */
+    pub fn set_role(&mut self, clan_id: &str, crab: CrabId, role: &str) -> Result<(), OceanError> {
+        if !self.is_member(clan_id, crab) {
+            return Err(OceanError::NotClanMember {
+                crab: self.describe(crab),
+                clan: clan_id.to_string(),
+            });
+        }
+        self.roles.insert(crab, role.to_string());
+        Ok(())
+    }
+
+    /** Takes away a crab's role, returning it if it had one. */
+    pub fn clear_role(&mut self, crab: CrabId) -> Option<String> {
+        self.roles.remove(&crab)
+    }
+
+    /** Returns the role of the crab with the given id, if it has one. */
+    pub fn get_role(&self, crab: CrabId) -> Option<&str> {
+        self.roles.get(&crab).map(|role| role.as_str())
+    }
+
+    /**
+     * Returns the names of the clan's members with the given role, in the
+     * order they joined.
+     */
+    pub fn members_with_role(&self, clan_id: &str, role: &str) -> Vec<&str> {
+        self.clan_members(clan_id)
+            .iter()
+            .filter(|member| self.roles.get(member).is_some_and(|r| r == role))
+            .filter_map(|&member| self.member_name(member))
+            .collect()
+    }
+
+    fn dissolve(&mut self, clan_id: &str) {
+        self.clans.remove(clan_id);
+        self.leaders.remove(clan_id);
+        self.vacancies.remove(clan_id);
+        self.relations
+            .retain(|(a, b), _| a != clan_id && b != clan_id);
+        let parent = self.parents.remove(clan_id);
+        for child in self.subclans(clan_id) {
+            match &parent {
+                Some(parent) => self.parents.insert(child, parent.clone()),
+                None => self.parents.remove(&child),
+            };
+        }
+    }
+
+    /**
+     * Disbands a clan, returning the names of its members, who are left
+     * without a clan or a role. Its relations go with it, and its sub-clans
+     * pass to its own parent clan, if it has one. Returns an error if there
+     * is no such clan.
+     */
+    pub fn disband_clan(&mut self, clan_id: &str) -> Result<Vec<String>, OceanError> {
+        let names = self.get_clan_member_names(clan_id);
+        let members = self
+            .clans
+            .get(clan_id)
+            .ok_or_else(|| OceanError::UnknownClan(clan_id.to_string()))?
+            .clone();
+        for member in &members {
+            self.memberships.remove(member);
+            self.member_names.remove(member);
+            self.roles.remove(member);
+        }
+        self.dissolve(clan_id);
+        Ok(names)
+    }
+
+    /**
+     * Gives a clan a new id, keeping its members, leader, relations and
+     * place among the sub-clans. Returns an error if there is no clan
+     * `old_id`, or another clan is already called `new_id`.
+     */
+    pub fn rename_clan(&mut self, old_id: &str, new_id: &str) -> Result<(), OceanError> {
+        if !self.has_clan(old_id) {
+            return Err(OceanError::UnknownClan(old_id.to_string()));
+        }
+        if old_id == new_id {
+            return Ok(());
+        }
+        if self.has_clan(new_id) {
+            return Err(OceanError::DuplicateClan(new_id.to_string()));
+        }
+        let members = self.clans.remove(old_id).expect("the clan exists");
+        for member in &members {
+            self.memberships.insert(*member, new_id.to_string());
+        }
+        self.clans.insert(new_id.to_string(), members);
+        if let Some(leader) = self.leaders.remove(old_id) {
+            self.leaders.insert(new_id.to_string(), leader);
+        }
+        if self.vacancies.remove(old_id) {
+            self.vacancies.insert(new_id.to_string());
+        }
+        if let Some(constitution) = self.constitutions.remove(old_id) {
+            self.constitutions.insert(new_id.to_string(), constitution);
+        }
+        let renamed: Vec<(String, String)> = self
+            .relations
+            .keys()
+            .filter(|(a, b)| a == old_id || b == old_id)
+            .cloned()
+            .collect();
+        for (a, b) in renamed {
+            let relation = self.relations.remove(&(a.clone(), b.clone())).unwrap();
+            let rename = |id: String| if id == old_id { new_id.to_string() } else { id };
+            self.relations
+                .insert(ordered_pair(&rename(a), &rename(b)), relation);
+        }
+        if let Some(parent) = self.parents.remove(old_id) {
+            self.parents.insert(new_id.to_string(), parent);
+        }
+        for parent in self.parents.values_mut() {
+            if parent == old_id {
+                *parent = new_id.to_string();
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * Merges two clans into one called `new_id`, which may be the id of
+     * either. The members of `id1` come first, followed by those of `id2`;
+     * `id1`'s leader leads, or `id2`'s if `id1` had none. The merged clan
+     * keeps the relations of both, `id1`'s where they differ, and the
+     * sub-clans of both. It stays under `id1`'s parent clan, or else
+     * `id2`'s, unless that clan ends up below it. It keeps `id1`'s
+     * constitution, or else `id2`'s. Returns an error if either clan does
+     * not exist, they are the same clan, another clan is already called
+     * `new_id`, or the merged clan would have more members than that
+     * constitution allows.
+     */
+    pub fn merge_clans(&mut self, id1: &str, id2: &str, new_id: &str) -> Result<(), OceanError> {
+        for id in [id1, id2] {
+            if !self.has_clan(id) {
+                return Err(OceanError::UnknownClan(id.to_string()));
+            }
+        }
+        if id1 == id2 {
+            return Err(OceanError::MergeWithSelf(id1.to_string()));
+        }
+        if new_id != id1 && new_id != id2 && self.has_clan(new_id) {
+            return Err(OceanError::DuplicateClan(new_id.to_string()));
+        }
+        let size = self.get_clan_member_count(id1) + self.get_clan_member_count(id2);
+        let kept = self.merged_constitution(id1, id2);
+        if let Some(max_size) = kept.and_then(|c| c.max_size).filter(|&max| size > max) {
+            return Err(OceanError::MergeTooLarge {
+                clan1: id1.to_string(),
+                clan2: id2.to_string(),
+                size,
+                max_size,
+            });
+        }
+
+        let absorbed = self.clans.remove(id2).expect("the clan exists");
+        for member in &absorbed {
+            self.memberships.insert(*member, id1.to_string());
+        }
+        self.clans
+            .get_mut(id1)
+            .expect("the clan exists")
+            .extend(absorbed);
+        if let Some(leader) = self.leaders.remove(id2) {
+            self.leaders.entry(id1.to_string()).or_insert(leader);
+        }
+        if self.vacancies.remove(id2) && !self.leaders.contains_key(id1) {
+            self.vacancies.insert(id1.to_string());
+        }
+        self.constitutions.remove(id2);
+        if let Some(constitution) = kept {
+            self.constitutions.insert(id1.to_string(), constitution);
+        }
+
+        let relations: Vec<((String, String), Relation)> = self
+            .relations
+            .iter()
+            .filter(|((a, b), _)| a == id2 || b == id2)
+            .map(|(key, &relation)| (key.clone(), relation))
+            .collect();
+        for ((a, b), relation) in relations {
+            self.relations.remove(&(a.clone(), b.clone()));
+            let other = if a == id2 { b } else { a };
+            if other != id1 {
+                self.relations
+                    .entry(ordered_pair(id1, &other))
+                    .or_insert(relation);
+            }
+        }
+
+        // Take both clans out of the hierarchy before moving `id2`'s
+        // sub-clans under `id1`, so no cycle forms on the way.
+        let parent1 = self.parents.remove(id1);
+        let parent2 = self.parents.remove(id2);
+        for child in self.subclans(id2) {
+            self.parents.insert(child, id1.to_string());
+        }
+        let tree = self.clan_tree(id1);
+        let parent = parent1
+            .filter(|parent| parent != id2 && !tree.contains(parent))
+            .or(parent2.filter(|parent| !tree.contains(parent)));
+        if let Some(parent) = parent {
+            self.parents.insert(id1.to_string(), parent);
+        }
+        self.rename_clan(id1, new_id)
+    }
+
+    /**
+     * Returns the constitution `merge_clans` gives the merger of `id1` and
+     * `id2`: `id1`'s, or else `id2`'s.
+     */
+    pub fn merged_constitution(&self, id1: &str, id2: &str) -> Option<Constitution> {
+        self.constitution(id1).or(self.constitution(id2)).copied()
+    }
+
+    /**
+     * Returns the ids of all clans, in sorted order.
+     */
+    pub fn clan_ids(&self) -> impl Iterator<Item = &str> {
+        self.clans.keys().map(|id| id.as_str())
+    }
+
+    /**
+     * Returns whether a clan with the given id exists.
+     */
+    pub fn has_clan(&self, clan_id: &str) -> bool {
+        self.clans.contains_key(clan_id)
     }
 
     /**
      * Returns a list of the names of the clan members for the given clan id.
      */
     pub fn get_clan_member_names(&self, clan_id: &str) -> Vec<String> {
-        unimplemented!();
+        self.clan_members(clan_id)
+            .iter()
+            .filter_map(|&member| self.member_name(member))
+            .map(str::to_string)
+            .collect()
+    }
+
+    /**
+     * Returns the ids of the clan's members, in the order they joined,
+     * without copying any names. Use `member_name` to read their names.
+     */
+    pub fn clan_members(&self, clan_id: &str) -> &[CrabId] {
+        self.clans
+            .get(clan_id)
+            .map_or(&[], |members| members.as_slice())
+    }
+
+    /** Returns whether the crab with the given id is a member of the clan. */
+    pub fn is_member(&self, clan_id: &str, crab: CrabId) -> bool {
+        self.get_clan_of(crab) == Some(clan_id)
+    }
+
+    /**
+     * Sets the rules crabs must meet to join the clan from now on; see
+     * `Beach::try_add_member_to_clan`. Members who no longer meet them stay.
+     * The rules belong to the clan id, and last while the clan has no
+     * members, so they may be set before anyone joins.
+     */
+    pub fn set_constitution(&mut self, clan_id: &str, constitution: Constitution) {
+        self.constitutions.insert(clan_id.to_string(), constitution);
+    }
+
+    /** Lifts the clan's rules, returning them, so that any crab may join. */
+    pub fn remove_constitution(&mut self, clan_id: &str) -> Option<Constitution> {
+        self.constitutions.remove(clan_id)
+    }
+
+    /** Returns the rules for joining the clan, if it has any. */
+    pub fn constitution(&self, clan_id: &str) -> Option<&Constitution> {
+        self.constitutions.get(clan_id)
+    }
+
+    /** Returns the rules for joining every clan that has them, by clan id. */
+    pub fn constitutions(&self) -> impl Iterator<Item = (&str, &Constitution)> {
+        self.constitutions
+            .iter()
+            .map(|(id, constitution)| (id.as_str(), constitution))
     }
 
     /**
      * Returns the number of clans currently in existence.
      */
     pub fn get_clan_count(&self) -> usize {
-        unimplemented!();
+        self.clans.len()
     }
 
     /**
      * Returns the number of clan members for the given clan id.
      */
     pub fn get_clan_member_count(&self, clan_id: &str) -> usize {
-        unimplemented!();
+        self.clans.get(clan_id).map_or(0, |members| members.len())
+    }
+
+    /**
+     * Returns the number of distinct crabs in the given clan and, through
+     * any depth of sub-clans, the clans below it.
+     */
+    pub fn get_clan_member_count_transitive(&self, clan_id: &str) -> usize {
+        let mut members = BTreeSet::new();
+        for id in self.clan_tree(clan_id) {
+            members.extend(self.clan_members(&id).iter().copied());
+        }
+        members.len()
+    }
+
+    /**
+     * Estimates the bytes allocated by the clan system: every id, the member
+     * lists, each member's clan and interned name, and the map entries
+     * holding them.
+     */
+    pub fn memory_bytes(&self) -> usize {
+        let clans: usize = self
+            .clans
+            .iter()
+            .map(|(id, members)| {
+                let spilled = if members.spilled() {
+                    members.capacity() * std::mem::size_of::<CrabId>()
+                } else {
+                    0
+                };
+                id.capacity() + spilled + std::mem::size_of::<(String, Members)>()
+            })
+            .sum();
+        let relations: usize = self
+            .relations
+            .keys()
+            .map(|(a, b)| {
+                a.capacity() + b.capacity() + std::mem::size_of::<((String, String), Relation)>()
+            })
+            .sum();
+        let parents: usize = self
+            .parents
+            .iter()
+            .map(|(child, parent)| {
+                child.capacity() + parent.capacity() + std::mem::size_of::<(String, String)>()
+            })
+            .sum();
+        let memberships: usize = self
+            .memberships
+            .values()
+            .map(|id| id.capacity() + std::mem::size_of::<(CrabId, String)>())
+            .sum();
+        let member_names = self.member_names.len() * std::mem::size_of::<(CrabId, Symbol)>();
+        let leaders: usize = self
+            .leaders
+            .keys()
+            .map(|id| id.capacity() + std::mem::size_of::<(String, CrabId)>())
+            .sum();
+        let roles: usize = self
+            .roles
+            .values()
+            .map(|role| role.capacity() + std::mem::size_of::<(CrabId, String)>())
+            .sum();
+        clans
+            + self.names.memory_bytes()
+            + member_names
+            + memberships
+            + relations
+            + parents
+            + leaders
+            + roles
     }
 
     /**
      * Returns the id of the clan with the most number of members, or None if such a clan does not exist.
+     *
+     * Ties are broken in favor of the clan whose id sorts first.
      */
     pub fn get_largest_clan_id(&self) -> Option<String> {
-        unimplemented!();
+        let mut largest: Option<(&String, usize)> = None;
+        for (id, members) in &self.clans {
+            if largest.is_none_or(|(_, count)| members.len() > count) {
+                largest = Some((id, members.len()));
+            }
+        }
+        largest.map(|(id, _)| id.clone())
+    }
+}
+
+impl ClanSystem {
+    /**
+     * Sets the relation between two existing, different clans, replacing any
+     * previous relation between them.
+     */
+    pub fn set_relation(&mut self, id1: &str, id2: &str, relation: Relation) -> Result<(), String> {
+        let key = self.relation_key(id1, id2)?;
+        self.relations.insert(key, relation);
+        Ok(())
+    }
+
+    /**
+     * Removes the relation between two clans, returning it if there was one.
+     */
+    pub fn clear_relation(&mut self, id1: &str, id2: &str) -> Option<Relation> {
+        self.relations.remove(&ordered_pair(id1, id2))
+    }
+
+    /**
+     * Returns the relation between two clans, if they have one.
+     */
+    pub fn get_relation(&self, id1: &str, id2: &str) -> Option<Relation> {
+        self.relations.get(&ordered_pair(id1, id2)).copied()
     }
-}
\ No newline at end of file
+
+    /**
+     * Returns every relation as (clan id, clan id, relation), sorted by clan ids.
+     */
+    pub fn relations(&self) -> Vec<(&str, &str, Relation)> {
+        self.relations
+            .iter()
+            .map(|((a, b), &relation)| (a.as_str(), b.as_str(), relation))
+            .collect()
+    }
+
+    /**
+     * Makes `child_id` a sub-clan of `parent_id`, moving it out of any clan
+     * it belonged to before. Both clans must exist, and a clan cannot become
+     * a sub-clan of itself or of one of its own sub-clans.
+     */
+    pub fn create_subclan(&mut self, parent_id: &str, child_id: &str) -> Result<(), String> {
+        for id in [parent_id, child_id] {
+            if !self.has_clan(id) {
+                return Err(tr(Message::NoSuchClan, &[&id]));
+            }
+        }
+        if self.clan_tree(child_id).iter().any(|id| id == parent_id) {
+            return Err(tr(Message::SubclanCycle, &[&child_id, &parent_id]));
+        }
+        self.parents
+            .insert(child_id.to_string(), parent_id.to_string());
+        Ok(())
+    }
+
+    /**
+     * Makes a sub-clan independent again, returning the id of the clan it
+     * belonged to, if any.
+     */
+    pub fn detach_subclan(&mut self, child_id: &str) -> Option<String> {
+        self.parents.remove(child_id)
+    }
+
+    /** Returns the id of the clan the given clan is a sub-clan of, if any. */
+    pub fn parent_clan(&self, clan_id: &str) -> Option<&str> {
+        self.parents.get(clan_id).map(|id| id.as_str())
+    }
+
+    /** Returns the ids of the direct sub-clans of a clan, in sorted order. */
+    pub fn subclans(&self, clan_id: &str) -> Vec<String> {
+        self.parents
+            .iter()
+            .filter(|(_, parent)| *parent == clan_id)
+            .map(|(child, _)| child.clone())
+            .collect()
+    }
+
+    /**
+     * Returns the given clan followed by every clan below it, through any
+     * depth of sub-clans.
+     */
+    fn clan_tree(&self, clan_id: &str) -> Vec<String> {
+        let mut tree = vec![clan_id.to_string()];
+        let mut k = 0;
+        while k < tree.len() {
+            let children = self.subclans(&tree[k]);
+            tree.extend(children);
+            k += 1;
+        }
+        tree
+    }
+
+    /** Makes two existing, different clans allies. */
+    pub fn form_alliance(&mut self, id1: &str, id2: &str) -> Result<(), String> {
+        self.set_relation(id1, id2, Relation::Alliance)
+    }
+
+    /**
+     * Returns the alliance a clan belongs to: the clan itself and every clan
+     * allied to it, directly or through other allies, in sorted order. A
+     * clan without allies is an alliance of its own.
+     */
+    pub fn alliance(&self, clan_id: &str) -> Vec<String> {
+        let mut alliance = BTreeSet::from([clan_id.to_string()]);
+        let mut pending = vec![clan_id.to_string()];
+        while let Some(id) = pending.pop() {
+            for ((a, b), relation) in &self.relations {
+                let ally = if *a == id {
+                    b
+                } else if *b == id {
+                    a
+                } else {
+                    continue;
+                };
+                if *relation == Relation::Alliance && alliance.insert(ally.clone()) {
+                    pending.push(ally.clone());
+                }
+            }
+        }
+        alliance.into_iter().collect()
+    }
+
+    /**
+     * Returns the ids of every crab fighting for a clan's alliance: the
+     * members of each allied clan and of their sub-clans, each crab once,
+     * in sorted order.
+     */
+    pub fn alliance_members(&self, clan_id: &str) -> Vec<CrabId> {
+        let mut members = BTreeSet::new();
+        for ally in self.alliance(clan_id) {
+            for id in self.clan_tree(&ally) {
+                members.extend(self.clan_members(&id).iter().copied());
+            }
+        }
+        members.into_iter().collect()
+    }
+
+    fn relation_key(&self, id1: &str, id2: &str) -> Result<(String, String), String> {
+        if id1 == id2 {
+            return Err(tr(Message::RelationWithSelf, &[&id1]));
+        }
+        for id in [id1, id2] {
+            if !self.has_clan(id) {
+                return Err(tr(Message::NoSuchClan, &[&id]));
+            }
+        }
+        Ok(ordered_pair(id1, id2))
+    }
+
+    /**
+     * Returns the clans and their relations as an undirected GraphViz graph.
+     *
+     * Each clan is a circle whose width is proportional to its member count
+     * (the largest clan is 2 inches wide). Alliances are solid green edges,
+     * rivalries dashed red ones.
+     */
+    pub fn relations_dot(&self) -> String {
+        let largest = self
+            .clans
+            .values()
+            .map(|m| m.len())
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let mut dot = String::from("graph clans {\n    node [shape=circle, fixedsize=true];\n");
+        for (id, members) in &self.clans {
+            let width = (2.0 * members.len() as f64 / largest as f64).max(0.3);
+            let _ = writeln!(
+                dot,
+                "    \"{}\" [label=\"{} ({})\", width={:.2}];",
+                escape_dot(id),
+                escape_dot(id),
+                members.len(),
+                width
+            );
+        }
+        for ((a, b), relation) in &self.relations {
+            let style = match relation {
+                Relation::Alliance => "style=solid, color=green, label=\"alliance\"",
+                Relation::Rivalry => "style=dashed, color=red, label=\"rivalry\"",
+            };
+            let _ = writeln!(
+                dot,
+                "    \"{}\" -- \"{}\" [{}];",
+                escape_dot(a),
+                escape_dot(b),
+                style
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn ordered_pair(id1: &str, id2: &str) -> (String, String) {
+    if id1 <= id2 {
+        (id1.to_string(), id2.to_string())
+    } else {
+        (id2.to_string(), id1.to_string())
+    }
+}
+
+pub(crate) fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/** One line per clan, in sorted order, listing its members. */
+impl fmt::Display for ClanSystem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (k, id) in self.clan_ids().enumerate() {
+            if k > 0 {
+                f.write_char('\n')?;
+            }
+            let members = self.get_clan_member_names(id).join(", ");
+            let count = self.get_clan_member_count(id);
+            f.write_str(&tr(Message::ClanSummary, &[&id, &count, &members]))?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for ClanSystem {
+    fn default() -> Self {
+        ClanSystem::new()
+    }
+}
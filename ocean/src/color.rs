@@ -1,12 +1,49 @@
-#[derive(Eq, PartialEq, Debug)]
+use rand::Rng;
+use std::cmp::Ordering;
+use std::fmt;
+
+#[derive(Eq, PartialEq, Debug, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Color {
     pub r: u8,
     pub g: u8,
     pub b: u8,
 }
 
+/**
+ * The named colors crabs commonly come in, for finding colors by name and
+ * naming colors.
+ */
+pub const PALETTE: &[(&str, Color)] = &[
+    ("red", Color::new(255, 0, 0)),
+    ("green", Color::new(0, 255, 0)),
+    ("blue", Color::new(0, 0, 255)),
+    ("orange", Color::new(255, 140, 0)),
+    ("purple", Color::new(128, 0, 128)),
+    ("brown", Color::new(139, 69, 19)),
+    ("sand", Color::new(194, 178, 128)),
+    ("white", Color::new(255, 255, 255)),
+    ("black", Color::new(0, 0, 0)),
+];
+
+/** How `Color::blend` mixes two colors. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    /** Each component is the sum of the two, modulo 256, as `Color::cross` does. */
+    Wrapping,
+    /** Each component is the sum of the two, up to 255, as mixing light does. */
+    Additive,
+    /** Each component is the mean of the two. */
+    Average,
+    /**
+     * The hue, saturation and lightness are each halfway between the two's,
+     * the hue going the shorter way round the color wheel.
+     */
+    Hsl,
+}
+
 impl Color {
-    pub fn new(r: u8, g: u8, b: u8) -> Color {
+    pub const fn new(r: u8, g: u8, b: u8) -> Color {
         Color { r, g, b }
     }
 
@@ -22,6 +59,156 @@ impl Color {
         Color::new(0, 0, 255)
     }
 
+    /**
+     * Returns the hue of this color in degrees, from 0 (red) through 120
+     * (green) and 240 (blue) up to 360. Grays have a hue of 0.
+     */
+    pub fn hue(&self) -> f64 {
+        let (r, g, b) = (self.r as f64, self.g as f64, self.b as f64);
+        let max = r.max(g).max(b);
+        let delta = max - r.min(g).min(b);
+        if delta == 0.0 {
+            return 0.0;
+        }
+        let hue = if max == r {
+            60.0 * ((g - b) / delta)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        if hue < 0.0 {
+            hue + 360.0
+        } else {
+            hue
+        }
+    }
+
+    /**
+     * Returns how far apart two colors are, from 0 for the same color up to 1
+     * for black and white: the straight-line distance between them in RGB
+     * space, scaled by the longest possible one.
+     */
+    pub fn distance(&self, other: &Color) -> f64 {
+        let channel = |a: u8, b: u8| (a as f64 - b as f64).powi(2);
+        let squared =
+            channel(self.r, other.r) + channel(self.g, other.g) + channel(self.b, other.b);
+        (squared / (3.0 * 255.0 * 255.0)).sqrt()
+    }
+
+    /**
+     * Returns how far apart two colors look, from 0 for the same color up to
+     * 1 for black and white. Unlike `distance`, differences in green count
+     * for more than differences in red and blue, and differences in red count
+     * for more among reddish colors, as they do to the eye.
+     */
+    pub fn perceptual_distance(&self, other: &Color) -> f64 {
+        let mean_r = (self.r as f64 + other.r as f64) / 2.0;
+        let delta = |a: u8, b: u8| (a as f64 - b as f64).powi(2);
+        let squared = (2.0 + mean_r / 256.0) * delta(self.r, other.r)
+            + 4.0 * delta(self.g, other.g)
+            + (2.0 + (255.0 - mean_r) / 256.0) * delta(self.b, other.b);
+        let longest = (8.0 + 255.0 / 256.0) * 255.0 * 255.0;
+        (squared / longest).sqrt().min(1.0)
+    }
+
+    /**
+     * Returns the color in hex notation, such as `#ff8000`, as it is
+     * displayed.
+     */
+    pub fn to_hex(&self) -> String {
+        self.to_string()
+    }
+
+    /**
+     * Parses a color in hex notation, with or without the leading `#`, or
+     * returns None if it is not one.
+     */
+    pub fn from_hex(text: &str) -> Option<Color> {
+        let hex = text.strip_prefix('#').unwrap_or(text);
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |k: usize| u8::from_str_radix(&hex[k..k + 2], 16).ok();
+        Some(Color::new(channel(0)?, channel(2)?, channel(4)?))
+    }
+
+    /** Returns the color in the palette with the given name, if there is one. */
+    pub fn named(name: &str) -> Option<Color> {
+        PALETTE
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, color)| color.clone())
+    }
+
+    /**
+     * Returns the name of the palette color closest to this one, by
+     * `perceptual_distance`.
+     */
+    pub fn nearest_name(&self) -> &'static str {
+        PALETTE
+            .iter()
+            .min_by(|(_, a), (_, b)| {
+                self.perceptual_distance(a)
+                    .total_cmp(&self.perceptual_distance(b))
+            })
+            .map(|&(name, _)| name)
+            .expect("the palette is not empty")
+    }
+
+    /** Returns the hue in degrees, and the saturation and lightness from 0 to 1. */
+    fn to_hsl(&self) -> (f64, f64, f64) {
+        let (r, g, b) = (
+            self.r as f64 / 255.0,
+            self.g as f64 / 255.0,
+            self.b as f64 / 255.0,
+        );
+        let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+        let lightness = (max + min) / 2.0;
+        let saturation = if max == min {
+            0.0
+        } else {
+            (max - min) / (1.0 - (2.0 * lightness - 1.0).abs())
+        };
+        (self.hue(), saturation, lightness)
+    }
+
+    fn from_hsl(hue: f64, saturation: f64, lightness: f64) -> Color {
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        let sector = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+        let (r, g, b) = match sector as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = lightness - chroma / 2.0;
+        let channel = |c: f64| ((c + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+        Color::new(channel(r), channel(g), channel(b))
+    }
+
+    /** Returns the mix of two colors by the given blend mode. */
+    pub fn blend(&self, other: &Color, mode: BlendMode) -> Color {
+        let mix = |f: fn(u8, u8) -> u8| {
+            Color::new(f(self.r, other.r), f(self.g, other.g), f(self.b, other.b))
+        };
+        match mode {
+            BlendMode::Wrapping => mix(u8::wrapping_add),
+            BlendMode::Additive => mix(u8::saturating_add),
+            BlendMode::Average => mix(|a, b| ((a as u16 + b as u16) / 2) as u8),
+            BlendMode::Hsl => {
+                let (h1, s1, l1) = self.to_hsl();
+                let (h2, s2, l2) = other.to_hsl();
+                // Go the shorter way round, e.g. from 350 through 0 to 10.
+                let turn = (h2 - h1 + 540.0).rem_euclid(360.0) - 180.0;
+                Color::from_hsl(h1 + turn / 2.0, (s1 + s2) / 2.0, (l1 + l2) / 2.0)
+            }
+        }
+    }
+
     /**
      * Returns a new `Color` whose components are the sum of `c1` and `c2`'s components, modulo 256.
      *
@@ -34,6 +221,83 @@ impl Color {
      * https://doc.rust-lang.org/std/primitive.u8.html
      */
     pub fn cross(c1: &Color, c2: &Color) -> Color {
-        unimplemented!();
+        c1.blend(c2, BlendMode::Wrapping)
+    }
+
+    /**
+     * Returns the genotype of a child of parents with the given genotypes,
+     * which takes one of the two alleles of each parent at random.
+     */
+    pub fn cross_genotypes<R: Rng + ?Sized>(g1: &Genotype, g2: &Genotype, rng: &mut R) -> Genotype {
+        let a = g1.alleles[rng.gen_range(0..2)].clone();
+        let b = g2.alleles[rng.gen_range(0..2)].clone();
+        Genotype::new(a, b)
+    }
+}
+
+/** Shows a color in hex notation, such as `#ff8000`. */
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+/** Dominance of the alleles of crabs that were not given a genotype. */
+pub const DEFAULT_DOMINANCE: u8 = 1;
+
+/**
+ * A variant of the color gene. When a crab carries two different alleles,
+ * the more dominant one decides its color; equally dominant alleles blend.
+ */
+#[derive(Eq, PartialEq, Debug, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Allele {
+    pub color: Color,
+    pub dominance: u8,
+}
+
+impl Allele {
+    pub fn new(color: Color, dominance: u8) -> Allele {
+        Allele { color, dominance }
+    }
+}
+
+/** The two color alleles a crab carries, one from each parent. */
+#[derive(Eq, PartialEq, Debug, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Genotype {
+    pub alleles: [Allele; 2],
+}
+
+impl Genotype {
+    pub fn new(a: Allele, b: Allele) -> Genotype {
+        Genotype { alleles: [a, b] }
+    }
+
+    /** Returns a genotype of two copies of an allele of the given color. */
+    pub fn homozygous(color: Color) -> Genotype {
+        let allele = Allele::new(color, DEFAULT_DOMINANCE);
+        Genotype::new(allele.clone(), allele)
+    }
+
+    /**
+     * Returns the color a crab with this genotype has: the color of the more
+     * dominant allele, or the mean of the two colors if they are equally
+     * dominant.
+     */
+    pub fn phenotype(&self) -> Color {
+        let [a, b] = &self.alleles;
+        match a.dominance.cmp(&b.dominance) {
+            Ordering::Greater => a.color.clone(),
+            Ordering::Less => b.color.clone(),
+            Ordering::Equal => {
+                let mean = |x: u8, y: u8| ((x as u16 + y as u16) / 2) as u8;
+                Color::new(
+                    mean(a.color.r, b.color.r),
+                    mean(a.color.g, b.color.g),
+                    mean(a.color.b, b.color.b),
+                )
+            }
+        }
     }
 }
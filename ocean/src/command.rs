@@ -0,0 +1,205 @@
+use crate::beach::Beach;
+use crate::color::Color;
+use crate::crab::Crab;
+use crate::history::BeachSnapshot;
+use crate::simulation::Operation;
+
+/**
+ * A change to a beach that can be applied and undone. Applying a change
+ * returns the change that reverses it.
+ */
+#[derive(Debug)]
+enum Change {
+    /** Re-runs an operation; used the first time a command is executed. */
+    Run(Operation),
+    /** Removes this many crabs from the end of the beach. */
+    RemoveCrabs(usize),
+    /** Adds these crabs back to the end of the beach, in order. */
+    AddCrabs(Vec<Crab>),
+    JoinClan {
+        clan_id: String,
+        crab_name: String,
+    },
+    LeaveClan {
+        clan_id: String,
+        crab_name: String,
+    },
+    /** Sets the health of the crabs at these indices. */
+    SetHealth(Vec<(usize, u32)>),
+    /** Ticks the beach; used the first time a tick is executed. */
+    Tick,
+    /** Puts back the beach as it was at the given tick. */
+    Rewind(Box<BeachSnapshot>, u64),
+}
+
+impl Change {
+    fn apply(self, beach: &mut Beach) -> Change {
+        match self {
+            Change::Run(operation) => run(operation, beach),
+            Change::RemoveCrabs(n) => {
+                let mut crabs: Vec<Crab> = (0..n).filter_map(|_| beach.pop_crab()).collect();
+                crabs.reverse();
+                Change::AddCrabs(crabs)
+            }
+            Change::AddCrabs(crabs) => {
+                let n = crabs.len();
+                for crab in crabs {
+                    beach.add_crab(crab);
+                }
+                Change::RemoveCrabs(n)
+            }
+            Change::JoinClan { clan_id, crab_name } => {
+                beach.rejoin_clan(&clan_id, &crab_name);
+                Change::LeaveClan { clan_id, crab_name }
+            }
+            Change::LeaveClan { clan_id, crab_name } => {
+                beach.remove_member_from_clan(&clan_id, &crab_name);
+                Change::JoinClan { clan_id, crab_name }
+            }
+            Change::SetHealth(healths) => {
+                let previous = healths
+                    .iter()
+                    .map(|&(index, health)| {
+                        let mut crab = beach.get_crab_mut(index);
+                        let old = crab.health();
+                        crab.injure(old);
+                        crab.heal(health);
+                        (index, old)
+                    })
+                    .collect();
+                Change::SetHealth(previous)
+            }
+            Change::Tick => {
+                let before = Change::Rewind(Box::new(beach.snapshot()), beach.ticks());
+                beach.tick();
+                before
+            }
+            Change::Rewind(snapshot, ticks) => {
+                let now = Change::Rewind(Box::new(beach.snapshot()), beach.ticks());
+                beach.restore_at(*snapshot, ticks);
+                now
+            }
+        }
+    }
+}
+
+/** Applies an operation to the beach and returns the change that undoes it. */
+fn run(operation: Operation, beach: &mut Beach) -> Change {
+    match operation {
+        Operation::AddCrab {
+            name,
+            speed,
+            color,
+            diet,
+        } => {
+            let (r, g, b) = color;
+            beach.add_crab(Crab::new(name, speed, Color::new(r, g, b), diet));
+            Change::RemoveCrabs(1)
+        }
+        Operation::BreedCrabs { i, j, name } => {
+            beach.breed_crabs_at(i, j, name);
+            Change::RemoveCrabs(1)
+        }
+        Operation::JoinClan { clan_id, crab_name } => {
+            beach.add_member_to_clan(&clan_id, &crab_name);
+            Change::LeaveClan { clan_id, crab_name }
+        }
+        Operation::BattleCrabs { i, j } => {
+            let before = vec![
+                (i, beach.crab_at(i).health()),
+                (j, beach.crab_at(j).health()),
+            ];
+            beach.battle_crabs(i, j);
+            Change::SetHealth(before)
+        }
+        Operation::PopulateRandom { n, seed } => {
+            beach.populate_random(n, seed);
+            Change::RemoveCrabs(n)
+        }
+        Operation::Tick => Change::Tick.apply(beach),
+    }
+}
+
+/**
+ * Applies operations to a beach as commands that can be undone and redone.
+ *
+ * Every executed command records the change that reverses it. Undoing a
+ * command restores the beach as it was, and redoing it restores the exact
+ * result of the first run: bred crabs come back with the same traits, which
+ * replaying the operation would not guarantee. Ticks are undone and redone
+ * by putting back a `BeachSnapshot` and the clock: crabs that died, aged or
+ * hatched during the tick come back as they were.
+ */
+#[derive(Debug)]
+pub struct Dispatcher {
+    beach: Beach,
+    done: Vec<(Operation, Change)>,
+    undone: Vec<(Operation, Change)>,
+}
+
+impl Dispatcher {
+    pub fn new(beach: Beach) -> Dispatcher {
+        Dispatcher {
+            beach,
+            done: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+
+    pub fn beach(&self) -> &Beach {
+        &self.beach
+    }
+
+    pub fn into_beach(self) -> Beach {
+        self.beach
+    }
+
+    /**
+     * Executes an operation, making it undoable and discarding any commands
+     * that were undone before it.
+     * Panics under the same conditions as the corresponding Beach method.
+     */
+    pub fn execute(&mut self, operation: Operation) {
+        let inverse = Change::Run(operation.clone()).apply(&mut self.beach);
+        self.done.push((operation, inverse));
+        self.undone.clear();
+    }
+
+    /**
+     * Undoes the most recent command, returning it, or None if there is
+     * nothing to undo.
+     */
+    pub fn undo(&mut self) -> Option<&Operation> {
+        let (operation, inverse) = self.done.pop()?;
+        let redo = inverse.apply(&mut self.beach);
+        self.undone.push((operation, redo));
+        self.undone.last().map(|(operation, _)| operation)
+    }
+
+    /**
+     * Redoes the most recently undone command, returning it, or None if there
+     * is nothing to redo.
+     */
+    pub fn redo(&mut self) -> Option<&Operation> {
+        let (operation, change) = self.undone.pop()?;
+        let inverse = change.apply(&mut self.beach);
+        self.done.push((operation, inverse));
+        self.done.last().map(|(operation, _)| operation)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /**
+     * The commands currently in effect, oldest first, for sending to another
+     * dispatcher or saving as a replay.
+     */
+    pub fn commands(&self) -> impl Iterator<Item = &Operation> {
+        self.done.iter().map(|(operation, _)| operation)
+    }
+}
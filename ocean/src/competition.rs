@@ -0,0 +1,41 @@
+/**
+ * How a competition between two clans that are as fast as each other is
+ * decided. A beach tries its tie-breakers in order until one decides.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreaker {
+    /** The clan with the fastest crab wins. */
+    MaxSpeed,
+    /** The clan with more crabs on the beach wins. */
+    MemberCount,
+    /** A coin flip drawn from the beach's random numbers decides; it always does. */
+    CoinFlip,
+}
+
+impl TieBreaker {
+    /** Falls back to the fastest crab, then the number of crabs, then a coin flip. */
+    pub const STANDARD: [TieBreaker; 3] = [
+        TieBreaker::MaxSpeed,
+        TieBreaker::MemberCount,
+        TieBreaker::CoinFlip,
+    ];
+}
+
+/** A competition between two clans recorded on a beach. */
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompetitionRecord {
+    /** The beach's tick when the competition was held. */
+    pub tick: u64,
+    pub clans: (String, String),
+    /** The winning clan, or None for a draw. */
+    pub winner: Option<String>,
+    /** The tie-breaker that decided the competition, if speed did not. */
+    pub tie_breaker: Option<TieBreaker>,
+}
+
+impl CompetitionRecord {
+    /** Returns whether the given clan took part. */
+    pub fn involves(&self, clan_id: &str) -> bool {
+        self.clans.0 == clan_id || self.clans.1 == clan_id
+    }
+}
@@ -0,0 +1,268 @@
+use crate::color::Color;
+use crate::crab::{Crab, MAX_HEALTH};
+use crate::diet::Diet;
+use crate::i18n::{tr, Message};
+use crate::store;
+use std::fmt;
+use std::ops::{Deref, DerefMut, RangeInclusive};
+
+/** A numeric trait of a crab that constraints can bound. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bounded {
+    Speed,
+    ClawSize,
+    Health,
+    /** The crab's `age`, which its constraints' `lifespan` caps. */
+    Age,
+}
+
+impl Bounded {
+    const ALL: [Bounded; 4] = [
+        Bounded::Speed,
+        Bounded::ClawSize,
+        Bounded::Health,
+        Bounded::Age,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Bounded::Speed => "speed",
+            Bounded::ClawSize => "claw_size",
+            Bounded::Health => "health",
+            Bounded::Age => "age",
+        }
+    }
+
+    fn of(self, crab: &Crab) -> u64 {
+        match self {
+            Bounded::Speed => crab.speed() as u64,
+            Bounded::ClawSize => crab.claw_size() as u64,
+            Bounded::Health => crab.health() as u64,
+            Bounded::Age => crab.age(),
+        }
+    }
+
+    fn set(self, crab: &mut Crab, value: u64) {
+        match self {
+            Bounded::Speed => crab.set_speed(value as u32),
+            Bounded::ClawSize => crab.set_claw_size(value as u32),
+            Bounded::Health => crab.set_health(value as u32),
+            Bounded::Age => crab.set_age(value),
+        }
+    }
+}
+
+/** A trait value outside the range its constraint allows. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstraintError {
+    pub name: String,
+    pub field: Bounded,
+    pub value: u64,
+    pub allowed: RangeInclusive<u64>,
+}
+
+impl fmt::Display for ConstraintError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = tr(
+            Message::ConstraintViolated,
+            &[
+                &self.field.name(),
+                &self.value,
+                self.allowed.start(),
+                self.allowed.end(),
+            ],
+        );
+        write!(f, "{}: {}", self.name, text)
+    }
+}
+
+/**
+ * The ranges a crab's traits must stay within. The defaults allow speeds
+ * from 1 to 100, claws from 1 to 10, any health up to `MAX_HEALTH` and any
+ * age. Colors need no constraint, since every channel value is valid.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraints {
+    pub speed: RangeInclusive<u32>,
+    pub claw_size: RangeInclusive<u32>,
+    pub health: RangeInclusive<u32>,
+    /** The oldest a crab may be, by `Crab::age`. */
+    pub lifespan: u64,
+}
+
+impl Constraints {
+    pub fn new() -> Constraints {
+        Constraints {
+            speed: 1..=100,
+            claw_size: 1..=10,
+            health: 0..=MAX_HEALTH,
+            lifespan: u64::MAX,
+        }
+    }
+
+    /** Constraints that accept every crab. */
+    pub fn none() -> Constraints {
+        Constraints {
+            speed: 0..=u32::MAX,
+            claw_size: 0..=u32::MAX,
+            health: 0..=u32::MAX,
+            lifespan: u64::MAX,
+        }
+    }
+
+    pub fn range(&self, field: Bounded) -> RangeInclusive<u64> {
+        let range = match field {
+            Bounded::Speed => &self.speed,
+            Bounded::ClawSize => &self.claw_size,
+            Bounded::Health => &self.health,
+            Bounded::Age => return 0..=self.lifespan,
+        };
+        *range.start() as u64..=*range.end() as u64
+    }
+
+    /** Checks every constrained trait of a crab, reporting the first violation. */
+    pub fn check(&self, crab: &Crab) -> Result<(), ConstraintError> {
+        for field in Bounded::ALL {
+            let value = field.of(crab);
+            let allowed = self.range(field);
+            if !allowed.contains(&value) {
+                return Err(ConstraintError {
+                    name: crab.name().to_string(),
+                    field,
+                    value,
+                    allowed,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Constraints {
+    fn default() -> Self {
+        Constraints::new()
+    }
+}
+
+impl Crab {
+    /** Creates a crab, failing if it breaks the given constraints. */
+    pub fn try_new(
+        name: String,
+        speed: u32,
+        color: Color,
+        diet: Diet,
+        constraints: &Constraints,
+    ) -> Result<Crab, ConstraintError> {
+        let crab = Crab::new(name, speed, color, diet);
+        constraints.check(&crab)?;
+        Ok(crab)
+    }
+
+    /**
+     * Sets this crab's speed, unless it breaks the given constraints; then
+     * the crab is left as it was.
+     */
+    pub fn try_set_speed(
+        &mut self,
+        speed: u32,
+        constraints: &Constraints,
+    ) -> Result<(), ConstraintError> {
+        let before = self.speed();
+        self.set_speed(speed);
+        constraints
+            .check(self)
+            .inspect_err(|_| self.set_speed(before))
+    }
+}
+
+/**
+ * A crab of a beach, borrowed to change in place; see `Beach::get_crab_mut`.
+ * Once it is dropped, the crab is checked against the beach's constraints.
+ * If it breaks them, its constrained traits are put back as they were and
+ * the drop panics.
+ */
+#[derive(Debug)]
+pub struct CrabMut<'a> {
+    crab: &'a mut Crab,
+    constraints: &'a Constraints,
+    before: [u64; 4],
+}
+
+impl<'a> CrabMut<'a> {
+    pub(crate) fn new(crab: &'a mut Crab, constraints: &'a Constraints) -> CrabMut<'a> {
+        let before = Bounded::ALL.map(|field| field.of(crab));
+        CrabMut {
+            crab,
+            constraints,
+            before,
+        }
+    }
+}
+
+impl Deref for CrabMut<'_> {
+    type Target = Crab;
+
+    fn deref(&self) -> &Crab {
+        self.crab
+    }
+}
+
+impl DerefMut for CrabMut<'_> {
+    fn deref_mut(&mut self) -> &mut Crab {
+        self.crab
+    }
+}
+
+impl Drop for CrabMut<'_> {
+    fn drop(&mut self) {
+        if let Err(error) = self.constraints.check(self.crab) {
+            for (field, value) in Bounded::ALL.into_iter().zip(self.before) {
+                field.set(self.crab, value);
+            }
+            if !std::thread::panicking() {
+                panic!("{}", error);
+            }
+        }
+    }
+}
+
+/**
+ * The crabs of a beach, in order, each borrowed like `CrabMut`; see
+ * `Beach::crabs_mut`.
+ */
+#[derive(Debug)]
+pub struct CheckedCrabsMut<'a> {
+    crabs: store::CrabsMut<'a>,
+    constraints: &'a Constraints,
+}
+
+impl<'a> CheckedCrabsMut<'a> {
+    pub(crate) fn new(
+        crabs: store::CrabsMut<'a>,
+        constraints: &'a Constraints,
+    ) -> CheckedCrabsMut<'a> {
+        CheckedCrabsMut { crabs, constraints }
+    }
+}
+
+impl<'a> Iterator for CheckedCrabsMut<'a> {
+    type Item = CrabMut<'a>;
+
+    fn next(&mut self) -> Option<CrabMut<'a>> {
+        let crab = self.crabs.next()?;
+        Some(CrabMut::new(crab, self.constraints))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.crabs.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for CheckedCrabsMut<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let crab = self.crabs.next_back()?;
+        Some(CrabMut::new(crab, self.constraints))
+    }
+}
+
+impl ExactSizeIterator for CheckedCrabsMut<'_> {}
@@ -15,11 +15,17 @@ impl Cookbook {
         }
     }
 
-    pub fn recipes(&self) -> std::slice::Iter<Recipe> {
+    pub fn recipes(&self) -> std::slice::Iter<'_, Recipe> {
         self.recipes.iter()
     }
 }
 
+impl Default for Cookbook {
+    fn default() -> Self {
+        Cookbook::new()
+    }
+}
+
 #[derive(Debug)]
 pub struct Recipe {
     name: String,
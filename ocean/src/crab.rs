@@ -1,36 +1,373 @@
-use crate::color::Color;
+use crate::beach::CrabId;
+use crate::builder::CrabBuilder;
+use crate::color::{Color, Genotype};
 use crate::cookbook::{Cookbook, Recipe};
 use crate::diet::Diet;
+use crate::i18n::{tr, Message};
+use crate::life::{
+    LifeStage, LifeState, LifecycleConfig, HUNGRY_AT, STARVATION_DAMAGE, STARVING_AT,
+};
+use crate::position::Position;
 use crate::prey::Prey;
 use crate::reef::Reef;
+use crate::rng::GlobalRng;
+use crate::speed::Speed;
+use rand::RngCore;
 use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::rc::Rc;
 
+/** Health of a crab that has never been injured. */
+pub const MAX_HEALTH: u32 = 100;
+
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Crab {
-    // TODO: Add fields here (some in part 1, some in part 2)
+    name: String,
+    speed: Speed,
+    color: Color,
+    // None for a crab that simply has its color, as if it carried two
+    // copies of it.
+    #[cfg_attr(feature = "serde", serde(default))]
+    genotype: Option<Genotype>,
+    diet: Diet,
+    claw_size: u32,
+    health: u32,
+    // Times the crab has advanced since it last ate.
+    #[cfg_attr(feature = "serde", serde(default))]
+    hunger: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    age: u64,
+    // Years the crab has grown older by its lifecycle; see `grow_older`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    years: u64,
+    position: Position,
+    generation: u32,
+    parents: Option<(CrabId, CrabId)>,
+    // The id of the crab on the beach it lives on; a beach issues it anew
+    // to every crab that arrives.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    id: Option<CrabId>,
+    // Reefs belong to the ocean, so a saved crab forgets them.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    reefs: Vec<Rc<RefCell<Reef>>>,
 }
 
 // Do NOT implement Copy for Crab.
 impl Crab {
     pub fn new(name: String, speed: u32, color: Color, diet: Diet) -> Crab {
-        unimplemented!();
+        Crab {
+            name,
+            speed: Speed::new(speed),
+            color,
+            genotype: None,
+            diet,
+            claw_size: 1,
+            health: MAX_HEALTH,
+            hunger: 0,
+            age: 0,
+            years: 0,
+            position: Position::default(),
+            generation: 0,
+            parents: None,
+            id: None,
+            reefs: Vec::new(),
+        }
+    }
+
+    /**
+     * Returns a copy of this crab for a beach snapshot. The copy shares the
+     * crab's reefs, which belong to the ocean.
+     */
+    pub(crate) fn duplicate(&self) -> Crab {
+        Crab {
+            name: self.name.clone(),
+            color: self.color.clone(),
+            genotype: self.genotype.clone(),
+            reefs: self.reefs.clone(),
+            ..*self
+        }
+    }
+
+    /** Returns a builder for a crab; see `CrabBuilder` for its defaults. */
+    pub fn builder() -> CrabBuilder {
+        CrabBuilder::new()
+    }
+
+    /**
+     * Returns a new crab that carries the given color alleles, whose color
+     * is the one they give it.
+     */
+    pub fn with_genotype(name: String, speed: u32, genotype: Genotype, diet: Diet) -> Crab {
+        let mut crab = Crab::new(name, speed, genotype.phenotype(), diet);
+        crab.genotype = Some(genotype);
+        crab
     }
 
     pub fn name(&self) -> &str {
-        unimplemented!();
+        &self.name
+    }
+
+    /**
+     * Crabs on a beach are found by name, so only `Beach::rename_crab` may
+     * change it.
+     */
+    pub(crate) fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    /** Bytes allocated for this crab's name. */
+    pub fn name_bytes(&self) -> usize {
+        self.name.capacity()
+    }
+
+    /** Bytes allocated for this crab's list of reefs, not counting the reefs. */
+    pub fn reef_list_bytes(&self) -> usize {
+        self.reefs.capacity() * std::mem::size_of::<Rc<RefCell<Reef>>>()
     }
 
     pub fn speed(&self) -> u32 {
-        unimplemented!();
+        self.speed.get()
+    }
+
+    /** Returns this crab's speed as a `Speed`, whose arithmetic cannot overflow. */
+    pub fn typed_speed(&self) -> Speed {
+        self.speed
+    }
+
+    /**
+     * Sets this crab's speed, whatever it is; `try_set_speed` checks it
+     * against constraints first.
+     */
+    pub fn set_speed(&mut self, speed: u32) {
+        self.speed = Speed::new(speed);
     }
 
     pub fn color(&self) -> &Color {
-        unimplemented!();
+        &self.color
+    }
+
+    /**
+     * Returns the color alleles this crab carries: two copies of its color,
+     * unless it was made with a genotype.
+     */
+    pub fn genotype(&self) -> Genotype {
+        self.genotype
+            .clone()
+            .unwrap_or_else(|| Genotype::homozygous(self.color.clone()))
     }
 
     pub fn diet(&self) -> Diet {
-        unimplemented!();
+        self.diet
+    }
+
+    pub fn claw_size(&self) -> u32 {
+        self.claw_size
+    }
+
+    pub fn set_claw_size(&mut self, claw_size: u32) {
+        self.claw_size = claw_size;
+    }
+
+    pub fn health(&self) -> u32 {
+        self.health
+    }
+
+    /**
+     * Reduces this crab's health by `damage`, stopping at zero.
+     */
+    pub fn injure(&mut self, damage: u32) {
+        self.health = self.health.saturating_sub(damage);
+    }
+
+    /**
+     * Restores up to `amount` health, never exceeding `MAX_HEALTH`.
+     */
+    pub fn heal(&mut self, amount: u32) {
+        self.health = self.health.saturating_add(amount).min(MAX_HEALTH);
+    }
+
+    /** Sets this crab's health, never exceeding `MAX_HEALTH`. */
+    pub fn set_health(&mut self, health: u32) {
+        self.health = health.min(MAX_HEALTH);
+    }
+
+    /** Returns how many times this crab has advanced since it last ate. */
+    pub fn hunger(&self) -> u32 {
+        self.hunger
+    }
+
+    pub fn set_hunger(&mut self, hunger: u32) {
+        self.hunger = hunger;
+    }
+
+    /** Returns how many times this crab has advanced since it was made. */
+    pub fn age(&self) -> u64 {
+        self.age
+    }
+
+    pub fn set_age(&mut self, age: u64) {
+        self.age = age;
+    }
+
+    /**
+     * Returns how many years this crab has grown older by `grow_older`,
+     * which is apart from its `age` in steps.
+     */
+    pub fn years(&self) -> u64 {
+        self.years
+    }
+
+    pub fn set_years(&mut self, years: u64) {
+        self.years = years;
+    }
+
+    /**
+     * Returns this crab's stage of life by its years and the given
+     * lifecycle; on a beach, see `Beach::life_stage`.
+     */
+    pub fn life_stage(&self, lifecycle: &LifecycleConfig) -> LifeStage {
+        lifecycle.stage(self.years)
+    }
+
+    /**
+     * Ages this crab by the given number of years, its speed changing each
+     * year as the lifecycle says. Dead crabs do not age.
+     */
+    pub fn grow_older(&mut self, years: u64, lifecycle: &LifecycleConfig) {
+        if self.is_dead() {
+            return;
+        }
+        for _ in 0..years {
+            self.speed = Speed::new(lifecycle.speed_after_year(self.speed.get(), self.years));
+            self.years += 1;
+        }
+    }
+
+    /** Returns how this crab is faring, from its health and hunger. */
+    pub fn state(&self) -> LifeState {
+        if self.health == 0 {
+            LifeState::Dead
+        } else if self.hunger >= STARVING_AT {
+            LifeState::Starving
+        } else if self.hunger >= HUNGRY_AT {
+            LifeState::Hungry
+        } else {
+            LifeState::Healthy
+        }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.state() == LifeState::Dead
+    }
+
+    /**
+     * Moves this crab one step through its life: it ages and grows hungrier,
+     * and if it is starving it loses `STARVATION_DAMAGE` health. Dead crabs
+     * do not change. Returns the crab's new state.
+     */
+    pub fn advance(&mut self) -> LifeState {
+        if self.is_dead() {
+            return LifeState::Dead;
+        }
+        self.age += 1;
+        self.hunger = self.hunger.saturating_add(1);
+        if self.state() == LifeState::Starving {
+            self.injure(STARVATION_DAMAGE);
+        }
+        self.state()
+    }
+
+    /** Feeds this crab, so that it is no longer hungry. Dead crabs cannot eat. */
+    pub fn feed(&mut self) {
+        if !self.is_dead() {
+            self.hunger = 0;
+        }
+    }
+
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: Position) {
+        self.position = position;
+    }
+
+    /**
+     * Returns how many generations separate this crab from the founders of
+     * its family: 0 for a founder, and one more than the later of its
+     * parents' generations for a crab that was bred.
+     */
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    pub fn set_generation(&mut self, generation: u32) {
+        self.generation = generation;
+    }
+
+    /**
+     * Returns the handles of the parents this crab was bred from on its
+     * beach, or None for a founder.
+     */
+    pub fn parents(&self) -> Option<(CrabId, CrabId)> {
+        self.parents
+    }
+
+    /** Returns the id of this crab on the beach it lives on, or None if on none. */
+    pub fn id(&self) -> Option<CrabId> {
+        self.id
+    }
+
+    pub(crate) fn set_id(&mut self, id: Option<CrabId>) {
+        self.id = id;
+    }
+
+    /**
+     * Returns whether the two crabs are alike in everything but their ids
+     * and reefs, as copies of one crab are.
+     */
+    pub fn is_identical(&self, other: &Crab) -> bool {
+        self.name == other.name
+            && self.speed == other.speed
+            && self.color == other.color
+            && self.genotype == other.genotype
+            && self.diet == other.diet
+            && self.claw_size == other.claw_size
+            && self.health == other.health
+            && self.hunger == other.hunger
+            && self.age == other.age
+            && self.years == other.years
+            && self.position == other.position
+            && self.generation == other.generation
+            && self.parents == other.parents
+    }
+
+    pub(crate) fn set_parents(&mut self, parents: (CrabId, CrabId)) {
+        self.parents = Some(parents);
+    }
+
+    /**
+     * Returns a new baby crab with the given name whose color is the cross of
+     * its parents' colors, whose diet is chosen at random, and whose speed is 1.
+     */
+    pub fn breed(parent1: &Crab, parent2: &Crab, name: String) -> Crab {
+        Crab::breed_from(parent1, parent2, name, &mut GlobalRng)
+    }
+
+    /** Breeds a baby crab like `breed`, choosing its diet by the given generator. */
+    pub fn breed_from<R: RngCore + ?Sized>(
+        parent1: &Crab,
+        parent2: &Crab,
+        name: String,
+        rng: &mut R,
+    ) -> Crab {
+        let color = Color::cross(parent1.color(), parent2.color());
+        let mut baby = Crab::new(name, 1, color, Diet::random_diet_from(rng));
+        baby.generation = parent1.generation.max(parent2.generation) + 1;
+        baby
     }
 
     // PART 2 BELOW
@@ -40,7 +377,7 @@ impl Crab {
      * Have this crab discover a new reef, adding it to its list of reefs.
      */
     pub fn discover_reef(&mut self, reef: Rc<RefCell<Reef>>) {
-        unimplemented!();
+        self.reefs.push(reef);
     }
 
     /**
@@ -53,14 +390,17 @@ impl Crab {
      * If all reefs are empty, or this crab has no reefs, return None.
      */
     fn catch_prey(&mut self) -> Option<(Box<dyn Prey>, usize)> {
-        unimplemented!();
+        self.reefs
+            .iter()
+            .enumerate()
+            .find_map(|(i, reef)| reef.borrow_mut().take_prey().map(|prey| (prey, i)))
     }
 
     /**
      * Releases the given prey back into the reef at the given index.
      */
     fn release_prey(&mut self, prey: Box<dyn Prey>, reef_index: usize) {
-        unimplemented!();
+        self.reefs[reef_index].borrow_mut().add_prey(prey);
     }
 
     /**
@@ -100,7 +440,33 @@ impl Crab {
      * Note: this pseudocode reads like a terrible poem.
      */
     pub fn hunt(&mut self) -> bool {
-        unimplemented!();
+        let mut escaped: Vec<(Box<dyn Prey>, usize)> = Vec::new();
+        let mut caught = false;
+
+        while let Some((mut prey, reef_index)) = self.catch_prey() {
+            if prey.try_escape(self) || prey.diet() != self.diet {
+                escaped.push((prey, reef_index));
+                continue;
+            }
+
+            caught = true;
+            break;
+        }
+
+        for (prey, reef_index) in escaped {
+            self.release_prey(prey, reef_index);
+        }
+        caught
+    }
+
+    /**
+     * Has this crab go hunting in the given reef only, instead of its own.
+     */
+    pub fn hunt_in(&mut self, reef: Rc<RefCell<Reef>>) -> bool {
+        let own = std::mem::replace(&mut self.reefs, vec![reef]);
+        let caught = self.hunt();
+        self.reefs = own;
+        caught
     }
 
     /**
@@ -111,7 +477,64 @@ impl Crab {
      * up to you to figure out which ones and where. Do not make any other changes
      * to the signature.
      */
-    pub fn choose_recipe(&self, cookbook: &Cookbook) -> Option<&Recipe> {
-        unimplemented!();
+    pub fn choose_recipe<'a>(&self, cookbook: &'a Cookbook) -> Option<&'a Recipe> {
+        cookbook.recipes().find(|recipe| recipe.diet() == self.diet)
+    }
+}
+
+/**
+ * Crabs are equal when they are the same crab: the one with the same id on
+ * a beach. Their other traits do not count, so a crab stays equal to
+ * itself as it grows or heals. Crabs on no beach have no id, and are told
+ * apart by speed and name. Ids are only meaningful on the beach that
+ * issued them, so only crabs of one beach should be compared.
+ */
+impl PartialEq for Crab {
+    fn eq(&self, other: &Crab) -> bool {
+        match (self.id, other.id) {
+            (Some(id), Some(other_id)) => id == other_id,
+            (None, None) => self.speed == other.speed && self.name == other.name,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Crab {}
+
+impl Hash for Crab {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+        if self.id.is_none() {
+            self.speed.hash(state);
+            self.name.hash(state);
+        }
+    }
+}
+
+/**
+ * Crabs are ordered by speed, slowest first, then by name, then by id,
+ * crabs on no beach first. Like any key, a crab in an ordered set must not
+ * change its speed or name.
+ */
+impl Ord for Crab {
+    fn cmp(&self, other: &Crab) -> Ordering {
+        (self.speed, &self.name, self.id).cmp(&(other.speed, &other.name, other.id))
+    }
+}
+
+impl PartialOrd for Crab {
+    fn partial_cmp(&self, other: &Crab) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/** A one-line description of the crab, in the current locale. */
+impl fmt::Display for Crab {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let diet = format!("{:?}", self.diet);
+        f.write_str(&tr(
+            Message::CrabSummary,
+            &[&self.name, &self.speed, &diet, &self.color],
+        ))
     }
 }
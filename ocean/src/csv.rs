@@ -0,0 +1,151 @@
+use crate::beach::Beach;
+use crate::color::Color;
+use crate::crab::Crab;
+use crate::diet::Diet;
+use crate::error::OceanError;
+use crate::i18n::{tr, Message};
+use std::io::{BufRead, BufReader, Read, Write};
+
+/** The columns of a crab population in CSV, in order. */
+pub const CSV_HEADER: [&str; 5] = ["name", "speed", "color", "diet", "clan"];
+
+/** A crab read from a CSV row, with the clan it belongs to, if any. */
+struct Row {
+    crab: Crab,
+    clan: Option<String>,
+}
+
+impl Beach {
+    /**
+     * Writes the crabs of this beach as CSV, one row per crab in the order
+     * they live on the beach, under a header row naming the columns of
+     * `CSV_HEADER`. Colors are written in hex notation, such as `#ff8000`,
+     * and crabs without a clan have an empty clan column.
+     */
+    pub fn export_csv<W: Write>(&self, mut w: W) -> Result<(), OceanError> {
+        writeln!(w, "{}", CSV_HEADER.join(",")).map_err(io_error)?;
+        let clans = self.get_clan_system();
+        for (i, crab) in self.crabs().enumerate() {
+            let fields = [
+                quote(crab.name()),
+                crab.speed().to_string(),
+                crab.color().to_string(),
+                format!("{:?}", crab.diet()),
+                quote(clans.get_clan_of(self.handle(i)).unwrap_or("")),
+            ];
+            writeln!(w, "{}", fields.join(",")).map_err(io_error)?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Adds the crabs of a CSV population written like `export_csv`, and
+     * makes them join their clans. Blank lines are skipped, and quoted
+     * fields may contain commas and doubled quotes, but not line breaks.
+     *
+     * Every row is checked before any crab is added, so on an error, which
+     * names the offending line (counting from 1), the beach is unchanged.
+     * Returns the number of crabs added.
+     */
+    pub fn import_csv<R: Read>(&mut self, r: R) -> Result<usize, OceanError> {
+        let mut rows = Vec::new();
+        let mut header = false;
+        for (k, line) in BufReader::new(r).lines().enumerate() {
+            let line = line.map_err(io_error)?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields = split(&line).map_err(|reason| csv_error(k + 1, reason))?;
+            if !header {
+                if fields.iter().map(|f| f.trim()).ne(CSV_HEADER) {
+                    let expected = CSV_HEADER.join(",");
+                    return Err(csv_error(k + 1, tr(Message::CsvBadHeader, &[&expected])));
+                }
+                header = true;
+                continue;
+            }
+            let row = parse_row(&fields).map_err(|reason| csv_error(k + 1, reason))?;
+            self.constraints()
+                .check(&row.crab)
+                .map_err(|error| csv_error(k + 1, error.to_string()))?;
+            rows.push(row);
+        }
+
+        let count = rows.len();
+        self.record(|beach| {
+            for row in rows {
+                let id = beach.add_crab_with_id(row.crab);
+                if let Some(clan) = row.clan {
+                    beach.add_member_to_clan_by_id(&clan, id);
+                }
+            }
+        });
+        Ok(count)
+    }
+}
+
+fn parse_row(fields: &[String]) -> Result<Row, String> {
+    let [name, speed, color, diet, clan] = fields else {
+        return Err(tr(
+            Message::CsvColumnCount,
+            &[&CSV_HEADER.len(), &fields.len()],
+        ));
+    };
+    let speed = speed
+        .trim()
+        .parse()
+        .map_err(|_| tr(Message::CsvBadNumber, &[speed]))?;
+    let color = Color::from_hex(color.trim()).ok_or_else(|| tr(Message::CsvBadColor, &[color]))?;
+    let diet = match diet.trim() {
+        "Fish" => Diet::Fish,
+        "Shellfish" => Diet::Shellfish,
+        "Plants" => Diet::Plants,
+        _ => return Err(tr(Message::CsvBadDiet, &[diet])),
+    };
+    Ok(Row {
+        crab: Crab::new(name.clone(), speed, color, diet),
+        clan: Some(clan.clone()).filter(|clan| !clan.is_empty()),
+    })
+}
+
+/** Splits a CSV line into its fields, unquoting quoted ones. */
+fn split(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(tr(Message::CsvUnterminatedQuote, &[]));
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+/** Quotes a field if it holds a comma, a quote or a line break. */
+pub(crate) fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn csv_error(line: usize, reason: String) -> OceanError {
+    OceanError::Csv { line, reason }
+}
+
+pub(crate) fn io_error(error: std::io::Error) -> OceanError {
+    OceanError::Io(error.to_string())
+}
@@ -1,19 +1,70 @@
-use crate::rand;
+use crate::rng::GlobalRng;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::{Rng, RngCore};
 
 /// A crab's diet: fish, shellfish, or plants (seaweed, algae, etc).
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Diet {
     Fish,
     Shellfish,
     Plants,
 }
 
+/** What a diet's meals are worth, and how hard they are to come by. */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Nutrition {
+    /** The health a crab regains from one meal. */
+    pub energy: u32,
+    /** How scarce the food is, from 0 for food found everywhere toward 1. */
+    pub rarity: f64,
+}
+
 impl Diet {
+    pub fn all() -> &'static [Diet] {
+        &[Diet::Fish, Diet::Shellfish, Diet::Plants]
+    }
+
+    /**
+     * Returns what the diet's meals are worth. Shellfish are the richest
+     * and scarcest meals, plants the poorest and most plentiful.
+     */
+    pub fn nutrition(&self) -> Nutrition {
+        match self {
+            Diet::Fish => Nutrition {
+                energy: 10,
+                rarity: 0.3,
+            },
+            Diet::Shellfish => Nutrition {
+                energy: 14,
+                rarity: 0.6,
+            },
+            Diet::Plants => Nutrition {
+                energy: 7,
+                rarity: 0.1,
+            },
+        }
+    }
+
+    /** Returns the health a crab with this diet regains from one meal. */
+    pub fn energy(&self) -> u32 {
+        self.nutrition().energy
+    }
+
+    pub fn rarity(&self) -> f64 {
+        self.nutrition().rarity
+    }
+
     pub fn random_diet() -> Diet {
+        Diet::random_diet_from(&mut GlobalRng)
+    }
+
+    /** Returns a diet chosen at random by the given generator. */
+    pub fn random_diet_from<R: RngCore + ?Sized>(rng: &mut R) -> Diet {
         // This brings the names in Diet into scope, so we can write
         // `Fish` rather than `Diet::Fish` (and so on) below.
         use Diet::*;
-        let r: u32 = rand::rand32() % 3;
+        let r: u32 = rng.next_u32() % 3;
         match r {
             0 => Fish,
             1 => Shellfish,
@@ -21,4 +72,26 @@ impl Diet {
             _ => unreachable!(),
         }
     }
+
+    /**
+     * Returns a diet chosen at random by the given generator, each in
+     * proportion to its weight; diets left out have no chance. Returns None
+     * if no weight is positive, or any is negative or not a number.
+     */
+    pub fn random_diet_with<R: Rng + ?Sized>(rng: &mut R, weights: &[(Diet, f64)]) -> Option<Diet> {
+        let index = WeightedIndex::new(weights.iter().map(|&(_, weight)| weight)).ok()?;
+        Some(weights[index.sample(rng)].0)
+    }
+
+    /// Returns whether a crab with this diet can eat a crab with the `other`
+    /// diet. Crabs are shellfish, so shellfish eaters can eat any of them; fish
+    /// eaters can only overpower crabs that live on plants, and plant eaters
+    /// eat no crabs at all.
+    pub fn can_eat(&self, other: &Diet) -> bool {
+        match self {
+            Diet::Shellfish => true,
+            Diet::Fish => *other == Diet::Plants,
+            Diet::Plants => false,
+        }
+    }
 }
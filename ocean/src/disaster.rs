@@ -0,0 +1,64 @@
+use crate::position::Position;
+use std::collections::BTreeMap;
+
+/**
+ * A catastrophe that strikes a beach all at once; see
+ * `Beach::apply_disaster`. Crabs that are protected, by themselves or
+ * through their clans, come through unharmed.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Disaster {
+    /**
+     * Toxic algae kill the slowest `percent` of the beach's crabs, rounded
+     * down; of crabs equally slow, those that arrived first die first.
+     */
+    RedTide { percent: u32 },
+    /** The heat costs every clan `percent` of its treasury, rounded down. */
+    HeatWave { percent: u32 },
+    /**
+     * The sea sweeps over the beach, throwing every crab up to `reach`
+     * positions along either axis at random, kept on the territory.
+     */
+    StormSurge { reach: u32 },
+}
+
+/** A crab a disaster moved, with where it stood before and after. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Displacement {
+    pub name: String,
+    pub from: Position,
+    pub to: Position,
+}
+
+/** What a disaster did to a beach. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisasterReport {
+    pub tick: u64,
+    pub disaster: Disaster,
+    /** The names of the crabs killed, in the order they lived on the beach. */
+    pub killed: Vec<String>,
+    /** The crabs moved, in the order they live on the beach. */
+    pub displaced: Vec<Displacement>,
+    /** The shells each clan lost, by clan id, for clans that lost any. */
+    pub treasury_losses: BTreeMap<String, u64>,
+}
+
+impl DisasterReport {
+    pub fn new(tick: u64, disaster: Disaster) -> DisasterReport {
+        DisasterReport {
+            tick,
+            disaster,
+            killed: Vec::new(),
+            displaced: Vec::new(),
+            treasury_losses: BTreeMap::new(),
+        }
+    }
+
+    /** Returns the names of every crab the disaster killed or moved. */
+    pub fn affected(&self) -> Vec<&str> {
+        let killed = self.killed.iter().map(String::as_str);
+        killed
+            .chain(self.displaced.iter().map(|moved| moved.name.as_str()))
+            .collect()
+    }
+}
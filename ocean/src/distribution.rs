@@ -0,0 +1,162 @@
+use crate::constraints::{Bounded, Constraints};
+use crate::i18n::{tr, Message};
+use rand::Rng;
+use rand_distr::{Distribution, LogNormal, Normal, Poisson};
+use std::ops::RangeInclusive;
+
+/**
+ * How a numeric trait of randomly generated crabs is distributed. Draws are
+ * rounded to the nearest whole number and clamped to the trait's range.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraitDistribution {
+    /** Always the same value. */
+    Constant(u32),
+    /** Any whole number from `min` to `max`, equally likely. */
+    Uniform {
+        min: u32,
+        max: u32,
+    },
+    Normal {
+        mean: f64,
+        std_dev: f64,
+    },
+    /** The exponential of a normal distribution with the given parameters. */
+    LogNormal {
+        mu: f64,
+        sigma: f64,
+    },
+    Poisson {
+        lambda: f64,
+    },
+}
+
+impl TraitDistribution {
+    /** Checks the distribution's parameters. */
+    pub fn validate(&self) -> Result<(), String> {
+        let invalid = |e: &dyn std::fmt::Display| tr(Message::InvalidDistribution, &[e]);
+        match *self {
+            TraitDistribution::Constant(_) => Ok(()),
+            TraitDistribution::Uniform { min, max } => {
+                if min <= max {
+                    Ok(())
+                } else {
+                    Err(invalid(&format!("{} > {}", min, max)))
+                }
+            }
+            TraitDistribution::Normal { mean, std_dev } => {
+                check_finite(mean)?;
+                check_spread(std_dev)?;
+                Normal::new(mean, std_dev)
+                    .map(|_| ())
+                    .map_err(|e| invalid(&e))
+            }
+            TraitDistribution::LogNormal { mu, sigma } => {
+                check_finite(mu)?;
+                check_spread(sigma)?;
+                LogNormal::new(mu, sigma)
+                    .map(|_| ())
+                    .map_err(|e| invalid(&e))
+            }
+            TraitDistribution::Poisson { lambda } => {
+                check_finite(lambda)?;
+                Poisson::new(lambda).map(|_| ()).map_err(|e| invalid(&e))
+            }
+        }
+    }
+
+    /**
+     * Draws a value within `range`. If the distribution's parameters are
+     * invalid, the method should panic.
+     */
+    pub fn sample<R: Rng>(&self, rng: &mut R, range: &RangeInclusive<u32>) -> u32 {
+        let drawn = match *self {
+            TraitDistribution::Constant(value) => return value.clamp(*range.start(), *range.end()),
+            TraitDistribution::Uniform { min, max } => {
+                return rng.gen_range(min..=max).clamp(*range.start(), *range.end())
+            }
+            TraitDistribution::Normal { mean, std_dev } => {
+                Normal::new(mean, std_dev).unwrap().sample(rng)
+            }
+            TraitDistribution::LogNormal { mu, sigma } => {
+                LogNormal::new(mu, sigma).unwrap().sample(rng)
+            }
+            TraitDistribution::Poisson { lambda } => Poisson::new(lambda).unwrap().sample(rng),
+        };
+        drawn
+            .round()
+            .clamp(*range.start() as f64, *range.end() as f64) as u32
+    }
+}
+
+fn check_finite(parameter: f64) -> Result<(), String> {
+    if parameter.is_finite() {
+        Ok(())
+    } else {
+        Err(tr(
+            Message::InvalidDistribution,
+            &[&format!("parameter {} is not finite", parameter)],
+        ))
+    }
+}
+
+fn check_spread(spread: f64) -> Result<(), String> {
+    if spread >= 0.0 && spread.is_finite() {
+        Ok(())
+    } else {
+        Err(tr(
+            Message::InvalidDistribution,
+            &[&format!("spread {} is negative or not finite", spread)],
+        ))
+    }
+}
+
+/**
+ * How the traits of randomly generated crabs are distributed. By default,
+ * speeds are uniform from 1 to 50 and claws have size 1, as for
+ * `Beach::populate_random`.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraitDistributions {
+    pub speed: TraitDistribution,
+    pub claw_size: TraitDistribution,
+    /** The ranges draws are clamped to. */
+    pub limits: Constraints,
+}
+
+impl TraitDistributions {
+    pub fn new() -> TraitDistributions {
+        TraitDistributions {
+            speed: TraitDistribution::Uniform { min: 1, max: 50 },
+            claw_size: TraitDistribution::Constant(1),
+            limits: Constraints::new(),
+        }
+    }
+
+    /** Checks the parameters of every distribution, and that no limit is empty. */
+    pub fn validate(&self) -> Result<(), String> {
+        self.speed.validate()?;
+        self.claw_size.validate()?;
+        for field in [Bounded::Speed, Bounded::ClawSize, Bounded::Health] {
+            let range = self.limits.range(field);
+            if range.is_empty() {
+                return Err(tr(
+                    Message::InvalidDistribution,
+                    &[&format!(
+                        "{} limits {} > {}",
+                        field.name(),
+                        range.start(),
+                        range.end()
+                    )],
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for TraitDistributions {
+    fn default() -> Self {
+        TraitDistributions::new()
+    }
+}
@@ -0,0 +1,482 @@
+use crate::error::OceanError;
+use crate::i18n::{tr, Message};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/** Shells awarded to a crab that catches prey while foraging. */
+pub const FORAGING_REWARD: u64 = 2;
+
+/** Something that can hold shells and items: a crab (by name) or a clan (by id). */
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Account {
+    Crab(String),
+    Clan(String),
+}
+
+impl Account {
+    pub fn crab(name: &str) -> Account {
+        Account::Crab(name.to_string())
+    }
+
+    pub fn clan(id: &str) -> Account {
+        Account::Clan(id.to_string())
+    }
+}
+
+impl fmt::Display for Account {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Account::Crab(name) => write!(f, "{}", tr(Message::CrabAccount, &[name])),
+            Account::Clan(id) => write!(f, "{}", tr(Message::ClanAccount, &[id])),
+        }
+    }
+}
+
+/** Where newly earned shells came from. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Earning {
+    Race,
+    Foraging,
+    /** A prize for a clan that won a competition. */
+    Competition,
+}
+
+/** What spent shells were spent on. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Spending {
+    /** Making a clan member faster. */
+    SpeedBoost,
+    /** Feeding hungry clan members. */
+    Meals,
+}
+
+/**
+ * How clans fill and spend their treasuries. By default clans earn nothing
+ * by themselves; boosts and meals cost shells either way.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreasuryRules {
+    /** Shells a clan earns whenever one of its members catches prey foraging. */
+    pub foraging_share: u64,
+    /** Shells a clan earns for every competition it wins. */
+    pub victory_prize: u64,
+    /** Shells a point of speed for a member costs. */
+    pub boost_price: u64,
+    /** Shells a meal for a hungry member costs. */
+    pub meal_price: u64,
+}
+
+impl Default for TreasuryRules {
+    fn default() -> Self {
+        TreasuryRules {
+            foraging_share: 0,
+            victory_prize: 0,
+            boost_price: 10,
+            meal_price: 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionKind {
+    /** New shells entered the economy. */
+    Earned {
+        account: Account,
+        amount: u64,
+        source: Earning,
+    },
+    /** Shells moved from one account to another. */
+    Transfer {
+        from: Account,
+        to: Account,
+        amount: u64,
+    },
+    /** Shells were spent, and left the economy. */
+    Spent {
+        account: Account,
+        amount: u64,
+        on: Spending,
+    },
+    /** Shells were lost to a disaster, and left the economy. */
+    Lost { account: Account, amount: u64 },
+    /** Shells left this economy for another beach's. */
+    Exported { account: Account, amount: u64 },
+    /** Shells arrived from another beach's economy. */
+    Imported { account: Account, amount: u64 },
+    /** A crab paid dues to its clan. */
+    Dues {
+        crab: String,
+        clan: String,
+        amount: u64,
+    },
+    /** New items were given to an account. */
+    ItemsGranted {
+        account: Account,
+        item: String,
+        quantity: u32,
+    },
+    /** The seller handed over items in exchange for the buyer's shells. */
+    Trade {
+        seller: Account,
+        buyer: Account,
+        item: String,
+        quantity: u32,
+        price: u64,
+    },
+}
+
+/** An entry of the transaction log. Ids increase in the order of the transactions. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+    pub id: u64,
+    pub kind: TransactionKind,
+}
+
+/**
+ * Shell balances and item inventories of crabs and clans, along with a log
+ * of every transaction that changed them.
+ *
+ * Failed operations leave balances, inventories and the log untouched.
+ */
+#[derive(Debug, Default)]
+pub struct Economy {
+    balances: BTreeMap<Account, u64>,
+    inventories: BTreeMap<Account, BTreeMap<String, u32>>,
+    log: Vec<Transaction>,
+}
+
+impl Economy {
+    pub fn new() -> Economy {
+        Economy {
+            balances: BTreeMap::new(),
+            inventories: BTreeMap::new(),
+            log: Vec::new(),
+        }
+    }
+
+    pub fn balance(&self, account: &Account) -> u64 {
+        self.balances.get(account).copied().unwrap_or(0)
+    }
+
+    /**
+     * Returns how many of the given item the account holds.
+     */
+    pub fn item_count(&self, account: &Account, item: &str) -> u32 {
+        self.inventories
+            .get(account)
+            .and_then(|items| items.get(item))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /**
+     * Returns the items held by the account, by name.
+     */
+    pub fn inventory(&self, account: &Account) -> Vec<(&str, u32)> {
+        self.inventories
+            .get(account)
+            .map(|items| items.iter().map(|(k, &v)| (k.as_str(), v)).collect())
+            .unwrap_or_default()
+    }
+
+    /**
+     * Returns the total number of shells held by all accounts, or
+     * `u64::MAX` if there are more than that.
+     */
+    pub fn total_shells(&self) -> u64 {
+        self.balances
+            .values()
+            .fold(0, |total, &balance| total.saturating_add(balance))
+    }
+
+    /** The full transaction log, oldest first. */
+    pub fn transactions(&self) -> &[Transaction] {
+        &self.log
+    }
+
+    /** The transactions that involve the given account, oldest first. */
+    pub fn transactions_for(&self, account: &Account) -> Vec<&Transaction> {
+        self.log
+            .iter()
+            .filter(|t| involves(&t.kind, account))
+            .collect()
+    }
+
+    fn record(&mut self, kind: TransactionKind) {
+        let id = self.log.len() as u64;
+        self.log.push(Transaction { id, kind });
+    }
+
+    fn withdraw(&mut self, account: &Account, amount: u64) -> Result<(), String> {
+        let balance = self.balance(account);
+        if balance < amount {
+            return Err(tr(
+                Message::InsufficientShells,
+                &[account, &balance, &amount],
+            ));
+        }
+        self.balances.insert(account.clone(), balance - amount);
+        Ok(())
+    }
+
+    fn deposit(&mut self, account: &Account, amount: u64) -> Result<(), String> {
+        let balance = self.balance(account);
+        let total = balance
+            .checked_add(amount)
+            .ok_or_else(|| tr(Message::TooManyShells, &[account, &balance, &amount]))?;
+        self.balances.insert(account.clone(), total);
+        Ok(())
+    }
+
+    // Withdraws from one account and deposits into the other, or neither.
+    fn move_shells(&mut self, from: &Account, to: &Account, amount: u64) -> Result<(), String> {
+        let balance = self.balance(from);
+        self.withdraw(from, amount)?;
+        self.deposit(to, amount).inspect_err(|_| {
+            self.balances.insert(from.clone(), balance);
+        })
+    }
+
+    /** Returns how many more shells the account can hold. */
+    pub fn room(&self, account: &Account) -> u64 {
+        u64::MAX - self.balance(account)
+    }
+
+    /** Returns an Err if the account cannot pay `amount` shells. */
+    pub(crate) fn check_afford(&self, account: &Account, amount: u64) -> Result<(), OceanError> {
+        let balance = self.balance(account);
+        if balance < amount {
+            return Err(OceanError::InsufficientShells {
+                account: account.clone(),
+                balance,
+                amount,
+            });
+        }
+        Ok(())
+    }
+
+    /** Returns an Err if the account cannot hold `amount` more shells. */
+    pub(crate) fn check_room(&self, account: &Account, amount: u64) -> Result<(), OceanError> {
+        if self.room(account) < amount {
+            return Err(OceanError::TooManyShells {
+                account: account.clone(),
+                balance: self.balance(account),
+                amount,
+            });
+        }
+        Ok(())
+    }
+
+    /**
+     * Credits newly earned shells to the account. Fails if the account
+     * cannot hold that many more.
+     */
+    pub fn earn(&mut self, account: Account, amount: u64, source: Earning) -> Result<(), String> {
+        self.deposit(&account, amount)?;
+        self.record(TransactionKind::Earned {
+            account,
+            amount,
+            source,
+        });
+        Ok(())
+    }
+
+    /**
+     * Pays out race prizes: the crab finishing `k`th receives `prizes[k]`.
+     * Finishers beyond the last prize receive nothing. Stops at the first
+     * crab that cannot hold its prize, having paid those before it.
+     */
+    pub fn pay_race_prizes(&mut self, finishers: &[&str], prizes: &[u64]) -> Result<(), String> {
+        for (name, &prize) in finishers.iter().zip(prizes) {
+            if prize > 0 {
+                self.earn(Account::crab(name), prize, Earning::Race)?;
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * Moves shells between two accounts. Fails if `from` cannot afford it,
+     * or `to` cannot hold that many more.
+     */
+    pub fn transfer(&mut self, from: &Account, to: &Account, amount: u64) -> Result<(), String> {
+        self.move_shells(from, to, amount)?;
+        self.record(TransactionKind::Transfer {
+            from: from.clone(),
+            to: to.clone(),
+            amount,
+        });
+        Ok(())
+    }
+
+    /**
+     * Moves every shell and item of one account to another, recording a
+     * transfer of the shells and a trade at no price for each item. Does
+     * nothing if the accounts are the same.
+     */
+    pub fn transfer_all(&mut self, from: &Account, to: &Account) {
+        if from == to {
+            return;
+        }
+        let shells = self.balance(from);
+        if shells > 0 {
+            self.transfer(from, to, shells)
+                .expect("an account can afford its own balance");
+        }
+        let items: Vec<(String, u32)> = self
+            .inventory(from)
+            .into_iter()
+            .map(|(item, quantity)| (item.to_string(), quantity))
+            .collect();
+        for (item, quantity) in items {
+            self.trade(from, to, &item, quantity, 0)
+                .expect("an account holds its own items");
+        }
+    }
+
+    /**
+     * Spends shells from the account, taking them out of the economy. Fails
+     * if the account cannot afford it.
+     */
+    pub fn spend(&mut self, account: &Account, amount: u64, on: Spending) -> Result<(), String> {
+        self.withdraw(account, amount)?;
+        self.record(TransactionKind::Spent {
+            account: account.clone(),
+            amount,
+            on,
+        });
+        Ok(())
+    }
+
+    /**
+     * Takes shells out of the account and the economy, as a disaster does.
+     * Fails if the account does not hold that many.
+     */
+    pub fn lose(&mut self, account: &Account, amount: u64) -> Result<(), String> {
+        self.withdraw(account, amount)?;
+        self.record(TransactionKind::Lost {
+            account: account.clone(),
+            amount,
+        });
+        Ok(())
+    }
+
+    /**
+     * Sends shells out of this economy, e.g. along a trade route to another
+     * beach. Fails if the account cannot afford it.
+     */
+    pub fn export(&mut self, account: &Account, amount: u64) -> Result<(), String> {
+        self.withdraw(account, amount)?;
+        self.record(TransactionKind::Exported {
+            account: account.clone(),
+            amount,
+        });
+        Ok(())
+    }
+
+    /**
+     * Receives shells sent from another beach's economy. Fails if the
+     * account cannot hold that many more.
+     */
+    pub fn import(&mut self, account: Account, amount: u64) -> Result<(), String> {
+        self.deposit(&account, amount)?;
+        self.record(TransactionKind::Imported { account, amount });
+        Ok(())
+    }
+
+    /**
+     * Has a crab pay dues to a clan. Fails if the crab cannot afford it, or
+     * the clan cannot hold that many more shells.
+     */
+    pub fn pay_dues(&mut self, crab_name: &str, clan_id: &str, amount: u64) -> Result<(), String> {
+        self.move_shells(&Account::crab(crab_name), &Account::clan(clan_id), amount)?;
+        self.record(TransactionKind::Dues {
+            crab: crab_name.to_string(),
+            clan: clan_id.to_string(),
+            amount,
+        });
+        Ok(())
+    }
+
+    /**
+     * Gives new items to the account.
+     */
+    pub fn grant_items(&mut self, account: Account, item: &str, quantity: u32) {
+        *self
+            .inventories
+            .entry(account.clone())
+            .or_default()
+            .entry(item.to_string())
+            .or_insert(0) += quantity;
+        self.record(TransactionKind::ItemsGranted {
+            account,
+            item: item.to_string(),
+            quantity,
+        });
+    }
+
+    /**
+     * Sells `quantity` of `item` from `seller` to `buyer` for `price` shells in total.
+     * Fails if the seller lacks the items, the buyer lacks the shells, the
+     * seller cannot hold the shells, or both are the same account.
+     */
+    pub fn trade(
+        &mut self,
+        seller: &Account,
+        buyer: &Account,
+        item: &str,
+        quantity: u32,
+        price: u64,
+    ) -> Result<(), String> {
+        if seller == buyer {
+            return Err(tr(Message::TradeWithSelf, &[seller]));
+        }
+        let held = self.item_count(seller, item);
+        if held < quantity {
+            return Err(tr(
+                Message::InsufficientItems,
+                &[seller, &held, &item, &quantity],
+            ));
+        }
+        self.move_shells(buyer, seller, price)?;
+
+        let seller_items = self.inventories.entry(seller.clone()).or_default();
+        if held == quantity {
+            seller_items.remove(item);
+        } else {
+            seller_items.insert(item.to_string(), held - quantity);
+        }
+        *self
+            .inventories
+            .entry(buyer.clone())
+            .or_default()
+            .entry(item.to_string())
+            .or_insert(0) += quantity;
+
+        self.record(TransactionKind::Trade {
+            seller: seller.clone(),
+            buyer: buyer.clone(),
+            item: item.to_string(),
+            quantity,
+            price,
+        });
+        Ok(())
+    }
+}
+
+fn involves(kind: &TransactionKind, account: &Account) -> bool {
+    match kind {
+        TransactionKind::Earned { account: a, .. }
+        | TransactionKind::Spent { account: a, .. }
+        | TransactionKind::Lost { account: a, .. }
+        | TransactionKind::Exported { account: a, .. }
+        | TransactionKind::Imported { account: a, .. }
+        | TransactionKind::ItemsGranted { account: a, .. } => a == account,
+        TransactionKind::Transfer { from, to, .. } => from == account || to == account,
+        TransactionKind::Dues { crab, clan, .. } => match account {
+            Account::Crab(name) => name == crab,
+            Account::Clan(id) => id == clan,
+        },
+        TransactionKind::Trade { seller, buyer, .. } => seller == account || buyer == account,
+    }
+}
@@ -0,0 +1,20 @@
+/** What happened when two crabs on a beach met. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Encounter {
+    /** The `predator` caught and ate the `prey`, which left the beach. */
+    Eaten { predator: String, prey: String },
+    /** The `predator` went after the `prey`, which was too fast for it. */
+    Escaped { predator: String, prey: String },
+    /** Neither crab could eat the other, or the one that could was protected. */
+    Ignored,
+}
+
+impl Encounter {
+    /** Returns the name of the crab that was eaten, if one was. */
+    pub fn eaten(&self) -> Option<&str> {
+        match self {
+            Encounter::Eaten { prey, .. } => Some(prey),
+            Encounter::Escaped { .. } | Encounter::Ignored => None,
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use crate::diet::Diet;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+use std::collections::HashMap;
+use std::f64::consts::TAU;
+
+/**
+ * How a beach's temperature, in degrees Celsius, changes over time: it
+ * follows the seasons around `mean`, swinging by up to `seasonal_amplitude`
+ * over a year of `year_length` ticks, and the weather adds up to `weather`
+ * degrees either way on any tick.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Climate {
+    pub mean: f64,
+    pub seasonal_amplitude: f64,
+    pub year_length: u64,
+    pub weather: f64,
+    pub seed: u64,
+}
+
+impl Default for Climate {
+    fn default() -> Self {
+        Climate {
+            mean: 20.0,
+            seasonal_amplitude: 8.0,
+            year_length: 100,
+            weather: 2.0,
+            seed: 0,
+        }
+    }
+}
+
+/**
+ * The temperatures a species does best in: performance peaks at `optimum`
+ * and falls off the further the temperature strays, to about a third at
+ * `tolerance` degrees away.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThermalTolerance {
+    pub optimum: f64,
+    pub tolerance: f64,
+}
+
+impl ThermalTolerance {
+    /** Returns how well the species performs at a temperature, from 0 to 1. */
+    pub fn performance(&self, temperature: f64) -> f64 {
+        let off = (temperature - self.optimum) / self.tolerance.max(f64::EPSILON);
+        (-off * off).exp()
+    }
+
+    /** The tolerance of crabs with the given diet unless set otherwise. */
+    pub fn default_for(diet: Diet) -> ThermalTolerance {
+        let (optimum, tolerance) = match diet {
+            Diet::Fish => (18.0, 8.0),
+            Diet::Shellfish => (22.0, 10.0),
+            Diet::Plants => (26.0, 7.0),
+        };
+        ThermalTolerance { optimum, tolerance }
+    }
+}
+
+/**
+ * The climate of a beach and the temperatures it has had. Crabs are told
+ * apart by diet, which stands in for their species.
+ */
+#[derive(Debug)]
+pub struct Environment {
+    climate: Climate,
+    rng: Pcg64,
+    tolerances: HashMap<Diet, ThermalTolerance>,
+    // The temperature on each tick, as (tick, degrees).
+    temperatures: Vec<(u64, f64)>,
+}
+
+impl Environment {
+    /** Starts an environment at tick `now`, recording its temperature. */
+    pub fn new(climate: Climate, now: u64) -> Environment {
+        let mut environment = Environment {
+            climate,
+            rng: Pcg64::seed_from_u64(climate.seed),
+            tolerances: HashMap::new(),
+            temperatures: Vec::new(),
+        };
+        environment.update(now);
+        environment
+    }
+
+    pub fn climate(&self) -> Climate {
+        self.climate
+    }
+
+    /** Returns the temperature now. */
+    pub fn temperature(&self) -> f64 {
+        self.temperatures
+            .last()
+            .map_or(self.climate.mean, |&(_, t)| t)
+    }
+
+    /** Returns the temperature on every tick so far, as (tick, degrees). */
+    pub fn temperatures(&self) -> &[(u64, f64)] {
+        &self.temperatures
+    }
+
+    /** Records the temperature at tick `now`, after the weather changes. */
+    pub fn update(&mut self, now: u64) {
+        let year = self.climate.year_length.max(1) as f64;
+        let season = (TAU * (now as f64) / year).sin() * self.climate.seasonal_amplitude;
+        let weather = if self.climate.weather > 0.0 {
+            self.rng
+                .gen_range(-self.climate.weather..=self.climate.weather)
+        } else {
+            0.0
+        };
+        self.temperatures
+            .push((now, self.climate.mean + season + weather));
+    }
+
+    pub fn tolerance(&self, diet: Diet) -> ThermalTolerance {
+        self.tolerances
+            .get(&diet)
+            .copied()
+            .unwrap_or_else(|| ThermalTolerance::default_for(diet))
+    }
+
+    pub fn set_tolerance(&mut self, diet: Diet, tolerance: ThermalTolerance) {
+        self.tolerances.insert(diet, tolerance);
+    }
+
+    /** Returns how well crabs with the given diet perform now, from 0 to 1. */
+    pub fn performance(&self, diet: Diet) -> f64 {
+        self.tolerance(diet).performance(self.temperature())
+    }
+
+    /**
+     * Decides at random whether a breeding succeeds now: it does with
+     * probability the product of both parents' performance.
+     */
+    pub fn breeding_succeeds(&mut self, a: Diet, b: Diet) -> bool {
+        let chance = (self.performance(a) * self.performance(b)).clamp(0.0, 1.0);
+        self.rng.gen_bool(chance)
+    }
+}
@@ -0,0 +1,176 @@
+use crate::beach::CrabId;
+use crate::clans::Rejection;
+use crate::constraints::ConstraintError;
+use crate::economy::Account;
+use crate::i18n::{tr, Message};
+use crate::position::Position;
+use std::fmt;
+
+/** Why an operation on a beach or its clans failed. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OceanError {
+    /** There is no crab at `index` on a beach of `size` crabs. */
+    InvalidIndex {
+        index: usize,
+        size: usize,
+    },
+    /** The beach has no crabs at all. */
+    EmptyBeach,
+    UnknownClan(String),
+    /** The crab with this id has left the beach, or never lived on it. */
+    UnknownCrabId(CrabId),
+    /** A clan was asked to compete against itself. */
+    ClanAgainstItself(String),
+    /** Two clans were asked to compete, but they are allies. */
+    SameAlliance(String, String),
+    /** A crab tried to join a clan while it already belongs to `clan`. */
+    DuplicateMembership {
+        crab: String,
+        clan: String,
+    },
+    /** The crab does not belong to the clan. */
+    NotClanMember {
+        crab: String,
+        clan: String,
+    },
+    /** The breeding rules or the temperature forbid the breeding, for the given reason. */
+    BreedingFailed(String),
+    /** Reading or writing failed, with the given message. */
+    Io(String),
+    /** A line of a CSV file could not be read, for the given reason. */
+    Csv {
+        line: usize,
+        reason: String,
+    },
+    /** A crab with this name already lives on a beach that keeps names unique. */
+    DuplicateName(String),
+    /** A crab was moved off the `width` by `height` territory. */
+    OutsideTerritory {
+        position: Position,
+        width: u32,
+        height: u32,
+    },
+    /** A crab was turned away from a beach that holds at most `capacity` crabs. */
+    BeachFull {
+        capacity: usize,
+    },
+    /** The ocean has no beach by this name. */
+    UnknownBeach(String),
+    /** The ocean already has a beach by this name. */
+    DuplicateBeach(String),
+    /** No crab or other inhabitant of the beach has this name. */
+    UnknownInhabitant(String),
+    /** Breeding within `clan` takes two of its crabs, but only `residents` live on the beach. */
+    ClanTooSmall {
+        clan: String,
+        residents: usize,
+    },
+    /** A scenario script could not be read, for the given reason. */
+    Script(String),
+    /** A command typed into a `Repl` could not be run, for the given reason. */
+    Command(String),
+    /** A crab was turned away from a clan by its constitution. */
+    Rejected(Rejection),
+    /** A crab's trait lies outside the range the beach's constraints allow. */
+    OutOfBounds(ConstraintError),
+    /** Another clan already has this id. */
+    DuplicateClan(String),
+    /** A clan was asked to merge with itself. */
+    MergeWithSelf(String),
+    /** Merging two clans would leave `size` members where at most `max_size` are allowed. */
+    MergeTooLarge {
+        clan1: String,
+        clan2: String,
+        size: usize,
+        max_size: usize,
+    },
+    /** The account holds `balance` shells, too few to pay `amount`. */
+    InsufficientShells {
+        account: Account,
+        balance: u64,
+        amount: u64,
+    },
+    /** The account holds `balance` shells, too many to take `amount` more. */
+    TooManyShells {
+        account: Account,
+        balance: u64,
+        amount: u64,
+    },
+    /** A clutch was to be laid without any eggs. */
+    ClutchEmpty,
+    /** Another clutch already lies at this position. */
+    ClutchPositionTaken(Position),
+    /** The position lies in a region closed off until tick `until`. */
+    RegionBlocked {
+        position: Position,
+        until: u64,
+    },
+}
+
+impl fmt::Display for OceanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = match self {
+            OceanError::InvalidIndex { index, size } => tr(Message::InvalidIndex, &[index, size]),
+            OceanError::EmptyBeach => tr(Message::EmptyBeach, &[]),
+            OceanError::UnknownClan(id) => tr(Message::NoSuchClan, &[id]),
+            OceanError::UnknownCrabId(id) => tr(Message::NoSuchCrabId, &[id]),
+            OceanError::ClanAgainstItself(id) => tr(Message::ClanAgainstItself, &[id]),
+            OceanError::SameAlliance(id1, id2) => tr(Message::SameAlliance, &[id1, id2]),
+            OceanError::DuplicateMembership { crab, clan } => {
+                tr(Message::DuplicateMembership, &[crab, clan])
+            }
+            OceanError::NotClanMember { crab, clan } => tr(Message::NotClanMember, &[crab, clan]),
+            OceanError::BreedingFailed(reason) => reason.clone(),
+            OceanError::Io(message) => message.clone(),
+            OceanError::Csv { line, reason } => tr(Message::CsvLine, &[line, reason]),
+            OceanError::DuplicateName(name) => tr(Message::ImportDuplicateName, &[name]),
+            OceanError::OutsideTerritory {
+                position,
+                width,
+                height,
+            } => tr(
+                Message::OutsideTerritory,
+                &[&position.x, &position.y, width, height],
+            ),
+            OceanError::BeachFull { capacity } => tr(Message::BeachFull, &[capacity]),
+            OceanError::UnknownBeach(name) => tr(Message::NoSuchBeach, &[name]),
+            OceanError::DuplicateBeach(name) => tr(Message::DuplicateBeach, &[name]),
+            OceanError::UnknownInhabitant(name) => tr(Message::NoSuchInhabitant, &[name]),
+            OceanError::ClanTooSmall { clan, residents } => {
+                tr(Message::ClanTooSmall, &[clan, residents])
+            }
+            OceanError::Script(reason) => tr(Message::ScriptInvalid, &[reason]),
+            OceanError::Command(reason) => reason.clone(),
+            OceanError::Rejected(rejection) => rejection.to_string(),
+            OceanError::OutOfBounds(error) => error.to_string(),
+            OceanError::DuplicateClan(id) => tr(Message::DuplicateClan, &[id]),
+            OceanError::MergeWithSelf(id) => tr(Message::MergeWithSelf, &[id]),
+            OceanError::MergeTooLarge {
+                clan1,
+                clan2,
+                size,
+                max_size,
+            } => tr(Message::MergeTooLarge, &[clan1, clan2, size, max_size]),
+            OceanError::InsufficientShells {
+                account,
+                balance,
+                amount,
+            } => tr(Message::InsufficientShells, &[account, balance, amount]),
+            OceanError::TooManyShells {
+                account,
+                balance,
+                amount,
+            } => tr(Message::TooManyShells, &[account, balance, amount]),
+            OceanError::ClutchEmpty => tr(Message::ClutchEmpty, &[]),
+            OceanError::ClutchPositionTaken(position) => {
+                tr(Message::ClutchPositionTaken, &[&position.x, &position.y])
+            }
+            OceanError::RegionBlocked { position, until } => {
+                tr(Message::RegionBlocked, &[&position.x, &position.y, until])
+            }
+        };
+        f.write_str(&text)
+    }
+}
+
+impl std::error::Error for OceanError {}
@@ -0,0 +1,157 @@
+use crate::life::Cause;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+/** Something that happened on a beach. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BeachEvent {
+    /** A crab started living on the beach. */
+    CrabAdded { name: String },
+    /** Two crabs on the beach produced a child, which was added to the beach. */
+    CrabBred {
+        parent1: String,
+        parent2: String,
+        child: String,
+    },
+    /**
+     * Two crabs bred although they are more closely related than the
+     * beach's breeding rules advise.
+     */
+    InbreedingWarning { parent1: String, parent2: String },
+    /** Seagulls carried off the `casualties`, leaving `survivors` crabs. */
+    SeagullRaid {
+        casualties: Vec<String>,
+        survivors: usize,
+    },
+    /**
+     * A crab left the beach, for whatever reason but seagulls or fishing,
+     * whose own events name the crabs taken. Crabs that die are announced
+     * by `CrabDied` as well.
+     */
+    CrabRemoved { name: String },
+    /** A crab died on the beach and was removed from it. */
+    CrabDied { name: String, cause: Cause },
+    /** Humans fished the named crabs off the beach. */
+    CrabsFished { names: Vec<String> },
+    /** A crab joined a clan, which now has `clan_size` members. */
+    ClanMemberAdded {
+        clan_id: String,
+        crab_name: String,
+        clan_size: usize,
+    },
+    /** The beach clock advanced; `tick` is the new time. */
+    Tick { tick: u64 },
+}
+
+/**
+ * Something that wants to be told about events on a beach.
+ */
+pub trait EventListener {
+    fn on_event(&mut self, event: &BeachEvent);
+
+    /**
+     * Returns whether the listener wants no more events, e.g. because what
+     * it fed is gone. The bus then drops it. A listener is never finished
+     * unless overridden.
+     */
+    fn is_finished(&self) -> bool {
+        false
+    }
+}
+
+/**
+ * Something that wants to react to changes to a beach's crabs and clans,
+ * with a callback for each kind of change. Every callback does nothing
+ * unless overridden.
+ */
+pub trait BeachObserver {
+    fn on_crab_added(&mut self, _name: &str) {}
+
+    /** Called after `child` was bred from `parent1` and `parent2` and added to the beach. */
+    fn on_crab_bred(&mut self, _parent1: &str, _parent2: &str, _child: &str) {}
+
+    fn on_clan_member_added(&mut self, _clan_id: &str, _crab_name: &str) {}
+
+    /** Called whenever a crab leaves the beach, for whatever reason. */
+    fn on_crab_removed(&mut self, _name: &str) {}
+}
+
+/** Passes the events a `BeachObserver` cares about on to its callbacks. */
+struct ObserverListener(Rc<RefCell<dyn BeachObserver>>);
+
+impl EventListener for ObserverListener {
+    fn on_event(&mut self, event: &BeachEvent) {
+        let mut observer = self.0.borrow_mut();
+        match event {
+            BeachEvent::CrabAdded { name } => observer.on_crab_added(name),
+            BeachEvent::CrabBred {
+                parent1,
+                parent2,
+                child,
+            } => observer.on_crab_bred(parent1, parent2, child),
+            BeachEvent::ClanMemberAdded {
+                clan_id, crab_name, ..
+            } => observer.on_clan_member_added(clan_id, crab_name),
+            BeachEvent::CrabRemoved { name } => observer.on_crab_removed(name),
+            BeachEvent::SeagullRaid {
+                casualties: names, ..
+            }
+            | BeachEvent::CrabsFished { names } => {
+                for name in names {
+                    observer.on_crab_removed(name);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/**
+ * Delivers events to every subscribed listener, in subscription order,
+ * dropping the listeners that are finished after each event.
+ *
+ * Listeners are shared with the subscriber through `Rc<RefCell<_>>`, so the
+ * subscriber can keep a reference and inspect the listener later.
+ */
+#[derive(Default)]
+pub struct EventBus {
+    listeners: Vec<Rc<RefCell<dyn EventListener>>>,
+}
+
+impl EventBus {
+    pub fn new() -> EventBus {
+        EventBus {
+            listeners: Vec::new(),
+        }
+    }
+
+    pub fn subscribe(&mut self, listener: Rc<RefCell<dyn EventListener>>) {
+        self.listeners.push(listener);
+    }
+
+    /** Subscribes an observer's callbacks to the events they are for. */
+    pub fn register(&mut self, observer: Rc<RefCell<dyn BeachObserver>>) {
+        self.subscribe(Rc::new(RefCell::new(ObserverListener(observer))));
+    }
+
+    pub fn listener_count(&self) -> usize {
+        self.listeners.len()
+    }
+
+    pub fn publish(&mut self, event: BeachEvent) {
+        for listener in &self.listeners {
+            listener.borrow_mut().on_event(&event);
+        }
+        self.listeners
+            .retain(|listener| !listener.borrow().is_finished());
+    }
+}
+
+impl fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EventBus")
+            .field("listeners", &self.listeners.len())
+            .finish()
+    }
+}
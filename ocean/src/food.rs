@@ -0,0 +1,94 @@
+use crate::diet::Diet;
+
+/**
+ * Health a crab regains from a typical meal. At a feeding or an encounter
+ * a crab regains its diet's `Diet::energy` instead.
+ */
+pub const MEAL_HEALTH: u32 = 10;
+
+/** Health a crab loses when it finds nothing to eat at a feeding. */
+pub const HUNGER_DAMAGE: u32 = 10;
+
+/** A kind of food lying on a beach. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Food {
+    Plankton,
+    Shellfish,
+    Algae,
+}
+
+impl Food {
+    /** Returns the food that crabs with the given diet eat. */
+    pub fn for_diet(diet: Diet) -> Food {
+        match diet {
+            Diet::Fish => Food::Plankton,
+            Diet::Shellfish => Food::Shellfish,
+            Diet::Plants => Food::Algae,
+        }
+    }
+
+    pub fn all() -> &'static [Food] {
+        &[Food::Plankton, Food::Shellfish, Food::Algae]
+    }
+}
+
+/** How many portions of each food lie on a beach. A new supply is empty. */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FoodSupply {
+    plankton: u32,
+    shellfish: u32,
+    algae: u32,
+}
+
+impl FoodSupply {
+    pub fn new() -> FoodSupply {
+        FoodSupply::default()
+    }
+
+    /** Returns how many portions of the given food are left. */
+    pub fn stock(&self, food: Food) -> u32 {
+        match food {
+            Food::Plankton => self.plankton,
+            Food::Shellfish => self.shellfish,
+            Food::Algae => self.algae,
+        }
+    }
+
+    /** Returns how many portions of food are left in all. */
+    pub fn total(&self) -> u32 {
+        Food::all().iter().map(|&food| self.stock(food)).sum()
+    }
+
+    fn stock_mut(&mut self, food: Food) -> &mut u32 {
+        match food {
+            Food::Plankton => &mut self.plankton,
+            Food::Shellfish => &mut self.shellfish,
+            Food::Algae => &mut self.algae,
+        }
+    }
+
+    pub fn add(&mut self, food: Food, portions: u32) {
+        let stock = self.stock_mut(food);
+        *stock = stock.saturating_add(portions);
+    }
+
+    /**
+     * Takes up to `portions` portions of the given food, returning how many
+     * were left to take.
+     */
+    pub fn take(&mut self, food: Food, portions: u32) -> u32 {
+        let stock = self.stock_mut(food);
+        let taken = portions.min(*stock);
+        *stock -= taken;
+        taken
+    }
+}
+
+/** What happened when the crabs of a beach were fed. */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeedingReport {
+    /** The names of the crabs that ate, in the order they live on the beach. */
+    pub fed: Vec<String>,
+    /** The names of the crabs that found nothing to eat. */
+    pub hungry: Vec<String>,
+}
@@ -0,0 +1,89 @@
+use crate::beach::Beach;
+use crate::crab::Crab;
+use crate::observe::TimeSeries;
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, StringArray, UInt32Array, UInt64Array};
+use arrow_schema::{ArrowError, DataType, Field, Schema};
+use std::sync::Arc;
+
+/**
+ * Returns the crabs of the beach as an Arrow record batch, one row per crab
+ * in beach order, with the columns `name`, `speed`, `claw_size`, `health`,
+ * `diet`, `hue`, `x`, `y` and `clan` (the id of the clan the crab belongs
+ * to, or null). Polars and other Arrow-based tools can take the batch as
+ * it is.
+ */
+pub fn population_batch(beach: &Beach) -> Result<RecordBatch, ArrowError> {
+    let clans = beach.get_clan_system();
+    let crabs: Vec<_> = beach.crabs().collect();
+    let u32s = |f: fn(&Crab) -> u32| -> ArrayRef {
+        Arc::new(crabs.iter().map(|c| f(c)).collect::<UInt32Array>())
+    };
+    let schema = Schema::new(vec![
+        Field::new("name", DataType::Utf8, false),
+        Field::new("speed", DataType::UInt32, false),
+        Field::new("claw_size", DataType::UInt32, false),
+        Field::new("health", DataType::UInt32, false),
+        Field::new("diet", DataType::Utf8, false),
+        Field::new("hue", DataType::Float64, false),
+        Field::new("x", DataType::UInt32, false),
+        Field::new("y", DataType::UInt32, false),
+        Field::new("clan", DataType::Utf8, true),
+    ]);
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(
+            crabs
+                .iter()
+                .map(|c| Some(c.name()))
+                .collect::<StringArray>(),
+        ),
+        u32s(|c| c.speed()),
+        u32s(|c| c.claw_size()),
+        u32s(|c| c.health()),
+        Arc::new(
+            crabs
+                .iter()
+                .map(|c| Some(format!("{:?}", c.diet())))
+                .collect::<StringArray>(),
+        ),
+        Arc::new(
+            crabs
+                .iter()
+                .map(|c| c.color().hue())
+                .collect::<Float64Array>(),
+        ),
+        u32s(|c| c.position().x),
+        u32s(|c| c.position().y),
+        Arc::new(
+            crabs
+                .iter()
+                .map(|c| c.id().and_then(|id| clans.get_clan_of(id)))
+                .collect::<StringArray>(),
+        ),
+    ];
+    RecordBatch::try_new(Arc::new(schema), columns)
+}
+
+/**
+ * Returns a time series as an Arrow record batch with a `tick` column and a
+ * value column with the given name.
+ */
+pub fn series_batch<T: Copy + Into<f64>>(
+    name: &str,
+    series: &TimeSeries<T>,
+) -> Result<RecordBatch, ArrowError> {
+    let schema = Schema::new(vec![
+        Field::new("tick", DataType::UInt64, false),
+        Field::new(name, DataType::Float64, false),
+    ]);
+    let points = series.points();
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(points.iter().map(|&(t, _)| t).collect::<UInt64Array>()),
+        Arc::new(
+            points
+                .iter()
+                .map(|&(_, v)| v.into())
+                .collect::<Float64Array>(),
+        ),
+    ];
+    RecordBatch::try_new(Arc::new(schema), columns)
+}
@@ -0,0 +1,194 @@
+use crate::beach::CrabId;
+use std::collections::{BTreeMap, HashMap};
+
+/**
+ * Who bred whom on a beach. Crabs are identified by their handles, so the
+ * family tree outlives crabs that have left the beach. Crabs without
+ * recorded parents are founders, unrelated to each other.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Lineage {
+    // Child -> its two parents.
+    parents: HashMap<CrabId, (CrabId, CrabId)>,
+    // The names of the crabs in the tree, kept for those that leave.
+    names: HashMap<CrabId, String>,
+}
+
+/**
+ * A crab's ancestry: the crab, then the trees of its two parents, back to
+ * founders, whose parents are unknown.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct FamilyTree {
+    pub crab: CrabId,
+    /** The crab's name, or None if it left before it was recorded. */
+    pub name: Option<String>,
+    /** 0 for a founder, else one more than the later parent's generation. */
+    pub generation: u32,
+    pub parents: Option<Box<(FamilyTree, FamilyTree)>>,
+}
+
+impl FamilyTree {
+    /** Returns the number of entries in the tree, counting repeated ancestors each time. */
+    pub fn size(&self) -> usize {
+        1 + self.parents.as_ref().map_or(0, |p| p.0.size() + p.1.size())
+    }
+
+    /** Returns the names of the ancestors `depth` generations back, in tree order. */
+    pub fn names_at(&self, depth: u32) -> Vec<Option<&str>> {
+        if depth == 0 {
+            return vec![self.name.as_deref()];
+        }
+        self.parents.as_ref().map_or(Vec::new(), |p| {
+            let mut names = p.0.names_at(depth - 1);
+            names.extend(p.1.names_at(depth - 1));
+            names
+        })
+    }
+}
+
+impl Lineage {
+    pub fn new() -> Lineage {
+        Lineage {
+            parents: HashMap::new(),
+            names: HashMap::new(),
+        }
+    }
+
+    /** Remembers a crab's name, so it can be told after the crab leaves. */
+    pub fn record_name(&mut self, crab: CrabId, name: &str) {
+        self.names.insert(crab, name.to_string());
+    }
+
+    /** Returns a crab's recorded name. */
+    pub fn name_of(&self, crab: CrabId) -> Option<&str> {
+        self.names.get(&crab).map(String::as_str)
+    }
+
+    /**
+     * Returns the family tree of a crab. Crabs whose names were never
+     * recorded are looked up with `name`.
+     */
+    pub fn family_tree<'a>(
+        &'a self,
+        crab: CrabId,
+        name: impl Fn(CrabId) -> Option<&'a str> + Copy,
+    ) -> FamilyTree {
+        let parents = self
+            .parents(crab)
+            .map(|(p, q)| Box::new((self.family_tree(p, name), self.family_tree(q, name))));
+        FamilyTree {
+            crab,
+            name: self.name_of(crab).or_else(|| name(crab)).map(String::from),
+            generation: parents
+                .as_ref()
+                .map_or(0, |p| p.0.generation.max(p.1.generation) + 1),
+            parents,
+        }
+    }
+
+    /** Records that a child was bred from two parents. */
+    pub fn record(&mut self, child: CrabId, parent1: CrabId, parent2: CrabId) {
+        self.parents.insert(child, (parent1, parent2));
+    }
+
+    /** Returns every crab bred on the beach with its parents, oldest first. */
+    pub fn births(&self) -> Vec<(CrabId, (CrabId, CrabId))> {
+        let mut births: Vec<_> = self.parents.iter().map(|(&c, &p)| (c, p)).collect();
+        births.sort_unstable();
+        births
+    }
+
+    /** Returns the parents of a crab, or None if it is a founder. */
+    pub fn parents(&self, crab: CrabId) -> Option<(CrabId, CrabId)> {
+        self.parents.get(&crab).copied()
+    }
+
+    /**
+     * Returns a crab's ancestors, including the crab itself, each with the
+     * fewest generations between it and the crab (0 for the crab itself).
+     */
+    pub fn ancestors(&self, crab: CrabId) -> BTreeMap<CrabId, u32> {
+        let mut ancestors = BTreeMap::new();
+        let mut generation = vec![crab];
+        let mut depth = 0;
+        while !generation.is_empty() {
+            let mut next = Vec::new();
+            for handle in generation {
+                if ancestors.contains_key(&handle) {
+                    continue;
+                }
+                ancestors.insert(handle, depth);
+                if let Some((p, q)) = self.parents(handle) {
+                    next.extend([p, q]);
+                }
+            }
+            generation = next;
+            depth += 1;
+        }
+        ancestors
+    }
+
+    /**
+     * Returns the nearest ancestor two crabs share, counting each crab as
+     * its own ancestor, or None if they are unrelated. The nearest ancestor
+     * has the fewest generations to both crabs combined; ties go to the
+     * youngest such ancestor.
+     */
+    pub fn lowest_common_ancestor(&self, a: CrabId, b: CrabId) -> Option<CrabId> {
+        let of_a = self.ancestors(a);
+        let of_b = self.ancestors(b);
+        of_a.iter()
+            .filter_map(|(handle, da)| of_b.get(handle).map(|db| (da + db, *handle)))
+            .min_by(|(d1, h1), (d2, h2)| d1.cmp(d2).then(h2.cmp(h1)))
+            .map(|(_, handle)| handle)
+    }
+
+    /**
+     * Returns the coefficient of relationship of two crabs: the expected
+     * share of genes they have in common by descent. It is 1 for a crab with
+     * itself, 1/2 for a parent and child or full siblings, 1/4 for half
+     * siblings and 0 for unrelated crabs, adjusted for any inbreeding.
+     */
+    pub fn relatedness(&self, a: CrabId, b: CrabId) -> f64 {
+        let mut memo = HashMap::new();
+        let kinship = self.kinship(a, b, &mut memo);
+        let fa = self.inbreeding(a, &mut memo);
+        let fb = self.inbreeding(b, &mut memo);
+        2.0 * kinship / ((1.0 + fa) * (1.0 + fb)).sqrt()
+    }
+
+    /** The inbreeding coefficient of a crab: the kinship of its parents. */
+    fn inbreeding(&self, crab: CrabId, memo: &mut Memo) -> f64 {
+        match self.parents(crab) {
+            Some((p, q)) => self.kinship(p, q, memo),
+            None => 0.0,
+        }
+    }
+
+    /**
+     * The kinship coefficient of two crabs: the probability that genes
+     * picked at random from each are identical by descent. Handles are
+     * issued in order, so the crab with the larger handle cannot be an
+     * ancestor of the other and is the one to look through.
+     */
+    fn kinship(&self, a: CrabId, b: CrabId, memo: &mut Memo) -> f64 {
+        let key = if a <= b { (a, b) } else { (b, a) };
+        if let Some(&kinship) = memo.get(&key) {
+            return kinship;
+        }
+        let (older, younger) = key;
+        let kinship = if older == younger {
+            (1.0 + self.inbreeding(younger, memo)) / 2.0
+        } else {
+            match self.parents(younger) {
+                Some((p, q)) => (self.kinship(p, older, memo) + self.kinship(q, older, memo)) / 2.0,
+                None => 0.0,
+            }
+        };
+        memo.insert(key, kinship);
+        kinship
+    }
+}
+
+type Memo = HashMap<(CrabId, CrabId), f64>;
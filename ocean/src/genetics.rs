@@ -0,0 +1,197 @@
+use crate::color::Color;
+use crate::crab::Crab;
+use crate::diet::Diet;
+use rand::{Rng, RngCore, SeedableRng};
+use rand_pcg::Pcg64;
+use std::fmt;
+
+/**
+ * How a beach breeds a child from two parents. Whatever the policy, the
+ * beach records the child's parents and generation itself.
+ */
+pub trait BreedingPolicy {
+    /** Returns a child of the two parents with the given name. */
+    fn breed(&mut self, parent1: &Crab, parent2: &Crab, name: String) -> Crab;
+
+    /**
+     * Returns a child like `breed`, drawing whatever is random about it from
+     * the given generator. Policies that do not override this ignore it.
+     */
+    fn breed_from(
+        &mut self,
+        parent1: &Crab,
+        parent2: &Crab,
+        name: String,
+        rng: &mut dyn RngCore,
+    ) -> Crab {
+        let _ = rng;
+        self.breed(parent1, parent2, name)
+    }
+
+    /** The genetics settings the policy follows, if it follows any. */
+    fn genetics(&self) -> Option<Genetics> {
+        None
+    }
+}
+
+impl fmt::Debug for dyn BreedingPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "BreedingPolicy({:?})", self.genetics())
+    }
+}
+
+/**
+ * The policy beaches breed by unless set otherwise: children are bred by
+ * `Crab::breed`, with speed 1, their parents' colors crossed and a random
+ * diet.
+ */
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultBreeding;
+
+impl BreedingPolicy for DefaultBreeding {
+    fn breed(&mut self, parent1: &Crab, parent2: &Crab, name: String) -> Crab {
+        Crab::breed(parent1, parent2, name)
+    }
+
+    fn breed_from(
+        &mut self,
+        parent1: &Crab,
+        parent2: &Crab,
+        name: String,
+        rng: &mut dyn RngCore,
+    ) -> Crab {
+        Crab::breed_from(parent1, parent2, name, rng)
+    }
+}
+
+/** How offspring inherit their parents' colors. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorInheritance {
+    /** The child's color is the cross of its parents' colors, as `Color::cross` makes it. */
+    #[default]
+    Cross,
+    /**
+     * The child takes one color allele from each parent at random, as
+     * `Color::cross_genotypes` does, and gets its color from them.
+     */
+    Mendelian,
+}
+
+/**
+ * How offspring inherit their parents' traits. A child's speed is the mean
+ * of its parents' speeds, shifted by a mutation of up to `mutation` either
+ * way and never below 1. With probability `diet_inheritance` the child
+ * takes the diet of one of its parents, chosen evenly; otherwise its diet is
+ * any of the three.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Genetics {
+    pub mutation: u32,
+    pub diet_inheritance: f64,
+    pub colors: ColorInheritance,
+    pub seed: u64,
+}
+
+impl Default for Genetics {
+    fn default() -> Self {
+        Genetics {
+            mutation: 2,
+            diet_inheritance: 0.9,
+            colors: ColorInheritance::Cross,
+            seed: 0,
+        }
+    }
+}
+
+impl Genetics {
+    /**
+     * Returns a child of the two parents with the given name, one generation
+     * after the younger parent's, whose speed, diet and color are inherited
+     * by these settings, drawing from the given generator.
+     */
+    pub fn inherit<R: Rng + ?Sized>(
+        &self,
+        parent1: &Crab,
+        parent2: &Crab,
+        name: String,
+        rng: &mut R,
+    ) -> Crab {
+        let mean = (parent1.speed() as f64 + parent2.speed() as f64) / 2.0;
+        let mutation = self.mutation as i64;
+        let shift = rng.gen_range(-mutation..=mutation);
+        let speed = (mean.round() as i64 + shift).max(1) as u32;
+
+        let chance = self.diet_inheritance.clamp(0.0, 1.0);
+        let diet = if rng.gen_bool(chance) {
+            if rng.gen_bool(0.5) {
+                parent1.diet()
+            } else {
+                parent2.diet()
+            }
+        } else {
+            [Diet::Fish, Diet::Shellfish, Diet::Plants][rng.gen_range(0..3)]
+        };
+
+        let mut child = match self.colors {
+            ColorInheritance::Cross => {
+                let color = Color::cross(parent1.color(), parent2.color());
+                Crab::new(name, speed, color, diet)
+            }
+            ColorInheritance::Mendelian => {
+                let genotype =
+                    Color::cross_genotypes(&parent1.genotype(), &parent2.genotype(), rng);
+                Crab::with_genotype(name, speed, genotype, diet)
+            }
+        };
+        child.set_generation(parent1.generation().max(parent2.generation()) + 1);
+        child
+    }
+}
+
+/** Breeds offspring according to genetics settings. */
+#[derive(Debug)]
+pub struct Heredity {
+    genetics: Genetics,
+    rng: Pcg64,
+}
+
+impl Heredity {
+    pub fn new(genetics: Genetics) -> Heredity {
+        Heredity {
+            genetics,
+            rng: Pcg64::seed_from_u64(genetics.seed),
+        }
+    }
+
+    pub fn genetics(&self) -> Genetics {
+        self.genetics
+    }
+
+    /**
+     * Returns a child of the two parents with the given name, one generation
+     * after the younger parent's, whose speed, diet and color are inherited.
+     */
+    pub fn inherit(&mut self, parent1: &Crab, parent2: &Crab, name: String) -> Crab {
+        self.genetics.inherit(parent1, parent2, name, &mut self.rng)
+    }
+}
+
+impl BreedingPolicy for Heredity {
+    fn breed(&mut self, parent1: &Crab, parent2: &Crab, name: String) -> Crab {
+        self.inherit(parent1, parent2, name)
+    }
+
+    fn breed_from(
+        &mut self,
+        parent1: &Crab,
+        parent2: &Crab,
+        name: String,
+        rng: &mut dyn RngCore,
+    ) -> Crab {
+        self.genetics.inherit(parent1, parent2, name, rng)
+    }
+
+    fn genetics(&self) -> Option<Genetics> {
+        Some(self.genetics)
+    }
+}
@@ -0,0 +1,118 @@
+use crate::beach::{Beach, CrabId};
+use crate::clans::{escape_dot, Relation};
+use crate::csv::io_error;
+use crate::error::OceanError;
+use std::collections::BTreeSet;
+use std::io::Write;
+
+/** Which of a beach's graphs `Beach::export_dot` draws. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphKind {
+    /**
+     * The clans, each with an arrow to every member, and the alliances and
+     * rivalries between them.
+     */
+    Clans,
+    /** Every crab bred on the beach, with an arrow from each parent. */
+    Lineage,
+}
+
+impl Beach {
+    /**
+     * Writes one of the beach's graphs in GraphViz's DOT language, for
+     * `dot` and the like to lay out.
+     *
+     * In the clan graph, clans are boxes and members ellipses, one for each
+     * crab by its id, labelled with its name. Alliances are solid green
+     * lines and rivalries dashed red ones, as in `ClanSystem::relations_dot`.
+     * A member that is not a crab living on the beach is drawn dashed.
+     *
+     * In the lineage graph, every crab living on the beach is drawn, along
+     * with every crab that was ever a parent or bred here. Each is labelled
+     * with its name and generation; crabs that have left the beach are
+     * drawn dashed.
+     */
+    pub fn export_dot<W: Write>(&self, mut w: W, kind: GraphKind) -> Result<(), OceanError> {
+        let dot = match kind {
+            GraphKind::Clans => self.clans_dot(),
+            GraphKind::Lineage => self.lineage_dot(),
+        };
+        w.write_all(dot.as_bytes()).map_err(io_error)
+    }
+
+    fn clans_dot(&self) -> String {
+        let clans = self.get_clan_system();
+        let mut dot = String::from("digraph clans {\n");
+        for id in clans.clan_ids() {
+            let clan = escape_dot(id);
+            dot.push_str(&format!(
+                "    \"clan/{}\" [label=\"{}\", shape=box];\n",
+                clan, clan
+            ));
+            for &member in clans.clan_members(id) {
+                let name = escape_dot(clans.member_name(member).unwrap_or_default());
+                let style = if self.resolve(member).is_none() {
+                    ", style=dashed"
+                } else {
+                    ""
+                };
+                dot.push_str(&format!(
+                    "    \"crab/{}\" [label=\"{}\"{}];\n",
+                    member, name, style
+                ));
+                dot.push_str(&format!("    \"clan/{}\" -> \"crab/{}\";\n", clan, member));
+            }
+        }
+        for (a, b, relation) in clans.relations() {
+            let style = match relation {
+                Relation::Alliance => "style=solid, color=green, label=\"alliance\"",
+                Relation::Rivalry => "style=dashed, color=red, label=\"rivalry\"",
+            };
+            dot.push_str(&format!(
+                "    \"clan/{}\" -> \"clan/{}\" [dir=none, {}];\n",
+                escape_dot(a),
+                escape_dot(b),
+                style
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn lineage_dot(&self) -> String {
+        let lineage = self.lineage();
+        let births = lineage.births();
+        let mut crabs: BTreeSet<CrabId> = (0..self.size()).map(|i| self.handle(i)).collect();
+        for &(child, (p, q)) in &births {
+            crabs.extend([child, p, q]);
+        }
+        let mut dot = String::from("digraph lineage {\n");
+        for &handle in &crabs {
+            let living = self.resolve(handle);
+            let name = living
+                .map(|crab| crab.name())
+                .or_else(|| lineage.name_of(handle))
+                .unwrap_or("?");
+            let generation = self.get_lineage(handle).map_or(0, |tree| tree.generation);
+            let style = if living.is_some() {
+                ""
+            } else {
+                ", style=dashed"
+            };
+            dot.push_str(&format!(
+                "    \"{}\" [label=\"{} (gen {})\"{}];\n",
+                handle,
+                escape_dot(name),
+                generation,
+                style
+            ));
+        }
+        for (child, (p, q)) in births {
+            for parent in [p, q] {
+                dot.push_str(&format!("    \"{}\" -> \"{}\";\n", parent, child));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
@@ -0,0 +1,153 @@
+use crate::beach::Beach;
+
+/** What each cell of a heatmap measures. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeatmapMetric {
+    /** Number of crabs in the cell. */
+    Density,
+    /** Mean speed of the crabs in the cell, or zero if it is empty. */
+    MeanSpeed,
+}
+
+/**
+ * A grid of values summarizing where crabs are on the beach, and how fast.
+ *
+ * The beach grid is divided into square cells of `cell_size` positions;
+ * crabs outside of the grid are not counted.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heatmap {
+    metric: HeatmapMetric,
+    tick: u64,
+    columns: usize,
+    rows: usize,
+    values: Vec<f64>,
+}
+
+impl Heatmap {
+    /**
+     * Summarizes a `grid_width` by `grid_height` beach grid in cells of
+     * `cell_size` by `cell_size` positions (at least 1).
+     */
+    pub fn capture(
+        beach: &Beach,
+        metric: HeatmapMetric,
+        grid_width: u32,
+        grid_height: u32,
+        cell_size: u32,
+    ) -> Heatmap {
+        let cell_size = cell_size.max(1);
+        let columns = grid_width.div_ceil(cell_size) as usize;
+        let rows = grid_height.div_ceil(cell_size) as usize;
+        let mut counts = vec![0u32; columns * rows];
+        let mut speeds = vec![0u64; columns * rows];
+
+        for crab in beach.crabs() {
+            let position = crab.position();
+            if position.x >= grid_width || position.y >= grid_height {
+                continue;
+            }
+            let cell =
+                (position.y / cell_size) as usize * columns + (position.x / cell_size) as usize;
+            counts[cell] += 1;
+            speeds[cell] += crab.speed() as u64;
+        }
+
+        let values = match metric {
+            HeatmapMetric::Density => counts.iter().map(|&c| c as f64).collect(),
+            HeatmapMetric::MeanSpeed => counts
+                .iter()
+                .zip(&speeds)
+                .map(|(&c, &s)| if c == 0 { 0.0 } else { s as f64 / c as f64 })
+                .collect(),
+        };
+        Heatmap {
+            metric,
+            tick: beach.ticks(),
+            columns,
+            rows,
+            values,
+        }
+    }
+
+    pub fn metric(&self) -> HeatmapMetric {
+        self.metric
+    }
+
+    /** The beach tick at which the heatmap was captured. */
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /**
+     * Returns the value of the cell at the given column and row.
+     * Panics if the cell is outside the heatmap.
+     */
+    pub fn value(&self, column: usize, row: usize) -> f64 {
+        assert!(
+            column < self.columns && row < self.rows,
+            "cell out of bounds"
+        );
+        self.values[row * self.columns + column]
+    }
+
+    pub fn max_value(&self) -> f64 {
+        self.values.iter().cloned().fold(0.0, f64::max)
+    }
+
+    /**
+     * Returns the color of each cell, row by row, on a scale from dark blue
+     * (zero) through green to yellow (the maximum value).
+     */
+    pub fn to_rgb(&self) -> Vec<[u8; 3]> {
+        let max = self.max_value();
+        self.values
+            .iter()
+            .map(|&v| colormap(if max > 0.0 { v / max } else { 0.0 }))
+            .collect()
+    }
+
+    /**
+     * Saves the heatmap as a PNG image, drawing each cell as a `scale` by
+     * `scale` pixel square.
+     */
+    #[cfg(feature = "png")]
+    pub fn save_png<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        scale: u32,
+    ) -> Result<(), image::ImageError> {
+        let scale = scale.max(1);
+        let colors = self.to_rgb();
+        let columns = self.columns as u32;
+        let image = image::RgbImage::from_fn(columns * scale, self.rows as u32 * scale, |x, y| {
+            let cell = (y / scale) * columns + x / scale;
+            image::Rgb(colors[cell as usize])
+        });
+        image.save_with_format(path, image::ImageFormat::Png)
+    }
+}
+
+/** Maps a fraction between 0 and 1 to a color. */
+fn colormap(t: f64) -> [u8; 3] {
+    let t = t.clamp(0.0, 1.0);
+    // Dark blue -> green -> yellow.
+    let (from, to, local) = if t < 0.5 {
+        ([20.0, 20.0, 90.0], [30.0, 170.0, 90.0], t * 2.0)
+    } else {
+        ([30.0, 170.0, 90.0], [250.0, 230.0, 40.0], (t - 0.5) * 2.0)
+    };
+    let mut rgb = [0u8; 3];
+    for k in 0..3 {
+        rgb[k] = (from[k] + (to[k] - from[k]) * local).round() as u8;
+    }
+    rgb
+}
@@ -0,0 +1,82 @@
+use crate::beach::CrabId;
+use crate::breeding::BreedingState;
+use crate::clans::ClanSystem;
+use crate::genealogy::Lineage;
+use crate::store::CrabStore;
+use std::collections::{HashMap, VecDeque};
+
+/**
+ * A copy of a beach's crabs, clans and family records as they were at some
+ * moment, which `Beach::restore` puts back. The clock, settings, tide pools,
+ * economy and everything else about the beach are not part of it.
+ */
+#[derive(Debug)]
+pub struct BeachSnapshot {
+    pub(crate) crabs: CrabStore,
+    pub(crate) by_name: HashMap<String, Vec<CrabId>>,
+    pub(crate) clan_system: ClanSystem,
+    pub(crate) lineage: Lineage,
+    pub(crate) breeding: BreedingState,
+}
+
+impl BeachSnapshot {
+    /** Returns the number of crabs that lived on the beach. */
+    pub fn size(&self) -> usize {
+        self.crabs.len()
+    }
+}
+
+impl Clone for BeachSnapshot {
+    fn clone(&self) -> Self {
+        BeachSnapshot {
+            crabs: self.crabs.duplicate(),
+            by_name: self.by_name.clone(),
+            clan_system: self.clan_system.clone(),
+            lineage: self.lineage.clone(),
+            breeding: self.breeding.clone(),
+        }
+    }
+}
+
+/** The snapshots taken before a beach's most recent changes, newest last. */
+#[derive(Debug)]
+pub(crate) struct History {
+    depth: usize,
+    snapshots: VecDeque<BeachSnapshot>,
+}
+
+impl History {
+    pub(crate) fn new(depth: usize) -> History {
+        History {
+            depth,
+            snapshots: VecDeque::with_capacity(depth),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    /** Keeps at most the `depth` newest snapshots from now on. */
+    pub(crate) fn set_depth(&mut self, depth: usize) {
+        self.depth = depth;
+        while self.snapshots.len() > depth {
+            self.snapshots.pop_front();
+        }
+    }
+
+    /** Adds a snapshot, forgetting the oldest one if there are too many. */
+    pub(crate) fn push(&mut self, snapshot: BeachSnapshot) {
+        if self.depth == 0 {
+            return;
+        }
+        if self.snapshots.len() == self.depth {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<BeachSnapshot> {
+        self.snapshots.pop_back()
+    }
+}
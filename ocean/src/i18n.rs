@@ -0,0 +1,1310 @@
+//! A small localization layer for user-facing text.
+//!
+//! Every message shown to users (error strings, `Display` output, CLI text)
+//! is identified by a `Message` and rendered in the current thread's locale
+//! through `tr`. Templates refer to their arguments by position: `{0}`, `{1}`...
+//! Messages missing from a locale's catalog fall back to English.
+
+use std::cell::Cell;
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Locale {
+    English,
+    Spanish,
+    French,
+}
+
+impl Locale {
+    /**
+     * Returns the locale for a language code such as "en", "es-MX" or "fr_FR",
+     * or None if it is not supported.
+     */
+    pub fn from_code(code: &str) -> Option<Locale> {
+        let language = code
+            .split(['-', '_'])
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        match language.as_str() {
+            "en" => Some(Locale::English),
+            "es" => Some(Locale::Spanish),
+            "fr" => Some(Locale::French),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::English => "en",
+            Locale::Spanish => "es",
+            Locale::French => "fr",
+        }
+    }
+
+    pub fn all() -> &'static [Locale] {
+        &[Locale::English, Locale::Spanish, Locale::French]
+    }
+}
+
+/** Every user-facing message of the crate. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Message {
+    /** {0}: clan id */
+    ClanAgainstItself,
+    /** {0}: clan id */
+    NoSuchClan,
+    /** {0}: crab name, {1}: clan id */
+    NotClanMember,
+    /** {0}: crab name */
+    CrabAccount,
+    /** {0}: clan id */
+    ClanAccount,
+    /** {0}: account, {1}: balance, {2}: amount needed */
+    InsufficientShells,
+    /** {0}: account, {1}: items held, {2}: item, {3}: items wanted */
+    InsufficientItems,
+    /** {0}: account */
+    TradeWithSelf,
+    /** {0}: clan id */
+    RelationWithSelf,
+    ReportTitle,
+    /** {0}: seed, {1}: ticks, {2}: crabs, {3}: clans */
+    ReportOverview,
+    ReportPopulation,
+    ReportLeaderboard,
+    ReportLeaderboardHistory,
+    ReportEvents,
+    ReportNothing,
+    ColumnTick,
+    ColumnRank,
+    ColumnClan,
+    ColumnMembers,
+    /** {0}: clan id, {1}: crab name */
+    EventClanFounded,
+    /** {0}: clan id, {1}: member count */
+    EventLargestClan,
+    /** {0}: winner, {1}: loser */
+    EventBattleWon,
+    /** {0}: crab that fled, {1}: opponent */
+    EventBattleRetreat,
+    /** {0}: attacker, {1}: defender */
+    EventBattleStalemate,
+    QueryUnexpectedEnd,
+    /** {0}: the token */
+    QueryUnexpectedToken,
+    QueryUnterminatedString,
+    /** {0}: the table */
+    QueryUnknownTable,
+    /** {0}: the column */
+    QueryUnknownColumn,
+    /** {0}: the column */
+    QueryTypeMismatch,
+    /** {0}: crab name, {1}: clan id */
+    AuditMemberMissing,
+    /** {0}: crab name, {1}: clan id */
+    AuditDuplicateMember,
+    /** {0}: crab name, {1}: clan ids */
+    AuditMultipleClans,
+    /** {0}: crab name, {1}: count */
+    AuditDuplicateName,
+    /** {0}: crab name, {1}: health */
+    AuditHealthOutOfRange,
+    /** {0}: clan id */
+    AuditEmptyClan,
+    /** {0}: clan id */
+    AuditRelationWithoutClan,
+    /** {0}: client id */
+    ServerUnknownClient,
+    /** {0}: clan id */
+    ServerClanTaken,
+    ServerReserved,
+    /** {0}: crab index */
+    ServerNoSuchCrab,
+    /** {0}: crab name */
+    ServerNameTaken,
+    /** {0}: crab name */
+    ServerCrabTaken,
+    /** {0}: reason */
+    ServerBadCommand,
+    /** {0}: peer id, {1}: tick */
+    LockstepDesync,
+    /** {0}: record number, {1}: reason */
+    ImportFailed,
+    ImportEmptyName,
+    /** {0}: crab name */
+    ImportDuplicateName,
+    /** {0}: where in the spec, {1}: the problem */
+    SpecError,
+    SpecEmptyName,
+    /** {0}: crab name */
+    SpecDuplicateCrab,
+    /** {0}: crab name */
+    SpecUnknownMember,
+    /** {0}: crab name, {1}: clan id */
+    SpecTwoClans,
+    SpecZeroClaw,
+    /** {0}: reef index */
+    SpecNoSuchReef,
+    /** {0}: trait, {1}: value, {2}: minimum, {3}: maximum */
+    ConstraintViolated,
+    /** {0}: the quota */
+    BreedQuotaReached,
+    /** {0}: crab name, {1}: its age, {2}: minimum age */
+    BreedTooYoung,
+    /** {0}, {1}: crab names, {2}: the limit */
+    BreedPairLimit,
+    /** {0}, {1}: crab names, {2}: their relatedness, {3}: the limit */
+    BreedTooClose,
+    /** {0}, {1}: the position */
+    ClutchPositionTaken,
+    ClutchEmpty,
+    /** {0}: the pool index */
+    NoSuchTidePool,
+    /** {0}, {1}: the position, {2}: the tick it opens again */
+    RegionBlocked,
+    /** {0}, {1}: crab names, {2}: the temperature */
+    BreedFailedTemperature,
+    /** {0}: what is wrong with it */
+    InvalidDistribution,
+    /** {0}: the beach index */
+    RouteSameBeach,
+    /** {0}: the beach index */
+    NoSuchBeach,
+    /** {0}: the route index */
+    NoSuchRoute,
+    /** {0}: crab id */
+    NoSuchCrabId,
+    /** {0}: clan id, {1}: the clan that would be its parent */
+    SubclanCycle,
+    /** {0}, {1}: clan ids */
+    SameAlliance,
+    /** {0}: the index, {1}: the number of crabs */
+    InvalidIndex,
+    EmptyBeach,
+    /** {0}: crab name, {1}: clan id */
+    DuplicateMembership,
+    /** {0}: crab name, {1}: speed, {2}: diet, {3}: color */
+    CrabSummary,
+    /** {0}: crabs, {1}: clans */
+    BeachSummary,
+    /** {0}: clan id, {1}: member count, {2}: member names */
+    ClanSummary,
+    BeachReportTitle,
+    /** {0}: crabs */
+    ReportPopulationCount,
+    /** {0}: crab name, {1}: speed */
+    ReportFastestCrab,
+    /** {0}: rank, {1}: clan id, {2}: member count, {3}: average speed */
+    ReportClanStanding,
+    ReportDiets,
+    /** {0}: diet, {1}: crabs, {2}: percentage */
+    ReportDietCount,
+    /** {0}: line number, {1}: what is wrong with it */
+    CsvLine,
+    /** {0}: the expected header */
+    CsvBadHeader,
+    /** {0}: columns expected, {1}: columns found */
+    CsvColumnCount,
+    /** {0}: the field */
+    CsvBadNumber,
+    /** {0}: the field */
+    CsvBadColor,
+    /** {0}: the field */
+    CsvBadDiet,
+    CsvUnterminatedQuote,
+    /** {0}, {1}: the position, {2}, {3}: the territory's width and height */
+    OutsideTerritory,
+    /** {0}, {1}: crab names, {2}: their distance, {3}: the limit */
+    BreedTooFar,
+    /** {0}, {1}: crab names */
+    BreedFailedWeather,
+    /** {0}: crab name, {1}: its age, {2}: the lifespan */
+    InvariantTooOld,
+    /** {0}: crab name, {1}: crabs found by name, {2}: crabs with that name */
+    InvariantNameIndex,
+    /** {0}: the beach's capacity */
+    BeachFull,
+    /** {0}: beach name */
+    DuplicateBeach,
+    BinaryTruncated,
+    BinaryBadMagic,
+    /** {0}: the save's format version, {1}: the latest version */
+    BinaryUnsupportedVersion,
+    /** {0}: what was read, {1}: the value */
+    BinaryBadValue,
+    /** {0}: name */
+    NoSuchInhabitant,
+    /** {0}: clan id */
+    DuplicateClan,
+    /** {0}: clan id */
+    MergeWithSelf,
+    /** {0}: color */
+    UnknownColor,
+    /** {0}: clan id, {1}: number of crabs */
+    ClanTooSmall,
+    /** {0}: reason */
+    ScriptInvalid,
+    /** {0}: crab name, {1}: ticks left */
+    BreedCoolingDown,
+    CommandUnknown,
+    CommandUsage,
+    CommandTicked,
+    /** {0}: crab name, {1}: clan id, {2}: its speed, {3}: the minimum */
+    ClanRejectsSlow,
+    /** {0}: crab name, {1}: clan id, {2}: its diet, {3}: the required diet */
+    ClanRejectsDiet,
+    /** {0}: crab name, {1}: clan id, {2}: the most members allowed */
+    ClanRejectsFull,
+    /** {0}: crab name, {1}: clan id */
+    ClanRejectsAbsent,
+    /** {0}: account, {1}: its balance, {2}: the shells it would receive */
+    TooManyShells,
+    ReplayEmpty,
+    ReplayNoSeed,
+    /** {0}: the color field */
+    ReplayBadColor,
+    /** {0}: the line */
+    ReplayUnknownOperation,
+    /** {0}: the field */
+    ReplayBadEscape,
+    /** {0}: the crab's index */
+    BattleItself,
+    SvgNoClan,
+    /** {0}: tick, {1}: crabs, {2}: clans */
+    TerminalStatus,
+    MergeTooLarge,
+}
+
+type Catalog = &'static [(Message, &'static str)];
+
+const ENGLISH: Catalog = &[
+    (
+        Message::ClanAgainstItself,
+        "a clan cannot compete against itself: {0}",
+    ),
+    (Message::NoSuchClan, "no such clan: {0}"),
+    (Message::NotClanMember, "{0} is not a member of {1}"),
+    (Message::CrabAccount, "crab {0}"),
+    (Message::ClanAccount, "clan {0}"),
+    (
+        Message::InsufficientShells,
+        "{0} has {1} shells but needs {2}",
+    ),
+    (
+        Message::InsufficientItems,
+        "{0} has {1} {2} but tried to sell {3}",
+    ),
+    (Message::TradeWithSelf, "{0} cannot trade with itself"),
+    (
+        Message::RelationWithSelf,
+        "a clan cannot have a relation with itself: {0}",
+    ),
+    (Message::ReportTitle, "Simulation report"),
+    (
+        Message::ReportOverview,
+        "Seed {0}, {1} ticks, {2} crabs in {3} clans.",
+    ),
+    (Message::ReportPopulation, "Population"),
+    (Message::ReportLeaderboard, "Clan leaderboard"),
+    (Message::ReportLeaderboardHistory, "Leaderboard history"),
+    (Message::ReportEvents, "Notable events"),
+    (Message::ReportNothing, "Nothing to report."),
+    (Message::ColumnTick, "Tick"),
+    (Message::ColumnRank, "Rank"),
+    (Message::ColumnClan, "Clan"),
+    (Message::ColumnMembers, "Members"),
+    (Message::EventClanFounded, "{1} founded {0}"),
+    (
+        Message::EventLargestClan,
+        "{0} became the largest clan with {1} members",
+    ),
+    (Message::EventBattleWon, "{0} defeated {1} in battle"),
+    (
+        Message::EventBattleRetreat,
+        "{0} retreated from a battle with {1}",
+    ),
+    (
+        Message::EventBattleStalemate,
+        "{0} and {1} fought to a stalemate",
+    ),
+    (Message::QueryUnexpectedEnd, "query ended unexpectedly"),
+    (
+        Message::QueryUnexpectedToken,
+        "unexpected token in query: {0}",
+    ),
+    (
+        Message::QueryUnterminatedString,
+        "unterminated string in query",
+    ),
+    (Message::QueryUnknownTable, "no such table: {0}"),
+    (Message::QueryUnknownColumn, "no such column: {0}"),
+    (
+        Message::QueryTypeMismatch,
+        "cannot compare column with that value: {0}",
+    ),
+    (
+        Message::AuditMemberMissing,
+        "clan {1} lists {0}, who does not live on the beach",
+    ),
+    (
+        Message::AuditDuplicateMember,
+        "{0} is listed more than once in clan {1}",
+    ),
+    (
+        Message::AuditMultipleClans,
+        "{0} belongs to several clans: {1}",
+    ),
+    (Message::AuditDuplicateName, "{1} crabs are named {0}"),
+    (
+        Message::AuditHealthOutOfRange,
+        "{0} has health {1}, above the maximum",
+    ),
+    (Message::AuditEmptyClan, "clan {0} has no members"),
+    (
+        Message::AuditRelationWithoutClan,
+        "a relation names clan {0}, which does not exist",
+    ),
+    (Message::ServerUnknownClient, "no such client: {0}"),
+    (
+        Message::ServerClanTaken,
+        "clan {0} is already controlled by another client",
+    ),
+    (Message::ServerReserved, "only the server may do that"),
+    (Message::ServerNoSuchCrab, "no crab at index {0}"),
+    (
+        Message::ServerNameTaken,
+        "a crab named {0} already lives on the beach",
+    ),
+    (Message::ServerCrabTaken, "{0} already belongs to a clan"),
+    (Message::ServerBadCommand, "bad command: {0}"),
+    (Message::LockstepDesync, "peer {0} diverged at tick {1}"),
+    (Message::ImportFailed, "record {0}: {1}"),
+    (Message::ImportEmptyName, "crab has no name"),
+    (
+        Message::ImportDuplicateName,
+        "a crab named {0} already exists",
+    ),
+    (Message::SpecError, "{0}: {1}"),
+    (Message::SpecEmptyName, "name must not be empty"),
+    (
+        Message::SpecDuplicateCrab,
+        "another crab on this beach is named {0}",
+    ),
+    (
+        Message::SpecUnknownMember,
+        "no crab named {0} lives on this beach",
+    ),
+    (Message::SpecTwoClans, "{0} already belongs to clan {1}"),
+    (Message::SpecZeroClaw, "claw size must be at least 1"),
+    (Message::SpecNoSuchReef, "there is no reef {0}"),
+    (
+        Message::ConstraintViolated,
+        "{0} {1} is outside the allowed range {2} to {3}",
+    ),
+    (
+        Message::BreedQuotaReached,
+        "the beach has reached its quota of {0} births this tick",
+    ),
+    (
+        Message::BreedTooYoung,
+        "{0} is {1} ticks old, younger than the minimum of {2}",
+    ),
+    (
+        Message::BreedPairLimit,
+        "{0} and {1} already have {2} offspring together",
+    ),
+    (
+        Message::BreedTooClose,
+        "{0} and {1} are too closely related to breed ({2} > {3})",
+    ),
+    (
+        Message::ClutchPositionTaken,
+        "a clutch is already incubating at ({0}, {1})",
+    ),
+    (Message::ClutchEmpty, "a clutch needs at least one egg"),
+    (Message::NoSuchTidePool, "no such tide pool: {0}"),
+    (
+        Message::RegionBlocked,
+        "({0}, {1}) is blocked off until tick {2}",
+    ),
+    (
+        Message::BreedFailedTemperature,
+        "{0} and {1} failed to breed at {2}°C",
+    ),
+    (Message::InvalidDistribution, "invalid distribution: {0}"),
+    (
+        Message::RouteSameBeach,
+        "a trade route cannot start and end at beach {0}",
+    ),
+    (Message::NoSuchBeach, "no such beach: {0}"),
+    (Message::NoSuchRoute, "no such trade route: {0}"),
+    (Message::NoSuchCrabId, "no crab with id {0}"),
+    (
+        Message::SubclanCycle,
+        "{0} cannot become a sub-clan of {1}, which is part of it",
+    ),
+    (
+        Message::SameAlliance,
+        "{0} and {1} are in the same alliance",
+    ),
+    (
+        Message::InvalidIndex,
+        "no crab at index {0} on a beach of {1} crabs",
+    ),
+    (Message::EmptyBeach, "the beach is empty"),
+    (
+        Message::DuplicateMembership,
+        "{0} is already a member of {1}",
+    ),
+    (Message::CrabSummary, "{0}: speed {1}, {2} diet, color {3}"),
+    (Message::BeachSummary, "{0} crabs in {1} clans"),
+    (Message::ClanSummary, "{0} ({1} members): {2}"),
+    (Message::BeachReportTitle, "Beach report"),
+    (Message::ReportPopulationCount, "Population: {0}"),
+    (Message::ReportFastestCrab, "Fastest crab: {0} (speed {1})"),
+    (
+        Message::ReportClanStanding,
+        "{0}. {1}: {2} members, average speed {3}",
+    ),
+    (Message::ReportDiets, "Diets"),
+    (Message::ReportDietCount, "{0}: {1} ({2}%)"),
+    (Message::CsvLine, "line {0}: {1}"),
+    (Message::CsvBadHeader, "the header must be {0}"),
+    (Message::CsvColumnCount, "expected {0} columns, found {1}"),
+    (Message::CsvBadNumber, "not a number: {0}"),
+    (Message::CsvBadColor, "not a hex color: {0}"),
+    (Message::CsvBadDiet, "unknown diet: {0}"),
+    (Message::CsvUnterminatedQuote, "unterminated quote"),
+    (
+        Message::OutsideTerritory,
+        "({0}, {1}) is outside the {2}x{3} territory",
+    ),
+    (
+        Message::BreedTooFar,
+        "{0} and {1} are too far apart to breed ({2} > {3})",
+    ),
+    (
+        Message::BreedFailedWeather,
+        "{0} and {1} failed to breed in bad weather",
+    ),
+    (
+        Message::InvariantTooOld,
+        "{0} is {1} ticks old, past the lifespan of {2}",
+    ),
+    (
+        Message::InvariantNameIndex,
+        "looking up {0} finds {1} crabs, but {2} live on the beach",
+    ),
+    (Message::BeachFull, "the beach is full ({0} crabs)"),
+    (
+        Message::DuplicateBeach,
+        "the ocean already has a beach named {0}",
+    ),
+    (Message::BinaryTruncated, "the binary save ends too soon"),
+    (Message::BinaryBadMagic, "not a binary beach save"),
+    (
+        Message::BinaryUnsupportedVersion,
+        "binary save version {0} is not supported (latest: {1})",
+    ),
+    (Message::BinaryBadValue, "invalid {0} in binary save: {1}"),
+    (
+        Message::NoSuchInhabitant,
+        "nothing on the beach is named {0}",
+    ),
+    (Message::DuplicateClan, "there is already a clan called {0}"),
+    (
+        Message::MergeWithSelf,
+        "a clan cannot merge with itself: {0}",
+    ),
+    (
+        Message::UnknownColor,
+        "neither a hex color nor a color name: {0}",
+    ),
+    (
+        Message::ClanTooSmall,
+        "clan {0} has {1} crabs on the beach, but breeding takes two",
+    ),
+    (Message::ScriptInvalid, "invalid scenario script: {0}"),
+    (
+        Message::BreedCoolingDown,
+        "{0} bred too recently, and may breed again in {1} ticks",
+    ),
+    (Message::CommandUnknown, "unknown command: {0}; try help"),
+    (Message::CommandUsage, "usage: {0}"),
+    (Message::CommandTicked, "tick {0}: {1} born, {2} died"),
+    (
+        Message::ClanRejectsSlow,
+        "{1} turns away {0}: speed {2} is below the minimum of {3}",
+    ),
+    (
+        Message::ClanRejectsDiet,
+        "{1} turns away {0}: it eats {2}, but the clan requires {3}",
+    ),
+    (
+        Message::ClanRejectsFull,
+        "{1} turns away {0}: the clan already has its {2} members",
+    ),
+    (
+        Message::ClanRejectsAbsent,
+        "{1} turns away {0}: no crab on the beach has that name",
+    ),
+    (
+        Message::TooManyShells,
+        "{0} has {1} shells and cannot hold {2} more",
+    ),
+    (Message::ReplayEmpty, "the replay is empty"),
+    (Message::ReplayNoSeed, "a replay must start with its seed"),
+    (Message::ReplayBadColor, "not a color: {0}"),
+    (Message::ReplayUnknownOperation, "unknown operation: {0}"),
+    (Message::ReplayBadEscape, "bad escape sequence in {0}"),
+    (
+        Message::BattleItself,
+        "the crab at index {0} cannot battle itself",
+    ),
+    (Message::SvgNoClan, "no clan"),
+    (Message::TerminalStatus, "tick {0} | {1} crabs | {2} clans"),
+    (
+        Message::MergeTooLarge,
+        "clans {0} and {1} have {2} members together, more than the {3} their constitution allows",
+    ),
+];
+
+const SPANISH: Catalog = &[
+    (
+        Message::ClanAgainstItself,
+        "un clan no puede competir contra sí mismo: {0}",
+    ),
+    (Message::NoSuchClan, "no existe el clan: {0}"),
+    (Message::NotClanMember, "{0} no es miembro de {1}"),
+    (Message::CrabAccount, "cangrejo {0}"),
+    (Message::ClanAccount, "clan {0}"),
+    (
+        Message::InsufficientShells,
+        "{0} tiene {1} conchas pero necesita {2}",
+    ),
+    (
+        Message::InsufficientItems,
+        "{0} tiene {1} {2} pero intentó vender {3}",
+    ),
+    (
+        Message::TradeWithSelf,
+        "{0} no puede comerciar consigo mismo",
+    ),
+    (
+        Message::RelationWithSelf,
+        "un clan no puede relacionarse consigo mismo: {0}",
+    ),
+    (Message::ReportTitle, "Informe de la simulación"),
+    (
+        Message::ReportOverview,
+        "Semilla {0}, {1} ticks, {2} cangrejos en {3} clanes.",
+    ),
+    (Message::ReportPopulation, "Población"),
+    (Message::ReportLeaderboard, "Clasificación de clanes"),
+    (
+        Message::ReportLeaderboardHistory,
+        "Historial de la clasificación",
+    ),
+    (Message::ReportEvents, "Eventos destacados"),
+    (Message::ReportNothing, "Nada que informar."),
+    (Message::ColumnTick, "Tick"),
+    (Message::ColumnRank, "Puesto"),
+    (Message::ColumnClan, "Clan"),
+    (Message::ColumnMembers, "Miembros"),
+    (Message::EventClanFounded, "{1} fundó {0}"),
+    (
+        Message::EventLargestClan,
+        "{0} se convirtió en el clan más grande con {1} miembros",
+    ),
+    (Message::EventBattleWon, "{0} derrotó a {1} en combate"),
+    (
+        Message::EventBattleRetreat,
+        "{0} huyó de un combate con {1}",
+    ),
+    (
+        Message::EventBattleStalemate,
+        "{0} y {1} empataron en combate",
+    ),
+    (
+        Message::QueryUnexpectedEnd,
+        "la consulta terminó inesperadamente",
+    ),
+    (
+        Message::QueryUnexpectedToken,
+        "símbolo inesperado en la consulta: {0}",
+    ),
+    (
+        Message::QueryUnterminatedString,
+        "cadena sin terminar en la consulta",
+    ),
+    (Message::QueryUnknownTable, "no existe la tabla: {0}"),
+    (Message::QueryUnknownColumn, "no existe la columna: {0}"),
+    (
+        Message::QueryTypeMismatch,
+        "no se puede comparar la columna con ese valor: {0}",
+    ),
+    (
+        Message::AuditMemberMissing,
+        "el clan {1} incluye a {0}, que no vive en la playa",
+    ),
+    (
+        Message::AuditDuplicateMember,
+        "{0} aparece más de una vez en el clan {1}",
+    ),
+    (
+        Message::AuditMultipleClans,
+        "{0} pertenece a varios clanes: {1}",
+    ),
+    (Message::AuditDuplicateName, "{1} cangrejos se llaman {0}"),
+    (
+        Message::AuditHealthOutOfRange,
+        "{0} tiene salud {1}, por encima del máximo",
+    ),
+    (Message::AuditEmptyClan, "el clan {0} no tiene miembros"),
+    (
+        Message::AuditRelationWithoutClan,
+        "una relación menciona el clan {0}, que no existe",
+    ),
+    (Message::ServerUnknownClient, "no existe el cliente: {0}"),
+    (
+        Message::ServerClanTaken,
+        "el clan {0} ya está controlado por otro cliente",
+    ),
+    (Message::ServerReserved, "solo el servidor puede hacer eso"),
+    (
+        Message::ServerNoSuchCrab,
+        "no hay ningún cangrejo en el índice {0}",
+    ),
+    (
+        Message::ServerNameTaken,
+        "ya vive en la playa un cangrejo llamado {0}",
+    ),
+    (Message::ServerCrabTaken, "{0} ya pertenece a un clan"),
+    (Message::ServerBadCommand, "orden no válida: {0}"),
+    (
+        Message::LockstepDesync,
+        "el par {0} divergió en el tick {1}",
+    ),
+    (Message::ImportFailed, "registro {0}: {1}"),
+    (Message::ImportEmptyName, "el cangrejo no tiene nombre"),
+    (
+        Message::ImportDuplicateName,
+        "ya existe un cangrejo llamado {0}",
+    ),
+    (Message::SpecError, "{0}: {1}"),
+    (Message::SpecEmptyName, "el nombre no puede estar vacío"),
+    (
+        Message::SpecDuplicateCrab,
+        "otro cangrejo de esta playa se llama {0}",
+    ),
+    (
+        Message::SpecUnknownMember,
+        "ningún cangrejo llamado {0} vive en esta playa",
+    ),
+    (Message::SpecTwoClans, "{0} ya pertenece al clan {1}"),
+    (
+        Message::SpecZeroClaw,
+        "el tamaño de la pinza debe ser al menos 1",
+    ),
+    (Message::SpecNoSuchReef, "no existe el arrecife {0}"),
+    (
+        Message::ConstraintViolated,
+        "{0} {1} está fuera del rango permitido de {2} a {3}",
+    ),
+    (
+        Message::BreedQuotaReached,
+        "la playa alcanzó su cupo de {0} nacimientos en este tick",
+    ),
+    (
+        Message::BreedTooYoung,
+        "{0} tiene {1} ticks, menos que el mínimo de {2}",
+    ),
+    (
+        Message::BreedPairLimit,
+        "{0} y {1} ya tienen {2} crías juntos",
+    ),
+    (
+        Message::BreedTooClose,
+        "{0} y {1} son parientes demasiado cercanos para criar ({2} > {3})",
+    ),
+    (
+        Message::ClutchPositionTaken,
+        "ya hay una puesta incubándose en ({0}, {1})",
+    ),
+    (
+        Message::ClutchEmpty,
+        "una puesta necesita al menos un huevo",
+    ),
+    (Message::NoSuchTidePool, "no existe la poza de marea: {0}"),
+    (
+        Message::RegionBlocked,
+        "({0}, {1}) está bloqueada hasta el tick {2}",
+    ),
+    (
+        Message::BreedFailedTemperature,
+        "{0} y {1} no lograron criar a {2}°C",
+    ),
+    (Message::InvalidDistribution, "distribución no válida: {0}"),
+    (
+        Message::RouteSameBeach,
+        "una ruta comercial no puede empezar y terminar en la playa {0}",
+    ),
+    (Message::NoSuchBeach, "no existe la playa: {0}"),
+    (Message::NoSuchRoute, "no existe la ruta comercial: {0}"),
+    (Message::NoSuchCrabId, "no hay ningún cangrejo con id {0}"),
+    (
+        Message::SubclanCycle,
+        "{0} no puede ser un subclán de {1}, que forma parte de él",
+    ),
+    (Message::SameAlliance, "{0} y {1} están en la misma alianza"),
+    (
+        Message::InvalidIndex,
+        "no hay cangrejo en el índice {0} de una playa de {1} cangrejos",
+    ),
+    (Message::EmptyBeach, "la playa está vacía"),
+    (Message::DuplicateMembership, "{0} ya es miembro de {1}"),
+    (
+        Message::CrabSummary,
+        "{0}: velocidad {1}, dieta {2}, color {3}",
+    ),
+    (Message::BeachSummary, "{0} cangrejos en {1} clanes"),
+    (Message::ClanSummary, "{0} ({1} miembros): {2}"),
+    (Message::BeachReportTitle, "Informe de la playa"),
+    (Message::ReportPopulationCount, "Población: {0}"),
+    (
+        Message::ReportFastestCrab,
+        "Cangrejo más rápido: {0} (velocidad {1})",
+    ),
+    (
+        Message::ReportClanStanding,
+        "{0}. {1}: {2} miembros, velocidad media {3}",
+    ),
+    (Message::ReportDiets, "Dietas"),
+    (Message::ReportDietCount, "{0}: {1} ({2} %)"),
+    (Message::CsvLine, "línea {0}: {1}"),
+    (Message::CsvBadHeader, "la cabecera debe ser {0}"),
+    (
+        Message::CsvColumnCount,
+        "se esperaban {0} columnas, hay {1}",
+    ),
+    (Message::CsvBadNumber, "no es un número: {0}"),
+    (Message::CsvBadColor, "no es un color hexadecimal: {0}"),
+    (Message::CsvBadDiet, "dieta desconocida: {0}"),
+    (Message::CsvUnterminatedQuote, "comillas sin cerrar"),
+    (
+        Message::OutsideTerritory,
+        "({0}, {1}) está fuera del territorio de {2}x{3}",
+    ),
+    (
+        Message::BreedTooFar,
+        "{0} y {1} están demasiado lejos para reproducirse ({2} > {3})",
+    ),
+    (
+        Message::BreedFailedWeather,
+        "{0} y {1} no lograron criar con mal tiempo",
+    ),
+    (
+        Message::InvariantTooOld,
+        "{0} tiene {1} ticks, más que la esperanza de vida de {2}",
+    ),
+    (
+        Message::InvariantNameIndex,
+        "buscar a {0} encuentra {1} cangrejos, pero {2} viven en la playa",
+    ),
+    (Message::BeachFull, "la playa está llena ({0} cangrejos)"),
+    (
+        Message::DuplicateBeach,
+        "el océano ya tiene una playa llamada {0}",
+    ),
+    (
+        Message::BinaryTruncated,
+        "el archivo binario termina demasiado pronto",
+    ),
+    (
+        Message::BinaryBadMagic,
+        "no es una playa guardada en binario",
+    ),
+    (
+        Message::BinaryUnsupportedVersion,
+        "la versión {0} del formato binario no es compatible (última: {1})",
+    ),
+    (
+        Message::BinaryBadValue,
+        "{0} no válido en el archivo binario: {1}",
+    ),
+    (Message::NoSuchInhabitant, "nada en la playa se llama {0}"),
+    (Message::DuplicateClan, "ya hay un clan llamado {0}"),
+    (
+        Message::MergeWithSelf,
+        "un clan no puede fusionarse consigo mismo: {0}",
+    ),
+    (
+        Message::UnknownColor,
+        "no es un color hexadecimal ni el nombre de un color: {0}",
+    ),
+    (
+        Message::ClanTooSmall,
+        "el clan {0} tiene {1} cangrejos en la playa, pero criar requiere dos",
+    ),
+    (Message::ScriptInvalid, "guion de escenario no válido: {0}"),
+    (
+        Message::BreedCoolingDown,
+        "{0} crió hace muy poco, y podrá volver a criar en {1} ticks",
+    ),
+    (
+        Message::CommandUnknown,
+        "orden desconocida: {0}; pruebe help",
+    ),
+    (Message::CommandUsage, "uso: {0}"),
+    (Message::CommandTicked, "tick {0}: {1} nacidos, {2} muertos"),
+    (
+        Message::ClanRejectsSlow,
+        "{1} rechaza a {0}: su velocidad {2} es menor que el mínimo de {3}",
+    ),
+    (
+        Message::ClanRejectsDiet,
+        "{1} rechaza a {0}: come {2}, pero el clan exige {3}",
+    ),
+    (
+        Message::ClanRejectsFull,
+        "{1} rechaza a {0}: el clan ya tiene sus {2} miembros",
+    ),
+    (
+        Message::ClanRejectsAbsent,
+        "{1} rechaza a {0}: ningún cangrejo de la playa se llama así",
+    ),
+    (
+        Message::TooManyShells,
+        "{0} tiene {1} conchas y no puede guardar {2} más",
+    ),
+    (Message::ReplayEmpty, "la repetición está vacía"),
+    (
+        Message::ReplayNoSeed,
+        "una repetición debe empezar con su semilla",
+    ),
+    (Message::ReplayBadColor, "no es un color: {0}"),
+    (
+        Message::ReplayUnknownOperation,
+        "operación desconocida: {0}",
+    ),
+    (
+        Message::ReplayBadEscape,
+        "secuencia de escape no válida en {0}",
+    ),
+    (
+        Message::BattleItself,
+        "el cangrejo en el índice {0} no puede luchar contra sí mismo",
+    ),
+    (Message::SvgNoClan, "sin clan"),
+    (
+        Message::TerminalStatus,
+        "tick {0} | {1} cangrejos | {2} clanes",
+    ),
+    (Message::MergeTooLarge, "los clanes {0} y {1} tienen {2} miembros juntos, más de los {3} que permite su constitución"),
+];
+
+const FRENCH: Catalog = &[
+    (
+        Message::ClanAgainstItself,
+        "un clan ne peut pas s'affronter lui-même : {0}",
+    ),
+    (Message::NoSuchClan, "clan inconnu : {0}"),
+    (Message::NotClanMember, "{0} n'est pas membre de {1}"),
+    (Message::CrabAccount, "crabe {0}"),
+    (Message::ClanAccount, "clan {0}"),
+    (
+        Message::InsufficientShells,
+        "{0} a {1} coquillages mais il en faut {2}",
+    ),
+    (
+        Message::InsufficientItems,
+        "{0} a {1} {2} mais a voulu en vendre {3}",
+    ),
+    (
+        Message::TradeWithSelf,
+        "{0} ne peut pas commercer avec lui-même",
+    ),
+    (
+        Message::RelationWithSelf,
+        "un clan ne peut pas avoir de relation avec lui-même : {0}",
+    ),
+    (Message::ReportTitle, "Rapport de simulation"),
+    (
+        Message::ReportOverview,
+        "Graine {0}, {1} ticks, {2} crabes dans {3} clans.",
+    ),
+    (Message::ReportPopulation, "Population"),
+    (Message::ReportLeaderboard, "Classement des clans"),
+    (
+        Message::ReportLeaderboardHistory,
+        "Historique du classement",
+    ),
+    (Message::ReportEvents, "Événements notables"),
+    (Message::ReportNothing, "Rien à signaler."),
+    (Message::ColumnTick, "Tick"),
+    (Message::ColumnRank, "Rang"),
+    (Message::ColumnClan, "Clan"),
+    (Message::ColumnMembers, "Membres"),
+    (Message::EventClanFounded, "{1} a fondé {0}"),
+    (
+        Message::EventLargestClan,
+        "{0} est devenu le plus grand clan avec {1} membres",
+    ),
+    (Message::EventBattleWon, "{0} a vaincu {1} au combat"),
+    (
+        Message::EventBattleRetreat,
+        "{0} a fui un combat contre {1}",
+    ),
+    (
+        Message::EventBattleStalemate,
+        "{0} et {1} se sont neutralisés au combat",
+    ),
+    (
+        Message::QueryUnexpectedEnd,
+        "la requête s'est terminée de façon inattendue",
+    ),
+    (
+        Message::QueryUnexpectedToken,
+        "symbole inattendu dans la requête : {0}",
+    ),
+    (
+        Message::QueryUnterminatedString,
+        "chaîne non terminée dans la requête",
+    ),
+    (Message::QueryUnknownTable, "table inconnue : {0}"),
+    (Message::QueryUnknownColumn, "colonne inconnue : {0}"),
+    (
+        Message::QueryTypeMismatch,
+        "impossible de comparer la colonne à cette valeur : {0}",
+    ),
+    (
+        Message::AuditMemberMissing,
+        "le clan {1} compte {0}, qui ne vit pas sur la plage",
+    ),
+    (
+        Message::AuditDuplicateMember,
+        "{0} figure plus d'une fois dans le clan {1}",
+    ),
+    (
+        Message::AuditMultipleClans,
+        "{0} appartient à plusieurs clans : {1}",
+    ),
+    (Message::AuditDuplicateName, "{1} crabes s'appellent {0}"),
+    (
+        Message::AuditHealthOutOfRange,
+        "{0} a une santé de {1}, au-dessus du maximum",
+    ),
+    (Message::AuditEmptyClan, "le clan {0} n'a aucun membre"),
+    (
+        Message::AuditRelationWithoutClan,
+        "une relation mentionne le clan {0}, qui n'existe pas",
+    ),
+    (Message::ServerUnknownClient, "client inconnu : {0}"),
+    (
+        Message::ServerClanTaken,
+        "le clan {0} est déjà contrôlé par un autre client",
+    ),
+    (Message::ServerReserved, "seul le serveur peut faire cela"),
+    (Message::ServerNoSuchCrab, "aucun crabe à l'indice {0}"),
+    (
+        Message::ServerNameTaken,
+        "un crabe nommé {0} vit déjà sur la plage",
+    ),
+    (Message::ServerCrabTaken, "{0} appartient déjà à un clan"),
+    (Message::ServerBadCommand, "commande invalide : {0}"),
+    (Message::LockstepDesync, "le pair {0} a divergé au tick {1}"),
+    (Message::ImportFailed, "enregistrement {0} : {1}"),
+    (Message::ImportEmptyName, "le crabe n'a pas de nom"),
+    (
+        Message::ImportDuplicateName,
+        "un crabe nommé {0} existe déjà",
+    ),
+    (Message::SpecError, "{0} : {1}"),
+    (Message::SpecEmptyName, "le nom ne doit pas être vide"),
+    (
+        Message::SpecDuplicateCrab,
+        "un autre crabe de cette plage s'appelle {0}",
+    ),
+    (
+        Message::SpecUnknownMember,
+        "aucun crabe nommé {0} ne vit sur cette plage",
+    ),
+    (Message::SpecTwoClans, "{0} appartient déjà au clan {1}"),
+    (
+        Message::SpecZeroClaw,
+        "la taille de la pince doit être d'au moins 1",
+    ),
+    (Message::SpecNoSuchReef, "il n'y a pas de récif {0}"),
+    (
+        Message::ConstraintViolated,
+        "{0} {1} est hors de l'intervalle permis de {2} à {3}",
+    ),
+    (
+        Message::BreedQuotaReached,
+        "la plage a atteint son quota de {0} naissances pour ce tick",
+    ),
+    (
+        Message::BreedTooYoung,
+        "{0} a {1} ticks, moins que le minimum de {2}",
+    ),
+    (
+        Message::BreedPairLimit,
+        "{0} et {1} ont déjà {2} petits ensemble",
+    ),
+    (
+        Message::BreedTooClose,
+        "{0} et {1} sont trop proches parents pour se reproduire ({2} > {3})",
+    ),
+    (
+        Message::ClutchPositionTaken,
+        "une ponte est déjà en incubation en ({0}, {1})",
+    ),
+    (Message::ClutchEmpty, "une ponte a besoin d'au moins un œuf"),
+    (Message::NoSuchTidePool, "pas de flaque de marée : {0}"),
+    (
+        Message::RegionBlocked,
+        "({0}, {1}) est bloquée jusqu'au tick {2}",
+    ),
+    (
+        Message::BreedFailedTemperature,
+        "{0} et {1} n'ont pas réussi à se reproduire à {2}°C",
+    ),
+    (Message::InvalidDistribution, "distribution invalide : {0}"),
+    (
+        Message::RouteSameBeach,
+        "une route commerciale ne peut pas partir de la plage {0} et y arriver",
+    ),
+    (Message::NoSuchBeach, "pas de plage : {0}"),
+    (Message::NoSuchRoute, "pas de route commerciale : {0}"),
+    (Message::NoSuchCrabId, "aucun crabe avec l'identifiant {0}"),
+    (
+        Message::SubclanCycle,
+        "{0} ne peut pas devenir un sous-clan de {1}, qui en fait partie",
+    ),
+    (
+        Message::SameAlliance,
+        "{0} et {1} sont dans la même alliance",
+    ),
+    (
+        Message::InvalidIndex,
+        "pas de crabe à l'indice {0} d'une plage de {1} crabes",
+    ),
+    (Message::EmptyBeach, "la plage est vide"),
+    (Message::DuplicateMembership, "{0} est déjà membre de {1}"),
+    (
+        Message::CrabSummary,
+        "{0} : vitesse {1}, régime {2}, couleur {3}",
+    ),
+    (Message::BeachSummary, "{0} crabes dans {1} clans"),
+    (Message::ClanSummary, "{0} ({1} membres) : {2}"),
+    (Message::BeachReportTitle, "Rapport de la plage"),
+    (Message::ReportPopulationCount, "Population : {0}"),
+    (
+        Message::ReportFastestCrab,
+        "Crabe le plus rapide : {0} (vitesse {1})",
+    ),
+    (
+        Message::ReportClanStanding,
+        "{0}. {1} : {2} membres, vitesse moyenne {3}",
+    ),
+    (Message::ReportDiets, "Régimes"),
+    (Message::ReportDietCount, "{0} : {1} ({2} %)"),
+    (Message::CsvLine, "ligne {0} : {1}"),
+    (Message::CsvBadHeader, "l'en-tête doit être {0}"),
+    (
+        Message::CsvColumnCount,
+        "{0} colonnes attendues, {1} trouvées",
+    ),
+    (Message::CsvBadNumber, "pas un nombre : {0}"),
+    (Message::CsvBadColor, "pas une couleur hexadécimale : {0}"),
+    (Message::CsvBadDiet, "régime inconnu : {0}"),
+    (Message::CsvUnterminatedQuote, "guillemet non fermé"),
+    (
+        Message::OutsideTerritory,
+        "({0}, {1}) est hors du territoire de {2}x{3}",
+    ),
+    (
+        Message::BreedTooFar,
+        "{0} et {1} sont trop éloignés pour se reproduire ({2} > {3})",
+    ),
+    (
+        Message::BreedFailedWeather,
+        "{0} et {1} n'ont pas réussi à se reproduire par mauvais temps",
+    ),
+    (
+        Message::InvariantTooOld,
+        "{0} a {1} ticks, au-delà de la durée de vie de {2}",
+    ),
+    (
+        Message::InvariantNameIndex,
+        "chercher {0} trouve {1} crabes, mais {2} vivent sur la plage",
+    ),
+    (Message::BeachFull, "la plage est pleine ({0} crabes)"),
+    (
+        Message::DuplicateBeach,
+        "l'océan a déjà une plage nommée {0}",
+    ),
+    (
+        Message::BinaryTruncated,
+        "la sauvegarde binaire se termine trop tôt",
+    ),
+    (
+        Message::BinaryBadMagic,
+        "pas une plage sauvegardée en binaire",
+    ),
+    (
+        Message::BinaryUnsupportedVersion,
+        "la version {0} du format binaire n'est pas prise en charge (dernière : {1})",
+    ),
+    (
+        Message::BinaryBadValue,
+        "{0} invalide dans la sauvegarde binaire : {1}",
+    ),
+    (
+        Message::NoSuchInhabitant,
+        "rien sur la plage ne s'appelle {0}",
+    ),
+    (Message::DuplicateClan, "il y a déjà un clan nommé {0}"),
+    (
+        Message::MergeWithSelf,
+        "un clan ne peut pas fusionner avec lui-même : {0}",
+    ),
+    (
+        Message::UnknownColor,
+        "ni une couleur hexadécimale ni un nom de couleur : {0}",
+    ),
+    (
+        Message::ClanTooSmall,
+        "le clan {0} a {1} crabes sur la plage, mais il en faut deux pour se reproduire",
+    ),
+    (Message::ScriptInvalid, "script de scénario invalide : {0}"),
+    (
+        Message::BreedCoolingDown,
+        "{0} s'est reproduit trop récemment, et pourra se reproduire dans {1} ticks",
+    ),
+    (
+        Message::CommandUnknown,
+        "commande inconnue : {0} ; essayez help",
+    ),
+    (Message::CommandUsage, "usage : {0}"),
+    (
+        Message::CommandTicked,
+        "tick {0} : {1} naissances, {2} morts",
+    ),
+    (
+        Message::ClanRejectsSlow,
+        "{1} refuse {0} : sa vitesse {2} est inférieure au minimum de {3}",
+    ),
+    (
+        Message::ClanRejectsDiet,
+        "{1} refuse {0} : il mange {2}, mais le clan exige {3}",
+    ),
+    (
+        Message::ClanRejectsFull,
+        "{1} refuse {0} : le clan a déjà ses {2} membres",
+    ),
+    (
+        Message::ClanRejectsAbsent,
+        "{1} refuse {0} : aucun crabe de la plage ne porte ce nom",
+    ),
+    (
+        Message::TooManyShells,
+        "{0} a {1} coquillages et ne peut en garder {2} de plus",
+    ),
+    (Message::ReplayEmpty, "la rediffusion est vide"),
+    (
+        Message::ReplayNoSeed,
+        "une rediffusion doit commencer par sa graine",
+    ),
+    (Message::ReplayBadColor, "pas une couleur : {0}"),
+    (Message::ReplayUnknownOperation, "opération inconnue : {0}"),
+    (
+        Message::ReplayBadEscape,
+        "séquence d'échappement invalide dans {0}",
+    ),
+    (
+        Message::BattleItself,
+        "le crabe à l'indice {0} ne peut pas se battre contre lui-même",
+    ),
+    (Message::SvgNoClan, "sans clan"),
+    (Message::TerminalStatus, "tick {0} | {1} crabes | {2} clans"),
+    (Message::MergeTooLarge, "les clans {0} et {1} ont {2} membres ensemble, plus que les {3} que permet leur constitution"),
+];
+
+fn catalog(locale: Locale) -> Catalog {
+    match locale {
+        Locale::English => ENGLISH,
+        Locale::Spanish => SPANISH,
+        Locale::French => FRENCH,
+    }
+}
+
+thread_local!(
+    static LOCALE: Cell<Locale> = const { Cell::new(Locale::English) };
+);
+
+/** Returns the locale used for messages on this thread. */
+pub fn locale() -> Locale {
+    LOCALE.with(|l| l.get())
+}
+
+/** Sets the locale used for messages on this thread. */
+pub fn set_locale(locale: Locale) {
+    LOCALE.with(|l| l.set(locale));
+}
+
+/**
+ * Returns the raw template for a message in the given locale, falling
+ * back to English.
+ */
+pub fn template(locale: Locale, message: Message) -> &'static str {
+    let find = |catalog: Catalog| {
+        catalog
+            .iter()
+            .find(|(m, _)| *m == message)
+            .map(|(_, template)| *template)
+    };
+    find(catalog(locale))
+        .or_else(|| find(ENGLISH))
+        .expect("every message has an English template")
+}
+
+/**
+ * Renders a message in the current locale, substituting `{0}`, `{1}`...
+ * with the given arguments.
+ */
+pub fn tr(message: Message, args: &[&dyn Display]) -> String {
+    tr_in(locale(), message, args)
+}
+
+/**
+ * Renders a message in the given locale. Placeholders are filled in one
+ * pass, left to right, so an argument that itself reads like a placeholder,
+ * such as a crab named "{1}", is left as it is. A placeholder with no
+ * argument to fill it is kept.
+ */
+pub fn tr_in(locale: Locale, message: Message, args: &[&dyn Display]) -> String {
+    let mut rest = template(locale, message);
+    let mut out = String::with_capacity(rest.len());
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        rest = &rest[open..];
+        let arg = rest
+            .find('}')
+            .and_then(|close| Some((close, rest[1..close].parse::<usize>().ok()?)))
+            .and_then(|(close, k)| Some((close, args.get(k)?)));
+        match arg {
+            Some((close, arg)) => {
+                out.push_str(&arg.to_string());
+                rest = &rest[close + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
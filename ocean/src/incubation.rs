@@ -0,0 +1,127 @@
+use crate::beach::CrabId;
+use crate::crab::Crab;
+use crate::error::OceanError;
+use crate::position::Position;
+
+/** The number of ticks eggs incubate for unless a beach says otherwise. */
+pub const DEFAULT_INCUBATION_TICKS: u64 = 3;
+
+/**
+ * Eggs laid together by two parents, incubating at one spot on the beach.
+ * Each egg is the juvenile crab it will hatch into.
+ */
+#[derive(Debug)]
+pub struct Clutch {
+    pub parents: (CrabId, CrabId),
+    pub parent_names: (String, String),
+    pub position: Position,
+    pub laid_at: u64,
+    pub eggs: Vec<Crab>,
+}
+
+/**
+ * The clutches incubating on a beach. At most one clutch can occupy a
+ * position, and clutches hatch `ticks_to_hatch` ticks after being laid.
+ */
+#[derive(Debug)]
+pub struct Incubator {
+    ticks_to_hatch: u64,
+    // In the order they were laid.
+    clutches: Vec<Clutch>,
+}
+
+impl Incubator {
+    pub fn new(ticks_to_hatch: u64) -> Incubator {
+        Incubator {
+            ticks_to_hatch,
+            clutches: Vec::new(),
+        }
+    }
+
+    pub fn ticks_to_hatch(&self) -> u64 {
+        self.ticks_to_hatch
+    }
+
+    pub fn set_ticks_to_hatch(&mut self, ticks: u64) {
+        self.ticks_to_hatch = ticks;
+    }
+
+    /** Returns the incubating clutches, in the order they were laid. */
+    pub fn clutches(&self) -> &[Clutch] {
+        &self.clutches
+    }
+
+    /** Returns the clutch at the given position, if there is one. */
+    pub fn clutch_at(&self, position: Position) -> Option<&Clutch> {
+        self.clutches.iter().find(|c| c.position == position)
+    }
+
+    /** Returns the number of eggs still incubating. */
+    pub fn egg_count(&self) -> usize {
+        self.clutches.iter().map(|c| c.eggs.len()).sum()
+    }
+
+    /**
+     * Starts incubating a clutch, unless it has no eggs or another clutch
+     * already occupies its position.
+     */
+    pub fn lay(&mut self, clutch: Clutch) -> Result<(), OceanError> {
+        if clutch.eggs.is_empty() {
+            return Err(OceanError::ClutchEmpty);
+        }
+        if self.clutch_at(clutch.position).is_some() {
+            return Err(OceanError::ClutchPositionTaken(clutch.position));
+        }
+        self.clutches.push(clutch);
+        Ok(())
+    }
+
+    /**
+     * A predator eats up to `eggs` eggs from the clutch at the given
+     * position. A clutch left without eggs is gone. Returns the number of
+     * eggs eaten.
+     */
+    pub fn predate(&mut self, position: Position, eggs: usize) -> usize {
+        let Some(k) = self.clutches.iter().position(|c| c.position == position) else {
+            return 0;
+        };
+        let clutch = &mut self.clutches[k];
+        let eaten = eggs.min(clutch.eggs.len());
+        clutch.eggs.truncate(clutch.eggs.len() - eaten);
+        if clutch.eggs.is_empty() {
+            self.clutches.remove(k);
+        }
+        eaten
+    }
+
+    /**
+     * The tide rises up the beach from the water's edge at `y = 0`, washing
+     * away every clutch laid below `level`. Returns the number of eggs lost.
+     */
+    pub fn tide(&mut self, level: u32) -> usize {
+        let mut lost = 0;
+        self.clutches.retain(|clutch| {
+            let washed_away = clutch.position.y < level;
+            if washed_away {
+                lost += clutch.eggs.len();
+            }
+            !washed_away
+        });
+        lost
+    }
+
+    /** Removes and returns the clutches that are due to hatch at `now`. */
+    pub fn take_hatched(&mut self, now: u64) -> Vec<Clutch> {
+        let (hatched, incubating) = std::mem::take(&mut self.clutches)
+            .into_iter()
+            .partition(|c| now >= c.laid_at + self.ticks_to_hatch);
+        self.clutches = incubating;
+        hatched
+    }
+}
+
+impl Default for Incubator {
+    fn default() -> Self {
+        Incubator::new(DEFAULT_INCUBATION_TICKS)
+    }
+}
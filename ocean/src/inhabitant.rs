@@ -0,0 +1,120 @@
+use crate::color::Color;
+use crate::crab::Crab;
+use crate::diet::Diet;
+use crate::speed::Speed;
+use std::any::Any;
+use std::fmt::Debug;
+
+/**
+ * Something that lives on a beach: a crab, or another species. Every
+ * inhabitant can join clans and run races; crab-specific operations such
+ * as breeding, hunting and the life rules apply to crabs only.
+ */
+pub trait Inhabitant: Debug + Any {
+    fn name(&self) -> &str;
+    fn speed(&self) -> u32;
+    fn diet(&self) -> Diet;
+    fn color(&self) -> &Color;
+
+    /** Returns the inhabitant's speed as a `Speed`, whose arithmetic cannot overflow. */
+    fn typed_speed(&self) -> Speed {
+        Speed::new(self.speed())
+    }
+
+    /** The name of the inhabitant's species, such as "crab". */
+    fn species(&self) -> &str;
+
+    /** Returns the inhabitant as `Any`, so it can be downcast to its type. */
+    fn as_any(&self) -> &dyn Any;
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl dyn Inhabitant {
+    /** Returns the inhabitant as a `T`, if that is what it is. */
+    pub fn downcast_ref<T: Inhabitant>(&self) -> Option<&T> {
+        self.as_any().downcast_ref()
+    }
+
+    pub fn is<T: Inhabitant>(&self) -> bool {
+        self.as_any().is::<T>()
+    }
+}
+
+impl Inhabitant for Crab {
+    fn name(&self) -> &str {
+        Crab::name(self)
+    }
+
+    fn speed(&self) -> u32 {
+        Crab::speed(self)
+    }
+
+    fn diet(&self) -> Diet {
+        Crab::diet(self)
+    }
+
+    fn color(&self) -> &Color {
+        Crab::color(self)
+    }
+
+    fn species(&self) -> &str {
+        "crab"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/** A lobster: slower than most crabs, and always after fish. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lobster {
+    name: String,
+    speed: u32,
+    color: Color,
+}
+
+impl Lobster {
+    pub fn new(name: &str, speed: u32, color: Color) -> Lobster {
+        Lobster {
+            name: name.to_string(),
+            speed,
+            color,
+        }
+    }
+}
+
+impl Inhabitant for Lobster {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn speed(&self) -> u32 {
+        self.speed
+    }
+
+    fn diet(&self) -> Diet {
+        Diet::Fish
+    }
+
+    fn color(&self) -> &Color {
+        &self.color
+    }
+
+    fn species(&self) -> &str {
+        "lobster"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
@@ -0,0 +1,117 @@
+use crate::position::Position;
+
+/** A rectangle of the beach, including its corners. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub min: Position,
+    pub max: Position,
+}
+
+impl Region {
+    /** Returns the region spanning two opposite corners, in either order. */
+    pub fn new(a: Position, b: Position) -> Region {
+        Region {
+            min: Position::new(a.x.min(b.x), a.y.min(b.y)),
+            max: Position::new(a.x.max(b.x), a.y.max(b.y)),
+        }
+    }
+
+    pub fn contains(&self, position: Position) -> bool {
+        (self.min.x..=self.max.x).contains(&position.x)
+            && (self.min.y..=self.max.y).contains(&position.y)
+    }
+}
+
+/** Something humans do to a beach. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interference {
+    /** Fishers take `count` crabs picked at random. */
+    Fishing { count: usize, seed: u64 },
+    /**
+     * A cleanup crew blocks off a region for `ticks` ticks: no crab can move
+     * into it, forage in it or lay eggs in it until they leave.
+     */
+    Cleanup { region: Region, ticks: u64 },
+}
+
+/** An interference that happened, and the crabs it took off the beach. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterferenceRecord {
+    pub tick: u64,
+    pub interference: Interference,
+    pub removed: Vec<String>,
+}
+
+/** The interference a beach has planned, is undergoing and went through. */
+#[derive(Debug, Default)]
+pub struct Humans {
+    // In the order they were scheduled.
+    scheduled: Vec<(u64, Interference)>,
+    // Each region with the tick it opens again.
+    blocked: Vec<(Region, u64)>,
+    log: Vec<InterferenceRecord>,
+}
+
+impl Humans {
+    pub fn new() -> Humans {
+        Humans {
+            scheduled: Vec::new(),
+            blocked: Vec::new(),
+            log: Vec::new(),
+        }
+    }
+
+    pub fn schedule(&mut self, tick: u64, interference: Interference) {
+        self.scheduled.push((tick, interference));
+    }
+
+    /** Returns the interference still to come, as (tick, interference). */
+    pub fn scheduled(&self) -> &[(u64, Interference)] {
+        &self.scheduled
+    }
+
+    /** Removes and returns the interference due at or before `now`. */
+    pub fn take_due(&mut self, now: u64) -> Vec<Interference> {
+        let mut due = Vec::new();
+        self.scheduled.retain(|&(tick, interference)| {
+            if tick <= now {
+                due.push(interference);
+            }
+            tick > now
+        });
+        due
+    }
+
+    /** Blocks off a region until the given tick. */
+    pub fn block(&mut self, region: Region, until: u64) {
+        self.blocked.push((region, until));
+    }
+
+    /** Reopens the regions whose cleanup is over at `now`. */
+    pub fn reopen(&mut self, now: u64) {
+        self.blocked.retain(|&(_, until)| until > now);
+    }
+
+    /** Returns the blocked regions, each with the tick it opens again. */
+    pub fn blocked(&self) -> &[(Region, u64)] {
+        &self.blocked
+    }
+
+    /** Returns when the position opens again, if it is blocked off. */
+    pub fn blocked_until(&self, position: Position) -> Option<u64> {
+        self.blocked
+            .iter()
+            .filter(|(region, _)| region.contains(position))
+            .map(|&(_, until)| until)
+            .max()
+    }
+
+    pub fn record(&mut self, record: InterferenceRecord) {
+        self.log.push(record);
+    }
+
+    /** Returns every interference that happened, oldest first. */
+    pub fn log(&self) -> &[InterferenceRecord] {
+        &self.log
+    }
+}
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/** A cheap, copyable stand-in for a name stored in an `Interner`. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+/**
+ * Stores each distinct name once and hands out symbols for it. Symbols are
+ * only meaningful to the interner that created them.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    symbols: HashMap<Rc<str>, Symbol>,
+    names: Vec<Rc<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Interner {
+        Interner {
+            symbols: HashMap::new(),
+            names: Vec::new(),
+        }
+    }
+
+    /** Returns the symbol for a name, storing the name if it is new. */
+    pub fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(&symbol) = self.symbols.get(name) {
+            return symbol;
+        }
+        let symbol = Symbol(self.names.len() as u32);
+        let name: Rc<str> = Rc::from(name);
+        self.names.push(Rc::clone(&name));
+        self.symbols.insert(name, symbol);
+        symbol
+    }
+
+    /** Returns the symbol for a name that has been interned, without storing it. */
+    pub fn get(&self, name: &str) -> Option<Symbol> {
+        self.symbols.get(name).copied()
+    }
+
+    /**
+     * Returns the name a symbol stands for.
+     * Panics if the symbol came from another interner.
+     */
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        &self.names[symbol.0 as usize]
+    }
+
+    /** Returns the number of distinct names stored. */
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /** Estimates the bytes used to store the names and their symbols. */
+    pub fn memory_bytes(&self) -> usize {
+        let text: usize = self.names.iter().map(|name| name.len()).sum();
+        text + self.names.capacity() * std::mem::size_of::<Rc<str>>()
+            + self.symbols.capacity() * std::mem::size_of::<(Rc<str>, Symbol)>()
+    }
+}
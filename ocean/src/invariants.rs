@@ -0,0 +1,133 @@
+use crate::beach::{Beach, CrabId};
+use crate::constraints::ConstraintError;
+use crate::i18n::{tr, Message};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/**
+ * Something about a beach that every operation on it should leave true,
+ * but that does not hold.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvariantViolation {
+    /** A clan lists a member that no longer lives on the beach. */
+    MemberMissing { clan_id: String, crab_name: String },
+    /** A crab belongs to more than one clan. */
+    MultipleClans {
+        crab_name: String,
+        clan_ids: Vec<String>,
+    },
+    /** A crab's trait lies outside the range the beach's constraints allow. */
+    OutOfBounds(ConstraintError),
+    /** A crab has outlived the lifespan the beach's life rules allow. */
+    TooOld {
+        crab_name: String,
+        age: u64,
+        lifespan: u64,
+    },
+    /** Looking crabs up by name finds a different number than live there. */
+    NameIndexOutOfStep {
+        name: String,
+        indexed: usize,
+        actual: usize,
+    },
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = match self {
+            InvariantViolation::MemberMissing { clan_id, crab_name } => {
+                tr(Message::AuditMemberMissing, &[crab_name, clan_id])
+            }
+            InvariantViolation::MultipleClans {
+                crab_name,
+                clan_ids,
+            } => tr(
+                Message::AuditMultipleClans,
+                &[crab_name, &clan_ids.join(", ")],
+            ),
+            InvariantViolation::OutOfBounds(error) => error.to_string(),
+            InvariantViolation::TooOld {
+                crab_name,
+                age,
+                lifespan,
+            } => tr(Message::InvariantTooOld, &[crab_name, age, lifespan]),
+            InvariantViolation::NameIndexOutOfStep {
+                name,
+                indexed,
+                actual,
+            } => tr(Message::InvariantNameIndex, &[name, indexed, actual]),
+        };
+        write!(f, "{}", text)
+    }
+}
+
+impl Beach {
+    /**
+     * Checks that the beach's crabs, clans, constraints and life rules agree
+     * with each other, returning every violation found if they do not. Every
+     * operation on a beach should leave this Ok, so tests can check it after
+     * each one.
+     */
+    pub fn check_invariants(&self) -> Result<(), Vec<InvariantViolation>> {
+        let mut violations = Vec::new();
+
+        let mut names: BTreeMap<&str, usize> = BTreeMap::new();
+        let lifespan = self.life_rules().lifespan;
+        for (index, crab) in self.crabs().enumerate() {
+            *names.entry(crab.name()).or_default() += 1;
+            if let Err(error) = self.constraints().check(crab) {
+                violations.push(InvariantViolation::OutOfBounds(error));
+            }
+            let age = self.age(index);
+            if lifespan > 0 && age > lifespan {
+                violations.push(InvariantViolation::TooOld {
+                    crab_name: crab.name().to_string(),
+                    age,
+                    lifespan,
+                });
+            }
+        }
+        for (&name, &actual) in &names {
+            let indexed = self.find_crabs_by_name(name).len();
+            if indexed != actual {
+                violations.push(InvariantViolation::NameIndexOutOfStep {
+                    name: name.to_string(),
+                    indexed,
+                    actual,
+                });
+            }
+        }
+
+        let clans = self.get_clan_system();
+        let mut memberships: BTreeMap<CrabId, Vec<String>> = BTreeMap::new();
+        for clan_id in clans.clan_ids() {
+            for &member in clans.clan_members(clan_id) {
+                if self.inhabitant_name(member).is_none() {
+                    violations.push(InvariantViolation::MemberMissing {
+                        clan_id: clan_id.to_string(),
+                        crab_name: clans.describe(member),
+                    });
+                }
+                memberships
+                    .entry(member)
+                    .or_default()
+                    .push(clan_id.to_string());
+            }
+        }
+        for (member, clan_ids) in memberships {
+            if clan_ids.len() > 1 {
+                violations.push(InvariantViolation::MultipleClans {
+                    crab_name: clans.describe(member),
+                    clan_ids,
+                });
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use crate::beach::CrabId;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/** The rating every crab and clan starts with. */
+pub const INITIAL_RATING: f64 = 1500.0;
+
+/** How far a single result can move a rating, unless set otherwise. */
+pub const DEFAULT_K_FACTOR: f64 = 32.0;
+
+/** A competitor's Elo rating, with the results it was earned by. */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rating {
+    pub rating: f64,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+impl Rating {
+    fn new() -> Rating {
+        Rating {
+            rating: INITIAL_RATING,
+            wins: 0,
+            losses: 0,
+            draws: 0,
+        }
+    }
+
+    /** Returns how many results the rating was earned by. */
+    pub fn games(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+
+    /** Returns the score this competitor is expected to get against `other`. */
+    fn expected(&self, other: &Rating) -> f64 {
+        1.0 / (1.0 + 10f64.powf((other.rating - self.rating) / 400.0))
+    }
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Rating::new()
+    }
+}
+
+/**
+ * The ratings of competitors of one kind, updated pairwise: every result
+ * between two competitors moves each rating by up to `k_factor`, by how much
+ * better or worse it was than their ratings led to expect.
+ */
+#[derive(Debug, Clone)]
+struct Ratings<K> {
+    ratings: HashMap<K, Rating>,
+}
+
+impl<K: Eq + Hash + Clone + Ord> Ratings<K> {
+    fn new() -> Ratings<K> {
+        Ratings {
+            ratings: HashMap::new(),
+        }
+    }
+
+    fn get(&self, id: &K) -> Option<&Rating> {
+        self.ratings.get(id)
+    }
+
+    /**
+     * Records a result between `a` and `b`: `score` is 1 if `a` won, 0 if
+     * `b` won, and 0.5 for a draw. Both ratings move by `k_factor` times the
+     * difference from the expected score.
+     */
+    fn record(&mut self, a: &K, b: &K, score: f64, k_factor: f64) {
+        let rating_a = self.ratings.get(a).copied().unwrap_or_default();
+        let rating_b = self.ratings.get(b).copied().unwrap_or_default();
+        let change = k_factor * (score - rating_a.expected(&rating_b));
+        for (id, sign, score) in [(a, 1.0, score), (b, -1.0, 1.0 - score)] {
+            let rating = self.ratings.entry(id.clone()).or_default();
+            rating.rating += sign * change;
+            if score > 0.5 {
+                rating.wins += 1;
+            } else if score < 0.5 {
+                rating.losses += 1;
+            } else {
+                rating.draws += 1;
+            }
+        }
+    }
+
+    /**
+     * Returns the `k` highest ratings, highest first; equal ratings are
+     * ordered by id.
+     */
+    fn top(&self, k: usize) -> Vec<(&K, &Rating)> {
+        let mut ranked: Vec<(&K, &Rating)> = self.ratings.iter().collect();
+        ranked.sort_by(|(id1, r1), (id2, r2)| {
+            r2.rating.total_cmp(&r1.rating).then_with(|| id1.cmp(id2))
+        });
+        ranked.truncate(k);
+        ranked
+    }
+}
+
+/**
+ * The cumulative results of crab races and clan competitions on a beach,
+ * as Elo ratings. Crabs and clans keep their ratings after they leave, so
+ * the rankings cover the beach's whole history.
+ */
+#[derive(Debug, Clone)]
+pub struct Leaderboard {
+    pub k_factor: f64,
+    crabs: Ratings<CrabId>,
+    clans: Ratings<String>,
+    races: usize,
+    competitions: usize,
+}
+
+impl Leaderboard {
+    pub fn new() -> Leaderboard {
+        Leaderboard {
+            k_factor: DEFAULT_K_FACTOR,
+            crabs: Ratings::new(),
+            clans: Ratings::new(),
+            races: 0,
+            competitions: 0,
+        }
+    }
+
+    /** Returns a crab's rating, if it has raced. */
+    pub fn crab_rating(&self, id: CrabId) -> Option<&Rating> {
+        self.crabs.get(&id)
+    }
+
+    /** Returns a clan's rating, if it has competed. */
+    pub fn clan_rating(&self, clan_id: &str) -> Option<&Rating> {
+        self.clans.get(&clan_id.to_string())
+    }
+
+    /** Returns how many races have been recorded. */
+    pub fn races(&self) -> usize {
+        self.races
+    }
+
+    /** Returns how many clan competitions have been recorded. */
+    pub fn competitions(&self) -> usize {
+        self.competitions
+    }
+
+    /**
+     * Records a race from its results: the crabs in the order they
+     * finished, with crabs that finished together in the same place. Every
+     * crab beats every crab behind it and draws with the ones beside it; so
+     * that a race counts as much as a single result however many ran, each
+     * of these counts for a share of it.
+     */
+    pub fn record_race(&mut self, places: &[Vec<CrabId>]) {
+        let runners: Vec<(usize, CrabId)> = places
+            .iter()
+            .enumerate()
+            .flat_map(|(place, ids)| ids.iter().map(move |&id| (place, id)))
+            .collect();
+        if runners.len() < 2 {
+            return;
+        }
+        let k_factor = self.k_factor / (runners.len() - 1) as f64;
+        for (i, &(place1, id1)) in runners.iter().enumerate() {
+            for &(place2, id2) in &runners[i + 1..] {
+                let score = if place1 == place2 { 0.5 } else { 1.0 };
+                self.crabs.record(&id1, &id2, score, k_factor);
+            }
+        }
+        self.races += 1;
+    }
+
+    /**
+     * Records a competition between two clans, won by `winner`, or drawn if
+     * there is none.
+     */
+    pub fn record_competition(&mut self, id1: &str, id2: &str, winner: Option<&str>) {
+        let score = match winner {
+            Some(winner) if winner == id1 => 1.0,
+            Some(_) => 0.0,
+            None => 0.5,
+        };
+        self.clans
+            .record(&id1.to_string(), &id2.to_string(), score, self.k_factor);
+        self.competitions += 1;
+    }
+
+    /** Returns the `k` highest rated crabs, highest first. */
+    pub fn top_crabs(&self, k: usize) -> Vec<(CrabId, Rating)> {
+        self.crabs
+            .top(k)
+            .into_iter()
+            .map(|(&id, &rating)| (id, rating))
+            .collect()
+    }
+
+    /** Returns the `k` highest rated clans, highest first. */
+    pub fn top_clans(&self, k: usize) -> Vec<(&str, Rating)> {
+        self.clans
+            .top(k)
+            .into_iter()
+            .map(|(id, &rating)| (id.as_str(), rating))
+            .collect()
+    }
+}
+
+impl Default for Leaderboard {
+    fn default() -> Self {
+        Leaderboard::new()
+    }
+}
@@ -1,10 +1,93 @@
+pub mod achievements;
+pub mod analysis;
+pub mod audit;
+pub mod batch;
+pub mod battle;
 pub mod beach;
+#[cfg(feature = "binary")]
+pub mod binary;
+pub mod breeding;
+pub mod builder;
+pub mod capacity;
 pub mod color;
+pub mod command;
+pub mod competition;
+pub mod constraints;
 pub mod cookbook;
 pub mod crab;
+pub mod csv;
 pub mod diet;
+pub mod disaster;
+pub mod distribution;
+pub mod economy;
+pub mod encounter;
+pub mod environment;
+pub mod error;
+pub mod event;
+pub mod food;
+#[cfg(feature = "arrow")]
+pub mod frame;
+pub mod genealogy;
+pub mod genetics;
+pub mod graph;
+pub mod heatmap;
+pub mod history;
+pub mod i18n;
+pub mod incubation;
+pub mod inhabitant;
+pub mod interference;
+pub mod leaderboard;
+pub mod intern;
+pub mod invariants;
+pub mod life;
+pub mod lockstep;
+pub mod merge;
+pub mod metrics;
+pub mod movement;
+pub mod names;
+pub mod observe;
 pub mod ocean;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+#[cfg(feature = "serde")]
+pub mod persist;
+pub mod pollution;
+pub mod position;
+pub mod predator;
 pub mod prey;
+pub mod protection;
+pub mod query;
+pub mod race;
+pub mod raid;
 pub mod rand;
 pub mod reef;
+pub mod report;
+#[cfg(feature = "cli")]
+pub mod repl;
+pub mod rng;
+pub mod rules;
+pub mod scenario;
+pub mod scheduler;
+#[cfg(feature = "toml")]
+pub mod script;
+pub mod server;
+pub mod shared;
+pub mod simulation;
+pub mod speed;
+pub mod spec;
+pub mod store;
+#[cfg(feature = "async")]
+pub mod stream;
+pub mod survey;
+pub mod svg;
+pub mod terminal;
+pub mod territory;
+pub mod tidepool;
+pub mod timeline;
+pub mod tournament;
+pub mod trade;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod watch;
+pub mod weather;
 pub mod clans;
@@ -0,0 +1,172 @@
+use crate::diet::Diet;
+use crate::predator::Hunt;
+
+/**
+ * How crabs live and die as the beach clock advances. Every rule is off by
+ * default, so crabs neither age, hunt nor die on their own.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LifeRules {
+    /** Ticks a crab lives; crabs older than this die. 0 for no limit. */
+    pub lifespan: u64,
+    /**
+     * Ticks a crab can go without eating before it is hungry and hunts on
+     * every tick until it eats. 0 for crabs that never hunt on their own.
+     * Crabs that do hunt also `Crab::advance` once each tick.
+     */
+    pub appetite: u64,
+    /** Health a hungry crab loses each tick it fails to eat. */
+    pub starvation: u32,
+    /** Health a crab regains when it eats. */
+    pub nourishment: u32,
+    /** Crabs younger than this gain 1 speed each tick. */
+    pub maturity: u64,
+    /** Crabs older than this lose 1 speed each tick, down to 1. 0 for never. */
+    pub senescence: u64,
+}
+
+/** Hunger at which a crab becomes hungry. */
+pub const HUNGRY_AT: u32 = 5;
+
+/** Hunger at which a crab starts starving. */
+pub const STARVING_AT: u32 = 10;
+
+/** Health a starving crab loses each time it advances. */
+pub const STARVATION_DAMAGE: u32 = 10;
+
+/**
+ * How a crab is faring. Crabs grow hungry, then start starving, as they go
+ * without eating; eating makes them healthy again. A crab whose health runs
+ * out is dead, and stays dead.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LifeState {
+    Healthy,
+    Hungry,
+    Starving,
+    Dead,
+}
+
+/** Where a crab is in its life, by its age. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LifeStage {
+    Juvenile,
+    Adult,
+    Elder,
+}
+
+/**
+ * How a crab's speed follows its age in years, as `Beach::age_all` ages
+ * it. A crab is a juvenile until `maturity`, gaining `growth` speed each
+ * year; an adult, holding its speed, until `peak`; and an elder after,
+ * losing `decline` speed each year, down to 1.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LifecycleConfig {
+    pub maturity: u64,
+    pub peak: u64,
+    pub growth: u32,
+    pub decline: u32,
+}
+
+impl Default for LifecycleConfig {
+    fn default() -> Self {
+        LifecycleConfig {
+            maturity: 2,
+            peak: 10,
+            growth: 1,
+            decline: 1,
+        }
+    }
+}
+
+impl LifecycleConfig {
+    /** Returns the stage of life of a crab of the given age. */
+    pub fn stage(&self, age: u64) -> LifeStage {
+        if age < self.maturity {
+            LifeStage::Juvenile
+        } else if age <= self.peak {
+            LifeStage::Adult
+        } else {
+            LifeStage::Elder
+        }
+    }
+
+    /** Returns the speed a crab of the given speed and age has a year later. */
+    pub fn speed_after_year(&self, speed: u32, age: u64) -> u32 {
+        if age < self.maturity {
+            speed.saturating_add(self.growth)
+        } else if age >= self.peak {
+            speed.saturating_sub(self.decline).max(1)
+        } else {
+            speed
+        }
+    }
+}
+
+/** Why a crab left the beach for good. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Cause {
+    OldAge,
+    Starvation,
+    Seagulls,
+    Predation,
+    Fishing,
+    /** Washed away by the high tide. */
+    Tide,
+    /** Killed by a disaster; see `Beach::apply_disaster`. */
+    Disaster,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Death {
+    pub name: String,
+    pub cause: Cause,
+}
+
+/** A crab that went hunting during a tick. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Meal {
+    pub name: String,
+    pub diet: Diet,
+    /** Whether the crab caught prey it could eat. */
+    pub caught: bool,
+}
+
+/** What happened on a beach during one tick. */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TickReport {
+    /** The tick the report is for. */
+    pub tick: u64,
+    /** The names of the juveniles that hatched. */
+    pub births: Vec<String>,
+    pub deaths: Vec<Death>,
+    /** Every hunt, in the order the crabs live on the beach. */
+    pub meals: Vec<Meal>,
+    /** Every predator's hunt, in the order the predators were added. */
+    pub hunts: Vec<Hunt>,
+    /** The number of crabs on the beach at the end of the tick. */
+    pub population: usize,
+}
+
+impl TickReport {
+    /** Returns how many hunts caught prey of the given diet. */
+    pub fn meals_of(&self, diet: Diet) -> usize {
+        self.meals
+            .iter()
+            .filter(|meal| meal.caught && meal.diet == diet)
+            .count()
+    }
+
+    /** Returns how many crabs died of the given cause. */
+    pub fn deaths_from(&self, cause: Cause) -> usize {
+        self.deaths
+            .iter()
+            .filter(|death| death.cause == cause)
+            .count()
+    }
+}
@@ -0,0 +1,211 @@
+use crate::i18n::{tr, Message};
+use crate::simulation::{Operation, Simulation};
+use crate::timeline::WorldState;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+
+pub type PeerId = usize;
+
+/**
+ * A 64-bit FNV-1a hasher. Unlike the standard library's default hasher its
+ * output is fixed, and lengths are hashed as 64-bit numbers, so peers on
+ * different platforms or Rust versions agree on every hash.
+ */
+struct Fnv(u64);
+
+impl Hasher for Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_usize(&mut self, n: usize) {
+        self.write(&(n as u64).to_le_bytes());
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/** Returns a hash of everything observable about a world. */
+pub fn state_hash(state: &WorldState) -> u64 {
+    let mut hasher = Fnv(0xcbf2_9ce4_8422_2325);
+    state.hash(&mut hasher);
+    hasher.finish()
+}
+
+/** What peers send each other. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LockstepMessage {
+    /** The commands a peer issues for a turn; an empty list still counts. */
+    Commands {
+        peer: PeerId,
+        turn: u64,
+        operations: Vec<Operation>,
+    },
+    /** The hash of a peer's world at the end of a tick. */
+    Checksum { peer: PeerId, tick: u64, hash: u64 },
+}
+
+/**
+ * One peer of a deterministic lockstep game.
+ *
+ * All peers start from the same seed and exchange only their commands. Turn
+ * `t` runs once commands for it have arrived from every peer: each peer's
+ * commands are applied in peer order, then the beach ticks. Because the
+ * simulation is deterministic, every peer ends each turn in the same state,
+ * which they check by trading a state hash every `hash_interval` ticks.
+ *
+ * The simulation draws from this thread's random number generator, so each
+ * peer needs a thread (or process) of its own.
+ */
+#[derive(Debug)]
+pub struct Peer {
+    id: PeerId,
+    peers: usize,
+    hash_interval: u64,
+    simulation: Simulation,
+    // Turn -> peer -> the commands it issued for that turn.
+    commands: BTreeMap<u64, BTreeMap<PeerId, Vec<Operation>>>,
+    // Tick -> the hash of this peer's world at the end of it.
+    hashes: BTreeMap<u64, u64>,
+    // Checksums received for ticks this peer has not reached yet.
+    remote: Vec<(PeerId, u64, u64)>,
+    desync: Option<(PeerId, u64)>,
+}
+
+impl Peer {
+    /**
+     * Creates peer `id` of `peers`, with a checksum every `hash_interval`
+     * ticks (at least 1).
+     */
+    pub fn new(id: PeerId, peers: usize, seed: u64, hash_interval: u64) -> Peer {
+        Peer {
+            id,
+            peers,
+            hash_interval: hash_interval.max(1),
+            simulation: Simulation::new(seed),
+            commands: BTreeMap::new(),
+            hashes: BTreeMap::new(),
+            remote: Vec::new(),
+            desync: None,
+        }
+    }
+
+    pub fn id(&self) -> PeerId {
+        self.id
+    }
+
+    pub fn simulation(&self) -> &Simulation {
+        &self.simulation
+    }
+
+    /** The next turn to run, which is also the number of ticks so far. */
+    pub fn turn(&self) -> u64 {
+        self.simulation.beach().ticks()
+    }
+
+    /** The first tick at which a peer's hash differed from ours, if any. */
+    pub fn desync(&self) -> Option<(PeerId, u64)> {
+        self.desync
+    }
+
+    /**
+     * Issues this peer's commands for a turn, returning the message to send
+     * to the other peers.
+     */
+    pub fn issue(&mut self, turn: u64, operations: Vec<Operation>) -> LockstepMessage {
+        self.commands
+            .entry(turn)
+            .or_default()
+            .insert(self.id, operations.clone());
+        LockstepMessage::Commands {
+            peer: self.id,
+            turn,
+            operations,
+        }
+    }
+
+    /**
+     * Handles a message from another peer. Fails when a checksum shows that
+     * the peer's world has diverged from ours.
+     */
+    pub fn receive(&mut self, message: LockstepMessage) -> Result<(), String> {
+        match message {
+            LockstepMessage::Commands {
+                peer,
+                turn,
+                operations,
+            } => {
+                self.commands
+                    .entry(turn)
+                    .or_default()
+                    .insert(peer, operations);
+                Ok(())
+            }
+            LockstepMessage::Checksum { peer, tick, hash } => match self.hashes.get(&tick) {
+                Some(&ours) => self.verify(peer, tick, ours, hash),
+                None => {
+                    self.remote.push((peer, tick, hash));
+                    Ok(())
+                }
+            },
+        }
+    }
+
+    fn verify(&mut self, peer: PeerId, tick: u64, ours: u64, theirs: u64) -> Result<(), String> {
+        if ours == theirs {
+            return Ok(());
+        }
+        if self.desync.is_none_or(|(_, first)| tick < first) {
+            self.desync = Some((peer, tick));
+        }
+        Err(tr(Message::LockstepDesync, &[&peer, &tick]))
+    }
+
+    /** Returns whether every peer's commands for the next turn have arrived. */
+    pub fn ready(&self) -> bool {
+        self.commands
+            .get(&self.turn())
+            .is_some_and(|issued| issued.len() == self.peers)
+    }
+
+    /**
+     * Runs the next turn if it is ready. Returns the checksum to send to the
+     * other peers if the turn ended on a hash tick, and fails if an earlier
+     * checksum from another peer disagrees with ours.
+     */
+    pub fn advance(&mut self) -> Result<Option<LockstepMessage>, String> {
+        if !self.ready() {
+            return Ok(None);
+        }
+        let turn = self.turn();
+        for (_, operations) in self.commands.remove(&turn).unwrap_or_default() {
+            for operation in operations {
+                self.simulation.apply(operation);
+            }
+        }
+        self.simulation.apply(Operation::Tick);
+
+        let tick = self.turn();
+        if !tick.is_multiple_of(self.hash_interval) {
+            return Ok(None);
+        }
+        let hash = state_hash(&WorldState::capture(self.simulation.beach()));
+        self.hashes.insert(tick, hash);
+
+        let (due, waiting): (Vec<_>, Vec<_>) = self.remote.drain(..).partition(|r| r.1 == tick);
+        self.remote = waiting;
+        for (peer, _, theirs) in due {
+            self.verify(peer, tick, hash, theirs)?;
+        }
+        Ok(Some(LockstepMessage::Checksum {
+            peer: self.id,
+            tick,
+            hash,
+        }))
+    }
+}
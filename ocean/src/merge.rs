@@ -0,0 +1,182 @@
+use crate::timeline::{CrabState, WorldState};
+use std::collections::BTreeMap;
+
+/** Which copy of the world wins a conflict. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pick {
+    Ours,
+    Theirs,
+}
+
+/**
+ * A crab or clan that both copies changed, in different ways, since the
+ * common base. None means the copy has no such crab or clan.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conflict<'a> {
+    Crab {
+        name: &'a str,
+        base: Option<&'a CrabState>,
+        ours: Option<&'a CrabState>,
+        theirs: Option<&'a CrabState>,
+    },
+    Clan {
+        clan_id: &'a str,
+        base: Option<&'a [String]>,
+        ours: Option<&'a [String]>,
+        theirs: Option<&'a [String]>,
+    },
+}
+
+/** Identifies a conflict that was resolved during a merge. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolved {
+    Crab { name: String, pick: Pick },
+    Clan { clan_id: String, pick: Pick },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeOutcome {
+    pub state: WorldState,
+    /** The conflicts found, in the order they were resolved. */
+    pub conflicts: Vec<Resolved>,
+}
+
+/**
+ * Merges the value of one crab or clan: a change on one side only is kept,
+ * and Err means both sides changed it differently.
+ */
+fn three_way<'a, T: PartialEq + ?Sized>(
+    base: Option<&'a T>,
+    ours: Option<&'a T>,
+    theirs: Option<&'a T>,
+) -> Result<Option<&'a T>, ()> {
+    if ours == theirs || theirs == base {
+        Ok(ours)
+    } else if ours == base {
+        Ok(theirs)
+    } else {
+        Err(())
+    }
+}
+
+/** Appends the keys of `from` that are not in `keys` yet, in order. */
+fn union<'a>(keys: &mut Vec<&'a str>, from: impl Iterator<Item = &'a str>) {
+    for key in from {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+}
+
+/**
+ * Merges two copies of a world that diverged from a common base, such as two
+ * people editing the same scenario.
+ *
+ * Crabs are matched by name and clans by id. A crab or clan changed by only
+ * one side takes that side's version, including being added or removed;
+ * when both sides changed it differently, `resolve` picks the version to
+ * keep. The merged world is at the later of the two ticks.
+ */
+pub fn merge<F>(
+    base: &WorldState,
+    ours: &WorldState,
+    theirs: &WorldState,
+    mut resolve: F,
+) -> MergeOutcome
+where
+    F: FnMut(&Conflict) -> Pick,
+{
+    let mut conflicts = Vec::new();
+    let find = |world: &'_ WorldState, name: &str| -> Option<CrabState> {
+        world.crabs().iter().find(|crab| crab.name == name).cloned()
+    };
+
+    let mut names = Vec::new();
+    for world in [base, ours, theirs] {
+        union(
+            &mut names,
+            world.crabs().iter().map(|crab| crab.name.as_str()),
+        );
+    }
+    let mut crabs = Vec::new();
+    for name in names {
+        let (b, o, t) = (find(base, name), find(ours, name), find(theirs, name));
+        let merged = match three_way(b.as_ref(), o.as_ref(), t.as_ref()) {
+            Ok(merged) => merged,
+            Err(()) => {
+                let pick = resolve(&Conflict::Crab {
+                    name,
+                    base: b.as_ref(),
+                    ours: o.as_ref(),
+                    theirs: t.as_ref(),
+                });
+                conflicts.push(Resolved::Crab {
+                    name: name.to_string(),
+                    pick,
+                });
+                match pick {
+                    Pick::Ours => o.as_ref(),
+                    Pick::Theirs => t.as_ref(),
+                }
+            }
+        };
+        crabs.extend(merged.cloned());
+    }
+
+    let members = |world: &'_ WorldState, id: &str| -> Option<Vec<String>> {
+        world
+            .clan_ids()
+            .any(|c| c == id)
+            .then(|| world.clan_members(id).to_vec())
+    };
+    let mut ids = Vec::new();
+    for world in [base, ours, theirs] {
+        union(&mut ids, world.clan_ids());
+    }
+    let mut clans = BTreeMap::new();
+    for clan_id in ids {
+        let (b, o, t) = (
+            members(base, clan_id),
+            members(ours, clan_id),
+            members(theirs, clan_id),
+        );
+        let merged = match three_way(b.as_deref(), o.as_deref(), t.as_deref()) {
+            Ok(merged) => merged,
+            Err(()) => {
+                let pick = resolve(&Conflict::Clan {
+                    clan_id,
+                    base: b.as_deref(),
+                    ours: o.as_deref(),
+                    theirs: t.as_deref(),
+                });
+                conflicts.push(Resolved::Clan {
+                    clan_id: clan_id.to_string(),
+                    pick,
+                });
+                match pick {
+                    Pick::Ours => o.as_deref(),
+                    Pick::Theirs => t.as_deref(),
+                }
+            }
+        };
+        if let Some(merged) = merged {
+            clans.insert(clan_id.to_string(), merged.to_vec());
+        }
+    }
+
+    MergeOutcome {
+        state: WorldState::new(ours.tick().max(theirs.tick()), crabs, clans),
+        conflicts,
+    }
+}
+
+/** Merges two copies of a world, resolving every conflict the same way. */
+pub fn merge_with_policy(
+    base: &WorldState,
+    ours: &WorldState,
+    theirs: &WorldState,
+    policy: Pick,
+) -> MergeOutcome {
+    merge(base, ours, theirs, |_| policy)
+}
@@ -0,0 +1,190 @@
+use crate::beach::Beach;
+use crate::csv::quote;
+use crate::error::OceanError;
+use crate::life::TickReport;
+use crate::observe::{ClanSizes, MeanSpeed, Metric, TimeSeries};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::io::Write;
+
+/** The columns of recorded metrics in CSV that come before the clans'. */
+pub const METRICS_CSV_HEADER: [&str; 5] = ["tick", "population", "mean_speed", "births", "deaths"];
+
+/** A beach as measured at the end of one tick. */
+#[derive(Debug, Clone, PartialEq)]
+pub struct TickMetrics {
+    pub tick: u64,
+    pub population: usize,
+    /** The mean speed of the crabs, or None if there were none. */
+    pub mean_speed: Option<f64>,
+    /** The number of members of every clan, by clan id. */
+    pub clan_sizes: BTreeMap<String, usize>,
+    pub births: usize,
+    pub deaths: usize,
+}
+
+impl TickMetrics {
+    /** Measures the beach at the end of the tick it just reported on. */
+    pub fn measure(beach: &Beach, report: &TickReport) -> TickMetrics {
+        TickMetrics {
+            tick: report.tick,
+            population: beach.size(),
+            mean_speed: MeanSpeed.measure(beach),
+            clan_sizes: ClanSizes.measure(beach).unwrap_or_default(),
+            births: report.births.len(),
+            deaths: report.deaths.len(),
+        }
+    }
+}
+
+/**
+ * The measurements of a beach's most recent ticks, oldest first. Once it
+ * holds `retention` ticks, each new one pushes out the oldest.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricsRecorder {
+    retention: usize,
+    ticks: VecDeque<TickMetrics>,
+}
+
+impl MetricsRecorder {
+    pub fn new(retention: usize) -> MetricsRecorder {
+        MetricsRecorder {
+            retention,
+            ticks: VecDeque::with_capacity(retention),
+        }
+    }
+
+    pub fn retention(&self) -> usize {
+        self.retention
+    }
+
+    /** Keeps at most the `retention` most recent ticks from now on. */
+    pub fn set_retention(&mut self, retention: usize) {
+        self.retention = retention;
+        while self.ticks.len() > retention {
+            self.ticks.pop_front();
+        }
+    }
+
+    /** Records a tick, forgetting the oldest one if there are too many. */
+    pub fn record(&mut self, metrics: TickMetrics) {
+        if self.retention == 0 {
+            return;
+        }
+        if self.ticks.len() == self.retention {
+            self.ticks.pop_front();
+        }
+        self.ticks.push_back(metrics);
+    }
+
+    pub fn len(&self) -> usize {
+        self.ticks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ticks.is_empty()
+    }
+
+    /** Returns the recorded ticks, oldest first. */
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &TickMetrics> {
+        self.ticks.iter()
+    }
+
+    pub fn latest(&self) -> Option<&TickMetrics> {
+        self.ticks.back()
+    }
+
+    /** Returns the ticks recorded over the last `n` ticks, up to the latest. */
+    fn last(&self, n: u64) -> impl Iterator<Item = &TickMetrics> {
+        let latest = self.latest().map_or(0, |metrics| metrics.tick);
+        self.ticks
+            .iter()
+            .rev()
+            .take_while(move |metrics| latest - metrics.tick < n)
+    }
+
+    /**
+     * Returns the average of the mean speeds over the last `n` ticks, up to
+     * the latest recorded, leaving out ticks the beach was empty. Returns
+     * None if there are none.
+     */
+    pub fn mean_speed_over(&self, n: u64) -> Option<f64> {
+        let speeds: Vec<f64> = self.last(n).filter_map(|m| m.mean_speed).collect();
+        (!speeds.is_empty()).then(|| speeds.iter().sum::<f64>() / speeds.len() as f64)
+    }
+
+    /** Returns how many crabs were born over the last `n` ticks. */
+    pub fn births_over(&self, n: u64) -> usize {
+        self.last(n).map(|metrics| metrics.births).sum()
+    }
+
+    /** Returns how many crabs died over the last `n` ticks. */
+    pub fn deaths_over(&self, n: u64) -> usize {
+        self.last(n).map(|metrics| metrics.deaths).sum()
+    }
+
+    pub fn population(&self) -> TimeSeries<usize> {
+        self.series(|metrics| Some(metrics.population))
+    }
+
+    pub fn mean_speed(&self) -> TimeSeries<f64> {
+        self.series(|metrics| metrics.mean_speed)
+    }
+
+    /** Returns the size of a clan at the ticks it existed. */
+    pub fn clan_size(&self, clan_id: &str) -> TimeSeries<usize> {
+        self.series(|metrics| metrics.clan_sizes.get(clan_id).copied())
+    }
+
+    fn series<T>(&self, value: impl Fn(&TickMetrics) -> Option<T>) -> TimeSeries<T> {
+        let mut series = TimeSeries::new();
+        for metrics in &self.ticks {
+            if let Some(v) = value(metrics) {
+                series.push(metrics.tick, v);
+            }
+        }
+        series
+    }
+
+    /**
+     * Writes the recorded ticks as CSV, one row per tick, oldest first,
+     * under a header row naming the columns of `METRICS_CSV_HEADER`
+     * followed by one column for every clan that existed at any of the
+     * ticks, by clan id. A clan's column is empty at ticks it did not
+     * exist, as is the mean speed at ticks the beach was empty.
+     */
+    pub fn write_csv<W: Write>(&self, mut w: W) -> Result<(), OceanError> {
+        let clans: BTreeSet<&str> = self
+            .ticks
+            .iter()
+            .flat_map(|metrics| metrics.clan_sizes.keys().map(String::as_str))
+            .collect();
+        let header = METRICS_CSV_HEADER
+            .iter()
+            .map(|column| column.to_string())
+            .chain(clans.iter().map(|clan| quote(clan)));
+        writeln!(w, "{}", header.collect::<Vec<_>>().join(",")).map_err(io_error)?;
+        for metrics in &self.ticks {
+            let fields = [
+                metrics.tick.to_string(),
+                metrics.population.to_string(),
+                metrics.mean_speed.map_or(String::new(), |s| s.to_string()),
+                metrics.births.to_string(),
+                metrics.deaths.to_string(),
+            ];
+            let sizes = clans.iter().map(|&clan| {
+                metrics
+                    .clan_sizes
+                    .get(clan)
+                    .map_or(String::new(), |size| size.to_string())
+            });
+            let row: Vec<String> = fields.into_iter().chain(sizes).collect();
+            writeln!(w, "{}", row.join(",")).map_err(io_error)?;
+        }
+        Ok(())
+    }
+}
+
+fn io_error(error: std::io::Error) -> OceanError {
+    OceanError::Io(error.to_string())
+}
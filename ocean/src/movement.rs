@@ -0,0 +1,175 @@
+use crate::beach::Beach;
+use crate::crab::Crab;
+use crate::food::Food;
+use crate::position::Position;
+use std::fmt::Debug;
+
+/**
+ * What crabs on a beach move toward or away from: the patches where food
+ * lies, and the spots where predators lurk.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Landmarks {
+    pub food: Vec<(Position, Food)>,
+    pub dangers: Vec<Position>,
+}
+
+/** What a crab sees as it picks where to go on a tick. */
+#[derive(Debug, Clone, Copy)]
+pub struct Surroundings<'a> {
+    pub beach: &'a Beach,
+    /** The index of the crab that is moving. */
+    pub index: usize,
+}
+
+impl<'a> Surroundings<'a> {
+    pub fn crab(&self) -> &'a Crab {
+        self.beach.crab_at(self.index)
+    }
+
+    pub fn position(&self) -> Position {
+        self.crab().position()
+    }
+
+    /** Returns the nearest patch of the food the crab eats, if any. */
+    pub fn nearest_food(&self) -> Option<Position> {
+        let food = Food::for_diet(self.crab().diet());
+        let patches = self.beach.landmarks().food.iter();
+        let patches = patches.filter(|&&(_, f)| f == food).map(|&(at, _)| at);
+        self.nearest(patches)
+    }
+
+    /** Returns the nearest spot where predators lurk, if any. */
+    pub fn nearest_danger(&self) -> Option<Position> {
+        self.nearest(self.beach.landmarks().dangers.iter().copied())
+    }
+
+    /** Returns where the other members of the crab's clan stand. */
+    pub fn clanmates(&self) -> Vec<Position> {
+        let me = self.beach.handle(self.index);
+        let clans = self.beach.get_clan_system();
+        let Some(clan) = clans.get_clan_of(me) else {
+            return Vec::new();
+        };
+        clans
+            .clan_members(clan)
+            .iter()
+            .filter(|&&member| member != me)
+            .filter_map(|&member| self.beach.resolve(member))
+            .map(Crab::position)
+            .collect()
+    }
+
+    fn nearest(&self, positions: impl Iterator<Item = Position>) -> Option<Position> {
+        let here = self.position();
+        positions.min_by_key(|&at| here.distance_squared(at))
+    }
+}
+
+/**
+ * How a crab picks where to go on each tick. The beach then steps the crab
+ * toward the target as far as its speed allows.
+ */
+pub trait MovementStrategy: Debug {
+    /** Returns where the crab wants to go, or None to stay where it is. */
+    fn target(&self, view: &Surroundings) -> Option<Position>;
+}
+
+/** Heads for the nearest patch of the food the crab eats. */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SeekFood;
+
+impl MovementStrategy for SeekFood {
+    fn target(&self, view: &Surroundings) -> Option<Position> {
+        view.nearest_food()
+    }
+}
+
+/** Heads for the middle of the crab's clanmates. Crabs without any stay. */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FollowClan;
+
+impl MovementStrategy for FollowClan {
+    fn target(&self, view: &Surroundings) -> Option<Position> {
+        let mates = view.clanmates();
+        if mates.is_empty() {
+            return None;
+        }
+        let count = mates.len() as u64;
+        let sum = |axis: fn(&Position) -> u32| mates.iter().map(|p| axis(p) as u64).sum::<u64>();
+        Some(Position::new(
+            (sum(|p| p.x) / count) as u32,
+            (sum(|p| p.y) / count) as u32,
+        ))
+    }
+}
+
+/**
+ * Runs straight away from the nearest danger, if it is at most `radius`
+ * away. A crab standing right on the danger cannot tell which way is away,
+ * and stays.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Flee {
+    pub radius: u32,
+}
+
+impl MovementStrategy for Flee {
+    fn target(&self, view: &Surroundings) -> Option<Position> {
+        let here = view.position();
+        let danger = view.nearest_danger()?;
+        let radius = self.radius as u64;
+        if here == danger || here.distance_squared(danger) > radius * radius {
+            return None;
+        }
+        let away = |from: u32, danger: u32| {
+            if from >= danger {
+                from.saturating_add(from - danger)
+            } else {
+                from.saturating_sub(danger - from)
+            }
+        };
+        Some(Position::new(
+            away(here.x, danger.x),
+            away(here.y, danger.y),
+        ))
+    }
+}
+
+/** Follows the first of its strategies that picks a target. */
+#[derive(Debug, Default)]
+pub struct FirstOf(pub Vec<Box<dyn MovementStrategy>>);
+
+impl FirstOf {
+    /** Flees dangers within `radius`, else seeks food, else follows the clan. */
+    pub fn cautious(radius: u32) -> FirstOf {
+        FirstOf(vec![
+            Box::new(Flee { radius }),
+            Box::new(SeekFood),
+            Box::new(FollowClan),
+        ])
+    }
+}
+
+impl MovementStrategy for FirstOf {
+    fn target(&self, view: &Surroundings) -> Option<Position> {
+        self.0.iter().find_map(|strategy| strategy.target(view))
+    }
+}
+
+/**
+ * Returns where a crab at `from` ends up stepping `distance` in a straight
+ * line toward `to`, rounded to the grid, or `to` if it is no further.
+ */
+pub fn step_toward(from: Position, to: Position, distance: u32) -> Position {
+    let limit = distance as u64 * distance as u64;
+    if from.distance_squared(to) <= limit {
+        return to;
+    }
+    let ratio = distance as f64 / (from.distance_squared(to) as f64).sqrt();
+    let step = |from: u32, to: u32| {
+        let moved = (to as f64 - from as f64) * ratio;
+        (from as f64 + moved).round() as u32
+    };
+    Position::new(step(from.x, to.x), step(from.y, to.y))
+}
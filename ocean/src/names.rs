@@ -0,0 +1,215 @@
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+use std::collections::HashSet;
+
+const ONSETS: &[&str] = &[
+    "b", "c", "d", "f", "g", "h", "k", "l", "m", "n", "p", "r", "s", "t", "v", "z", "br", "cl",
+    "cr", "pr", "sh", "st", "th",
+];
+const VOWELS: &[&str] = &["a", "e", "i", "o", "u", "ae", "ia", "ou"];
+const CODAS: &[&str] = &["", "", "", "n", "r", "s", "l", "x", "m"];
+
+const ADJECTIVES: &[&str] = &[
+    "Coral", "Sandy", "Salty", "Briny", "Misty", "Sunny", "Stormy", "Pearly", "Rusty", "Mossy",
+    "Tidal", "Lucky", "Swift", "Sleepy", "Grumpy", "Jolly",
+];
+const NOUNS: &[&str] = &[
+    "Pincher", "Shell", "Claw", "Scuttler", "Barnacle", "Kelp", "Drifter", "Pebble", "Urchin",
+    "Wave", "Dune", "Current", "Reef", "Snapper",
+];
+
+/** What kind of names a `NameGenerator` produces. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameStyle {
+    /** Pronounceable made-up names of two or three syllables, like "Brelix". */
+    Syllables,
+    /** An adjective and a noun from the seaside, like "Salty Pincher". */
+    Themed,
+}
+
+/**
+ * Generates distinct crab names from a seed, so the same seed always
+ * produces the same sequence of names.
+ *
+ * Names never repeat within one generator: once the random combinations
+ * start colliding, a Roman numeral is appended ("Brelix II").
+ */
+#[derive(Debug)]
+pub struct NameGenerator {
+    rng: Pcg64,
+    style: NameStyle,
+    used: HashSet<String>,
+}
+
+impl NameGenerator {
+    pub fn new(seed: u64) -> NameGenerator {
+        NameGenerator::with_style(seed, NameStyle::Syllables)
+    }
+
+    pub fn with_style(seed: u64, style: NameStyle) -> NameGenerator {
+        NameGenerator {
+            rng: Pcg64::seed_from_u64(seed),
+            style,
+            used: HashSet::new(),
+        }
+    }
+
+    pub fn style(&self) -> NameStyle {
+        self.style
+    }
+
+    /**
+     * Marks a name as taken, so it will never be generated.
+     */
+    pub fn reserve(&mut self, name: &str) {
+        if !self.used.contains(name) {
+            self.used.insert(name.to_string());
+        }
+    }
+
+    /**
+     * Returns a name that has not been generated or reserved before.
+     */
+    pub fn next_name(&mut self) -> String {
+        const ATTEMPTS: usize = 8;
+
+        let mut base = self.candidate();
+        for _ in 1..ATTEMPTS {
+            if !self.used.contains(&base) {
+                break;
+            }
+            base = self.candidate();
+        }
+
+        let mut name = base.clone();
+        let mut n = 2;
+        while self.used.contains(&name) {
+            name = format!("{} {}", base, roman(n));
+            n += 1;
+        }
+        self.used.insert(name.clone());
+        name
+    }
+
+    fn pick(&mut self, words: &[&'static str]) -> &'static str {
+        words[self.rng.gen_range(0..words.len())]
+    }
+
+    fn candidate(&mut self) -> String {
+        match self.style {
+            NameStyle::Syllables => {
+                let syllables = self.rng.gen_range(2..=3);
+                let mut name = String::new();
+                for k in 0..syllables {
+                    name.push_str(self.pick(ONSETS));
+                    name.push_str(self.pick(VOWELS));
+                    if k + 1 == syllables {
+                        name.push_str(self.pick(CODAS));
+                    }
+                }
+                capitalize(&name)
+            }
+            NameStyle::Themed => format!("{} {}", self.pick(ADJECTIVES), self.pick(NOUNS)),
+        }
+    }
+}
+
+impl Iterator for NameGenerator {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        Some(self.next_name())
+    }
+}
+
+/** What a beach with a `NameRegistry` does about a crab whose name is taken. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NamePolicy {
+    /** Refuse the crab. */
+    Reject,
+    /** Append a Roman numeral to its name, as in "Brelix II". */
+    Suffix,
+}
+
+/**
+ * Keeps the names of a beach's crabs unique, so that clans, which know their
+ * members by name, always mean one crab.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NameRegistry {
+    pub policy: NamePolicy,
+}
+
+impl NameRegistry {
+    pub fn new(policy: NamePolicy) -> NameRegistry {
+        NameRegistry { policy }
+    }
+
+    /**
+     * Returns `name` if it is not taken, or else the first name with a Roman
+     * numeral appended that is not.
+     */
+    pub fn free_name(name: &str, taken: impl Fn(&str) -> bool) -> String {
+        let mut free = name.to_string();
+        let mut n = 2;
+        while taken(&free) {
+            free = format!("{} {}", name, roman(n));
+            n += 1;
+        }
+        free
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn roman(mut n: u32) -> String {
+    const NUMERALS: &[(u32, &str)] = &[
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut out = String::new();
+    for &(value, numeral) in NUMERALS {
+        while n >= value {
+            out.push_str(numeral);
+            n -= value;
+        }
+    }
+    out
+}
+
+/**
+ * Returns the Levenshtein distance between two names: the fewest characters
+ * that must be inserted, deleted or replaced to turn one into the other.
+ */
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    // The distances from the prefix of `a` seen so far to every prefix of `b`.
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let replaced = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = replaced.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
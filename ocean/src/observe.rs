@@ -0,0 +1,246 @@
+use crate::beach::Beach;
+use crate::crab::Crab;
+use crate::speed::Speed;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+
+/**
+ * Values recorded over time, one per tick at most, in tick order.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeSeries<T> {
+    points: Vec<(u64, T)>,
+}
+
+impl<T> TimeSeries<T> {
+    pub fn new() -> TimeSeries<T> {
+        TimeSeries { points: Vec::new() }
+    }
+
+    /**
+     * Records a value at a tick. A value for a tick that already has one
+     * replaces it; ticks earlier than the last one are ignored.
+     */
+    pub fn push(&mut self, tick: u64, value: T) {
+        match self.points.last_mut() {
+            Some(last) if last.0 == tick => last.1 = value,
+            Some(last) if last.0 > tick => {}
+            _ => self.points.push((tick, value)),
+        }
+    }
+
+    /** Returns every point as (tick, value), in tick order. */
+    pub fn points(&self) -> &[(u64, T)] {
+        &self.points
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /** Returns the value recorded at exactly the given tick. */
+    pub fn at(&self, tick: u64) -> Option<&T> {
+        let k = self.points.binary_search_by_key(&tick, |(t, _)| *t).ok()?;
+        Some(&self.points[k].1)
+    }
+
+    /** Returns the latest value recorded at or before the given tick. */
+    pub fn as_of(&self, tick: u64) -> Option<&T> {
+        let k = self.points.partition_point(|(t, _)| *t <= tick);
+        k.checked_sub(1).map(|k| &self.points[k].1)
+    }
+
+    /** Returns the most recent point. */
+    pub fn last(&self) -> Option<&(u64, T)> {
+        self.points.last()
+    }
+
+    /** Returns the points recorded between two ticks, inclusive. */
+    pub fn range(&self, ticks: RangeInclusive<u64>) -> &[(u64, T)] {
+        let start = self.points.partition_point(|(t, _)| t < ticks.start());
+        let end = self.points.partition_point(|(t, _)| t <= ticks.end());
+        &self.points[start..end.max(start)]
+    }
+
+    /**
+     * Groups the points into buckets of `every` ticks (ticks 0 to
+     * `every - 1`, and so on) and combines each bucket's values with
+     * `combine`. Each combined value is recorded at its bucket's first tick.
+     */
+    pub fn downsample_with<U>(
+        &self,
+        every: u64,
+        mut combine: impl FnMut(&[&T]) -> U,
+    ) -> TimeSeries<U> {
+        let every = every.max(1);
+        let mut downsampled = TimeSeries::new();
+        let mut k = 0;
+        while k < self.points.len() {
+            let bucket = self.points[k].0 / every;
+            let values: Vec<&T> = self.points[k..]
+                .iter()
+                .take_while(|(t, _)| t / every == bucket)
+                .map(|(_, v)| v)
+                .collect();
+            k += values.len();
+            downsampled.push(bucket * every, combine(&values));
+        }
+        downsampled
+    }
+}
+
+impl<T: Clone> TimeSeries<T> {
+    /** Keeps the first point of every bucket of `every` ticks. */
+    pub fn downsample(&self, every: u64) -> TimeSeries<T> {
+        self.downsample_with(every, |values| values[0].clone())
+    }
+}
+
+impl<T: Copy + Into<f64>> TimeSeries<T> {
+    /** Averages the values in every bucket of `every` ticks. */
+    pub fn downsample_mean(&self, every: u64) -> TimeSeries<f64> {
+        self.downsample_with(every, |values| {
+            values.iter().map(|&&v| v.into()).sum::<f64>() / values.len() as f64
+        })
+    }
+}
+
+impl<T> Default for TimeSeries<T> {
+    fn default() -> Self {
+        TimeSeries::new()
+    }
+}
+
+/** Something measurable about a beach. */
+pub trait Metric {
+    type Value;
+
+    /** Measures the beach, or returns None if there is nothing to measure. */
+    fn measure(&self, beach: &Beach) -> Option<Self::Value>;
+}
+
+/** The number of crabs on the beach. */
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Population;
+
+impl Metric for Population {
+    type Value = u32;
+
+    fn measure(&self, beach: &Beach) -> Option<u32> {
+        Some(beach.size() as u32)
+    }
+}
+
+/** The mean speed of the crabs, not measured on an empty beach. */
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MeanSpeed;
+
+impl Metric for MeanSpeed {
+    type Value = f64;
+
+    fn measure(&self, beach: &Beach) -> Option<f64> {
+        Speed::mean(beach.crabs().map(Crab::typed_speed))
+    }
+}
+
+/** The number of members of every clan, by clan id. */
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClanSizes;
+
+impl Metric for ClanSizes {
+    type Value = BTreeMap<String, usize>;
+
+    fn measure(&self, beach: &Beach) -> Option<Self::Value> {
+        let clans = beach.get_clan_system();
+        Some(
+            clans
+                .clan_ids()
+                .map(|id| (id.to_string(), clans.get_clan_member_count(id)))
+                .collect(),
+        )
+    }
+}
+
+/** Something that wants to look at a beach at the end of every tick. */
+pub trait Observe {
+    fn observe(&mut self, beach: &Beach);
+}
+
+/**
+ * Records one metric of a beach at every tick it observes. Subscribe it to
+ * a beach with `Beach::add_observer` to have it observe every tick.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Observer<M: Metric> {
+    metric: M,
+    series: TimeSeries<M::Value>,
+}
+
+impl<M: Metric> Observer<M> {
+    pub fn new(metric: M) -> Observer<M> {
+        Observer {
+            metric,
+            series: TimeSeries::new(),
+        }
+    }
+
+    pub fn series(&self) -> &TimeSeries<M::Value> {
+        &self.series
+    }
+}
+
+impl<M: Metric> Observe for Observer<M> {
+    fn observe(&mut self, beach: &Beach) {
+        if let Some(value) = self.metric.measure(beach) {
+            self.series.push(beach.ticks(), value);
+        }
+    }
+}
+
+/** The observers of a beach, in the order they were added. */
+#[derive(Default)]
+pub struct Observers {
+    observers: Vec<Rc<RefCell<dyn Observe>>>,
+}
+
+impl Observers {
+    pub fn new() -> Observers {
+        Observers {
+            observers: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, observer: Rc<RefCell<dyn Observe>>) {
+        self.observers.push(observer);
+    }
+
+    pub fn len(&self) -> usize {
+        self.observers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.observers.is_empty()
+    }
+
+    /** Has every observer look at the beach. */
+    pub fn observe(&self, beach: &Beach) {
+        for observer in &self.observers {
+            observer.borrow_mut().observe(beach);
+        }
+    }
+}
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Observers")
+            .field("observers", &self.observers.len())
+            .finish()
+    }
+}
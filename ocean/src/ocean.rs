@@ -1,30 +1,345 @@
-use crate::beach::Beach;
+use crate::beach::{Beach, CrabId};
+use crate::crab::Crab;
+use crate::economy::Account;
+use crate::error::OceanError;
+use crate::i18n::{tr, Message};
 use crate::prey::{Algae, Clam, Minnow, Shrimp};
 use crate::reef::Reef;
+use crate::trade::{RouteStats, StormSettings, TradeNetwork, TradeRoute};
 use std::cell::RefCell;
 use std::rc::Rc;
 use std::slice::Iter;
 
+/**
+ * How crabs leave crowded beaches by themselves: every tick, a beach with
+ * more than `crowded_at` crabs sends its newest arrivals, up to
+ * `max_per_tick` of them and no more than bring it down to `crowded_at`,
+ * each to the least crowded other beach that is not crowded or full itself.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MigrationRules {
+    pub crowded_at: usize,
+    pub max_per_tick: usize,
+}
+
+/** A crab that moved from one beach to another, by the beaches' indices. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    pub tick: u64,
+    /** The crab's name on the beach it arrived at. */
+    pub crab_name: String,
+    pub from: usize,
+    pub to: usize,
+}
+
 #[derive(Debug)]
 pub struct Ocean {
-    // TODO: Fill in fields here.
+    beaches: Vec<Beach>,
+    names: Vec<Option<String>>,
+    migration: Option<MigrationRules>,
+    migrations: Vec<Migration>,
+    reefs: Vec<Rc<RefCell<Reef>>>,
+    trade: TradeNetwork,
+    ticks: u64,
 }
 
 impl Ocean {
     pub fn new() -> Ocean {
-        unimplemented!();
+        Ocean {
+            beaches: Vec::new(),
+            names: Vec::new(),
+            migration: None,
+            migrations: Vec::new(),
+            reefs: Vec::new(),
+            trade: TradeNetwork::new(),
+            ticks: 0,
+        }
     }
 
     pub fn add_beach(&mut self, beach: Beach) {
-        unimplemented!();
+        self.beaches.push(beach);
+        self.names.push(None);
+    }
+
+    /**
+     * Adds a beach that can be found by name, returning its index. Fails if
+     * the ocean already has a beach by that name.
+     */
+    pub fn add_named_beach(&mut self, name: &str, beach: Beach) -> Result<usize, OceanError> {
+        if self.beach_index(name).is_some() {
+            return Err(OceanError::DuplicateBeach(name.to_string()));
+        }
+        self.beaches.push(beach);
+        self.names.push(Some(name.to_string()));
+        Ok(self.beaches.len() - 1)
+    }
+
+    /** Returns the index of the beach with the given name, if there is one. */
+    pub fn beach_index(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n.as_deref() == Some(name))
+    }
+
+    /** Returns the name of the beach at the given index, if it has one. */
+    pub fn beach_name(&self, index: usize) -> Option<&str> {
+        self.names.get(index)?.as_deref()
+    }
+
+    pub fn beach_named(&self, name: &str) -> Option<&Beach> {
+        self.beach(self.beach_index(name)?)
+    }
+
+    pub fn beach_named_mut(&mut self, name: &str) -> Option<&mut Beach> {
+        let index = self.beach_index(name)?;
+        self.beach_mut(index)
+    }
+
+    pub fn beaches(&self) -> Iter<'_, Beach> {
+        self.beaches.iter()
+    }
+
+    /** Returns the beach at the given index, if there is one. */
+    pub fn beach(&self, index: usize) -> Option<&Beach> {
+        self.beaches.get(index)
+    }
+
+    pub fn beach_mut(&mut self, index: usize) -> Option<&mut Beach> {
+        self.beaches.get_mut(index)
+    }
+
+    /** Returns how many times the ocean has ticked. */
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /**
+     * Advances every beach by one tick, then ships goods along every trade
+     * route that no storm has closed. A route moves up to its capacity in
+     * shells from its clan's treasury on the source beach to the clan's
+     * treasury on the destination beach, and up to its number of migrants
+     * from the clan's members, who join the same clan at the destination.
+     * Routes run in the order they were opened.
+     */
+    pub fn tick(&mut self) {
+        self.ticks += 1;
+        for beach in &mut self.beaches {
+            beach.tick();
+        }
+        for (k, route) in self.trade.open_routes(self.ticks) {
+            let account = Account::clan(&route.clan_id);
+            // Ship no more than the destination's treasury can hold.
+            let room = self.beaches[route.to].economy().room(&account);
+            let source = &mut self.beaches[route.from];
+            let shells = route
+                .capacity
+                .min(source.economy().balance(&account))
+                .min(room);
+            if shells > 0 {
+                source
+                    .economy_mut()
+                    .export(&account, shells)
+                    .expect("shipment within the balance");
+            }
+
+            let leaving: Vec<CrabId> = source
+                .get_clan_system()
+                .clan_members(&route.clan_id)
+                .iter()
+                .copied()
+                .take(route.migrants)
+                .collect();
+            let migrants: Vec<Crab> = leaving
+                .into_iter()
+                .filter_map(|id| source.remove_crab_by_id(id))
+                .collect();
+
+            let dest = &mut self.beaches[route.to];
+            if shells > 0 {
+                dest.economy_mut()
+                    .import(account, shells)
+                    .expect("shipment within the room");
+            }
+            let members = migrants.len();
+            for crab in migrants {
+                let id = dest.add_crab_with_id(crab);
+                dest.add_member_to_clan_by_id(&route.clan_id, id);
+            }
+            self.trade.record(k, shells, members);
+        }
+        self.migrate_crowds();
+    }
+
+    /**
+     * Moves a crab, with its clan membership, from one named beach to
+     * another, returning its id on the beach it arrived at. Fails if either
+     * beach does not exist, if the crab does not live on the first, or if the
+     * second is full; a migrant never makes room by evicting anyone. Moving a
+     * crab to the beach it is on leaves it where it is.
+     */
+    pub fn migrate(&mut self, id: CrabId, from: &str, to: &str) -> Result<CrabId, OceanError> {
+        let [from, to] = [from, to].map(|name| {
+            self.beach_index(name)
+                .ok_or_else(|| OceanError::UnknownBeach(name.to_string()))
+        });
+        let (from, to) = (from?, to?);
+        let index = self.beaches[from]
+            .index_of(id)
+            .ok_or(OceanError::UnknownCrabId(id))?;
+        if from == to {
+            return Ok(id);
+        }
+        let dest = &self.beaches[to];
+        if let Some(capacity) = dest.capacity().filter(|&c| dest.size() >= c) {
+            return Err(OceanError::BeachFull { capacity });
+        }
+        Ok(self.move_crab(index, from, to))
+    }
+
+    /** Moves the crab at `index` from one beach to another, returning its new id. */
+    fn move_crab(&mut self, index: usize, from: usize, to: usize) -> CrabId {
+        let (source, dest) = if from < to {
+            let (left, right) = self.beaches.split_at_mut(to);
+            (&mut left[from], &mut right[0])
+        } else {
+            let (left, right) = self.beaches.split_at_mut(from);
+            (&mut right[0], &mut left[to])
+        };
+        source.transfer_crab(index, dest);
+        let arrived = dest.size() - 1;
+        self.migrations.push(Migration {
+            tick: self.ticks,
+            crab_name: dest.crab_at(arrived).name().to_string(),
+            from,
+            to,
+        });
+        dest.handle(arrived)
+    }
+
+    /** Sets how crabs leave crowded beaches each tick, or stops them. */
+    pub fn set_migration_rules(&mut self, rules: Option<MigrationRules>) {
+        self.migration = rules;
+    }
+
+    pub fn migration_rules(&self) -> Option<MigrationRules> {
+        self.migration
+    }
+
+    /** Returns every crab that has moved between beaches, in order. */
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    fn migrate_crowds(&mut self) {
+        let Some(rules) = self.migration else {
+            return;
+        };
+        let has_room = |beach: &Beach| {
+            beach.size() < rules.crowded_at && beach.capacity().is_none_or(|c| beach.size() < c)
+        };
+        for from in 0..self.beaches.len() {
+            let leaving = self.beaches[from]
+                .size()
+                .saturating_sub(rules.crowded_at)
+                .min(rules.max_per_tick);
+            for _ in 0..leaving {
+                let Some(to) = (0..self.beaches.len())
+                    .filter(|&to| to != from && has_room(&self.beaches[to]))
+                    .min_by_key(|&to| (self.beaches[to].size(), to))
+                else {
+                    break;
+                };
+                let newest = self.beaches[from].size() - 1;
+                self.move_crab(newest, from, to);
+            }
+        }
     }
 
-    pub fn beaches(&self) -> Iter<Beach> {
-        unimplemented!();
+    /** Returns how many crabs live in the whole ocean. */
+    pub fn population(&self) -> usize {
+        self.beaches.iter().map(Beach::size).sum()
     }
 
-    pub fn reefs(&self) -> Iter<Rc<RefCell<Reef>>> {
-        unimplemented!();
+    /**
+     * Returns the fastest crab in the whole ocean, with the index of its
+     * beach, or None if every beach is empty.
+     */
+    pub fn get_fastest_crab(&self) -> Option<(usize, &Crab)> {
+        self.beaches
+            .iter()
+            .enumerate()
+            .flat_map(|(b, beach)| beach.crabs().map(move |crab| (b, crab)))
+            .max_by_key(|(_, crab)| crab.speed())
+    }
+
+    /**
+     * Returns the members of a clan on every beach, with the index of the
+     * beach each lives on, beach by beach.
+     */
+    pub fn get_clan_members(&self, clan_id: &str) -> Vec<(usize, String)> {
+        self.beaches
+            .iter()
+            .enumerate()
+            .flat_map(|(b, beach)| {
+                beach
+                    .get_clan_system()
+                    .get_clan_member_names(clan_id)
+                    .into_iter()
+                    .map(move |name| (b, name))
+            })
+            .collect()
+    }
+
+    /**
+     * Opens a trade route for a clan, returning its index. Fails if either
+     * beach does not exist, if both ends are the same beach, or if the clan
+     * does not exist on the source beach.
+     */
+    pub fn open_route(&mut self, route: TradeRoute) -> Result<usize, String> {
+        if let Some(&missing) = [route.from, route.to]
+            .iter()
+            .find(|&&b| b >= self.beaches.len())
+        {
+            return Err(tr(Message::NoSuchBeach, &[&missing]));
+        }
+        if route.from == route.to {
+            return Err(tr(Message::RouteSameBeach, &[&route.from]));
+        }
+        if !self.beaches[route.from]
+            .get_clan_system()
+            .has_clan(&route.clan_id)
+        {
+            return Err(tr(Message::NoSuchClan, &[&route.clan_id]));
+        }
+        Ok(self.trade.open(route))
+    }
+
+    /** Returns the ocean's trade routes. */
+    pub fn trade(&self) -> &TradeNetwork {
+        &self.trade
+    }
+
+    pub fn route_stats(&self, route: usize) -> Option<&RouteStats> {
+        self.trade.stats(route)
+    }
+
+    /** Sets how storms strike the trade routes. */
+    pub fn set_storms(&mut self, storms: StormSettings) {
+        self.trade.set_storms(storms);
+    }
+
+    /**
+     * Closes a trade route for the next `ticks` ticks, as a storm would.
+     * Fails if there is no such route.
+     */
+    pub fn storm(&mut self, route: usize, ticks: u64) -> Result<(), String> {
+        if self.trade.disrupt(route, self.ticks + 1, ticks) {
+            Ok(())
+        } else {
+            Err(tr(Message::NoSuchRoute, &[&route]))
+        }
+    }
+
+    pub fn reefs(&self) -> Iter<'_, Rc<RefCell<Reef>>> {
+        self.reefs.iter()
     }
 
     /**
@@ -41,6 +356,28 @@ impl Ocean {
         n_clams: u32,
         n_algae: u32,
     ) -> Rc<RefCell<Reef>> {
-        unimplemented!();
+        let mut reef = Reef::new();
+        for _ in 0..n_minnows {
+            reef.add_prey(Box::new(Minnow::new(25)));
+        }
+        for _ in 0..n_shrimp {
+            reef.add_prey(Box::new(Shrimp::new(1)));
+        }
+        for _ in 0..n_clams {
+            reef.add_prey(Box::new(Clam::new()));
+        }
+        for _ in 0..n_algae {
+            reef.add_prey(Box::new(Algae::new()));
+        }
+
+        let reef = Rc::new(RefCell::new(reef));
+        self.reefs.push(Rc::clone(&reef));
+        reef
+    }
+}
+
+impl Default for Ocean {
+    fn default() -> Self {
+        Ocean::new()
     }
 }
@@ -0,0 +1,166 @@
+use crate::beach::Beach;
+use crate::crab::Crab;
+use crate::diet::Diet;
+use crate::error::OceanError;
+use std::collections::HashSet;
+use std::thread;
+
+/**
+ * What the parallel statistics can see of a crab. Crabs themselves share
+ * their reefs through `Rc` and so must stay on the beach's thread, but
+ * these views of them can be read from any thread.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrabView<'a> {
+    /** The crab's index on the beach. */
+    pub index: usize,
+    pub name: &'a str,
+    pub speed: u32,
+    pub diet: Diet,
+}
+
+impl<'a> CrabView<'a> {
+    fn of(index: usize, crab: &'a Crab) -> CrabView<'a> {
+        CrabView {
+            index,
+            name: crab.name(),
+            speed: crab.speed(),
+            diet: crab.diet(),
+        }
+    }
+}
+
+/**
+ * Beaches with fewer crabs than this per thread are not worth splitting up;
+ * their statistics are computed on fewer threads, or just the caller's.
+ */
+pub const MIN_CRABS_PER_THREAD: usize = 4096;
+
+impl Beach {
+    /**
+     * Runs `f` on every crab, split across up to `threads` threads, and
+     * returns what it returned, in the order the crabs live on the beach.
+     */
+    pub fn par_crabs<T: Send>(
+        &self,
+        threads: usize,
+        f: impl Fn(CrabView) -> Option<T> + Sync,
+    ) -> Vec<T> {
+        self.par_chunks(threads, |chunk| {
+            chunk.iter().filter_map(|&view| f(view)).collect::<Vec<T>>()
+        })
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    /**
+     * Returns the crab with the highest speed like `get_fastest_crab`, on up
+     * to `threads` threads.
+     */
+    pub fn par_get_fastest_crab(&self, threads: usize) -> Option<&Crab> {
+        let fastest = self.par_chunks(threads, |chunk| {
+            let fastest = chunk.iter().max_by_key(|view| view.speed)?;
+            Some((fastest.speed, fastest.index))
+        });
+        let (_, index) = fastest
+            .into_iter()
+            .flatten()
+            .max_by_key(|&(speed, _)| speed)?;
+        Some(self.crab_at(index))
+    }
+
+    /**
+     * Returns the crabs with a given name like `find_crabs_by_name`, on up to
+     * `threads` threads.
+     */
+    pub fn par_find_crabs_by_name(&self, name: &str, threads: usize) -> Vec<&Crab> {
+        self.par_crabs(threads, |view| (view.name == name).then_some(view.index))
+            .into_iter()
+            .map(|index| self.crab_at(index))
+            .collect()
+    }
+
+    /**
+     * Returns the average speed of a clan's resident crabs like
+     * `get_crabs_avg_speed`, on up to `threads` threads.
+     */
+    pub fn par_get_crabs_avg_speed(&self, clan_id: &str, threads: usize) -> Option<f64> {
+        let clans = self.get_clan_system();
+        let members: HashSet<usize> = clans
+            .clan_members(clan_id)
+            .iter()
+            .filter_map(|&member| self.index_of(member))
+            .collect();
+        let sums = self.par_chunks(threads, |chunk| {
+            let mut total: u64 = 0;
+            let mut count: u64 = 0;
+            for view in chunk.iter().filter(|view| members.contains(&view.index)) {
+                total += view.speed as u64;
+                count += 1;
+            }
+            (total, count)
+        });
+        let (total, count) = sums
+            .into_iter()
+            .fold((0, 0), |(t, c), (total, count)| (t + total, c + count));
+        if count == 0 {
+            None
+        } else {
+            Some(total as f64 / count as f64)
+        }
+    }
+
+    /** Decides a competition like `get_winner_clan`, on up to `threads` threads. */
+    pub fn par_get_winner_clan(
+        &self,
+        id1: &str,
+        id2: &str,
+        threads: usize,
+    ) -> Result<Option<String>, OceanError> {
+        if id1 == id2 {
+            return Err(OceanError::ClanAgainstItself(id1.to_string()));
+        }
+        let avg1 = self
+            .par_get_crabs_avg_speed(id1, threads)
+            .ok_or_else(|| OceanError::UnknownClan(id1.to_string()))?;
+        let avg2 = self
+            .par_get_crabs_avg_speed(id2, threads)
+            .ok_or_else(|| OceanError::UnknownClan(id2.to_string()))?;
+
+        if avg1 > avg2 {
+            Ok(Some(id1.to_string()))
+        } else if avg2 > avg1 {
+            Ok(Some(id2.to_string()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /**
+     * Splits views of the crabs into one chunk per thread, runs `f` on each
+     * chunk on its own thread, and returns the results in chunk order.
+     */
+    fn par_chunks<T: Send>(&self, threads: usize, f: impl Fn(&[CrabView]) -> T + Sync) -> Vec<T> {
+        let views: Vec<CrabView> = self
+            .crabs()
+            .enumerate()
+            .map(|(index, crab)| CrabView::of(index, crab))
+            .collect();
+        let threads = threads.clamp(1, (views.len() / MIN_CRABS_PER_THREAD).max(1));
+        if threads == 1 {
+            return vec![f(&views)];
+        }
+        let chunk_size = views.len().div_ceil(threads);
+        thread::scope(|scope| {
+            let workers: Vec<_> = views
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(|| f(chunk)))
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .collect()
+        })
+    }
+}
@@ -0,0 +1,237 @@
+use crate::beach::{Beach, CrabId};
+use crate::clans::{ClanSystem, Constitution, Relation};
+use crate::crab::Crab;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/**
+ * How a clan system is saved: each clan's members by id and name, the
+ * relations, then each sub-clan with its parent, then each clan's
+ * constitution.
+ */
+#[derive(Serialize, Deserialize)]
+struct SavedClans {
+    clans: BTreeMap<String, Vec<SavedMember>>,
+    relations: Vec<(String, String, Relation)>,
+    #[serde(default)]
+    subclans: Vec<(String, String)>,
+    #[serde(default)]
+    constitutions: BTreeMap<String, Constitution>,
+}
+
+/** A clan member as saved. */
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum SavedMember {
+    /** The member's id on its beach, and the name it goes by. */
+    Crab { crab: CrabId, name: String },
+    /** A member saved by name alone, before clans knew crabs by id. */
+    Name(String),
+}
+
+impl SavedClans {
+    /**
+     * Rebuilds the saved clan system, finding the ids members saved by name
+     * alone stand for with `ids_by_name`.
+     */
+    fn restore(
+        self,
+        mut ids_by_name: impl FnMut(&str, &str) -> Result<Vec<CrabId>, String>,
+    ) -> Result<ClanSystem, String> {
+        let mut clan_system = ClanSystem::new();
+        for (id, members) in &self.clans {
+            for member in members {
+                match member {
+                    SavedMember::Crab { crab, name } => {
+                        clan_system.add_member(id, *crab, name);
+                    }
+                    SavedMember::Name(name) => {
+                        for crab in ids_by_name(id, name)? {
+                            clan_system.add_member(id, crab, name);
+                        }
+                    }
+                }
+            }
+        }
+        for (id1, id2, relation) in &self.relations {
+            clan_system.set_relation(id1, id2, *relation)?;
+        }
+        for (child, parent) in &self.subclans {
+            clan_system.create_subclan(parent, child)?;
+        }
+        for (id, constitution) in self.constitutions {
+            clan_system.set_constitution(&id, constitution);
+        }
+        Ok(clan_system)
+    }
+}
+
+impl Serialize for ClanSystem {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let clans = self
+            .clan_ids()
+            .map(|id| {
+                let members = self
+                    .clan_members(id)
+                    .iter()
+                    .map(|&crab| SavedMember::Crab {
+                        crab,
+                        name: self.member_name(crab).unwrap_or_default().to_string(),
+                    })
+                    .collect();
+                (id.to_string(), members)
+            })
+            .collect();
+        let relations = self
+            .relations()
+            .into_iter()
+            .map(|(id1, id2, relation)| (id1.to_string(), id2.to_string(), relation))
+            .collect();
+        let subclans = self
+            .clan_ids()
+            .filter_map(|id| Some((id.to_string(), self.parent_clan(id)?.to_string())))
+            .collect();
+        let constitutions = self
+            .constitutions()
+            .map(|(id, constitution)| (id.to_string(), *constitution))
+            .collect();
+        SavedClans {
+            clans,
+            relations,
+            subclans,
+            constitutions,
+        }
+        .serialize(serializer)
+    }
+}
+
+/**
+ * Loads a clan system saved on its own. Members saved by name alone, from
+ * before clans knew crabs by id, need the crabs of their beach to be found
+ * and fail to load; `Deserialize for Beach` finds them.
+ */
+impl<'de> Deserialize<'de> for ClanSystem {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        SavedClans::deserialize(deserializer)?
+            .restore(|id, name| {
+                Err(format!(
+                    "{} is in clan {} by name alone, which needs its beach to be found",
+                    name, id
+                ))
+            })
+            .map_err(D::Error::custom)
+    }
+}
+
+/**
+ * A crab as saved with its beach, with its id, unless saved before beaches
+ * kept crabs' ids, and the tick it arrived at.
+ */
+#[derive(Serialize, Deserialize)]
+struct SavedCrab {
+    #[serde(default)]
+    id: Option<CrabId>,
+    #[serde(flatten)]
+    crab: Crab,
+    arrived: u64,
+}
+
+#[derive(Serialize)]
+struct SavingBeach<'a> {
+    ticks: u64,
+    crabs: Vec<SavingCrab<'a>>,
+    clans: &'a ClanSystem,
+}
+
+#[derive(Serialize)]
+struct SavingCrab<'a> {
+    id: CrabId,
+    #[serde(flatten)]
+    crab: &'a Crab,
+    arrived: u64,
+}
+
+#[derive(Deserialize)]
+struct SavedBeach {
+    ticks: u64,
+    crabs: Vec<SavedCrab>,
+    clans: SavedClans,
+}
+
+/**
+ * Saves the beach clock, the crabs with their ids and ages, and the clans
+ * with their relations. The crabs' reefs belong to the ocean and are not saved,
+ * nor is anything else attached to the beach, such as listeners, watches,
+ * the economy or breeding history.
+ */
+impl Serialize for Beach {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SavingBeach {
+            ticks: self.ticks(),
+            crabs: self
+                .crabs()
+                .enumerate()
+                .map(|(i, crab)| SavingCrab {
+                    id: self.handle(i),
+                    crab,
+                    arrived: self.ticks() - self.age(i),
+                })
+                .collect(),
+            clans: self.get_clan_system(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Beach {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let saved = SavedBeach::deserialize(deserializer)?;
+        if let Some(late) = saved.crabs.iter().find(|c| c.arrived > saved.ticks) {
+            return Err(D::Error::custom(format!(
+                "{} arrived at tick {}, after the beach's tick {}",
+                late.crab.name(),
+                late.arrived,
+                saved.ticks
+            )));
+        }
+        let ids: Vec<CrabId> = saved.crabs.iter().filter_map(|c| c.id).collect();
+        if !ids.is_empty() && ids.len() < saved.crabs.len() {
+            return Err(D::Error::custom("only some crabs were saved with ids"));
+        }
+        if let Some(pair) = ids.windows(2).find(|pair| pair[0] >= pair[1]) {
+            return Err(D::Error::custom(format!(
+                "crab {} was saved after crab {}, which is not older",
+                pair[1], pair[0]
+            )));
+        }
+        let crabs = saved
+            .crabs
+            .into_iter()
+            .map(|c| (c.id, c.crab, c.arrived))
+            .collect();
+        let clans = saved.clans;
+        Beach::from_saved(saved.ticks, crabs, |beach| {
+            clans.restore(|_, name| Ok(beach.ids_saved_by_name(name)))
+        })
+        .map_err(D::Error::custom)
+    }
+}
+
+impl Beach {
+    /** Saves the beach as JSON at the given path. See `Serialize for Beach`. */
+    pub fn save_to_json<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writer.flush()
+    }
+
+    /** Loads a beach saved by `save_to_json`. */
+    pub fn load_from_json<P: AsRef<Path>>(path: P) -> io::Result<Beach> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+}
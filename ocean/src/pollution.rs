@@ -0,0 +1,60 @@
+/** The most polluted a beach can get. */
+pub const MAX_POLLUTION: f64 = 100.0;
+/** How much pollution each crab fished off a beach leaves behind. */
+pub const POLLUTION_PER_CATCH: f64 = 1.0;
+/** How much pollution a cleanup crew removes for every tick it works. */
+pub const CLEANUP_PER_TICK: f64 = 10.0;
+/** Every this much pollution costs each crab one health point per tick. */
+pub const POLLUTION_PER_DAMAGE: f64 = 20.0;
+
+/**
+ * How polluted a beach is, from 0 (clean) to `MAX_POLLUTION`. Pollution
+ * builds up from human activity and fades by `natural_decay` every tick.
+ * While it lasts, it wears down the health of every crab and holds back the
+ * regrowth of food.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Pollution {
+    level: f64,
+    pub natural_decay: f64,
+}
+
+impl Pollution {
+    pub fn new() -> Pollution {
+        Pollution {
+            level: 0.0,
+            natural_decay: 0.0,
+        }
+    }
+
+    pub fn level(&self) -> f64 {
+        self.level
+    }
+
+    /** Adds pollution, up to `MAX_POLLUTION`. */
+    pub fn add(&mut self, amount: f64) {
+        self.level = (self.level + amount.max(0.0)).min(MAX_POLLUTION);
+    }
+
+    /** Removes up to `amount` pollution, returning how much was removed. */
+    pub fn clean(&mut self, amount: f64) -> f64 {
+        let removed = amount.max(0.0).min(self.level);
+        self.level -= removed;
+        removed
+    }
+
+    /** Lets a tick's worth of pollution fade away. */
+    pub fn decay(&mut self) {
+        self.clean(self.natural_decay);
+    }
+
+    /** Returns the health every crab loses on each tick. */
+    pub fn damage_per_tick(&self) -> u32 {
+        (self.level / POLLUTION_PER_DAMAGE) as u32
+    }
+
+    /** Returns the share of food that still regrows, from 1 (clean) to 0. */
+    pub fn regrowth_factor(&self) -> f64 {
+        1.0 - self.level / MAX_POLLUTION
+    }
+}
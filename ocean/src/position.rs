@@ -0,0 +1,20 @@
+/** A crab's location on the beach grid. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    pub x: u32,
+    pub y: u32,
+}
+
+impl Position {
+    pub fn new(x: u32, y: u32) -> Position {
+        Position { x, y }
+    }
+
+    /** Returns the square of the straight-line distance to another position. */
+    pub fn distance_squared(&self, other: Position) -> u64 {
+        let dx = self.x.abs_diff(other.x) as u64;
+        let dy = self.y.abs_diff(other.y) as u64;
+        dx * dx + dy * dy
+    }
+}
@@ -0,0 +1,97 @@
+use crate::crab::Crab;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+
+pub trait Predator {
+    /** What kind of predator this is, e.g. "seagull". */
+    fn kind(&self) -> &str;
+
+    fn speed(&self) -> u32;
+
+    /**
+     * `Predator`s eat `Crab`s. This method is called when this predator goes
+     * after a crab running at `speed`, which may differ from the crab's own
+     * speed, e.g. in the cold. Return true if the crab is caught, and false
+     * if it gets away.
+     */
+    fn try_catch(&mut self, crab: &Crab, speed: u32) -> bool;
+}
+
+impl core::fmt::Debug for dyn Predator {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "Predator({})", self.kind())
+    }
+}
+
+/** A predator going after a crab during a tick. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Hunt {
+    /** The kind of predator. */
+    pub predator: String,
+    /** The name of the crab it went after. */
+    pub prey: String,
+    pub caught: bool,
+}
+
+#[derive(Debug)]
+pub struct Seagull {
+    speed: u32,
+}
+
+impl Seagull {
+    pub fn new(speed: u32) -> Seagull {
+        Seagull { speed }
+    }
+}
+
+impl Predator for Seagull {
+    fn kind(&self) -> &str {
+        "seagull"
+    }
+
+    fn speed(&self) -> u32 {
+        self.speed
+    }
+
+    /**
+     * Seagulls swoop down from above, and catch every crab slower than them.
+     */
+    fn try_catch(&mut self, _crab: &Crab, speed: u32) -> bool {
+        speed < self.speed
+    }
+}
+
+#[derive(Debug)]
+pub struct Octopus {
+    speed: u32,
+    rng: Pcg64,
+}
+
+impl Octopus {
+    pub fn new(speed: u32, seed: u64) -> Octopus {
+        Octopus {
+            speed,
+            rng: Pcg64::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Predator for Octopus {
+    fn kind(&self) -> &str {
+        "octopus"
+    }
+
+    fn speed(&self) -> u32 {
+        self.speed
+    }
+
+    /**
+     * Octopuses strike from ambush, so even a fast crab can be caught: the
+     * chance of getting away grows with the crab's share of the two speeds.
+     */
+    fn try_catch(&mut self, _crab: &Crab, speed: u32) -> bool {
+        let total = (self.speed as u64 + speed as u64) as f64;
+        total > 0.0 && self.rng.gen_bool(self.speed as f64 / total)
+    }
+}
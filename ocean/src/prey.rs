@@ -42,11 +42,11 @@ impl Prey for Shrimp {
     fn try_escape(&mut self, _crab: &Crab) -> bool {
         if self.energy == 0 {
             // No remaining energy to escape!
-            return false;
+            false
         } else {
             // Escaped, but getting more tired...
             self.energy -= 1;
-            return true;
+            true
         }
     }
 }
@@ -84,6 +84,12 @@ impl Algae {
     }
 }
 
+impl Default for Algae {
+    fn default() -> Self {
+        Algae::new()
+    }
+}
+
 impl Prey for Algae {
     fn diet(&self) -> Diet {
         Diet::Plants
@@ -93,7 +99,7 @@ impl Prey for Algae {
      * Algae can't move. They're plants.
      */
     fn try_escape(&mut self, _crab: &Crab) -> bool {
-        return false;
+        false
     }
 }
 
@@ -106,6 +112,12 @@ impl Clam {
     }
 }
 
+impl Default for Clam {
+    fn default() -> Self {
+        Clam::new()
+    }
+}
+
 impl Prey for Clam {
     fn diet(&self) -> Diet {
         Diet::Shellfish
@@ -115,6 +127,6 @@ impl Prey for Clam {
      * There is no escape for the clam.
      */
     fn try_escape(&mut self, _crab: &Crab) -> bool {
-        return false;
+        false
     }
 }
@@ -0,0 +1,79 @@
+use crate::beach::CrabId;
+use std::collections::{BTreeSet, HashSet};
+
+/** Something that can be protected: one crab, or every member of a clan. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Protected {
+    Crab(CrabId),
+    Clan(String),
+}
+
+/** A change in protection, as recorded in the audit trail. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtectionChange {
+    pub tick: u64,
+    pub target: Protected,
+    /** Whether the target became protected, rather than losing protection. */
+    pub protected: bool,
+}
+
+/**
+ * The crabs and clans under protection on a beach. Predators, culls and
+ * reaping pass over protected crabs. Every change is kept in an audit trail.
+ */
+#[derive(Debug, Default)]
+pub struct Protection {
+    crabs: HashSet<CrabId>,
+    clans: BTreeSet<String>,
+    log: Vec<ProtectionChange>,
+}
+
+impl Protection {
+    pub fn new() -> Protection {
+        Protection {
+            crabs: HashSet::new(),
+            clans: BTreeSet::new(),
+            log: Vec::new(),
+        }
+    }
+
+    /**
+     * Protects or stops protecting a target at tick `now`. Returns whether
+     * that changed anything; only changes are recorded.
+     */
+    pub fn set(&mut self, target: Protected, protected: bool, now: u64) -> bool {
+        let changed = match (&target, protected) {
+            (Protected::Crab(handle), true) => self.crabs.insert(*handle),
+            (Protected::Crab(handle), false) => self.crabs.remove(handle),
+            (Protected::Clan(id), true) => self.clans.insert(id.clone()),
+            (Protected::Clan(id), false) => self.clans.remove(id),
+        };
+        if changed {
+            self.log.push(ProtectionChange {
+                tick: now,
+                target,
+                protected,
+            });
+        }
+        changed
+    }
+
+    /** Returns whether the crab itself is protected, ignoring its clans. */
+    pub fn is_crab_protected(&self, crab: CrabId) -> bool {
+        self.crabs.contains(&crab)
+    }
+
+    pub fn is_clan_protected(&self, clan_id: &str) -> bool {
+        self.clans.contains(clan_id)
+    }
+
+    /** Returns the ids of the protected clans, in sorted order. */
+    pub fn clans(&self) -> impl Iterator<Item = &str> {
+        self.clans.iter().map(|id| id.as_str())
+    }
+
+    /** Returns every change in protection, oldest first. */
+    pub fn log(&self) -> &[ProtectionChange] {
+        &self.log
+    }
+}
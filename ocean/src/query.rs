@@ -0,0 +1,530 @@
+use crate::beach::Beach;
+use crate::color::Color;
+use crate::crab::Crab;
+use crate::diet::Diet;
+use crate::i18n::{tr, Message};
+use std::cmp::Ordering;
+use std::fmt;
+
+/**
+ * A tiny SQL-like query language over the crabs of a beach, e.g.
+ *
+ * `SELECT name, speed FROM crabs WHERE diet = 'Fish' AND speed > 10 ORDER BY speed DESC LIMIT 5`
+ *
+ * Keywords are case-insensitive. Only the `crabs` table exists; its columns
+ * are the variants of `Column`. Text comparisons ignore case.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    columns: Vec<Column>,
+    conditions: Vec<Condition>,
+    order_by: Option<(Column, Direction)>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Name,
+    Speed,
+    Diet,
+    ClawSize,
+    Health,
+    X,
+    Y,
+}
+
+impl Column {
+    pub fn all() -> [Column; 7] {
+        [
+            Column::Name,
+            Column::Speed,
+            Column::Diet,
+            Column::ClawSize,
+            Column::Health,
+            Column::X,
+            Column::Y,
+        ]
+    }
+
+    /** The column's name as written in queries. */
+    pub fn name(self) -> &'static str {
+        match self {
+            Column::Name => "name",
+            Column::Speed => "speed",
+            Column::Diet => "diet",
+            Column::ClawSize => "claw_size",
+            Column::Health => "health",
+            Column::X => "x",
+            Column::Y => "y",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Column> {
+        Column::all()
+            .into_iter()
+            .find(|c| c.name().eq_ignore_ascii_case(name))
+    }
+
+    fn is_text(self) -> bool {
+        matches!(self, Column::Name | Column::Diet)
+    }
+
+    pub fn value(self, crab: &Crab) -> Value {
+        match self {
+            Column::Name => Value::Text(crab.name().to_string()),
+            Column::Speed => Value::Number(crab.speed() as i64),
+            Column::Diet => Value::Text(format!("{:?}", crab.diet())),
+            Column::ClawSize => Value::Number(crab.claw_size() as i64),
+            Column::Health => Value::Number(crab.health() as i64),
+            Column::X => Value::Number(crab.position().x as i64),
+            Column::Y => Value::Number(crab.position().y as i64),
+        }
+    }
+}
+
+/** A single cell of a query result. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Number(i64),
+    Text(String),
+}
+
+impl Value {
+    fn compare(&self, other: &Value) -> Ordering {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => a.cmp(b),
+            (Value::Text(a), Value::Text(b)) => a.to_lowercase().cmp(&b.to_lowercase()),
+            (Value::Number(_), Value::Text(_)) => Ordering::Less,
+            (Value::Text(_), Value::Number(_)) => Ordering::Greater,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Text(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Equal => ordering == Ordering::Equal,
+            Comparison::NotEqual => ordering != Ordering::Equal,
+            Comparison::Less => ordering == Ordering::Less,
+            Comparison::LessOrEqual => ordering != Ordering::Greater,
+            Comparison::Greater => ordering == Ordering::Greater,
+            Comparison::GreaterOrEqual => ordering != Ordering::Less,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition {
+    pub column: Column,
+    pub comparison: Comparison,
+    pub value: Value,
+}
+
+impl Condition {
+    fn matches(&self, crab: &Crab) -> bool {
+        self.comparison
+            .holds(self.column.value(crab).compare(&self.value))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Ascending,
+    Descending,
+}
+
+/** The rows returned by a query, with the selected columns as headers. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryResult {
+    pub columns: Vec<Column>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Number(i64),
+    Text(String),
+    Symbol(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Word(w) => write!(f, "{}", w),
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Text(s) => write!(f, "'{}'", s),
+            Token::Symbol(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    const SYMBOLS: [&str; 10] = ["<=", ">=", "!=", "<>", "=", "<", ">", ",", "*", ";"];
+    let mut tokens = Vec::new();
+    let mut rest = text.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let len = if c == '\'' {
+            let end = rest[1..]
+                .find('\'')
+                .ok_or_else(|| tr(Message::QueryUnterminatedString, &[]))?;
+            tokens.push(Token::Text(rest[1..end + 1].to_string()));
+            end + 2
+        } else if c.is_ascii_digit()
+            || (c == '-' && rest[1..].starts_with(|d: char| d.is_ascii_digit()))
+        {
+            let end = rest[1..]
+                .find(|d: char| !d.is_ascii_digit())
+                .map_or(rest.len(), |k| k + 1);
+            let number = rest[..end]
+                .parse()
+                .map_err(|_| tr(Message::QueryUnexpectedToken, &[&&rest[..end]]))?;
+            tokens.push(Token::Number(number));
+            end
+        } else if c.is_alphabetic() || c == '_' {
+            let end = rest
+                .find(|d: char| !(d.is_alphanumeric() || d == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Word(rest[..end].to_string()));
+            end
+        } else if let Some(symbol) = SYMBOLS.iter().find(|s| rest.starts_with(*s)) {
+            tokens.push(Token::Symbol(symbol));
+            symbol.len()
+        } else {
+            return Err(tr(Message::QueryUnexpectedToken, &[&c]));
+        };
+        rest = rest[len..].trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    next: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next)
+    }
+
+    fn advance(&mut self) -> Result<Token, String> {
+        let token = self
+            .tokens
+            .get(self.next)
+            .cloned()
+            .ok_or_else(|| tr(Message::QueryUnexpectedEnd, &[]))?;
+        self.next += 1;
+        Ok(token)
+    }
+
+    /** Consumes the next token if it is the given keyword. */
+    fn accept(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword) => {
+                self.next += 1;
+                true
+            }
+            Some(Token::Symbol(s)) if *s == keyword => {
+                self.next += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect(&mut self, keyword: &str) -> Result<(), String> {
+        if self.accept(keyword) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn unexpected(&self) -> String {
+        match self.peek() {
+            Some(token) => tr(Message::QueryUnexpectedToken, &[token]),
+            None => tr(Message::QueryUnexpectedEnd, &[]),
+        }
+    }
+
+    fn column(&mut self) -> Result<Column, String> {
+        match self.advance()? {
+            Token::Word(w) => {
+                Column::from_name(&w).ok_or_else(|| tr(Message::QueryUnknownColumn, &[&w]))
+            }
+            token => Err(tr(Message::QueryUnexpectedToken, &[&token])),
+        }
+    }
+
+    fn condition(&mut self) -> Result<Condition, String> {
+        let column = self.column()?;
+        let comparison = match self.advance()? {
+            Token::Symbol("=") => Comparison::Equal,
+            Token::Symbol("!=") | Token::Symbol("<>") => Comparison::NotEqual,
+            Token::Symbol("<") => Comparison::Less,
+            Token::Symbol("<=") => Comparison::LessOrEqual,
+            Token::Symbol(">") => Comparison::Greater,
+            Token::Symbol(">=") => Comparison::GreaterOrEqual,
+            token => return Err(tr(Message::QueryUnexpectedToken, &[&token])),
+        };
+        let value = match self.advance()? {
+            Token::Number(n) if !column.is_text() => Value::Number(n),
+            Token::Text(s) if column.is_text() => Value::Text(s),
+            Token::Number(_) | Token::Text(_) => {
+                return Err(tr(Message::QueryTypeMismatch, &[&column.name()]))
+            }
+            token => return Err(tr(Message::QueryUnexpectedToken, &[&token])),
+        };
+        Ok(Condition {
+            column,
+            comparison,
+            value,
+        })
+    }
+
+    fn query(&mut self) -> Result<Query, String> {
+        self.expect("select")?;
+        let columns = if self.accept("*") {
+            Column::all().to_vec()
+        } else {
+            let mut columns = vec![self.column()?];
+            while self.accept(",") {
+                columns.push(self.column()?);
+            }
+            columns
+        };
+
+        self.expect("from")?;
+        match self.advance()? {
+            Token::Word(w) if w.eq_ignore_ascii_case("crabs") => {}
+            Token::Word(w) => return Err(tr(Message::QueryUnknownTable, &[&w])),
+            token => return Err(tr(Message::QueryUnexpectedToken, &[&token])),
+        }
+
+        let mut conditions = Vec::new();
+        if self.accept("where") {
+            conditions.push(self.condition()?);
+            while self.accept("and") {
+                conditions.push(self.condition()?);
+            }
+        }
+
+        let mut order_by = None;
+        if self.accept("order") {
+            self.expect("by")?;
+            let column = self.column()?;
+            let direction = if self.accept("desc") {
+                Direction::Descending
+            } else {
+                self.accept("asc");
+                Direction::Ascending
+            };
+            order_by = Some((column, direction));
+        }
+
+        let mut limit = None;
+        if self.accept("limit") {
+            match self.advance()? {
+                Token::Number(n) if n >= 0 => limit = Some(n as usize),
+                token => return Err(tr(Message::QueryUnexpectedToken, &[&token])),
+            }
+        }
+
+        self.accept(";");
+        if self.peek().is_some() {
+            return Err(self.unexpected());
+        }
+        Ok(Query {
+            columns,
+            conditions,
+            order_by,
+            limit,
+        })
+    }
+}
+
+impl Query {
+    /**
+     * Parses a query, returning an error describing the first problem found.
+     */
+    pub fn parse(text: &str) -> Result<Query, String> {
+        let tokens = tokenize(text)?;
+        Parser { tokens, next: 0 }.query()
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    pub fn conditions(&self) -> &[Condition] {
+        &self.conditions
+    }
+
+    /**
+     * Runs the query against a beach. Crabs that compare equal under
+     * `ORDER BY` keep their order on the beach.
+     */
+    pub fn run(&self, beach: &Beach) -> QueryResult {
+        let mut query = beach.crab_query();
+        for condition in &self.conditions {
+            query = query.matching(condition.clone());
+        }
+        if let Some((column, direction)) = self.order_by {
+            query = query.order_by(column, direction);
+        }
+        if let Some(limit) = self.limit {
+            query = query.limit(limit);
+        }
+        let crabs = query.collect();
+        QueryResult {
+            columns: self.columns.clone(),
+            rows: crabs
+                .into_iter()
+                .map(|crab| self.columns.iter().map(|c| c.value(crab)).collect())
+                .collect(),
+        }
+    }
+}
+
+type Filter<'a> = Box<dyn Fn(&Crab) -> bool + 'a>;
+type Sorter<'a> = Box<dyn Fn(&Crab, &Crab) -> Ordering + 'a>;
+
+/**
+ * A query over the crabs of a beach built up in code, e.g.
+ *
+ * `beach.crab_query().speed_gt(10).diet(Diet::Shellfish).in_clan("sharks").collect()`
+ *
+ * Every filter must hold for a crab to be returned. Crabs are returned in
+ * the order they live on the beach unless an order is given, and crabs that
+ * compare equal keep that order.
+ */
+pub struct CrabQuery<'a> {
+    beach: &'a Beach,
+    filters: Vec<Filter<'a>>,
+    order: Option<Sorter<'a>>,
+    limit: Option<usize>,
+}
+
+impl fmt::Debug for CrabQuery<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CrabQuery")
+            .field("filters", &self.filters.len())
+            .field("ordered", &self.order.is_some())
+            .field("limit", &self.limit)
+            .finish()
+    }
+}
+
+impl<'a> CrabQuery<'a> {
+    pub fn new(beach: &'a Beach) -> CrabQuery<'a> {
+        CrabQuery {
+            beach,
+            filters: Vec::new(),
+            order: None,
+            limit: None,
+        }
+    }
+
+    /** Keeps only the crabs for which `f` returns true. */
+    pub fn filter(mut self, f: impl Fn(&Crab) -> bool + 'a) -> CrabQuery<'a> {
+        self.filters.push(Box::new(f));
+        self
+    }
+
+    /** Keeps only the crabs a `WHERE` condition holds for. */
+    pub fn matching(self, condition: Condition) -> CrabQuery<'a> {
+        self.filter(move |crab| condition.matches(crab))
+    }
+
+    pub fn named(self, name: &'a str) -> CrabQuery<'a> {
+        self.filter(move |crab| crab.name() == name)
+    }
+
+    pub fn speed_gt(self, speed: u32) -> CrabQuery<'a> {
+        self.filter(move |crab| crab.speed() > speed)
+    }
+
+    pub fn speed_lt(self, speed: u32) -> CrabQuery<'a> {
+        self.filter(move |crab| crab.speed() < speed)
+    }
+
+    pub fn diet(self, diet: Diet) -> CrabQuery<'a> {
+        self.filter(move |crab| crab.diet() == diet)
+    }
+
+    /**
+     * Keeps only the crabs whose color is within `tolerance` of the given
+     * one, as measured by `Color::distance`.
+     */
+    pub fn color_near(self, color: Color, tolerance: f64) -> CrabQuery<'a> {
+        self.filter(move |crab| crab.color().distance(&color) <= tolerance)
+    }
+
+    /** Keeps only the members of the given clan. */
+    pub fn in_clan(self, clan_id: &'a str) -> CrabQuery<'a> {
+        let clans = self.beach.get_clan_system();
+        self.filter(move |crab| crab.id().is_some_and(|id| clans.is_member(clan_id, id)))
+    }
+
+    /** Orders the crabs by the given column, like `ORDER BY`. */
+    pub fn order_by(self, column: Column, direction: Direction) -> CrabQuery<'a> {
+        self.sort_by(move |a, b| {
+            let ordering = column.value(a).compare(&column.value(b));
+            match direction {
+                Direction::Ascending => ordering,
+                Direction::Descending => ordering.reverse(),
+            }
+        })
+    }
+
+    /** Orders the crabs with the given comparison, replacing any previous order. */
+    pub fn sort_by(mut self, compare: impl Fn(&Crab, &Crab) -> Ordering + 'a) -> CrabQuery<'a> {
+        self.order = Some(Box::new(compare));
+        self
+    }
+
+    /** Returns at most `limit` crabs, counted after ordering. */
+    pub fn limit(mut self, limit: usize) -> CrabQuery<'a> {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn collect(self) -> Vec<&'a Crab> {
+        let mut crabs: Vec<&Crab> = self
+            .beach
+            .crabs()
+            .filter(|crab| self.filters.iter().all(|f| f(crab)))
+            .collect();
+        if let Some(order) = &self.order {
+            crabs.sort_by(|a, b| order(a, b));
+        }
+        if let Some(limit) = self.limit {
+            crabs.truncate(limit);
+        }
+        crabs
+    }
+
+    pub fn count(self) -> usize {
+        self.collect().len()
+    }
+}
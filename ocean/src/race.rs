@@ -0,0 +1,168 @@
+use crate::beach::{Beach, CrabId};
+use crate::crab::MAX_HEALTH;
+use crate::error::OceanError;
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
+
+/**
+ * How a race is run. Each crab's finishing time is drawn from a normal
+ * distribution around the time it would take to run `distance` at its
+ * pace, with a standard deviation of `spread` times that time. A crab's
+ * pace is its speed, cut in proportion to its lost health if
+ * `health_matters`, and divided by one plus `age_penalty` for every tick
+ * it has aged.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RaceRules {
+    pub distance: f64,
+    pub spread: f64,
+    pub health_matters: bool,
+    pub age_penalty: f64,
+}
+
+impl Default for RaceRules {
+    fn default() -> Self {
+        RaceRules {
+            distance: 100.0,
+            spread: 0.2,
+            health_matters: false,
+            age_penalty: 0.0,
+        }
+    }
+}
+
+impl RaceRules {
+    /** Draws the time it takes to run the race at the given pace. */
+    fn draw_time(&self, pace: f64, rng: &mut impl Rng) -> f64 {
+        if pace <= 0.0 {
+            return f64::INFINITY;
+        }
+        let expected = self.distance / pace;
+        let spread = (self.spread * expected).max(0.0);
+        let drawn = Normal::new(expected, spread).map_or(expected, |normal| normal.sample(rng));
+        // However lucky, no crab finishes before it starts.
+        drawn.max(0.0)
+    }
+}
+
+/** How long a crab took to finish a race. */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finish {
+    pub id: CrabId,
+    pub name: String,
+    /** The crab's time, or infinity for a crab that could not move. */
+    pub time: f64,
+}
+
+/** The crabs of a race in the order they finished, fastest first. */
+#[derive(Debug, Clone, PartialEq)]
+pub struct RaceResult {
+    pub finishes: Vec<Finish>,
+}
+
+impl RaceResult {
+    pub fn winner(&self) -> Option<&Finish> {
+        self.finishes.first()
+    }
+
+    /**
+     * Returns the crabs in the places they finished, with crabs that
+     * finished in the same time in the same place, as
+     * `Leaderboard::record_race` takes them.
+     */
+    pub fn places(&self) -> Vec<Vec<CrabId>> {
+        self.finishes
+            .chunk_by(|a, b| a.time == b.time)
+            .map(|place| place.iter().map(|finish| finish.id).collect())
+            .collect()
+    }
+}
+
+impl Beach {
+    /** Returns the pace of the crab at the given index by the rules. */
+    fn pace(&self, index: usize, rules: &RaceRules) -> f64 {
+        let crab = self.crab_at(index);
+        let mut pace = self.effective_speed(index) as f64;
+        if rules.health_matters {
+            pace *= crab.health() as f64 / MAX_HEALTH as f64;
+        }
+        pace / (1.0 + rules.age_penalty * crab.age() as f64)
+    }
+
+    /** Races the crabs at the given indices by the default `RaceRules`. */
+    pub fn race(&self, indices: &[usize], rng: &mut impl Rng) -> Result<RaceResult, OceanError> {
+        self.race_with(indices, &RaceRules::default(), rng)
+    }
+
+    /**
+     * Races the crabs at the given indices against each other, drawing their
+     * times from `rng`, so the same seed always gives the same result. Crabs
+     * that finish in the same time keep the order they were listed in. A
+     * crab listed twice runs once. Fails if any index is out of bounds.
+     */
+    pub fn race_with(
+        &self,
+        indices: &[usize],
+        rules: &RaceRules,
+        rng: &mut impl Rng,
+    ) -> Result<RaceResult, OceanError> {
+        let mut finishes: Vec<Finish> = Vec::new();
+        for &index in indices {
+            let crab = self.try_get_crab(index)?;
+            let id = self.handle(index);
+            if finishes.iter().any(|finish| finish.id == id) {
+                continue;
+            }
+            finishes.push(Finish {
+                id,
+                name: crab.name().to_string(),
+                time: rules.draw_time(self.pace(index, rules), rng),
+            });
+        }
+        finishes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Ok(RaceResult { finishes })
+    }
+
+    /**
+     * Races the crabs at the given indices like `race_with`, drawing their
+     * times from the beach's own generator if it was seeded with
+     * `set_seed`, or else from the crate-wide random numbers.
+     */
+    pub fn run_race(
+        &mut self,
+        indices: &[usize],
+        rules: &RaceRules,
+    ) -> Result<RaceResult, OceanError> {
+        self.with_rng(|beach, mut rng| beach.race_with(indices, rules, &mut rng))
+    }
+
+    /**
+     * Races inhabitants of any species, by name, like `race_with`. Crabs
+     * run at their pace by the rules; other species at their speed. Returns
+     * each inhabitant's name and time, fastest first. A name listed twice
+     * runs once. Fails if no inhabitant has one of the names.
+     */
+    pub fn race_inhabitants(
+        &self,
+        names: &[&str],
+        rules: &RaceRules,
+        rng: &mut impl Rng,
+    ) -> Result<Vec<(String, f64)>, OceanError> {
+        let mut times: Vec<(String, f64)> = Vec::new();
+        for &name in names {
+            let inhabitant = self
+                .inhabitant(name)
+                .ok_or_else(|| OceanError::UnknownInhabitant(name.to_string()))?;
+            if times.iter().any(|(runner, _)| runner == name) {
+                continue;
+            }
+            let pace = match self.crabs().position(|crab| crab.name() == name) {
+                Some(index) => self.pace(index, rules),
+                None => inhabitant.typed_speed().into(),
+            };
+            times.push((name.to_string(), rules.draw_time(pace, rng)));
+        }
+        times.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+        Ok(times)
+    }
+}
@@ -0,0 +1,143 @@
+use crate::color::Color;
+use crate::crab::Crab;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+
+/** The color of the sand crabs try to blend into. */
+pub const SAND: Color = Color {
+    r: 0xf4,
+    g: 0xe4,
+    b: 0xbc,
+};
+
+/**
+ * How seagulls raid a beach. Raids happen every `every` ticks and, on any
+ * other tick, at random with probability `chance`; during a raid each of the
+ * `gulls` seagulls carries off one crab. The defaults never raid.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RaidSettings {
+    pub gulls: usize,
+    /** Ticks between scheduled raids, or 0 for no scheduled raids. */
+    pub every: u64,
+    /** The probability of a random raid on each tick. */
+    pub chance: f64,
+    pub seed: u64,
+}
+
+/** What happened during one seagull raid. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RaidReport {
+    pub tick: u64,
+    /** The names of the crabs carried off, in the order they were taken. */
+    pub casualties: Vec<String>,
+    /** The number of crabs left on the beach. */
+    pub survivors: usize,
+}
+
+/**
+ * Returns how well a color blends into the sand, from 0 (as far from it as
+ * a color can be) to 1 (sand-colored).
+ */
+pub fn camouflage(color: &Color) -> f64 {
+    let channel = |c: u8, sand: u8| {
+        let far = sand.max(255 - sand) as f64;
+        (c as f64 - sand as f64).abs() / far
+    };
+    let distance = (channel(color.r, SAND.r).powi(2)
+        + channel(color.g, SAND.g).powi(2)
+        + channel(color.b, SAND.b).powi(2))
+        / 3.0;
+    1.0 - distance.sqrt()
+}
+
+/**
+ * Returns how likely a seagull is to pick a crab, relative to other crabs:
+ * slow crabs that stand out against the sand are the most vulnerable.
+ */
+pub fn vulnerability(crab: &Crab) -> f64 {
+    (2.0 - camouflage(crab.color())) / crab.speed().max(1) as f64
+}
+
+/** Decides when seagulls raid and whom they take. */
+#[derive(Debug)]
+pub struct Raids {
+    settings: RaidSettings,
+    rng: Pcg64,
+    reports: Vec<RaidReport>,
+}
+
+impl Raids {
+    pub fn new(settings: RaidSettings) -> Raids {
+        Raids {
+            settings,
+            rng: Pcg64::seed_from_u64(settings.seed),
+            reports: Vec::new(),
+        }
+    }
+
+    pub fn settings(&self) -> RaidSettings {
+        self.settings
+    }
+
+    /** Replaces the settings, reseeding the raids. */
+    pub fn set_settings(&mut self, settings: RaidSettings) {
+        self.settings = settings;
+        self.rng = Pcg64::seed_from_u64(settings.seed);
+    }
+
+    /** Returns the reports of every raid so far, oldest first. */
+    pub fn reports(&self) -> &[RaidReport] {
+        &self.reports
+    }
+
+    pub fn record(&mut self, report: RaidReport) {
+        self.reports.push(report);
+    }
+
+    /** Decides whether the seagulls raid on the given tick. */
+    pub fn due(&mut self, tick: u64) -> bool {
+        if self.settings.gulls == 0 {
+            return false;
+        }
+        let every = self.settings.every;
+        if every > 0 && tick.is_multiple_of(every) {
+            return true;
+        }
+        self.settings.chance > 0.0 && self.rng.gen_bool(self.settings.chance.min(1.0))
+    }
+
+    /**
+     * Picks the indices of the crabs the seagulls take, one per gull, each
+     * chosen at random in proportion to its vulnerability. Returns them in
+     * the order they were taken.
+     */
+    pub fn pick_victims<'a>(&mut self, crabs: impl Iterator<Item = &'a Crab>) -> Vec<usize> {
+        let mut weights: Vec<f64> = crabs.map(vulnerability).collect();
+        let mut victims = Vec::new();
+        for _ in 0..self.settings.gulls {
+            let total: f64 = weights.iter().sum();
+            if total <= 0.0 {
+                break;
+            }
+            let mut target = self.rng.gen_range(0.0..total);
+            let mut victim = weights.iter().rposition(|&w| w > 0.0).unwrap_or(0);
+            for (k, &w) in weights.iter().enumerate() {
+                if target < w {
+                    victim = k;
+                    break;
+                }
+                target -= w;
+            }
+            weights[victim] = 0.0;
+            victims.push(victim);
+        }
+        victims
+    }
+}
+
+impl Default for Raids {
+    fn default() -> Self {
+        Raids::new(RaidSettings::default())
+    }
+}
@@ -11,15 +11,17 @@ pub struct Reef {
 
 impl Reef {
     pub fn new() -> Self {
-        unimplemented!();
+        Reef {
+            prey: VecDeque::new(),
+        }
     }
 
-    pub fn prey(&self) -> Iter<Box<dyn Prey>> {
-        unimplemented!();
+    pub fn prey(&self) -> Iter<'_, Box<dyn Prey>> {
+        self.prey.iter()
     }
 
     pub fn population(&self) -> usize {
-        unimplemented!();
+        self.prey.len()
     }
 
     /**
@@ -28,7 +30,7 @@ impl Reef {
      * This function takes ownership of the boxed prey.
      */
     pub fn add_prey(&mut self, prey: Box<dyn Prey>) {
-        unimplemented!();
+        self.prey.push_back(prey);
     }
 
     /**
@@ -37,6 +39,12 @@ impl Reef {
      * The callee of this function receives ownership of the boxed prey.
      */
     pub fn take_prey(&mut self) -> Option<Box<dyn Prey>> {
-        unimplemented!();
+        self.prey.pop_front()
+    }
+}
+
+impl Default for Reef {
+    fn default() -> Self {
+        Reef::new()
     }
 }
@@ -0,0 +1,240 @@
+use crate::beach::Beach;
+use crate::color::Color;
+use crate::crab::Crab;
+use crate::diet::Diet;
+use crate::error::OceanError;
+use crate::i18n::{tr, Message};
+use std::fmt::Write as _;
+use std::io::{BufRead, Write};
+
+/** The commands a `Repl` understands, with their arguments. */
+pub const COMMANDS: [&str; 12] = [
+    "add <name> <speed> <color> <diet>",
+    "remove <name>",
+    "breed <parent> <parent> <child>",
+    "join <clan> <crab>...",
+    "compete <clan> <clan>",
+    "tick [count]",
+    "seed <seed>",
+    "crabs",
+    "clans",
+    "report",
+    "help",
+    "quit",
+];
+
+/**
+ * An interactive session on a beach: reads commands, one per line, and
+ * runs them on the beach, such as:
+ *
+ * ```text
+ *     add Ferris 20 #0000ff Plants
+ *     add Ruby 30 red Plants
+ *     breed Ferris Ruby Cargo
+ *     join Rustaceans Ferris Ruby
+ *     tick 10
+ *     report
+ * ```
+ *
+ * Crabs are found by name; where several share a name, the first is
+ * meant. Colors are in hex notation or color names; diets are "Fish",
+ * "Shellfish" or "Plants". Blank lines and lines starting with `#` are
+ * skipped, so a file of commands may be commented.
+ */
+#[derive(Debug, Default)]
+pub struct Repl {
+    beach: Beach,
+    done: bool,
+}
+
+impl Repl {
+    pub fn new() -> Repl {
+        Repl::default()
+    }
+
+    /** Starts a session on a beach that already has crabs or clans. */
+    pub fn with_beach(beach: Beach) -> Repl {
+        Repl { beach, done: false }
+    }
+
+    pub fn beach(&self) -> &Beach {
+        &self.beach
+    }
+
+    pub fn into_beach(self) -> Beach {
+        self.beach
+    }
+
+    /** Returns whether the session was ended by `quit`. */
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /**
+     * Runs one line and returns what it prints, which is empty for
+     * commands that only change the beach. Fails, leaving the beach as it
+     * was, if the command is unknown, its arguments are wrong, or the beach
+     * refuses it.
+     */
+    pub fn execute(&mut self, line: &str) -> Result<String, OceanError> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = words.split_first() else {
+            return Ok(String::new());
+        };
+        if command.starts_with('#') {
+            return Ok(String::new());
+        }
+        let mut out = String::new();
+        match (command, args) {
+            ("add", &[name, speed, color, diet]) => {
+                let speed = speed.parse().map_err(|_| usage(command))?;
+                let color = Color::from_hex(color)
+                    .or_else(|| Color::named(color))
+                    .ok_or_else(|| OceanError::Command(tr(Message::UnknownColor, &[&color])))?;
+                let diet = parse_diet(diet)?;
+                self.beach
+                    .admit_crab(Crab::new(name.to_string(), speed, color, diet))?;
+            }
+            ("remove", &[name]) => {
+                let index = self.index_of(name)?;
+                self.beach.remove_crab_at(index);
+            }
+            ("breed", &[parent1, parent2, child]) => {
+                let (i, j) = (self.index_of(parent1)?, self.index_of(parent2)?);
+                self.beach.try_breed_crabs(i, j, child.to_string())?;
+            }
+            ("join", &[clan, ref crabs @ ..]) if !crabs.is_empty() => {
+                // Every crab is judged before any joins, those admitted so
+                // far counting towards the clan's size.
+                let mut joining = Vec::new();
+                for &crab in crabs {
+                    self.index_of(crab)?;
+                    let clans = self.beach.get_clan_system();
+                    if self.beach.get_clan_of(crab) == Some(clan) || joining.contains(&crab) {
+                        continue;
+                    }
+                    self.beach
+                        .check_admission(clan, crab)
+                        .and_then(|()| clans.check_room(clan, crab, joining.len() + 1))
+                        .map_err(OceanError::Rejected)?;
+                    joining.push(crab);
+                }
+                for crab in joining {
+                    self.beach.add_member_to_clan(clan, crab);
+                }
+            }
+            ("compete", &[clan1, clan2]) => match self.beach.get_winner_clan(clan1, clan2)? {
+                Some(winner) => writeln!(out, "{}", winner).unwrap(),
+                None => writeln!(out, "-").unwrap(),
+            },
+            ("tick", &[]) => self.tick(1, &mut out),
+            ("tick", &[count]) => {
+                let count = count.parse().map_err(|_| usage(command))?;
+                self.tick(count, &mut out);
+            }
+            ("seed", &[seed]) => {
+                let seed = seed.parse().map_err(|_| usage(command))?;
+                self.beach.set_seed(seed);
+            }
+            ("crabs", &[]) => {
+                for crab in self.beach.crabs() {
+                    writeln!(out, "{}", crab).unwrap();
+                }
+            }
+            ("clans", &[]) => {
+                let clans = self.beach.get_clan_system();
+                for id in clans.clan_ids() {
+                    let members = clans.get_clan_member_names(id);
+                    writeln!(out, "{}: {}", id, members.join(", ")).unwrap();
+                }
+            }
+            ("report", &[]) => out = self.beach.report(),
+            ("help", &[]) => {
+                for usage in COMMANDS {
+                    writeln!(out, "{}", usage).unwrap();
+                }
+            }
+            ("quit", &[]) => self.done = true,
+            _ => return Err(usage(command)),
+        }
+        Ok(out)
+    }
+
+    /**
+     * Runs every line of `input` until it ends or a line says `quit`,
+     * writing what each prints to `output`. A line that fails has its
+     * error written in its place, and the session carries on. If `prompt`
+     * is given, it is written before every line is read, as for a person
+     * typing.
+     */
+    pub fn run<R: BufRead, W: Write>(
+        &mut self,
+        input: R,
+        mut output: W,
+        prompt: Option<&str>,
+    ) -> Result<(), OceanError> {
+        let io_error = |e: std::io::Error| OceanError::Io(e.to_string());
+        let mut lines = input.lines();
+        while !self.done {
+            if let Some(prompt) = prompt {
+                write!(output, "{}", prompt).map_err(io_error)?;
+                output.flush().map_err(io_error)?;
+            }
+            let Some(line) = lines.next() else {
+                break;
+            };
+            let text = match self.execute(&line.map_err(io_error)?) {
+                Ok(text) => text,
+                Err(error) => format!("{}\n", error),
+            };
+            write!(output, "{}", text).map_err(io_error)?;
+        }
+        Ok(())
+    }
+
+    fn index_of(&self, name: &str) -> Result<usize, OceanError> {
+        self.beach
+            .crabs()
+            .position(|crab| crab.name() == name)
+            .ok_or_else(|| OceanError::UnknownInhabitant(name.to_string()))
+    }
+
+    fn tick(&mut self, count: u64, out: &mut String) {
+        for _ in 0..count {
+            let report = self.beach.tick();
+            writeln!(
+                out,
+                "{}",
+                tr(
+                    Message::CommandTicked,
+                    &[&report.tick, &report.births.len(), &report.deaths.len()]
+                )
+            )
+            .unwrap();
+        }
+    }
+}
+
+fn parse_diet(diet: &str) -> Result<Diet, OceanError> {
+    match diet {
+        "Fish" => Ok(Diet::Fish),
+        "Shellfish" => Ok(Diet::Shellfish),
+        "Plants" => Ok(Diet::Plants),
+        _ => Err(OceanError::Command(tr(Message::CsvBadDiet, &[&diet]))),
+    }
+}
+
+/**
+ * Returns the error for a command given the wrong arguments, with its
+ * usage, or for a command that does not exist.
+ */
+fn usage(command: &str) -> OceanError {
+    let message = match COMMANDS
+        .iter()
+        .find(|usage| usage.split(' ').next() == Some(command))
+    {
+        Some(usage) => tr(Message::CommandUsage, &[usage]),
+        None => tr(Message::CommandUnknown, &[&command]),
+    };
+    OceanError::Command(message)
+}
@@ -0,0 +1,391 @@
+use crate::battle::{Resolution, Side};
+use crate::beach::Beach;
+use crate::diet::Diet;
+use crate::i18n::{tr, Message};
+use std::fmt::Write;
+
+/** The most rows shown in the population and leaderboard tables. */
+const MAX_ROWS: usize = 20;
+/** The most clans shown per row of the leaderboard history. */
+const HISTORY_CLANS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+/** The state of the beach at the start of a tick. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TickRecord {
+    pub tick: u64,
+    pub population: usize,
+    /** Clans by id with their member counts, largest first. */
+    pub clans: Vec<(String, usize)>,
+}
+
+impl TickRecord {
+    pub fn capture(beach: &Beach) -> TickRecord {
+        TickRecord {
+            tick: beach.ticks(),
+            population: beach.size(),
+            clans: leaderboard(beach),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotableEvent {
+    ClanFounded {
+        clan_id: String,
+        founder: String,
+    },
+    LargestClanChanged {
+        clan_id: String,
+        members: usize,
+    },
+    Battle {
+        attacker: String,
+        defender: String,
+        resolution: Resolution,
+    },
+}
+
+impl NotableEvent {
+    /** Describes the event in the current locale. */
+    pub fn describe(&self) -> String {
+        match self {
+            NotableEvent::ClanFounded { clan_id, founder } => {
+                tr(Message::EventClanFounded, &[clan_id, founder])
+            }
+            NotableEvent::LargestClanChanged { clan_id, members } => {
+                tr(Message::EventLargestClan, &[clan_id, members])
+            }
+            NotableEvent::Battle {
+                attacker,
+                defender,
+                resolution,
+            } => {
+                let name = |side: Side| match side {
+                    Side::Attacker => attacker,
+                    Side::Defender => defender,
+                };
+                match *resolution {
+                    Resolution::Victory(side) => tr(
+                        Message::EventBattleWon,
+                        &[name(side), name(side.opponent())],
+                    ),
+                    Resolution::Retreat(side) => tr(
+                        Message::EventBattleRetreat,
+                        &[name(side), name(side.opponent())],
+                    ),
+                    Resolution::Stalemate => {
+                        tr(Message::EventBattleStalemate, &[attacker, defender])
+                    }
+                }
+            }
+        }
+    }
+}
+
+/** What happened over a run: one record per tick, and the notable events. */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct History {
+    pub ticks: Vec<TickRecord>,
+    /** Notable events with the tick during which they happened. */
+    pub events: Vec<(u64, NotableEvent)>,
+}
+
+/** Returns the beach's clans with their member counts, largest first. */
+pub fn leaderboard(beach: &Beach) -> Vec<(String, usize)> {
+    let clans = beach.get_clan_system();
+    let mut board: Vec<(String, usize)> = clans
+        .clan_ids()
+        .map(|id| (id.to_string(), clans.get_clan_member_count(id)))
+        .collect();
+    board.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    board
+}
+
+/** Picks at most `MAX_ROWS` records, evenly spaced, always keeping the last. */
+fn downsample(records: &[TickRecord]) -> Vec<&TickRecord> {
+    let step = records.len().div_ceil(MAX_ROWS).max(1);
+    let mut rows: Vec<&TickRecord> = records.iter().step_by(step).collect();
+    if let Some(last) = records.last() {
+        if rows.last().map(|r| r.tick) != Some(last.tick) {
+            rows.push(last);
+        }
+    }
+    rows
+}
+
+fn sparkline(values: &[usize]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    values
+        .iter()
+        .map(|&v| BARS[v * (BARS.len() - 1) / max])
+        .collect()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+/**
+ * Renders a report of a run: an overview of the final beach, the population
+ * curve, the final clan leaderboard and how it evolved, and notable events.
+ * Long histories are sampled down to at most 20 rows per table.
+ */
+pub fn render(beach: &Beach, seed: u64, history: &History, format: ReportFormat) -> String {
+    let overview = tr(
+        Message::ReportOverview,
+        &[
+            &seed,
+            &beach.ticks(),
+            &beach.size(),
+            &beach.get_clan_system().get_clan_count(),
+        ],
+    );
+    let rows = downsample(&history.ticks);
+
+    let population: Vec<Vec<String>> = rows
+        .iter()
+        .map(|r| vec![r.tick.to_string(), r.population.to_string()])
+        .collect();
+    let standings: Vec<Vec<String>> = leaderboard(beach)
+        .into_iter()
+        .enumerate()
+        .map(|(k, (id, n))| vec![(k + 1).to_string(), id, n.to_string()])
+        .collect();
+    let leaders: Vec<Vec<String>> = rows
+        .iter()
+        .filter(|r| !r.clans.is_empty())
+        .map(|r| {
+            let top: Vec<String> = r
+                .clans
+                .iter()
+                .take(HISTORY_CLANS)
+                .map(|(id, n)| format!("{} ({})", id, n))
+                .collect();
+            vec![r.tick.to_string(), top.join(", ")]
+        })
+        .collect();
+    let events: Vec<String> = history
+        .events
+        .iter()
+        .map(|(tick, event)| {
+            format!(
+                "{} {}: {}",
+                tr(Message::ColumnTick, &[]),
+                tick,
+                event.describe()
+            )
+        })
+        .collect();
+
+    let tick = tr(Message::ColumnTick, &[]);
+    let sections: Vec<(String, Section)> = vec![
+        (
+            tr(Message::ReportPopulation, &[]),
+            Section::Table(
+                vec![tick.clone(), tr(Message::ReportPopulation, &[])],
+                population,
+            ),
+        ),
+        (
+            tr(Message::ReportLeaderboard, &[]),
+            Section::Table(
+                vec![
+                    tr(Message::ColumnRank, &[]),
+                    tr(Message::ColumnClan, &[]),
+                    tr(Message::ColumnMembers, &[]),
+                ],
+                standings,
+            ),
+        ),
+        (
+            tr(Message::ReportLeaderboardHistory, &[]),
+            Section::Table(vec![tick, tr(Message::ReportLeaderboard, &[])], leaders),
+        ),
+        (tr(Message::ReportEvents, &[]), Section::List(events)),
+    ];
+    let curve: Vec<usize> = history.ticks.iter().map(|r| r.population).collect();
+
+    match format {
+        ReportFormat::Markdown => markdown(&overview, &curve, &sections),
+        ReportFormat::Html => html(&overview, &sections),
+    }
+}
+
+enum Section {
+    Table(Vec<String>, Vec<Vec<String>>),
+    List(Vec<String>),
+}
+
+impl Section {
+    fn is_empty(&self) -> bool {
+        match self {
+            Section::Table(_, rows) => rows.is_empty(),
+            Section::List(items) => items.is_empty(),
+        }
+    }
+}
+
+fn markdown(overview: &str, curve: &[usize], sections: &[(String, Section)]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# {}\n\n{}", tr(Message::ReportTitle, &[]), overview);
+    for (k, (title, section)) in sections.iter().enumerate() {
+        let _ = writeln!(out, "\n## {}\n", title);
+        if k == 0 && !curve.is_empty() {
+            let _ = writeln!(out, "`{}`\n", sparkline(curve));
+        }
+        match section {
+            _ if section.is_empty() => {
+                let _ = writeln!(out, "{}", tr(Message::ReportNothing, &[]));
+            }
+            Section::Table(header, rows) => {
+                let _ = writeln!(out, "| {} |", header.join(" | "));
+                let _ = writeln!(out, "|{}", " --- |".repeat(header.len()));
+                for row in rows {
+                    let cells: Vec<String> = row.iter().map(|c| escape_markdown(c)).collect();
+                    let _ = writeln!(out, "| {} |", cells.join(" | "));
+                }
+            }
+            Section::List(items) => {
+                for item in items {
+                    let _ = writeln!(out, "- {}", item);
+                }
+            }
+        }
+    }
+    out
+}
+
+fn html(overview: &str, sections: &[(String, Section)]) -> String {
+    let title = escape_html(&tr(Message::ReportTitle, &[]));
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{}</title></head>\n<body>\n<h1>{}</h1>\n<p>{}</p>",
+        title,
+        title,
+        escape_html(overview)
+    );
+    for (title, section) in sections {
+        let _ = writeln!(out, "<h2>{}</h2>", escape_html(title));
+        match section {
+            _ if section.is_empty() => {
+                let _ = writeln!(
+                    out,
+                    "<p>{}</p>",
+                    escape_html(&tr(Message::ReportNothing, &[]))
+                );
+            }
+            Section::Table(header, rows) => {
+                out.push_str("<table>\n<tr>");
+                for cell in header {
+                    let _ = write!(out, "<th>{}</th>", escape_html(cell));
+                }
+                out.push_str("</tr>\n");
+                for row in rows {
+                    out.push_str("<tr>");
+                    for cell in row {
+                        let _ = write!(out, "<td>{}</td>", escape_html(cell));
+                    }
+                    out.push_str("</tr>\n");
+                }
+                out.push_str("</table>\n");
+            }
+            Section::List(items) => {
+                out.push_str("<ul>\n");
+                for item in items {
+                    let _ = writeln!(out, "<li>{}</li>", escape_html(item));
+                }
+                out.push_str("</ul>\n");
+            }
+        }
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+impl Beach {
+    /**
+     * Returns a plain-text summary of the beach, one fact per line: its
+     * population, its fastest crab, its clans ranked by average speed and
+     * how many crabs follow each diet.
+     */
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "{}", tr(Message::BeachReportTitle, &[]));
+        let _ = writeln!(
+            out,
+            "{}",
+            tr(Message::ReportPopulationCount, &[&self.size()])
+        );
+        if let Some(fastest) = self.get_fastest_crab() {
+            let _ = writeln!(
+                out,
+                "{}",
+                tr(
+                    Message::ReportFastestCrab,
+                    &[&fastest.name(), &fastest.speed()]
+                )
+            );
+        }
+
+        let _ = writeln!(out, "\n{}", tr(Message::ReportLeaderboard, &[]));
+        let clans = self.get_clan_system();
+        let mut standings: Vec<(&str, Option<f64>)> = clans
+            .clan_ids()
+            .map(|id| (id, self.get_crabs_avg_speed(id)))
+            .collect();
+        // Clans without crabs on the beach come last.
+        standings.sort_by(|a, b| {
+            let speed = |s: Option<f64>| s.unwrap_or(f64::NEG_INFINITY);
+            speed(b.1).total_cmp(&speed(a.1)).then(a.0.cmp(b.0))
+        });
+        if standings.is_empty() {
+            let _ = writeln!(out, "  {}", tr(Message::ReportNothing, &[]));
+        }
+        for (k, (id, speed)) in standings.into_iter().enumerate() {
+            let speed = speed.map_or(String::from("-"), |s| format!("{:.1}", s));
+            let members = clans.get_clan_member_count(id);
+            let _ = writeln!(
+                out,
+                "  {}",
+                tr(
+                    Message::ReportClanStanding,
+                    &[&(k + 1), &id, &members, &speed]
+                )
+            );
+        }
+
+        let _ = writeln!(out, "\n{}", tr(Message::ReportDiets, &[]));
+        if self.size() == 0 {
+            let _ = writeln!(out, "  {}", tr(Message::ReportNothing, &[]));
+        }
+        for diet in [Diet::Fish, Diet::Shellfish, Diet::Plants] {
+            let count = self.crabs().filter(|crab| crab.diet() == diet).count();
+            if count > 0 {
+                let share = format!("{:.0}", 100.0 * count as f64 / self.size() as f64);
+                let _ = writeln!(
+                    out,
+                    "  {}",
+                    tr(
+                        Message::ReportDietCount,
+                        &[&format!("{:?}", diet), &count, &share]
+                    )
+                );
+            }
+        }
+        out
+    }
+}
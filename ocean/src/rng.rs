@@ -0,0 +1,33 @@
+use crate::rand::rand32;
+use ::rand::{Error, RngCore};
+
+/**
+ * The crate-wide random numbers of `rand::rand32` as a `RngCore`, for the
+ * operations of beaches that have no random number generator of their own.
+ * Every draw advances the same per-thread sequence as `rand32`.
+ */
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GlobalRng;
+
+impl RngCore for GlobalRng {
+    fn next_u32(&mut self) -> u32 {
+        rand32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let low = self.next_u32() as u64;
+        (self.next_u32() as u64) << 32 | low
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            let bytes = self.next_u32().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use crate::beach::Beach;
+
+/**
+ * A condition on the state of a beach, checked at the end of each tick.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EndCondition {
+    /** Some clan has at least this many members; that clan wins. */
+    ClanMembers(usize),
+    /** The beach clock has reached this tick. */
+    Tick(u64),
+    /** The beach holds at least this many crabs. */
+    PopulationAtLeast(usize),
+    /** The beach holds at most this many crabs. */
+    PopulationAtMost(usize),
+    /** Every one of the conditions holds. */
+    AllOf(Vec<EndCondition>),
+    /** At least one of the conditions holds. */
+    AnyOf(Vec<EndCondition>),
+}
+
+impl EndCondition {
+    /**
+     * Returns None if the condition does not hold, and otherwise Some of the
+     * winning clan, if the condition names one.
+     */
+    pub fn check(&self, beach: &Beach) -> Option<Option<String>> {
+        match self {
+            EndCondition::ClanMembers(members) => {
+                let clans = beach.get_clan_system();
+                let largest = clans.get_largest_clan_id()?;
+                if clans.get_clan_member_count(&largest) >= *members {
+                    Some(Some(largest))
+                } else {
+                    None
+                }
+            }
+            EndCondition::Tick(tick) => (beach.ticks() >= *tick).then_some(None),
+            EndCondition::PopulationAtLeast(n) => (beach.size() >= *n).then_some(None),
+            EndCondition::PopulationAtMost(n) => (beach.size() <= *n).then_some(None),
+            EndCondition::AllOf(conditions) => {
+                let mut winner = None;
+                for condition in conditions {
+                    winner = winner.or(condition.check(beach)?);
+                }
+                Some(winner)
+            }
+            EndCondition::AnyOf(conditions) => conditions.iter().find_map(|c| c.check(beach)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub name: String,
+    pub condition: EndCondition,
+}
+
+/** Which rule ended a simulation, when, and who won. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verdict {
+    pub rule: String,
+    pub tick: u64,
+    pub winner: Option<String>,
+}
+
+/**
+ * The end conditions of a scenario. Rules are checked in the order they
+ * were added, and the first one that holds ends the simulation.
+ *
+ * For example, "the simulation ends when any clan reaches 50 members or at
+ * tick 10 000" is a rule for `ClanMembers(50)` followed by one for
+ * `Tick(10_000)`.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    pub fn new() -> RuleSet {
+        RuleSet { rules: Vec::new() }
+    }
+
+    pub fn with_rule(mut self, name: &str, condition: EndCondition) -> RuleSet {
+        self.add_rule(name, condition);
+        self
+    }
+
+    pub fn add_rule(&mut self, name: &str, condition: EndCondition) {
+        self.rules.push(Rule {
+            name: name.to_string(),
+            condition,
+        });
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /**
+     * Returns the verdict of the first rule that holds on the beach, or None
+     * if the simulation should go on.
+     */
+    pub fn evaluate(&self, beach: &Beach) -> Option<Verdict> {
+        self.rules.iter().find_map(|rule| {
+            let winner = rule.condition.check(beach)?;
+            Some(Verdict {
+                rule: rule.name.clone(),
+                tick: beach.ticks(),
+                winner,
+            })
+        })
+    }
+}
@@ -0,0 +1,324 @@
+use crate::beach::Beach;
+use crate::breeding::BreedingRules;
+use crate::color::Color;
+use crate::crab::Crab;
+use crate::diet::Diet;
+use crate::event::{BeachEvent, EventListener};
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+type Action = Box<dyn FnOnce(&mut Beach) -> Result<(), String>>;
+
+struct Step {
+    description: String,
+    action: Action,
+}
+
+/**
+ * A readable end-to-end test: a beach given some crabs and clans, then the
+ * things that happen to it, in order. Nothing happens until `run`, which
+ * returns an `Outcome` to check expectations against. For example:
+ *
+ * ```text
+ *     Scenario::new()
+ *         .crab("Ferris", 20, Color::new_blue(), Diet::Plants)
+ *         .crab("Ruby", 30, Color::new_red(), Diet::Plants)
+ *         .clan("Rustaceans", &["Ferris", "Ruby"])
+ *         .breed("Ferris", "Ruby", "Cargo")
+ *         .ticks(10)
+ *         .run()
+ *         .expect_population(3)
+ *         .expect_parents("Cargo", "Ferris", "Ruby");
+ * ```
+ *
+ * When a step or an expectation fails, the panic message tells the whole
+ * story of the scenario up to that point.
+ */
+pub struct Scenario {
+    steps: Vec<Step>,
+}
+
+impl Scenario {
+    /** Starts a scenario on an empty beach. */
+    pub fn new() -> Scenario {
+        Scenario { steps: Vec::new() }
+    }
+
+    /** Adds a step described by `description`, for anything not built in. */
+    pub fn then(
+        mut self,
+        description: &str,
+        action: impl FnOnce(&mut Beach) -> Result<(), String> + 'static,
+    ) -> Scenario {
+        self.steps.push(Step {
+            description: description.to_string(),
+            action: Box::new(action),
+        });
+        self
+    }
+
+    /** Adds a crab with a claw size of 1. */
+    pub fn crab(self, name: &str, speed: u32, color: Color, diet: Diet) -> Scenario {
+        self.with_crab(Crab::new(name.to_string(), speed, color, diet))
+    }
+
+    /** Adds a crab built elsewhere. */
+    pub fn with_crab(self, crab: Crab) -> Scenario {
+        let description = format!("given a crab {} with speed {}", crab.name(), crab.speed());
+        self.then(&description, move |beach| {
+            beach.admit_crab(crab).map_err(|e| e.to_string())?;
+            Ok(())
+        })
+    }
+
+    /** Has the named crabs join a clan, in order. */
+    pub fn clan(self, clan_id: &str, members: &[&str]) -> Scenario {
+        let id = clan_id.to_string();
+        let members: Vec<String> = members.iter().map(|m| m.to_string()).collect();
+        let description = format!("given a clan {} of {}", id, members.join(", "));
+        self.then(&description, move |beach| {
+            for member in &members {
+                index_of(beach, member)?;
+                beach.add_member_to_clan(&id, member);
+            }
+            Ok(())
+        })
+    }
+
+    pub fn breeding_rules(self, rules: BreedingRules) -> Scenario {
+        self.then("given breeding rules", move |beach| {
+            beach.set_breeding_rules(rules);
+            Ok(())
+        })
+    }
+
+    /** Breeds two crabs, found by name, into a child with the given name. */
+    pub fn breed(self, parent1: &str, parent2: &str, child: &str) -> Scenario {
+        let (parent1, parent2, child) =
+            (parent1.to_string(), parent2.to_string(), child.to_string());
+        let description = format!("after breeding {} and {} into {}", parent1, parent2, child);
+        self.then(&description, move |beach| {
+            let i = index_of(beach, &parent1)?;
+            let j = index_of(beach, &parent2)?;
+            beach
+                .try_breed_crabs(i, j, child)
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    /** Advances the beach clock `n` times. */
+    pub fn ticks(self, n: u64) -> Scenario {
+        self.then(&format!("after {} ticks", n), move |beach| {
+            for _ in 0..n {
+                beach.tick();
+            }
+            Ok(())
+        })
+    }
+
+    /**
+     * Plays every step in order on a fresh beach. If a step fails, the
+     * method should panic.
+     */
+    pub fn run(self) -> Outcome {
+        let mut beach = Beach::new();
+        let recorder = Rc::new(RefCell::new(Recorder(Vec::new())));
+        beach.subscribe(recorder.clone());
+        let mut story = Vec::new();
+        for step in self.steps {
+            story.push(step.description);
+            if let Err(reason) = (step.action)(&mut beach) {
+                panic!("{}\nfailed: {}", tell(&story), reason);
+            }
+        }
+        Outcome {
+            beach,
+            recorder,
+            story,
+        }
+    }
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Scenario::new()
+    }
+}
+
+impl fmt::Debug for Scenario {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list()
+            .entries(self.steps.iter().map(|step| &step.description))
+            .finish()
+    }
+}
+
+struct Recorder(Vec<BeachEvent>);
+
+impl EventListener for Recorder {
+    fn on_event(&mut self, event: &BeachEvent) {
+        self.0.push(event.clone());
+    }
+}
+
+fn index_of(beach: &Beach, name: &str) -> Result<usize, String> {
+    beach
+        .crabs()
+        .position(|crab| crab.name() == name)
+        .ok_or_else(|| format!("no crab named {}", name))
+}
+
+fn tell(story: &[String]) -> String {
+    story
+        .iter()
+        .enumerate()
+        .map(|(n, line)| format!("  {}. {}", n + 1, line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/**
+ * The beach a scenario left behind, with the events it published. Every
+ * expectation panics with the scenario's story if it does not hold, and
+ * returns the outcome otherwise so expectations can be chained.
+ */
+pub struct Outcome {
+    beach: Beach,
+    recorder: Rc<RefCell<Recorder>>,
+    story: Vec<String>,
+}
+
+impl Outcome {
+    pub fn beach(&self) -> &Beach {
+        &self.beach
+    }
+
+    /** Returns every event the beach published while the scenario ran. */
+    pub fn events(&self) -> Vec<BeachEvent> {
+        self.recorder.borrow().0.clone()
+    }
+
+    fn fail(&self, expectation: String) -> ! {
+        panic!("{}\nexpected {}", tell(&self.story), expectation);
+    }
+
+    /** Expects `predicate` to hold for the beach. */
+    pub fn expect(&self, description: &str, predicate: impl FnOnce(&Beach) -> bool) -> &Outcome {
+        if !predicate(&self.beach) {
+            self.fail(description.to_string());
+        }
+        self
+    }
+
+    pub fn expect_population(&self, size: usize) -> &Outcome {
+        if self.beach.size() != size {
+            self.fail(format!("{} crabs, found {}", size, self.beach.size()));
+        }
+        self
+    }
+
+    pub fn expect_crab(&self, name: &str) -> &Outcome {
+        if index_of(&self.beach, name).is_err() {
+            self.fail(format!("a crab named {}", name));
+        }
+        self
+    }
+
+    pub fn expect_no_crab(&self, name: &str) -> &Outcome {
+        if index_of(&self.beach, name).is_ok() {
+            self.fail(format!("no crab named {}", name));
+        }
+        self
+    }
+
+    pub fn expect_speed(&self, name: &str, speed: u32) -> &Outcome {
+        match index_of(&self.beach, name) {
+            Ok(i) if self.beach.crab_at(i).speed() == speed => self,
+            Ok(i) => self.fail(format!(
+                "{} to have speed {}, found {}",
+                name,
+                speed,
+                self.beach.crab_at(i).speed()
+            )),
+            Err(_) => self.fail(format!("a crab named {}", name)),
+        }
+    }
+
+    /** Expects exactly these members in the clan, in joining order. */
+    pub fn expect_members(&self, clan_id: &str, members: &[&str]) -> &Outcome {
+        let found = self.beach.get_clan_system().get_clan_member_names(clan_id);
+        if found != members {
+            self.fail(format!(
+                "{} to have members {:?}, found {:?}",
+                clan_id, members, found
+            ));
+        }
+        self
+    }
+
+    pub fn expect_clan_size(&self, clan_id: &str, size: usize) -> &Outcome {
+        let found = self.beach.get_clan_system().get_clan_member_count(clan_id);
+        if found != size {
+            self.fail(format!(
+                "{} to have {} members, found {}",
+                clan_id, size, found
+            ));
+        }
+        self
+    }
+
+    /** Expects the named crab to have been bred from the two parents, in either order. */
+    pub fn expect_parents(&self, child: &str, parent1: &str, parent2: &str) -> &Outcome {
+        let name_of = |handle| self.beach.resolve(handle).map(|crab| crab.name());
+        let parents = index_of(&self.beach, child)
+            .ok()
+            .and_then(|i| self.beach.lineage().parents(self.beach.handle(i)))
+            .map(|(p1, p2)| (name_of(p1), name_of(p2)));
+        let expected = (Some(parent1), Some(parent2));
+        let swapped = (Some(parent2), Some(parent1));
+        if parents != Some(expected) && parents != Some(swapped) {
+            self.fail(format!(
+                "{} to be the child of {} and {}, found {:?}",
+                child, parent1, parent2, parents
+            ));
+        }
+        self
+    }
+
+    /** Expects the beach to have published the event. */
+    pub fn expect_event(&self, event: &BeachEvent) -> &Outcome {
+        if !self.recorder.borrow().0.contains(event) {
+            self.fail(format!("the event {:?}", event));
+        }
+        self
+    }
+
+    /** Expects exactly `count` published events to match `predicate`. */
+    pub fn expect_event_count(
+        &self,
+        count: usize,
+        predicate: impl Fn(&BeachEvent) -> bool,
+    ) -> &Outcome {
+        let found = self
+            .recorder
+            .borrow()
+            .0
+            .iter()
+            .filter(|e| predicate(e))
+            .count();
+        if found != count {
+            self.fail(format!("{} matching events, found {}", count, found));
+        }
+        self
+    }
+}
+
+impl fmt::Debug for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Outcome")
+            .field("beach", &self.beach)
+            .field("story", &self.story)
+            .finish()
+    }
+}
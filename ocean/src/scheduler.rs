@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+
+/** An event waiting in a scheduler, and how often it recurs. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry<T> {
+    event: T,
+    every: Option<u64>,
+}
+
+/**
+ * A queue of events due at future ticks, so that timed mechanics don't each
+ * keep their own countdown. Events due at the same tick come out in the
+ * order they were scheduled; recurring events are rescheduled as they are
+ * drained.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scheduler<T> {
+    // (due tick, scheduling order) -> the event.
+    queue: BTreeMap<(u64, u64), Entry<T>>,
+    scheduled: u64,
+}
+
+impl<T: Clone> Scheduler<T> {
+    pub fn new() -> Scheduler<T> {
+        Scheduler {
+            queue: BTreeMap::new(),
+            scheduled: 0,
+        }
+    }
+
+    fn push(&mut self, tick: u64, entry: Entry<T>) {
+        self.queue.insert((tick, self.scheduled), entry);
+        self.scheduled += 1;
+    }
+
+    /** Schedules an event for the given tick. */
+    pub fn at(&mut self, tick: u64, event: T) {
+        self.push(tick, Entry { event, every: None });
+    }
+
+    /** Schedules an event `delay` ticks after the tick `now`. */
+    pub fn after(&mut self, now: u64, delay: u64, event: T) {
+        self.at(now + delay, event);
+    }
+
+    /**
+     * Schedules an event for every `interval` ticks, starting `interval`
+     * ticks after `now`. Panics if `interval` is zero.
+     */
+    pub fn every(&mut self, now: u64, interval: u64, event: T) {
+        assert!(interval > 0, "a recurring event needs a positive interval");
+        self.push(
+            now + interval,
+            Entry {
+                event,
+                every: Some(interval),
+            },
+        );
+    }
+
+    /** Returns the number of events waiting, counting recurring ones once. */
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /** Returns the tick at which the next event is due, if any. */
+    pub fn next_due(&self) -> Option<u64> {
+        self.queue.keys().next().map(|&(tick, _)| tick)
+    }
+
+    /** Removes every waiting event, including recurring ones. */
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    /**
+     * Removes and returns the events due at or before the given tick, oldest
+     * first, rescheduling any recurring ones.
+     */
+    pub fn drain(&mut self, tick: u64) -> Vec<T> {
+        let later = self.queue.split_off(&(tick + 1, 0));
+        let due = std::mem::replace(&mut self.queue, later);
+        let mut events = Vec::with_capacity(due.len());
+        for ((at, _), entry) in due {
+            if let Some(interval) = entry.every {
+                self.push(at + interval, entry.clone());
+            }
+            events.push(entry.event);
+        }
+        events
+    }
+}
+
+impl<T: Clone> Default for Scheduler<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,201 @@
+use crate::color::Color;
+use crate::diet::Diet;
+use crate::error::OceanError;
+use crate::i18n::{tr, Message};
+use crate::scenario::Scenario;
+use crate::weather::{Weather, WeatherRules};
+use serde::Deserialize;
+use std::path::Path;
+
+/**
+ * A scenario written down rather than built in code: the crabs, clans and
+ * weather a beach starts with, and the events that happen to it as its
+ * clock runs. Scripts are read from TOML, such as:
+ *
+ * ```text
+ *     seed = 7
+ *     ticks = 12
+ *
+ *     [weather]
+ *     rain_chance = 0.5
+ *
+ *     [[crabs]]
+ *     name = "Ferris"
+ *     speed = 20
+ *     color = "#0000ff"
+ *     diet = "Plants"
+ *
+ *     [[crabs]]
+ *     name = "Ruby"
+ *     speed = 30
+ *     color = "red"
+ *     diet = "Plants"
+ *
+ *     [[clans]]
+ *     id = "Rustaceans"
+ *     members = ["Ferris", "Ruby"]
+ *
+ *     [[events]]
+ *     tick = 5
+ *     do = "breed"
+ *     parents = ["Ferris", "Ruby"]
+ *     child = "Cargo"
+ *
+ *     [[events]]
+ *     tick = 10
+ *     do = "storm"
+ * ```
+ *
+ * Everything but the crabs' fields may be left out. Colors are in hex
+ * notation or color names; diets are "Fish", "Shellfish" or "Plants".
+ */
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Script {
+    /** The seed of the beach's random numbers. */
+    pub seed: Option<u64>,
+    /** How many ticks to run for, at least; the last event may run it longer. */
+    #[serde(default)]
+    pub ticks: u64,
+    /** The beach's weather, if it has any; see `Beach::set_weather`. */
+    pub weather: Option<WeatherRules>,
+    #[serde(default)]
+    pub crabs: Vec<ScriptCrab>,
+    #[serde(default)]
+    pub clans: Vec<ScriptClan>,
+    #[serde(default)]
+    pub events: Vec<ScriptEvent>,
+}
+
+/** A crab on the beach when a script starts, or arriving during it. */
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScriptCrab {
+    pub name: String,
+    pub speed: u32,
+    pub color: String,
+    pub diet: Diet,
+}
+
+/** A clan whose crabs, found by name, join it in order. */
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScriptClan {
+    pub id: String,
+    #[serde(default)]
+    pub members: Vec<String>,
+}
+
+/**
+ * Something that happens once the beach clock reads `tick`, before it
+ * advances further. Events at the same tick happen in the order written.
+ */
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ScriptEvent {
+    pub tick: u64,
+    #[serde(flatten)]
+    pub action: ScriptAction,
+}
+
+/** What an event does, named by its `do` field. */
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "do", rename_all = "snake_case")]
+pub enum ScriptAction {
+    /** Breeds two crabs, found by name, into a child with the given name. */
+    Breed { parents: [String; 2], child: String },
+    /** Makes the next tick stormy; see `Beach::forecast_weather`. */
+    Storm,
+    /** Makes the weather on the next tick whatever is given. */
+    Weather { weather: Weather },
+    /** Has a crab, found by name, join a clan. */
+    Join { clan: String, crab: String },
+    /** Brings a new crab to the beach. */
+    Arrive(ScriptCrab),
+}
+
+impl Script {
+    /** Reads a script from TOML text. */
+    pub fn from_toml(text: &str) -> Result<Script, OceanError> {
+        toml::from_str(text).map_err(|e| OceanError::Script(e.to_string()))
+    }
+
+    /**
+     * Turns the script into a scenario to run. Fails if a crab's color is
+     * neither a hex color nor a color name; crabs and clans that are missing
+     * only fail the scenario when it runs.
+     */
+    pub fn to_scenario(&self) -> Result<Scenario, OceanError> {
+        let mut scenario = Scenario::new();
+        if let Some(seed) = self.seed {
+            scenario = scenario.then(&format!("given the seed {}", seed), move |beach| {
+                beach.set_seed(seed);
+                Ok(())
+            });
+        }
+        if let Some(rules) = self.weather {
+            scenario = scenario.then("given weather", move |beach| {
+                beach.set_weather(rules);
+                Ok(())
+            });
+        }
+        for crab in &self.crabs {
+            scenario = scenario.crab(&crab.name, crab.speed, color(crab)?, crab.diet);
+        }
+        for clan in &self.clans {
+            let members: Vec<&str> = clan.members.iter().map(String::as_str).collect();
+            scenario = scenario.clan(&clan.id, &members);
+        }
+        let mut events: Vec<&ScriptEvent> = self.events.iter().collect();
+        events.sort_by_key(|event| event.tick);
+        let mut now = 0;
+        for event in events {
+            if event.tick > now {
+                scenario = scenario.ticks(event.tick - now);
+                now = event.tick;
+            }
+            scenario = match &event.action {
+                ScriptAction::Breed {
+                    parents: [parent1, parent2],
+                    child,
+                } => scenario.breed(parent1, parent2, child),
+                ScriptAction::Storm => forecast(scenario, Weather::Storm),
+                ScriptAction::Weather { weather } => forecast(scenario, *weather),
+                ScriptAction::Join { clan, crab } => scenario.clan(clan, &[crab.as_str()]),
+                ScriptAction::Arrive(crab) => {
+                    scenario.crab(&crab.name, crab.speed, color(crab)?, crab.diet)
+                }
+            };
+        }
+        if self.ticks > now {
+            scenario = scenario.ticks(self.ticks - now);
+        }
+        Ok(scenario)
+    }
+}
+
+impl Scenario {
+    /** Reads a scenario from a TOML script; see `Script`. */
+    pub fn from_toml(text: &str) -> Result<Scenario, OceanError> {
+        Script::from_toml(text)?.to_scenario()
+    }
+
+    /** Reads a scenario from a TOML script file; see `Script`. */
+    pub fn load_toml(path: impl AsRef<Path>) -> Result<Scenario, OceanError> {
+        let text = std::fs::read_to_string(path).map_err(|e| OceanError::Io(e.to_string()))?;
+        Scenario::from_toml(&text)
+    }
+}
+
+fn color(crab: &ScriptCrab) -> Result<Color, OceanError> {
+    Color::from_hex(&crab.color)
+        .or_else(|| Color::named(&crab.color))
+        .ok_or_else(|| OceanError::Script(tr(Message::UnknownColor, &[&crab.color])))
+}
+
+fn forecast(scenario: Scenario, weather: Weather) -> Scenario {
+    let description = format!("forecasting {:?} weather", weather);
+    scenario.then(&description, move |beach| {
+        beach.forecast_weather(weather);
+        Ok(())
+    })
+}
@@ -0,0 +1,184 @@
+use crate::event::{BeachEvent, EventListener};
+use crate::i18n::{tr, Message};
+use crate::simulation::{Operation, Simulation};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+pub type ClientId = usize;
+
+/** Forwards every beach event to the connected clients. */
+#[derive(Debug, Default)]
+struct Broadcast {
+    clients: Vec<(ClientId, Sender<BeachEvent>)>,
+}
+
+impl EventListener for Broadcast {
+    fn on_event(&mut self, event: &BeachEvent) {
+        self.clients
+            .retain(|(_, sender)| sender.send(event.clone()).is_ok());
+    }
+}
+
+/**
+ * A shared ocean beach on which each connected client controls one clan.
+ *
+ * Clients submit operations, which the server checks before applying so that
+ * a client can only act through its own clan's crabs. Every change to the
+ * beach is broadcast to all clients as a stream of events. The server is
+ * independent of any transport: `handle_line` accepts operations encoded as
+ * in replays, ready to be read off a socket.
+ */
+#[derive(Debug)]
+pub struct Server {
+    simulation: Simulation,
+    // Client id -> the clan it controls, or None once disconnected.
+    clients: Vec<Option<String>>,
+    broadcast: Rc<RefCell<Broadcast>>,
+}
+
+impl Server {
+    pub fn new(seed: u64) -> Server {
+        let broadcast = Rc::new(RefCell::new(Broadcast::default()));
+        let mut simulation = Simulation::new(seed);
+        simulation.beach_mut().subscribe(broadcast.clone());
+        Server {
+            simulation,
+            clients: Vec::new(),
+            broadcast,
+        }
+    }
+
+    pub fn simulation(&self) -> &Simulation {
+        &self.simulation
+    }
+
+    /**
+     * Connects a client that will control the given clan, returning its id
+     * and the stream of events it will receive. Fails if another connected
+     * client already controls the clan.
+     */
+    pub fn connect(&mut self, clan_id: &str) -> Result<(ClientId, Receiver<BeachEvent>), String> {
+        if self.clients.iter().flatten().any(|clan| clan == clan_id) {
+            return Err(tr(Message::ServerClanTaken, &[&clan_id]));
+        }
+        let id = self.clients.len();
+        let (sender, receiver) = mpsc::channel();
+        self.clients.push(Some(clan_id.to_string()));
+        self.broadcast.borrow_mut().clients.push((id, sender));
+        Ok((id, receiver))
+    }
+
+    /** Disconnects a client, freeing its clan for someone else. */
+    pub fn disconnect(&mut self, client: ClientId) {
+        if let Some(slot) = self.clients.get_mut(client) {
+            *slot = None;
+        }
+        self.broadcast
+            .borrow_mut()
+            .clients
+            .retain(|(id, _)| *id != client);
+    }
+
+    /** Returns the clan controlled by a connected client. */
+    pub fn clan_of(&self, client: ClientId) -> Option<&str> {
+        self.clients.get(client)?.as_deref()
+    }
+
+    /**
+     * Applies an operation on behalf of a client, if it is allowed to:
+     *   - New crabs, added or bred, join the client's clan.
+     *   - Only members of the client's clan can breed or start a battle.
+     *   - A client can only recruit crabs that belong to no clan.
+     *   - Populating the beach and ticking are reserved for the server.
+     */
+    pub fn submit(&mut self, client: ClientId, operation: Operation) -> Result<(), String> {
+        let clan_id = self
+            .clan_of(client)
+            .ok_or_else(|| tr(Message::ServerUnknownClient, &[&client]))?
+            .to_string();
+        let recruit = match &operation {
+            Operation::AddCrab { name, .. } | Operation::BreedCrabs { name, .. } => {
+                if !self.simulation.beach().find_crabs_by_name(name).is_empty() {
+                    return Err(tr(Message::ServerNameTaken, &[name]));
+                }
+                if let Operation::BreedCrabs { i, j, .. } = operation {
+                    self.check_member(&clan_id, i)?;
+                    self.check_member(&clan_id, j)?;
+                }
+                Some(name.clone())
+            }
+            Operation::JoinClan {
+                clan_id: target,
+                crab_name,
+            } => {
+                if *target != clan_id {
+                    return Err(tr(Message::ServerReserved, &[]));
+                }
+                let beach = self.simulation.beach();
+                if beach.find_crabs_by_name(crab_name).is_empty() {
+                    return Err(tr(Message::ServerBadCommand, &[crab_name]));
+                }
+                if beach.get_clan_of(crab_name).is_some() {
+                    return Err(tr(Message::ServerCrabTaken, &[crab_name]));
+                }
+                None
+            }
+            Operation::BattleCrabs { i, j } => {
+                self.check_member(&clan_id, *i)?;
+                self.check_index(*j)?;
+                if i == j {
+                    return Err(tr(Message::ServerBadCommand, &[&operation.encode()]));
+                }
+                None
+            }
+            Operation::PopulateRandom { .. } | Operation::Tick => {
+                return Err(tr(Message::ServerReserved, &[]))
+            }
+        };
+        self.simulation.apply(operation);
+        if let Some(crab_name) = recruit {
+            self.simulation
+                .apply(Operation::JoinClan { clan_id, crab_name });
+        }
+        Ok(())
+    }
+
+    /**
+     * Decodes a line received from a client and submits it.
+     */
+    pub fn handle_line(&mut self, client: ClientId, line: &str) -> Result<(), String> {
+        let operation =
+            Operation::decode(line.trim_end()).map_err(|e| tr(Message::ServerBadCommand, &[&e]))?;
+        self.submit(client, operation)
+    }
+
+    /**
+     * Applies an operation with the server's authority, without any checks.
+     */
+    pub fn apply(&mut self, operation: Operation) {
+        self.simulation.apply(operation);
+    }
+
+    fn check_index(&self, index: usize) -> Result<(), String> {
+        if index < self.simulation.beach().size() {
+            Ok(())
+        } else {
+            Err(tr(Message::ServerNoSuchCrab, &[&index]))
+        }
+    }
+
+    fn check_member(&self, clan_id: &str, index: usize) -> Result<(), String> {
+        self.check_index(index)?;
+        let beach = self.simulation.beach();
+        let name = beach.crab_at(index).name();
+        if beach
+            .get_clan_system()
+            .is_member(clan_id, beach.handle(index))
+        {
+            Ok(())
+        } else {
+            Err(tr(Message::NotClanMember, &[&name, &clan_id]))
+        }
+    }
+}
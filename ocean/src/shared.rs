@@ -0,0 +1,109 @@
+use crate::analysis::{describe, Summary, Trait};
+use crate::beach::Beach;
+use crate::life::TickReport;
+use crate::observe::{ClanSizes, Metric};
+use std::collections::BTreeMap;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, RwLock};
+use std::thread;
+
+/** Figures about a beach that any thread can read. */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BeachStats {
+    pub ticks: u64,
+    pub population: usize,
+    /** The crabs' speeds, or None if there are no crabs. */
+    pub speed: Option<Summary>,
+    /** The number of members of every clan, by clan id. */
+    pub clan_sizes: BTreeMap<String, usize>,
+}
+
+impl BeachStats {
+    pub fn of(beach: &Beach) -> BeachStats {
+        BeachStats {
+            ticks: beach.ticks(),
+            population: beach.size(),
+            speed: describe(beach, Trait::Speed),
+            clan_sizes: ClanSizes.measure(beach).unwrap_or_default(),
+        }
+    }
+}
+
+// A write, given the beach and what publishes its stats.
+type Job = Box<dyn FnOnce(&mut Beach, &dyn Fn(&Beach)) + Send>;
+
+/**
+ * A beach that several threads can use at once. Crabs share their reefs
+ * through `Rc`, so a beach cannot move between threads; instead the beach
+ * lives on a thread of its own, and every write runs there in turn, in the
+ * order it was sent. After every write the beach's `BeachStats` are
+ * published, so readers that only need those, such as a server answering
+ * queries, read them concurrently without waiting on the writes.
+ *
+ * Clones share the same beach. Its thread stops once every clone is
+ * dropped.
+ */
+#[derive(Debug, Clone)]
+pub struct SharedBeach {
+    jobs: Sender<Job>,
+    stats: Arc<RwLock<BeachStats>>,
+}
+
+impl SharedBeach {
+    /** Builds a beach with `make` on a new thread, and shares it once built. */
+    pub fn spawn(make: impl FnOnce() -> Beach + Send + 'static) -> SharedBeach {
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let stats = Arc::new(RwLock::new(BeachStats::default()));
+        let published = Arc::clone(&stats);
+        let publish = move |beach: &Beach| {
+            let stats = BeachStats::of(beach);
+            *published.write().unwrap_or_else(|e| e.into_inner()) = stats;
+        };
+        thread::spawn(move || {
+            let mut beach = make();
+            for job in queue {
+                job(&mut beach, &publish);
+            }
+        });
+        let shared = SharedBeach { jobs, stats };
+        // Wait for the beach to be built, so its stats are there to read.
+        shared.read(|_| ());
+        shared
+    }
+
+    /**
+     * Runs `f` on the beach after every write sent before it, and returns
+     * what it returned. The beach's stats are published before this
+     * returns.
+     */
+    pub fn write<T: Send + 'static>(&self, f: impl FnOnce(&mut Beach) -> T + Send + 'static) -> T {
+        let (reply, result) = mpsc::sync_channel(1);
+        let job: Job = Box::new(move |beach, publish| {
+            let value = f(beach);
+            publish(beach);
+            // The caller is waiting on the result, so it cannot be gone.
+            let _ = reply.send(value);
+        });
+        let stopped = "the shared beach's thread stopped when a write panicked";
+        self.jobs.send(job).expect(stopped);
+        result.recv().expect(stopped)
+    }
+
+    /**
+     * Runs `f` on the beach like `write`, but without changing it. Reads
+     * that need more than the stats wait their turn with the writes.
+     */
+    pub fn read<T: Send + 'static>(&self, f: impl FnOnce(&Beach) -> T + Send + 'static) -> T {
+        self.write(move |beach| f(beach))
+    }
+
+    /** Advances the beach clock by one tick; see `Beach::tick`. */
+    pub fn tick(&self) -> TickReport {
+        self.write(Beach::tick)
+    }
+
+    /** Returns the beach's stats as of the most recent write. */
+    pub fn stats(&self) -> BeachStats {
+        self.stats.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
@@ -0,0 +1,547 @@
+use crate::audit::{self, AuditMode, AuditReport};
+use crate::beach::Beach;
+use crate::color::Color;
+use crate::crab::Crab;
+use crate::diet::Diet;
+use crate::i18n::{tr, Message};
+use crate::report::{self, History, NotableEvent, ReportFormat, TickRecord};
+use crate::rules::{RuleSet, Verdict};
+use crate::scheduler::Scheduler;
+use crate::timeline::{BeachCapture, Timeline, WorldState};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/** A mutation of the simulated beach. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation {
+    AddCrab {
+        name: String,
+        speed: u32,
+        color: (u8, u8, u8),
+        diet: Diet,
+    },
+    BreedCrabs {
+        i: usize,
+        j: usize,
+        name: String,
+    },
+    JoinClan {
+        clan_id: String,
+        crab_name: String,
+    },
+    BattleCrabs {
+        i: usize,
+        j: usize,
+    },
+    PopulateRandom {
+        n: usize,
+        seed: u64,
+    },
+    Tick,
+}
+
+impl Operation {
+    /**
+     * Encodes the operation as one tab-separated line, as in replays.
+     */
+    pub fn encode(&self) -> String {
+        encode(self)
+    }
+
+    /**
+     * Decodes a line produced by `encode`.
+     */
+    pub fn decode(line: &str) -> io::Result<Operation> {
+        decode(line)
+    }
+}
+
+/**
+ * A beach driven through recorded operations.
+ *
+ * A simulation's beach draws its random numbers from a generator seeded
+ * with the simulation's seed, and every operation applied through it is
+ * recorded. Saving the recording and replaying it later reproduces the run
+ * exactly.
+ */
+#[derive(Debug)]
+pub struct Simulation {
+    seed: u64,
+    beach: Beach,
+    operations: Vec<Operation>,
+    timeline: Option<Timeline>,
+    history: History,
+    rules: RuleSet,
+    verdict: Option<Verdict>,
+    scheduler: Scheduler<Operation>,
+    audit_mode: Option<AuditMode>,
+    audit_reports: Vec<AuditReport>,
+}
+
+impl Simulation {
+    pub fn new(seed: u64) -> Simulation {
+        let mut beach = Beach::new();
+        beach.set_seed(seed);
+        Simulation {
+            seed,
+            beach,
+            operations: Vec::new(),
+            timeline: None,
+            history: History::default(),
+            rules: RuleSet::new(),
+            verdict: None,
+            scheduler: Scheduler::new(),
+            audit_mode: None,
+            audit_reports: Vec::new(),
+        }
+    }
+
+    /**
+     * Starts keeping a timeline of the beach from now on, with a snapshot
+     * every `interval` ticks, so that `at_tick` can inspect past states.
+     */
+    pub fn enable_timeline(&mut self, interval: u64) {
+        self.timeline = Some(Timeline::new(&self.beach, interval));
+    }
+
+    pub fn timeline(&self) -> Option<&Timeline> {
+        self.timeline.as_ref()
+    }
+
+    /**
+     * Returns a read-only view of the beach as of the end of the given tick,
+     * or None if no timeline covers that tick.
+     */
+    pub fn at_tick(&self, tick: u64) -> Option<WorldState> {
+        self.timeline.as_ref()?.at_tick(tick)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn beach(&self) -> &Beach {
+        &self.beach
+    }
+
+    /**
+     * Gives direct access to the beach, for setting up listeners. Changes made
+     * through it are not recorded.
+     */
+    pub fn beach_mut(&mut self) -> &mut Beach {
+        &mut self.beach
+    }
+
+    /** What happened so far: per-tick records and notable events. */
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /**
+     * Renders a summary of the run so far as Markdown or HTML.
+     */
+    pub fn report(&self, format: ReportFormat) -> String {
+        report::render(&self.beach, self.seed, &self.history, format)
+    }
+
+    /**
+     * Sets the rules that end this simulation. They are checked at the end of
+     * every tick until one of them holds.
+     */
+    pub fn set_rules(&mut self, rules: RuleSet) {
+        self.rules = rules;
+    }
+
+    /** The verdict of the rule that ended the simulation, if one has. */
+    pub fn verdict(&self) -> Option<&Verdict> {
+        self.verdict.as_ref()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.verdict.is_some()
+    }
+
+    /**
+     * Ticks until a rule ends the simulation or `max_ticks` ticks have passed,
+     * returning the verdict if there is one.
+     */
+    pub fn run(&mut self, max_ticks: u64) -> Option<&Verdict> {
+        for _ in 0..max_ticks {
+            if self.is_over() {
+                break;
+            }
+            self.apply(Operation::Tick);
+        }
+        self.verdict()
+    }
+
+    /**
+     * The operations scheduled for later ticks. After every tick, the
+     * operations that have come due are applied and recorded like any other.
+     */
+    pub fn scheduler(&self) -> &Scheduler<Operation> {
+        &self.scheduler
+    }
+
+    pub fn scheduler_mut(&mut self) -> &mut Scheduler<Operation> {
+        &mut self.scheduler
+    }
+
+    /**
+     * Starts auditing the beach's invariants after every operation or every
+     * tick, to catch corruption close to where it happens. Auditing scans the
+     * whole beach, so it is meant for debugging. Violations are kept as
+     * reports naming the operation that preceded them.
+     */
+    pub fn enable_audit(&mut self, mode: AuditMode) {
+        self.audit_mode = Some(mode);
+    }
+
+    pub fn disable_audit(&mut self) {
+        self.audit_mode = None;
+    }
+
+    pub fn audit_reports(&self) -> &[AuditReport] {
+        &self.audit_reports
+    }
+
+    fn run_audit(&mut self) {
+        let index = self.operations.len() - 1;
+        let operation = &self.operations[index];
+        let due = match self.audit_mode {
+            Some(AuditMode::EveryOperation) => true,
+            Some(AuditMode::EveryTick) => *operation == Operation::Tick,
+            None => false,
+        };
+        if !due {
+            return;
+        }
+        let violations = audit::audit(&self.beach);
+        if violations.is_empty() {
+            return;
+        }
+        let report = AuditReport {
+            tick: self.beach.ticks(),
+            index,
+            operation: operation.clone(),
+            violations,
+        };
+        self.audit_reports.push(report);
+    }
+
+    /** The operations applied so far, oldest first. */
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    /**
+     * Applies an operation to the beach and records it.
+     * Panics under the same conditions as the corresponding Beach method.
+     */
+    pub fn apply(&mut self, operation: Operation) {
+        let before = self
+            .timeline
+            .as_ref()
+            .map(|_| BeachCapture::of(&self.beach));
+        let largest = self.beach.get_clan_system().get_largest_clan_id();
+        let tick = self.beach.ticks();
+        if self.history.ticks.is_empty() {
+            self.history.ticks.push(TickRecord::capture(&self.beach));
+        }
+
+        match &operation {
+            Operation::AddCrab {
+                name,
+                speed,
+                color,
+                diet,
+            } => {
+                let color = Color::new(color.0, color.1, color.2);
+                self.beach
+                    .add_crab(Crab::new(name.clone(), *speed, color, *diet));
+            }
+            Operation::BreedCrabs { i, j, name } => self.beach.breed_crabs_at(*i, *j, name.clone()),
+            Operation::JoinClan { clan_id, crab_name } => {
+                if !self.beach.get_clan_system().has_clan(clan_id) {
+                    self.note(NotableEvent::ClanFounded {
+                        clan_id: clan_id.clone(),
+                        founder: crab_name.clone(),
+                    });
+                }
+                self.beach.add_member_to_clan(clan_id, crab_name)
+            }
+            Operation::BattleCrabs { i, j } => {
+                let battle = self.beach.battle_crabs(*i, *j);
+                self.note(NotableEvent::Battle {
+                    attacker: self.beach.crab_at(*i).name().to_string(),
+                    defender: self.beach.crab_at(*j).name().to_string(),
+                    resolution: battle.resolution,
+                });
+            }
+            Operation::PopulateRandom { n, seed } => self.beach.populate_random(*n, *seed),
+            Operation::Tick => {
+                self.beach.tick();
+            }
+        }
+
+        let clans = self.beach.get_clan_system();
+        match clans.get_largest_clan_id() {
+            Some(clan_id) if Some(&clan_id) != largest.as_ref() => {
+                let members = clans.get_clan_member_count(&clan_id);
+                self.history
+                    .events
+                    .push((tick, NotableEvent::LargestClanChanged { clan_id, members }));
+            }
+            _ => {}
+        }
+        if operation == Operation::Tick {
+            self.history.ticks.push(TickRecord::capture(&self.beach));
+        }
+        let ticked = operation == Operation::Tick;
+        self.record_deltas(before, ticked);
+        self.operations.push(operation);
+        self.run_audit();
+        if ticked {
+            for due in self.scheduler.drain(self.beach.ticks()) {
+                self.apply(due);
+            }
+            if self.verdict.is_none() {
+                self.verdict = self.rules.evaluate(&self.beach);
+            }
+        }
+    }
+
+    fn note(&mut self, event: NotableEvent) {
+        self.history.events.push((self.beach.ticks(), event));
+    }
+
+    /**
+     * Records the changes made by an operation in the timeline, if any,
+     * given the beach as it was before.
+     */
+    fn record_deltas(&mut self, before: Option<BeachCapture>, ticked: bool) {
+        if let (Some(timeline), Some(before)) = (self.timeline.as_mut(), before) {
+            timeline.record_change(&before, &self.beach, ticked);
+        }
+    }
+
+    /**
+     * Writes the seed and every recorded operation, one per line.
+     */
+    pub fn write_replay<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut writer = BufWriter::new(writer);
+        writeln!(writer, "seed\t{}", self.seed)?;
+        for operation in &self.operations {
+            writeln!(writer, "{}", encode(operation))?;
+        }
+        writer.flush()
+    }
+
+    pub fn save_replay<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_replay(File::create(path)?)
+    }
+
+    /**
+     * Saves the recording gzip-compressed. `replay` reads such files back
+     * like uncompressed ones.
+     */
+    #[cfg(feature = "gzip")]
+    pub fn save_replay_gzip<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut encoder =
+            flate2::write::GzEncoder::new(File::create(path)?, flate2::Compression::default());
+        self.write_replay(&mut encoder)?;
+        encoder.finish()?;
+        Ok(())
+    }
+
+    /**
+     * Re-runs a recording produced by `write_replay` from scratch. Fails,
+     * naming the line, if a line cannot be read, or an operation refers to
+     * a crab the beach does not have at that point.
+     */
+    pub fn replay_from<R: Read>(reader: R) -> io::Result<Simulation> {
+        let mut lines = BufReader::new(reader).lines();
+        let header = lines
+            .next()
+            .ok_or_else(|| invalid(tr(Message::ReplayEmpty, &[])))??;
+        let seed = match header.split('\t').collect::<Vec<_>>().as_slice() {
+            ["seed", seed] => parse(seed).map_err(|e| at_line(1, e))?,
+            _ => return Err(invalid(tr(Message::ReplayNoSeed, &[]))),
+        };
+
+        let mut simulation = Simulation::new(seed);
+        for (k, line) in lines.enumerate() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            let operation = decode(&line).map_err(|e| at_line(k + 2, e))?;
+            check_indices(&simulation.beach, &operation).map_err(|e| at_line(k + 2, e))?;
+            simulation.apply(operation);
+        }
+        Ok(simulation)
+    }
+
+    /**
+     * Re-runs the recording saved at the given path. With the `gzip` feature,
+     * gzip-compressed recordings are recognized and decompressed.
+     */
+    pub fn replay<P: AsRef<Path>>(path: P) -> io::Result<Simulation> {
+        let mut reader = BufReader::new(File::open(path)?);
+        #[cfg(feature = "gzip")]
+        if reader.fill_buf()?.starts_with(&[0x1f, 0x8b]) {
+            return Simulation::replay_from(flate2::bufread::GzDecoder::new(reader));
+        }
+        Simulation::replay_from(&mut reader)
+    }
+}
+
+fn encode(operation: &Operation) -> String {
+    match operation {
+        Operation::AddCrab {
+            name,
+            speed,
+            color,
+            diet,
+        } => format!(
+            "add\t{}\t{}\t{},{},{}\t{:?}",
+            escape(name),
+            speed,
+            color.0,
+            color.1,
+            color.2,
+            diet
+        ),
+        Operation::BreedCrabs { i, j, name } => {
+            format!("breed\t{}\t{}\t{}", i, j, escape(name))
+        }
+        Operation::JoinClan { clan_id, crab_name } => {
+            format!("join\t{}\t{}", escape(clan_id), escape(crab_name))
+        }
+        Operation::BattleCrabs { i, j } => format!("battle\t{}\t{}", i, j),
+        Operation::PopulateRandom { n, seed } => format!("populate\t{}\t{}", n, seed),
+        Operation::Tick => String::from("tick"),
+    }
+}
+
+fn decode(line: &str) -> io::Result<Operation> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    let operation = match fields.as_slice() {
+        ["add", name, speed, color, diet] => {
+            let rgb: Vec<&str> = color.split(',').collect();
+            if rgb.len() != 3 {
+                return Err(invalid(tr(Message::ReplayBadColor, &[color])));
+            }
+            Operation::AddCrab {
+                name: unescape(name)?,
+                speed: parse(speed)?,
+                color: (parse(rgb[0])?, parse(rgb[1])?, parse(rgb[2])?),
+                diet: parse_diet(diet)?,
+            }
+        }
+        ["breed", i, j, name] => Operation::BreedCrabs {
+            i: parse(i)?,
+            j: parse(j)?,
+            name: unescape(name)?,
+        },
+        ["join", clan_id, crab_name] => Operation::JoinClan {
+            clan_id: unescape(clan_id)?,
+            crab_name: unescape(crab_name)?,
+        },
+        ["battle", i, j] => Operation::BattleCrabs {
+            i: parse(i)?,
+            j: parse(j)?,
+        },
+        ["populate", n, seed] => Operation::PopulateRandom {
+            n: parse(n)?,
+            seed: parse(seed)?,
+        },
+        ["tick"] => Operation::Tick,
+        _ => return Err(invalid(tr(Message::ReplayUnknownOperation, &[&line]))),
+    };
+    Ok(operation)
+}
+
+/**
+ * Checks that the crabs an operation refers to by index are on the beach,
+ * and that a crab is not battling itself, so that replaying it cannot
+ * panic.
+ */
+fn check_indices(beach: &Beach, operation: &Operation) -> io::Result<()> {
+    let (i, j) = match *operation {
+        Operation::BreedCrabs { i, j, .. } => (i, j),
+        Operation::BattleCrabs { i, j } if i == j => {
+            return Err(invalid(tr(Message::BattleItself, &[&i])));
+        }
+        Operation::BattleCrabs { i, j } => (i, j),
+        _ => return Ok(()),
+    };
+    for index in [i, j] {
+        beach
+            .try_get_crab(index)
+            .map_err(|e| invalid(e.to_string()))?;
+    }
+    Ok(())
+}
+
+/**
+ * Escapes the backslashes, tabs and line breaks in a field, so that names
+ * holding them do not break up its line.
+ */
+fn escape(field: &str) -> String {
+    let mut out = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/** Reverses `escape`. */
+fn unescape(field: &str) -> io::Result<String> {
+    let mut out = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => out.push('\\'),
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            _ => return Err(invalid(tr(Message::ReplayBadEscape, &[&field]))),
+        }
+    }
+    Ok(out)
+}
+
+fn parse_diet(s: &str) -> io::Result<Diet> {
+    match s {
+        "Fish" => Ok(Diet::Fish),
+        "Shellfish" => Ok(Diet::Shellfish),
+        "Plants" => Ok(Diet::Plants),
+        _ => Err(invalid(tr(Message::CsvBadDiet, &[&s]))),
+    }
+}
+
+fn parse<T: std::str::FromStr>(s: &str) -> io::Result<T> {
+    s.parse()
+        .map_err(|_| invalid(tr(Message::CsvBadNumber, &[&s])))
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/** Names the line of the replay an error came from. */
+fn at_line(line: usize, error: io::Error) -> io::Error {
+    invalid(tr(Message::CsvLine, &[&line, &error]))
+}
@@ -0,0 +1,183 @@
+use crate::beach::Beach;
+use crate::breeding::BreedingRules;
+use crate::clans::Relation;
+use crate::color::Color;
+use crate::crab::Crab;
+use crate::diet::Diet;
+use crate::distribution::TraitDistributions;
+use crate::environment::Climate;
+use crate::i18n::{tr, Message};
+use crate::interference::Interference;
+use crate::ocean::Ocean;
+use crate::raid::RaidSettings;
+use std::collections::HashMap;
+
+/**
+ * A declarative description of a whole world: the reefs of the ocean and
+ * its beaches with their crabs and clans. `Ocean::from_spec` checks a spec
+ * and builds the world it describes.
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WorldSpec {
+    pub reefs: Vec<ReefSpec>,
+    pub beaches: Vec<BeachSpec>,
+}
+
+/** How many of each kind of prey a reef starts with. */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReefSpec {
+    pub minnows: u32,
+    pub shrimp: u32,
+    pub clams: u32,
+    pub algae: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BeachSpec {
+    /** Crabs described one by one, added first. */
+    pub crabs: Vec<CrabSpec>,
+    /** Random crabs added after the described ones, as (count, seed). */
+    pub random: Option<(usize, u64)>,
+    pub clans: Vec<ClanSpec>,
+    pub relations: Vec<(String, String, Relation)>,
+    pub breeding: BreedingRules,
+    pub raids: RaidSettings,
+    pub climate: Option<Climate>,
+    /** How the traits of the random crabs are distributed. */
+    pub distributions: TraitDistributions,
+    /** Human interference planned for the beach, as (tick, interference). */
+    pub interference: Vec<(u64, Interference)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrabSpec {
+    pub name: String,
+    pub speed: u32,
+    pub color: Color,
+    pub diet: Diet,
+    pub claw_size: u32,
+    /** Indices of the ocean reefs this crab has discovered. */
+    pub reefs: Vec<usize>,
+}
+
+impl CrabSpec {
+    /** Describes a crab with a claw size of 1 that knows no reefs. */
+    pub fn new(name: &str, speed: u32, color: Color, diet: Diet) -> CrabSpec {
+        CrabSpec {
+            name: name.to_string(),
+            speed,
+            color,
+            diet,
+            claw_size: 1,
+            reefs: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClanSpec {
+    pub id: String,
+    /** Names of crabs on the same beach, in joining order. */
+    pub members: Vec<String>,
+}
+
+/** Wraps a problem with where in the spec it was found. */
+fn at(location: String, problem: String) -> String {
+    tr(Message::SpecError, &[&location, &problem])
+}
+
+impl Ocean {
+    /**
+     * Builds the world a spec describes, after checking that:
+     *   - crabs and clans have names, and crab names are unique per beach;
+     *   - claws have a size of at least 1 and discovered reefs exist;
+     *   - clan members live on the clan's beach and belong to one clan only;
+     *   - relations are between two different clans of the beach.
+     *
+     * Errors say where in the spec the first problem is, such as
+     * `beaches[0].clans[1]`.
+     */
+    pub fn from_spec(spec: &WorldSpec) -> Result<Ocean, String> {
+        let mut ocean = Ocean::new();
+        let reefs: Vec<_> = spec
+            .reefs
+            .iter()
+            .map(|r| ocean.generate_reef(r.minnows, r.shrimp, r.clams, r.algae))
+            .collect();
+
+        for (b, beach_spec) in spec.beaches.iter().enumerate() {
+            let mut beach = Beach::new();
+            beach.set_breeding_rules(beach_spec.breeding);
+            beach.set_raid_settings(beach_spec.raids);
+            if let Some(climate) = beach_spec.climate {
+                beach.set_climate(climate);
+            }
+            for &(tick, interference) in &beach_spec.interference {
+                beach.schedule_interference(tick, interference);
+            }
+            for (c, crab_spec) in beach_spec.crabs.iter().enumerate() {
+                let location = format!("beaches[{}].crabs[{}]", b, c);
+                let mut crab = Crab::new(
+                    crab_spec.name.clone(),
+                    crab_spec.speed,
+                    crab_spec.color.clone(),
+                    crab_spec.diet,
+                );
+                crab.set_claw_size(crab_spec.claw_size);
+                let problem = if crab_spec.name.is_empty() {
+                    Some(tr(Message::SpecEmptyName, &[]))
+                } else if !beach.find_crabs_by_name(&crab_spec.name).is_empty() {
+                    Some(tr(Message::SpecDuplicateCrab, &[&crab_spec.name]))
+                } else if crab_spec.claw_size == 0 {
+                    Some(tr(Message::SpecZeroClaw, &[]))
+                } else if let Err(error) = beach.constraints().check(&crab) {
+                    Some(error.to_string())
+                } else {
+                    crab_spec
+                        .reefs
+                        .iter()
+                        .find(|&&r| r >= reefs.len())
+                        .map(|r| tr(Message::SpecNoSuchReef, &[r]))
+                };
+                if let Some(problem) = problem {
+                    return Err(at(location, problem));
+                }
+
+                for &r in &crab_spec.reefs {
+                    crab.discover_reef(reefs[r].clone());
+                }
+                beach.add_crab(crab);
+            }
+            if let Some((count, seed)) = beach_spec.random {
+                beach
+                    .populate_with(count, seed, &beach_spec.distributions)
+                    .map_err(|e| at(format!("beaches[{}].distributions", b), e))?;
+            }
+
+            let mut clan_of: HashMap<&str, &str> = HashMap::new();
+            for (c, clan) in beach_spec.clans.iter().enumerate() {
+                let location = format!("beaches[{}].clans[{}]", b, c);
+                if clan.id.is_empty() {
+                    return Err(at(location, tr(Message::SpecEmptyName, &[])));
+                }
+                for member in &clan.members {
+                    if beach.find_crabs_by_name(member).is_empty() {
+                        return Err(at(location, tr(Message::SpecUnknownMember, &[member])));
+                    }
+                    if let Some(other) = clan_of.insert(member, &clan.id) {
+                        return Err(at(location, tr(Message::SpecTwoClans, &[member, &other])));
+                    }
+                    beach.add_member_to_clan(&clan.id, member);
+                }
+            }
+
+            for (r, (id1, id2, relation)) in beach_spec.relations.iter().enumerate() {
+                beach
+                    .set_clan_relation(id1, id2, *relation)
+                    .map_err(|problem| at(format!("beaches[{}].relations[{}]", b, r), problem))?;
+            }
+            ocean.add_beach(beach);
+        }
+        Ok(ocean)
+    }
+}
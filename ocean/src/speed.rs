@@ -0,0 +1,130 @@
+use std::fmt;
+use std::iter::Sum;
+
+/**
+ * How fast a crab runs, in whatever units the beach measures it in. Speeds
+ * only grow and shrink through checked or saturating arithmetic, so a very
+ * fast crab cannot wrap around to a slow one.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Speed(u32);
+
+impl Speed {
+    pub const ZERO: Speed = Speed(0);
+    pub const MAX: Speed = Speed(u32::MAX);
+
+    pub const fn new(speed: u32) -> Speed {
+        Speed(speed)
+    }
+
+    pub const fn get(self) -> u32 {
+        self.0
+    }
+
+    /** Returns the sum of the two speeds, or None if it would overflow. */
+    pub fn checked_add(self, other: Speed) -> Option<Speed> {
+        self.0.checked_add(other.0).map(Speed)
+    }
+
+    /** Returns the difference of the two speeds, or None if it is negative. */
+    pub fn checked_sub(self, other: Speed) -> Option<Speed> {
+        self.0.checked_sub(other.0).map(Speed)
+    }
+
+    /** Returns the sum of the two speeds, or `Speed::MAX` if it would overflow. */
+    pub fn saturating_add(self, other: Speed) -> Speed {
+        Speed(self.0.saturating_add(other.0))
+    }
+
+    /** Returns the difference of the two speeds, or zero if it is negative. */
+    pub fn saturating_sub(self, other: Speed) -> Speed {
+        Speed(self.0.saturating_sub(other.0))
+    }
+
+    /** Returns the mean of the speeds, or None if there are none. */
+    pub fn mean<I: IntoIterator<Item = Speed>>(speeds: I) -> Option<f64> {
+        speeds.into_iter().sum::<SpeedTotal>().mean()
+    }
+}
+
+impl From<u32> for Speed {
+    fn from(speed: u32) -> Speed {
+        Speed(speed)
+    }
+}
+
+impl From<Speed> for u32 {
+    fn from(speed: Speed) -> u32 {
+        speed.0
+    }
+}
+
+impl From<Speed> for u64 {
+    fn from(speed: Speed) -> u64 {
+        speed.0 as u64
+    }
+}
+
+impl From<Speed> for f64 {
+    fn from(speed: Speed) -> f64 {
+        speed.0 as f64
+    }
+}
+
+impl fmt::Display for Speed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/**
+ * A running total of speeds and how many there were, kept wide enough
+ * that no number of crabs at any speed overflows it.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SpeedTotal {
+    total: u128,
+    count: u64,
+}
+
+impl SpeedTotal {
+    pub fn new() -> SpeedTotal {
+        SpeedTotal::default()
+    }
+
+    pub fn add(&mut self, speed: Speed) {
+        self.total += speed.0 as u128;
+        self.count += 1;
+    }
+
+    pub fn total(&self) -> u128 {
+        self.total
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /** Returns the mean of the speeds added, or None if there were none. */
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.total as f64 / self.count as f64)
+    }
+}
+
+impl Extend<Speed> for SpeedTotal {
+    fn extend<I: IntoIterator<Item = Speed>>(&mut self, speeds: I) {
+        for speed in speeds {
+            self.add(speed);
+        }
+    }
+}
+
+impl Sum<Speed> for SpeedTotal {
+    fn sum<I: Iterator<Item = Speed>>(speeds: I) -> SpeedTotal {
+        let mut total = SpeedTotal::new();
+        total.extend(speeds);
+        total
+    }
+}
@@ -0,0 +1,435 @@
+use crate::beach::CrabId;
+use crate::crab::Crab;
+use std::collections::HashMap;
+use std::ops::{Index, IndexMut};
+
+/** A crab living on a beach, along with what the beach keeps about it. */
+#[derive(Debug)]
+struct Resident {
+    handle: CrabId,
+    crab: Crab,
+    // The tick at which the crab arrived.
+    arrived: u64,
+    // The tick at which the crab last ate.
+    fed_at: u64,
+}
+
+impl Resident {
+    fn duplicate(&self) -> Resident {
+        Resident {
+            handle: self.handle,
+            crab: self.crab.duplicate(),
+            arrived: self.arrived,
+            fed_at: self.fed_at,
+        }
+    }
+}
+
+/**
+ * Counts the occupied slots of a store, so that the slot of the crab at an
+ * index, and the index of the crab in a slot, take logarithmic time. It is
+ * a Fenwick tree: its `k`th entry counts the occupied slots among the
+ * `k & k.wrapping_neg()` slots before slot `k`.
+ */
+#[derive(Debug, Clone, Default)]
+struct Occupancy {
+    tree: Vec<usize>,
+}
+
+impl Occupancy {
+    /** Adds a slot after the others, occupied. */
+    fn push(&mut self) {
+        let k = self.tree.len() + 1;
+        let below = k - (k & k.wrapping_neg());
+        let count = 1 + self.before(k - 1) - self.before(below);
+        self.tree.push(count);
+    }
+
+    /** Drops the last slot, which must be empty. */
+    fn pop(&mut self) {
+        self.tree.pop();
+    }
+
+    /** Counts the given slot as empty. */
+    fn vacate(&mut self, slot: usize) {
+        let mut k = slot + 1;
+        while k <= self.tree.len() {
+            self.tree[k - 1] -= 1;
+            k += k & k.wrapping_neg();
+        }
+    }
+
+    /** Returns how many of the slots before the given one are occupied. */
+    fn before(&self, slot: usize) -> usize {
+        let (mut k, mut count) = (slot, 0);
+        while k > 0 {
+            count += self.tree[k - 1];
+            k -= k & k.wrapping_neg();
+        }
+        count
+    }
+
+    /** Returns the occupied slot with the given number of occupied slots before it. */
+    fn nth(&self, index: usize) -> Option<usize> {
+        let (mut slot, mut remaining) = (0, index);
+        let mut step = self.tree.len().checked_next_power_of_two()?;
+        while step > 0 {
+            if slot + step <= self.tree.len() && self.tree[slot + step - 1] <= remaining {
+                slot += step;
+                remaining -= self.tree[slot - 1];
+            }
+            step /= 2;
+        }
+        (slot < self.tree.len()).then_some(slot)
+    }
+
+    fn clear(&mut self) {
+        self.tree.clear();
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.tree.reserve(additional);
+    }
+
+    fn memory_bytes(&self) -> usize {
+        self.tree.capacity() * std::mem::size_of::<usize>()
+    }
+}
+
+/**
+ * The crabs of a beach, in an arena keyed by their handles. A crab keeps
+ * its slot for as long as it lives on the beach, so finding it by handle
+ * takes constant time, and removing it moves no other crab: its slot is
+ * just left empty until there are more empty slots than crabs, when the
+ * arena is compacted. Slots are filled in the order the crabs arrive, so
+ * the occupied slots, in order, are the crabs by index; going between
+ * indices and slots takes logarithmic time.
+ */
+#[derive(Debug, Default)]
+pub(crate) struct CrabStore {
+    slots: Vec<Option<Resident>>,
+    occupancy: Occupancy,
+    len: usize,
+    by_handle: HashMap<CrabId, usize>,
+}
+
+impl CrabStore {
+    pub(crate) fn new() -> CrabStore {
+        CrabStore::default()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        self.slots.reserve(additional);
+        self.occupancy.reserve(additional);
+        self.by_handle.reserve(additional);
+    }
+
+    /**
+     * Adds a crab that arrived at the given tick, and ate then, after every
+     * crab already here. Its handle must be newer than theirs.
+     */
+    pub(crate) fn push(&mut self, handle: CrabId, crab: Crab, arrived: u64) {
+        debug_assert!(self
+            .slots
+            .last()
+            .is_none_or(|last| last.as_ref().unwrap().handle < handle));
+        let slot = self.slots.len();
+        self.slots.push(Some(Resident {
+            handle,
+            crab,
+            arrived,
+            fed_at: arrived,
+        }));
+        self.occupancy.push();
+        self.len += 1;
+        self.by_handle.insert(handle, slot);
+    }
+
+    /**
+     * Takes out the crab at the given index, returning its handle and the
+     * crab. If the index is out of bounds, the method should panic.
+     */
+    pub(crate) fn remove(&mut self, index: usize) -> (CrabId, Crab) {
+        let slot = self.slot(index);
+        let resident = self.slots[slot].take().expect("ordered slots are occupied");
+        self.occupancy.vacate(slot);
+        self.len -= 1;
+        self.by_handle.remove(&resident.handle);
+        // The last slot is always occupied, so crabs popped off the end
+        // leave no empty slots behind.
+        while let Some(None) = self.slots.last() {
+            self.slots.pop();
+            self.occupancy.pop();
+        }
+        if self.slots.len() > 2 * self.len {
+            self.compact();
+        }
+        (resident.handle, resident.crab)
+    }
+
+    /** Drops the empty slots, moving the crabs to the front in order. */
+    fn compact(&mut self) {
+        self.slots.retain(Option::is_some);
+        self.occupancy.clear();
+        for _ in &self.slots {
+            self.occupancy.push();
+        }
+        self.by_handle = self
+            .slots
+            .iter()
+            .enumerate()
+            .map(|(slot, resident)| (resident.as_ref().unwrap().handle, slot))
+            .collect();
+    }
+
+    /**
+     * Returns the slot of the crab at the given index. If the index is out
+     * of bounds, the method should panic.
+     */
+    fn slot(&self, index: usize) -> usize {
+        self.occupancy.nth(index).unwrap_or_else(|| {
+            panic!(
+                "index out of bounds: the len is {} but the index is {}",
+                self.len, index
+            )
+        })
+    }
+
+    fn resident(&self, slot: usize) -> &Resident {
+        self.slots[slot]
+            .as_ref()
+            .expect("ordered slots are occupied")
+    }
+
+    fn resident_mut(&mut self, slot: usize) -> &mut Resident {
+        self.slots[slot]
+            .as_mut()
+            .expect("ordered slots are occupied")
+    }
+
+    pub(crate) fn handle(&self, index: usize) -> CrabId {
+        self.resident(self.slot(index)).handle
+    }
+
+    /** Returns the handles of the crabs, in order. */
+    pub(crate) fn handles(&self) -> Vec<CrabId> {
+        self.iter_residents()
+            .map(|resident| resident.handle)
+            .collect()
+    }
+
+    /** Returns the index of the crab with the given handle, if it is here. */
+    pub(crate) fn index_of(&self, handle: CrabId) -> Option<usize> {
+        let &slot = self.by_handle.get(&handle)?;
+        Some(self.occupancy.before(slot))
+    }
+
+    /** Returns the crab with the given handle, if it is here. */
+    pub(crate) fn get(&self, handle: CrabId) -> Option<&Crab> {
+        let &slot = self.by_handle.get(&handle)?;
+        Some(&self.resident(slot).crab)
+    }
+
+    pub(crate) fn get_mut(&mut self, handle: CrabId) -> Option<&mut Crab> {
+        let &slot = self.by_handle.get(&handle)?;
+        Some(&mut self.resident_mut(slot).crab)
+    }
+
+    /**
+     * Returns the crabs at two different indices. If either index is out of
+     * bounds, or they are the same, the method should panic.
+     */
+    pub(crate) fn pair_mut(&mut self, i: usize, j: usize) -> (&mut Crab, &mut Crab) {
+        assert_ne!(i, j, "a crab cannot be paired with itself");
+        let (a, b) = (self.slot(i), self.slot(j));
+        let (first, second) = if a < b {
+            let (left, right) = self.slots.split_at_mut(b);
+            (&mut left[a], &mut right[0])
+        } else {
+            let (left, right) = self.slots.split_at_mut(a);
+            (&mut right[0], &mut left[b])
+        };
+        let occupied = "ordered slots are occupied";
+        (
+            &mut first.as_mut().expect(occupied).crab,
+            &mut second.as_mut().expect(occupied).crab,
+        )
+    }
+
+    pub(crate) fn arrived(&self, index: usize) -> u64 {
+        self.resident(self.slot(index)).arrived
+    }
+
+    #[cfg(any(feature = "serde", feature = "binary"))]
+    pub(crate) fn set_arrived(&mut self, index: usize, tick: u64) {
+        let slot = self.slot(index);
+        self.resident_mut(slot).arrived = tick;
+    }
+
+    pub(crate) fn fed_at(&self, index: usize) -> u64 {
+        self.resident(self.slot(index)).fed_at
+    }
+
+    pub(crate) fn set_fed_at(&mut self, index: usize, tick: u64) {
+        let slot = self.slot(index);
+        self.resident_mut(slot).fed_at = tick;
+    }
+
+    /** Counts every crab as having last eaten at the given tick. */
+    pub(crate) fn fill_fed_at(&mut self, tick: u64) {
+        for resident in self.slots.iter_mut().flatten() {
+            resident.fed_at = tick;
+        }
+    }
+
+    fn iter_residents(&self) -> impl Iterator<Item = &Resident> {
+        self.slots.iter().flatten()
+    }
+
+    pub(crate) fn iter(&self) -> Crabs<'_> {
+        Crabs {
+            slots: self.slots.iter(),
+            remaining: self.len(),
+        }
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> CrabsMut<'_> {
+        let remaining = self.len();
+        CrabsMut {
+            slots: self.slots.iter_mut(),
+            remaining,
+        }
+    }
+
+    /** Takes the crabs out of the store, in order. */
+    pub(crate) fn into_crabs(self) -> impl Iterator<Item = Crab> {
+        self.slots
+            .into_iter()
+            .flatten()
+            .map(|resident| resident.crab)
+    }
+
+    /** Returns a copy of the store, sharing the crabs' reefs; see `Crab::duplicate`. */
+    pub(crate) fn duplicate(&self) -> CrabStore {
+        CrabStore {
+            slots: self
+                .slots
+                .iter()
+                .map(|slot| slot.as_ref().map(Resident::duplicate))
+                .collect(),
+            occupancy: self.occupancy.clone(),
+            len: self.len,
+            by_handle: self.by_handle.clone(),
+        }
+    }
+
+    /** Estimates the bytes allocated for the slots and the indices into them. */
+    pub(crate) fn memory_bytes(&self) -> usize {
+        self.slots.capacity() * std::mem::size_of::<Option<Resident>>()
+            + self.occupancy.memory_bytes()
+            + self.by_handle.capacity() * std::mem::size_of::<(CrabId, usize)>()
+    }
+}
+
+impl Index<usize> for CrabStore {
+    type Output = Crab;
+
+    fn index(&self, index: usize) -> &Crab {
+        &self.resident(self.slot(index)).crab
+    }
+}
+
+impl IndexMut<usize> for CrabStore {
+    fn index_mut(&mut self, index: usize) -> &mut Crab {
+        let slot = self.slot(index);
+        &mut self.resident_mut(slot).crab
+    }
+}
+
+impl<'a> IntoIterator for &'a CrabStore {
+    type Item = &'a Crab;
+    type IntoIter = Crabs<'a>;
+
+    fn into_iter(self) -> Crabs<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut CrabStore {
+    type Item = &'a mut Crab;
+    type IntoIter = CrabsMut<'a>;
+
+    fn into_iter(self) -> CrabsMut<'a> {
+        self.iter_mut()
+    }
+}
+
+/** The crabs of a beach, in order; see `Beach::crabs`. */
+#[derive(Debug, Clone)]
+pub struct Crabs<'a> {
+    slots: std::slice::Iter<'a, Option<Resident>>,
+    remaining: usize,
+}
+
+impl<'a> Iterator for Crabs<'a> {
+    type Item = &'a Crab;
+
+    fn next(&mut self) -> Option<&'a Crab> {
+        let resident = self.slots.by_ref().flatten().next()?;
+        self.remaining -= 1;
+        Some(&resident.crab)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl DoubleEndedIterator for Crabs<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let resident = self.slots.by_ref().flatten().next_back()?;
+        self.remaining -= 1;
+        Some(&resident.crab)
+    }
+}
+
+impl ExactSizeIterator for Crabs<'_> {}
+
+/** The crabs of a beach, in order, to change in place; see `Beach::crabs_mut`. */
+#[derive(Debug)]
+pub struct CrabsMut<'a> {
+    slots: std::slice::IterMut<'a, Option<Resident>>,
+    remaining: usize,
+}
+
+impl<'a> Iterator for CrabsMut<'a> {
+    type Item = &'a mut Crab;
+
+    fn next(&mut self) -> Option<&'a mut Crab> {
+        let resident = self.slots.by_ref().flatten().next()?;
+        self.remaining -= 1;
+        Some(&mut resident.crab)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl DoubleEndedIterator for CrabsMut<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let resident = self.slots.by_ref().flatten().next_back()?;
+        self.remaining -= 1;
+        Some(&mut resident.crab)
+    }
+}
+
+impl ExactSizeIterator for CrabsMut<'_> {}
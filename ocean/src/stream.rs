@@ -0,0 +1,179 @@
+use crate::beach::Beach;
+use crate::event::{BeachEvent, EventListener};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll, Waker};
+
+/**
+ * How many events an event stream holds on to before it starts dropping the
+ * oldest, unless set otherwise.
+ */
+pub const DEFAULT_STREAM_CAPACITY: usize = 1024;
+
+#[derive(Debug)]
+struct Queue {
+    events: VecDeque<BeachEvent>,
+    capacity: usize,
+    missed: u64,
+    waker: Option<Waker>,
+    // Set once the beach is gone, so no more events will come.
+    closed: bool,
+}
+
+/**
+ * The events of a beach as an asynchronous stream, for consumers such as a
+ * dashboard that run elsewhere, e.g. on another thread. Only arrivals,
+ * births, clan memberships, removals, deaths, seagull raids and fishing
+ * are streamed; a crab that dies is streamed both as removed and as dead.
+ * A stream that falls
+ * behind by more than its capacity drops the oldest events it holds, and
+ * counts them as missed. The stream ends once the beach is dropped and
+ * every event it held has been taken.
+ */
+#[derive(Debug)]
+pub struct EventStream {
+    queue: Arc<Mutex<Queue>>,
+}
+
+impl EventStream {
+    /**
+     * Returns the next event if there is one, Ready(None) if the stream has
+     * ended, and Pending otherwise, in which case the task is woken once
+     * there is something new. This is `Stream::poll_next` from the futures
+     * crate.
+     */
+    pub fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<BeachEvent>> {
+        let mut queue = self.queue.lock().unwrap();
+        match queue.events.pop_front() {
+            Some(event) => Poll::Ready(Some(event)),
+            None if queue.closed => Poll::Ready(None),
+            None => {
+                queue.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    /** Waits for the next event, or None once the stream has ended. */
+    pub fn recv(&mut self) -> Recv<'_> {
+        Recv { stream: self }
+    }
+
+    /** Takes the next event if there is one, without waiting. */
+    pub fn try_next(&mut self) -> Option<BeachEvent> {
+        self.queue.lock().unwrap().events.pop_front()
+    }
+
+    /** Returns how many events were dropped because the stream fell behind. */
+    pub fn missed(&self) -> u64 {
+        self.queue.lock().unwrap().missed
+    }
+
+    /** Returns whether the beach is gone, so no more events will come. */
+    pub fn is_closed(&self) -> bool {
+        self.queue.lock().unwrap().closed
+    }
+}
+
+/** The future returned by `EventStream::recv`. */
+#[derive(Debug)]
+pub struct Recv<'a> {
+    stream: &'a mut EventStream,
+}
+
+impl Future for Recv<'_> {
+    type Output = Option<BeachEvent>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<BeachEvent>> {
+        Pin::new(&mut *self.stream).poll_next(cx)
+    }
+}
+
+/**
+ * Feeds the streamed events into a stream's queue, as long as the stream is
+ * still around.
+ */
+struct StreamListener {
+    queue: Weak<Mutex<Queue>>,
+}
+
+impl StreamListener {
+    fn streams(event: &BeachEvent) -> bool {
+        matches!(
+            event,
+            BeachEvent::CrabAdded { .. }
+                | BeachEvent::CrabBred { .. }
+                | BeachEvent::ClanMemberAdded { .. }
+                | BeachEvent::CrabRemoved { .. }
+                | BeachEvent::CrabDied { .. }
+                | BeachEvent::SeagullRaid { .. }
+                | BeachEvent::CrabsFished { .. }
+        )
+    }
+}
+
+impl EventListener for StreamListener {
+    fn on_event(&mut self, event: &BeachEvent) {
+        let Some(queue) = self.queue.upgrade() else {
+            return;
+        };
+        if !StreamListener::streams(event) {
+            return;
+        }
+        let mut queue = queue.lock().unwrap();
+        if queue.events.len() >= queue.capacity.max(1) {
+            queue.events.pop_front();
+            queue.missed += 1;
+        }
+        queue.events.push_back(event.clone());
+        if let Some(waker) = queue.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.queue.strong_count() == 0
+    }
+}
+
+impl Drop for StreamListener {
+    fn drop(&mut self) {
+        if let Some(queue) = self.queue.upgrade() {
+            let mut queue = queue.lock().unwrap();
+            queue.closed = true;
+            if let Some(waker) = queue.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl Beach {
+    /**
+     * Returns a stream of the crabs arriving, being bred, joining clans,
+     * leaving and dying on this beach from now on, holding up to
+     * `DEFAULT_STREAM_CAPACITY` events.
+     */
+    pub fn event_stream(&mut self) -> EventStream {
+        self.event_stream_with_capacity(DEFAULT_STREAM_CAPACITY)
+    }
+
+    /** Like `event_stream`, but holding up to `capacity` events. */
+    pub fn event_stream_with_capacity(&mut self, capacity: usize) -> EventStream {
+        let queue = Arc::new(Mutex::new(Queue {
+            events: VecDeque::new(),
+            capacity,
+            missed: 0,
+            waker: None,
+            closed: false,
+        }));
+        self.subscribe(Rc::new(RefCell::new(StreamListener {
+            queue: Arc::downgrade(&queue),
+        })));
+        EventStream { queue }
+    }
+}
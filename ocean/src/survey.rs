@@ -0,0 +1,113 @@
+use crate::beach::CrabId;
+use rand::seq::index;
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+use std::collections::HashSet;
+
+/** The outcome of resampling a beach in a mark–recapture survey. */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecaptureReport {
+    /** The number of crabs tagged so far, whether or not still on the beach. */
+    pub marked: usize,
+    /** The number of crabs caught in this sample. */
+    pub caught: usize,
+    /** How many of the caught crabs were already tagged. */
+    pub recaptured: usize,
+    /** The Lincoln–Petersen estimate of the population, if any were recaptured. */
+    pub estimate: Option<f64>,
+    /** The true population when the sample was taken. */
+    pub actual: usize,
+}
+
+impl RecaptureReport {
+    /**
+     * Returns how far the estimate is off from the true population, as a
+     * share of it (e.g. 0.1 for 10% too high), if there is an estimate.
+     */
+    pub fn relative_error(&self) -> Option<f64> {
+        let estimate = self.estimate?;
+        (self.actual > 0).then(|| (estimate - self.actual as f64) / self.actual as f64)
+    }
+}
+
+/**
+ * A mark–recapture survey of a beach: tag a random sample of crabs, let
+ * time pass, then catch another sample and count the tagged crabs in it.
+ * If `M` crabs were tagged and a later sample of `C` crabs holds `R` tagged
+ * ones, the Lincoln–Petersen estimate of the population is `M * C / R`.
+ *
+ * Samples are drawn without the beach knowing which crabs are tagged, so
+ * the estimate can be checked against the true population.
+ */
+#[derive(Debug, Default)]
+pub struct Survey {
+    tagged: HashSet<CrabId>,
+    reports: Vec<RecaptureReport>,
+}
+
+impl Survey {
+    pub fn new() -> Survey {
+        Survey {
+            tagged: HashSet::new(),
+            reports: Vec::new(),
+        }
+    }
+
+    /** Returns whether a crab carries a tag. */
+    pub fn is_tagged(&self, crab: CrabId) -> bool {
+        self.tagged.contains(&crab)
+    }
+
+    /** Returns the number of crabs tagged so far. */
+    pub fn marked(&self) -> usize {
+        self.tagged.len()
+    }
+
+    /** Returns the report of every recapture so far, oldest first. */
+    pub fn reports(&self) -> &[RecaptureReport] {
+        &self.reports
+    }
+
+    /** Removes every tag and forgets every recapture, to start over. */
+    pub fn reset(&mut self) {
+        self.tagged.clear();
+        self.reports.clear();
+    }
+
+    /**
+     * Catches up to `count` crabs at random from the given population and
+     * tags them. Returns how many of them were newly tagged.
+     */
+    pub fn tag_sample(&mut self, population: &[CrabId], count: usize, seed: u64) -> usize {
+        sample(population, count, seed)
+            .filter(|&crab| self.tagged.insert(crab))
+            .count()
+    }
+
+    /**
+     * Catches up to `count` crabs at random from the given population,
+     * counts the tagged ones among them and releases them all.
+     */
+    pub fn recapture(&mut self, population: &[CrabId], count: usize, seed: u64) -> RecaptureReport {
+        let caught: Vec<CrabId> = sample(population, count, seed).collect();
+        let recaptured = caught.iter().filter(|c| self.tagged.contains(c)).count();
+        let marked = self.tagged.len();
+        let report = RecaptureReport {
+            marked,
+            caught: caught.len(),
+            recaptured,
+            estimate: (recaptured > 0)
+                .then(|| marked as f64 * caught.len() as f64 / recaptured as f64),
+            actual: population.len(),
+        };
+        self.reports.push(report);
+        report
+    }
+}
+
+fn sample(population: &[CrabId], count: usize, seed: u64) -> impl Iterator<Item = CrabId> + '_ {
+    let mut rng = Pcg64::seed_from_u64(seed);
+    index::sample(&mut rng, population.len(), count.min(population.len()))
+        .into_iter()
+        .map(move |k| population[k])
+}
@@ -0,0 +1,153 @@
+use crate::beach::Beach;
+use crate::crab::Crab;
+use crate::i18n::{tr, Message};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/** Side of the square cell each crab is drawn in. */
+const CELL: usize = 40;
+const MARGIN: usize = 20;
+const LEGEND_ROW: usize = 20;
+
+/** Fill colors for clan territories, reused in order when there are many clans. */
+const TERRITORY_COLORS: &[&str] = &[
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#ff9da7",
+];
+
+/**
+ * Renders the beach as a standalone SVG document.
+ *
+ * Each clan's members are drawn together inside the clan's territory, a
+ * tinted rectangle, followed by the crabs without a clan. Every crab is a
+ * circle in its own color, labelled with its name. A legend below the beach
+ * names each territory and how many members it has.
+ */
+pub fn render_svg(beach: &Beach) -> String {
+    let groups = group_by_territory(beach);
+
+    // Lay the territories out left to right, each as a square-ish grid.
+    let mut regions: Vec<(usize, usize, usize)> = Vec::new(); // (x, columns, rows)
+    let mut x = MARGIN;
+    let mut height = 0;
+    for (_, crabs) in &groups {
+        let columns = (crabs.len() as f64).sqrt().ceil().max(1.0) as usize;
+        let rows = crabs.len().div_ceil(columns).max(1);
+        regions.push((x, columns, rows));
+        x += columns * CELL + MARGIN;
+        height = height.max(rows * CELL);
+    }
+    let width = x.max(2 * MARGIN + CELL);
+    let legend_y = MARGIN + height + MARGIN;
+    let total_height = legend_y + groups.len() * LEGEND_ROW + MARGIN;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+        width, total_height, width, total_height
+    );
+    let _ = writeln!(
+        svg,
+        r##"<rect width="100%" height="100%" fill="#f4e4bc"/>"##
+    );
+
+    for (k, ((clan, crabs), &(x, columns, rows))) in groups.iter().zip(&regions).enumerate() {
+        if clan.is_some() {
+            let _ = writeln!(
+                svg,
+                r#"<rect class="territory" x="{}" y="{}" width="{}" height="{}" rx="8" fill="{}" fill-opacity="0.3"/>"#,
+                x,
+                MARGIN,
+                columns * CELL,
+                rows * CELL,
+                TERRITORY_COLORS[k % TERRITORY_COLORS.len()]
+            );
+        }
+        for (n, crab) in crabs.iter().enumerate() {
+            let cx = x + (n % columns) * CELL + CELL / 2;
+            let cy = MARGIN + (n / columns) * CELL + CELL / 2;
+            render_crab(&mut svg, crab, cx, cy);
+        }
+    }
+
+    for (k, (clan, crabs)) in groups.iter().enumerate() {
+        let y = legend_y + k * LEGEND_ROW;
+        let (fill, label) = match clan {
+            Some(id) => (TERRITORY_COLORS[k % TERRITORY_COLORS.len()], escape(id)),
+            None => ("none", escape(&tr(Message::SvgNoClan, &[]))),
+        };
+        let _ = writeln!(
+            svg,
+            r##"<rect x="{}" y="{}" width="12" height="12" fill="{}" stroke="#333"/>"##,
+            MARGIN, y, fill
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text class="legend" x="{}" y="{}" font-size="12">{} ({})</text>"#,
+            MARGIN + 18,
+            y + 11,
+            label,
+            crabs.len()
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn render_crab(svg: &mut String, crab: &Crab, cx: usize, cy: usize) {
+    let color = crab.color();
+    let name = escape(crab.name());
+    let _ = writeln!(
+        svg,
+        r##"<circle class="crab" cx="{}" cy="{}" r="{}" fill="rgb({},{},{})" stroke="#333"><title>{}</title></circle>"##,
+        cx,
+        cy,
+        CELL / 3,
+        color.r,
+        color.g,
+        color.b,
+        name
+    );
+    let _ = writeln!(
+        svg,
+        r#"<text x="{}" y="{}" font-size="8" text-anchor="middle">{}</text>"#,
+        cx,
+        cy + CELL / 2 - 1,
+        name
+    );
+}
+
+/**
+ * Groups residents by the clan they belong to, in clan id order,
+ * followed by the crabs without a clan (if there are any).
+ */
+fn group_by_territory(beach: &Beach) -> Vec<(Option<String>, Vec<&Crab>)> {
+    let clan_system = beach.get_clan_system();
+    let mut group_of: HashMap<&str, usize> = HashMap::new();
+    let mut groups: Vec<(Option<String>, Vec<&Crab>)> = Vec::new();
+    for id in clan_system.clan_ids() {
+        group_of.insert(id, groups.len());
+        groups.push((Some(id.to_string()), Vec::new()));
+    }
+
+    let mut unaffiliated = Vec::new();
+    for (i, crab) in beach.crabs().enumerate() {
+        let clan = clan_system.get_clan_of(beach.handle(i));
+        match clan.and_then(|clan| group_of.get(clan)) {
+            Some(&k) => groups[k].1.push(crab),
+            None => unaffiliated.push(crab),
+        }
+    }
+    if !unaffiliated.is_empty() {
+        groups.push((None, unaffiliated));
+    }
+    groups
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,131 @@
+use crate::beach::Beach;
+use crate::crab::Crab;
+use crate::diet::Diet;
+use crate::i18n::{tr, Message};
+use crate::simulation::{Operation, Simulation};
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
+
+/** The glyph a crab is drawn with, by diet. */
+pub fn glyph(diet: Diet) -> char {
+    match diet {
+        Diet::Fish => 'F',
+        Diet::Shellfish => 'S',
+        Diet::Plants => 'P',
+    }
+}
+
+/**
+ * Draws a beach as a grid of glyphs in the terminal, replacing the
+ * previous frame in place so that successive frames animate.
+ */
+#[derive(Debug)]
+pub struct TerminalRenderer {
+    /** Number of cells per row of the grid. */
+    width: usize,
+    /** Whether to color glyphs with ANSI true-color escapes. */
+    color: bool,
+    /** Number of lines drawn by the previous frame. */
+    last_height: usize,
+}
+
+impl TerminalRenderer {
+    pub fn new(width: usize) -> TerminalRenderer {
+        TerminalRenderer {
+            width: width.max(1),
+            color: true,
+            last_height: 0,
+        }
+    }
+
+    /**
+     * Returns a renderer that draws plain glyphs, for terminals or logs
+     * without color support.
+     */
+    pub fn without_color(mut self) -> TerminalRenderer {
+        self.color = false;
+        self
+    }
+
+    /**
+     * Renders one frame: a border, one glyph per crab (in beach order, `width`
+     * per row), and a status line with the tick and population.
+     */
+    pub fn render(&self, beach: &Beach) -> String {
+        let crabs: Vec<&Crab> = beach.crabs().collect();
+        let border = format!("+{}+\n", "-".repeat(self.width));
+        let mut frame = border.clone();
+        // Always draw at least one row, even for an empty beach.
+        let rows: Vec<&[&Crab]> = if crabs.is_empty() {
+            vec![&[]]
+        } else {
+            crabs.chunks(self.width).collect()
+        };
+        for row in rows {
+            frame.push('|');
+            for crab in row {
+                frame.push_str(&self.cell(crab));
+            }
+            frame.push_str(&" ".repeat(self.width - row.len()));
+            frame.push_str("|\n");
+        }
+        frame.push_str(&border);
+        frame.push_str(&tr(
+            Message::TerminalStatus,
+            &[
+                &beach.ticks(),
+                &beach.size(),
+                &beach.get_clan_system().get_clan_count(),
+            ],
+        ));
+        frame.push('\n');
+        frame
+    }
+
+    fn cell(&self, crab: &Crab) -> String {
+        let glyph = glyph(crab.diet());
+        if self.color {
+            let c = crab.color();
+            format!("\x1b[38;2;{};{};{}m{}\x1b[0m", c.r, c.g, c.b, glyph)
+        } else {
+            glyph.to_string()
+        }
+    }
+
+    /**
+     * Writes a frame, first moving the cursor up over the previous frame and
+     * clearing it, so the beach is redrawn in place.
+     */
+    pub fn draw<W: Write>(&mut self, out: &mut W, beach: &Beach) -> io::Result<()> {
+        if self.last_height > 0 {
+            write!(out, "\x1b[{}A\x1b[J", self.last_height)?;
+        }
+        let frame = self.render(beach);
+        self.last_height = frame.lines().count();
+        out.write_all(frame.as_bytes())?;
+        out.flush()
+    }
+
+    /**
+     * Advances the simulation `ticks` times, drawing a frame before the first
+     * tick and after every tick, and pausing `delay` between frames.
+     */
+    pub fn watch<W: Write>(
+        &mut self,
+        out: &mut W,
+        simulation: &mut Simulation,
+        ticks: u64,
+        delay: Duration,
+    ) -> io::Result<()> {
+        self.draw(out, simulation.beach())?;
+        for _ in 0..ticks {
+            if !delay.is_zero() {
+                thread::sleep(delay);
+            }
+            simulation.apply(Operation::Tick);
+            self.draw(out, simulation.beach())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+use crate::beach::CrabId;
+use crate::position::Position;
+use std::collections::HashMap;
+
+/** The side of the cells crabs are bucketed into, unless set otherwise. */
+pub const DEFAULT_CELL_SIZE: u32 = 16;
+
+/**
+ * The bounded grid crabs move about on: `move_crab` keeps them within
+ * `width` by `height` positions.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Territory {
+    pub width: u32,
+    pub height: u32,
+    /**
+     * The side of the square cells crabs are bucketed into for finding
+     * nearby crabs; 0 counts as 1.
+     */
+    pub cell_size: u32,
+    /** How far apart two crabs may be and still breed, if there is a limit. */
+    pub breeding_distance: Option<u32>,
+}
+
+impl Territory {
+    pub fn new(width: u32, height: u32) -> Territory {
+        Territory {
+            width,
+            height,
+            cell_size: DEFAULT_CELL_SIZE,
+            breeding_distance: None,
+        }
+    }
+
+    /** Returns whether a position lies on the grid. */
+    pub fn contains(&self, position: Position) -> bool {
+        position.x < self.width && position.y < self.height
+    }
+}
+
+type Cell = (u32, u32);
+
+/**
+ * The crabs of a beach bucketed by the grid cell they stand in. Crabs placed
+ * off the grid are bucketed all the same.
+ */
+#[derive(Debug, Clone)]
+pub(crate) struct SpatialIndex {
+    territory: Territory,
+    cells: HashMap<Cell, Vec<(CrabId, Position)>>,
+    // Set when crabs may have moved without the index being told; a stale
+    // index is rebuilt before it is next changed, and not consulted until.
+    stale: bool,
+}
+
+impl SpatialIndex {
+    pub(crate) fn new(
+        territory: Territory,
+        crabs: impl Iterator<Item = (CrabId, Position)>,
+    ) -> SpatialIndex {
+        let mut index = SpatialIndex {
+            territory,
+            cells: HashMap::new(),
+            stale: false,
+        };
+        for (handle, position) in crabs {
+            index.insert(handle, position);
+        }
+        index
+    }
+
+    pub(crate) fn territory(&self) -> &Territory {
+        &self.territory
+    }
+
+    pub(crate) fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    pub(crate) fn mark_stale(&mut self) {
+        self.stale = true;
+    }
+
+    fn cell_size(&self) -> u32 {
+        self.territory.cell_size.max(1)
+    }
+
+    fn cell(&self, position: Position) -> Cell {
+        (position.x / self.cell_size(), position.y / self.cell_size())
+    }
+
+    pub(crate) fn insert(&mut self, handle: CrabId, position: Position) {
+        if self.stale {
+            return;
+        }
+        let bucket = self.cells.entry(self.cell(position)).or_default();
+        // Handles are issued in increasing order, so keep each bucket sorted.
+        let at = bucket.partition_point(|&(h, _)| h < handle);
+        bucket.insert(at, (handle, position));
+    }
+
+    pub(crate) fn remove(&mut self, handle: CrabId, position: Position) {
+        if self.stale {
+            return;
+        }
+        let cell = self.cell(position);
+        if let Some(bucket) = self.cells.get_mut(&cell) {
+            bucket.retain(|&(h, _)| h != handle);
+            if bucket.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+    }
+
+    pub(crate) fn relocate(&mut self, handle: CrabId, from: Position, to: Position) {
+        self.remove(handle, from);
+        self.insert(handle, to);
+    }
+
+    /**
+     * Returns the handles of the crabs at most `radius` away from `point`,
+     * in increasing order.
+     */
+    pub(crate) fn within(&self, point: Position, radius: u32) -> Vec<CrabId> {
+        let (left, top) = self.cell(Position::new(
+            point.x.saturating_sub(radius),
+            point.y.saturating_sub(radius),
+        ));
+        let (right, bottom) = self.cell(Position::new(
+            point.x.saturating_add(radius),
+            point.y.saturating_add(radius),
+        ));
+        let span = (right - left + 1) as u64 * (bottom - top + 1) as u64;
+        let near = |&(_, position): &(CrabId, Position)| {
+            point.distance_squared(position) <= radius as u64 * radius as u64
+        };
+        let mut handles: Vec<CrabId> = if span > self.cells.len() as u64 {
+            self.cells
+                .iter()
+                .filter(|(&(x, y), _)| (left..=right).contains(&x) && (top..=bottom).contains(&y))
+                .flat_map(|(_, bucket)| bucket.iter().filter(|entry| near(entry)))
+                .map(|&(handle, _)| handle)
+                .collect()
+        } else {
+            (top..=bottom)
+                .flat_map(|y| (left..=right).map(move |x| (x, y)))
+                .filter_map(|cell| self.cells.get(&cell))
+                .flat_map(|bucket| bucket.iter().filter(|entry| near(entry)))
+                .map(|&(handle, _)| handle)
+                .collect()
+        };
+        handles.sort();
+        handles
+    }
+
+    /**
+     * Returns the handle of the crab closest to `point`, the earliest issued
+     * one if several are as close, searching outwards ring by ring of cells.
+     */
+    pub(crate) fn nearest(&self, point: Position) -> Option<CrabId> {
+        let (cx, cy) = self.cell(point);
+        let reach = self
+            .cells
+            .keys()
+            .map(|&(x, y)| x.abs_diff(cx).max(y.abs_diff(cy)))
+            .max()?;
+        let size = self.cell_size() as u64;
+        let mut best: Option<(u64, CrabId)> = None;
+        for ring in 0..=reach {
+            for cell in ring_cells((cx, cy), ring) {
+                for &(handle, position) in self.cells.get(&cell).into_iter().flatten() {
+                    let candidate = (point.distance_squared(position), handle);
+                    if best.is_none_or(|best| candidate < best) {
+                        best = Some(candidate);
+                    }
+                }
+            }
+            // Every crab further out is more than `ring` cells away.
+            let covered = ring as u64 * size;
+            if best.is_some_and(|(distance, _)| distance <= covered * covered) {
+                break;
+            }
+        }
+        best.map(|(_, handle)| handle)
+    }
+}
+
+/** Returns the cells exactly `ring` cells away from `center`, on the grid. */
+fn ring_cells(center: Cell, ring: u32) -> Vec<Cell> {
+    let (cx, cy) = (center.0 as i64, center.1 as i64);
+    let ring = ring as i64;
+    let mut cells = Vec::new();
+    for x in cx - ring..=cx + ring {
+        cells.push((x, cy - ring));
+        if ring > 0 {
+            cells.push((x, cy + ring));
+        }
+    }
+    for y in cy - ring + 1..cy + ring {
+        cells.push((cx - ring, y));
+        cells.push((cx + ring, y));
+    }
+    cells
+        .into_iter()
+        .filter_map(|(x, y)| Some((u32::try_from(x).ok()?, u32::try_from(y).ok()?)))
+        .collect()
+}
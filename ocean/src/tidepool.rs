@@ -0,0 +1,211 @@
+use crate::beach::CrabId;
+use crate::i18n::{tr, Message};
+use crate::prey::Algae;
+use crate::reef::Reef;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/** The share of crabs that move between the pools and the beach each tide. */
+pub const DEFAULT_EXCHANGE_RATE: f64 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tide {
+    Low,
+    High,
+}
+
+impl Tide {
+    pub fn turned(self) -> Tide {
+        match self {
+            Tide::Low => Tide::High,
+            Tide::High => Tide::Low,
+        }
+    }
+}
+
+/**
+ * A pool left on the beach by the sea, with its own residents and its own
+ * stock of food. Residents still live on the beach; they just feed here.
+ */
+#[derive(Debug)]
+pub struct TidePool {
+    pub name: String,
+    pub food: Rc<RefCell<Reef>>,
+    /** How many algae grow in the pool every tick, on a clean beach. */
+    pub regrowth: u32,
+    residents: Vec<CrabId>,
+}
+
+impl TidePool {
+    /** Returns the crabs living in the pool, in the order they moved in. */
+    pub fn residents(&self) -> &[CrabId] {
+        &self.residents
+    }
+}
+
+/** The crabs that moved when the tide turned. */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Exchange {
+    /** Crabs that left a pool for the main beach, with the pool they left. */
+    pub left: Vec<(CrabId, usize)>,
+    /** Crabs that moved from the main beach into a pool. */
+    pub entered: Vec<(CrabId, usize)>,
+}
+
+/**
+ * The tide pools of a beach and the state of the tide.
+ *
+ * Every `period` ticks the tide turns, and the sea mixes the pools with the
+ * main beach: each pool resident leaves its pool with probability
+ * `exchange_rate`, and each crab on the main beach moves into a random pool
+ * with the same probability. A period of 0 means the tide never turns on
+ * its own.
+ */
+#[derive(Debug)]
+pub struct TidePools {
+    pools: Vec<TidePool>,
+    tide: Tide,
+    period: u64,
+    exchange_rate: f64,
+    rng: Pcg64,
+}
+
+impl TidePools {
+    pub fn new(seed: u64) -> TidePools {
+        TidePools {
+            pools: Vec::new(),
+            tide: Tide::Low,
+            period: 0,
+            exchange_rate: DEFAULT_EXCHANGE_RATE,
+            rng: Pcg64::seed_from_u64(seed),
+        }
+    }
+
+    /** Adds an empty pool with the given food stock, returning its index. */
+    pub fn add_pool(&mut self, name: &str, food: Rc<RefCell<Reef>>) -> usize {
+        self.pools.push(TidePool {
+            name: name.to_string(),
+            food,
+            regrowth: 0,
+            residents: Vec::new(),
+        });
+        self.pools.len() - 1
+    }
+
+    pub fn pools(&self) -> &[TidePool] {
+        &self.pools
+    }
+
+    pub fn tide(&self) -> Tide {
+        self.tide
+    }
+
+    pub fn period(&self) -> u64 {
+        self.period
+    }
+
+    pub fn set_period(&mut self, period: u64) {
+        self.period = period;
+    }
+
+    pub fn exchange_rate(&self) -> f64 {
+        self.exchange_rate
+    }
+
+    /** Sets the share of crabs exchanged, clamped to between 0 and 1. */
+    pub fn set_exchange_rate(&mut self, rate: f64) {
+        self.exchange_rate = rate.clamp(0.0, 1.0);
+    }
+
+    /** Sets how many algae grow in a pool every tick, on a clean beach. */
+    pub fn set_regrowth(&mut self, pool: usize, per_tick: u32) -> Result<(), String> {
+        let pool = self
+            .pools
+            .get_mut(pool)
+            .ok_or_else(|| tr(Message::NoSuchTidePool, &[&pool]))?;
+        pool.regrowth = per_tick;
+        Ok(())
+    }
+
+    /**
+     * Grows each pool's algae for one tick, scaled by `factor` (from 0 to 1)
+     * and rounded to the nearest alga.
+     */
+    pub fn regrow(&mut self, factor: f64) {
+        for pool in &self.pools {
+            let grown = (pool.regrowth as f64 * factor.clamp(0.0, 1.0)).round() as u32;
+            let mut food = pool.food.borrow_mut();
+            for _ in 0..grown {
+                food.add_prey(Box::new(Algae::new()));
+            }
+        }
+    }
+
+    /** Returns the index of the pool a crab lives in, if any. */
+    pub fn pool_of(&self, crab: CrabId) -> Option<usize> {
+        self.pools
+            .iter()
+            .position(|pool| pool.residents.contains(&crab))
+    }
+
+    /**
+     * Moves a crab into a pool, out of any pool it lived in before.
+     * Fails if there is no such pool.
+     */
+    pub fn enter(&mut self, crab: CrabId, pool: usize) -> Result<(), String> {
+        if pool >= self.pools.len() {
+            return Err(tr(Message::NoSuchTidePool, &[&pool]));
+        }
+        self.leave(crab);
+        self.pools[pool].residents.push(crab);
+        Ok(())
+    }
+
+    /** Moves a crab out of its pool, returning the pool it left. */
+    pub fn leave(&mut self, crab: CrabId) -> Option<usize> {
+        let pool = self.pool_of(crab)?;
+        self.pools[pool].residents.retain(|&c| c != crab);
+        Some(pool)
+    }
+
+    /**
+     * Turns the tide and mixes the pools with the main beach, given the
+     * handles of every crab on the beach.
+     */
+    pub fn turn(&mut self, crabs: &[CrabId]) -> Exchange {
+        self.tide = self.tide.turned();
+        let mut exchange = Exchange::default();
+        if self.pools.is_empty() {
+            return exchange;
+        }
+        let rate = self.exchange_rate;
+        for (k, pool) in self.pools.iter_mut().enumerate() {
+            let rng = &mut self.rng;
+            pool.residents.retain(|&crab| {
+                let leaves = rng.gen_bool(rate);
+                if leaves {
+                    exchange.left.push((crab, k));
+                }
+                !leaves
+            });
+        }
+        for &crab in crabs {
+            let on_beach =
+                self.pool_of(crab).is_none() && !exchange.left.iter().any(|&(c, _)| c == crab);
+            if on_beach && self.rng.gen_bool(rate) {
+                let pool = self.rng.gen_range(0..self.pools.len());
+                self.pools[pool].residents.push(crab);
+                exchange.entered.push((crab, pool));
+            }
+        }
+        exchange
+    }
+}
+
+impl Default for TidePools {
+    fn default() -> Self {
+        TidePools::new(0)
+    }
+}
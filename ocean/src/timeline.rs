@@ -0,0 +1,376 @@
+use crate::beach::{Beach, CrabId};
+use crate::color::Color;
+use crate::constraints::Constraints;
+use crate::crab::Crab;
+use crate::diet::Diet;
+use std::collections::{BTreeMap, BTreeSet};
+
+/** The observable state of one crab at some point in time. */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CrabState {
+    pub name: String,
+    pub speed: u32,
+    pub color: Color,
+    pub diet: Diet,
+    pub claw_size: u32,
+    pub health: u32,
+}
+
+impl CrabState {
+    pub fn capture(crab: &Crab) -> CrabState {
+        CrabState {
+            name: crab.name().to_string(),
+            speed: crab.speed(),
+            color: crab.color().clone(),
+            diet: crab.diet(),
+            claw_size: crab.claw_size(),
+            health: crab.health(),
+        }
+    }
+}
+
+/**
+ * A read-only copy of a beach's crabs and clans as of some tick.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WorldState {
+    tick: u64,
+    crabs: Vec<CrabState>,
+    clans: BTreeMap<String, Vec<String>>,
+}
+
+impl WorldState {
+    /**
+     * Builds a world from its parts: the crabs in beach order, and each
+     * clan's member names by clan id.
+     */
+    pub fn new(
+        tick: u64,
+        crabs: Vec<CrabState>,
+        clans: BTreeMap<String, Vec<String>>,
+    ) -> WorldState {
+        WorldState { tick, crabs, clans }
+    }
+
+    pub fn capture(beach: &Beach) -> WorldState {
+        let clan_system = beach.get_clan_system();
+        let clans = clan_system
+            .clan_ids()
+            .map(|id| (id.to_string(), clan_system.get_clan_member_names(id)))
+            .collect();
+        WorldState {
+            tick: beach.ticks(),
+            crabs: beach.crabs().map(CrabState::capture).collect(),
+            clans,
+        }
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn population(&self) -> usize {
+        self.crabs.len()
+    }
+
+    pub fn crabs(&self) -> &[CrabState] {
+        &self.crabs
+    }
+
+    pub fn crab(&self, index: usize) -> Option<&CrabState> {
+        self.crabs.get(index)
+    }
+
+    pub fn clan_ids(&self) -> impl Iterator<Item = &str> {
+        self.clans.keys().map(|id| id.as_str())
+    }
+
+    pub fn clan_members(&self, clan_id: &str) -> &[String] {
+        self.clans
+            .get(clan_id)
+            .map_or(&[], |members| members.as_slice())
+    }
+
+    /**
+     * Builds a beach holding this world's crabs and clans, with its clock at
+     * this world's tick. Crabs start at the default position.
+     */
+    pub fn to_beach(&self) -> Beach {
+        let mut beach = Beach::new();
+        // The crabs were already on a beach, whatever its constraints.
+        beach.set_constraints(Constraints::none());
+        for state in &self.crabs {
+            let mut crab = Crab::new(
+                state.name.clone(),
+                state.speed,
+                state.color.clone(),
+                state.diet,
+            );
+            crab.set_claw_size(state.claw_size);
+            crab.injure(crab.health().saturating_sub(state.health));
+            beach.add_crab(crab);
+        }
+        for (clan_id, members) in &self.clans {
+            for member in members {
+                beach.add_member_to_clan(clan_id, member);
+            }
+        }
+        for _ in 0..self.tick {
+            beach.tick();
+        }
+        beach
+    }
+
+    fn apply(&mut self, delta: &Delta) {
+        match delta {
+            Delta::CrabAdded(state) => self.crabs.push(state.clone()),
+            Delta::CrabChanged { index, state } => self.crabs[*index] = state.clone(),
+            Delta::CrabRemoved { index } => {
+                self.crabs.remove(*index);
+            }
+            Delta::ClanMemberAdded { clan_id, crab_name } => self
+                .clans
+                .entry(clan_id.clone())
+                .or_default()
+                .push(crab_name.clone()),
+            Delta::ClanMemberRemoved { clan_id, crab_name } => {
+                if let Some(members) = self.clans.get_mut(clan_id) {
+                    members.retain(|member| member != crab_name);
+                    if members.is_empty() {
+                        self.clans.remove(clan_id);
+                    }
+                }
+            }
+            Delta::Tick => self.tick += 1,
+        }
+    }
+}
+
+/**
+ * A change to the world, as recorded between snapshots. Crabs are found by
+ * their index in the world as it stands when the change is applied.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Delta {
+    CrabAdded(CrabState),
+    CrabChanged {
+        index: usize,
+        state: CrabState,
+    },
+    CrabRemoved {
+        index: usize,
+    },
+    ClanMemberAdded {
+        clan_id: String,
+        crab_name: String,
+    },
+    /** The crab left the clan; a clan left without members is gone. */
+    ClanMemberRemoved {
+        clan_id: String,
+        crab_name: String,
+    },
+    Tick,
+}
+
+/**
+ * A beach's crabs, each with its handle, and its clans, as they were just
+ * before a change; see `Timeline::record_change`.
+ */
+#[derive(Debug, Clone)]
+pub struct BeachCapture {
+    crabs: Vec<(CrabId, CrabState)>,
+    clans: BTreeMap<String, Vec<String>>,
+}
+
+impl BeachCapture {
+    pub fn of(beach: &Beach) -> BeachCapture {
+        let crabs = (0..beach.size())
+            .map(|index| {
+                (
+                    beach.handle(index),
+                    CrabState::capture(beach.crab_at(index)),
+                )
+            })
+            .collect();
+        BeachCapture {
+            crabs,
+            clans: WorldState::capture(beach).clans,
+        }
+    }
+
+    /**
+     * Returns the deltas that turn the world as captured into `beach`. Crabs
+     * are matched by handle, so crabs that left are removed wherever they
+     * stood, and the crabs left behind keep their own changes.
+     */
+    pub fn deltas_to(&self, beach: &Beach) -> Vec<Delta> {
+        let after = BeachCapture::of(beach);
+        let mut deltas = Vec::new();
+
+        let mut crabs: Vec<CrabId> = self.crabs.iter().map(|&(handle, _)| handle).collect();
+        let remaining: BTreeSet<CrabId> = after.crabs.iter().map(|&(handle, _)| handle).collect();
+        let mut index = 0;
+        while index < crabs.len() {
+            if remaining.contains(&crabs[index]) {
+                index += 1;
+            } else {
+                crabs.remove(index);
+                deltas.push(Delta::CrabRemoved { index });
+            }
+        }
+        // Crabs that stayed keep their order, and arrivals come after them;
+        // should a change ever reorder the crabs, they are all replaced.
+        let kept = crabs.len();
+        if after.crabs.len() < kept
+            || after.crabs[..kept]
+                .iter()
+                .map(|&(handle, _)| handle)
+                .ne(crabs.iter().copied())
+        {
+            deltas.extend((0..kept).rev().map(|index| Delta::CrabRemoved { index }));
+            crabs.clear();
+        }
+        let before: BTreeMap<CrabId, &CrabState> = self
+            .crabs
+            .iter()
+            .map(|(handle, state)| (*handle, state))
+            .collect();
+        for (index, (handle, state)) in after.crabs.iter().enumerate() {
+            if index >= crabs.len() {
+                deltas.push(Delta::CrabAdded(state.clone()));
+            } else if before[handle] != state {
+                deltas.push(Delta::CrabChanged {
+                    index,
+                    state: state.clone(),
+                });
+            }
+        }
+
+        for (clan_id, members) in &self.clans {
+            let now = after.clans.get(clan_id).map_or(&[][..], Vec::as_slice);
+            for crab_name in members.iter().filter(|&member| !now.contains(member)) {
+                deltas.push(Delta::ClanMemberRemoved {
+                    clan_id: clan_id.clone(),
+                    crab_name: crab_name.clone(),
+                });
+            }
+        }
+        for (clan_id, members) in &after.clans {
+            let then = self.clans.get(clan_id).map_or(&[][..], Vec::as_slice);
+            for crab_name in members.iter().filter(|&member| !then.contains(member)) {
+                deltas.push(Delta::ClanMemberAdded {
+                    clan_id: clan_id.clone(),
+                    crab_name: crab_name.clone(),
+                });
+            }
+        }
+        deltas
+    }
+}
+
+/**
+ * The history of a beach: a full snapshot every `interval` ticks, and the
+ * deltas that happened in between.
+ *
+ * Reconstructing any past tick costs at most one snapshot copy and
+ * `interval` ticks' worth of deltas, without re-running the simulation.
+ */
+#[derive(Debug)]
+pub struct Timeline {
+    interval: u64,
+    // Each snapshot, with the number of deltas recorded when it was taken.
+    snapshots: Vec<(WorldState, usize)>,
+    deltas: Vec<Delta>,
+    current_tick: u64,
+}
+
+impl Timeline {
+    /**
+     * Starts a timeline from the beach's current state, taking a snapshot
+     * every `interval` ticks (at least 1).
+     */
+    pub fn new(beach: &Beach, interval: u64) -> Timeline {
+        Timeline {
+            interval: interval.max(1),
+            snapshots: vec![(WorldState::capture(beach), 0)],
+            deltas: Vec::new(),
+            current_tick: beach.ticks(),
+        }
+    }
+
+    pub fn interval(&self) -> u64 {
+        self.interval
+    }
+
+    /** The first tick that can be inspected. */
+    pub fn start_tick(&self) -> u64 {
+        self.snapshots[0].0.tick()
+    }
+
+    /** The latest tick that can be inspected. */
+    pub fn current_tick(&self) -> u64 {
+        self.current_tick
+    }
+
+    pub fn snapshot_count(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    /**
+     * Records the change from `before` to `beach`, after advancing the clock
+     * if the change was a tick, so that what happened during a tick counts
+     * toward the end of that tick. Takes a snapshot when a tick brings the
+     * clock to a multiple of the interval.
+     */
+    pub fn record_change(&mut self, before: &BeachCapture, beach: &Beach, ticked: bool) {
+        if ticked {
+            self.deltas.push(Delta::Tick);
+            self.current_tick += 1;
+        }
+        self.deltas.extend(before.deltas_to(beach));
+        if ticked && self.current_tick.is_multiple_of(self.interval) {
+            self.snapshots
+                .push((WorldState::capture(beach), self.deltas.len()));
+        }
+    }
+
+    /**
+     * Records a change. When `Delta::Tick` brings the clock to a multiple of
+     * the interval, `beach` (which must already reflect all recorded deltas)
+     * is snapshotted.
+     */
+    pub fn record(&mut self, delta: Delta, beach: &Beach) {
+        let is_tick = delta == Delta::Tick;
+        self.deltas.push(delta);
+        if is_tick {
+            self.current_tick += 1;
+            if self.current_tick.is_multiple_of(self.interval) {
+                self.snapshots
+                    .push((WorldState::capture(beach), self.deltas.len()));
+            }
+        }
+    }
+
+    /**
+     * Returns the state of the world at the end of the given tick, or None
+     * if the tick is outside the recorded history.
+     */
+    pub fn at_tick(&self, tick: u64) -> Option<WorldState> {
+        if tick < self.start_tick() || tick > self.current_tick {
+            return None;
+        }
+        let position = self
+            .snapshots
+            .partition_point(|(state, _)| state.tick() <= tick);
+        let (snapshot, first_delta) = &self.snapshots[position - 1];
+
+        let mut state = snapshot.clone();
+        for delta in &self.deltas[*first_delta..] {
+            if *delta == Delta::Tick && state.tick() == tick {
+                break;
+            }
+            state.apply(delta);
+        }
+        Some(state)
+    }
+}
@@ -0,0 +1,528 @@
+use crate::beach::Beach;
+use crate::error::OceanError;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+/** Points awarded for each match result. */
+pub const POINTS_FOR_WIN: u32 = 3;
+pub const POINTS_FOR_DRAW: u32 = 1;
+
+/** How pairings are generated from one day to the next. */
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Format {
+    /** Every participant meets every other participant exactly once. */
+    RoundRobin,
+    /**
+     * Participants with similar scores are paired each day, avoiding
+     * rematches where possible, for the given number of days.
+     */
+    Swiss { days: usize },
+    /**
+     * Single elimination: the loser of each match is out, and the top seeds
+     * get byes until the field is a power of two. A drawn match is won by the
+     * higher seed, i.e. the home side.
+     */
+    Knockout,
+}
+
+/** A match scheduled between two participants, or a bye if `away` is None. */
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Pairing {
+    pub home: String,
+    pub away: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MatchResult {
+    HomeWin,
+    AwayWin,
+    Draw,
+}
+
+/** A match in a tournament's history, or a bye if `away` is None. */
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PlayedMatch {
+    /** The day the match was played on, starting at 0. */
+    pub day: usize,
+    pub home: String,
+    pub away: Option<String>,
+    pub result: MatchResult,
+}
+
+impl PlayedMatch {
+    /** Returns the id of the winner, or None for a draw. Byes are won by `home`. */
+    pub fn winner(&self) -> Option<&str> {
+        match (self.result, &self.away) {
+            (MatchResult::HomeWin, _) | (_, None) => Some(&self.home),
+            (MatchResult::AwayWin, Some(away)) => Some(away),
+            (MatchResult::Draw, _) => None,
+        }
+    }
+}
+
+/** One row of the standings table. */
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Standing {
+    pub id: String,
+    pub played: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    pub byes: u32,
+}
+
+impl Standing {
+    fn new(id: &str) -> Standing {
+        Standing {
+            id: id.to_string(),
+            played: 0,
+            wins: 0,
+            draws: 0,
+            losses: 0,
+            byes: 0,
+        }
+    }
+
+    pub fn points(&self) -> u32 {
+        self.wins * POINTS_FOR_WIN + self.draws * POINTS_FOR_DRAW
+    }
+}
+
+/**
+ * A multi-day tournament between clans or individual crabs, identified by
+ * their clan id or crab name respectively.
+ *
+ * The tournament keeps its standings between days, so a simulation can play
+ * one day at a time and inspect the table in between.
+ */
+#[derive(Debug)]
+pub struct Tournament {
+    format: Format,
+    standings: Vec<Standing>,
+    // Pairs of participants (by index into `standings`) that have already met.
+    played: HashSet<(usize, usize)>,
+    // Participants knocked out of a knockout tournament.
+    eliminated: HashSet<usize>,
+    history: Vec<PlayedMatch>,
+    day: usize,
+}
+
+impl Tournament {
+    /**
+     * Creates a tournament between the given participants. Duplicate ids are ignored.
+     */
+    pub fn new(format: Format, participants: &[&str]) -> Tournament {
+        let mut standings: Vec<Standing> = Vec::new();
+        for id in participants {
+            if !standings.iter().any(|s| s.id == *id) {
+                standings.push(Standing::new(id));
+            }
+        }
+        Tournament {
+            format,
+            standings,
+            played: HashSet::new(),
+            eliminated: HashSet::new(),
+            history: Vec::new(),
+            day: 0,
+        }
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /**
+     * Returns the number of days already played.
+     */
+    pub fn day(&self) -> usize {
+        self.day
+    }
+
+    /**
+     * Returns the total number of days this tournament lasts.
+     */
+    pub fn total_days(&self) -> usize {
+        match self.format {
+            Format::RoundRobin => {
+                let n = self.standings.len();
+                if n < 2 {
+                    0
+                } else if n.is_multiple_of(2) {
+                    n - 1
+                } else {
+                    n
+                }
+            }
+            Format::Swiss { days } => days,
+            Format::Knockout => self.standings.len().next_power_of_two().trailing_zeros() as usize,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.day >= self.total_days()
+    }
+
+    /**
+     * Returns the pairings for the next day, or an empty vector if the
+     * tournament is finished.
+     */
+    pub fn next_pairings(&self) -> Vec<Pairing> {
+        if self.is_finished() {
+            return Vec::new();
+        }
+        let pairs = match self.format {
+            Format::RoundRobin => self.round_robin_pairs(self.day),
+            Format::Swiss { .. } => self.swiss_pairs(),
+            Format::Knockout => self.knockout_pairs(),
+        };
+        pairs
+            .into_iter()
+            .map(|(home, away)| Pairing {
+                home: self.standings[home].id.clone(),
+                away: away.map(|a| self.standings[a].id.clone()),
+            })
+            .collect()
+    }
+
+    /**
+     * Round-robin pairings for the given day, using the circle method: the
+     * first participant stays put while everyone else rotates around it.
+     */
+    fn round_robin_pairs(&self, day: usize) -> Vec<(usize, Option<usize>)> {
+        // With an odd number of participants, a phantom slot gives out byes.
+        let mut slots: Vec<Option<usize>> = (0..self.standings.len()).map(Some).collect();
+        if slots.len() % 2 == 1 {
+            slots.push(None);
+        }
+        let n = slots.len();
+        let mut rotated = vec![slots[0]];
+        for k in 0..n - 1 {
+            rotated.push(slots[1 + (k + n - 1 - day % (n - 1)) % (n - 1)]);
+        }
+
+        let mut pairs = Vec::new();
+        for k in 0..n / 2 {
+            match (rotated[k], rotated[n - 1 - k]) {
+                (Some(a), Some(b)) => pairs.push((a, Some(b))),
+                (Some(a), None) | (None, Some(a)) => pairs.push((a, None)),
+                (None, None) => {}
+            }
+        }
+        pairs
+    }
+
+    /**
+     * Swiss pairings: participants are ranked by the current table and each
+     * is paired with the highest-ranked remaining opponent it has not met yet.
+     * If everyone left has already been met, a rematch is allowed. With an odd
+     * number of participants, the lowest-ranked one without a bye sits out.
+     */
+    fn swiss_pairs(&self) -> Vec<(usize, Option<usize>)> {
+        let mut order = self.ranked_indices();
+        let mut pairs = Vec::new();
+
+        if order.len() % 2 == 1 {
+            let bye = order
+                .iter()
+                .rposition(|&i| self.standings[i].byes == 0)
+                .unwrap_or(order.len() - 1);
+            pairs.push((order.remove(bye), None));
+        }
+
+        while !order.is_empty() {
+            let home = order.remove(0);
+            let away = order
+                .iter()
+                .position(|&other| !self.played.contains(&ordered(home, other)))
+                .unwrap_or(0);
+            pairs.push((home, Some(order.remove(away))));
+        }
+        pairs
+    }
+
+    /**
+     * Knockout pairings: the participants still in, in seeding order. The top
+     * seeds get byes until the rest make a power of two, and the rest are
+     * paired best against worst.
+     */
+    fn knockout_pairs(&self) -> Vec<(usize, Option<usize>)> {
+        let alive: Vec<usize> = (0..self.standings.len())
+            .filter(|i| !self.eliminated.contains(i))
+            .collect();
+        let byes = alive.len().next_power_of_two() - alive.len();
+        let (seeded, rest) = alive.split_at(byes);
+
+        let mut pairs: Vec<(usize, Option<usize>)> = seeded.iter().map(|&i| (i, None)).collect();
+        for k in 0..rest.len() / 2 {
+            pairs.push((rest[k], Some(rest[rest.len() - 1 - k])));
+        }
+        pairs
+    }
+
+    /**
+     * Records the results of the next day's matches, in the same order as
+     * returned by `next_pairings`. Byes may be given any result.
+     * Panics if the number of results does not match the number of pairings.
+     */
+    pub fn record_day(&mut self, results: &[MatchResult]) {
+        let pairings = self.next_pairings();
+        assert_eq!(
+            pairings.len(),
+            results.len(),
+            "expected one result per pairing"
+        );
+
+        for (pairing, result) in pairings.iter().zip(results) {
+            self.history.push(PlayedMatch {
+                day: self.day,
+                home: pairing.home.clone(),
+                away: pairing.away.clone(),
+                result: *result,
+            });
+            let home = self.index_of(&pairing.home);
+            let away = match &pairing.away {
+                Some(away) => self.index_of(away),
+                None => {
+                    self.standings[home].byes += 1;
+                    continue;
+                }
+            };
+            self.played.insert(ordered(home, away));
+            self.standings[home].played += 1;
+            self.standings[away].played += 1;
+            match result {
+                MatchResult::HomeWin => {
+                    self.standings[home].wins += 1;
+                    self.standings[away].losses += 1;
+                }
+                MatchResult::AwayWin => {
+                    self.standings[away].wins += 1;
+                    self.standings[home].losses += 1;
+                }
+                MatchResult::Draw => {
+                    self.standings[home].draws += 1;
+                    self.standings[away].draws += 1;
+                }
+            }
+            if self.format == Format::Knockout {
+                let loser = if *result == MatchResult::AwayWin {
+                    home
+                } else {
+                    away
+                };
+                self.eliminated.insert(loser);
+            }
+        }
+        self.day += 1;
+    }
+
+    /**
+     * Plays the next day, deciding each match with `decide(home, away)`:
+     * `Ordering::Greater` means the home side wins, `Less` the away side,
+     * and `Equal` a draw. Returns the pairings that were played.
+     */
+    pub fn play_day<F>(&mut self, mut decide: F) -> Vec<Pairing>
+    where
+        F: FnMut(&str, &str) -> Ordering,
+    {
+        let pairings = self.next_pairings();
+        let results: Vec<MatchResult> = pairings
+            .iter()
+            .map(|pairing| match &pairing.away {
+                Some(away) => match decide(&pairing.home, away) {
+                    Ordering::Greater => MatchResult::HomeWin,
+                    Ordering::Less => MatchResult::AwayWin,
+                    Ordering::Equal => MatchResult::Draw,
+                },
+                None => MatchResult::HomeWin,
+            })
+            .collect();
+        self.record_day(&results);
+        pairings
+    }
+
+    /**
+     * Plays the next day of a tournament between clans of the given beach,
+     * using `Beach::get_winner_clan` to decide each match. Matches involving
+     * clans that can no longer compete are drawn.
+     */
+    pub fn play_clan_day(&mut self, beach: &Beach) -> Vec<Pairing> {
+        self.play_day(|home, away| match beach.get_winner_clan(home, away) {
+            Ok(Some(winner)) if winner == home => Ordering::Greater,
+            Ok(Some(_)) => Ordering::Less,
+            _ => Ordering::Equal,
+        })
+    }
+
+    /**
+     * Plays the next day of a tournament between crabs of the given beach.
+     * The faster crab wins; equally fast or missing crabs draw.
+     */
+    pub fn play_crab_day(&mut self, beach: &Beach) -> Vec<Pairing> {
+        let speed_of = |name: &str| {
+            beach
+                .find_crabs_by_name(name)
+                .iter()
+                .map(|crab| crab.typed_speed())
+                .max()
+        };
+        self.play_day(|home, away| match (speed_of(home), speed_of(away)) {
+            (Some(home), Some(away)) => home.cmp(&away),
+            _ => Ordering::Equal,
+        })
+    }
+
+    /**
+     * Returns the current standings, best first: by points, then wins, then id.
+     */
+    pub fn table(&self) -> Vec<&Standing> {
+        self.ranked_indices()
+            .into_iter()
+            .map(|i| &self.standings[i])
+            .collect()
+    }
+
+    /**
+     * Returns the standing of the participant with the given id, if any.
+     */
+    pub fn standing(&self, id: &str) -> Option<&Standing> {
+        self.standings.iter().find(|s| s.id == id)
+    }
+
+    /**
+     * Returns every match played so far, byes included, in the order they
+     * were played.
+     */
+    pub fn history(&self) -> &[PlayedMatch] {
+        &self.history
+    }
+
+    /**
+     * Returns the matches the given participant played or had a bye in.
+     */
+    pub fn matches_of(&self, id: &str) -> Vec<&PlayedMatch> {
+        self.history
+            .iter()
+            .filter(|m| m.home == id || m.away.as_deref() == Some(id))
+            .collect()
+    }
+
+    /**
+     * Returns the matches played between two participants, in either order.
+     */
+    pub fn head_to_head(&self, id1: &str, id2: &str) -> Vec<&PlayedMatch> {
+        self.history
+            .iter()
+            .filter(|m| match &m.away {
+                Some(away) => (m.home == id1 && away == id2) || (m.home == id2 && away == id1),
+                None => false,
+            })
+            .collect()
+    }
+
+    /**
+     * Returns true if the participant is still in a knockout tournament.
+     * Participants of other formats are never knocked out.
+     */
+    pub fn is_alive(&self, id: &str) -> bool {
+        self.standings
+            .iter()
+            .position(|s| s.id == id)
+            .is_some_and(|i| !self.eliminated.contains(&i))
+    }
+
+    fn ranked_indices(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.standings.len()).collect();
+        order.sort_by(|&a, &b| {
+            let (a, b) = (&self.standings[a], &self.standings[b]);
+            b.points()
+                .cmp(&a.points())
+                .then(b.wins.cmp(&a.wins))
+                .then(a.id.cmp(&b.id))
+        });
+        order
+    }
+
+    fn index_of(&self, id: &str) -> usize {
+        self.standings
+            .iter()
+            .position(|s| s.id == id)
+            .expect("pairings only contain participants")
+    }
+}
+
+fn ordered(a: usize, b: usize) -> (usize, usize) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/** One row of the final table of a tournament between clans. */
+#[derive(Debug, PartialEq, Clone)]
+pub struct ClanStanding {
+    pub standing: Standing,
+    /** The clan's average speed when the tournament was run. */
+    pub avg_speed: f64,
+}
+
+/** A tournament between clans, played to the end. */
+#[derive(Debug)]
+pub struct ClanTournament {
+    /**
+     * The final table, best first: by points, then wins, then average speed,
+     * then clan id.
+     */
+    pub table: Vec<ClanStanding>,
+    /** The tournament itself, with its match history. */
+    pub tournament: Tournament,
+}
+
+impl Beach {
+    /**
+     * Runs a whole tournament of the given format between the given clans,
+     * deciding each match with `get_winner_clan`. Returns an error if one of
+     * the clans has no crabs living on this beach.
+     */
+    pub fn run_tournament(
+        &self,
+        format: Format,
+        clan_ids: &[&str],
+    ) -> Result<ClanTournament, OceanError> {
+        let mut speeds = Vec::new();
+        for &id in clan_ids {
+            let speed = self
+                .get_crabs_avg_speed(id)
+                .ok_or_else(|| OceanError::UnknownClan(id.to_string()))?;
+            speeds.push((id, speed));
+        }
+
+        let mut tournament = Tournament::new(format, clan_ids);
+        while !tournament.is_finished() {
+            tournament.play_clan_day(self);
+        }
+
+        let mut table: Vec<ClanStanding> = tournament
+            .standings
+            .iter()
+            .map(|standing| ClanStanding {
+                standing: standing.clone(),
+                avg_speed: speeds
+                    .iter()
+                    .find(|(id, _)| *id == standing.id)
+                    .map_or(0.0, |&(_, speed)| speed),
+            })
+            .collect();
+        table.sort_by(|a, b| {
+            b.standing
+                .points()
+                .cmp(&a.standing.points())
+                .then(b.standing.wins.cmp(&a.standing.wins))
+                .then(b.avg_speed.total_cmp(&a.avg_speed))
+                .then(a.standing.id.cmp(&b.standing.id))
+        });
+        Ok(ClanTournament { table, tournament })
+    }
+}
@@ -0,0 +1,150 @@
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+
+/**
+ * A route along which a clan ships its treasury's shells, and optionally
+ * some of its members, from one beach of an ocean to another every tick.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TradeRoute {
+    pub clan_id: String,
+    /** The index of the beach the route starts from. */
+    pub from: usize,
+    /** The index of the beach the route leads to. */
+    pub to: usize,
+    /** The most shells the route carries per tick. */
+    pub capacity: u64,
+    /** The most members the route carries per tick. */
+    pub migrants: usize,
+}
+
+/** What a trade route has done. */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RouteStats {
+    pub shells_moved: u64,
+    pub members_moved: usize,
+    /** Ticks the route was open for trade. */
+    pub active_ticks: u64,
+    /** Ticks storms kept the route closed. */
+    pub disrupted_ticks: u64,
+    pub storms: u32,
+}
+
+/**
+ * How storms strike trade routes: on each tick, every open route is hit
+ * with probability `chance` and closed for `duration` ticks. The defaults
+ * never strike.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StormSettings {
+    pub chance: f64,
+    pub duration: u64,
+    pub seed: u64,
+}
+
+#[derive(Debug)]
+struct Route {
+    route: TradeRoute,
+    stats: RouteStats,
+    // The tick the route reopens after a storm.
+    closed_until: u64,
+}
+
+/** The trade routes of an ocean. */
+#[derive(Debug)]
+pub struct TradeNetwork {
+    routes: Vec<Route>,
+    storms: StormSettings,
+    rng: Pcg64,
+}
+
+impl TradeNetwork {
+    pub fn new() -> TradeNetwork {
+        TradeNetwork {
+            routes: Vec::new(),
+            storms: StormSettings::default(),
+            rng: Pcg64::seed_from_u64(0),
+        }
+    }
+
+    /** Returns every route with its statistics, in the order opened. */
+    pub fn routes(&self) -> impl Iterator<Item = (&TradeRoute, &RouteStats)> {
+        self.routes.iter().map(|r| (&r.route, &r.stats))
+    }
+
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    pub fn stats(&self, route: usize) -> Option<&RouteStats> {
+        self.routes.get(route).map(|r| &r.stats)
+    }
+
+    /** Sets how storms strike the routes, reseeding them. */
+    pub fn set_storms(&mut self, storms: StormSettings) {
+        self.storms = storms;
+        self.rng = Pcg64::seed_from_u64(storms.seed);
+    }
+
+    pub(crate) fn open(&mut self, route: TradeRoute) -> usize {
+        self.routes.push(Route {
+            route,
+            stats: RouteStats::default(),
+            closed_until: 0,
+        });
+        self.routes.len() - 1
+    }
+
+    /** Returns whether a route is closed by a storm at tick `now`. */
+    pub fn is_disrupted(&self, route: usize, now: u64) -> bool {
+        self.routes.get(route).is_some_and(|r| now < r.closed_until)
+    }
+
+    /** Closes a route for `ticks` ticks from `now`, as a storm would. */
+    pub fn disrupt(&mut self, route: usize, now: u64, ticks: u64) -> bool {
+        let Some(r) = self.routes.get_mut(route) else {
+            return false;
+        };
+        r.closed_until = r.closed_until.max(now + ticks);
+        r.stats.storms += 1;
+        true
+    }
+
+    /**
+     * Lets storms strike at tick `now`, then returns the routes open for
+     * trade, counting every route's active or disrupted tick.
+     */
+    pub(crate) fn open_routes(&mut self, now: u64) -> Vec<(usize, TradeRoute)> {
+        let chance = self.storms.chance.clamp(0.0, 1.0);
+        let mut open = Vec::new();
+        for k in 0..self.routes.len() {
+            if !self.is_disrupted(k, now) && chance > 0.0 && self.rng.gen_bool(chance) {
+                self.disrupt(k, now, self.storms.duration);
+            }
+            let r = &mut self.routes[k];
+            if now < r.closed_until {
+                r.stats.disrupted_ticks += 1;
+            } else {
+                r.stats.active_ticks += 1;
+                open.push((k, r.route.clone()));
+            }
+        }
+        open
+    }
+
+    pub(crate) fn record(&mut self, route: usize, shells: u64, members: usize) {
+        let stats = &mut self.routes[route].stats;
+        stats.shells_moved += shells;
+        stats.members_moved += members;
+    }
+}
+
+impl Default for TradeNetwork {
+    fn default() -> Self {
+        TradeNetwork::new()
+    }
+}
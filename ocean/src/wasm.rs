@@ -0,0 +1,146 @@
+use crate::beach::Beach;
+use crate::color::Color;
+use crate::crab::Crab;
+use crate::diet::Diet;
+use crate::i18n::{tr, Message};
+use wasm_bindgen::prelude::*;
+
+/**
+ * A beach driven from JavaScript. Crabs are found by their index, as on
+ * `Beach`. Crabs, tick reports and whole beaches cross over as JSON
+ * strings, in the same shape `persist` saves them in; names and clan ids
+ * as plain strings. Every failure becomes a JavaScript `Error` with the
+ * message the crate would give.
+ */
+#[wasm_bindgen(js_name = Beach)]
+#[derive(Debug, Default)]
+pub struct WasmBeach {
+    beach: Beach,
+}
+
+#[wasm_bindgen(js_class = Beach)]
+impl WasmBeach {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmBeach {
+        WasmBeach::default()
+    }
+
+    /** Returns an empty beach whose random numbers come from the given seed. */
+    #[wasm_bindgen(js_name = withSeed)]
+    pub fn with_seed(seed: u64) -> WasmBeach {
+        WasmBeach {
+            beach: Beach::with_seed(seed),
+        }
+    }
+
+    /** Loads a beach from JSON saved by `toJson`. */
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: &str) -> Result<WasmBeach, JsError> {
+        Ok(WasmBeach {
+            beach: serde_json::from_str(json)?,
+        })
+    }
+
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> Result<String, JsError> {
+        Ok(serde_json::to_string(&self.beach)?)
+    }
+
+    pub fn size(&self) -> usize {
+        self.beach.size()
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.beach.ticks()
+    }
+
+    /**
+     * Adds a crab and returns its index. The color is in hex notation, such
+     * as "#ff8800", or a color name such as "coral"; the diet is "Fish",
+     * "Shellfish" or "Plants".
+     */
+    #[wasm_bindgen(js_name = addCrab)]
+    pub fn add_crab(
+        &mut self,
+        name: &str,
+        speed: u32,
+        color: &str,
+        diet: &str,
+    ) -> Result<usize, JsError> {
+        let color = Color::from_hex(color)
+            .or_else(|| Color::named(color))
+            .ok_or_else(|| JsError::new(&tr(Message::UnknownColor, &[&color])))?;
+        let diet = match diet {
+            "Fish" => Diet::Fish,
+            "Shellfish" => Diet::Shellfish,
+            "Plants" => Diet::Plants,
+            _ => return Err(JsError::new(&tr(Message::CsvBadDiet, &[&diet]))),
+        };
+        self.beach
+            .admit_crab(Crab::new(name.to_string(), speed, color, diet))?;
+        Ok(self.beach.size() - 1)
+    }
+
+    /** Returns the crab at the given index as JSON. */
+    pub fn crab(&self, index: usize) -> Result<String, JsError> {
+        Ok(serde_json::to_string(self.beach.try_get_crab(index)?)?)
+    }
+
+    /** Returns every crab on the beach, in order, as a JSON array. */
+    pub fn crabs(&self) -> Result<String, JsError> {
+        Ok(serde_json::to_string(
+            &self.beach.crabs().collect::<Vec<_>>(),
+        )?)
+    }
+
+    /** Breeds the crabs at the given indices, naming the child. */
+    pub fn breed(&mut self, i: usize, j: usize, name: &str) -> Result<(), JsError> {
+        Ok(self.beach.try_breed_crabs(i, j, name.to_string())?)
+    }
+
+    /** Advances the beach clock by one tick and returns its report as JSON. */
+    pub fn tick(&mut self) -> Result<String, JsError> {
+        Ok(serde_json::to_string(&self.beach.tick())?)
+    }
+
+    /** Adds the crab with the given name to a clan; see `Beach::add_member_to_clan`. */
+    #[wasm_bindgen(js_name = addToClan)]
+    pub fn add_to_clan(&mut self, clan_id: &str, crab_name: &str) {
+        self.beach.add_member_to_clan(clan_id, crab_name);
+    }
+
+    #[wasm_bindgen(js_name = clanIds)]
+    pub fn clan_ids(&self) -> Vec<String> {
+        self.beach
+            .get_clan_system()
+            .clan_ids()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[wasm_bindgen(js_name = clanMembers)]
+    pub fn clan_members(&self, clan_id: &str) -> Vec<String> {
+        self.beach.get_clan_system().get_clan_member_names(clan_id)
+    }
+
+    #[wasm_bindgen(js_name = clanOf)]
+    pub fn clan_of(&self, crab_name: &str) -> Option<String> {
+        self.beach.get_clan_of(crab_name).map(str::to_string)
+    }
+
+    /**
+     * Returns the id of the clan that wins a competition between the two,
+     * or undefined if neither does; see `Beach::get_winner_clan`.
+     */
+    #[wasm_bindgen(js_name = winnerClan)]
+    pub fn winner_clan(&self, id1: &str, id2: &str) -> Result<Option<String>, JsError> {
+        Ok(self.beach.get_winner_clan(id1, id2)?)
+    }
+}
+
+impl WasmBeach {
+    /** Returns the beach being driven, for Rust code sharing it with JavaScript. */
+    pub fn beach(&self) -> &Beach {
+        &self.beach
+    }
+}
@@ -0,0 +1,149 @@
+use crate::beach::Beach;
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/** A condition on a beach worth being told about. */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Watch {
+    /** More than this many crabs live on the beach. */
+    PopulationAbove(usize),
+    /** Fewer than this many crabs live on the beach. */
+    PopulationBelow(usize),
+    /** The clan's resident members are slower than this on average. */
+    ClanAverageSpeedBelow { clan_id: String, speed: f64 },
+    /** The clan has at least this many members. */
+    ClanSizeAtLeast { clan_id: String, members: usize },
+}
+
+impl Watch {
+    /**
+     * Returns the watched value if the condition holds on the beach, or None
+     * if it does not.
+     */
+    pub fn check(&self, beach: &Beach) -> Option<f64> {
+        match self {
+            Watch::PopulationAbove(n) => (beach.size() > *n).then_some(beach.size() as f64),
+            Watch::PopulationBelow(n) => (beach.size() < *n).then_some(beach.size() as f64),
+            Watch::ClanAverageSpeedBelow { clan_id, speed } => beach
+                .get_crabs_avg_speed(clan_id)
+                .filter(|average| average < speed),
+            Watch::ClanSizeAtLeast { clan_id, members } => {
+                let size = beach.get_clan_system().get_clan_member_count(clan_id);
+                (size >= *members).then_some(size as f64)
+            }
+        }
+    }
+}
+
+/** A notification that a watch tripped. */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    pub name: String,
+    pub tick: u64,
+    /** The watched value when the watch tripped. */
+    pub value: f64,
+}
+
+enum Notify {
+    Callback(Box<dyn FnMut(&Alert)>),
+    Channel(Sender<Alert>),
+}
+
+struct Entry {
+    name: String,
+    watch: Watch,
+    notify: Notify,
+    tripped: bool,
+}
+
+/**
+ * The watches registered on a beach.
+ *
+ * A watch notifies once when its condition starts to hold, and again only
+ * after the condition has stopped holding in between. Beaches check their
+ * watches as crabs arrive and leave and as clan membership changes, so
+ * monitors don't have to scan the world every tick.
+ */
+#[derive(Default)]
+pub struct WatchList {
+    entries: Vec<Entry>,
+}
+
+impl WatchList {
+    pub fn new() -> WatchList {
+        WatchList {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /** Registers a watch that calls `callback` whenever it trips. */
+    pub fn add(&mut self, name: &str, watch: Watch, callback: impl FnMut(&Alert) + 'static) {
+        self.push(name, watch, Notify::Callback(Box::new(callback)));
+    }
+
+    /** Registers a watch that sends an alert down a channel whenever it trips. */
+    pub fn add_channel(&mut self, name: &str, watch: Watch) -> Receiver<Alert> {
+        let (sender, receiver) = mpsc::channel();
+        self.push(name, watch, Notify::Channel(sender));
+        receiver
+    }
+
+    fn push(&mut self, name: &str, watch: Watch, notify: Notify) {
+        self.entries.push(Entry {
+            name: name.to_string(),
+            watch,
+            notify,
+            tripped: false,
+        });
+    }
+
+    /** Removes the watches with the given name; returns whether there were any. */
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|entry| entry.name != name);
+        self.entries.len() != before
+    }
+
+    /**
+     * Checks every watch against the beach, notifying those that trip.
+     * Watches whose channel has been closed are dropped.
+     */
+    pub fn check(&mut self, beach: &Beach) {
+        self.entries.retain_mut(|entry| {
+            let value = entry.watch.check(beach);
+            let trips = value.is_some() && !entry.tripped;
+            entry.tripped = value.is_some();
+            let Some(value) = value.filter(|_| trips) else {
+                return true;
+            };
+            let alert = Alert {
+                name: entry.name.clone(),
+                tick: beach.ticks(),
+                value,
+            };
+            match &mut entry.notify {
+                Notify::Callback(callback) => {
+                    callback(&alert);
+                    true
+                }
+                Notify::Channel(sender) => sender.send(alert).is_ok(),
+            }
+        });
+    }
+}
+
+impl fmt::Debug for WatchList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WatchList")
+            .field("watches", &self.entries.len())
+            .finish()
+    }
+}
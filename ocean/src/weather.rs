@@ -0,0 +1,210 @@
+use crate::position::Position;
+use crate::territory::Territory;
+use crate::tidepool::Tide;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+
+/** The weather over a beach on a tick. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Weather {
+    Calm,
+    Rain,
+    Storm,
+}
+
+impl Weather {
+    /** Returns the chance that a breeding succeeds in this weather, from 0 to 1. */
+    pub fn breeding_chance(self) -> f64 {
+        match self {
+            Weather::Calm => 1.0,
+            Weather::Rain => 0.75,
+            Weather::Storm => 0.25,
+        }
+    }
+}
+
+/**
+ * How the weather and tide over a beach change: the tide turns every
+ * `tide_period` ticks, and on every tick it storms with chance
+ * `storm_chance`, else rains with chance `rain_chance`, and is calm
+ * otherwise.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct WeatherRules {
+    /** Ticks between turns of the tide; 0 for a tide that never turns. */
+    pub tide_period: u64,
+    pub rain_chance: f64,
+    pub storm_chance: f64,
+    /** Speed crabs lose at high tide, down to 1. */
+    pub high_tide_drag: u32,
+    /**
+     * Crabs this slow or slower at high tide, drag and all, are washed away
+     * unless protected. 0 for a tide that washes no crab away.
+     */
+    pub washout_speed: u32,
+    /** How far a storm throws each crab along either axis, at most. */
+    pub storm_scatter: u32,
+    pub seed: u64,
+}
+
+impl Default for WeatherRules {
+    fn default() -> Self {
+        WeatherRules {
+            tide_period: 10,
+            rain_chance: 0.2,
+            storm_chance: 0.05,
+            high_tide_drag: 1,
+            washout_speed: 0,
+            storm_scatter: 4,
+            seed: 0,
+        }
+    }
+}
+
+/** A tide that turns every `period` ticks, starting low. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TideCycle {
+    period: u64,
+    tide: Tide,
+}
+
+impl TideCycle {
+    pub fn new(period: u64) -> TideCycle {
+        TideCycle {
+            period,
+            tide: Tide::Low,
+        }
+    }
+
+    pub fn period(&self) -> u64 {
+        self.period
+    }
+
+    pub fn tide(&self) -> Tide {
+        self.tide
+    }
+
+    /** Advances the cycle to tick `now`, returning whether the tide turned. */
+    pub fn advance(&mut self, now: u64) -> bool {
+        let turns = self.period > 0 && now.is_multiple_of(self.period);
+        if turns {
+            self.tide = self.tide.turned();
+        }
+        turns
+    }
+}
+
+/** The weather and tide over a beach, which change on every tick. */
+#[derive(Debug, Clone)]
+pub struct WeatherSystem {
+    rules: WeatherRules,
+    rng: Pcg64,
+    cycle: TideCycle,
+    weather: Weather,
+    forecast: Option<Weather>,
+}
+
+impl WeatherSystem {
+    /** Starts calm weather at low tide. */
+    pub fn new(rules: WeatherRules) -> WeatherSystem {
+        WeatherSystem {
+            rules,
+            rng: Pcg64::seed_from_u64(rules.seed),
+            cycle: TideCycle::new(rules.tide_period),
+            weather: Weather::Calm,
+            forecast: None,
+        }
+    }
+
+    pub fn rules(&self) -> WeatherRules {
+        self.rules
+    }
+
+    pub fn weather(&self) -> Weather {
+        self.weather
+    }
+
+    pub fn tide(&self) -> Tide {
+        self.cycle.tide()
+    }
+
+    /** Moves the weather and tide on to tick `now`. */
+    pub fn advance(&mut self, now: u64) {
+        self.cycle.advance(now);
+        let roll: f64 = self.rng.gen();
+        self.weather = if roll < self.rules.storm_chance {
+            Weather::Storm
+        } else if roll < self.rules.storm_chance + self.rules.rain_chance {
+            Weather::Rain
+        } else {
+            Weather::Calm
+        };
+        if let Some(weather) = self.forecast.take() {
+            self.weather = weather;
+        }
+    }
+
+    /**
+     * Makes the weather on the next tick `weather`, whatever the rules
+     * would have it be. The random weather of later ticks is unchanged.
+     */
+    pub fn forecast(&mut self, weather: Weather) {
+        self.forecast = Some(weather);
+    }
+
+    /** Returns how fast a crab of the given speed runs in this tide. */
+    pub fn drag(&self, speed: u32) -> u32 {
+        match self.tide() {
+            Tide::High => speed.saturating_sub(self.rules.high_tide_drag).max(1),
+            Tide::Low => speed,
+        }
+    }
+
+    /** Returns whether a crab running at `speed` is washed away now. */
+    pub fn washes_away(&self, speed: u32) -> bool {
+        self.tide() == Tide::High && speed <= self.rules.washout_speed
+    }
+
+    /** Decides at random whether a breeding succeeds in this weather. */
+    pub fn breeding_succeeds(&mut self) -> bool {
+        self.rng
+            .gen_bool(self.weather.breeding_chance().clamp(0.0, 1.0))
+    }
+
+    /**
+     * Returns where a storm throws a crab standing at `position`, kept on
+     * the territory if there is one.
+     */
+    pub fn scatter(&mut self, position: Position, territory: Option<&Territory>) -> Position {
+        scatter(position, self.rules.storm_scatter, territory, &mut self.rng)
+    }
+}
+
+/**
+ * Returns where a crab standing at `position` lands when thrown up to
+ * `reach` positions along either axis at random, kept on the territory if
+ * there is one.
+ */
+pub fn scatter<R: Rng + ?Sized>(
+    position: Position,
+    reach: u32,
+    territory: Option<&Territory>,
+    rng: &mut R,
+) -> Position {
+    let reach = reach as i64;
+    let mut throw = |at: u32, size: Option<u32>| {
+        let to = at as i64 + rng.gen_range(-reach..=reach);
+        let last = size.map_or(u32::MAX, |size| size.saturating_sub(1)) as i64;
+        to.clamp(0, last) as u32
+    };
+    Position::new(
+        throw(position.x, territory.map(|t| t.width)),
+        throw(position.y, territory.map(|t| t.height)),
+    )
+}
@@ -1,18 +1,103 @@
+#![allow(
+    deprecated,
+    clippy::bool_assert_comparison,
+    clippy::assertions_on_constants
+)]
+
 extern crate ocean;
 
 /*
  * Run `cargo test public` to run all of the provided tests.
  */
 
+use ocean::achievements::*;
+use ocean::analysis::*;
+use ocean::audit::*;
+use ocean::batch::*;
+use ocean::battle::*;
 use ocean::beach::*;
+#[cfg(feature = "binary")]
+use ocean::binary::*;
+use ocean::breeding::*;
+use ocean::builder::*;
+use ocean::capacity::*;
+use ocean::clans::*;
 use ocean::color::*;
+use ocean::command::*;
+use ocean::competition::*;
+use ocean::constraints::*;
 use ocean::cookbook::*;
 use ocean::crab::*;
 use ocean::diet::*;
+use ocean::disaster::*;
+use ocean::distribution::*;
+use ocean::economy::*;
+use ocean::encounter::*;
+use ocean::environment::*;
+use ocean::error::*;
+use ocean::event::*;
+use ocean::food::*;
+#[cfg(feature = "arrow")]
+use ocean::frame::*;
+use ocean::genealogy::*;
+use ocean::genetics::*;
+use ocean::graph::*;
+use ocean::heatmap::*;
+use ocean::i18n::*;
+use ocean::incubation::*;
+use ocean::inhabitant::*;
+use ocean::interference::*;
+use ocean::intern::*;
+use ocean::invariants::*;
+use ocean::leaderboard::*;
+use ocean::life::*;
+use ocean::lockstep::*;
+use ocean::merge::*;
+use ocean::movement::*;
+use ocean::names::*;
+use ocean::observe::*;
+#[cfg(feature = "parallel")]
+use ocean::parallel::*;
 use ocean::ocean::*;
+use ocean::pollution::*;
+use ocean::position::*;
+use ocean::predator::*;
 use ocean::prey::*;
+use ocean::protection::*;
+use ocean::query::*;
+use ocean::race::*;
+use ocean::raid::*;
 use ocean::reef::*;
+use ocean::report::*;
+#[cfg(feature = "cli")]
+use ocean::repl::*;
+use ocean::rules::*;
+use ocean::scenario::*;
+use ocean::scheduler::*;
+#[cfg(feature = "toml")]
+use ocean::script::*;
+use ocean::server::*;
+use ocean::shared::*;
+use ocean::simulation::*;
+use ocean::speed::*;
+use ocean::spec::*;
+#[cfg(feature = "async")]
+use ocean::stream::*;
+use ocean::survey::*;
+use ocean::svg::*;
+use ocean::terminal::*;
+use ocean::territory::*;
+use ocean::tidepool::*;
+use ocean::timeline::*;
+use ocean::tournament::*;
+use ocean::trade::*;
+#[cfg(feature = "wasm")]
+use ocean::wasm::*;
+use ocean::watch::*;
+use ocean::weather::*;
 
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
 use std::cell::RefCell;
 use std::rc::Rc;
 
@@ -560,3 +645,6232 @@ fn part3_beach_get_winner_clan() {
     assert_eq!(beach.get_winner_clan(CLAN_2, CLAN_3), Ok(Some(CLAN_3.to_string())));
     assert_eq!(beach.get_winner_clan(CLAN_1, CLAN_3), Ok(Some(CLAN_3.to_string())));
 }
+
+#[test]
+fn part4_battle_stronger_crab_wins() {
+    let mut beach = Beach::new();
+    let mut brawler = new_crab(CRAB_1, 10);
+    brawler.set_claw_size(5);
+    beach.add_crab(brawler);
+    // Slower than the attacker, so it is cornered and cannot retreat.
+    beach.add_crab(new_crab(CRAB_2, 5));
+
+    let report = beach.battle_crabs(0, 1);
+    assert_eq!(report.resolution, Resolution::Victory(Side::Attacker));
+    assert_eq!(report.winner(), Some(Side::Attacker));
+    assert_eq!(beach.get_crab(1).health(), 0);
+    assert_eq!(report.defender_injury, MAX_HEALTH);
+    assert_eq!(
+        beach.get_crab(0).health(),
+        MAX_HEALTH - report.attacker_injury
+    );
+}
+
+#[test]
+fn part4_battle_faster_wounded_crab_retreats() {
+    let mut attacker = new_crab(CRAB_1, 10);
+    attacker.set_claw_size(4);
+    let mut defender = new_crab(CRAB_2, 40);
+
+    let report = ocean::battle::fight(&mut attacker, &mut defender, &BattleRules::default());
+    assert_eq!(report.resolution, Resolution::Retreat(Side::Defender));
+    assert_eq!(report.winner(), Some(Side::Attacker));
+    assert!(defender.health() > 0);
+    assert!(defender.health() <= BattleRules::default().retreat_threshold);
+}
+
+#[test]
+fn part4_battle_stalemate_and_knocked_out() {
+    let mut c1 = new_crab(CRAB_1, 10);
+    let mut c2 = new_crab(CRAB_2, 10);
+    let rules = BattleRules {
+        max_rounds: 2,
+        retreat_threshold: 0,
+    };
+
+    let report = ocean::battle::fight(&mut c1, &mut c2, &rules);
+    assert_eq!(report.resolution, Resolution::Stalemate);
+    assert_eq!(report.rounds, 2);
+    assert_eq!(report.winner(), None);
+
+    // A crab with no health left cannot fight back.
+    c2.injure(MAX_HEALTH);
+    let report = ocean::battle::fight(&mut c1, &mut c2, &rules);
+    assert_eq!(report.resolution, Resolution::Victory(Side::Attacker));
+    assert_eq!(report.rounds, 0);
+}
+
+#[test]
+fn part4_tournament_round_robin_meets_everyone_once() {
+    let names = [CRAB_1, CRAB_2, CRAB_3, CRAB_4, CRAB_5];
+    let mut tournament = Tournament::new(Format::RoundRobin, &names);
+    assert_eq!(tournament.total_days(), 5);
+
+    let mut meetings = std::collections::HashSet::new();
+    let mut byes = 0;
+    while !tournament.is_finished() {
+        for pairing in tournament.play_day(|_, _| std::cmp::Ordering::Equal) {
+            match pairing.away {
+                Some(away) => {
+                    let mut pair = [pairing.home.clone(), away];
+                    pair.sort();
+                    assert!(meetings.insert(pair));
+                }
+                None => byes += 1,
+            }
+        }
+    }
+    assert_eq!(meetings.len(), 10);
+    assert_eq!(byes, 5);
+    assert!(tournament.next_pairings().is_empty());
+    for standing in tournament.table() {
+        assert_eq!(standing.played, 4);
+        assert_eq!(standing.points(), 4 * POINTS_FOR_DRAW);
+    }
+}
+
+#[test]
+fn part4_tournament_crab_standings() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 20));
+    beach.add_crab(new_crab(CRAB_3, 30));
+    beach.add_crab(new_crab(CRAB_4, 40));
+
+    let mut tournament = Tournament::new(Format::RoundRobin, &[CRAB_1, CRAB_2, CRAB_3, CRAB_4]);
+    while !tournament.is_finished() {
+        tournament.play_crab_day(&beach);
+    }
+    assert_eq!(tournament.day(), 3);
+
+    let table: Vec<&str> = tournament.table().iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(table, vec![CRAB_4, CRAB_3, CRAB_2, CRAB_1]);
+    assert_eq!(tournament.standing(CRAB_4).unwrap().wins, 3);
+    assert_eq!(tournament.standing(CRAB_1).unwrap().losses, 3);
+}
+
+#[test]
+fn part4_tournament_swiss_clans() {
+    let mut beach = Beach::new();
+    let clans = [CLAN_1, CLAN_2, CLAN_3, "Claw Crew"];
+    for (i, clan) in clans.iter().enumerate() {
+        let name = format!("Crab {}", i);
+        beach.add_crab(new_crab(&name, 10 * (i as u32 + 1)));
+        beach.add_member_to_clan(clan, &name);
+    }
+
+    let mut tournament = Tournament::new(Format::Swiss { days: 2 }, &clans);
+    let first_day = tournament.play_clan_day(&beach);
+    assert_eq!(first_day.len(), 2);
+
+    // Winners meet winners on the second day, and nobody plays a rematch.
+    let second_day = tournament.next_pairings();
+    let winners: Vec<&str> = tournament.table()[..2].iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(second_day[0].home, winners[0]);
+    assert_eq!(second_day[0].away.as_deref(), Some(winners[1]));
+    for pairing in &second_day {
+        assert!(!first_day.contains(pairing));
+    }
+
+    tournament.play_clan_day(&beach);
+    assert!(tournament.is_finished());
+    assert_eq!(tournament.table()[0].id, "Claw Crew");
+    assert_eq!(tournament.table()[0].points(), 2 * POINTS_FOR_WIN);
+}
+
+#[test]
+fn part4_tournament_swiss_gives_one_bye_each() {
+    let mut tournament = Tournament::new(Format::Swiss { days: 3 }, &[CLAN_1, CLAN_2, CLAN_3]);
+    while !tournament.is_finished() {
+        tournament.play_day(|home, away| home.cmp(away));
+    }
+    for standing in tournament.table() {
+        assert_eq!(standing.byes, 1);
+    }
+}
+
+#[test]
+fn part4_achievements_first_clan_to_size() {
+    let mut beach = Beach::new();
+    let tracker = Rc::new(RefCell::new(AchievementTracker::with_milestones(&[
+        Milestone::ClanSize(2),
+        Milestone::Population(3),
+    ])));
+    beach.subscribe(tracker.clone());
+
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 10));
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    beach.add_member_to_clan(CLAN_2, CRAB_2);
+    assert!(tracker.borrow().unlocked().is_empty());
+    let population = tracker.borrow().get(Milestone::Population(3)).unwrap().clone();
+    assert_eq!(population.progress, 2);
+
+    beach.add_crab(new_crab(CRAB_3, 10));
+    beach.add_member_to_clan(CLAN_2, CRAB_3);
+    beach.add_crab(new_crab(CRAB_4, 10));
+    beach.add_member_to_clan(CLAN_1, CRAB_4);
+
+    let tracker = tracker.borrow();
+    let clan = tracker.get(Milestone::ClanSize(2)).unwrap();
+    assert!(clan.is_unlocked());
+    assert_eq!(clan.unlocked_by.as_deref(), Some(CLAN_2));
+    assert_eq!(tracker.unlocked().len(), 2);
+}
+
+#[test]
+fn part4_achievements_crab_survives_ticks() {
+    let mut beach = Beach::new();
+    let tracker = Rc::new(RefCell::new(AchievementTracker::new()));
+    beach.subscribe(tracker.clone());
+
+    beach.add_crab(new_sebastian());
+    for _ in 0..50 {
+        beach.tick();
+    }
+    beach.add_crab(new_prinz());
+    let survivor = tracker.borrow().get(Milestone::CrabSurvives(100)).unwrap().clone();
+    assert_eq!(survivor.progress, 50);
+    assert!((survivor.fraction() - 0.5).abs() < 1e-9);
+
+    for _ in 0..50 {
+        beach.tick();
+    }
+    let survivor = tracker.borrow().get(Milestone::CrabSurvives(100)).unwrap().clone();
+    assert_eq!(survivor.unlocked_by.as_deref(), Some(SEBASTIAN));
+    assert_eq!(survivor.unlocked_at, Some(100));
+    assert_eq!(beach.ticks(), 100);
+}
+
+#[test]
+fn part4_event_bus_delivers_beach_events() {
+    struct Recorder(Vec<BeachEvent>);
+    impl EventListener for Recorder {
+        fn on_event(&mut self, event: &BeachEvent) {
+            self.0.push(event.clone());
+        }
+    }
+
+    let mut beach = Beach::new();
+    let recorder = Rc::new(RefCell::new(Recorder(Vec::new())));
+    beach.subscribe(recorder.clone());
+    beach.add_crab(new_prinz());
+    beach.add_crab(new_sebastian());
+    beach.breed_crabs(0, 1, String::from(CRAB_1));
+    beach.tick();
+
+    let events = &recorder.borrow().0;
+    assert_eq!(events.len(), 5);
+    assert_eq!(
+        events[3],
+        BeachEvent::CrabBred {
+            parent1: String::from(PRINZ),
+            parent2: String::from(SEBASTIAN),
+            child: String::from(CRAB_1),
+        }
+    );
+    assert_eq!(events[4], BeachEvent::Tick { tick: 1 });
+}
+
+#[test]
+fn part4_economy_foraging_and_dues() {
+    let mut ocean = Ocean::new();
+    let reef = ocean.generate_reef(0, 0, 2, 0);
+    let mut beach = Beach::new();
+    let mut prinz = new_prinz();
+    prinz.discover_reef(Rc::clone(&reef));
+    beach.add_crab(prinz);
+    beach.add_crab(new_sebastian());
+    beach.add_member_to_clan(CLAN_1, PRINZ);
+
+    assert!(beach.forage(0));
+    assert!(beach.forage(0));
+    assert!(!beach.forage(0));
+    let prinz = Account::crab(PRINZ);
+    assert_eq!(beach.economy().balance(&prinz), 2 * FORAGING_REWARD);
+
+    assert!(beach.pay_clan_dues(CLAN_1, PRINZ, 3).is_ok());
+    assert_eq!(
+        beach.pay_clan_dues(CLAN_1, PRINZ, 3),
+        Err(OceanError::InsufficientShells {
+            account: prinz.clone(),
+            balance: 2 * FORAGING_REWARD - 3,
+            amount: 3,
+        })
+    );
+    assert_eq!(
+        beach.pay_clan_dues(CLAN_1, SEBASTIAN, 0),
+        Err(OceanError::NotClanMember {
+            crab: SEBASTIAN.to_string(),
+            clan: CLAN_1.to_string(),
+        })
+    );
+    assert_eq!(beach.economy().balance(&Account::clan(CLAN_1)), 3);
+    assert_eq!(beach.economy().balance(&prinz), 2 * FORAGING_REWARD - 3);
+
+    let log = beach.economy().transactions_for(&prinz);
+    assert_eq!(log.len(), 3);
+    assert_eq!(
+        log[2].kind,
+        TransactionKind::Dues {
+            crab: String::from(PRINZ),
+            clan: String::from(CLAN_1),
+            amount: 3,
+        }
+    );
+}
+
+#[test]
+fn part4_economy_trade_items() {
+    let mut economy = Economy::new();
+    let seller = Account::crab(CRAB_1);
+    let buyer = Account::clan(CLAN_1);
+    economy
+        .pay_race_prizes(&[CRAB_2, CRAB_3], &[10, 5, 1])
+        .unwrap();
+    economy.transfer(&Account::crab(CRAB_2), &buyer, 8).unwrap();
+    economy.grant_items(seller.clone(), "pearl", 3);
+
+    // The buyer cannot afford this, and nothing changes.
+    assert!(economy.trade(&seller, &buyer, "pearl", 2, 9).is_err());
+    // The seller does not have this many pearls.
+    assert!(economy.trade(&seller, &buyer, "pearl", 4, 1).is_err());
+    assert!(economy.trade(&seller, &seller, "pearl", 1, 0).is_err());
+    assert_eq!(economy.transactions().len(), 4);
+
+    economy.trade(&seller, &buyer, "pearl", 2, 6).unwrap();
+    assert_eq!(economy.item_count(&seller, "pearl"), 1);
+    assert_eq!(economy.item_count(&buyer, "pearl"), 2);
+    assert_eq!(economy.balance(&seller), 6);
+    assert_eq!(economy.balance(&buyer), 2);
+    assert_eq!(economy.total_shells(), 15);
+    assert_eq!(economy.transactions().last().unwrap().id, 4);
+}
+
+#[test]
+fn part4_names_seeded_and_distinct() {
+    let first: Vec<String> = NameGenerator::new(7).take(500).collect();
+    let again: Vec<String> = NameGenerator::new(7).take(500).collect();
+    assert_eq!(first, again);
+
+    let distinct: std::collections::HashSet<&String> = first.iter().collect();
+    assert_eq!(distinct.len(), first.len());
+    assert!(first
+        .iter()
+        .all(|name| name.chars().next().unwrap().is_uppercase()));
+
+    // There are few themed combinations, so numerals keep them distinct.
+    let themed: Vec<String> = NameGenerator::with_style(7, NameStyle::Themed)
+        .take(500)
+        .collect();
+    let distinct: std::collections::HashSet<&String> = themed.iter().collect();
+    assert_eq!(distinct.len(), themed.len());
+    assert!(themed.iter().any(|name| name.ends_with(" II")));
+}
+
+#[test]
+fn part4_beach_populate_random_and_hatch() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_prinz());
+    beach.populate_random(200, 42);
+    assert_eq!(beach.size(), 201);
+
+    let mut other = Beach::new();
+    other.add_crab(new_prinz());
+    other.populate_random(200, 42);
+    for (a, b) in beach.crabs().zip(other.crabs()) {
+        assert_eq!(a.name(), b.name());
+        assert_eq!(a.speed(), b.speed());
+        assert_eq!(a.color(), b.color());
+    }
+
+    let mut names = NameGenerator::new(1);
+    let hatchling = beach.hatch(0, 1, &mut names);
+    assert_eq!(beach.size(), 202);
+    assert_eq!(beach.get_crab(201).name(), hatchling);
+    for crab in beach.crabs() {
+        assert_eq!(beach.find_crabs_by_name(crab.name()).len(), 1);
+    }
+}
+
+#[test]
+fn part4_i18n_localized_errors() {
+    let beach = Beach::new();
+    assert_eq!(locale(), Locale::English);
+    assert_eq!(
+        beach.get_winner_clan(CLAN_1, CLAN_2).map_err(|e| e.to_string()),
+        Err(format!("no such clan: {}", CLAN_1))
+    );
+
+    set_locale(Locale::from_code("es-MX").unwrap());
+    assert_eq!(
+        beach.get_winner_clan(CLAN_1, CLAN_1).map_err(|e| e.to_string()),
+        Err(format!("un clan no puede competir contra sí mismo: {}", CLAN_1))
+    );
+    let mut economy = Economy::new();
+    let err = economy
+        .transfer(&Account::crab(PRINZ), &Account::clan(CLAN_1), 5)
+        .unwrap_err();
+    assert_eq!(err, "cangrejo Prinz tiene 0 conchas pero necesita 5");
+
+    set_locale(Locale::French);
+    assert_eq!(Account::clan(CLAN_2).to_string(), "clan Reef Raiders");
+    set_locale(Locale::English);
+}
+
+#[test]
+fn part4_i18n_catalogs_are_complete() {
+    assert_eq!(Locale::from_code("de"), None);
+    let args: [&dyn std::fmt::Display; 4] = [&"a", &"b", &"c", &"d"];
+    for &locale in Locale::all() {
+        assert_eq!(Locale::from_code(locale.code()), Some(locale));
+        let rendered = tr_in(locale, Message::InsufficientItems, &args);
+        assert!(!rendered.contains('{'));
+        assert!(rendered.contains('d'));
+    }
+}
+
+fn run_recorded_simulation() -> Simulation {
+    let mut simulation = Simulation::new(3);
+    simulation.apply(Operation::AddCrab {
+        name: String::from(PRINZ),
+        speed: 20,
+        color: (255, 215, 0),
+        diet: Diet::Shellfish,
+    });
+    simulation.apply(Operation::PopulateRandom { n: 5, seed: 9 });
+    for k in 0..20 {
+        simulation.apply(Operation::BreedCrabs {
+            i: 0,
+            j: k % 5 + 1,
+            name: format!("Crab {}", k),
+        });
+    }
+    simulation.apply(Operation::JoinClan {
+        clan_id: String::from(CLAN_1),
+        crab_name: String::from(PRINZ),
+    });
+    simulation.apply(Operation::BattleCrabs { i: 0, j: 1 });
+    simulation.apply(Operation::Tick);
+    simulation
+}
+
+#[test]
+fn part4_simulation_replay_reproduces_run() {
+    let original = run_recorded_simulation();
+    assert_eq!(original.operations().len(), 25);
+
+    let mut recording = Vec::new();
+    original.write_replay(&mut recording).unwrap();
+    let replayed = Simulation::replay_from(recording.as_slice()).unwrap();
+
+    assert_eq!(replayed.seed(), 3);
+    assert_eq!(replayed.operations(), original.operations());
+    assert_eq!(replayed.beach().size(), original.beach().size());
+    assert_eq!(replayed.beach().ticks(), 1);
+    for (a, b) in replayed.beach().crabs().zip(original.beach().crabs()) {
+        assert_eq!(a.name(), b.name());
+        assert_eq!(a.diet(), b.diet());
+        assert_eq!(a.color(), b.color());
+        assert_eq!(a.health(), b.health());
+    }
+    assert_eq!(
+        replayed.beach().get_clan_system().get_clan_member_names(CLAN_1),
+        vec![String::from(PRINZ)]
+    );
+}
+
+#[test]
+fn part4_simulation_replay_file_and_errors() {
+    let path = std::env::temp_dir().join(format!("ocean-replay-{}.txt", std::process::id()));
+    let original = run_recorded_simulation();
+    original.save_replay(&path).unwrap();
+    let replayed = Simulation::replay(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(replayed.operations(), original.operations());
+
+    assert!(Simulation::replay_from("".as_bytes()).is_err());
+    assert!(Simulation::replay_from("seed\t1\nfly\t2".as_bytes()).is_err());
+    assert!(Simulation::replay_from("seed\t1\nadd\tX\t1\t1,2\tFish".as_bytes()).is_err());
+}
+
+#[test]
+fn part4_timeline_at_tick() {
+    let mut simulation = Simulation::new(11);
+    simulation.apply(Operation::AddCrab {
+        name: String::from(PRINZ),
+        speed: 20,
+        color: (255, 215, 0),
+        diet: Diet::Shellfish,
+    });
+    simulation.apply(Operation::AddCrab {
+        name: String::from(SEBASTIAN),
+        speed: 30,
+        color: (255, 0, 0),
+        diet: Diet::Plants,
+    });
+    simulation.enable_timeline(10);
+
+    // The population at the end of each tick, i.e. right before the next one.
+    let mut populations = Vec::new();
+    for k in 0..45 {
+        simulation.apply(Operation::BreedCrabs {
+            i: 0,
+            j: 1,
+            name: format!("Crab {}", k),
+        });
+        if k == 20 {
+            simulation.apply(Operation::BattleCrabs { i: 0, j: 1 });
+        }
+        populations.push(simulation.beach().size());
+        simulation.apply(Operation::Tick);
+    }
+    populations.push(simulation.beach().size());
+    simulation.apply(Operation::JoinClan {
+        clan_id: String::from(CLAN_1),
+        crab_name: String::from(PRINZ),
+    });
+
+    assert_eq!(simulation.timeline().unwrap().snapshot_count(), 5);
+    assert!(simulation.at_tick(46).is_none());
+    for (tick, &population) in populations.iter().enumerate() {
+        let state = simulation.at_tick(tick as u64).unwrap();
+        assert_eq!(state.tick(), tick as u64);
+        assert_eq!(state.population(), population);
+    }
+
+    // Prinz was injured during tick 20, and joined a clan during tick 45.
+    assert_eq!(simulation.at_tick(19).unwrap().crab(0).unwrap().health, MAX_HEALTH);
+    assert!(simulation.at_tick(20).unwrap().crab(0).unwrap().health < MAX_HEALTH);
+    let now = simulation.at_tick(45).unwrap();
+    assert_eq!(now.crab(0).unwrap().health, simulation.beach().get_crab(0).health());
+    assert!(now.crab(0).unwrap().health < MAX_HEALTH);
+    assert_eq!(now.clan_members(CLAN_1), [String::from(PRINZ)]);
+    assert!(simulation.at_tick(44).unwrap().clan_members(CLAN_1).is_empty());
+
+    // The reconstructed state matches a fresh capture of the beach.
+    assert_eq!(now, WorldState::capture(simulation.beach()));
+}
+
+#[test]
+fn part4_svg_render_beach() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_prinz());
+    beach.add_crab(new_sebastian());
+    beach.add_crab(new_crab("<Crab & Co>", 5));
+    beach.add_member_to_clan(CLAN_1, PRINZ);
+    beach.add_member_to_clan(CLAN_2, SEBASTIAN);
+
+    let svg = render_svg(&beach);
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.trim_end().ends_with("</svg>"));
+    assert_eq!(svg.matches(r#"class="crab""#).count(), 3);
+    assert_eq!(svg.matches(r#"class="territory""#).count(), 2);
+    assert_eq!(svg.matches(r#"class="legend""#).count(), 3);
+    assert!(svg.contains("rgb(255,215,0)"));
+    assert!(svg.contains("Pincher Patrol (1)"));
+    assert!(svg.contains("no clan (1)"));
+    assert!(svg.contains("&lt;Crab &amp; Co&gt;"));
+    assert!(!svg.contains("<Crab"));
+
+    let empty = render_svg(&Beach::new());
+    assert_eq!(empty.matches("<circle").count(), 0);
+}
+
+#[test]
+fn part4_terminal_render_grid() {
+    let mut beach = Beach::new();
+    for _ in 0..3 {
+        beach.add_crab(new_prinz());
+        beach.add_crab(new_sebastian());
+    }
+    let renderer = TerminalRenderer::new(4).without_color();
+    assert_eq!(
+        renderer.render(&beach),
+        "+----+\n|SPSP|\n|SP  |\n+----+\ntick 0 | 6 crabs | 0 clans\n"
+    );
+
+    let colored = TerminalRenderer::new(4).render(&beach);
+    assert!(colored.contains("\x1b[38;2;255;215;0mS\x1b[0m"));
+
+    let empty = TerminalRenderer::new(2).without_color().render(&Beach::new());
+    assert_eq!(empty, "+--+\n|  |\n+--+\ntick 0 | 0 crabs | 0 clans\n");
+}
+
+#[test]
+fn part4_terminal_watch_redraws_in_place() {
+    let mut simulation = Simulation::new(0);
+    simulation.apply(Operation::PopulateRandom { n: 3, seed: 1 });
+    let mut renderer = TerminalRenderer::new(10).without_color();
+    let mut out = Vec::new();
+    renderer
+        .watch(&mut out, &mut simulation, 2, std::time::Duration::ZERO)
+        .unwrap();
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(out.matches("\x1b[4A\x1b[J").count(), 2);
+    assert!(out.ends_with("tick 2 | 3 crabs | 0 clans\n"));
+    assert_eq!(simulation.beach().ticks(), 2);
+}
+
+#[test]
+fn part4_heatmap_density_and_speed() {
+    let mut beach = Beach::new();
+    let spots = [(0, 0, 10), (3, 4, 20), (9, 9, 30), (5, 0, 40), (50, 50, 99)];
+    for (i, &(x, y, speed)) in spots.iter().enumerate() {
+        beach.add_crab(new_crab(&format!("Crab {}", i), speed));
+        beach.place_crab(i, Position::new(x, y));
+    }
+
+    let density = Heatmap::capture(&beach, HeatmapMetric::Density, 10, 10, 5);
+    assert_eq!((density.columns(), density.rows()), (2, 2));
+    assert_eq!(density.value(0, 0), 2.0);
+    assert_eq!(density.value(1, 0), 1.0);
+    assert_eq!(density.value(0, 1), 0.0);
+    assert_eq!(density.value(1, 1), 1.0);
+    assert_eq!(density.max_value(), 2.0);
+
+    let speed = Heatmap::capture(&beach, HeatmapMetric::MeanSpeed, 10, 10, 5);
+    assert_eq!(speed.value(0, 0), 15.0);
+    assert_eq!(speed.value(0, 1), 0.0);
+    let colors = speed.to_rgb();
+    assert_eq!(colors.len(), 4);
+    assert_eq!(colors[1], [250, 230, 40]);
+    assert_eq!(colors[2], [20, 20, 90]);
+}
+
+#[cfg(feature = "png")]
+#[test]
+fn part4_heatmap_save_png() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_prinz());
+    let heatmap = Heatmap::capture(&beach, HeatmapMetric::Density, 4, 4, 2);
+    let path = std::env::temp_dir().join(format!("ocean-heatmap-{}.png", std::process::id()));
+    heatmap.save_png(&path, 3).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(&bytes[1..4], b"PNG");
+}
+
+#[test]
+fn part4_clans_relations_dot() {
+    let mut beach = Beach::new();
+    for (name, clan) in [(CRAB_1, CLAN_1), (CRAB_2, CLAN_1), (CRAB_3, CLAN_2), (CRAB_4, CLAN_3)] {
+        beach.add_crab(new_crab(name, 10));
+        beach.add_member_to_clan(clan, name);
+    }
+    assert!(beach.set_clan_relation(CLAN_2, CLAN_1, Relation::Alliance).is_ok());
+    assert!(beach.set_clan_relation(CLAN_1, CLAN_3, Relation::Rivalry).is_ok());
+    assert!(beach.set_clan_relation(CLAN_1, CLAN_1, Relation::Rivalry).is_err());
+    assert!(beach.set_clan_relation(CLAN_1, "Nobody", Relation::Rivalry).is_err());
+
+    let clans = beach.get_clan_system();
+    assert_eq!(clans.get_relation(CLAN_1, CLAN_2), Some(Relation::Alliance));
+    assert_eq!(clans.get_relation(CLAN_2, CLAN_3), None);
+    assert_eq!(clans.relations().len(), 2);
+
+    let dot = clans.relations_dot();
+    assert!(dot.starts_with("graph clans {"));
+    assert!(dot.contains("\"Pincher Patrol\" [label=\"Pincher Patrol (2)\", width=2.00];"));
+    assert!(dot.contains("\"Reef Raiders\" [label=\"Reef Raiders (1)\", width=1.00];"));
+    assert!(dot.contains("\"Pincher Patrol\" -- \"Reef Raiders\" [style=solid"));
+    assert!(dot.contains("\"Pincher Patrol\" -- \"Shell Shockers\" [style=dashed"));
+    assert!(dot.trim_end().ends_with('}'));
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-9,
+        "expected {}, got {}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn part4_analysis_summary_statistics() {
+    let values = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+    let summary = summarize(&values).unwrap();
+    assert_eq!(summary.count, 8);
+    assert_close(summary.mean, 5.0);
+    assert_close(summary.std_dev, 2.0);
+    assert_close(summary.min, 2.0);
+    assert_close(summary.max, 9.0);
+    assert_close(summary.median, 4.5);
+    assert_close(percentile(&values, 25.0).unwrap(), 4.0);
+    assert_close(percentile(&[1.0, 2.0], 75.0).unwrap(), 1.75);
+    assert!(summarize(&[]).is_none());
+
+    assert_close(correlation(&[1.0, 2.0, 3.0], &[2.0, 4.0, 6.0]).unwrap(), 1.0);
+    assert_close(correlation(&[1.0, 2.0, 3.0], &[3.0, 2.0, 1.0]).unwrap(), -1.0);
+    assert!(correlation(&[1.0, 1.0], &[1.0, 2.0]).is_none());
+}
+
+#[test]
+fn part4_analysis_over_beach_and_clans() {
+    let mut beach = Beach::new();
+    for (k, name) in [CRAB_1, CRAB_2, CRAB_3, CRAB_4].iter().enumerate() {
+        let mut crab = new_crab(name, 10 * (k as u32 + 1));
+        crab.set_claw_size(k as u32 + 1);
+        beach.add_crab(crab);
+    }
+    let speed = describe(&beach, Trait::Speed).unwrap();
+    assert_close(speed.mean, 25.0);
+    assert_close(trait_correlation(&beach, Trait::Speed, Trait::ClawSize).unwrap(), 1.0);
+
+    // Fast crabs in one clan, slow ones in another: membership explains most of the variance.
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    beach.add_member_to_clan(CLAN_1, CRAB_2);
+    beach.add_member_to_clan(CLAN_2, CRAB_3);
+    beach.add_member_to_clan(CLAN_2, CRAB_4);
+    assert_close(clan_correlation(&beach, Trait::Speed).unwrap(), 0.8);
+    assert_eq!(values_by_clan(&beach, Trait::Speed)[CLAN_2], vec![30.0, 40.0]);
+
+    // All crabs are blue, so hue does not vary at all.
+    assert!(clan_correlation(&beach, Trait::Hue).is_none());
+    assert_close(Color::new_blue().hue(), 240.0);
+    assert_close(Color::new(255, 0, 255).hue(), 300.0);
+
+    let state = WorldState::capture(&beach);
+    assert_eq!(describe_state(&state, Trait::Speed), Some(speed));
+}
+
+#[test]
+fn part4_report_markdown() {
+    let simulation = run_recorded_simulation();
+    let history = simulation.history();
+    assert_eq!(history.ticks.len(), 2);
+    assert_eq!(history.ticks[1].population, 26);
+    assert_eq!(
+        history.ticks[1].clans,
+        vec![(String::from(CLAN_1), 1)]
+    );
+    assert_eq!(
+        history.events[0],
+        (
+            0,
+            NotableEvent::ClanFounded {
+                clan_id: String::from(CLAN_1),
+                founder: String::from(PRINZ),
+            }
+        )
+    );
+
+    let report = simulation.report(ReportFormat::Markdown);
+    assert!(report.starts_with("# "));
+    assert!(report.contains("| 1 | 26 |"));
+    assert!(report.contains(&format!("| 1 | {} | 1 |", CLAN_1)));
+    assert!(report.contains(&format!("{} (1)", CLAN_1)));
+    assert!(report.contains(PRINZ));
+}
+
+#[test]
+fn part4_report_html() {
+    let mut simulation = Simulation::new(1);
+    let empty = simulation.report(ReportFormat::Html);
+    assert!(empty.starts_with("<!DOCTYPE html>"));
+    assert!(!empty.contains("<li>"));
+
+    simulation.apply(Operation::AddCrab {
+        name: String::from("<Crab & Co>"),
+        speed: 3,
+        color: (0, 0, 0),
+        diet: Diet::Plants,
+    });
+    simulation.apply(Operation::JoinClan {
+        clan_id: String::from(CLAN_2),
+        crab_name: String::from("<Crab & Co>"),
+    });
+    let report = simulation.report(ReportFormat::Html);
+    assert!(report.contains("&lt;Crab &amp; Co&gt;"));
+    assert!(!report.contains("<Crab"));
+    assert!(report.contains("<li>"));
+    assert!(report.ends_with("</html>\n"));
+}
+
+#[test]
+fn part4_query_select_where_order_limit() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 5));
+    beach.add_crab(new_crab(CRAB_2, 30));
+    beach.add_crab(new_crab(CRAB_3, 20));
+    beach.add_crab(new_crab(CRAB_4, 40));
+    beach.add_crab(Crab::new(
+        String::from(CRAB_5),
+        50,
+        Color::new_blue(),
+        Diet::Fish,
+    ));
+
+    let result = beach
+        .query("SELECT name, speed FROM crabs WHERE diet = 'plants' AND speed > 10 ORDER BY speed DESC LIMIT 2")
+        .unwrap();
+    assert_eq!(result.columns, vec![Column::Name, Column::Speed]);
+    assert_eq!(
+        result.rows,
+        vec![
+            vec![Value::Text(String::from(CRAB_4)), Value::Number(40)],
+            vec![Value::Text(String::from(CRAB_2)), Value::Number(30)],
+        ]
+    );
+
+    let all = beach.query("select * from crabs where speed <= 20;").unwrap();
+    assert_eq!(all.columns.len(), Column::all().len());
+    assert_eq!(all.rows.len(), 2);
+    assert_eq!(all.rows[0][0].to_string(), CRAB_1);
+}
+
+#[test]
+fn part4_query_errors() {
+    let beach = Beach::new();
+    assert!(beach.query("SELECT name FROM crabs").unwrap().rows.is_empty());
+    assert!(beach.query("SELECT name FROM clans").is_err());
+    assert!(beach.query("SELECT wings FROM crabs").is_err());
+    assert!(beach.query("SELECT name FROM crabs WHERE speed = 'fast'").is_err());
+    assert!(beach.query("SELECT name FROM crabs WHERE name = 'open").is_err());
+    assert!(beach.query("SELECT name FROM crabs LIMIT").is_err());
+    assert!(beach.query("SELECT name FROM crabs extra").is_err());
+}
+
+#[test]
+fn part4_command_undo_redo() {
+    let mut dispatcher = Dispatcher::new(Beach::new());
+    assert!(dispatcher.undo().is_none());
+    dispatcher.execute(Operation::AddCrab {
+        name: String::from(PRINZ),
+        speed: 20,
+        color: (255, 215, 0),
+        diet: Diet::Shellfish,
+    });
+    dispatcher.execute(Operation::AddCrab {
+        name: String::from(SEBASTIAN),
+        speed: 5,
+        color: (255, 0, 0),
+        diet: Diet::Plants,
+    });
+    dispatcher.execute(Operation::BreedCrabs {
+        i: 0,
+        j: 1,
+        name: String::from(CRAB_1),
+    });
+    let child_diet = dispatcher.beach().get_crab(2).diet();
+    dispatcher.execute(Operation::JoinClan {
+        clan_id: String::from(CLAN_1),
+        crab_name: String::from(PRINZ),
+    });
+    dispatcher.execute(Operation::BattleCrabs { i: 0, j: 1 });
+    dispatcher.execute(Operation::Tick);
+    let injured = dispatcher.beach().get_crab(1).health();
+    assert!(injured < 100);
+
+    assert_eq!(dispatcher.undo(), Some(&Operation::Tick));
+    assert_eq!(dispatcher.beach().ticks(), 0);
+    dispatcher.undo();
+    assert_eq!(dispatcher.beach().get_crab(1).health(), 100);
+    dispatcher.undo();
+    assert!(!dispatcher.beach().get_clan_system().has_clan(CLAN_1));
+    dispatcher.undo();
+    assert_eq!(dispatcher.beach().size(), 2);
+    assert_eq!(dispatcher.commands().count(), 2);
+
+    while dispatcher.redo().is_some() {}
+    assert!(!dispatcher.can_redo());
+    let beach = dispatcher.beach();
+    assert_eq!(beach.size(), 3);
+    assert_eq!(beach.get_crab(2).name(), CRAB_1);
+    assert_eq!(beach.get_crab(2).diet(), child_diet);
+    assert_eq!(beach.get_crab(1).health(), injured);
+    assert_eq!(beach.get_clan_system().get_clan_member_count(CLAN_1), 1);
+    assert_eq!(beach.ticks(), 1);
+
+    dispatcher.undo();
+    dispatcher.execute(Operation::PopulateRandom { n: 4, seed: 1 });
+    assert!(!dispatcher.can_redo());
+    dispatcher.undo();
+    assert_eq!(dispatcher.beach().size(), 3);
+}
+
+#[test]
+fn part4_rules_end_simulation() {
+    let mut simulation = Simulation::new(2);
+    simulation.set_rules(
+        RuleSet::new()
+            .with_rule("domination", EndCondition::ClanMembers(2))
+            .with_rule("time limit", EndCondition::Tick(5)),
+    );
+    simulation.apply(Operation::PopulateRandom { n: 3, seed: 4 });
+    assert_eq!(simulation.run(10).unwrap().rule, "time limit");
+    assert_eq!(simulation.beach().ticks(), 5);
+
+    let mut simulation = Simulation::new(2);
+    simulation.set_rules(
+        RuleSet::new()
+            .with_rule("domination", EndCondition::ClanMembers(2))
+            .with_rule("time limit", EndCondition::Tick(5)),
+    );
+    for name in [CRAB_1, CRAB_2] {
+        simulation.apply(Operation::AddCrab {
+            name: String::from(name),
+            speed: 10,
+            color: (0, 0, 255),
+            diet: Diet::Fish,
+        });
+        simulation.apply(Operation::JoinClan {
+            clan_id: String::from(CLAN_1),
+            crab_name: String::from(name),
+        });
+    }
+    // Rules are only checked at the end of a tick.
+    assert!(!simulation.is_over());
+    let verdict = simulation.run(10).unwrap();
+    assert_eq!(verdict.rule, "domination");
+    assert_eq!(verdict.tick, 1);
+    assert_eq!(verdict.winner.as_deref(), Some(CLAN_1));
+}
+
+#[test]
+fn part4_rules_compound_conditions() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 3));
+    beach.add_member_to_clan(CLAN_2, CRAB_1);
+
+    let crowded = EndCondition::AllOf(vec![
+        EndCondition::PopulationAtLeast(1),
+        EndCondition::ClanMembers(1),
+    ]);
+    assert_eq!(crowded.check(&beach), Some(Some(String::from(CLAN_2))));
+    let either = EndCondition::AnyOf(vec![EndCondition::Tick(3), EndCondition::PopulationAtMost(0)]);
+    assert_eq!(either.check(&beach), None);
+    assert!(RuleSet::new().evaluate(&beach).is_none());
+}
+
+#[test]
+fn part4_scheduler_drain_order_and_recurrence() {
+    let mut scheduler = Scheduler::new();
+    scheduler.at(5, "storm");
+    scheduler.after(0, 2, "hatch");
+    scheduler.every(0, 2, "feed");
+    assert_eq!(scheduler.len(), 3);
+    assert_eq!(scheduler.next_due(), Some(2));
+
+    assert!(scheduler.drain(1).is_empty());
+    assert_eq!(scheduler.drain(2), vec!["hatch", "feed"]);
+    assert_eq!(scheduler.drain(5), vec!["feed", "storm"]);
+    assert_eq!(scheduler.next_due(), Some(6));
+    scheduler.clear();
+    assert!(scheduler.is_empty());
+}
+
+#[test]
+fn part4_scheduler_drives_simulation() {
+    let mut simulation = Simulation::new(8);
+    simulation.apply(Operation::AddCrab {
+        name: String::from(CRAB_1),
+        speed: 10,
+        color: (1, 2, 3),
+        diet: Diet::Fish,
+    });
+    simulation.scheduler_mut().every(
+        0,
+        3,
+        Operation::JoinClan {
+            clan_id: String::from(CLAN_1),
+            crab_name: String::from(CRAB_1),
+        },
+    );
+    simulation
+        .scheduler_mut()
+        .at(2, Operation::PopulateRandom { n: 2, seed: 1 });
+    simulation.set_rules(RuleSet::new().with_rule("crowd", EndCondition::PopulationAtLeast(3)));
+
+    assert_eq!(simulation.run(10).unwrap().tick, 2);
+    simulation.run(5);
+    assert_eq!(simulation.beach().ticks(), 2);
+    for _ in 0..4 {
+        simulation.apply(Operation::Tick);
+    }
+    // The crab joined twice, but belongs to the clan only once.
+    assert_eq!(
+        simulation.beach().get_clan_system().get_clan_member_count(CLAN_1),
+        1
+    );
+    assert_eq!(simulation.operations().len(), 10);
+}
+
+#[test]
+fn part4_watch_callbacks_trip_once() {
+    let alerts = Rc::new(RefCell::new(Vec::new()));
+    let mut beach = Beach::new();
+    let seen = Rc::clone(&alerts);
+    beach.watch("crowded", Watch::PopulationAbove(2), move |alert| {
+        seen.borrow_mut().push(alert.clone())
+    });
+
+    for (name, speed) in [(CRAB_1, 5), (CRAB_2, 6), (CRAB_3, 7), (CRAB_4, 8)] {
+        beach.add_crab(new_crab(name, speed));
+    }
+    assert_eq!(alerts.borrow().len(), 1);
+    assert_eq!(alerts.borrow()[0].name, "crowded");
+    assert_close(alerts.borrow()[0].value, 3.0);
+
+    beach.pop_crab();
+    beach.pop_crab();
+    beach.add_crab(new_crab(CRAB_5, 9));
+    assert_eq!(alerts.borrow().len(), 2);
+
+    assert!(beach.unwatch("crowded"));
+    beach.add_crab(new_crab(PRINZ, 9));
+    assert_eq!(alerts.borrow().len(), 2);
+}
+
+#[test]
+fn part4_watch_channel_clan_speed() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 30));
+    beach.add_crab(new_crab(CRAB_2, 2));
+    let alerts = beach.watch_channel(
+        "slow clan",
+        Watch::ClanAverageSpeedBelow {
+            clan_id: String::from(CLAN_1),
+            speed: 20.0,
+        },
+    );
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    assert!(alerts.try_recv().is_err());
+    beach.add_member_to_clan(CLAN_1, CRAB_2);
+    let alert = alerts.try_recv().unwrap();
+    assert_close(alert.value, 16.0);
+
+    drop(alerts);
+    beach.remove_member_from_clan(CLAN_1, CRAB_2);
+    beach.add_member_to_clan(CLAN_1, CRAB_2);
+    assert!(!beach.unwatch("slow clan"));
+}
+
+#[test]
+fn part4_audit_finds_violations() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 3));
+    beach.add_crab(new_crab(CRAB_2, 3));
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    beach.add_member_to_clan(CLAN_2, CRAB_2);
+    beach
+        .set_clan_relation(CLAN_1, CLAN_2, Relation::Rivalry)
+        .unwrap();
+    assert!(audit(&beach).is_empty());
+
+    beach.add_crab(new_crab(CRAB_1, 4));
+    beach.add_member_to_clan(CLAN_2, CRAB_1);
+    // A crab that is not on the beach cannot join a clan.
+    beach.add_member_to_clan(CLAN_2, CRAB_3);
+    let violations = audit(&beach);
+    assert_eq!(
+        violations,
+        vec![Violation::DuplicateName {
+            name: String::from(CRAB_1),
+            count: 2,
+        }]
+    );
+    // Joining another clan moves a crab rather than adding a second membership.
+    assert_eq!(beach.get_clan_of(CRAB_1), Some(CLAN_2));
+    assert!(violations[0].to_string().contains(CRAB_1));
+}
+
+#[test]
+fn part4_audit_simulation_reports() {
+    let sebastian = Operation::AddCrab {
+        name: String::from(SEBASTIAN),
+        speed: 30,
+        color: (255, 0, 0),
+        diet: Diet::Plants,
+    };
+    let mut simulation = Simulation::new(6);
+    simulation.enable_audit(AuditMode::EveryOperation);
+    simulation.apply(sebastian.clone());
+    simulation.apply(sebastian.clone());
+    simulation.apply(Operation::Tick);
+    let reports = simulation.audit_reports();
+    assert_eq!(reports.len(), 2);
+    assert_eq!(reports[0].index, 1);
+    assert_eq!(reports[1].operation, Operation::Tick);
+    assert_eq!(reports[1].tick, 1);
+
+    let mut simulation = Simulation::new(6);
+    simulation.enable_audit(AuditMode::EveryTick);
+    simulation.apply(sebastian.clone());
+    simulation.apply(sebastian);
+    assert!(simulation.audit_reports().is_empty());
+    simulation.apply(Operation::Tick);
+    assert_eq!(simulation.audit_reports().len(), 1);
+    assert!(simulation.audit_reports()[0].to_string().contains(SEBASTIAN));
+}
+
+#[test]
+fn part4_merge_divergent_worlds() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 10));
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    let base = WorldState::capture(&beach);
+
+    let mut ours = base.to_beach();
+    ours.add_crab(new_crab(CRAB_3, 7));
+    ours.add_member_to_clan(CLAN_1, CRAB_2);
+    ours.get_crab_mut(0).injure(10);
+    let ours = WorldState::capture(&ours);
+
+    let mut theirs = base.to_beach();
+    theirs.add_member_to_clan(CLAN_2, CRAB_2);
+    theirs.get_crab_mut(0).injure(20);
+    theirs.get_crab_mut(1).set_claw_size(3);
+    theirs.tick();
+    let theirs = WorldState::capture(&theirs);
+
+    let outcome = merge_with_policy(&base, &ours, &theirs, Pick::Theirs);
+    assert_eq!(
+        outcome.conflicts,
+        vec![Resolved::Crab {
+            name: String::from(CRAB_1),
+            pick: Pick::Theirs,
+        }]
+    );
+    let merged = outcome.state;
+    assert_eq!(merged.tick(), 1);
+    assert_eq!(merged.population(), 3);
+    assert_eq!(merged.crab(0).unwrap().health, 80);
+    assert_eq!(merged.crab(1).unwrap().claw_size, 3);
+    assert_eq!(merged.crab(2).unwrap().name, CRAB_3);
+    assert_eq!(merged.clan_members(CLAN_1), [CRAB_1, CRAB_2]);
+    assert_eq!(merged.clan_members(CLAN_2), [CRAB_2]);
+
+    let merged_beach = merged.to_beach();
+    assert_eq!(merged_beach.ticks(), 1);
+    assert_eq!(merged_beach.get_crab(0).health(), 80);
+}
+
+#[test]
+fn part4_merge_callback_sees_clan_conflicts() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    let base = WorldState::capture(&beach);
+    let mut ours = base.to_beach();
+    ours.add_crab(new_crab(CRAB_2, 10));
+    ours.add_member_to_clan(CLAN_1, CRAB_2);
+    let mut theirs = base.to_beach();
+    theirs.add_crab(new_crab(CRAB_3, 10));
+    theirs.add_member_to_clan(CLAN_1, CRAB_3);
+
+    let mut seen = Vec::new();
+    let outcome = merge(
+        &base,
+        &WorldState::capture(&ours),
+        &WorldState::capture(&theirs),
+        |conflict| {
+            if let Conflict::Clan { clan_id, ours, .. } = conflict {
+                seen.push((clan_id.to_string(), ours.map(|m| m.len())));
+            }
+            Pick::Ours
+        },
+    );
+    assert_eq!(seen, vec![(String::from(CLAN_1), Some(2))]);
+    assert_eq!(outcome.state.clan_members(CLAN_1), [CRAB_1, CRAB_2]);
+}
+
+#[test]
+fn part4_server_enforces_clan_permissions() {
+    let mut server = Server::new(12);
+    let (red, red_events) = server.connect(CLAN_1).unwrap();
+    let (blue, _blue_events) = server.connect(CLAN_2).unwrap();
+    assert!(server.connect(CLAN_1).is_err());
+
+    server
+        .handle_line(red, "add\tRed Crab\t20\t255,0,0\tFish\n")
+        .unwrap();
+    server
+        .submit(
+            blue,
+            Operation::AddCrab {
+                name: String::from("Blue Crab"),
+                speed: 10,
+                color: (0, 0, 255),
+                diet: Diet::Plants,
+            },
+        )
+        .unwrap();
+    let clans = server.simulation().beach().get_clan_system();
+    assert_eq!(clans.get_clan_member_names(CLAN_1), vec!["Red Crab"]);
+    assert_eq!(clans.get_clan_member_names(CLAN_2), vec!["Blue Crab"]);
+
+    assert!(server.submit(blue, Operation::BattleCrabs { i: 0, j: 1 }).is_err());
+    assert!(server.submit(red, Operation::BattleCrabs { i: 0, j: 1 }).is_ok());
+    assert!(server
+        .submit(
+            red,
+            Operation::BreedCrabs {
+                i: 0,
+                j: 1,
+                name: String::from("Purple Crab"),
+            },
+        )
+        .is_err());
+    assert!(server
+        .submit(
+            red,
+            Operation::JoinClan {
+                clan_id: String::from(CLAN_1),
+                crab_name: String::from("Blue Crab"),
+            },
+        )
+        .is_err());
+    assert!(server.submit(red, Operation::Tick).is_err());
+    assert!(server.handle_line(red, "fly\taway").is_err());
+
+    let events: Vec<BeachEvent> = red_events.try_iter().collect();
+    assert_eq!(events.len(), 4);
+    assert_eq!(
+        events[3],
+        BeachEvent::ClanMemberAdded {
+            clan_id: String::from(CLAN_2),
+            crab_name: String::from("Blue Crab"),
+            clan_size: 1,
+        }
+    );
+
+    server.disconnect(red);
+    assert!(server.submit(red, Operation::BattleCrabs { i: 0, j: 1 }).is_err());
+    assert!(server.connect(CLAN_1).is_ok());
+}
+
+fn lockstep_commands(peer: PeerId, turn: u64) -> Vec<Operation> {
+    match (peer, turn) {
+        (0, 0) => vec![Operation::PopulateRandom { n: 3, seed: 5 }],
+        (1, 1) => vec![Operation::BreedCrabs {
+            i: 0,
+            j: 1,
+            name: String::from(CRAB_1),
+        }],
+        (0, 2) => vec![Operation::BattleCrabs { i: 2, j: 0 }],
+        _ => Vec::new(),
+    }
+}
+
+/** Runs one peer for four turns, returning its checksums. */
+fn run_peer(id: PeerId, remote: &[LockstepMessage]) -> (Peer, Vec<LockstepMessage>, bool) {
+    let mut peer = Peer::new(id, 2, 77, 2);
+    let mut checksums = Vec::new();
+    let mut failed = false;
+    for message in remote {
+        failed |= peer.receive(message.clone()).is_err();
+    }
+    for turn in 0..4 {
+        peer.issue(turn, lockstep_commands(id, turn));
+        let other = 1 - id;
+        peer.receive(LockstepMessage::Commands {
+            peer: other,
+            turn,
+            operations: lockstep_commands(other, turn),
+        })
+        .unwrap();
+        assert!(peer.ready());
+        match peer.advance() {
+            Ok(Some(checksum)) => checksums.push(checksum),
+            Ok(None) => {}
+            Err(_) => failed = true,
+        }
+    }
+    (peer, checksums, failed)
+}
+
+#[test]
+fn part4_lockstep_peers_agree() {
+    let (first, checksums, failed) = run_peer(0, &[]);
+    assert!(!failed);
+    assert_eq!(checksums.len(), 2);
+    assert_eq!(first.turn(), 4);
+    assert!(!first.ready());
+
+    let (second, _, failed) = run_peer(1, &checksums);
+    assert!(!failed);
+    assert_eq!(second.desync(), None);
+    assert_eq!(
+        state_hash(&WorldState::capture(first.simulation().beach())),
+        state_hash(&WorldState::capture(second.simulation().beach()))
+    );
+}
+
+#[test]
+fn part4_lockstep_detects_divergence() {
+    let (_, checksums, _) = run_peer(0, &[]);
+    let mut rogue = Peer::new(1, 2, 77, 2);
+    for message in checksums {
+        rogue.receive(message).unwrap();
+    }
+    for turn in 0..2 {
+        // The rogue peer changes its own beach without telling anyone.
+        let mut operations = lockstep_commands(1, turn);
+        operations.push(Operation::AddCrab {
+            name: String::from(CRAB_5),
+            speed: 1,
+            color: (0, 0, 0),
+            diet: Diet::Fish,
+        });
+        rogue.issue(turn, operations);
+        rogue
+            .receive(LockstepMessage::Commands {
+                peer: 0,
+                turn,
+                operations: lockstep_commands(0, turn),
+            })
+            .unwrap();
+        let result = rogue.advance();
+        assert_eq!(result.is_err(), turn == 1);
+    }
+    assert_eq!(rogue.desync(), Some((0, 2)));
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn part4_simulation_gzip_replay() {
+    let path = std::env::temp_dir().join(format!("ocean-replay-{}.gz", std::process::id()));
+    let original = run_recorded_simulation();
+    original.save_replay_gzip(&path).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    assert_eq!(&bytes[..2], &[0x1f, 0x8b]);
+    let replayed = Simulation::replay(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(replayed.operations(), original.operations());
+}
+
+#[test]
+fn part4_beach_import_stream() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_prinz());
+    let mut reports = Vec::new();
+    let records = (0..IMPORT_BATCH + 5).map(|k| Ok::<Crab, String>(new_crab(&format!("Crab #{}", k), 3)));
+    let imported = beach
+        .import_stream(records, |done| reports.push(done))
+        .unwrap();
+    assert_eq!(imported, IMPORT_BATCH + 5);
+    assert_eq!(reports, vec![IMPORT_BATCH, IMPORT_BATCH + 5]);
+    assert_eq!(beach.size(), IMPORT_BATCH + 6);
+
+    let records = vec![
+        Ok(new_crab(CRAB_1, 2)),
+        Err(String::from("truncated line")),
+        Ok(new_crab(CRAB_2, 2)),
+    ];
+    let error = beach.import_stream(records, |_| {}).unwrap_err();
+    assert!(error.contains("truncated line"));
+    assert!(error.contains('2'));
+    assert_eq!(beach.size(), IMPORT_BATCH + 7);
+
+    let duplicate = vec![Ok::<Crab, String>(new_crab(PRINZ, 2))];
+    assert!(beach.import_stream(duplicate, |_| {}).is_err());
+    let unnamed = vec![Ok::<Crab, String>(new_crab("", 2))];
+    assert!(beach.import_stream(unnamed, |_| {}).is_err());
+}
+
+#[test]
+fn part4_beach_memory_stats() {
+    let mut beach = Beach::new();
+    assert_eq!(beach.memory_stats().total(), 0);
+
+    beach.add_crab(new_prinz());
+    let before = beach.memory_stats();
+    assert!(before.crabs >= std::mem::size_of::<Crab>());
+    assert!(before.names >= PRINZ.len());
+    assert_eq!(before.clans, 0);
+
+    beach.add_member_to_clan(CLAN_1, PRINZ);
+    let after = beach.memory_stats();
+    assert!(after.clans >= CLAN_1.len() + PRINZ.len());
+    assert_eq!(after.total(), after.crabs + after.names + after.clans);
+}
+
+#[test]
+fn part4_intern_names() {
+    let mut names = Interner::new();
+    let prinz = names.intern(PRINZ);
+    assert_eq!(names.intern(PRINZ), prinz);
+    assert_ne!(names.intern(SEBASTIAN), prinz);
+    assert_eq!(names.resolve(prinz), PRINZ);
+    assert_eq!(names.get(CRAB_1), None);
+    assert_eq!(names.len(), 2);
+
+    let mut beach = Beach::new();
+    let prinz = beach.add_crab_with_id(new_prinz());
+    let sebastian = beach.add_crab_with_id(new_sebastian());
+    let mut clans = ClanSystem::new();
+    clans.add_member(CLAN_1, prinz, PRINZ);
+    clans.add_member(CLAN_2, prinz, PRINZ);
+    assert_eq!(clans.clan_members(CLAN_2), &[prinz]);
+    assert_eq!(clans.member_name(prinz), Some(PRINZ));
+    assert!(clans.is_member(CLAN_2, prinz));
+    assert!(!clans.is_member(CLAN_2, sebastian));
+    assert!(clans.clan_members(CLAN_3).is_empty());
+}
+
+#[test]
+fn part4_clan_members_beyond_inline_capacity() {
+    let mut beach = Beach::new();
+    let mut clans = ClanSystem::new();
+    let names: Vec<String> = (0..20).map(|k| format!("Crab {}", k)).collect();
+    let ids: Vec<CrabId> = names
+        .iter()
+        .map(|name| beach.add_crab_with_id(new_crab(name, 10)))
+        .collect();
+    for (&id, name) in ids.iter().zip(&names) {
+        clans.add_member(CLAN_1, id, name);
+    }
+    assert_eq!(clans.get_clan_member_names(CLAN_1), names);
+    assert_eq!(clans.clan_members(CLAN_1), &ids[..]);
+    assert_eq!(clans.remove_member(ids[7]).as_deref(), Some(CLAN_1));
+    assert_eq!(clans.get_clan_member_count(CLAN_1), 19);
+    assert!(clans.memory_bytes() > 0);
+}
+
+#[test]
+fn part4_beach_handles_survive_mutation() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_prinz());
+    beach.add_crab(new_sebastian());
+    let prinz = beach.handle(0);
+    let sebastian = beach.handle(1);
+    assert_ne!(prinz, sebastian);
+
+    beach.breed_crabs(0, 1, String::from(CRAB_1));
+    beach.tick();
+    assert_eq!(beach.resolve(prinz).unwrap().name(), PRINZ);
+    assert_eq!(beach.index_of(sebastian), Some(1));
+
+    beach.resolve_mut(sebastian).unwrap().injure(5);
+    assert_eq!(beach.get_crab(1).health(), 95);
+
+    let child = beach.handle(2);
+    beach.pop_crab();
+    assert!(beach.resolve(child).is_none());
+    beach.add_crab(new_crab(CRAB_2, 3));
+    assert!(beach.resolve(child).is_none());
+    assert_eq!(beach.resolve(beach.handle(2)).unwrap().name(), CRAB_2);
+}
+
+fn sample_spec() -> WorldSpec {
+    let mut prinz = CrabSpec::new(PRINZ, 20, Color::new(255, 215, 0), Diet::Shellfish);
+    prinz.claw_size = 3;
+    prinz.reefs = vec![0];
+    WorldSpec {
+        reefs: vec![ReefSpec {
+            minnows: 1,
+            clams: 2,
+            ..ReefSpec::default()
+        }],
+        beaches: vec![BeachSpec {
+            crabs: vec![
+                prinz,
+                CrabSpec::new(SEBASTIAN, 30, Color::new_red(), Diet::Plants),
+            ],
+            random: Some((3, 9)),
+            clans: vec![
+                ClanSpec {
+                    id: String::from(CLAN_1),
+                    members: vec![String::from(PRINZ)],
+                },
+                ClanSpec {
+                    id: String::from(CLAN_2),
+                    members: vec![String::from(SEBASTIAN)],
+                },
+            ],
+            relations: vec![(String::from(CLAN_1), String::from(CLAN_2), Relation::Rivalry)],
+            ..BeachSpec::default()
+        }],
+    }
+}
+
+#[test]
+fn part4_ocean_from_spec() {
+    let ocean = Ocean::from_spec(&sample_spec()).unwrap();
+    assert_eq!(ocean.reefs().len(), 1);
+    assert_eq!(ocean.reefs().next().unwrap().borrow().population(), 3);
+    let beach = ocean.beaches().next().unwrap();
+    assert_eq!(beach.size(), 5);
+    assert_eq!(beach.get_crab(0).claw_size(), 3);
+    assert_eq!(
+        beach.get_clan_system().get_relation(CLAN_2, CLAN_1),
+        Some(Relation::Rivalry)
+    );
+}
+
+#[test]
+fn part4_ocean_from_spec_errors() {
+    let mut spec = sample_spec();
+    spec.beaches[0].crabs[1].name = String::from(PRINZ);
+    let error = Ocean::from_spec(&spec).unwrap_err();
+    assert!(error.starts_with("beaches[0].crabs[1]"));
+
+    let mut spec = sample_spec();
+    spec.beaches[0].crabs[0].reefs = vec![4];
+    assert!(Ocean::from_spec(&spec).is_err());
+
+    let mut spec = sample_spec();
+    spec.beaches[0].clans[1].members.push(String::from(PRINZ));
+    let error = Ocean::from_spec(&spec).unwrap_err();
+    assert!(error.starts_with("beaches[0].clans[1]"));
+    assert!(error.contains(CLAN_1));
+
+    let mut spec = sample_spec();
+    spec.beaches[0].clans[0].members.push(String::from(CRAB_5));
+    assert!(Ocean::from_spec(&spec).is_err());
+
+    let mut spec = sample_spec();
+    spec.beaches[0].relations[0].1 = String::from(CLAN_3);
+    let error = Ocean::from_spec(&spec).unwrap_err();
+    assert!(error.starts_with("beaches[0].relations[0]"));
+}
+
+#[test]
+fn part4_constraints_on_construction() {
+    let constraints = Constraints::new();
+    let too_fast = Crab::try_new(
+        String::from(CRAB_1),
+        500,
+        Color::new_blue(),
+        Diet::Fish,
+        &constraints,
+    )
+    .unwrap_err();
+    assert_eq!(too_fast.field, Bounded::Speed);
+    assert_eq!(too_fast.value, 500);
+    assert_eq!(too_fast.allowed, 1..=100);
+    assert!(too_fast.to_string().contains("500"));
+    assert!(Crab::try_new(String::from(CRAB_1), 50, Color::new_blue(), Diet::Fish, &constraints).is_ok());
+
+    let mut beach = Beach::new();
+    assert!(beach.try_add_crab(new_crab(CRAB_2, 0)).is_err());
+    beach.set_constraints(Constraints::none());
+    beach.add_crab(new_crab(CRAB_2, 0));
+    beach.set_constraints(constraints);
+    assert!(beach.try_add_crab(new_crab(CRAB_3, 0)).is_err());
+    assert!(beach.try_add_crab(new_crab(CRAB_3, 1)).is_ok());
+    assert_eq!(beach.size(), 2);
+}
+
+#[test]
+fn part4_constraints_on_mutation() {
+    let mut beach = Beach::new();
+    beach.set_constraints(Constraints {
+        health: 10..=MAX_HEALTH,
+        ..Constraints::new()
+    });
+    beach.try_add_crab(new_crab(CRAB_1, 5)).unwrap();
+
+    assert!(beach.update_crab(0, |crab| crab.set_claw_size(4)).is_ok());
+    assert_eq!(beach.get_crab(0).claw_size(), 4);
+
+    let error = beach
+        .update_crab(0, |crab| {
+            crab.set_claw_size(11);
+            crab.injure(95);
+        })
+        .unwrap_err();
+    assert_eq!(error.field, Bounded::ClawSize);
+    assert_eq!(beach.get_crab(0).claw_size(), 4);
+    assert_eq!(beach.get_crab(0).health(), MAX_HEALTH);
+
+    assert!(beach.update_crab(0, |crab| crab.injure(95)).is_err());
+    assert_eq!(beach.get_crab(0).health(), MAX_HEALTH);
+}
+
+#[test]
+fn part4_breeding_rules_limit_quota_and_pairs() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 20));
+    beach.add_crab(new_crab(CRAB_3, 30));
+    beach.set_breeding_rules(BreedingRules {
+        per_tick_quota: Some(2),
+        min_parent_age: 0,
+        cooldown: 0,
+        max_offspring_per_pair: Some(1),
+        inbreeding: None,
+    });
+    assert!(beach.try_breed_crabs(0, 1, String::from("a")).is_ok());
+    assert!(beach.try_breed_crabs(1, 0, String::from("b")).is_err());
+    assert!(beach.try_breed_crabs(0, 2, String::from("c")).is_ok());
+    assert!(beach.try_breed_crabs(1, 2, String::from("d")).is_err());
+    assert_eq!(beach.size(), 5);
+    assert_eq!(beach.breeding().bred_this_tick(), 2);
+
+    beach.tick();
+    assert!(beach.try_breed_crabs(1, 2, String::from("d")).is_ok());
+    assert_eq!(beach.breeding().offspring(beach.handle(2), beach.handle(1)), 1);
+}
+
+#[test]
+fn part4_breeding_rules_require_old_enough_parents() {
+    let mut beach = Beach::new();
+    beach.set_breeding_rules(BreedingRules {
+        min_parent_age: 2,
+        ..BreedingRules::default()
+    });
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.tick();
+    beach.add_crab(new_crab(CRAB_2, 20));
+    beach.tick();
+    assert_eq!(beach.age(0), 2);
+    assert_eq!(beach.age(1), 1);
+    let err = beach
+        .try_breed_crabs(0, 1, String::from("a"))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains(CRAB_2), "{}", err);
+    beach.tick();
+    assert!(beach.can_breed(0, 1).is_ok());
+    beach.breed_crabs(0, 1, String::from("a"));
+    assert_eq!(beach.age(2), 0);
+}
+
+#[test]
+fn part4_genealogy_relatedness_of_family() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 20));
+    beach.add_crab(new_crab(CRAB_3, 30));
+    beach.breed_crabs(0, 1, String::from("kid1"));
+    beach.breed_crabs(0, 1, String::from("kid2"));
+    beach.breed_crabs(0, 2, String::from("half"));
+    let h: Vec<CrabHandle> = (0..beach.size()).map(|i| beach.handle(i)).collect();
+    let lineage: &Lineage = beach.lineage();
+
+    assert_eq!(lineage.parents(h[3]), Some((h[0], h[1])));
+    assert_eq!(lineage.parents(h[0]), None);
+    assert_close(lineage.relatedness(h[3], h[3]), 1.0);
+    assert_close(lineage.relatedness(h[0], h[3]), 0.5);
+    assert_close(lineage.relatedness(h[3], h[4]), 0.5);
+    assert_close(lineage.relatedness(h[3], h[5]), 0.25);
+    assert_close(lineage.relatedness(h[1], h[2]), 0.0);
+}
+
+#[test]
+fn part4_genealogy_lowest_common_ancestor() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 20));
+    beach.add_crab(new_crab(CRAB_3, 30));
+    beach.breed_crabs(0, 1, String::from("kid"));
+    beach.breed_crabs(3, 2, String::from("grandkid"));
+    beach.breed_crabs(0, 2, String::from("half"));
+    let h: Vec<CrabHandle> = (0..beach.size()).map(|i| beach.handle(i)).collect();
+    let lineage: &Lineage = beach.lineage();
+
+    assert_eq!(lineage.lowest_common_ancestor(h[4], h[3]), Some(h[3]));
+    assert_eq!(lineage.lowest_common_ancestor(h[4], h[5]), Some(h[2]));
+    assert_eq!(lineage.lowest_common_ancestor(h[1], h[2]), None);
+    assert_eq!(lineage.ancestors(h[4]).get(&h[0]), Some(&2));
+}
+
+#[test]
+fn part4_breeding_rejects_or_warns_about_close_relatives() {
+    struct Recorder(Vec<BeachEvent>);
+    impl EventListener for Recorder {
+        fn on_event(&mut self, event: &BeachEvent) {
+            self.0.push(event.clone());
+        }
+    }
+
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 20));
+    beach.breed_crabs(0, 1, String::from("kid1"));
+    beach.breed_crabs(0, 1, String::from("kid2"));
+    let mut limit = InbreedingLimit {
+        max_relatedness: 0.25,
+        policy: InbreedingPolicy::Reject,
+    };
+    beach.set_breeding_rules(BreedingRules {
+        inbreeding: Some(limit),
+        ..BreedingRules::default()
+    });
+    let err = beach
+        .try_breed_crabs(2, 3, String::from("inbred"))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("kid1"), "{}", err);
+    assert!(beach.can_breed(0, 2).is_err());
+    assert_eq!(beach.size(), 4);
+
+    limit.policy = InbreedingPolicy::Warn;
+    beach.set_breeding_rules(BreedingRules {
+        inbreeding: Some(limit),
+        ..BreedingRules::default()
+    });
+    let recorder = Rc::new(RefCell::new(Recorder(Vec::new())));
+    beach.subscribe(recorder.clone());
+    beach.breed_crabs(2, 3, String::from("inbred"));
+    assert_eq!(beach.size(), 5);
+    assert!(recorder.borrow().0.contains(&BeachEvent::InbreedingWarning {
+        parent1: String::from("kid1"),
+        parent2: String::from("kid2"),
+    }));
+}
+
+#[test]
+fn part4_incubation_clutches_hatch_into_juveniles() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_prinz());
+    beach.add_crab(new_sebastian());
+    beach.incubator_mut().set_ticks_to_hatch(2);
+    let nest = Position::new(4, 7);
+    let names = vec![String::from(CRAB_1), String::from(CRAB_2)];
+    beach.lay_clutch(0, 1, nest, names).unwrap();
+    assert_eq!(
+        beach.lay_clutch(0, 1, nest, vec![String::from(CRAB_3)]),
+        Err(OceanError::ClutchPositionTaken(nest))
+    );
+    assert_eq!(
+        beach.lay_clutch(0, 1, Position::new(1, 1), Vec::new()),
+        Err(OceanError::ClutchEmpty)
+    );
+    assert_eq!(beach.incubator().egg_count(), 2);
+    assert_eq!(beach.breeding().bred_this_tick(), 2);
+
+    beach.tick();
+    assert_eq!(beach.size(), 2);
+    beach.tick();
+    assert_eq!(beach.size(), 4);
+    assert!(beach.incubator().clutches().is_empty());
+    let juvenile = beach.get_crab(2);
+    assert_eq!(juvenile.name(), CRAB_1);
+    assert_eq!(juvenile.speed(), 1);
+    assert_eq!(juvenile.position(), nest);
+    assert_eq!(beach.age(3), 0);
+    let lineage = beach.lineage();
+    assert_eq!(lineage.parents(beach.handle(3)), Some((beach.handle(0), beach.handle(1))));
+}
+
+#[test]
+fn part4_incubation_predators_and_tides_destroy_eggs() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_prinz());
+    beach.add_crab(new_sebastian());
+    let names = |n: usize| (0..n).map(|k| format!("egg{}", k)).collect::<Vec<_>>();
+    beach.lay_clutch(0, 1, Position::new(0, 1), names(3)).unwrap();
+    beach.lay_clutch(0, 1, Position::new(0, 5), names(2)).unwrap();
+    beach.lay_clutch(0, 1, Position::new(3, 9), names(4)).unwrap();
+
+    let incubator = beach.incubator_mut();
+    assert_eq!(incubator.predate(Position::new(3, 9), 3), 3);
+    assert_eq!(incubator.predate(Position::new(3, 9), 3), 1);
+    assert!(incubator.clutch_at(Position::new(3, 9)).is_none());
+    assert_eq!(incubator.tide(2), 3);
+    assert_eq!(incubator.egg_count(), 2);
+
+    for _ in 0..DEFAULT_INCUBATION_TICKS {
+        beach.tick();
+    }
+    assert_eq!(beach.size(), 4);
+}
+
+#[test]
+fn part4_tidepool_residents_feed_on_pool_food() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_prinz());
+    beach.add_crab(new_crab(CRAB_1, 10));
+    let pool_food = Rc::new(RefCell::new(Reef::new()));
+    pool_food.borrow_mut().add_prey(Box::new(Clam::new()));
+    let prinz = beach.handle(0);
+    let pools = beach.tide_pools_mut();
+    let pool = pools.add_pool("rock pool", pool_food.clone());
+    assert!(pools.enter(prinz, 3).is_err());
+    beach.tide_pools_mut().enter(prinz, pool).unwrap();
+    assert_eq!(beach.tide_pools().pool_of(prinz), Some(pool));
+    assert!(beach.forage(0));
+    assert_eq!(pool_food.borrow().population(), 0);
+    assert!(!beach.forage(0));
+
+    beach.pop_crab();
+    beach.pop_crab();
+    assert!(beach.tide_pools().pools()[pool].residents().is_empty());
+}
+
+#[test]
+fn part4_tidepool_tides_exchange_inhabitants() {
+    let mut beach = Beach::new();
+    for k in 0..20 {
+        beach.add_crab(new_crab(&format!("crab{}", k), 10));
+    }
+    let pools = beach.tide_pools_mut();
+    pools.add_pool("north", Rc::new(RefCell::new(Reef::new())));
+    pools.add_pool("south", Rc::new(RefCell::new(Reef::new())));
+    pools.set_period(5);
+    pools.set_exchange_rate(0.5);
+
+    for _ in 0..4 {
+        beach.tick();
+    }
+    assert_eq!(beach.tide_pools().tide(), Tide::Low);
+    beach.tick();
+    assert_eq!(beach.tide_pools().tide(), Tide::High);
+    let residents: usize = beach
+        .tide_pools()
+        .pools()
+        .iter()
+        .map(|pool| pool.residents().len())
+        .sum();
+    assert!(residents > 0 && residents < 20, "{}", residents);
+
+    let exchange = beach.turn_tide();
+    assert_eq!(beach.tide_pools().tide(), Tide::Low);
+    assert!(!exchange.left.is_empty());
+    for (crab, _) in &exchange.left {
+        assert!(!exchange.entered.iter().any(|(c, _)| c == crab));
+    }
+}
+
+#[test]
+fn part4_raid_seagulls_take_slow_conspicuous_crabs() {
+    assert_close(camouflage(&SAND), 1.0);
+    assert!(camouflage(&Color::new_blue()) < camouflage(&Color::new(200, 180, 140)));
+
+    let mut beach = Beach::new();
+    for k in 0..10 {
+        beach.add_crab(Crab::new(format!("fast{}", k), 100, SAND.clone(), Diet::Plants));
+    }
+    beach.add_crab(Crab::new(String::from(CRAB_1), 1, Color::new_blue(), Diet::Plants));
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    beach.set_raid_settings(RaidSettings {
+        gulls: 1,
+        every: 10,
+        seed: 3,
+        ..RaidSettings::default()
+    });
+    for _ in 0..9 {
+        beach.tick();
+    }
+    assert!(beach.raid_reports().is_empty());
+    beach.tick();
+
+    let report = &beach.raid_reports()[0];
+    assert_eq!(report.tick, 10);
+    assert_eq!(report.casualties, vec![String::from(CRAB_1)]);
+    assert_eq!(report.survivors, 10);
+    assert_eq!(beach.size(), 10);
+    assert!(beach.find_crabs_by_name(CRAB_1).is_empty());
+    assert!(!beach.get_clan_system().has_clan(CLAN_1));
+}
+
+#[test]
+fn part4_raid_disabled_by_default_and_random_when_tuned() {
+    let mut beach = Beach::new();
+    for k in 0..20 {
+        beach.add_crab(new_crab(&format!("crab{}", k), 10));
+    }
+    for _ in 0..50 {
+        beach.tick();
+    }
+    assert_eq!(beach.size(), 20);
+
+    beach.set_raid_settings(RaidSettings {
+        gulls: 2,
+        chance: 0.5,
+        seed: 1,
+        ..RaidSettings::default()
+    });
+    for _ in 0..6 {
+        beach.tick();
+    }
+    let raids = beach.raid_reports().len();
+    assert!(raids > 0);
+    assert_eq!(beach.size(), 20 - 2 * raids);
+    let report = beach.seagull_raid();
+    assert_eq!(report.casualties.len(), 2);
+    assert_eq!(report.survivors, beach.size());
+}
+
+#[test]
+fn part4_interference_fishing_removes_random_sample() {
+    let mut beach = Beach::new();
+    for k in 0..10 {
+        beach.add_crab(new_crab(&format!("crab{}", k), 10));
+    }
+    beach.add_member_to_clan(CLAN_1, "crab3");
+    beach.schedule_interference(3, Interference::Fishing { count: 4, seed: 5 });
+    beach.tick();
+    beach.tick();
+    assert_eq!(beach.size(), 10);
+    beach.tick();
+    assert_eq!(beach.size(), 6);
+
+    let record = &beach.humans().log()[0];
+    assert_eq!(record.tick, 3);
+    assert_eq!(record.removed.len(), 4);
+    for name in &record.removed {
+        assert!(beach.find_crabs_by_name(name).is_empty());
+        assert_ne!(beach.get_clan_of(name), Some(CLAN_1));
+    }
+    assert!(beach.humans().scheduled().is_empty());
+    let all = beach.interfere(Interference::Fishing { count: 100, seed: 1 });
+    assert_eq!(all.len(), 6);
+    assert_eq!(beach.size(), 0);
+}
+
+#[test]
+fn part4_interference_cleanup_blocks_region_temporarily() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_prinz());
+    beach.add_crab(new_sebastian());
+    let region = Region::new(Position::new(5, 5), Position::new(2, 2));
+    assert!(region.contains(Position::new(2, 5)));
+    assert!(!region.contains(Position::new(6, 3)));
+    beach.interfere(Interference::Cleanup { region, ticks: 2 });
+
+    assert!(beach.try_place_crab(0, Position::new(3, 3)).is_err());
+    assert!(beach.try_place_crab(0, Position::new(8, 3)).is_ok());
+    assert!(matches!(
+        beach.lay_clutch(0, 1, Position::new(4, 4), vec![String::from(CRAB_1)]),
+        Err(OceanError::RegionBlocked { .. })
+    ));
+    beach.place_crab(1, Position::new(4, 4));
+    assert!(!beach.forage(1));
+
+    beach.tick();
+    assert_eq!(beach.humans().blocked().len(), 1);
+    beach.tick();
+    assert!(beach.humans().blocked().is_empty());
+    assert!(beach.try_place_crab(0, Position::new(3, 3)).is_ok());
+}
+
+#[test]
+fn part4_environment_temperature_follows_seasons() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 50));
+    assert_eq!(beach.effective_speed(0), 50);
+    beach.set_climate(Climate {
+        mean: 26.0,
+        seasonal_amplitude: 10.0,
+        year_length: 40,
+        weather: 0.0,
+        seed: 0,
+    });
+    for _ in 0..40 {
+        beach.tick();
+    }
+    let environment = beach.environment().unwrap();
+    let series = environment.temperatures();
+    assert_eq!(series.len(), 41);
+    assert_eq!(series[10].0, 10);
+    assert_close(series[0].1, 26.0);
+    assert_close(series[10].1, 36.0);
+    assert_close(series[30].1, 16.0);
+    assert_close(environment.temperature(), series[40].1);
+    assert_close(environment.performance(Diet::Plants), 1.0);
+    assert_eq!(beach.effective_speed(0), 50);
+
+    for _ in 0..10 {
+        beach.tick();
+    }
+    assert!(beach.effective_speed(0) < 15);
+}
+
+#[test]
+fn part4_environment_cold_hampers_breeding() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 20));
+    beach.set_climate(Climate {
+        mean: -10.0,
+        seasonal_amplitude: 0.0,
+        weather: 0.0,
+        ..Climate::default()
+    });
+    let err = beach
+        .try_breed_crabs(0, 1, String::from("a"))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains(CRAB_1), "{}", err);
+    assert!(matches!(
+        beach.lay_clutch(0, 1, Position::new(1, 1), vec![String::from("b")]),
+        Err(OceanError::BreedingFailed(_))
+    ));
+    assert_eq!(beach.size(), 2);
+    assert_eq!(beach.breeding().bred_this_tick(), 0);
+
+    let environment = beach.environment_mut().unwrap();
+    environment.set_tolerance(
+        Diet::Plants,
+        ThermalTolerance {
+            optimum: -10.0,
+            tolerance: 5.0,
+        },
+    );
+    assert!(beach.try_breed_crabs(0, 1, String::from("a")).is_ok());
+}
+
+#[test]
+fn part4_pollution_wears_down_health_and_regrowth() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_prinz());
+    let pool = beach
+        .tide_pools_mut()
+        .add_pool("pool", Rc::new(RefCell::new(Reef::new())));
+    beach.tide_pools_mut().set_regrowth(pool, 4).unwrap();
+    let health = beach.get_crab(0).health();
+
+    beach.tick();
+    assert_eq!(beach.get_crab(0).health(), health);
+    assert_eq!(beach.tide_pools().pools()[pool].food.borrow().population(), 4);
+
+    beach.pollute(50.0);
+    beach.set_pollution_decay(5.0);
+    beach.tick();
+    assert_eq!(beach.get_crab(0).health(), health - 2);
+    assert_eq!(beach.tide_pools().pools()[pool].food.borrow().population(), 6);
+    assert_close(beach.pollution().level(), 45.0);
+
+    beach.pollute(1000.0);
+    assert_close(beach.pollution().level(), MAX_POLLUTION);
+}
+
+#[test]
+fn part4_pollution_from_fishing_and_cleanups() {
+    let mut beach = Beach::new();
+    for k in 0..10 {
+        beach.add_crab(new_crab(&format!("crab{}", k), 10));
+    }
+    beach.interfere(Interference::Fishing { count: 5, seed: 2 });
+    assert_close(beach.pollution().level(), 5.0 * POLLUTION_PER_CATCH);
+    beach.pollute(20.0);
+    let region = Region::new(Position::new(0, 0), Position::new(1, 1));
+    beach.interfere(Interference::Cleanup { region, ticks: 1 });
+    assert_close(beach.pollution().level(), 25.0 - CLEANUP_PER_TICK);
+    assert_close(beach.clean_up(100.0), 15.0);
+    assert_close(beach.pollution().level(), 0.0);
+}
+
+#[test]
+fn part4_protection_spares_crabs_from_raids_and_fishing() {
+    let mut beach = Beach::new();
+    for k in 0..6 {
+        beach.add_crab(new_crab(&format!("crab{}", k), 10));
+    }
+    beach.add_member_to_clan(CLAN_1, "crab4");
+    beach.add_member_to_clan(CLAN_1, "crab5");
+    assert!(beach.set_crab_protected(0, true));
+    assert!(!beach.set_crab_protected(0, true));
+    assert!(beach.set_clan_protected(CLAN_1, true));
+    assert!(beach.is_protected(0));
+    assert!(beach.is_protected(5));
+    assert!(!beach.is_protected(1));
+
+    beach.set_raid_settings(RaidSettings {
+        gulls: 10,
+        ..RaidSettings::default()
+    });
+    let report = beach.seagull_raid();
+    assert_eq!(report.casualties.len(), 3);
+    assert_eq!(report.survivors, 3);
+    assert_eq!(beach.get_crab(0).name(), "crab0");
+    assert_eq!(beach.interfere(Interference::Fishing { count: 5, seed: 0 }), Vec::<String>::new());
+    assert_eq!(beach.size(), 3);
+}
+
+#[test]
+fn part4_protection_changes_are_audited() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_prinz());
+    beach.set_crab_protected(0, true);
+    beach.tick();
+    beach.set_clan_protected(CLAN_2, true);
+    beach.set_crab_protected(0, false);
+    beach.set_crab_protected(0, false);
+
+    let log = beach.protection().log();
+    assert_eq!(log.len(), 3);
+    assert_eq!(
+        log[0],
+        ProtectionChange {
+            tick: 0,
+            target: Protected::Crab(beach.handle(0)),
+            protected: true,
+        }
+    );
+    assert_eq!(log[1].target, Protected::Clan(String::from(CLAN_2)));
+    assert_eq!(log[1].tick, 1);
+    assert!(!log[2].protected);
+    assert!(!beach.is_protected(0));
+}
+
+#[test]
+fn part4_survey_lincoln_petersen_estimate() {
+    let mut beach = Beach::new();
+    for k in 0..200 {
+        beach.add_crab(new_crab(&format!("crab{}", k), 10));
+    }
+    assert_eq!(beach.tag_sample(50, 1), 50);
+    assert_eq!(beach.survey().marked(), 50);
+    let tagged = (0..beach.size())
+        .filter(|&k| beach.survey().is_tagged(beach.handle(k)))
+        .count();
+    assert_eq!(tagged, 50);
+
+    let report: RecaptureReport = beach.recapture(60, 2);
+    assert_eq!(report.caught, 60);
+    assert_eq!(report.marked, 50);
+    assert_eq!(report.actual, 200);
+    assert!(report.recaptured > 0);
+    assert_close(report.estimate.unwrap(), 3000.0 / report.recaptured as f64);
+    assert!(report.relative_error().unwrap().abs() < 0.5);
+    assert_eq!(beach.survey().reports().len(), 1);
+}
+
+#[test]
+fn part4_survey_without_recaptures_has_no_estimate() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_prinz());
+    beach.add_crab(new_sebastian());
+    let report = beach.recapture(2, 0);
+    assert_eq!(report.recaptured, 0);
+    assert_eq!(report.estimate, None);
+    assert_eq!(report.relative_error(), None);
+
+    beach.tag_sample(5, 0);
+    let report = beach.recapture(5, 9);
+    assert_eq!(report.marked, 2);
+    assert_close(report.estimate.unwrap(), 2.0);
+    beach.survey_mut().reset();
+    assert_eq!(beach.survey().marked(), 0);
+}
+
+#[test]
+fn part4_observe_records_metrics_every_tick() {
+    let mut beach = Beach::new();
+    let population = Rc::new(RefCell::new(Observer::new(Population)));
+    let speed = Rc::new(RefCell::new(Observer::new(MeanSpeed)));
+    let clans = Rc::new(RefCell::new(Observer::new(ClanSizes)));
+    beach.add_observer(population.clone());
+    beach.add_observer(speed.clone());
+    beach.add_observer(clans.clone());
+
+    beach.tick();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 30));
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    beach.tick();
+    beach.add_crab(new_crab(CRAB_3, 50));
+    beach.tick();
+
+    let population = population.borrow();
+    assert_eq!(population.series().points(), &[(1, 0), (2, 2), (3, 3)]);
+    let speed = speed.borrow();
+    assert_eq!(speed.series().len(), 2);
+    assert_close(*speed.series().at(3).unwrap(), 30.0);
+    assert_eq!(speed.series().at(1), None);
+    let clans = clans.borrow();
+    assert_eq!(clans.series().at(2).unwrap().get(CLAN_1), Some(&1));
+}
+
+#[test]
+fn part4_observe_time_series_query_and_downsample() {
+    let mut series = TimeSeries::new();
+    for tick in 0..10u64 {
+        series.push(tick, tick as f64 * 2.0);
+    }
+    series.push(3, 100.0);
+    series.push(2, -1.0);
+    assert_eq!(series.len(), 10);
+    assert_eq!(series.at(3), Some(&6.0));
+    assert_eq!(series.range(4..=6).len(), 3);
+    assert_eq!(series.range(20..=30).len(), 0);
+    assert_eq!(series.as_of(100), Some(&18.0));
+    assert_eq!(series.last(), Some(&(9, 18.0)));
+
+    let every_fourth = series.downsample(4);
+    assert_eq!(every_fourth.points(), &[(0, 0.0), (4, 8.0), (8, 16.0)]);
+    let means = series.downsample_mean(5);
+    assert_eq!(means.points(), &[(0, 4.0), (5, 14.0)]);
+    let counts = series.downsample_with(5, |values| values.len());
+    assert_eq!(counts.points(), &[(0, 5), (5, 5)]);
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn part4_stream_events_as_they_happen() {
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    let mut beach = Beach::new();
+    let mut stream: EventStream = beach.event_stream();
+    let mut lagging = beach.event_stream_with_capacity(1);
+    let mut cx = Context::from_waker(Waker::noop());
+    assert_eq!(pin!(stream.recv()).poll(&mut cx), Poll::Pending);
+
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 20));
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    beach.breed_crabs(0, 1, CRAB_3.to_string());
+    // Ticks are not streamed.
+    beach.tick();
+    assert_eq!(lagging.missed(), 4);
+    assert_eq!(
+        lagging
+            .try_next()
+            .map(|event| matches!(event, BeachEvent::CrabBred { .. })),
+        Some(true)
+    );
+    assert!(!stream.is_closed());
+
+    // The stream can be read on another thread, and ends with the beach.
+    drop(beach);
+    let events = std::thread::spawn(move || {
+        let mut events = Vec::new();
+        let mut cx = Context::from_waker(Waker::noop());
+        while let Poll::Ready(Some(event)) = pin!(stream.recv()).poll(&mut cx) {
+            events.push(event);
+        }
+        events
+    })
+    .join()
+    .unwrap();
+    assert_eq!(
+        events,
+        vec![
+            BeachEvent::CrabAdded {
+                name: CRAB_1.to_string()
+            },
+            BeachEvent::CrabAdded {
+                name: CRAB_2.to_string()
+            },
+            BeachEvent::ClanMemberAdded {
+                clan_id: CLAN_1.to_string(),
+                crab_name: CRAB_1.to_string(),
+                clan_size: 1,
+            },
+            BeachEvent::CrabAdded {
+                name: CRAB_3.to_string()
+            },
+            BeachEvent::CrabBred {
+                parent1: CRAB_1.to_string(),
+                parent2: CRAB_2.to_string(),
+                child: CRAB_3.to_string(),
+            },
+        ]
+    );
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn part4_frame_population_and_series_batches() {
+    use arrow_array::{Array, Float64Array, StringArray, UInt32Array};
+
+    let mut beach = Beach::new();
+    beach.add_crab(new_prinz());
+    beach.add_crab(new_sebastian());
+    beach.add_member_to_clan(CLAN_1, SEBASTIAN);
+    let batch = population_batch(&beach).unwrap();
+    assert_eq!(batch.num_rows(), 2);
+    assert_eq!(batch.num_columns(), 9);
+    let speeds = batch
+        .column_by_name("speed")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<UInt32Array>()
+        .unwrap();
+    assert_eq!(speeds.value(1), 30);
+    let clans = batch
+        .column_by_name("clan")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert!(clans.is_null(0));
+    assert_eq!(clans.value(1), CLAN_1);
+
+    let mut series = TimeSeries::new();
+    series.push(1, 2u32);
+    series.push(4, 5u32);
+    let batch = series_batch("population", &series).unwrap();
+    assert_eq!(batch.schema().field(1).name(), "population");
+    let values = batch
+        .column(1)
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .unwrap();
+    assert_close(values.value(1), 5.0);
+}
+
+#[test]
+fn part4_distribution_traits_of_random_crabs() {
+    let mut normal = Beach::new();
+    let distributions = TraitDistributions {
+        speed: TraitDistribution::Normal {
+            mean: 60.0,
+            std_dev: 5.0,
+        },
+        claw_size: TraitDistribution::Poisson { lambda: 3.0 },
+        ..TraitDistributions::default()
+    };
+    normal.populate_with(500, 4, &distributions).unwrap();
+    let speeds: Vec<f64> = normal.crabs().map(|c| c.speed() as f64).collect();
+    assert!((mean(&speeds).unwrap() - 60.0).abs() < 1.0);
+    assert!((std_dev(&speeds).unwrap() - 5.0).abs() < 1.0);
+    let claws: Vec<f64> = normal.crabs().map(|c| c.claw_size() as f64).collect();
+    assert!((mean(&claws).unwrap() - 3.0).abs() < 0.3);
+    assert!(normal.crabs().all(|c| (1..=10).contains(&c.claw_size())));
+
+    let mut skewed = Beach::new();
+    let distributions = TraitDistributions {
+        speed: TraitDistribution::LogNormal { mu: 5.0, sigma: 1.0 },
+        ..TraitDistributions::default()
+    };
+    skewed.populate_with(50, 1, &distributions).unwrap();
+    assert!(skewed.crabs().all(|c| c.speed() <= 100));
+    assert!(skewed.crabs().any(|c| c.speed() == 100));
+
+    let mut uniform = Beach::new();
+    let mut random = Beach::new();
+    uniform.populate_with(20, 8, &TraitDistributions::default()).unwrap();
+    random.populate_random(20, 8);
+    let speeds = |beach: &Beach| beach.crabs().map(|c| c.speed()).collect::<Vec<_>>();
+    assert_eq!(speeds(&uniform), speeds(&random));
+}
+
+#[test]
+fn part4_distribution_invalid_parameters_are_rejected() {
+    let mut beach = Beach::new();
+    let distributions = TraitDistributions {
+        speed: TraitDistribution::Normal {
+            mean: 10.0,
+            std_dev: -1.0,
+        },
+        ..TraitDistributions::default()
+    };
+    assert!(beach.populate_with(5, 0, &distributions).is_err());
+    assert_eq!(beach.size(), 0);
+    assert!(TraitDistribution::Uniform { min: 5, max: 1 }.validate().is_err());
+    assert!(TraitDistribution::Poisson { lambda: 0.0 }.validate().is_err());
+    for parameter in [f64::NAN, f64::INFINITY] {
+        let normal = TraitDistribution::Normal {
+            mean: parameter,
+            std_dev: 1.0,
+        };
+        let log_normal = TraitDistribution::LogNormal {
+            mu: parameter,
+            sigma: 1.0,
+        };
+        let poisson = TraitDistribution::Poisson { lambda: parameter };
+        for distribution in [normal, log_normal, poisson] {
+            assert!(distribution.validate().is_err(), "{:?}", distribution);
+        }
+    }
+
+    // An empty limit would make clamping panic halfway through populating.
+    #[allow(clippy::reversed_empty_ranges)]
+    let limits = Constraints {
+        speed: 50..=10,
+        ..Constraints::new()
+    };
+    let empty = TraitDistributions {
+        limits,
+        ..TraitDistributions::default()
+    };
+    assert!(empty.validate().is_err());
+    assert!(beach.populate_with(5, 0, &empty).is_err());
+    assert_eq!(beach.size(), 0);
+
+    let spec = WorldSpec {
+        beaches: vec![BeachSpec {
+            random: Some((3, 0)),
+            distributions,
+            ..BeachSpec::default()
+        }],
+        ..WorldSpec::default()
+    };
+    let err = Ocean::from_spec(&spec).unwrap_err();
+    assert!(err.contains("beaches[0].distributions"), "{}", err);
+}
+
+#[test]
+fn part4_batch_sweep_grid_and_aggregation() {
+    let sweep = Sweep::new()
+        .axis("a", [1.0, 2.0])
+        .axis("b", [10.0, 20.0, 30.0])
+        .seeds(0..4);
+    let points = sweep.points();
+    assert_eq!(points.len(), 6);
+    assert_eq!(points[1].get("a"), Some(&1.0));
+    assert_eq!(points[1].get("b"), Some(&20.0));
+
+    let table = sweep.run(|point, seed| {
+        Metrics::from([(String::from("sum"), point["a"] + point["b"] + seed as f64)])
+    });
+    assert_eq!(table.rows.len(), 6);
+    let sum = table.rows[5].metrics["sum"];
+    assert_eq!(sum.count, 4);
+    assert_close(sum.mean, 33.5);
+    assert_close(sum.min, 32.0);
+    let csv = table.to_csv();
+    assert!(csv.starts_with("a,b,sum_mean,sum_std_dev,sum_min,sum_max\n"), "{}", csv);
+    assert_eq!(csv.lines().count(), 7);
+}
+
+#[test]
+fn part4_batch_parallel_ecology_sweep_matches_serial() {
+    let sweep = Sweep::new()
+        .axis("food", [0.0, 3.0])
+        .axis("gulls", [0.0, 2.0])
+        .axis("ticks", [10.0])
+        .seeds([1, 2]);
+    let serial = sweep.run(ecology_experiment);
+    let parallel = sweep.run_parallel(4, ecology_experiment);
+    assert_eq!(serial, parallel);
+
+    let row = |food: f64, gulls: f64| {
+        serial
+            .rows
+            .iter()
+            .find(|row| row.point["food"] == food && row.point["gulls"] == gulls)
+            .unwrap()
+    };
+    assert_close(row(0.0, 0.0).metrics["meals"].mean, 0.0);
+    assert!(row(3.0, 0.0).metrics["meals"].mean > 0.0);
+    assert_close(row(3.0, 0.0).metrics["population"].mean, 20.0);
+    assert_close(row(3.0, 2.0).metrics["population"].mean, 16.0);
+}
+
+fn new_trading_ocean() -> Ocean {
+    let mut ocean = Ocean::new();
+    for _ in 0..2 {
+        ocean.add_beach(Beach::new());
+    }
+    let home = ocean.beach_mut(0).unwrap();
+    for (i, name) in [CRAB_1, CRAB_2, CRAB_3].into_iter().enumerate() {
+        home.add_crab(new_crab(name, 10 + i as u32));
+        home.add_member_to_clan(CLAN_1, name);
+    }
+    home.economy_mut()
+        .earn(Account::clan(CLAN_1), 25, Earning::Foraging)
+        .unwrap();
+    ocean
+}
+
+#[test]
+fn part4_trade_route_moves_shells_and_members() {
+    let mut ocean = new_trading_ocean();
+    assert!(ocean
+        .open_route(TradeRoute {
+            clan_id: CLAN_1.to_string(),
+            from: 0,
+            to: 0,
+            capacity: 10,
+            migrants: 1,
+        })
+        .is_err());
+    assert!(ocean
+        .open_route(TradeRoute {
+            clan_id: CLAN_2.to_string(),
+            from: 0,
+            to: 1,
+            capacity: 10,
+            migrants: 1,
+        })
+        .is_err());
+    let route = ocean
+        .open_route(TradeRoute {
+            clan_id: CLAN_1.to_string(),
+            from: 0,
+            to: 1,
+            capacity: 10,
+            migrants: 1,
+        })
+        .unwrap();
+
+    for _ in 0..4 {
+        ocean.tick();
+    }
+    let treasury = Account::clan(CLAN_1);
+    let (home, away) = (ocean.beach(0).unwrap(), ocean.beach(1).unwrap());
+    assert_eq!(home.economy().balance(&treasury), 0);
+    assert_eq!(away.economy().balance(&treasury), 25);
+    assert_eq!(home.size(), 0);
+    assert_eq!(
+        away.get_clan_system().get_clan_member_names(CLAN_1),
+        vec![CRAB_1, CRAB_2, CRAB_3]
+    );
+
+    let stats: &RouteStats = ocean.route_stats(route).unwrap();
+    assert_eq!(stats.shells_moved, 25);
+    assert_eq!(stats.members_moved, 3);
+    assert_eq!(stats.active_ticks, 4);
+}
+
+#[test]
+fn part4_trade_storms_close_routes() {
+    let mut ocean = new_trading_ocean();
+    let route = TradeRoute {
+        clan_id: CLAN_1.to_string(),
+        from: 0,
+        to: 1,
+        capacity: 5,
+        migrants: 0,
+    };
+    let route = ocean.open_route(route).unwrap();
+    assert!(ocean.storm(route + 1, 2).is_err());
+    ocean.storm(route, 2).unwrap();
+    for _ in 0..3 {
+        ocean.tick();
+    }
+    let stats = ocean.route_stats(route).unwrap();
+    assert_eq!((stats.disrupted_ticks, stats.active_ticks), (2, 1));
+    assert_eq!(stats.shells_moved, 5);
+    assert_eq!(ocean.beach(1).unwrap().size(), 0);
+
+    ocean.set_storms(StormSettings {
+        chance: 1.0,
+        duration: 3,
+        seed: 1,
+    });
+    for _ in 0..6 {
+        ocean.tick();
+    }
+    let stats = ocean.route_stats(route).unwrap();
+    assert_eq!(stats.storms, 3);
+    assert_eq!(stats.shells_moved, 5);
+}
+
+#[test]
+fn part4_scenario_breeding_and_ticks() {
+    let outcome: Outcome = Scenario::new()
+        .crab(CRAB_1, 20, Color::new_blue(), Diet::Plants)
+        .crab(CRAB_2, 30, Color::new_red(), Diet::Plants)
+        .clan(CLAN_1, &[CRAB_1, CRAB_2])
+        .breed(CRAB_1, CRAB_2, CRAB_3)
+        .ticks(10)
+        .run();
+    outcome
+        .expect_population(3)
+        .expect_crab(CRAB_3)
+        .expect_no_crab(CRAB_4)
+        .expect_speed(CRAB_3, 1)
+        .expect_members(CLAN_1, &[CRAB_1, CRAB_2])
+        .expect_clan_size(CLAN_1, 2)
+        .expect_parents(CRAB_3, CRAB_2, CRAB_1)
+        .expect_event(&BeachEvent::Tick { tick: 10 })
+        .expect_event_count(1, |e| matches!(e, BeachEvent::CrabBred { .. }))
+        .expect("the child is the slowest", |beach| {
+            beach.crabs().map(|c| c.speed()).min() == Some(1)
+        });
+    assert_eq!(outcome.beach().ticks(), 10);
+}
+
+#[test]
+#[should_panic(expected = "2. after breeding Ferris and Nobody into Baby")]
+fn part4_scenario_failure_tells_the_story() {
+    Scenario::new()
+        .crab("Ferris", 20, Color::new_blue(), Diet::Plants)
+        .breed("Ferris", "Nobody", "Baby")
+        .run();
+}
+
+#[test]
+fn part4_beach_remove_crabs() {
+    let mut beach = Beach::new();
+    for name in [CRAB_1, CRAB_2, CRAB_1, CRAB_3] {
+        beach.add_crab(new_crab(name, 10));
+    }
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    beach.add_member_to_clan(CLAN_1, CRAB_2);
+
+    let crab = beach.remove_crab(1);
+    assert_eq!(crab.name(), CRAB_2);
+    assert_eq!(beach.size(), 3);
+    assert_eq!(beach.get_clan_system().get_clan_member_names(CLAN_1), vec![CRAB_1]);
+
+    // Its namesake never joined, so it does not keep the membership.
+    beach.remove_crab(0);
+    assert_eq!(beach.get_clan_of(CRAB_1), None);
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    assert_eq!(beach.get_clan_of(CRAB_1), Some(CLAN_1));
+    beach.add_crab(new_crab(CRAB_1, 20));
+    let removed = beach.remove_crabs_by_name(CRAB_1);
+    assert_eq!(removed.iter().map(|c| c.speed()).collect::<Vec<_>>(), vec![10, 20]);
+    assert_ne!(beach.get_clan_of(CRAB_1), Some(CLAN_1));
+    assert_eq!(beach.get_crab(0).name(), CRAB_3);
+    assert!(beach.remove_crabs_by_name(CRAB_4).is_empty());
+}
+
+#[test]
+fn part4_beach_transfer_crab_keeps_clans() {
+    let mut home = Beach::new();
+    let mut away = Beach::new();
+    home.add_crab(new_prinz());
+    home.add_crab(new_sebastian());
+    home.add_member_to_clan(CLAN_2, PRINZ);
+    home.add_member_to_clan(CLAN_1, SEBASTIAN);
+    away.add_crab(new_crab("Local", 10));
+    away.add_member_to_clan(CLAN_2, "Local");
+
+    home.transfer_crab(0, &mut away);
+    assert_eq!(home.size(), 1);
+    assert_eq!(home.get_clan_system().get_clan_member_names(CLAN_1), vec![SEBASTIAN]);
+    assert_eq!(away.get_crab(1).name(), PRINZ);
+    assert!(!away.get_clan_system().has_clan(CLAN_1));
+    assert_eq!(
+        away.get_clan_system().get_clan_member_names(CLAN_2),
+        vec!["Local", PRINZ]
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn part4_persist_json_round_trip() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_prinz());
+    beach.tick();
+    beach.tick();
+    beach.add_crab(new_sebastian());
+    beach.get_crab_mut(1).injure(30);
+    beach.add_member_to_clan(CLAN_1, PRINZ);
+    beach.add_member_to_clan(CLAN_2, SEBASTIAN);
+    beach.set_clan_relation(CLAN_1, CLAN_2, Relation::Rivalry).unwrap();
+
+    let path = std::env::temp_dir().join(format!("ocean-beach-{}.json", std::process::id()));
+    beach.save_to_json(&path).unwrap();
+    let loaded = Beach::load_from_json(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.ticks(), 2);
+    assert_eq!(loaded.size(), 2);
+    assert_eq!((loaded.age(0), loaded.age(1)), (2, 0));
+    let sebastian = loaded.get_crab(1);
+    assert_eq!(sebastian.name(), SEBASTIAN);
+    assert_eq!(sebastian.speed(), 30);
+    assert_eq!(sebastian.diet(), Diet::Plants);
+    assert_eq!(sebastian.health(), MAX_HEALTH - 30);
+    let clans = loaded.get_clan_system();
+    assert_eq!(clans.get_clan_member_names(CLAN_1), vec![PRINZ]);
+    assert_eq!(clans.get_relation(CLAN_2, CLAN_1), Some(Relation::Rivalry));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn part4_persist_rejects_bad_input() {
+    let path = std::env::temp_dir().join(format!("ocean-bad-beach-{}.json", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"{"ticks": 1, "crabs": [], "clans": {"clans": {}, "relations": [["a", "a", "Alliance"]]}}"#,
+    )
+    .unwrap();
+    let error = Beach::load_from_json(&path).unwrap_err();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn part4_genetics_inherited_traits() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 20));
+    beach.add_crab(new_crab(CRAB_2, 30));
+    beach.breed_crabs(0, 1, CRAB_3.to_string());
+    assert_eq!(beach.get_crab(2).speed(), 1);
+    assert_eq!(beach.get_crab(2).generation(), 1);
+    assert!(beach.genetics().is_none());
+
+    beach.set_genetics(Genetics {
+        mutation: 3,
+        diet_inheritance: 1.0,
+        seed: 5,
+        ..Genetics::default()
+    });
+    for n in 0..20 {
+        beach.breed_crabs(0, 1, format!("Kid {}", n));
+    }
+    for kid in beach.crabs().skip(3) {
+        assert!((22..=28).contains(&kid.speed()), "{}", kid.speed());
+        assert_eq!(kid.diet(), Diet::Plants);
+        assert_eq!(kid.generation(), 1);
+        assert_eq!(kid.parents(), Some((beach.handle(0), beach.handle(1))));
+    }
+    assert!(beach.get_crab(0).parents().is_none());
+
+    beach.breed_crabs(2, 3, CRAB_4.to_string());
+    let grandchild = beach.get_crab(beach.size() - 1);
+    assert_eq!(grandchild.generation(), 2);
+    assert!(grandchild.speed() <= 17);
+}
+
+#[test]
+fn part4_genetics_lineage_tree_outlives_crabs() {
+    let mut beach = Beach::new();
+    for name in [CRAB_1, CRAB_2, CRAB_3] {
+        beach.add_crab(new_crab(name, 10));
+    }
+    beach.breed_crabs(0, 1, CRAB_4.to_string());
+    beach.breed_crabs(3, 2, CRAB_5.to_string());
+    let youngest = beach.handle(4);
+    beach.remove_crab(0);
+
+    let tree: FamilyTree = beach.get_lineage(youngest).unwrap();
+    assert_eq!(tree.name.as_deref(), Some(CRAB_5));
+    assert_eq!(tree.generation, 2);
+    assert_eq!(tree.size(), 5);
+    assert_eq!(tree.names_at(1), vec![Some(CRAB_4), Some(CRAB_3)]);
+    assert_eq!(tree.names_at(2), vec![Some(CRAB_1), Some(CRAB_2)]);
+    assert!(tree.names_at(3).is_empty());
+
+    let founder = beach.get_lineage(beach.handle(0)).unwrap();
+    assert_eq!(founder.name.as_deref(), Some(CRAB_2));
+    assert!(founder.parents.is_none());
+    assert_eq!(founder.generation, 0);
+}
+
+#[test]
+fn part4_beach_ids_survive_removal() {
+    let mut beach = Beach::new();
+    let prinz: CrabId = beach.add_crab_with_id(new_prinz());
+    let sebastian = beach.add_crab_with_id(new_sebastian());
+    let child = beach
+        .breed_crabs_by_id(prinz, sebastian, CRAB_1.to_string())
+        .unwrap();
+    assert_eq!(beach.get_crab_by_id(child).unwrap().name(), CRAB_1);
+
+    let removed = beach.remove_crab_by_id(prinz).unwrap();
+    assert_eq!(removed.name(), PRINZ);
+    assert!(beach.get_crab_by_id(prinz).is_none());
+    assert!(beach.remove_crab_by_id(prinz).is_none());
+    assert_eq!(beach.get_crab_by_id(sebastian).unwrap().name(), SEBASTIAN);
+    assert_eq!(beach.get_crab_by_id(child).unwrap().name(), CRAB_1);
+
+    let error = beach
+        .breed_crabs_by_id(prinz, sebastian, CRAB_2.to_string())
+        .unwrap_err();
+    assert_eq!(error, OceanError::UnknownCrabId(prinz));
+    assert_eq!(error.to_string(), format!("no crab with id {}", prinz));
+}
+
+#[test]
+fn part4_life_crabs_age_and_die() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.tick();
+    beach.add_crab(new_crab(CRAB_2, 10));
+    beach.set_life_rules(LifeRules {
+        lifespan: 3,
+        maturity: 2,
+        senescence: 2,
+        ..LifeRules::default()
+    });
+
+    let report: TickReport = beach.tick();
+    assert_eq!(report.tick, 2);
+    assert!(report.deaths.is_empty());
+    assert_eq!(beach.get_crab(0).speed(), 10);
+    assert_eq!(beach.get_crab(1).speed(), 11);
+
+    beach.tick();
+    beach.tick();
+    let report = beach.tick();
+    assert_eq!(
+        report.deaths,
+        vec![Death {
+            name: CRAB_2.to_string(),
+            cause: Cause::OldAge
+        }]
+    );
+    assert_eq!(report.population, 0);
+}
+
+#[test]
+fn part4_life_hungry_crabs_hunt_or_starve() {
+    let mut ocean = Ocean::new();
+    let reef = ocean.generate_reef(0, 0, 0, 3);
+    let mut beach = Beach::new();
+    let mut grazer = new_crab(CRAB_1, 10);
+    grazer.discover_reef(reef);
+    beach.add_crab(grazer);
+    beach.add_crab(new_crab(CRAB_2, 10));
+    beach.get_crab_mut(0).injure(50);
+    beach.set_life_rules(LifeRules {
+        appetite: 1,
+        starvation: 40,
+        nourishment: 5,
+        ..LifeRules::default()
+    });
+
+    let report = beach.tick();
+    assert_eq!(report.meals.len(), 2);
+    assert_eq!(report.meals_of(Diet::Plants), 1);
+    assert_eq!(beach.get_crab(0).health(), 55);
+    assert_eq!(beach.get_crab(1).health(), 60);
+    assert_eq!(beach.ticks_since_meal(1), 1);
+
+    beach.tick();
+    let report = beach.tick();
+    assert_eq!(report.deaths_from(Cause::Starvation), 1);
+    assert!(beach.find_crabs_by_name(CRAB_2).is_empty());
+    assert_eq!(beach.get_crab(0).health(), 65);
+
+    let report = beach.tick();
+    assert!(!report.meals[0].caught);
+    assert_eq!(beach.get_crab(0).health(), 25);
+}
+
+#[test]
+fn part4_predators_catch_slow_crabs() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 5));
+    beach.add_crab(new_crab(CRAB_2, 20));
+    beach.add_member_to_clan("clan1", CRAB_1);
+    beach.add_predator(Box::new(Seagull::new(10)));
+
+    let report = beach.tick();
+    assert_eq!(
+        report.hunts,
+        vec![Hunt {
+            predator: "seagull".to_string(),
+            prey: CRAB_1.to_string(),
+            caught: true,
+        }]
+    );
+    assert_eq!(report.deaths_from(Cause::Predation), 1);
+    assert_eq!(report.population, 1);
+    assert_eq!(beach.get_clan_system().get_clan_member_count("clan1"), 0);
+
+    let report = beach.tick();
+    assert!(!report.hunts[0].caught);
+    assert_eq!(beach.size(), 1);
+
+    beach.set_crab_protected(0, true);
+    assert!(beach.hunt_crabs().is_empty());
+}
+
+#[test]
+fn part4_predators_octopus_ambushes_fast_crabs() {
+    let mut beach = Beach::new();
+    for k in 0..20 {
+        beach.add_crab(new_crab(&format!("crab{}", k), 30));
+    }
+    beach.add_predator(Box::new(Octopus::new(10, 7)));
+    let eaten: usize = (0..20)
+        .map(|_| beach.tick().deaths_from(Cause::Predation))
+        .sum();
+    assert!(eaten > 0 && eaten < 20);
+    assert_eq!(beach.size(), 20 - eaten);
+}
+
+#[test]
+fn part4_clans_subclans_count_transitively() {
+    let mut beach = Beach::new();
+    for (name, clan) in [(CRAB_1, CLAN_1), (CRAB_2, CLAN_2), (CRAB_3, CLAN_3)] {
+        beach.add_crab(new_crab(name, 10));
+        beach.add_member_to_clan(clan, name);
+    }
+    beach.create_subclan(CLAN_1, CLAN_2).unwrap();
+    beach.create_subclan(CLAN_2, CLAN_3).unwrap();
+    let clans = beach.get_clan_system();
+    assert_eq!(clans.get_clan_member_count(CLAN_1), 1);
+    assert_eq!(clans.get_clan_member_count_transitive(CLAN_1), 3);
+    assert_eq!(clans.parent_clan(CLAN_3), Some(CLAN_2));
+    assert_eq!(
+        beach.create_subclan(CLAN_3, CLAN_1).unwrap_err(),
+        format!(
+            "{} cannot become a sub-clan of {}, which is part of it",
+            CLAN_1, CLAN_3
+        )
+    );
+
+    beach.remove_member_from_clan(CLAN_2, CRAB_2);
+    let clans = beach.get_clan_system();
+    assert_eq!(clans.parent_clan(CLAN_3), Some(CLAN_1));
+    assert_eq!(clans.get_clan_member_count_transitive(CLAN_1), 2);
+}
+
+#[test]
+fn part4_clans_alliances_compete() {
+    let mut beach = Beach::new();
+    for (name, speed, clan) in [
+        (CRAB_1, 10, CLAN_1),
+        (CRAB_2, 30, CLAN_2),
+        (CRAB_3, 15, CLAN_3),
+    ] {
+        beach.add_crab(new_crab(name, speed));
+        beach.add_member_to_clan(clan, name);
+    }
+    assert_eq!(
+        beach.get_winner_clan(CLAN_1, CLAN_3),
+        Ok(Some(CLAN_3.to_string()))
+    );
+
+    beach.form_alliance(CLAN_1, CLAN_2).unwrap();
+    assert_eq!(beach.get_alliance_avg_speed(CLAN_1), Some(20.0));
+    assert_eq!(
+        beach.get_winner_alliance(CLAN_1, CLAN_3),
+        Ok(Some(CLAN_1.to_string()))
+    );
+    assert!(beach.get_winner_alliance(CLAN_2, CLAN_1).is_err());
+}
+
+#[test]
+fn part4_errors_try_variants_do_not_panic() {
+    let mut beach = Beach::new();
+    assert_eq!(beach.try_get_crab(0).unwrap_err(), OceanError::EmptyBeach);
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 20));
+    assert_eq!(beach.try_get_crab(1).unwrap().name(), CRAB_2);
+
+    let err = beach.try_breed_crabs(0, 5, String::from("a")).unwrap_err();
+    assert_eq!(err, OceanError::InvalidIndex { index: 5, size: 2 });
+    assert_eq!(err.to_string(), "no crab at index 5 on a beach of 2 crabs");
+    let err: Box<dyn std::error::Error> = Box::new(err);
+    assert!(err.source().is_none());
+
+    beach.try_add_member_to_clan(CLAN_1, CRAB_1).unwrap();
+    assert_eq!(
+        beach.try_add_member_to_clan(CLAN_1, CRAB_1),
+        Err(OceanError::DuplicateMembership {
+            crab: CRAB_1.to_string(),
+            clan: CLAN_1.to_string(),
+        })
+    );
+    assert_eq!(
+        beach.get_winner_clan(CLAN_1, CLAN_2),
+        Err(OceanError::UnknownClan(CLAN_2.to_string()))
+    );
+}
+
+#[test]
+fn part4_events_observers_see_every_change() {
+    #[derive(Default)]
+    struct Log(Vec<String>);
+
+    impl BeachObserver for Log {
+        fn on_crab_added(&mut self, name: &str) {
+            self.0.push(format!("added {}", name));
+        }
+
+        fn on_crab_bred(&mut self, parent1: &str, parent2: &str, child: &str) {
+            self.0
+                .push(format!("bred {} from {} and {}", child, parent1, parent2));
+        }
+
+        fn on_clan_member_added(&mut self, clan_id: &str, crab_name: &str) {
+            self.0.push(format!("{} joined {}", crab_name, clan_id));
+        }
+
+        fn on_crab_removed(&mut self, name: &str) {
+            self.0.push(format!("removed {}", name));
+        }
+    }
+
+    let mut beach = Beach::new();
+    let log = Rc::new(RefCell::new(Log::default()));
+    beach.register_observer(log.clone());
+    beach.add_crab(new_prinz());
+    beach.add_crab(new_sebastian());
+    beach.breed_crabs(0, 1, String::from(CRAB_1));
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    beach.remove_crab(0);
+    beach.pop_crab();
+    beach.tick();
+
+    assert_eq!(
+        log.borrow().0,
+        vec![
+            format!("added {}", PRINZ),
+            format!("added {}", SEBASTIAN),
+            format!("added {}", CRAB_1),
+            format!("bred {} from {} and {}", CRAB_1, PRINZ, SEBASTIAN),
+            format!("{} joined {}", CRAB_1, CLAN_1),
+            format!("removed {}", PRINZ),
+            format!("removed {}", CRAB_1),
+        ]
+    );
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn part4_parallel_statistics_match_serial() {
+    let mut beach = Beach::new();
+    beach.populate_random(3 * MIN_CRABS_PER_THREAD, 11);
+    let names: Vec<String> = beach.crabs().map(|c| c.name().to_string()).collect();
+    for (k, name) in names.iter().enumerate().step_by(7) {
+        beach.add_member_to_clan(if k % 2 == 0 { CLAN_1 } else { CLAN_2 }, name);
+    }
+    beach.add_member_to_clan(CLAN_1, &names[0]);
+
+    for threads in [1, 3, 8] {
+        assert_eq!(
+            beach.par_get_fastest_crab(threads).unwrap().name(),
+            beach.get_fastest_crab().unwrap().name()
+        );
+        assert_eq!(beach.par_find_crabs_by_name(&names[9000], threads).len(), 1);
+        let avg = beach.par_get_crabs_avg_speed(CLAN_1, threads).unwrap();
+        assert!((avg - beach.get_crabs_avg_speed(CLAN_1).unwrap()).abs() < 1e-9);
+        assert_eq!(
+            beach.par_get_winner_clan(CLAN_1, CLAN_2, threads),
+            beach.get_winner_clan(CLAN_1, CLAN_2)
+        );
+        let slow = beach.par_crabs(threads, |crab| (crab.speed < 5).then_some(crab.index));
+        assert!(slow.windows(2).all(|w| w[0] < w[1]));
+    }
+}
+
+#[test]
+fn part4_beach_name_index_stays_in_step() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 20));
+    beach.add_crab(new_crab(CRAB_1, 30));
+    beach.breed_crabs(0, 1, String::from(CRAB_3));
+    assert_eq!(beach.count_crabs_named(CRAB_1), 2);
+    let speeds: Vec<u32> = beach
+        .find_crabs_by_name(CRAB_1)
+        .iter()
+        .map(|c| c.speed())
+        .collect();
+    assert_eq!(speeds, vec![10, 30]);
+    assert_eq!(beach.count_crabs_named(CRAB_3), 1);
+
+    beach.remove_crab(0);
+    assert_eq!(beach.find_crabs_by_name(CRAB_1)[0].speed(), 30);
+    let mut other = Beach::new();
+    beach.transfer_crab(0, &mut other);
+    assert_eq!(beach.count_crabs_named(CRAB_2), 0);
+    assert_eq!(other.count_crabs_named(CRAB_2), 1);
+    beach.pop_crab();
+    assert!(beach.find_crabs_by_name(CRAB_3).is_empty());
+    assert_eq!(beach.remove_crabs_by_name(CRAB_1).len(), 1);
+    assert_eq!(beach.size(), 0);
+    assert_eq!(beach.count_crabs_named(CRAB_1), 0);
+
+    let error = beach
+        .import_stream(
+            vec![
+                Ok::<_, String>(new_crab(CRAB_4, 1)),
+                Ok(new_crab(CRAB_4, 2)),
+            ],
+            |_| {},
+        )
+        .unwrap_err();
+    assert!(error.contains(CRAB_4), "{}", error);
+    assert_eq!(beach.count_crabs_named(CRAB_4), 1);
+}
+
+#[test]
+fn part4_food_feeding_matches_diets() {
+    let mut beach = Beach::new();
+    let mut fisher = Crab::new(String::from(CRAB_1), 10, Color::new_blue(), Diet::Fish);
+    fisher.injure(30);
+    beach.add_crab(fisher);
+    beach.add_crab(new_crab(CRAB_2, 10));
+    beach.add_crab(new_crab(CRAB_3, 10));
+    beach.add_food(Food::Algae, 1);
+    beach.add_food(Food::Plankton, 5);
+    assert_eq!(Food::for_diet(Diet::Fish), Food::Plankton);
+    assert_eq!(beach.food_supply().total(), 6);
+
+    let report = beach.feed_all();
+    assert_eq!(report.fed, vec![CRAB_1.to_string(), CRAB_2.to_string()]);
+    assert_eq!(report.hungry, vec![CRAB_3.to_string()]);
+    assert_eq!(beach.food_supply().stock(Food::Algae), 0);
+    assert_eq!(beach.food_supply().stock(Food::Plankton), 4);
+    assert_eq!(beach.get_crab(0).health(), 80);
+    assert_eq!(beach.get_crab(2).health(), 90);
+    assert_eq!(beach.get_crab(2).speed(), 9);
+
+    assert_eq!(beach.food_supply_mut().take(Food::Plankton, 8), 4);
+    assert_eq!(beach.feed_all().hungry.len(), 3);
+}
+
+#[test]
+fn part4_life_crab_states_advance() {
+    let mut crab = new_crab(CRAB_1, 10);
+    assert_eq!(crab.state(), LifeState::Healthy);
+    for _ in 0..HUNGRY_AT {
+        crab.advance();
+    }
+    assert_eq!(crab.state(), LifeState::Hungry);
+    assert_eq!(crab.age(), HUNGRY_AT as u64);
+    crab.feed();
+    assert_eq!(crab.state(), LifeState::Healthy);
+
+    crab.set_hunger(STARVING_AT - 1);
+    assert_eq!(crab.advance(), LifeState::Starving);
+    assert_eq!(crab.health(), 100 - STARVATION_DAMAGE);
+    crab.set_health(STARVATION_DAMAGE);
+    assert_eq!(crab.advance(), LifeState::Dead);
+    crab.feed();
+    assert_eq!(crab.advance(), LifeState::Dead);
+    assert!(crab.is_dead());
+
+    let mut beach = Beach::new();
+    beach.add_crab(crab);
+    beach.add_crab(new_crab(CRAB_2, 10));
+    beach.add_crab(new_crab(CRAB_3, 10));
+    beach.get_crab_mut(2).set_hunger(HUNGRY_AT);
+    let counts = beach.count_by_state();
+    assert_eq!(counts.get(&LifeState::Dead), Some(&1));
+    assert_eq!(counts.get(&LifeState::Hungry), Some(&1));
+    assert_eq!(counts.get(&LifeState::Starving), None);
+
+    let dead = beach.remove_dead_crabs();
+    assert_eq!(dead.len(), 1);
+    assert_eq!(dead[0].name(), CRAB_1);
+    assert_eq!(beach.size(), 2);
+}
+
+#[test]
+fn part4_genetics_mendelian_colors() {
+    let red = Allele::new(Color::new_red(), 2);
+    let blue = Allele::new(Color::new_blue(), 1);
+    assert_eq!(
+        Genotype::new(blue.clone(), red.clone()).phenotype(),
+        Color::new_red()
+    );
+    assert_eq!(
+        Genotype::new(blue.clone(), blue.clone()).phenotype(),
+        Color::new_blue()
+    );
+    assert_eq!(
+        Genotype::homozygous(Color::new_green()).phenotype(),
+        Color::new_green()
+    );
+    let even = Genotype::new(
+        Allele::new(Color::new(100, 0, 0), 1),
+        Allele::new(Color::new(0, 0, 50), 1),
+    );
+    assert_eq!(even.phenotype(), Color::new(50, 0, 25));
+
+    let mut beach = Beach::new();
+    let carrier = Genotype::new(red.clone(), blue.clone());
+    for name in [CRAB_1, CRAB_2] {
+        beach.add_crab(Crab::with_genotype(
+            name.to_string(),
+            10,
+            carrier.clone(),
+            Diet::Fish,
+        ));
+    }
+    assert_eq!(beach.get_crab(0).color(), &Color::new_red());
+    beach.set_genetics(Genetics {
+        colors: ColorInheritance::Mendelian,
+        seed: 3,
+        ..Genetics::default()
+    });
+    for n in 0..40 {
+        beach.breed_crabs(0, 1, format!("Kid {}", n));
+    }
+    let blue_kids = beach
+        .crabs()
+        .skip(2)
+        .filter(|kid| kid.color() == &Color::new_blue())
+        .count();
+    assert!(blue_kids > 0 && blue_kids < 20, "{}", blue_kids);
+    for kid in beach.crabs().skip(2) {
+        assert_eq!(kid.color(), &kid.genotype().phenotype());
+    }
+}
+
+#[test]
+fn part4_tournament_knockout_between_clans() {
+    let mut beach = Beach::new();
+    let clans = [CLAN_1, CLAN_2, CLAN_3, "Claw Crew", "Shell Shock"];
+    for (i, clan) in clans.iter().enumerate() {
+        let name = format!("Crab {}", i);
+        beach.add_crab(new_crab(&name, 10 * (i as u32 + 1)));
+        beach.add_member_to_clan(clan, &name);
+    }
+
+    let result = beach.run_tournament(Format::Knockout, &clans).unwrap();
+    let tournament = &result.tournament;
+    assert_eq!(tournament.total_days(), 3);
+    assert!(tournament.is_finished());
+    assert!(tournament.is_alive("Shell Shock"));
+    assert!(!tournament.is_alive(CLAN_3));
+
+    // The losers of the first two rounds are ranked by their average speed.
+    let table: Vec<&str> = result
+        .table
+        .iter()
+        .map(|s| s.standing.id.as_str())
+        .collect();
+    assert_eq!(
+        table,
+        vec!["Shell Shock", CLAN_3, "Claw Crew", CLAN_2, CLAN_1]
+    );
+    assert_eq!(result.table[0].standing.wins, 3);
+    assert_eq!(result.table[2].avg_speed, 40.0);
+
+    // Three byes and one match, then two matches, then the final.
+    assert_eq!(tournament.history().len(), 7);
+    assert_eq!(tournament.matches_of(CLAN_1).len(), 2);
+    let final_match = tournament.head_to_head(CLAN_3, "Shell Shock");
+    assert_eq!(final_match.len(), 1);
+    assert_eq!(final_match[0].day, 2);
+    assert_eq!(final_match[0].winner(), Some("Shell Shock"));
+
+    assert_eq!(
+        beach
+            .run_tournament(Format::RoundRobin, &[CLAN_1, "Nobody"])
+            .unwrap_err(),
+        OceanError::UnknownClan("Nobody".to_string())
+    );
+}
+
+#[test]
+fn part4_beach_merge_and_split() {
+    let mut north = Beach::new();
+    north.add_crab(new_crab(CRAB_1, 10));
+    north.add_crab(new_crab(CRAB_2, 20));
+    north.add_member_to_clan(CLAN_1, CRAB_1);
+    north.add_member_to_clan(CLAN_2, CRAB_2);
+
+    let mut south = Beach::new();
+    south.add_crab(new_crab(CRAB_3, 30));
+    south.add_crab(new_crab(CRAB_4, 40));
+    south.add_member_to_clan(CLAN_1, CRAB_1);
+    south.add_member_to_clan(CLAN_1, CRAB_3);
+    south.add_member_to_clan(CLAN_3, CRAB_4);
+    south.create_subclan(CLAN_1, CLAN_3).unwrap();
+
+    let beach = north.merge(south);
+    let names: Vec<&str> = beach.crabs().map(|crab| crab.name()).collect();
+    assert_eq!(names, vec![CRAB_1, CRAB_2, CRAB_3, CRAB_4]);
+    let clans = beach.get_clan_system();
+    assert_eq!(clans.get_clan_member_names(CLAN_1), vec![CRAB_1, CRAB_3]);
+    assert_eq!(clans.parent_clan(CLAN_3), Some(CLAN_1));
+
+    let (slow, fast) = beach.split_by(|crab| crab.speed() < 25);
+    let names: Vec<&str> = fast.crabs().map(|crab| crab.name()).collect();
+    assert_eq!(names, vec![CRAB_3, CRAB_4]);
+    assert_eq!(slow.size(), 2);
+    assert_eq!(
+        slow.get_clan_system().get_clan_member_names(CLAN_1),
+        vec![CRAB_1]
+    );
+    assert!(!slow.get_clan_system().has_clan(CLAN_3));
+    let clans = fast.get_clan_system();
+    assert_eq!(clans.get_clan_member_names(CLAN_1), vec![CRAB_3]);
+    assert!(!clans.has_clan(CLAN_2));
+    assert_eq!(clans.parent_clan(CLAN_3), Some(CLAN_1));
+}
+
+#[test]
+fn part4_query_builder_filters_sorts_and_limits() {
+    let mut beach = Beach::new();
+    beach.add_crab(Crab::new(
+        String::from(CRAB_1),
+        30,
+        Color::new(250, 10, 0),
+        Diet::Shellfish,
+    ));
+    beach.add_crab(Crab::new(
+        String::from(CRAB_2),
+        20,
+        Color::new_red(),
+        Diet::Shellfish,
+    ));
+    beach.add_crab(Crab::new(
+        String::from(CRAB_3),
+        40,
+        Color::new_red(),
+        Diet::Shellfish,
+    ));
+    beach.add_crab(Crab::new(
+        String::from(CRAB_4),
+        50,
+        Color::new_blue(),
+        Diet::Shellfish,
+    ));
+    beach.add_crab(new_crab(CRAB_5, 60));
+    for name in [CRAB_1, CRAB_3, CRAB_4, CRAB_5] {
+        beach.add_member_to_clan(CLAN_1, name);
+    }
+
+    let names = |crabs: Vec<&Crab>| -> Vec<String> {
+        crabs.iter().map(|crab| crab.name().to_string()).collect()
+    };
+    let found = beach
+        .crab_query()
+        .speed_gt(10)
+        .diet(Diet::Shellfish)
+        .color_near(Color::new_red(), 0.2)
+        .in_clan(CLAN_1)
+        .collect();
+    assert_eq!(names(found), vec![CRAB_1, CRAB_3]);
+
+    let fastest = beach
+        .crab_query()
+        .speed_lt(60)
+        .order_by(Column::Speed, Direction::Descending)
+        .limit(2)
+        .collect();
+    assert_eq!(names(fastest), vec![CRAB_4, CRAB_3]);
+    assert_eq!(beach.crab_query().named(CRAB_2).count(), 1);
+    assert_eq!(Color::new_red().distance(&Color::new_red()), 0.0);
+    assert_eq!(
+        Color::new(0, 0, 0).distance(&Color::new(255, 255, 255)),
+        1.0
+    );
+}
+
+#[test]
+fn part4_clans_single_membership() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_prinz());
+    beach.add_crab(new_sebastian());
+    beach.add_member_to_clan(CLAN_1, PRINZ);
+    beach.add_member_to_clan(CLAN_1, PRINZ);
+    beach.add_member_to_clan(CLAN_1, SEBASTIAN);
+    assert_eq!(beach.get_clan_system().get_clan_member_count(CLAN_1), 2);
+    assert_eq!(beach.get_clan_of(PRINZ), Some(CLAN_1));
+
+    assert_eq!(
+        beach.try_add_member_to_clan(CLAN_2, PRINZ),
+        Err(OceanError::DuplicateMembership {
+            crab: PRINZ.to_string(),
+            clan: CLAN_1.to_string(),
+        })
+    );
+    beach.move_member(PRINZ, CLAN_1, CLAN_2).unwrap();
+    assert_eq!(beach.get_clan_of(PRINZ), Some(CLAN_2));
+    assert_ne!(beach.get_clan_of(PRINZ), Some(CLAN_1));
+    assert!(beach.move_member(PRINZ, CLAN_1, CLAN_3).is_err());
+
+    // Joining another clan moves the crab, dissolving the clan it leaves empty.
+    beach.add_member_to_clan(CLAN_1, PRINZ);
+    assert!(!beach.get_clan_system().has_clan(CLAN_2));
+    assert_eq!(beach.remove_member(SEBASTIAN).as_deref(), Some(CLAN_1));
+    assert_eq!(beach.get_clan_of(SEBASTIAN), None);
+    assert_eq!(beach.remove_member(SEBASTIAN), None);
+    assert_eq!(
+        beach.get_clan_system().get_clan_member_names(CLAN_1),
+        vec![PRINZ]
+    );
+}
+
+#[test]
+fn part4_beach_generate_random_is_reproducible() {
+    let describe = |beach: &Beach| -> Vec<(String, u32, Color, Diet, Option<String>)> {
+        beach
+            .crabs()
+            .map(|crab| {
+                (
+                    crab.name().to_string(),
+                    crab.speed(),
+                    crab.color().clone(),
+                    crab.diet(),
+                    beach.get_clan_of(crab.name()).map(String::from),
+                )
+            })
+            .collect()
+    };
+    let beach = Beach::generate_random(50, 4, 7);
+    assert_eq!(beach.size(), 50);
+    assert_eq!(
+        describe(&beach),
+        describe(&Beach::generate_random(50, 4, 7))
+    );
+    assert_ne!(
+        describe(&beach),
+        describe(&Beach::generate_random(50, 4, 8))
+    );
+
+    let clans = beach.get_clan_system();
+    assert!(clans.get_clan_count() <= 4);
+    let members: usize = clans
+        .clan_ids()
+        .map(|id| clans.get_clan_member_count(id))
+        .sum();
+    assert_eq!(members, 50);
+    assert!(clans.clan_ids().all(|id| id.starts_with("Clan ")));
+    assert_eq!(
+        Beach::generate_random(5, 0, 7)
+            .get_clan_system()
+            .get_clan_count(),
+        0
+    );
+}
+
+#[test]
+fn part4_report_beach_summary() {
+    let mut beach = Beach::new();
+    assert!(beach.report().contains(&tr(Message::ReportNothing, &[])));
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 30));
+    beach.add_crab(Crab::new(
+        String::from(CRAB_3),
+        20,
+        Color::new(255, 128, 0),
+        Diet::Fish,
+    ));
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    beach.add_member_to_clan(CLAN_2, CRAB_2);
+    beach.add_member_to_clan(CLAN_2, CRAB_3);
+
+    assert_eq!(
+        beach.get_crab(2).to_string(),
+        format!("{}: speed 20, Fish diet, color #ff8000", CRAB_3)
+    );
+    assert_eq!(beach.to_string(), "3 crabs in 2 clans");
+    assert_eq!(
+        beach.get_clan_system().to_string(),
+        format!(
+            "{} (1 members): {}\n{} (2 members): {}, {}",
+            CLAN_1, CRAB_1, CLAN_2, CRAB_2, CRAB_3
+        )
+    );
+
+    let report = beach.report();
+    let lines: Vec<&str> = report.lines().collect();
+    assert_eq!(lines[1], "Population: 3");
+    assert_eq!(lines[2], format!("Fastest crab: {} (speed 30)", CRAB_2));
+    assert_eq!(
+        lines[5],
+        format!("  1. {}: 2 members, average speed 25.0", CLAN_2)
+    );
+    assert!(report.contains("  Fish: 1 (33%)\n  Plants: 2 (67%)\n"));
+}
+
+#[test]
+fn part4_csv_round_trip_and_errors() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab("Pinchy, the Second", 12));
+    beach.add_crab(Crab::new(
+        String::from(CRAB_2),
+        30,
+        Color::new(255, 128, 0),
+        Diet::Shellfish,
+    ));
+    beach.add_member_to_clan(CLAN_1, "Pinchy, the Second");
+
+    let mut csv = Vec::new();
+    beach.export_csv(&mut csv).unwrap();
+    let text = String::from_utf8(csv).unwrap();
+    assert_eq!(
+        text,
+        format!(
+            "name,speed,color,diet,clan\n\"Pinchy, the Second\",12,#0000ff,Plants,{}\n{},30,#ff8000,Shellfish,\n",
+            CLAN_1, CRAB_2
+        )
+    );
+
+    let mut copy = Beach::new();
+    assert_eq!(copy.import_csv(text.as_bytes()), Ok(2));
+    assert_eq!(copy.get_crab(0).name(), "Pinchy, the Second");
+    assert_eq!(copy.get_crab(1).color(), &Color::new(255, 128, 0));
+    assert_eq!(copy.get_crab(1).diet(), Diet::Shellfish);
+    assert_eq!(copy.get_clan_of("Pinchy, the Second"), Some(CLAN_1));
+    assert_eq!(copy.get_clan_of(CRAB_2), None);
+
+    let bad = "name,speed,color,diet,clan\nA,1,#000000,Fish,\n\nB,fast,#000000,Fish,\n";
+    let error = copy.import_csv(bad.as_bytes()).unwrap_err();
+    assert_eq!(
+        error,
+        OceanError::Csv {
+            line: 4,
+            reason: tr(Message::CsvBadNumber, &[&"fast"]),
+        }
+    );
+    assert_eq!(error.to_string(), "line 4: not a number: fast");
+    assert_eq!(copy.size(), 2);
+    assert!(copy.import_csv("name,speed\n".as_bytes()).is_err());
+    assert!(copy
+        .import_csv("name,speed,color,diet,clan\nA,1,red,Fish,\n".as_bytes())
+        .is_err());
+    assert!(copy
+        .import_csv("name,speed,color,diet,clan\n\"A,1,#000000,Fish,\n".as_bytes())
+        .is_err());
+}
+
+#[test]
+fn part4_analysis_clan_stats_and_metrics() {
+    let mut beach = Beach::new();
+    for (name, speed, blue, diet) in [
+        (CRAB_1, 10, 201, Diet::Fish),
+        (CRAB_2, 20, 201, Diet::Plants),
+        (CRAB_3, 60, 198, Diet::Plants),
+    ] {
+        beach.add_crab(Crab::new(
+            String::from(name),
+            speed,
+            Color::new(0, 100, blue),
+            diet,
+        ));
+        beach.add_member_to_clan(CLAN_1, name);
+    }
+    for (name, speed) in [(CRAB_4, 25), (CRAB_5, 26)] {
+        beach.add_crab(new_crab(name, speed));
+        beach.add_member_to_clan(CLAN_2, name);
+    }
+
+    let stats = beach.clan_stats(CLAN_1).unwrap();
+    assert_eq!(stats.member_count, 3);
+    assert_eq!((stats.min_speed, stats.max_speed), (10, 60));
+    assert_eq!(stats.mean_speed, 30.0);
+    assert_eq!(stats.median_speed, 20.0);
+    assert!((stats.speed_std_dev - 21.602).abs() < 0.001);
+    assert_eq!(stats.dominant_diet, Diet::Plants);
+    assert_eq!(stats.average_color, Color::new(0, 100, 200));
+    assert_eq!(beach.clan_stats(CLAN_3), None);
+
+    let winner = |metric| beach.get_winner_clan_by(CLAN_1, CLAN_2, metric).unwrap();
+    assert_eq!(winner(SpeedMetric::Mean).as_deref(), Some(CLAN_1));
+    assert_eq!(winner(SpeedMetric::Median).as_deref(), Some(CLAN_2));
+    assert_eq!(winner(SpeedMetric::Max).as_deref(), Some(CLAN_1));
+    assert_eq!(
+        beach.get_winner_clan(CLAN_1, CLAN_2).unwrap(),
+        winner(SpeedMetric::default())
+    );
+}
+
+#[test]
+fn part4_beach_mutable_access_and_rename() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 1));
+    beach.add_crab(new_crab(CRAB_2, 2));
+    beach.add_crab(new_crab(CRAB_1, 3));
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+
+    beach.for_each_crab_mut(|crab| crab.set_speed(crab.speed() * 2));
+    for mut crab in beach.crabs_mut() {
+        let speed = crab.speed();
+        crab.set_speed(speed + 1);
+    }
+    let speeds: Vec<u32> = beach.crabs().map(|crab| crab.speed()).collect();
+    assert_eq!(speeds, vec![3, 5, 7]);
+
+    // The membership goes with the renamed crab, not with its old namesake.
+    beach.rename_crab(0, CRAB_3.to_string());
+    assert_eq!(beach.find_crabs_by_name(CRAB_1).len(), 1);
+    assert_eq!(beach.find_crabs_by_name(CRAB_3)[0].speed(), 3);
+    assert_eq!(beach.get_clan_of(CRAB_1), None);
+    assert_eq!(beach.get_clan_of(CRAB_3), Some(CLAN_1));
+
+    beach.rename_crab(2, CRAB_3.to_string());
+    assert!(beach.find_crabs_by_name(CRAB_1).is_empty());
+    let renamed: Vec<u32> = beach
+        .find_crabs_by_name(CRAB_3)
+        .iter()
+        .map(|crab| crab.speed())
+        .collect();
+    assert_eq!(renamed, vec![3, 7]);
+    assert_eq!(beach.get_clan_of(CRAB_1), None);
+    assert_eq!(beach.get_clan_of(CRAB_3), Some(CLAN_1));
+    assert_eq!(beach.get_clan_of(CRAB_2), None);
+}
+
+#[test]
+fn part4_territory_finds_nearby_crabs() {
+    let mut beach = Beach::new();
+    for (name, speed) in [(CRAB_1, 1), (CRAB_2, 2), (CRAB_3, 3), (CRAB_4, 4)] {
+        beach.add_crab(new_crab(name, speed));
+    }
+    beach.set_territory(Territory {
+        cell_size: 10,
+        breeding_distance: Some(5),
+        ..Territory::new(100, 100)
+    });
+    assert!(beach.move_crab(0, Position::new(10, 10)).is_ok());
+    assert!(beach.move_crab(1, Position::new(13, 14)).is_ok());
+    assert!(beach.move_crab(2, Position::new(40, 10)).is_ok());
+    assert!(beach.move_crab(3, Position::new(95, 95)).is_ok());
+    assert_eq!(
+        beach.move_crab(3, Position::new(100, 5)),
+        Err(OceanError::OutsideTerritory {
+            position: Position::new(100, 5),
+            width: 100,
+            height: 100,
+        })
+    );
+    assert!(beach.move_crab(4, Position::new(1, 1)).is_err());
+
+    let names = |crabs: Vec<&Crab>| -> Vec<String> {
+        crabs.iter().map(|crab| crab.name().to_string()).collect()
+    };
+    assert_eq!(
+        names(beach.crabs_within_radius(Position::new(10, 10), 5)),
+        vec![CRAB_1, CRAB_2]
+    );
+    assert_eq!(
+        names(beach.crabs_within_radius(Position::new(25, 10), 15)),
+        vec![CRAB_1, CRAB_2, CRAB_3]
+    );
+    assert_eq!(
+        beach.nearest_crab(Position::new(30, 12)).unwrap().name(),
+        CRAB_3
+    );
+    assert_eq!(
+        beach.nearest_crab(Position::new(70, 70)).unwrap().name(),
+        CRAB_4
+    );
+
+    // Crabs moved through a mutable reference are still found.
+    beach.get_crab_mut(3).set_position(Position::new(12, 12));
+    assert_eq!(
+        beach.nearest_crab(Position::new(12, 11)).unwrap().name(),
+        CRAB_4
+    );
+    assert_eq!(beach.crabs_within_radius(Position::new(10, 10), 5).len(), 3);
+    assert!(beach.move_crab(0, Position::new(50, 50)).is_ok());
+    assert_eq!(
+        names(beach.crabs_within_radius(Position::new(10, 10), 5)),
+        vec![CRAB_2, CRAB_4]
+    );
+
+    // Parents must be within the breeding distance of each other.
+    assert!(beach.can_breed(1, 3).is_ok());
+    assert!(beach.can_breed(0, 1).is_err());
+    beach.remove_crab(1);
+    assert_eq!(
+        beach.nearest_crab(Position::new(13, 14)).unwrap().name(),
+        CRAB_4
+    );
+}
+
+#[test]
+fn part4_names_registry_keeps_names_unique() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 1));
+    beach.add_crab(new_crab(CRAB_1, 2));
+    assert_eq!(audit_names(&beach).len(), 1);
+
+    beach.set_name_registry(Some(NameRegistry::new(NamePolicy::Reject)));
+    assert!(beach.register_crab(new_crab(CRAB_2, 3)).is_ok());
+    assert_eq!(
+        beach.register_crab(new_crab(CRAB_2, 4)),
+        Err(OceanError::DuplicateName(CRAB_2.to_string()))
+    );
+    assert_eq!(
+        beach.try_breed_crabs(0, 2, CRAB_2.to_string()),
+        Err(OceanError::DuplicateName(CRAB_2.to_string()))
+    );
+    assert_eq!(beach.size(), 3);
+
+    beach.set_name_registry(Some(NameRegistry::new(NamePolicy::Suffix)));
+    beach.add_crab(new_crab(CRAB_2, 5));
+    assert!(beach.try_breed_crabs(0, 2, CRAB_2.to_string()).is_ok());
+    let names: Vec<&str> = beach.crabs().map(|crab| crab.name()).collect();
+    assert_eq!(names, vec![CRAB_1, CRAB_1, CRAB_2, "Mira II", "Mira III"]);
+
+    // Crabs transferred in keep their clan under their new name.
+    let mut other = Beach::new();
+    other.add_crab(new_crab(CRAB_2, 6));
+    other.add_member_to_clan(CLAN_2, CRAB_2);
+    other.transfer_crab(0, &mut beach);
+    assert_eq!(beach.get_clan_of("Mira IV"), Some(CLAN_2));
+    assert_eq!(beach.get_clan_of(CRAB_2), None);
+}
+
+#[test]
+fn part4_encounter_faster_crab_eats_compatible_prey() {
+    assert!(Diet::Shellfish.can_eat(&Diet::Fish));
+    assert!(Diet::Fish.can_eat(&Diet::Plants));
+    assert!(!Diet::Fish.can_eat(&Diet::Shellfish));
+    assert!(!Diet::Plants.can_eat(&Diet::Plants));
+
+    let mut beach = Beach::new();
+    let mut hunter = Crab::new(CRAB_1.to_string(), 10, Color::new_blue(), Diet::Shellfish);
+    hunter.injure(30);
+    beach.add_crab(hunter);
+    beach.add_crab(new_crab(CRAB_2, 5));
+    beach.add_crab(new_crab(CRAB_3, 20));
+    beach.add_crab(Crab::new(
+        CRAB_4.to_string(),
+        5,
+        Color::new_blue(),
+        Diet::Fish,
+    ));
+    beach.add_member_to_clan(CLAN_1, CRAB_2);
+
+    // Plant eaters leave each other alone.
+    assert_eq!(beach.simulate_encounter(1, 2), Encounter::Ignored);
+    // The slower hunter cannot catch its prey.
+    assert_eq!(
+        beach.simulate_encounter(0, 2),
+        Encounter::Escaped {
+            predator: CRAB_1.to_string(),
+            prey: CRAB_3.to_string(),
+        }
+    );
+    // Neither is faster, so the plant eater gets away.
+    assert_eq!(beach.simulate_encounter(1, 3).eaten(), None);
+
+    let encounter = beach.simulate_encounter(1, 0);
+    assert_eq!(
+        encounter,
+        Encounter::Eaten {
+            predator: CRAB_1.to_string(),
+            prey: CRAB_2.to_string(),
+        }
+    );
+    assert_eq!(beach.size(), 3);
+    assert_eq!(beach.get_crab(0).health(), 70 + Diet::Shellfish.energy());
+    assert!(!beach.get_clan_system().has_clan(CLAN_1));
+}
+
+#[test]
+fn part4_builder_spawns_crabs_with_defaults() {
+    let crab = Crab::builder().name(CRAB_1).speed(7).age(3).build();
+    assert_eq!(crab.name(), CRAB_1);
+    assert_eq!(crab.speed(), 7);
+    assert_eq!(crab.age(), 3);
+    assert_eq!(crab.diet(), Diet::Plants);
+    assert_eq!(crab.color(), &Color::new_blue());
+
+    let mut beach = Beach::new();
+    let id = beach
+        .spawn()
+        .name(CRAB_2)
+        .diet(Diet::Fish)
+        .color(Color::new_red())
+        .clan(CLAN_1)
+        .build();
+    beach.spawn().build();
+    assert_eq!(beach.size(), 2);
+    assert_eq!(beach.get_crab_by_id(id).unwrap().diet(), Diet::Fish);
+    assert_eq!(beach.get_crab(1).name(), DEFAULT_NAME);
+    assert_eq!(beach.get_clan_of(CRAB_2), Some(CLAN_1));
+
+    beach.add_built(CrabBuilder::new().name(CRAB_3).clan(CLAN_1));
+    assert_eq!(
+        beach.get_clan_system().get_clan_member_names(CLAN_1).len(),
+        2
+    );
+}
+
+#[test]
+fn part4_beach_sorted_grouped_and_top_crabs() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 5));
+    beach.add_crab(Crab::new(
+        CRAB_2.to_string(),
+        9,
+        Color::new_red(),
+        Diet::Fish,
+    ));
+    beach.add_crab(new_crab(CRAB_3, 5));
+    beach.add_crab(new_crab(CRAB_4, 7));
+    beach.add_member_to_clan(CLAN_1, CRAB_3);
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    beach.add_member_to_clan(CLAN_2, CRAB_2);
+
+    let names = |crabs: &[&Crab]| -> Vec<String> {
+        crabs.iter().map(|crab| crab.name().to_string()).collect()
+    };
+    assert_eq!(
+        names(&beach.crabs_sorted_by_speed()),
+        vec![CRAB_2, CRAB_4, CRAB_1, CRAB_3]
+    );
+    assert_eq!(names(&beach.top_n_fastest(2)), vec![CRAB_2, CRAB_4]);
+    assert_eq!(beach.top_n_fastest(10).len(), 4);
+
+    let diets = beach.group_by_diet();
+    assert_eq!(diets.len(), 2);
+    assert_eq!(names(&diets[&Diet::Plants]), vec![CRAB_1, CRAB_3, CRAB_4]);
+    assert_eq!(names(&diets[&Diet::Fish]), vec![CRAB_2]);
+
+    let clans = beach.group_by_clan();
+    assert_eq!(clans.len(), 2);
+    assert_eq!(names(&clans[CLAN_1]), vec![CRAB_1, CRAB_3]);
+    assert_eq!(names(&clans[CLAN_2]), vec![CRAB_2]);
+}
+
+#[test]
+fn part4_history_snapshot_restore_and_undo() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 20));
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    let snapshot = beach.snapshot();
+    assert_eq!(snapshot.size(), 2);
+
+    beach.remove_crab(0);
+    beach.add_member_to_clan(CLAN_2, CRAB_2);
+    beach.restore(snapshot.clone());
+    assert_eq!(beach.size(), 2);
+    assert_eq!(beach.get_clan_of(CRAB_1), Some(CLAN_1));
+    assert_eq!(beach.get_clan_of(CRAB_2), None);
+    assert_eq!(beach.undo_depth(), 0);
+
+    beach.enable_history(3);
+    beach.breed_crabs(0, 1, CRAB_3.to_string());
+    beach.add_member_to_clan(CLAN_1, CRAB_3);
+    beach.remove_crabs_by_name(CRAB_1);
+    beach.spawn().name(CRAB_4).clan(CLAN_2).build();
+    // Failed changes are not recorded.
+    assert!(beach.create_subclan(CLAN_1, "No such clan").is_err());
+    assert_eq!(beach.undo_depth(), 3);
+
+    // Spawning a crab into a clan is a single change.
+    assert!(beach.undo());
+    assert_eq!(beach.size(), 2);
+    assert!(!beach.get_clan_system().has_clan(CLAN_2));
+    assert!(beach.undo());
+    assert_eq!(beach.get_crab(0).name(), CRAB_1);
+    assert_eq!(
+        beach.get_clan_system().get_clan_member_names(CLAN_1),
+        vec![CRAB_1, CRAB_3]
+    );
+    assert!(beach.undo());
+    assert_eq!(beach.get_clan_of(CRAB_3), None);
+    // The breeding fell out of the history, which only goes three deep.
+    assert!(!beach.undo());
+    assert_eq!(beach.size(), 3);
+
+    // Ids are never reused after rolling back.
+    let id = beach.add_crab_with_id(new_crab(CRAB_5, 1));
+    beach.undo();
+    assert!(beach.get_crab_by_id(id).is_none());
+    let again = beach.add_crab_with_id(new_crab(CRAB_5, 1));
+    assert_ne!(id, again);
+
+    beach.restore(snapshot);
+    assert_eq!(beach.size(), 2);
+    assert!(beach.undo());
+    assert_eq!(beach.size(), 4);
+}
+
+#[test]
+fn part4_weather_high_tide_and_storms() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 3));
+    beach.add_crab(new_crab(CRAB_3, 1));
+    beach.set_territory(Territory::new(10, 10));
+    for index in 0..beach.size() {
+        beach.move_crab(index, Position::new(5, 5)).unwrap();
+    }
+    beach.set_crab_protected(2, true);
+    beach.set_weather(WeatherRules {
+        tide_period: 1,
+        rain_chance: 0.0,
+        storm_chance: 1.0,
+        high_tide_drag: 2,
+        washout_speed: 2,
+        storm_scatter: 3,
+        seed: 7,
+    });
+    assert_eq!(beach.weather().unwrap().tide(), Tide::Low);
+    assert_eq!(beach.effective_speed(0), 10);
+
+    // The tide comes in with a storm, washing away the unprotected crab
+    // it slows to a crawl.
+    let report = beach.tick();
+    let weather = beach.weather().unwrap();
+    assert_eq!(weather.tide(), Tide::High);
+    assert_eq!(weather.weather(), Weather::Storm);
+    assert_eq!(
+        report.deaths,
+        vec![Death {
+            name: CRAB_2.to_string(),
+            cause: Cause::Tide
+        }]
+    );
+    assert_eq!(beach.size(), 2);
+    assert_eq!(beach.effective_speed(0), 8);
+    assert_eq!(beach.effective_speed(1), 1);
+    for crab in beach.crabs() {
+        let position = crab.position();
+        assert!(beach.territory().unwrap().contains(position));
+        assert!(position.x.abs_diff(5) <= 3 && position.y.abs_diff(5) <= 3);
+    }
+
+    // Storms make breeding unlikely to succeed.
+    let failed = (0..20)
+        .map(|k| beach.try_breed_crabs(0, 1, format!("Stormborn {}", k)))
+        .filter(|result| matches!(result, Err(OceanError::BreedingFailed(why)) if why.contains("bad weather")))
+        .count();
+    assert!(failed > 0);
+
+    beach.tick();
+    assert_eq!(beach.weather().unwrap().tide(), Tide::Low);
+    assert_eq!(beach.effective_speed(0), 10);
+}
+
+#[test]
+fn part4_invariants_hold_after_operations() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 60));
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    beach.add_member_to_clan(CLAN_2, CRAB_2);
+    beach.add_member_to_clan(CLAN_1, CRAB_2);
+    beach.tick();
+    beach.tick();
+    beach.breed_crabs(0, 1, CRAB_3.to_string());
+    beach.remove_crabs_by_name(CRAB_1);
+    assert_eq!(beach.check_invariants(), Ok(()));
+
+    // Tightening the rules leaves crabs that break them until dealt with.
+    beach.set_constraints(Constraints {
+        speed: 1..=50,
+        ..Constraints::none()
+    });
+    beach.set_life_rules(LifeRules {
+        lifespan: 1,
+        ..LifeRules::default()
+    });
+    let violations = beach.check_invariants().unwrap_err();
+    assert_eq!(violations.len(), 2);
+    assert!(matches!(
+        &violations[0],
+        InvariantViolation::OutOfBounds(error) if error.name == CRAB_2 && error.value == 60
+    ));
+    assert_eq!(
+        violations[1],
+        InvariantViolation::TooOld {
+            crab_name: CRAB_2.to_string(),
+            age: 2,
+            lifespan: 1,
+        }
+    );
+    assert_eq!(
+        violations[1].to_string(),
+        format!("{} is 2 ticks old, past the lifespan of 1", CRAB_2)
+    );
+}
+
+#[test]
+fn part4_leaderboard_rates_races_and_competitions() {
+    let mut beach = Beach::new();
+    let edward = beach.add_crab_with_id(new_crab(CRAB_1, 10));
+    let mira = beach.add_crab_with_id(new_crab(CRAB_2, 20));
+    let third = beach.add_crab_with_id(new_crab(CRAB_3, 20));
+    let fourth = beach.add_crab_with_id(new_crab(CRAB_4, 5));
+
+    let places = beach
+        .record_race(&[edward, mira, third, fourth, edward])
+        .unwrap();
+    assert_eq!(places, vec![vec![mira, third], vec![edward], vec![fourth]]);
+    let leaderboard = beach.leaderboard();
+    assert_eq!(leaderboard.races(), 1);
+    let rating = |id| leaderboard.crab_rating(id).unwrap().rating;
+    assert!(rating(mira) > rating(edward) && rating(edward) > rating(fourth));
+    assert!((rating(mira) - rating(third)).abs() < 1.0);
+    let total: f64 = [edward, mira, third, fourth].into_iter().map(rating).sum();
+    assert!((total - 4.0 * INITIAL_RATING).abs() < 1e-9);
+    let top: Vec<CrabId> = leaderboard
+        .top_crabs(2)
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect();
+    assert_eq!(top, vec![mira, third]);
+    assert_eq!(leaderboard.crab_rating(edward).unwrap().games(), 3);
+
+    // Ratings outlast the crabs that earned them.
+    beach.remove_crab_by_id(fourth);
+    assert!(beach.leaderboard().crab_rating(fourth).is_some());
+    assert_eq!(
+        beach.record_race(&[mira, fourth]),
+        Err(OceanError::UnknownCrabId(fourth))
+    );
+    assert_eq!(beach.leaderboard().races(), 1);
+
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    beach.add_member_to_clan(CLAN_2, CRAB_2);
+    assert_eq!(
+        beach.record_competition(CLAN_1, CLAN_2),
+        Ok(Some(CLAN_2.to_string()))
+    );
+    assert!(beach.record_competition(CLAN_1, CLAN_1).is_err());
+    let top = beach.leaderboard().top_clans(5);
+    assert_eq!(top.len(), 2);
+    assert_eq!((top[0].0, top[0].1.rating), (CLAN_2, INITIAL_RATING + 16.0));
+    assert_eq!((top[1].0, top[1].1.rating), (CLAN_1, INITIAL_RATING - 16.0));
+}
+
+#[test]
+fn part4_breeding_policy_decides_offspring() {
+    /** Children run as fast as their faster parent and eat what it eats. */
+    struct Fastest;
+
+    impl BreedingPolicy for Fastest {
+        fn breed(&mut self, parent1: &Crab, parent2: &Crab, name: String) -> Crab {
+            let faster = if parent2.speed() > parent1.speed() {
+                parent2
+            } else {
+                parent1
+            };
+            Crab::new(name, faster.speed(), faster.color().clone(), faster.diet())
+        }
+    }
+
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(Crab::new(
+        CRAB_2.to_string(),
+        30,
+        Color::new(255, 0, 0),
+        Diet::Fish,
+    ));
+    beach.set_genetics(Genetics::default());
+    assert!(beach.genetics().is_some());
+
+    beach.set_breeding_policy(Box::new(Fastest));
+    assert_eq!(beach.genetics(), None);
+    beach.breed_crabs(0, 1, CRAB_3.to_string());
+    beach.breed_crabs(2, 0, CRAB_4.to_string());
+    let child = beach.get_crab(2);
+    assert_eq!((child.speed(), child.diet()), (30, Diet::Fish));
+    // The beach keeps track of generations whatever the policy.
+    assert_eq!(child.generation(), 1);
+    assert_eq!(beach.get_crab(3).speed(), 30);
+    assert_eq!(beach.get_crab(3).generation(), 2);
+
+    beach.set_breeding_policy(Box::new(DefaultBreeding));
+    beach.breed_crabs(0, 1, CRAB_5.to_string());
+    assert_eq!(beach.get_crab(4).speed(), 1);
+}
+
+#[test]
+fn part4_clan_leaders_and_roles() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 20));
+    beach.add_crab(new_crab(CRAB_3, 30));
+    beach.add_crab(new_crab(CRAB_4, 5));
+    for name in [CRAB_1, CRAB_2, CRAB_3] {
+        beach.add_member_to_clan(CLAN_1, name);
+    }
+    beach.add_member_to_clan(CLAN_2, CRAB_4);
+    assert_eq!(beach.get_leader(CLAN_1), None);
+    assert_eq!(beach.elect_leader(CLAN_1).as_deref(), Some(CRAB_3));
+    assert_eq!(beach.elect_leader(CLAN_3), None);
+
+    beach.set_role(CLAN_1, CRAB_1, "scout").unwrap();
+    beach.set_role(CLAN_1, CRAB_2, "scout").unwrap();
+    assert_eq!(
+        beach.set_role(CLAN_2, CRAB_1, "guard"),
+        Err(OceanError::NotClanMember {
+            crab: CRAB_1.to_string(),
+            clan: CLAN_2.to_string(),
+        })
+    );
+    assert_eq!(
+        beach.members_with_role(CLAN_1, "scout"),
+        vec![CRAB_1, CRAB_2]
+    );
+
+    // A clan whose leader leaves elects the next fastest member.
+    beach.remove_crabs_by_name(CRAB_3);
+    assert_eq!(beach.get_leader(CLAN_1), Some(CRAB_2));
+    beach.add_member_to_clan(CLAN_2, CRAB_2);
+    assert_eq!(beach.get_leader(CLAN_1), Some(CRAB_1));
+    assert_eq!(beach.members_with_role(CLAN_1, "scout"), vec![CRAB_1]);
+    assert_eq!(beach.get_role(CRAB_2), None);
+    assert_eq!(beach.get_leader(CLAN_2), None);
+
+    beach.set_leadership_criterion(|crab| 100 - crab.speed());
+    assert_eq!(beach.elect_leader(CLAN_2).as_deref(), Some(CRAB_4));
+    beach.rename_crab(2, CRAB_5.to_string());
+    assert_eq!(beach.get_leader(CLAN_2), Some(CRAB_5));
+}
+
+#[test]
+fn part4_capacity_rejects_or_evicts() {
+    let mut beach = Beach::with_capacity(2);
+    assert_eq!(beach.capacity(), Some(2));
+    assert_eq!(beach.eviction_policy(), EvictionPolicy::RejectNew);
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 5));
+    assert_eq!(
+        beach.admit_crab(new_crab(CRAB_3, 15)).unwrap_err(),
+        OceanError::BeachFull { capacity: 2 }
+    );
+    assert_eq!(
+        beach.try_breed_crabs(0, 1, CRAB_3.to_string()),
+        Err(OceanError::BeachFull { capacity: 2 })
+    );
+    assert_eq!(beach.size(), 2);
+
+    beach.set_eviction_policy(EvictionPolicy::EvictSlowest);
+    let evicted = beach.admit_crab(new_crab(CRAB_3, 15)).unwrap();
+    let names: Vec<&str> = evicted.iter().map(|crab| crab.name()).collect();
+    assert_eq!(names, vec![CRAB_2]);
+    assert_eq!(beach.size(), 2);
+
+    beach.tick();
+    beach.set_eviction_policy(EvictionPolicy::EvictOldest);
+    beach.add_crab(new_crab(CRAB_4, 1));
+    assert!(beach.find_crabs_by_name(CRAB_1).is_empty());
+    let evicted = beach.admit_offspring(0, 1, CRAB_5.to_string()).unwrap();
+    assert_eq!(evicted[0].name(), CRAB_3);
+    let names: Vec<&str> = beach.crabs().map(|crab| crab.name()).collect();
+    assert_eq!(names, vec![CRAB_4, CRAB_5]);
+
+    // Lowering the capacity keeps the crabs already there.
+    beach.set_capacity(Some(1));
+    assert_eq!(beach.size(), 2);
+    beach.set_capacity(None);
+    beach.add_crab(new_crab(CRAB_1, 1));
+    assert_eq!(beach.size(), 3);
+}
+
+#[test]
+fn part4_ocean_migration_between_named_beaches() {
+    let mut ocean = Ocean::new();
+    let mut north = Beach::new();
+    north.add_crab(new_crab(CRAB_1, 3));
+    north.add_crab(new_crab(CRAB_2, 9));
+    north.add_crab(new_crab(CRAB_3, 5));
+    north.add_member_to_clan(CLAN_1, CRAB_1);
+    north.add_member_to_clan(CLAN_1, CRAB_2);
+    assert_eq!(ocean.add_named_beach("north", north), Ok(0));
+    assert_eq!(
+        ocean.add_named_beach("south", Beach::with_capacity(2)),
+        Ok(1)
+    );
+    assert_eq!(
+        ocean.add_named_beach("north", Beach::new()),
+        Err(OceanError::DuplicateBeach("north".to_string()))
+    );
+    assert_eq!(ocean.beach_name(1), Some("south"));
+
+    let id = ocean.beach_named("north").unwrap().handle(1);
+    assert_eq!(
+        ocean.migrate(id, "north", "west"),
+        Err(OceanError::UnknownBeach("west".to_string()))
+    );
+    let moved = ocean.migrate(id, "north", "south").unwrap();
+    assert_eq!(
+        ocean.migrate(id, "north", "south"),
+        Err(OceanError::UnknownCrabId(id))
+    );
+    let south = ocean.beach_named("south").unwrap();
+    assert_eq!(south.get_crab_by_id(moved).unwrap().name(), CRAB_2);
+    assert_eq!(south.get_clan_of(CRAB_2), Some(CLAN_1));
+    assert_eq!(
+        ocean.get_clan_members(CLAN_1),
+        vec![(0, CRAB_1.to_string()), (1, CRAB_2.to_string())]
+    );
+    let (beach, fastest) = ocean.get_fastest_crab().unwrap();
+    assert_eq!((beach, fastest.name()), (1, CRAB_2));
+
+    let north = ocean.beach_named_mut("north").unwrap();
+    north.add_crab(new_crab(CRAB_4, 1));
+    north.add_crab(new_crab(CRAB_5, 1));
+    ocean.set_migration_rules(Some(MigrationRules {
+        crowded_at: 2,
+        max_per_tick: 1,
+    }));
+    ocean.tick();
+    assert_eq!(ocean.beach(0).unwrap().size(), 3);
+    assert_eq!(ocean.beach(1).unwrap().size(), 2);
+    ocean.tick();
+    // The south beach is full, so the north beach stays crowded.
+    assert_eq!(ocean.beach(0).unwrap().size(), 3);
+    assert_eq!(ocean.population(), 5);
+    let last = ocean.migrations().last().unwrap();
+    assert_eq!(
+        (last.crab_name.as_str(), last.from, last.to),
+        (CRAB_5, 0, 1)
+    );
+    assert_eq!(ocean.migrations().len(), 2);
+}
+
+#[test]
+fn part4_color_blending_hex_and_palette() {
+    let red = Color::new_red();
+    let blue = Color::new_blue();
+    assert_eq!(red.distance(&red), 0.0);
+    assert_eq!(
+        Color::named("black")
+            .unwrap()
+            .perceptual_distance(&Color::named("White").unwrap()),
+        1.0
+    );
+    assert!(
+        red.perceptual_distance(&Color::new(255, 40, 0))
+            > red.perceptual_distance(&Color::new(255, 0, 40))
+    );
+
+    assert_eq!(
+        red.blend(&blue, BlendMode::Average),
+        Color::new(127, 0, 127)
+    );
+    assert_eq!(
+        Color::new(200, 10, 0).blend(&Color::new(100, 10, 0), BlendMode::Additive),
+        Color::new(255, 20, 0)
+    );
+    assert_eq!(
+        Color::new(200, 10, 0).blend(&Color::new(100, 10, 0), BlendMode::Wrapping),
+        Color::new(44, 20, 0)
+    );
+    // Halfway between red and blue the short way round is magenta.
+    assert_eq!(red.blend(&blue, BlendMode::Hsl), Color::new(255, 0, 255));
+    assert_eq!(
+        red.blend(&Color::new_green(), BlendMode::Hsl),
+        Color::new(255, 255, 0)
+    );
+
+    assert_eq!(Color::new(255, 128, 0).to_hex(), "#ff8000");
+    assert_eq!(Color::from_hex("#FF8000"), Some(Color::new(255, 128, 0)));
+    assert_eq!(Color::from_hex("ff8000"), Some(Color::new(255, 128, 0)));
+    assert_eq!(Color::from_hex("#ff80"), None);
+    assert_eq!(Color::from_hex("#gg8000"), None);
+    assert_eq!(Color::named("mauve"), None);
+    assert_eq!(Color::new(250, 130, 10).nearest_name(), "orange");
+
+    let mut beach = Beach::new();
+    beach.add_crab(Crab::new(
+        CRAB_1.to_string(),
+        1,
+        Color::new(250, 0, 0),
+        Diet::Fish,
+    ));
+    beach.add_crab(Crab::new(
+        CRAB_2.to_string(),
+        1,
+        Color::new(0, 0, 250),
+        Diet::Fish,
+    ));
+    let near: Vec<&str> = beach
+        .find_crabs_by_color_near(&red, 0.05)
+        .iter()
+        .map(|c| c.name())
+        .collect();
+    assert_eq!(near, vec![CRAB_1]);
+}
+
+#[test]
+fn part4_race_outcomes_are_random_but_reproducible() {
+    let mut beach = Beach::new();
+    beach.set_constraints(Constraints::none());
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 11));
+    beach.add_crab(new_crab(CRAB_3, 0));
+    assert_eq!(
+        beach.race(&[0, 3], &mut Pcg64::seed_from_u64(1)),
+        Err(OceanError::InvalidIndex { index: 3, size: 3 })
+    );
+
+    let race = |seed| {
+        beach
+            .race(&[0, 1, 2, 1], &mut Pcg64::seed_from_u64(seed))
+            .unwrap()
+    };
+    assert_eq!(race(7), race(7));
+    let mut winners = std::collections::BTreeSet::new();
+    for seed in 0..20 {
+        let result = race(seed);
+        assert_eq!(result.finishes.len(), 3);
+        // A crab that cannot move never finishes.
+        assert_eq!(result.finishes[2].name, CRAB_3);
+        assert_eq!(result.finishes[2].time, f64::INFINITY);
+        assert!(result.finishes[0].time <= result.finishes[1].time);
+        winners.insert(result.winner().unwrap().name.clone());
+    }
+    assert_eq!(winners.len(), 2);
+
+    // Without spread, the faster crab always wins, unless it is worn down.
+    let mut rules = RaceRules {
+        spread: 0.0,
+        ..RaceRules::default()
+    };
+    let result = beach
+        .race_with(&[0, 1], &rules, &mut Pcg64::seed_from_u64(0))
+        .unwrap();
+    assert_eq!(result.winner().unwrap().name, CRAB_2);
+    assert_eq!(result.finishes[0].time, 100.0 / 11.0);
+    beach.get_crab_mut(1).set_health(50);
+    rules.health_matters = true;
+    let result = beach
+        .race_with(&[0, 1], &rules, &mut Pcg64::seed_from_u64(0))
+        .unwrap();
+    assert_eq!(result.winner().unwrap().name, CRAB_1);
+
+    let places = result.places();
+    beach.leaderboard_mut().record_race(&places);
+    assert_eq!(beach.leaderboard().races(), 1);
+}
+
+#[test]
+fn part4_economy_clan_treasuries() {
+    let mut ocean = Ocean::new();
+    let reef = ocean.generate_reef(0, 0, 1, 0);
+    let mut beach = Beach::new();
+    let mut prinz = new_prinz();
+    prinz.discover_reef(Rc::clone(&reef));
+    beach.add_crab(prinz);
+    beach.add_crab(new_crab(CRAB_1, 5));
+    beach.add_crab(new_crab(CRAB_2, 3));
+    beach.add_member_to_clan(CLAN_1, PRINZ);
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    beach.add_member_to_clan(CLAN_2, CRAB_2);
+    beach.set_treasury_rules(TreasuryRules {
+        foraging_share: 1,
+        victory_prize: 10,
+        boost_price: 5,
+        meal_price: 2,
+    });
+
+    assert!(beach.forage(0));
+    assert_eq!(beach.get_treasury(CLAN_1), 1);
+    assert_eq!(
+        beach.record_competition(CLAN_1, CLAN_2),
+        Ok(Some(CLAN_1.to_string()))
+    );
+    assert_eq!(beach.get_treasury(CLAN_1), 11);
+    assert_eq!(beach.get_treasury(CLAN_2), 0);
+
+    assert_eq!(
+        beach.wager_competition(CLAN_1, CLAN_2, 5),
+        Err(OceanError::InsufficientShells {
+            account: Account::clan(CLAN_2),
+            balance: 0,
+            amount: 5,
+        })
+    );
+    assert_eq!(
+        beach.wager_competition(CLAN_1, CLAN_1, 0),
+        Err(OceanError::ClanAgainstItself(CLAN_1.to_string()))
+    );
+    assert_eq!(beach.leaderboard().competitions(), 1);
+    beach
+        .economy_mut()
+        .import(Account::clan(CLAN_2), 5)
+        .unwrap();
+    assert_eq!(
+        beach.wager_competition(CLAN_1, CLAN_2, 5),
+        Ok(Some(CLAN_1.to_string()))
+    );
+    assert_eq!(beach.get_treasury(CLAN_1), 26);
+    assert_eq!(beach.get_treasury(CLAN_2), 0);
+
+    assert!(beach.boost_member(CLAN_1, CRAB_2, 1).is_err());
+    assert!(beach.boost_member(CLAN_1, CRAB_1, 6).is_err());
+    assert_eq!(beach.get_treasury(CLAN_1), 26);
+    assert!(beach.boost_member(CLAN_1, CRAB_1, 4).is_ok());
+    assert_eq!(beach.get_crab(1).speed(), 9);
+    assert_eq!(beach.get_treasury(CLAN_1), 6);
+
+    beach.get_crab_mut(0).set_hunger(STARVING_AT);
+    beach.get_crab_mut(1).set_hunger(HUNGRY_AT);
+    beach.spend_treasury(CLAN_1, 3, Spending::Meals).unwrap();
+    assert_eq!(
+        beach.feed_hungry_members(CLAN_1),
+        Ok(vec![PRINZ.to_string()])
+    );
+    assert_eq!(beach.get_crab(0).hunger(), 0);
+    assert_eq!(beach.get_crab(1).state(), LifeState::Hungry);
+    assert_eq!(beach.get_treasury(CLAN_1), 1);
+    assert!(beach.feed_hungry_members(CLAN_3).is_err());
+    assert_eq!(
+        beach.spend_treasury(CLAN_3, 0, Spending::Meals),
+        Err(OceanError::UnknownClan(CLAN_3.to_string()))
+    );
+    assert!(matches!(
+        beach.spend_treasury(CLAN_1, 2, Spending::Meals),
+        Err(OceanError::InsufficientShells { balance: 1, .. })
+    ));
+}
+
+#[cfg(feature = "binary")]
+#[test]
+fn part4_persist_binary_round_trip_and_old_versions() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_prinz());
+    beach.tick();
+    beach.add_crab(new_sebastian());
+    beach.get_crab_mut(1).injure(30);
+    beach.get_crab_mut(1).set_position(Position::new(3, 4));
+    beach.get_crab_mut(1).set_years(6);
+    beach.add_member_to_clan(CLAN_1, PRINZ);
+    beach.add_member_to_clan(CLAN_2, SEBASTIAN);
+    beach
+        .set_clan_relation(CLAN_1, CLAN_2, Relation::Rivalry)
+        .unwrap();
+    beach.create_subclan(CLAN_1, CLAN_2).unwrap();
+    let constitution = Constitution {
+        min_speed: 2,
+        diet: Some(Diet::Plants),
+        max_size: Some(3),
+    };
+    beach.set_constitution(CLAN_2, constitution);
+
+    let path = std::env::temp_dir().join(format!("ocean-beach-{}.bin", std::process::id()));
+    beach.save_binary(&path).unwrap();
+    let loaded = Beach::load_binary(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.ticks(), 1);
+    assert_eq!((loaded.age(0), loaded.age(1)), (1, 0));
+    let sebastian = loaded.get_crab(1);
+    assert_eq!(sebastian.name(), SEBASTIAN);
+    assert_eq!(sebastian.diet(), Diet::Plants);
+    assert_eq!(sebastian.health(), MAX_HEALTH - 30);
+    assert_eq!(sebastian.position(), Position::new(3, 4));
+    assert_eq!(sebastian.years(), 6);
+    assert_eq!(sebastian.color(), beach.get_crab(1).color());
+    let clans = loaded.get_clan_system();
+    assert_eq!(clans.get_clan_member_names(CLAN_2), vec![SEBASTIAN]);
+    assert_eq!(clans.get_relation(CLAN_1, CLAN_2), Some(Relation::Rivalry));
+    assert_eq!(clans.parent_clan(CLAN_2), Some(CLAN_1));
+    assert_eq!(clans.constitution(CLAN_2), Some(&constitution));
+    assert_eq!(clans.constitution(CLAN_1), None);
+    assert_eq!(loaded.to_binary(), beach.to_binary());
+
+    // A version 1 save: tick 5, one crab that arrived at tick 2, one clan.
+    let mut v1 = BINARY_MAGIC.to_vec();
+    v1.extend_from_slice(&1u16.to_le_bytes());
+    v1.extend_from_slice(&5u64.to_le_bytes());
+    v1.extend_from_slice(&1u32.to_le_bytes());
+    v1.extend_from_slice(&(CRAB_1.len() as u32).to_le_bytes());
+    v1.extend_from_slice(CRAB_1.as_bytes());
+    v1.extend_from_slice(&7u32.to_le_bytes());
+    v1.extend_from_slice(&[255, 128, 0, 1]);
+    v1.extend_from_slice(&2u32.to_le_bytes());
+    v1.extend_from_slice(&80u32.to_le_bytes());
+    v1.extend_from_slice(&2u64.to_le_bytes());
+    v1.extend_from_slice(&1u32.to_le_bytes());
+    v1.extend_from_slice(&(CLAN_1.len() as u32).to_le_bytes());
+    v1.extend_from_slice(CLAN_1.as_bytes());
+    v1.extend_from_slice(&1u32.to_le_bytes());
+    v1.extend_from_slice(&(CRAB_1.len() as u32).to_le_bytes());
+    v1.extend_from_slice(CRAB_1.as_bytes());
+    let old = Beach::from_binary(&v1).unwrap();
+    assert_eq!((old.ticks(), old.age(0)), (5, 3));
+    let edward = old.get_crab(0);
+    assert_eq!((edward.name(), edward.speed()), (CRAB_1, 7));
+    assert_eq!(edward.color(), &Color::new(255, 128, 0));
+    assert_eq!(edward.diet(), Diet::Shellfish);
+    assert_eq!((edward.claw_size(), edward.health()), (2, 80));
+    assert_eq!((edward.hunger(), edward.age()), (0, 0));
+    assert_eq!(old.get_clan_of(CRAB_1), Some(CLAN_1));
+
+    assert!(Beach::from_binary(&v1[..v1.len() - 1]).is_err());
+    assert!(Beach::from_binary(b"JSON").is_err());
+    let mut future = v1.clone();
+    future[4..6].copy_from_slice(&(BINARY_VERSION + 1).to_le_bytes());
+    assert!(Beach::from_binary(&future).is_err());
+}
+
+#[test]
+fn part4_inhabitants_of_other_species() {
+    let mut beach = Beach::new();
+    beach.add_inhabitant(Box::new(new_crab(CRAB_1, 4)));
+    beach.add_inhabitant(Box::new(Lobster::new("Larry", 6, Color::new_red())));
+    assert_eq!(beach.size(), 1);
+    assert_eq!(beach.get_crab(0).name(), CRAB_1);
+
+    let species: Vec<(&str, &str)> = beach
+        .inhabitants()
+        .iter()
+        .map(|i| (i.name(), i.species()))
+        .collect();
+    assert_eq!(species, vec![(CRAB_1, "crab"), ("Larry", "lobster")]);
+    let larry = beach.inhabitant("Larry").unwrap();
+    assert_eq!(larry.diet(), Diet::Fish);
+    assert_eq!(larry.downcast_ref::<Lobster>().unwrap().speed(), 6);
+    assert!(beach
+        .inhabitant(CRAB_1)
+        .unwrap()
+        .downcast_ref::<Lobster>()
+        .is_none());
+    assert_eq!(
+        beach
+            .inhabitant(CRAB_1)
+            .unwrap()
+            .downcast_ref::<Crab>()
+            .unwrap()
+            .speed(),
+        4
+    );
+
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    beach.add_member_to_clan(CLAN_1, "Larry");
+    assert!(beach.check_invariants().is_ok());
+    let rules = RaceRules {
+        spread: 0.0,
+        ..RaceRules::default()
+    };
+    let times = beach
+        .race_inhabitants(&["Larry", CRAB_1], &rules, &mut Pcg64::seed_from_u64(0))
+        .unwrap();
+    assert_eq!(
+        times,
+        vec![
+            ("Larry".to_string(), 100.0 / 6.0),
+            (CRAB_1.to_string(), 25.0)
+        ]
+    );
+    assert_eq!(
+        beach.race_inhabitants(&["Nemo"], &rules, &mut Pcg64::seed_from_u64(0)),
+        Err(OceanError::UnknownInhabitant("Nemo".to_string()))
+    );
+
+    let larry = beach.remove_inhabitant("Larry").unwrap();
+    assert!(larry.is::<Lobster>());
+    assert_eq!(
+        beach.get_clan_system().get_clan_member_names(CLAN_1),
+        vec![CRAB_1]
+    );
+    assert!(beach.remove_inhabitant(CRAB_1).unwrap().is::<Crab>());
+    assert!(beach.inhabitants().is_empty());
+}
+
+#[test]
+fn part4_analysis_histograms_and_distributions() {
+    let mut beach = Beach::new();
+    assert_eq!(beach.speed_histogram(5).total(), 0);
+    assert_eq!(beach.speed_percentile(50.0), None);
+    beach.add_crab(new_crab(CRAB_1, 3));
+    beach.add_crab(new_crab(CRAB_2, 4));
+    beach.tick();
+    beach.add_crab(Crab::new(
+        CRAB_3.to_string(),
+        12,
+        Color::new_blue(),
+        Diet::Fish,
+    ));
+
+    let histogram = beach.speed_histogram(5);
+    let counts: Vec<(u64, u64, usize)> = histogram
+        .buckets
+        .iter()
+        .map(|b| (b.start, b.end, b.count))
+        .collect();
+    assert_eq!(counts, vec![(0, 5, 2), (5, 10, 0), (10, 15, 1)]);
+    assert_eq!(histogram.total(), 3);
+    assert_eq!(beach.speed_histogram(0).bucket_size, 1);
+
+    let diets = beach.diet_distribution();
+    assert_eq!(diets[&Diet::Fish], 1);
+    assert_eq!(diets[&Diet::Shellfish], 0);
+    assert_eq!(diets.values().sum::<usize>(), 3);
+    assert_eq!(
+        beach.age_distribution(),
+        std::collections::BTreeMap::from([(0, 1), (1, 2)])
+    );
+    assert_eq!(beach.speed_percentile(50.0), Some(4.0));
+    assert_eq!(beach.speed_percentile(100.0), Some(12.0));
+}
+
+#[test]
+fn part4_rng_seeded_beach_is_reproducible() {
+    let run = |seed: u64, genetics: bool| {
+        let mut beach = Beach::with_seed(seed);
+        if genetics {
+            beach.set_genetics(Genetics::default());
+        }
+        beach.add_crab(new_crab(CRAB_1, 10));
+        beach.add_crab(new_crab(CRAB_2, 12));
+        for i in 0..8 {
+            beach.breed_crabs(0, 1, format!("Crab {}", i));
+        }
+        let diets: Vec<Diet> = beach.crabs().map(|crab| crab.diet()).collect();
+        let speeds: Vec<u32> = beach.crabs().map(|crab| crab.speed()).collect();
+        let race = beach.run_race(&[0, 1, 2], &RaceRules::default()).unwrap();
+        (diets, speeds, race)
+    };
+    for genetics in [false, true] {
+        assert_eq!(run(3, genetics), run(3, genetics));
+    }
+    assert!((0..10).any(|seed| run(seed, false).0 != run(3, false).0));
+    assert!((0..10).any(|seed| run(seed, true).2 != run(3, true).2));
+}
+
+#[test]
+fn part4_clans_disband_rename_and_merge() {
+    let mut beach = Beach::new();
+    for (name, clan) in [
+        (CRAB_1, CLAN_1),
+        (CRAB_2, CLAN_1),
+        (CRAB_3, CLAN_2),
+        (CRAB_4, CLAN_3),
+    ] {
+        beach.add_crab(new_crab(name, 10));
+        beach.add_member_to_clan(clan, name);
+    }
+    beach
+        .set_clan_relation(CLAN_2, CLAN_3, Relation::Rivalry)
+        .unwrap();
+    beach
+        .economy_mut()
+        .earn(Account::clan(CLAN_2), 5, Earning::Foraging)
+        .unwrap();
+
+    assert_eq!(
+        beach.rename_clan(CLAN_2, CLAN_3),
+        Err(OceanError::DuplicateClan(CLAN_3.to_string()))
+    );
+    assert_eq!(
+        beach.rename_clan(CLAN_2, CLAN_3).unwrap_err().to_string(),
+        format!("there is already a clan called {}", CLAN_3)
+    );
+    beach.rename_clan(CLAN_2, "Renamed").unwrap();
+    assert_eq!(beach.get_clan_of(CRAB_3), Some("Renamed"));
+    assert!(!beach.get_clan_system().has_clan(CLAN_2));
+    assert_eq!(
+        beach.get_clan_system().get_relation(CLAN_3, "Renamed"),
+        Some(Relation::Rivalry)
+    );
+    assert_eq!(beach.get_treasury("Renamed"), 5);
+    assert_eq!(beach.get_treasury(CLAN_2), 0);
+
+    assert_eq!(
+        beach.merge_clans(CLAN_1, CLAN_1, "United"),
+        Err(OceanError::MergeWithSelf(CLAN_1.to_string()))
+    );
+    assert_eq!(
+        beach.merge_clans(CLAN_1, "Renamed", CLAN_3),
+        Err(OceanError::DuplicateClan(CLAN_3.to_string()))
+    );
+    beach.merge_clans(CLAN_1, "Renamed", "United").unwrap();
+    let clans = beach.get_clan_system();
+    assert_eq!(
+        clans.get_clan_member_names("United"),
+        vec![CRAB_1, CRAB_2, CRAB_3]
+    );
+    assert_eq!(clans.get_clan_count(), 2);
+    assert_eq!(
+        clans.get_relation("United", CLAN_3),
+        Some(Relation::Rivalry)
+    );
+    assert_eq!(beach.get_treasury("United"), 5);
+
+    assert_eq!(
+        beach.disband_clan("United").unwrap(),
+        vec![CRAB_1, CRAB_2, CRAB_3]
+    );
+    assert_eq!(beach.get_clan_of(CRAB_1), None);
+    assert_eq!(beach.get_clan_system().relations(), vec![]);
+    assert_eq!(beach.get_clan_system().get_clan_count(), 1);
+    assert_eq!(
+        beach.disband_clan("United"),
+        Err(OceanError::UnknownClan(String::from("United")))
+    );
+
+    // Merging a clan with one below it leaves no cycle of sub-clans.
+    let mut beach = Beach::new();
+    let mut clans = ClanSystem::new();
+    for (name, clan) in [(CRAB_1, CLAN_1), (CRAB_2, CLAN_2), (CRAB_3, CLAN_3)] {
+        let id = beach.add_crab_with_id(new_crab(name, 10));
+        clans.add_member(clan, id, name);
+    }
+    clans.create_subclan(CLAN_1, CLAN_2).unwrap();
+    clans.create_subclan(CLAN_2, CLAN_3).unwrap();
+    clans.merge_clans(CLAN_3, CLAN_1, CLAN_1).unwrap();
+    assert_eq!(clans.parent_clan(CLAN_1), None);
+    assert_eq!(clans.parent_clan(CLAN_2), Some(CLAN_1));
+    assert_eq!(clans.get_clan_member_count_transitive(CLAN_1), 3);
+}
+
+#[test]
+fn part4_competition_history_and_tie_breakers() {
+    let mut beach = Beach::with_seed(4);
+    for (name, speed, clan) in [
+        (CRAB_1, 10, CLAN_1),
+        (CRAB_2, 2, CLAN_1),
+        (CRAB_3, 6, CLAN_2),
+        (CRAB_4, 6, CLAN_2),
+        (CRAB_5, 6, CLAN_3),
+    ] {
+        beach.add_crab(new_crab(name, speed));
+        beach.add_member_to_clan(clan, name);
+    }
+    assert_eq!(beach.record_competition(CLAN_1, CLAN_2), Ok(None));
+
+    beach.set_tie_breakers(&TieBreaker::STANDARD);
+    assert_eq!(
+        beach.record_competition(CLAN_1, CLAN_2),
+        Ok(Some(CLAN_1.to_string()))
+    );
+    assert_eq!(
+        beach.record_competition(CLAN_3, CLAN_2),
+        Ok(Some(CLAN_2.to_string()))
+    );
+    beach.set_tie_breakers(&[TieBreaker::CoinFlip]);
+    let winner = beach.record_competition(CLAN_3, CLAN_2).unwrap();
+    assert!(winner.is_some());
+    assert!(beach.record_competition(CLAN_1, CLAN_1).is_err());
+
+    let history = beach.competition_history();
+    assert_eq!(history.len(), 4);
+    assert_eq!(history[0].winner, None);
+    assert_eq!(history[0].tie_breaker, None);
+    assert_eq!(history[1].tie_breaker, Some(TieBreaker::MaxSpeed));
+    assert_eq!(history[2].tie_breaker, Some(TieBreaker::MemberCount));
+    assert_eq!(history[3].tie_breaker, Some(TieBreaker::CoinFlip));
+    assert_eq!(history[3].winner, winner);
+    assert_eq!(
+        beach.competition_history_of(CLAN_1),
+        history[..2].iter().collect::<Vec<_>>()
+    );
+    assert_eq!(beach.competition_history_of(CLAN_3).len(), 2);
+}
+
+#[test]
+fn part4_life_stages_follow_the_lifecycle() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 5));
+    beach.add_crab(new_crab(CRAB_2, 3));
+    beach.get_crab_mut(1).set_health(0);
+    beach.set_lifecycle(LifecycleConfig {
+        maturity: 2,
+        peak: 4,
+        growth: 3,
+        decline: 2,
+    });
+    assert_eq!(beach.life_stage(0), LifeStage::Juvenile);
+
+    beach.age_all(2);
+    assert_eq!(beach.get_crab(0).years(), 2);
+    assert_eq!(beach.get_crab(0).speed(), 11);
+    assert_eq!(beach.life_stage(0), LifeStage::Adult);
+    assert_eq!(beach.get_crab(0).life_stage(&beach.lifecycle()), LifeStage::Adult);
+    // Dead crabs do not age.
+    assert_eq!(beach.get_crab(1).years(), 0);
+    assert_eq!(beach.get_crab(1).speed(), 3);
+
+    beach.age_all(2);
+    assert_eq!(beach.get_crab(0).speed(), 11);
+    assert_eq!(beach.life_stage(0), LifeStage::Adult);
+    beach.age_all(1);
+    assert_eq!(beach.get_crab(0).speed(), 9);
+    assert_eq!(beach.life_stage(0), LifeStage::Elder);
+    beach.age_all(10);
+    assert_eq!(beach.get_crab(0).speed(), 1);
+    assert_eq!(beach.get_crab(0).years(), 15);
+    // Years pass apart from the steps a crab advances through its life.
+    assert_eq!(beach.get_crab(0).age(), 0);
+}
+
+#[test]
+fn part4_storage_removals_keep_order_and_ids() {
+    let mut beach = Beach::new();
+    beach.set_constraints(Constraints::none());
+    let ids: Vec<CrabId> = (0..40)
+        .map(|k| beach.add_crab_with_id(new_crab(&format!("Crab {}", k), k)))
+        .collect();
+    for &id in ids.iter().step_by(3) {
+        assert!(beach.remove_crab_by_id(id).is_some());
+    }
+    assert_eq!(beach.size(), 26);
+    let speeds: Vec<u32> = beach.crabs().map(|crab| crab.speed()).collect();
+    let expected: Vec<u32> = (0..40).filter(|k| k % 3 != 0).collect();
+    assert_eq!(speeds, expected);
+    assert_eq!(beach.crabs().len(), 26);
+    assert_eq!(beach.crabs().next_back().unwrap().speed(), 38);
+    for (index, &id) in ids.iter().enumerate() {
+        match index % 3 {
+            0 => assert_eq!(beach.index_of(id), None),
+            _ => {
+                let at = beach.index_of(id).unwrap();
+                assert_eq!(beach.handle(at), id);
+                assert_eq!(beach.get_crab(at).speed(), index as u32);
+                assert_eq!(beach.get_crab_by_id(id).unwrap().speed(), index as u32);
+            }
+        }
+    }
+    beach.add_crab(new_crab(CRAB_1, 99));
+    assert_eq!(beach.get_crab(26).name(), CRAB_1);
+
+    // Removing most crabs compacts the storage without disturbing the rest.
+    while beach.size() > 5 {
+        beach.remove_crab(1);
+    }
+    let speeds: Vec<u32> = beach.crabs().map(|crab| crab.speed()).collect();
+    assert_eq!(speeds, vec![1, 35, 37, 38, 99]);
+    assert_eq!(beach.index_of(ids[37]), Some(2));
+}
+
+#[cfg(feature = "wasm")]
+#[test]
+fn part4_wasm_beach_drives_a_beach_through_json() {
+    let mut beach = WasmBeach::with_seed(7);
+    assert_eq!(beach.add_crab("Ann", 10, "#ff0000", "Fish").unwrap(), 0);
+    assert_eq!(beach.add_crab("Bob", 4, "#0000ff", "Plants").unwrap(), 1);
+    beach.breed(0, 1, "Cal").unwrap();
+    assert_eq!(beach.size(), 3);
+    assert!(beach.crab(2).unwrap().contains("\"name\":\"Cal\""));
+
+    beach.add_to_clan("reds", "Ann");
+    beach.add_to_clan("blues", "Bob");
+    assert_eq!(beach.clan_members("reds"), vec!["Ann".to_string()]);
+    assert_eq!(beach.clan_of("Bob"), Some("blues".to_string()));
+    assert_eq!(
+        beach.winner_clan("reds", "blues").unwrap(),
+        Some("reds".to_string())
+    );
+
+    let report: serde_json::Value = serde_json::from_str(&beach.tick().unwrap()).unwrap();
+    assert_eq!(report["tick"], 1);
+    assert_eq!(report["population"], 3);
+
+    let restored = WasmBeach::from_json(&beach.to_json().unwrap()).unwrap();
+    assert_eq!(restored.crabs().unwrap(), beach.crabs().unwrap());
+    assert_eq!(restored.beach().ticks(), 1);
+}
+
+#[test]
+fn part4_movement_strategies_step_crabs_toward_their_targets() {
+    let mut beach = Beach::new();
+    beach.set_territory(Territory::new(100, 100));
+    let fish = |name: &str| Crab::new(name.to_string(), 3, Color::new_red(), Diet::Fish);
+    for (crab, at) in [
+        (new_crab("Ann", 5), Position::new(0, 0)),
+        (new_crab("Bob", 5), Position::new(50, 50)),
+        (fish("Cy"), Position::new(90, 0)),
+        (fish("Dee"), Position::new(90, 10)),
+    ] {
+        beach.add_crab(crab);
+        beach.place_crab(beach.size() - 1, at);
+    }
+    beach.add_member_to_clan("north", "Cy");
+    beach.add_member_to_clan("north", "Dee");
+    beach
+        .landmarks_mut()
+        .food
+        .push((Position::new(3, 4), Food::Algae));
+    beach.landmarks_mut().dangers.push(Position::new(52, 50));
+
+    // Without a strategy, nobody moves.
+    beach.tick();
+    assert_eq!(beach.get_crab(0).position(), Position::new(0, 0));
+
+    beach.set_movement_strategy(Box::new(FirstOf::cautious(10)));
+    beach.tick();
+    let positions: Vec<Position> = beach.crabs().map(|crab| crab.position()).collect();
+    assert_eq!(
+        positions,
+        vec![
+            Position::new(3, 4),
+            Position::new(48, 50),
+            Position::new(90, 3),
+            Position::new(90, 7),
+        ]
+    );
+
+    assert_eq!(
+        step_toward(Position::new(0, 0), Position::new(10, 0), 3),
+        Position::new(3, 0)
+    );
+    beach.place_crab(0, Position::new(99, 99));
+    beach.set_movement_strategy(Box::new(Flee { radius: 5 }));
+    beach.landmarks_mut().dangers.push(Position::new(97, 97));
+    beach.move_crabs();
+    assert_eq!(beach.get_crab(0).position(), Position::new(99, 99));
+}
+
+#[test]
+fn part4_clans_breed_within_clan_and_enroll_offspring() {
+    let mut beach = Beach::with_seed(3);
+    for (name, speed) in [("Ann", 4), ("Bob", 9), ("Cy", 7), ("Dee", 8)] {
+        beach.add_crab(new_crab(name, speed));
+    }
+    for name in ["Ann", "Bob", "Cy"] {
+        beach.add_member_to_clan("reds", name);
+    }
+    beach.add_member_to_clan("blues", "Dee");
+
+    let parents = beach.breed_within_clan("reds", "Eve".to_string()).unwrap();
+    assert_eq!(parents, ("Bob".to_string(), "Cy".to_string()));
+    assert_eq!(beach.size(), 5);
+    assert_eq!(beach.get_clan_of("Eve"), Some("reds"));
+
+    let (p1, p2) = beach
+        .breed_within_clan_by("reds", "Fay".to_string(), ParentChoice::Random)
+        .unwrap();
+    assert_ne!(p1, p2);
+    for parent in [&p1, &p2] {
+        assert_eq!(beach.get_clan_of(parent), Some("reds"));
+    }
+    assert_eq!(beach.get_clan_of("Fay"), Some("reds"));
+
+    assert_eq!(
+        beach.breed_within_clan("blues", "Gus".to_string()),
+        Err(OceanError::ClanTooSmall {
+            clan: "blues".to_string(),
+            residents: 1
+        })
+    );
+    assert_eq!(
+        beach.breed_within_clan("greens", "Gus".to_string()),
+        Err(OceanError::UnknownClan("greens".to_string()))
+    );
+    assert_eq!(beach.size(), 6);
+
+    // A child the clan cannot take is not born.
+    let full = Constitution {
+        max_size: Some(5),
+        ..Constitution::default()
+    };
+    beach.set_constitution("reds", full);
+    let Err(OceanError::Rejected(rejection)) = beach.breed_within_clan("reds", "Gus".to_string())
+    else {
+        panic!("the full clan took the child");
+    };
+    assert_eq!(rejection.crab, "Gus");
+    assert_eq!(rejection.reason, RejectionReason::ClanFull { max_size: 5 });
+    assert_eq!(beach.size(), 6);
+    assert!(beach.find_crabs_by_name("Gus").is_empty());
+    assert_eq!(beach.get_clan_system().get_clan_member_count("reds"), 5);
+}
+
+#[test]
+fn part4_metrics_record_recent_ticks() {
+    let mut beach = Beach::new();
+    assert!(beach.metrics().is_none());
+    beach.enable_metrics(3);
+    beach.add_crab(new_crab("Ann", 2));
+    beach.add_crab(new_crab("Bob", 4));
+    beach.add_member_to_clan("reds", "Ann");
+    beach.tick();
+    beach.add_crab(new_crab("Cy", 9));
+    beach.add_member_to_clan("blues", "Cy");
+    beach.tick();
+    beach.remove_crab(0);
+    beach.remove_crab(0);
+    beach.remove_crab(0);
+    beach.tick();
+    beach.tick();
+
+    let metrics = beach.metrics().unwrap();
+    assert_eq!(metrics.len(), 3);
+    let ticks: Vec<u64> = metrics.iter().map(|m| m.tick).collect();
+    assert_eq!(ticks, vec![2, 3, 4]);
+    assert_eq!(metrics.latest().unwrap().population, 0);
+    assert_eq!(metrics.mean_speed_over(1), None);
+    assert_eq!(metrics.mean_speed_over(3), Some(5.0));
+    assert_eq!(metrics.population().points(), &[(2, 3), (3, 0), (4, 0)]);
+    assert_eq!(metrics.clan_size("blues").points(), &[(2, 1)]);
+    assert_eq!(metrics.births_over(10), 0);
+
+    let mut csv = Vec::new();
+    metrics.write_csv(&mut csv).unwrap();
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "tick,population,mean_speed,births,deaths,blues,reds\n\
+         2,3,5,0,0,1,1\n\
+         3,0,,0,0,,\n\
+         4,0,,0,0,,\n"
+    );
+}
+
+#[test]
+fn part4_search_crabs_by_prefix_case_and_fuzzy_name() {
+    let mut beach = Beach::new();
+    for name in ["Shelly", "Sheldon", "shelly", "Pinch", "Shelly", "Shelby"] {
+        beach.add_crab(new_crab(name, 1));
+    }
+    let names = |crabs: Vec<&Crab>| -> Vec<String> {
+        crabs.iter().map(|crab| crab.name().to_string()).collect()
+    };
+
+    assert_eq!(
+        names(beach.find_crabs_by_name_prefix("Shel")),
+        vec!["Shelly", "Sheldon", "Shelly", "Shelby"]
+    );
+    assert!(beach.find_crabs_by_name_prefix("Crab").is_empty());
+    assert_eq!(
+        names(beach.find_crabs_by_name_ignore_case("SHELLY")),
+        vec!["Shelly", "shelly", "Shelly"]
+    );
+    assert_eq!(
+        names(beach.find_crabs_by_name_fuzzy("Shelly", 1)),
+        vec!["Shelly", "shelly", "Shelly", "Shelby"]
+    );
+    assert_eq!(
+        names(beach.find_crabs_by_name_fuzzy("Pinch", 0)),
+        vec!["Pinch"]
+    );
+
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(edit_distance("", "crab"), 4);
+    assert_eq!(edit_distance("crab", "crab"), 0);
+}
+
+#[test]
+fn part4_diet_nutrition_sets_what_meals_are_worth() {
+    assert_eq!(Diet::all(), &[Diet::Fish, Diet::Shellfish, Diet::Plants]);
+    assert!(Diet::Shellfish.energy() > Diet::Fish.energy());
+    assert!(Diet::Plants.rarity() < Diet::Shellfish.rarity());
+
+    let mut beach = Beach::new();
+    for diet in [Diet::Shellfish, Diet::Plants] {
+        let mut crab = Crab::new(format!("{:?}", diet), 5, Color::new_red(), diet);
+        crab.injure(50);
+        beach.add_crab(crab);
+    }
+    beach.add_food(Food::Shellfish, 1);
+    beach.add_food(Food::Algae, 1);
+    beach.feed_all();
+    assert_eq!(beach.get_crab(0).health(), 50 + Diet::Shellfish.energy());
+    assert_eq!(beach.get_crab(1).health(), 50 + Diet::Plants.energy());
+
+    let mut rng = Pcg64::seed_from_u64(11);
+    for _ in 0..20 {
+        let diet = Diet::random_diet_with(&mut rng, &[(Diet::Fish, 0.0), (Diet::Plants, 2.0)]);
+        assert_eq!(diet, Some(Diet::Plants));
+    }
+    assert_eq!(Diet::random_diet_with(&mut rng, &[(Diet::Fish, 0.0)]), None);
+    assert_eq!(Diet::random_diet_with(&mut rng, &[]), None);
+}
+
+#[test]
+fn part4_shared_beach_serves_readers_while_it_is_written() {
+    let shared = SharedBeach::spawn(|| {
+        let mut beach = Beach::new();
+        beach.add_crab(new_crab(CRAB_1, 4));
+        beach
+    });
+    assert_eq!(shared.stats().population, 1);
+
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let shared = shared.clone();
+            std::thread::spawn(move || {
+                let mut seen = 0;
+                while seen < 11 {
+                    let stats = shared.stats();
+                    assert!(stats.population >= seen);
+                    seen = stats.population;
+                }
+                shared.read(|beach| beach.ticks())
+            })
+        })
+        .collect();
+    for k in 0..10 {
+        shared.write(move |beach| {
+            beach.add_crab(new_crab(&format!("Crab {}", k), k + 1));
+        });
+    }
+    let report = shared.tick();
+    assert_eq!(report.population, 11);
+    for reader in readers {
+        assert!(reader.join().unwrap() <= 1);
+    }
+
+    let stats = shared.stats();
+    assert_eq!(stats.ticks, 1);
+    assert_eq!(stats.speed.unwrap().max, 10.0);
+    shared.write(|beach| beach.add_member_to_clan(CLAN_1, CRAB_1));
+    assert_eq!(shared.stats().clan_sizes[CLAN_1], 1);
+    assert_eq!(
+        shared.read(|beach| beach.get_crab(0).name().to_string()),
+        CRAB_1
+    );
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn part4_script_runs_a_scenario_from_toml() {
+    let script = r##"
+        seed = 7
+        ticks = 12
+
+        [weather]
+        rain_chance = 0.0
+        storm_chance = 0.0
+
+        [[crabs]]
+        name = "Ferris"
+        speed = 20
+        color = "#0000ff"
+        diet = "Plants"
+
+        [[crabs]]
+        name = "Ruby"
+        speed = 30
+        color = "red"
+        diet = "Plants"
+
+        [[clans]]
+        id = "Rustaceans"
+        members = ["Ferris", "Ruby"]
+
+        [[events]]
+        tick = 11
+        do = "storm"
+
+        [[events]]
+        tick = 5
+        do = "breed"
+        parents = ["Ferris", "Ruby"]
+        child = "Cargo"
+
+        [[events]]
+        tick = 5
+        do = "join"
+        clan = "Rustaceans"
+        crab = "Cargo"
+    "##;
+    let outcome = Scenario::from_toml(script).unwrap().run();
+    outcome
+        .expect_population(3)
+        .expect_parents("Cargo", "Ferris", "Ruby")
+        .expect_members("Rustaceans", &["Ferris", "Ruby", "Cargo"])
+        .expect("12 ticks to have passed", |beach| beach.ticks() == 12)
+        .expect("a storm on the last tick", |beach| {
+            beach.weather().unwrap().weather() == Weather::Storm
+        });
+
+    let bad_color =
+        "[[crabs]]\nname = \"Ferris\"\nspeed = 20\ncolor = \"plaid\"\ndiet = \"Plants\"\n";
+    let error = Script::from_toml(bad_color)
+        .unwrap()
+        .to_scenario()
+        .unwrap_err();
+    assert_eq!(
+        error,
+        OceanError::Script(tr(Message::UnknownColor, &[&"plaid"]))
+    );
+    assert!(matches!(
+        Script::from_toml("[[events]]\ntick = 1\ndo = \"dance\"\n"),
+        Err(OceanError::Script(_))
+    ));
+}
+
+#[test]
+fn part4_crab_equality_is_identity_and_ordering_is_by_speed_then_name() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab("Ferris", 20));
+    beach.add_crab(new_crab("Ruby", 10));
+    beach.add_crab(new_crab("Ferris", 20));
+    beach.add_crab(new_crab("Cargo", 20));
+
+    // Copies of a crab on a beach are different crabs, with their own ids.
+    assert_ne!(beach.get_crab(0), beach.get_crab(2));
+    assert_ne!(beach.get_crab(0).id(), None);
+    let id = beach.get_crab(0).id();
+    beach.update_crab(0, |crab| crab.set_speed(25)).unwrap();
+    assert_eq!(beach.get_crab(0).id(), id);
+    let crabs: std::collections::HashSet<&Crab> = beach.crabs().collect();
+    assert_eq!(crabs.len(), 4);
+
+    let unplaced = new_crab("Ferris", 20);
+    assert_eq!(unplaced.id(), None);
+    assert_eq!(unplaced, new_crab("Ferris", 20));
+    assert_ne!(unplaced, new_crab("Ferris", 21));
+    assert_ne!(&unplaced, beach.get_crab(0));
+    let unplaced: std::collections::HashSet<Crab> =
+        [new_crab("Ruby", 10), new_crab("Ruby", 10)].into();
+    assert_eq!(unplaced.len(), 1);
+
+    let names: Vec<&str> = beach.crab_set().iter().map(|crab| crab.name()).collect();
+    assert_eq!(names, vec!["Ruby", "Cargo", "Ferris", "Ferris"]);
+    beach.update_crab(0, |crab| crab.set_speed(20)).unwrap();
+
+    let removed = beach.dedup_identical_crabs();
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0].id(), None);
+    let names: Vec<&str> = beach.crabs().map(|crab| crab.name()).collect();
+    assert_eq!(names, vec!["Ferris", "Ruby", "Cargo"]);
+    assert!(beach.dedup_identical_crabs().is_empty());
+}
+
+#[test]
+fn part4_clans_resolve_members_to_crabs_and_report_stale_ones() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab("Ferris", 20));
+    beach.add_crab(new_crab("Ruby", 10));
+    beach.add_crab(new_crab("Ferris", 30));
+    beach.add_member_to_clan(CLAN_1, "Ruby");
+    beach.add_member_to_clan(CLAN_1, "Ferris");
+    let ghost = beach.add_inhabitant(Box::new(Lobster::new("Ghost", 6, Color::new_red())));
+    beach.add_member_to_clan(CLAN_2, "Ghost");
+
+    let speeds: Vec<u32> = beach.clan_members(CLAN_1).map(Crab::speed).collect();
+    assert_eq!(speeds, vec![10, 20]);
+    assert_eq!(beach.clan_members(CLAN_2).count(), 0);
+    assert_eq!(beach.clan_members("nobody").count(), 0);
+
+    let clans = beach.clans_of_crabs();
+    assert_eq!(clans.clans[CLAN_1].len(), 2);
+    assert!(clans.clans[CLAN_2].is_empty());
+    let ghost = StaleMembership {
+        clan_id: CLAN_2.to_string(),
+        crab: ghost,
+        crab_name: "Ghost".to_string(),
+    };
+    assert_eq!(clans.stale, vec![ghost.clone()]);
+    assert_eq!(beach.stale_memberships(), vec![ghost]);
+}
+
+#[test]
+fn part4_breeding_cooldown_rests_parents_between_offspring() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 20));
+    beach.add_crab(new_crab(CRAB_3, 30));
+    beach.set_breeding_rules(BreedingRules {
+        cooldown: 2,
+        ..BreedingRules::default()
+    });
+    beach.try_breed_crabs(0, 1, String::from("a")).unwrap();
+    assert_eq!(beach.breeding().cooldown(beach.handle(0)), 2);
+    assert_eq!(
+        beach.can_breed(1, 2),
+        Err(BreedingError::CoolingDown {
+            crab: CRAB_2.to_string(),
+            ticks_left: 2,
+        })
+    );
+    let err = beach.try_breed_crabs(0, 2, String::from("b")).unwrap_err();
+    assert_eq!(
+        err,
+        OceanError::BreedingFailed(tr(Message::BreedCoolingDown, &[&CRAB_1, &2]))
+    );
+
+    beach.tick();
+    assert_eq!(beach.breeding().cooldown(beach.handle(0)), 1);
+    assert!(beach.can_breed(0, 2).is_err());
+    beach.tick();
+    assert_eq!(beach.breeding().cooldown(beach.handle(0)), 0);
+    assert_eq!(beach.can_breed(0, 2), Ok(()));
+    assert!(beach.try_breed_crabs(0, 1, String::from("b")).is_ok());
+}
+
+#[test]
+fn part4_graph_exports_clans_and_lineage_as_dot() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 20));
+    beach.add_crab(new_crab(CRAB_3, 30));
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    beach.add_member_to_clan(CLAN_1, CRAB_3);
+    beach.add_member_to_clan(CLAN_2, CRAB_2);
+    beach
+        .set_clan_relation(CLAN_1, CLAN_2, Relation::Rivalry)
+        .unwrap();
+    beach.breed_crabs(0, 1, String::from("kid"));
+    beach.remove_crab(0);
+    let ghost = beach.add_inhabitant(Box::new(Lobster::new("Ghost", 6, Color::new_red())));
+    beach.add_member_to_clan(CLAN_2, "Ghost");
+
+    let mut out = Vec::new();
+    beach.export_dot(&mut out, GraphKind::Clans).unwrap();
+    let dot = String::from_utf8(out).unwrap();
+    assert!(dot.starts_with("digraph clans {"));
+    assert!(dot.contains(&format!(
+        "\"clan/{}\" [label=\"{}\", shape=box];",
+        CLAN_2, CLAN_2
+    )));
+    assert!(dot.contains(&format!(
+        "\"crab/{}\" [label=\"{}\"];",
+        beach.handle(0),
+        CRAB_2
+    )));
+    assert!(dot.contains(&format!(
+        "\"clan/{}\" -> \"crab/{}\";",
+        CLAN_2,
+        beach.handle(0)
+    )));
+    assert!(dot.contains(&format!(
+        "\"crab/{}\" [label=\"Ghost\", style=dashed];",
+        ghost
+    )));
+    assert!(dot.contains("[dir=none, style=dashed, color=red"));
+    assert!(!dot.contains(&format!("label=\"{}\"", CRAB_1)));
+
+    let mut out = Vec::new();
+    beach.export_dot(&mut out, GraphKind::Lineage).unwrap();
+    let dot = String::from_utf8(out).unwrap();
+    assert!(dot.starts_with("digraph lineage {"));
+    assert!(dot.contains(&format!(
+        "\"#0\" [label=\"{} (gen 0)\", style=dashed];",
+        CRAB_1
+    )));
+    assert!(dot.contains(&format!("\"#1\" [label=\"{} (gen 0)\"];", CRAB_2)));
+    assert!(dot.contains("\"#3\" [label=\"kid (gen 1)\"];"));
+    assert!(dot.contains("\"#0\" -> \"#3\";"));
+    assert!(dot.contains("\"#1\" -> \"#3\";"));
+    assert!(dot.trim_end().ends_with('}'));
+}
+
+#[test]
+fn part4_bulk_loading_adds_crabs_from_iterators() {
+    let mut beach: Beach = (0..1000)
+        .map(|k| new_crab(&format!("crab{}", k), k % 50 + 1))
+        .collect();
+    assert_eq!(beach.size(), 1000);
+    assert_eq!(beach.get_crab(999).name(), "crab999");
+    assert_eq!(beach.find_crabs_by_name("crab500").len(), 1);
+
+    beach.enable_history(10);
+    beach.extend([new_crab(CRAB_1, 10), new_crab(CRAB_2, 20)]);
+    assert_eq!(beach.size(), 1002);
+    assert_eq!(beach.get_crab(1001).name(), CRAB_2);
+    assert!(beach.undo());
+    assert_eq!(beach.size(), 1000);
+
+    let mut reserved = Beach::with_reserved(100);
+    assert_eq!(reserved.capacity(), None);
+    reserved.add_crabs(std::iter::empty());
+    assert_eq!(reserved.size(), 0);
+    reserved.add_crabs((0..100).map(|k| new_crab(&format!("crab{}", k), 10)));
+    assert_eq!(reserved.size(), 100);
+}
+
+#[test]
+fn part4_disasters_kill_drain_and_scatter() {
+    let mut beach = Beach::with_seed(3);
+    for (name, speed) in [
+        (CRAB_1, 30),
+        (CRAB_2, 10),
+        (CRAB_3, 20),
+        (CRAB_4, 10),
+        (CRAB_5, 40),
+    ] {
+        beach.add_crab(new_crab(name, speed));
+    }
+    assert!(beach.set_crab_protected(3, true));
+
+    let report = beach.apply_disaster(Disaster::RedTide { percent: 40 });
+    assert_eq!(report.killed, vec![CRAB_2.to_string(), CRAB_3.to_string()]);
+    assert_eq!(report.affected(), vec![CRAB_2, CRAB_3]);
+    assert_eq!(beach.size(), 3);
+
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    beach
+        .economy_mut()
+        .earn(Account::clan(CLAN_1), 15, Earning::Race)
+        .unwrap();
+    let report = beach.apply_disaster(Disaster::HeatWave { percent: 50 });
+    assert_eq!(report.treasury_losses.get(CLAN_1), Some(&7));
+    assert_eq!(beach.get_treasury(CLAN_1), 8);
+    assert!(report.affected().is_empty());
+
+    beach.set_territory(Territory::new(10, 10));
+    let before: Vec<Position> = beach.crabs().map(Crab::position).collect();
+    let report = beach.apply_disaster(Disaster::StormSurge { reach: 3 });
+    assert!(!report.displaced.is_empty());
+    for moved in &report.displaced {
+        assert_ne!(moved.name, CRAB_4, "protected crabs stay put");
+        assert!(moved.to.x < 10 && moved.to.y < 10);
+        let index = beach.crabs().position(|c| c.name() == moved.name).unwrap();
+        assert_eq!(before[index], moved.from);
+        assert_eq!(beach.get_crab(index).position(), moved.to);
+    }
+}
+
+#[test]
+fn part4_speed_sums_do_not_overflow() {
+    let fast = Speed::new(u32::MAX);
+    assert_eq!(fast.checked_add(Speed::new(1)), None);
+    assert_eq!(fast.saturating_add(Speed::new(1)), Speed::MAX);
+    assert_eq!(Speed::new(3).checked_sub(Speed::new(5)), None);
+    assert_eq!(Speed::new(3).saturating_sub(Speed::new(5)), Speed::ZERO);
+    assert_eq!(Speed::mean(Vec::new()), None);
+    assert_eq!(
+        Speed::mean([fast, fast, Speed::ZERO]),
+        Some(u32::MAX as f64 * 2.0 / 3.0)
+    );
+
+    let mut beach = Beach::new();
+    beach.set_constraints(Constraints::none());
+    for name in [CRAB_1, CRAB_2] {
+        beach.add_crab(new_crab(name, u32::MAX));
+        beach.add_member_to_clan(CLAN_1, name);
+    }
+    assert_eq!(beach.get_crab(0).typed_speed(), fast);
+    assert_eq!(beach.get_crabs_avg_speed(CLAN_1), Some(u32::MAX as f64));
+    assert_eq!(beach.get_alliance_avg_speed(CLAN_1), Some(u32::MAX as f64));
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn part4_repl_runs_commands() {
+    let commands = "
+        # two crabs and their child
+        add Ferris 20 #0000ff Plants
+        add Ruby 30 red Plants
+        breed Ferris Ruby Cargo
+        join Rustaceans Ferris Ruby
+        join Crew Cargo
+        compete Rustaceans Crew
+        add Bob 3 plaid Fish
+        add Bob
+        swim
+        quit
+        tick
+    ";
+    let mut repl = Repl::new();
+    let mut output = Vec::new();
+    repl.run(commands.as_bytes(), &mut output, None).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0], "Rustaceans");
+    assert!(lines[1].contains("plaid"));
+    assert!(lines[2].contains("add <name> <speed> <color> <diet>"));
+    assert!(lines[3].contains("swim"));
+    assert!(repl.is_done());
+    assert_eq!(repl.beach().size(), 3);
+    assert_eq!(repl.beach().ticks(), 0);
+
+    let mut repl = Repl::new();
+    assert!(matches!(
+        repl.execute("remove Nobody"),
+        Err(OceanError::UnknownInhabitant(_))
+    ));
+    assert_eq!(repl.execute("tick 2").unwrap().lines().count(), 2);
+}
+
+#[test]
+fn part4_clan_constitution_turns_crabs_away() {
+    let mut beach = Beach::new();
+    for (name, speed) in [(CRAB_1, 10), (CRAB_2, 3), (CRAB_3, 12), (CRAB_4, 20)] {
+        beach.add_crab(new_crab(name, speed));
+    }
+    beach.add_crab(Crab::new(
+        String::from(CRAB_5),
+        30,
+        Color::new_blue(),
+        Diet::Fish,
+    ));
+    let rules = Constitution {
+        min_speed: 5,
+        diet: Some(Diet::Plants),
+        max_size: Some(2),
+    };
+    beach.set_constitution(CLAN_1, rules);
+    assert_eq!(beach.get_clan_system().constitution(CLAN_1), Some(&rules));
+
+    assert_eq!(beach.try_add_member_to_clan(CLAN_1, CRAB_1), Ok(()));
+    let rejection = beach.check_admission(CLAN_1, CRAB_2).unwrap_err();
+    assert_eq!(
+        rejection.reason,
+        RejectionReason::TooSlow {
+            speed: 3,
+            min_speed: 5
+        }
+    );
+    assert_eq!(
+        beach.try_add_member_to_clan(CLAN_1, CRAB_2),
+        Err(OceanError::Rejected(rejection))
+    );
+    beach.add_member_to_clan(CLAN_1, CRAB_2);
+    assert_ne!(beach.get_clan_of(CRAB_2), Some(CLAN_1));
+    assert!(matches!(
+        beach.check_admission(CLAN_1, CRAB_5).unwrap_err().reason,
+        RejectionReason::WrongDiet { .. }
+    ));
+    assert_eq!(
+        beach.check_admission(CLAN_1, "Nobody").unwrap_err().reason,
+        RejectionReason::NotOnBeach
+    );
+    assert_eq!(beach.check_admission(CLAN_2, "Nobody"), Ok(()));
+
+    beach.add_member_to_clan(CLAN_1, CRAB_3);
+    assert_eq!(
+        beach.check_admission(CLAN_1, CRAB_4).unwrap_err().reason,
+        RejectionReason::ClanFull { max_size: 2 }
+    );
+    beach.add_member_to_clan(CLAN_2, CRAB_4);
+    assert!(beach.move_member(CRAB_4, CLAN_2, CLAN_1).is_err());
+
+    // New rules only judge crabs joining from now on; those in stay.
+    beach.set_constitution(
+        CLAN_1,
+        Constitution {
+            min_speed: 11,
+            ..rules
+        },
+    );
+    beach.remove_member(CRAB_3);
+    assert!(beach.check_admission(CLAN_1, CRAB_4).is_ok());
+    assert_eq!(beach.get_clan_of(CRAB_1), Some(CLAN_1));
+    assert_eq!(
+        beach.remove_constitution(CLAN_1).map(|c| c.min_speed),
+        Some(11)
+    );
+    beach.add_member_to_clan(CLAN_1, CRAB_2);
+    assert_eq!(beach.get_clan_of(CRAB_2), Some(CLAN_1));
+}
+
+#[test]
+fn part4_economy_deposits_do_not_overflow() {
+    let mut economy = Economy::new();
+    let crab = Account::crab(CRAB_1);
+    let clan = Account::clan(CLAN_1);
+    economy.earn(crab.clone(), u64::MAX, Earning::Race).unwrap();
+    economy.earn(clan.clone(), 1, Earning::Race).unwrap();
+    assert!(economy.earn(crab.clone(), 1, Earning::Race).is_err());
+    assert!(economy.import(crab.clone(), 1).is_err());
+    assert_eq!(economy.room(&crab), 0);
+
+    // Nothing moves if the receiver cannot hold it.
+    assert!(economy.transfer(&clan, &crab, 1).is_err());
+    assert_eq!(economy.balance(&clan), 1);
+    assert_eq!(economy.balance(&crab), u64::MAX);
+    assert_eq!(economy.transactions().len(), 2);
+    economy.transfer(&crab, &clan, 5).unwrap();
+    assert_eq!(economy.balance(&clan), 6);
+    assert_eq!(economy.total_shells(), u64::MAX);
+}
+
+#[test]
+fn part4_i18n_arguments_are_not_substituted_again() {
+    let args: [&dyn std::fmt::Display; 2] = [&"{1}", &CLAN_1];
+    assert_eq!(
+        tr_in(Locale::English, Message::NotClanMember, &args),
+        format!("{{1}} is not a member of {}", CLAN_1)
+    );
+    let args: [&dyn std::fmt::Display; 1] = [&"{0}"];
+    assert_eq!(
+        tr_in(Locale::English, Message::NotClanMember, &args),
+        "{0} is not a member of {1}"
+    );
+}
+
+#[test]
+fn part4_replay_escapes_names_and_checks_indices() {
+    let operation = Operation::AddCrab {
+        name: String::from("Tab\tand\nnewline\\"),
+        speed: 3,
+        color: (1, 2, 3),
+        diet: Diet::Fish,
+    };
+    let line = operation.encode();
+    assert!(!line.contains('\n'));
+    assert_eq!(Operation::decode(&line).unwrap(), operation);
+    assert!(Operation::decode("join\tclan\tbad\\q").is_err());
+
+    let error = Simulation::replay_from("seed\t1\ntick\nbreed\t0\t7\tX".as_bytes()).unwrap_err();
+    assert!(error.to_string().starts_with("line 3: "));
+    let recording = "seed\t1\npopulate\t2\t5\nbattle\t1\t1";
+    assert!(Simulation::replay_from(recording.as_bytes()).is_err());
+    let recording = "seed\t1\npopulate\t2\t5\nbattle\t0\t1";
+    assert!(Simulation::replay_from(recording.as_bytes()).is_ok());
+}
+
+#[test]
+fn part4_timeline_at_tick_across_deaths() {
+    let mut simulation = Simulation::new(5);
+    simulation.beach_mut().set_life_rules(LifeRules {
+        lifespan: 3,
+        ..LifeRules::default()
+    });
+    simulation.enable_timeline(4);
+    let add = |name: &str| Operation::AddCrab {
+        name: String::from(name),
+        speed: 10,
+        color: (0, 0, 255),
+        diet: Diet::Plants,
+    };
+    let join = |name: &str| Operation::JoinClan {
+        clan_id: String::from(CLAN_1),
+        crab_name: String::from(name),
+    };
+    simulation.apply(add(PRINZ));
+    simulation.apply(join(PRINZ));
+
+    // The world at the end of each tick, i.e. right before the next one.
+    let mut states = Vec::new();
+    for k in 0..9 {
+        if k == 2 {
+            simulation.apply(add(SEBASTIAN));
+            simulation.apply(join(SEBASTIAN));
+        }
+        states.push(WorldState::capture(simulation.beach()));
+        simulation.apply(Operation::Tick);
+    }
+    states.push(WorldState::capture(simulation.beach()));
+
+    assert!(states.iter().any(|state| state.population() == 2));
+    let after_prinz = states
+        .iter()
+        .position(|state| state.population() == 1 && state.crab(0).unwrap().name == SEBASTIAN);
+    assert!(after_prinz.is_some());
+    assert_eq!(states.last().unwrap().population(), 0);
+    for (tick, state) in states.iter().enumerate() {
+        assert_eq!(simulation.at_tick(tick as u64).as_ref(), Some(state));
+    }
+}
+
+#[test]
+fn part4_breeding_with_climate_never_panics() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 20));
+    beach.set_climate(Climate {
+        mean: -10.0,
+        seasonal_amplitude: 0.0,
+        weather: 0.0,
+        ..Climate::default()
+    });
+    beach.set_weather(WeatherRules {
+        rain_chance: 0.0,
+        storm_chance: 1.0,
+        ..WeatherRules::default()
+    });
+    beach.tick();
+    assert!(beach.try_breed_crabs(0, 1, String::from("a")).is_err());
+    // The breeding that must happen is not left to the cold or the storm.
+    beach.breed_crabs(0, 1, String::from("b"));
+    assert_eq!(beach.size(), 3);
+    assert_eq!(beach.get_crab(2).name(), "b");
+}
+
+#[test]
+fn part4_achievements_population_counts_removals_once() {
+    let mut beach = Beach::new();
+    let tracker = Rc::new(RefCell::new(AchievementTracker::with_milestones(&[
+        Milestone::Population(4),
+    ])));
+    beach.subscribe(tracker.clone());
+    let unlocked = |tracker: &Rc<RefCell<AchievementTracker>>| {
+        tracker
+            .borrow()
+            .get(Milestone::Population(4))
+            .unwrap()
+            .is_unlocked()
+    };
+
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 10));
+    beach.add_crab(new_crab(CRAB_3, 10));
+    beach.remove_crab(0);
+    beach.pop_crab();
+    beach.add_crab(new_crab(CRAB_4, 10));
+    beach.add_crab(new_crab(CRAB_5, 10));
+    assert!(!unlocked(&tracker));
+
+    // A fished crab is counted out once, not once per event naming it.
+    let fished = beach.interfere(Interference::Fishing { count: 1, seed: 1 });
+    assert_eq!(fished.len(), 1);
+    beach.add_crab(new_crab(CRAB_1, 10));
+    assert!(!unlocked(&tracker));
+    beach.add_crab(new_crab(CRAB_3, 10));
+    assert_eq!(beach.size(), 4);
+    assert!(unlocked(&tracker));
+}
+
+#[test]
+fn part4_life_ticks_advance_crab_states() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.set_life_rules(LifeRules {
+        appetite: 100,
+        ..LifeRules::default()
+    });
+    for _ in 0..HUNGRY_AT {
+        beach.tick();
+    }
+    assert_eq!(beach.get_crab(0).hunger(), HUNGRY_AT);
+    assert_eq!(beach.get_crab(0).state(), LifeState::Hungry);
+    assert_eq!(beach.count_by_state().get(&LifeState::Hungry), Some(&1));
+
+    for _ in HUNGRY_AT..STARVING_AT {
+        beach.tick();
+    }
+    assert_eq!(beach.get_crab(0).state(), LifeState::Starving);
+    assert_eq!(beach.get_crab(0).health(), 100 - STARVATION_DAMAGE);
+
+    // Without an appetite, crabs never grow hungry on their own.
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.tick();
+    assert_eq!(beach.get_crab(0).hunger(), 0);
+}
+
+#[test]
+fn part4_clan_constitution_holds_on_every_path() {
+    let full = Constitution {
+        max_size: Some(1),
+        ..Constitution::default()
+    };
+    let mut beach = Beach::new();
+    let [crab1, crab2, crab3] =
+        [CRAB_1, CRAB_2, CRAB_3].map(|name| beach.add_crab_with_id(new_crab(name, 10)));
+    let mut clans = ClanSystem::new();
+    clans.add_member(CLAN_1, crab1, CRAB_1);
+    clans.add_member(CLAN_2, crab2, CRAB_2);
+    clans.set_constitution(CLAN_1, full);
+    assert_eq!(clans.add_member(CLAN_1, crab3, CRAB_3), None);
+    assert!(!clans.is_member(CLAN_1, crab3));
+    assert_eq!(
+        clans.try_add_member(CLAN_1, crab3, CRAB_3).unwrap_err().reason,
+        RejectionReason::ClanFull { max_size: 1 }
+    );
+    assert!(clans.move_member(crab2, CLAN_2, CLAN_1).is_err());
+    assert!(clans.is_member(CLAN_2, crab2));
+    assert_eq!(
+        clans.merge_clans(CLAN_2, CLAN_1, CLAN_3),
+        Err(OceanError::MergeTooLarge {
+            clan1: CLAN_2.to_string(),
+            clan2: CLAN_1.to_string(),
+            size: 2,
+            max_size: 1,
+        })
+    );
+    assert_eq!(clans.get_clan_member_count(CLAN_1), 1);
+
+    // The merged clan keeps the first clan's constitution, or the second's.
+    clans.set_constitution(
+        CLAN_1,
+        Constitution {
+            max_size: Some(2),
+            ..full
+        },
+    );
+    clans.merge_clans(CLAN_2, CLAN_1, CLAN_3).unwrap();
+    assert_eq!(clans.constitution(CLAN_3).unwrap().max_size, Some(2));
+    assert_eq!(clans.constitution(CLAN_1), None);
+
+    // A beach also judges the crabs a merger brings in by its rules.
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 3));
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    beach.add_member_to_clan(CLAN_2, CRAB_2);
+    beach.set_constitution(
+        CLAN_1,
+        Constitution {
+            min_speed: 5,
+            ..Constitution::default()
+        },
+    );
+    assert!(matches!(
+        beach.merge_clans(CLAN_1, CLAN_2, CLAN_3),
+        Err(OceanError::Rejected(_))
+    ));
+    assert!(beach.get_clan_system().has_clan(CLAN_2));
+
+    // A crab renamed away from a name it shares joins under its new name
+    // only if the clan has room.
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    beach.set_constitution(CLAN_1, full);
+    beach.rename_crab(1, CRAB_2.to_string());
+    assert_eq!(beach.get_clan_system().get_clan_member_count(CLAN_1), 1);
+    assert_eq!(beach.get_clan_of(CRAB_2), None);
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn part4_repl_join_counts_every_newcomer() {
+    let mut beach = Beach::new();
+    for name in [CRAB_1, CRAB_2, CRAB_3] {
+        beach.add_crab(new_crab(name, 10));
+    }
+    beach.set_constitution(
+        "Rustaceans",
+        Constitution {
+            max_size: Some(2),
+            ..Constitution::default()
+        },
+    );
+    let mut repl = Repl::with_beach(beach);
+    let line = format!("join Rustaceans {} {} {}", CRAB_1, CRAB_2, CRAB_3);
+    assert!(matches!(
+        repl.execute(&line),
+        Err(OceanError::Rejected(Rejection {
+            reason: RejectionReason::ClanFull { max_size: 2 },
+            ..
+        }))
+    ));
+    assert_eq!(repl.beach().get_clan_system().get_clan_count(), 0);
+
+    let line = format!("join Rustaceans {} {} {}", CRAB_1, CRAB_2, CRAB_1);
+    repl.execute(&line).unwrap();
+    let clans = repl.beach().get_clan_system();
+    assert_eq!(
+        clans.get_clan_member_names("Rustaceans"),
+        vec![CRAB_1, CRAB_2]
+    );
+}
+
+#[test]
+fn part4_rename_keeps_roles_and_clans() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 30));
+    beach.add_crab(new_crab(CRAB_2, 20));
+    beach.add_crab(new_crab(CRAB_3, 10));
+    beach.add_member_to_clan(CLAN_1, CRAB_1);
+    beach.add_member_to_clan(CLAN_1, CRAB_2);
+    beach.add_member_to_clan(CLAN_2, CRAB_3);
+    beach.set_role(CLAN_1, CRAB_2, "scout").unwrap();
+    assert_eq!(beach.elect_leader(CLAN_1).as_deref(), Some(CRAB_1));
+
+    beach.rename_crab(1, CRAB_4.to_string());
+    assert_eq!(beach.members_with_role(CLAN_1, "scout"), vec![CRAB_4]);
+    assert_eq!(
+        beach.get_clan_system().get_clan_member_names(CLAN_1),
+        vec![CRAB_1, CRAB_4]
+    );
+    beach.rename_crab(0, CRAB_5.to_string());
+    assert_eq!(beach.get_leader(CLAN_1), Some(CRAB_5));
+
+    // Taking the name of a crab in another clan leaves both where they were.
+    beach.try_rename_crab(0, CRAB_3.to_string()).unwrap();
+    let clans = beach.get_clan_system();
+    assert_eq!(clans.get_clan_of(beach.handle(0)), Some(CLAN_1));
+    assert_eq!(clans.get_clan_of(beach.handle(2)), Some(CLAN_2));
+    assert_eq!(clans.get_clan_member_names(CLAN_1), vec![CRAB_3, CRAB_4]);
+    assert_eq!(beach.get_leader(CLAN_1), Some(CRAB_3));
+    assert!(beach.try_rename_crab(3, CRAB_1.to_string()).is_err());
+
+    // Watches see changes made in place by the next tick at the latest.
+    let alerts = beach.watch_channel(
+        "slow clan",
+        Watch::ClanAverageSpeedBelow {
+            clan_id: String::from(CLAN_1),
+            speed: 10.0,
+        },
+    );
+    beach.get_crab_mut(0).set_speed(1);
+    beach.get_crab_mut(1).set_speed(1);
+    assert!(alerts.try_recv().is_err());
+    beach.tick();
+    assert_close(alerts.try_recv().unwrap().value, 1.0);
+}
+
+#[test]
+fn part4_undo_skips_changes_that_fail() {
+    let mut beach = Beach::new();
+    beach.enable_history(10);
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 20));
+    let depth = beach.undo_depth();
+
+    assert_eq!(beach.remove_constitution(CLAN_1), None);
+    assert!(beach.try_breed_crabs(0, 5, String::from("a")).is_err());
+    assert!(beach.try_breed_crabs(0, 1, CRAB_1.to_string()).is_ok());
+    assert_eq!(beach.undo_depth(), depth + 1);
+
+    beach.set_constitution(CLAN_1, Constitution::default());
+    assert_eq!(
+        beach.remove_constitution(CLAN_1),
+        Some(Constitution::default())
+    );
+    assert_eq!(beach.undo_depth(), depth + 3);
+    assert!(beach.undo());
+    assert!(beach.get_clan_system().constitution(CLAN_1).is_some());
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn part4_stream_removals_and_drops_finished_listeners() {
+    let mut beach = Beach::new();
+    let mut stream = beach.event_stream();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 20));
+    beach.add_crab(new_crab(CRAB_3, 30));
+    beach.remove_crab(0);
+    beach.interfere(Interference::Fishing { count: 1, seed: 1 });
+    beach.add_crab(new_crab(CRAB_4, 40));
+    beach.seagull_raid();
+    let events: Vec<BeachEvent> = std::iter::from_fn(|| stream.try_next()).collect();
+    assert!(events.contains(&BeachEvent::CrabRemoved {
+        name: CRAB_1.to_string()
+    }));
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, BeachEvent::CrabsFished { names } if names.len() == 1)));
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, BeachEvent::SeagullRaid { .. })));
+
+    struct Once(bool);
+    impl EventListener for Once {
+        fn on_event(&mut self, _event: &BeachEvent) {
+            self.0 = true;
+        }
+        fn is_finished(&self) -> bool {
+            self.0
+        }
+    }
+    let mut bus = EventBus::new();
+    bus.subscribe(Rc::new(RefCell::new(Once(false))));
+    assert_eq!(bus.listener_count(), 1);
+    bus.publish(BeachEvent::Tick { tick: 1 });
+    assert_eq!(bus.listener_count(), 0);
+}
+
+#[test]
+fn part4_bulk_loading_checks_the_whole_batch() {
+    let mut beach = Beach::new();
+    beach.set_name_registry(Some(NameRegistry::new(NamePolicy::Reject)));
+    beach.add_crab(new_crab(CRAB_1, 10));
+    let batch = vec![
+        new_crab(CRAB_2, 10),
+        new_crab(CRAB_3, 10),
+        new_crab(CRAB_2, 10),
+    ];
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        beach.add_crabs(batch);
+    }));
+    assert!(result.is_err());
+    assert_eq!(beach.size(), 1);
+
+    let mut beach = Beach::with_capacity(2);
+    beach.add_crab(new_crab(CRAB_1, 10));
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        beach.add_crabs([new_crab(CRAB_2, 10), new_crab(CRAB_3, 10)]);
+    }));
+    assert!(result.is_err());
+    assert_eq!(beach.size(), 1);
+
+    // Crabs evicted for the batch are handed back, batch crabs included.
+    beach.set_eviction_policy(EvictionPolicy::EvictSlowest);
+    let evicted = beach.add_crabs([
+        new_crab(CRAB_2, 5),
+        new_crab(CRAB_3, 20),
+        new_crab(CRAB_4, 30),
+    ]);
+    let names: Vec<&str> = evicted.iter().map(|crab| crab.name()).collect();
+    assert_eq!(names, vec![CRAB_2, CRAB_1]);
+    assert_eq!(beach.size(), 2);
+}
+
+#[test]
+fn part4_life_growth_saturates_at_the_fastest_speed() {
+    let mut beach = Beach::new();
+    beach.set_constraints(Constraints::none());
+    beach.add_crab(new_crab(CRAB_1, u32::MAX));
+    beach.set_life_rules(LifeRules {
+        maturity: 10,
+        ..LifeRules::default()
+    });
+    beach.tick();
+    assert_eq!(beach.get_crab(0).speed(), u32::MAX);
+    assert_eq!(beach.get_crab(0).typed_speed(), Speed::MAX);
+}
+
+#[test]
+fn part4_storage_indices_follow_every_removal() {
+    let mut beach = Beach::new();
+    beach.set_constraints(Constraints::none());
+    let mut model: Vec<(CrabId, u32)> = (0..64)
+        .map(|k| {
+            (
+                beach.add_crab_with_id(new_crab(&format!("Crab {}", k), k)),
+                k,
+            )
+        })
+        .collect();
+    let mut step = 0;
+    while !model.is_empty() {
+        // Take from the end, the front and the middle in turn.
+        let index = match step % 3 {
+            0 => model.len() - 1,
+            1 => 0,
+            _ => model.len() / 2,
+        };
+        step += 1;
+        assert_eq!(beach.remove_crab(index).speed(), model.remove(index).1);
+        let speeds: Vec<u32> = beach.crabs().map(|crab| crab.speed()).collect();
+        assert_eq!(
+            speeds,
+            model.iter().map(|&(_, speed)| speed).collect::<Vec<_>>()
+        );
+        for (at, &(id, speed)) in model.iter().enumerate() {
+            assert_eq!(beach.index_of(id), Some(at));
+            assert_eq!(beach.handle(at), id);
+            assert_eq!(beach.get_crab(at).speed(), speed);
+        }
+        if step == 10 {
+            let id = beach.add_crab_with_id(new_crab(CRAB_1, 100));
+            model.push((id, 100));
+        }
+    }
+    assert_eq!(beach.size(), 0);
+}
+
+#[test]
+fn part4_index_methods_agree_with_ids() {
+    let mut beach = Beach::new();
+    let first = beach.add_crab_with_id(new_crab(CRAB_1, 10));
+    let second = beach.add_crab_with_id(new_crab(CRAB_2, 20));
+    let third = beach.add_crab_with_id(new_crab(CRAB_3, 30));
+    assert_eq!(beach.get_crab(1).id(), Some(second));
+    assert_eq!(beach.get_crab_by_id(second).unwrap().name(), CRAB_2);
+
+    // Ids outlive the crabs before them leaving; indices shift.
+    assert_eq!(beach.remove_crab(0).id(), None);
+    assert_eq!(beach.get_crab(1).id(), Some(third));
+    assert_eq!(beach.get_crab_by_id(third).unwrap().name(), CRAB_3);
+    assert!(beach.get_crab_by_id(first).is_none());
+
+    beach.breed_crabs(0, 1, CRAB_4.to_string());
+    let child = beach
+        .breed_crabs_by_id(second, third, CRAB_5.to_string())
+        .unwrap();
+    assert_eq!(beach.get_crab(3).id(), Some(child));
+    assert_eq!(beach.get_crab(2).parents(), Some((second, third)));
+    assert_eq!(first.to_string(), "#0");
+}
+
+#[test]
+fn part4_food_feeding_keeps_crabs_from_starving() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.set_life_rules(LifeRules {
+        appetite: 2,
+        starvation: 100,
+        ..LifeRules::default()
+    });
+    for _ in 0..10 {
+        beach.add_food(Food::Algae, 1);
+        assert_eq!(beach.feed_all().fed, vec![CRAB_1.to_string()]);
+        assert_eq!(beach.ticks_since_meal(0), 0);
+        assert!(beach.tick().deaths.is_empty());
+    }
+    assert_eq!(beach.size(), 1);
+}
+
+#[test]
+fn part4_simulation_leaves_the_global_random_numbers_alone() {
+    let draws = |simulate: bool| {
+        std::thread::spawn(move || {
+            if simulate {
+                run_recorded_simulation();
+            }
+            (0..5).map(|_| ocean::rand::rand32()).collect::<Vec<u32>>()
+        })
+        .join()
+        .unwrap()
+    };
+    assert_eq!(draws(true), draws(false));
+
+    // Other draws on the thread do not change what a simulation does.
+    let replayed = std::thread::spawn(|| {
+        ocean::rand::rand32();
+        run_recorded_simulation()
+            .beach()
+            .crabs()
+            .map(|crab| (crab.name().to_string(), crab.diet()))
+            .collect::<Vec<_>>()
+    })
+    .join()
+    .unwrap();
+    let original: Vec<_> = run_recorded_simulation()
+        .beach()
+        .crabs()
+        .map(|crab| (crab.name().to_string(), crab.diet()))
+        .collect();
+    assert_eq!(replayed, original);
+}
+
+#[test]
+fn part4_command_undoing_a_tick_puts_back_its_crabs() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.set_life_rules(LifeRules {
+        appetite: 1,
+        starvation: 100,
+        maturity: 5,
+        ..LifeRules::default()
+    });
+    let mut dispatcher = Dispatcher::new(beach);
+    dispatcher.execute(Operation::Tick);
+    dispatcher.execute(Operation::AddCrab {
+        name: String::from(CRAB_2),
+        speed: 20,
+        color: (0, 0, 255),
+        diet: Diet::Plants,
+    });
+    assert_eq!(dispatcher.beach().size(), 1);
+    assert_eq!(dispatcher.beach().age(0), 0);
+
+    dispatcher.undo();
+    dispatcher.undo();
+    let beach = dispatcher.beach();
+    assert_eq!(beach.ticks(), 0);
+    assert_eq!(beach.size(), 1);
+    assert_eq!(beach.get_crab(0).name(), CRAB_1);
+    assert_eq!(beach.get_crab(0).speed(), 10);
+    assert_eq!(beach.age(0), 0);
+    assert_eq!(beach.ticks_since_meal(0), 0);
+
+    dispatcher.redo();
+    assert_eq!(dispatcher.beach().ticks(), 1);
+    assert_eq!(dispatcher.beach().size(), 0);
+    dispatcher.redo();
+    assert_eq!(dispatcher.beach().get_crab(0).name(), CRAB_2);
+}
+
+#[test]
+fn part4_pop_crab_leaves_its_clan() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    beach.add_crab(new_crab(CRAB_2, 10));
+    beach.add_member_to_clan(CLAN_1, CRAB_2);
+    assert_eq!(beach.pop_crab().unwrap().name(), CRAB_2);
+    assert_eq!(beach.get_clan_of(CRAB_2), None);
+    assert!(!beach.get_clan_system().has_clan(CLAN_1));
+}
+
+#[test]
+fn part4_capacity_holds_for_every_arrival() {
+    let full = || {
+        let mut beach = Beach::with_capacity(2);
+        beach.add_crab(new_crab(CRAB_1, 10));
+        beach.add_crab(new_crab(CRAB_2, 5));
+        beach
+    };
+    let names = |beach: &Beach| -> Vec<String> {
+        beach.crabs().map(|crab| crab.name().to_string()).collect()
+    };
+
+    let mut beach = full();
+    beach.set_eviction_policy(EvictionPolicy::EvictSlowest);
+    let evicted = beach.add_crab(new_crab(CRAB_3, 15));
+    assert_eq!(evicted.len(), 1);
+    assert_eq!(evicted[0].name(), CRAB_2);
+
+    // Transfers make room on the other beach, or are refused before the
+    // crab leaves.
+    let mut home = Beach::new();
+    home.add_crab(new_crab(CRAB_4, 20));
+    let evicted = home.transfer_crab(0, &mut beach);
+    assert_eq!(evicted[0].name(), CRAB_1);
+    assert_eq!(names(&beach), vec![CRAB_3, CRAB_4]);
+    let mut home = Beach::new();
+    home.add_crab(new_crab(CRAB_5, 20));
+    let mut refusing = full();
+    let refused = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        home.transfer_crab(0, &mut refusing)
+    }));
+    assert!(refused.is_err());
+    assert_eq!(home.size(), 1);
+    assert_eq!(refusing.size(), 2);
+
+    // Merged crabs make room too.
+    let mut other = Beach::new();
+    other.add_crab(new_crab(CRAB_5, 30));
+    let merged = beach.merge(other);
+    assert_eq!(names(&merged), vec![CRAB_4, CRAB_5]);
+    let refused = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| full().merge(home)));
+    assert!(refused.is_err());
+
+    // Imports stop at the first crab there is no room for.
+    let mut beach = Beach::with_capacity(1);
+    let records = [new_crab(CRAB_1, 1), new_crab(CRAB_2, 2)].map(Ok::<Crab, String>);
+    let err = beach.import_stream(records, |_| {}).unwrap_err();
+    assert!(err.contains('2'), "{}", err);
+    assert_eq!(beach.size(), 1);
+}
+
+#[test]
+fn part4_constraints_hold_on_every_arrival_and_change() {
+    let mut beach = Beach::new();
+    assert_eq!(beach.constraints(), &Constraints::new());
+    beach.add_crab(new_crab(CRAB_1, 50));
+    let too_fast = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        beach.add_crab(new_crab(CRAB_2, 500));
+    }));
+    assert!(too_fast.is_err());
+    let batch = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        beach.add_crabs([new_crab(CRAB_2, 5), new_crab(CRAB_3, 0)]);
+    }));
+    assert!(batch.is_err());
+    assert!(matches!(
+        beach.admit_crab(new_crab(CRAB_2, 101)),
+        Err(OceanError::OutOfBounds(ConstraintError {
+            field: Bounded::Speed,
+            value: 101,
+            ..
+        }))
+    ));
+    assert_eq!(beach.size(), 1);
+
+    // A change that breaks the constraints is undone entirely.
+    let error = beach
+        .update_crab(0, |crab| {
+            crab.set_speed(500);
+            crab.set_hunger(3);
+        })
+        .unwrap_err();
+    assert_eq!(error.field, Bounded::Speed);
+    assert_eq!(beach.get_crab(0).speed(), 50);
+    assert_eq!(beach.get_crab(0).hunger(), 0);
+
+    let changed = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        beach.get_crab_mut(0).set_speed(500);
+    }));
+    assert!(changed.is_err());
+    assert_eq!(beach.get_crab(0).speed(), 50);
+    beach.get_crab_mut(0).set_speed(60);
+    assert_eq!(beach.get_crab(0).speed(), 60);
+
+    beach.add_crab(new_crab(CRAB_2, 70));
+    let doubled = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        beach.for_each_crab_mut(|crab| crab.set_speed(crab.speed() * 2));
+    }));
+    assert!(doubled.is_err());
+    assert_eq!(beach.get_crab(1).speed(), 70);
+    let doubled = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        for mut crab in beach.crabs_mut() {
+            crab.set_claw_size(0);
+        }
+    }));
+    assert!(doubled.is_err());
+    assert_eq!(beach.get_crab(0).claw_size(), 1);
+
+    // Crabs may not outlive their lifespan.
+    beach.set_constraints(Constraints {
+        lifespan: 3,
+        ..Constraints::new()
+    });
+    assert!(beach.update_crab(0, |crab| crab.set_age(3)).is_ok());
+    let error = beach.update_crab(0, |crab| crab.set_age(4)).unwrap_err();
+    assert_eq!(error.field, Bounded::Age);
+    assert_eq!(error.allowed, 0..=3);
+    assert_eq!(beach.get_crab(0).age(), 3);
+
+    let mut crab = new_crab(CRAB_3, 10);
+    assert!(crab.try_set_speed(0, beach.constraints()).is_err());
+    assert_eq!(crab.speed(), 10);
+    assert!(crab.try_set_speed(20, beach.constraints()).is_ok());
+    assert_eq!(crab.speed(), 20);
+}
+
+#[test]
+fn part4_clans_keep_namesakes_apart() {
+    let mut beach = Beach::new();
+    let first = beach.add_crab_with_id(new_crab(CRAB_1, 10));
+    let second = beach.add_crab_with_id(new_crab(CRAB_1, 20));
+    beach.add_member_to_clan_by_id(CLAN_1, first);
+    beach.add_member_to_clan_by_id(CLAN_2, second);
+    assert_eq!(
+        beach.try_add_member_to_clan_by_id(CLAN_2, first),
+        Err(OceanError::DuplicateMembership {
+            crab: CRAB_1.to_string(),
+            clan: CLAN_1.to_string(),
+        })
+    );
+
+    let clans = beach.get_clan_system();
+    assert_eq!(clans.get_clan_of(first), Some(CLAN_1));
+    assert_eq!(clans.get_clan_of(second), Some(CLAN_2));
+    assert_eq!(clans.clan_members(CLAN_2), &[second]);
+    assert_eq!(clans.get_clan_member_names(CLAN_2), vec![CRAB_1]);
+    let speeds: Vec<u32> = beach.clan_members(CLAN_2).map(Crab::speed).collect();
+    assert_eq!(speeds, vec![20]);
+    // Name-based methods act on the first crab with the name.
+    assert_eq!(beach.get_clan_of(CRAB_1), Some(CLAN_1));
+
+    // Leaving the beach ends one membership, not its namesake's.
+    beach.remove_crab_by_id(first);
+    assert_eq!(beach.get_clan_system().get_clan_of(first), None);
+    assert_eq!(beach.get_clan_of(CRAB_1), Some(CLAN_2));
+    assert_eq!(beach.remove_member_by_id(second).as_deref(), Some(CLAN_2));
+    assert_eq!(beach.get_clan_of(CRAB_1), None);
+    assert_eq!(
+        beach.try_add_member_to_clan_by_id(CLAN_1, first),
+        Err(OceanError::UnknownCrabId(first))
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn part4_persist_keeps_namesakes_apart() {
+    let mut beach = Beach::new();
+    beach.add_crab(new_crab(CRAB_1, 10));
+    let second = beach.add_crab_with_id(new_crab(CRAB_1, 20));
+    beach.add_member_to_clan_by_id(CLAN_1, second);
+
+    let path = std::env::temp_dir().join(format!("ocean-namesakes-{}.json", std::process::id()));
+    beach.save_to_json(&path).unwrap();
+    let loaded = Beach::load_from_json(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let speeds: Vec<u32> = loaded.clan_members(CLAN_1).map(Crab::speed).collect();
+    assert_eq!(speeds, vec![20]);
+    assert_eq!(loaded.handle(1), second);
+}